@@ -33,9 +33,11 @@ pub fn listen(
         chat::cf_set_status_text(chat_cid, t!("mtxchat.busy.rx_events", locales::LANG));
         chat::cf_set_busy_state(chat_cid, true);
         let mut event_count = 0;
+        let mut last_message: Option<(String, String)> = None;
         for event in events {
             let sender = event.sender.unwrap_or("anon".to_string());
             let body = event.body.unwrap_or("...".to_string());
+            last_message = Some((get_username(&sender), body.clone()));
             let post = chat::Post {
                 dialogue_id: xous_ipc::String::from_str(&dialogue_id),
                 author: xous_ipc::String::from_str(&get_username(&sender)),
@@ -54,6 +56,14 @@ pub fn listen(
                 &format!("{} {}", t!("mtxchat.busy.rx_events", locales::LANG), event_count),
             );
         }
+        if let Some((author, text)) = last_message {
+            // let the user know about inbound messages even if mtxchat isn't in the foreground
+            chat::cf_notify(
+                chat_cid,
+                &format!("{} {}", t!("mtxchat.notify.title", locales::LANG), dialogue_id),
+                &format!("{}: {}", author, text),
+            );
+        }
     }
     chat::cf_set_busy_state(chat_cid, false);
     // trigger the chat ui to save the dialogue to the pddb
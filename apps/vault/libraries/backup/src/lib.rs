@@ -7,6 +7,14 @@ pub const CONTINUE_RESPONSE: &[u8] = &[42, 43, 44, 45];
 pub const OKAY_CANARY: &[u8] = &[0xca, 0xfe, 0xba, 0xbe];
 pub const ERROR_VENDOR_HANDLING: u8 = 0x35;
 pub const VENDOR_SESSION_ERROR: u8 = 0x36;
+pub const FIDO_BACKUP_MODE_DISABLED: u8 = 0x37;
+/// Codes vault answers vendor commands with, as a CTAPHID error, instead of data
+pub const RESTORE_FAILED: u8 = 41;
+pub const BACKUP_FAILED: u8 = 42;
+pub const HOST_READOUT_DISABLED: u8 = 44;
+pub const NO_MORE_CHUNKS: u8 = 88;
+/// Second byte of a backup request, after the `PayloadType`, asking for the entries without their secrets
+pub const NAMES_ONLY: u8 = 1;
 
 #[derive(Debug)]
 pub enum CborConversionError {
@@ -98,6 +106,9 @@ pub struct TotpEntry {
     pub name: String,
     #[serde(default)] // if hotp is missing from the JSON representation, it's assumed to be false.
     pub hotp: bool,
+    /// Steam Guard codes are HMAC-SHA1, spelled out in Steam's own 5-character alphabet
+    #[serde(default)]
+    pub steam: bool,
 }
 
 impl From<TotpEntry> for cbor::Value {
@@ -108,6 +119,8 @@ impl From<TotpEntry> for cbor::Value {
             cbor_int!(3) => te.digit_count as i64,
             cbor_int!(4) => te.algorithm,
             cbor_int!(5) => te.name,
+            cbor_int!(6) => te.hotp,
+            cbor_int!(7) => te.steam,
         }
     }
 }
@@ -129,6 +142,7 @@ impl TryFrom<cbor::Value> for TotpEntry {
                 4 => algorithm,
                 5 => name,
                 6 => hotp,
+                7 => steam,
             } = rawmap;
         }
 
@@ -139,8 +153,10 @@ impl TryFrom<cbor::Value> for TotpEntry {
         let name = extract_string(name.unwrap())?;
         let hotp =
             extract_bool(hotp.unwrap_or(cbor::Value::Simple(cbor::SimpleValue::FalseValue))).unwrap_or(false);
+        let steam = extract_bool(steam.unwrap_or(cbor::Value::Simple(cbor::SimpleValue::FalseValue)))
+            .unwrap_or(false);
 
-        Ok(TotpEntry { step_seconds, shared_secret, digit_count, algorithm, name, hotp })
+        Ok(TotpEntry { step_seconds, shared_secret, digit_count, algorithm, name, hotp, steam })
     }
 }
 
@@ -175,12 +191,55 @@ impl From<&TotpEntries> for Vec<u8> {
     }
 }
 
+/// A password that an entry used before, and when it was replaced
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct PreviousPassword {
+    pub password: String,
+    /// seconds since the epoch
+    pub retired: u64,
+}
+
+impl From<PreviousPassword> for cbor::Value {
+    fn from(pp: PreviousPassword) -> Self {
+        cbor_map! {
+            cbor_int!(1) => pp.password,
+            cbor_int!(2) => pp.retired,
+        }
+    }
+}
+
+impl TryFrom<cbor::Value> for PreviousPassword {
+    type Error = CborConversionError;
+
+    fn try_from(value: cbor::Value) -> Result<Self, Self::Error> {
+        let rawmap = match value {
+            cbor::Value::Map(m) => m,
+            _ => return Err(CborConversionError::BadCbor),
+        };
+
+        destructure_cbor_map! {
+            let {
+                1 => password,
+                2 => retired,
+            } = rawmap;
+        }
+
+        let password = extract_string(password.ok_or(CborConversionError::BadCbor)?)?;
+        let retired = extract_unsigned(retired.ok_or(CborConversionError::BadCbor)?)?;
+
+        Ok(PreviousPassword { password, retired })
+    }
+}
+
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct PasswordEntry {
     pub description: String,
     pub username: String,
     pub password: String,
     pub notes: String,
+    /// the passwords the entry used before, newest first
+    #[serde(default)]
+    pub history: Vec<PreviousPassword>,
 }
 
 impl From<PasswordEntry> for cbor::Value {
@@ -190,6 +249,7 @@ impl From<PasswordEntry> for cbor::Value {
             cbor_int!(2) => te.username,
             cbor_int!(3) => te.password,
             cbor_int!(4) => te.notes,
+            cbor_int!(5) => cbor_array_vec!(te.history),
         }
     }
 }
@@ -209,6 +269,7 @@ impl TryFrom<cbor::Value> for PasswordEntry {
                 2 => username,
                 3 => password,
                 4 => notes,
+                5 => history,
             } = rawmap;
         }
 
@@ -216,8 +277,16 @@ impl TryFrom<cbor::Value> for PasswordEntry {
         let username = extract_string(username.unwrap())?;
         let password = extract_string(password.unwrap())?;
         let notes = extract_string(notes.unwrap())?;
+        // backups made before the history was kept don't have one
+        let history = match history {
+            Some(history) => extract_array(history)?
+                .into_iter()
+                .map(PreviousPassword::try_from)
+                .collect::<Result<_, _>>()?,
+            None => Vec::new(),
+        };
 
-        Ok(PasswordEntry { description, username, password, notes })
+        Ok(PasswordEntry { description, username, password, notes, history })
     }
 }
 
@@ -252,10 +321,61 @@ impl TryFrom<cbor::Value> for PasswordEntries {
     }
 }
 
+/// Resident FIDO2 credentials, each one sealed on the device under a key derived from a passphrase
+/// entered there. The host only ever sees opaque blobs: restoring them needs the same passphrase.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct FidoEntries {
+    /// PBKDF2-HMAC-SHA256 salt
+    pub salt: Vec<u8>,
+    /// PBKDF2-HMAC-SHA256 iteration count
+    pub iterations: u64,
+    /// IV, AES-256-CBC ciphertext and HMAC-SHA256 tag of each serialized credential
+    pub credentials: Vec<Vec<u8>>,
+}
+
+impl From<&FidoEntries> for cbor::Value {
+    fn from(fe: &FidoEntries) -> Self {
+        cbor_map! {
+            cbor_int!(1) => fe.salt.clone(),
+            cbor_int!(2) => fe.iterations,
+            cbor_int!(3) => cbor_array_vec!(fe.credentials.clone()),
+        }
+    }
+}
+
+impl TryFrom<cbor::Value> for FidoEntries {
+    type Error = CborConversionError;
+
+    fn try_from(value: cbor::Value) -> Result<Self, Self::Error> {
+        let rawmap = match value {
+            cbor::Value::Map(m) => m,
+            _ => return Err(CborConversionError::BadCbor),
+        };
+
+        destructure_cbor_map! {
+            let {
+                1 => salt,
+                2 => iterations,
+                3 => credentials,
+            } = rawmap;
+        }
+
+        let salt = extract_byte_string(salt.ok_or(CborConversionError::BadCbor)?)?;
+        let iterations = extract_unsigned(iterations.ok_or(CborConversionError::BadCbor)?)?;
+        let credentials = extract_array(credentials.ok_or(CborConversionError::BadCbor)?)?
+            .into_iter()
+            .map(extract_byte_string)
+            .collect::<Result<_, _>>()?;
+
+        Ok(FidoEntries { salt, iterations, credentials })
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub enum DataPacket {
     Password(PasswordEntries),
     TOTP(TotpEntries),
+    Fido(FidoEntries),
 }
 
 impl DataPacket {
@@ -263,6 +383,32 @@ impl DataPacket {
         match self {
             DataPacket::Password(_) => 1,
             DataPacket::TOTP(_) => 2,
+            DataPacket::Fido(_) => 3,
+        }
+    }
+
+    /// Drops every secret, keeping only what tells the entries apart.
+    pub fn without_secrets(self) -> Self {
+        match self {
+            DataPacket::Password(mut passwords) => {
+                for pw in passwords.0.iter_mut() {
+                    pw.password.clear();
+                    pw.notes.clear();
+                    pw.history.clear();
+                }
+                DataPacket::Password(passwords)
+            }
+            DataPacket::TOTP(mut totps) => {
+                for t in totps.0.iter_mut() {
+                    t.shared_secret.clear();
+                }
+                DataPacket::TOTP(totps)
+            }
+            DataPacket::Fido(fido) => DataPacket::Fido(FidoEntries {
+                salt: vec![],
+                iterations: 0,
+                credentials: vec![vec![]; fido.credentials.len()],
+            }),
         }
     }
 }
@@ -292,6 +438,12 @@ impl From<DataPacket> for cbor::Value {
                     cbor_int!(2) => t,
                 }
             }
+            DataPacket::Fido(ref f) => {
+                cbor_map! {
+                    cbor_int!(1) => dpt,
+                    cbor_int!(2) => f,
+                }
+            }
         }
     }
 }
@@ -325,6 +477,11 @@ impl TryFrom<cbor::Value> for DataPacket {
                 let pes: TotpEntries = shared_secret.try_into()?;
                 DataPacket::TOTP(pes)
             }
+            3 => {
+                // DataPacket::Fido
+                let fes: FidoEntries = shared_secret.try_into()?;
+                DataPacket::Fido(fes)
+            }
             others => panic!("cannot convert from data packet type {} from cbor::Value!", others),
         };
 
@@ -335,6 +492,7 @@ impl TryFrom<cbor::Value> for DataPacket {
 pub enum PayloadType {
     TOTP,
     Password,
+    Fido,
 }
 
 impl From<&PayloadType> for u8 {
@@ -342,6 +500,7 @@ impl From<&PayloadType> for u8 {
         match t {
             PayloadType::TOTP => 1,
             PayloadType::Password => 2,
+            PayloadType::Fido => 3,
         }
     }
 }
@@ -362,6 +521,7 @@ impl TryFrom<&Vec<u8>> for PayloadType {
         match u[0] {
             1 => Ok(PayloadType::TOTP),
             2 => Ok(PayloadType::Password),
+            3 => Ok(PayloadType::Fido),
             _ => Err(PayloadTypeError::BadType),
         }
     }
@@ -529,3 +689,66 @@ fn extract_string(cbor_value: cbor::Value) -> Result<String, CborConversionError
         _ => Err(CborConversionError::BadCbor),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(history: Vec<PreviousPassword>) -> PasswordEntry {
+        PasswordEntry {
+            description: "example.com".to_string(),
+            username: "alice".to_string(),
+            password: "now:with:colons".to_string(),
+            notes: "line one\nline two".to_string(),
+            history,
+        }
+    }
+
+    fn round_trip(entries: &PasswordEntries) -> PasswordEntries {
+        let bytes: Vec<u8> = entries.into();
+        PasswordEntries::try_from(cbor::read(&bytes).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn previous_passwords_round_trip() {
+        let history = vec![
+            PreviousPassword { password: "was:here".to_string(), retired: 1_700_000_000 },
+            PreviousPassword { password: ":".to_string(), retired: 0 },
+            PreviousPassword { password: "plain".to_string(), retired: u64::MAX },
+        ];
+        let restored = round_trip(&PasswordEntries(vec![entry(history.clone())]));
+        assert_eq!(restored.0.len(), 1);
+        let restored = &restored.0[0];
+        assert_eq!(restored.password, "now:with:colons");
+        assert_eq!(restored.notes, "line one\nline two");
+        assert_eq!(restored.history.len(), history.len());
+        for (restored, original) in restored.history.iter().zip(history.iter()) {
+            assert_eq!(restored.password, original.password);
+            assert_eq!(restored.retired, original.retired);
+        }
+    }
+
+    #[test]
+    fn entries_without_history() {
+        // backups from before the history was kept have no key 5
+        let old = cbor_map! {
+            cbor_int!(1) => "example.com",
+            cbor_int!(2) => "alice",
+            cbor_int!(3) => "secret",
+            cbor_int!(4) => "",
+        };
+        let restored = PasswordEntry::try_from(old).unwrap();
+        assert_eq!(restored.password, "secret");
+        assert!(restored.history.is_empty());
+
+        let restored = round_trip(&PasswordEntries(vec![entry(Vec::new())]));
+        assert!(restored.0[0].history.is_empty());
+    }
+
+    #[test]
+    fn malformed_previous_password() {
+        let missing_time = cbor_map! { cbor_int!(1) => "was" };
+        assert!(PreviousPassword::try_from(missing_time).is_err());
+        assert!(PreviousPassword::try_from(cbor_int!(1)).is_err());
+    }
+}
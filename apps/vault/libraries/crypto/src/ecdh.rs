@@ -117,6 +117,17 @@ impl SecKey {
     }
 }
 
+// DH key agreement with a long-lived secret, e.g. the decryption key of an OpenPGP card. `peer` is
+// a SEC1 encoded point; None means either the secret or the point was invalid.
+pub fn exchange_x_static(secret: &[u8; NBYTES], peer: &[u8]) -> Option<[u8; 32]> {
+    let sk = p256::SecretKey::from_be_bytes(secret).ok()?;
+    let pk = PublicKey::from_sec1_bytes(peer).ok()?;
+    let shared = p256::elliptic_curve::ecdh::diffie_hellman(sk.to_nonzero_scalar(), pk.as_affine());
+    let mut ret = [0u8; 32];
+    ret.copy_from_slice(shared.raw_secret_bytes().as_slice());
+    Some(ret)
+}
+
 #[cfg(test)]
 pub struct EphemeralSecretTest {
     scalar: NonZeroScalar,
@@ -347,5 +358,20 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_exchange_x_static_matches_ephemeral() {
+        let mut rng = ThreadRng256 {};
+
+        let sk_a = SecKey::gensk(&mut rng);
+        let mut pk_bytes_a = [Default::default(); 65];
+        sk_a.genpk().to_bytes_uncompressed(&mut pk_bytes_a);
+
+        let sk_b = SecKey::gensk(&mut rng);
+        let secret_b: [u8; 32] = sk_b.to_bytes().as_slice().try_into().unwrap();
+        let pk_b = sk_b.genpk();
+        assert_eq!(exchange_x_static(&secret_b, &pk_bytes_a), Some(sk_a.exchange_x(&pk_b)));
+        assert_eq!(exchange_x_static(&secret_b, &pk_bytes_a[..64]), None);
+    }
+
     // TODO: tests with invalid public shares.
 }
@@ -17,6 +17,7 @@ use p256::{
     ecdsa::{SigningKey, Signature as P256Signature, signature::{Signer, RandomizedSigner}},
 };
 use p256::ecdsa::VerifyingKey;
+use p256::ecdsa::signature::hazmat::PrehashSigner;
 use rand_core::OsRng;
 
 use super::rng256::Rng256;
@@ -79,6 +80,16 @@ impl SecKey {
         }
     }
 
+    // ECDSA signature over a digest that was computed elsewhere, e.g. by the host of an OpenPGP card.
+    // Digests longer than the curve order are truncated; None means the digest was too short (under
+    // 16 bytes) to be signed safely.
+    pub fn sign_prehash(&self, digest: &[u8]) -> Option<Signature> {
+        match self.k.sign_prehash(digest) {
+            Ok(sig) => Some(Signature{sig}),
+            _ => None,
+        }
+    }
+
     pub fn from_bytes(bytes: &[u8; 32]) -> Option<SecKey> {
         let sk = SigningKey::from_bytes(bytes);
         match sk {
@@ -181,8 +192,8 @@ impl Signature {
         }
     }
 
-    #[cfg(test)]
-    fn to_bytes(&self, bytes: &mut [u8; 64]) {
+    // Raw r || s encoding, as used by OpenPGP cards.
+    pub fn to_bytes(&self, bytes: &mut [u8; 64]) {
         bytes[..32].copy_from_slice(self.sig.r().to_bytes().as_slice());
         bytes[32..].copy_from_slice(self.sig.s().to_bytes().as_slice());
         //self.r.to_int().to_bin(array_mut_ref![bytes, 0, 32]);
@@ -486,6 +497,22 @@ mod test {
         }
     }
 
+    // Test that a signature over a host-computed digest verifies against the message.
+    #[test]
+    fn test_sign_prehash_verify_random() {
+        let mut rng = ThreadRng256 {};
+
+        for _ in 0..ITERATIONS {
+            let msg = rng.gen_uniform_u8x32();
+            let sk = SecKey::gensk(&mut rng);
+            let pk = sk.genpk();
+            let sign = sk.sign_prehash(&Sha256::hash(&msg)).unwrap();
+            assert!(pk.verify_vartime::<Sha256>(&msg, &sign));
+        }
+        let sk = SecKey::gensk(&mut rng);
+        assert!(sk.sign_prehash(&[0x55; 15]).is_none());
+    }
+
     /** Tests that this code is compatible with the ring crate **/
     // Test that the ring crate works properly.
     #[test]
@@ -3,27 +3,34 @@ use std::cell::RefCell;
 use std::io::ErrorKind;
 use std::io::{Read, Write};
 use std::sync::{
-    atomic::{AtomicBool, Ordering},
     Arc, Mutex,
+    atomic::{AtomicBool, Ordering},
 };
 
+use chrono::NaiveDateTime;
 use gam::TextEntryPayload;
 use locales::t;
 use num_traits::*;
-use passwords::PasswordGenerator;
 use pddb::BasisRetentionPolicy;
 #[cfg(feature = "vaultperf")]
 use perflib::*;
 use persistent_store::store::OPENSK2_DICT;
 use vault::env::xous::U2F_APP_DICT;
 use vault::{
-    atime_to_str, basis_change, ctap::data_formats::PublicKeyCredentialSource, deserialize_app_info,
-    serialize_app_info, utc_now, AppInfo, VAULT_ALLOC_HINT, VAULT_PASSWORD_DICT, VAULT_TOTP_DICT,
+    AppInfo, VAULT_ALLOC_HINT, VAULT_PASSWORD_DICT, VAULT_TOTP_DICT, atime_to_str, basis_change,
+    ctap::data_formats::{PublicKeyCredentialSource, SignatureAlgorithm},
+    deserialize_app_info, serialize_app_info, utc_now,
 };
 use xous::{send_message, Message};
 
-use crate::storage::{self, PasswordRecord, StorageContent};
-use crate::totp::TotpAlgorithm;
+use crate::import;
+use crate::openpgp;
+use crate::pwgen::{self, SitePolicy};
+use crate::ssh_agent::{self, SshKey};
+use crate::storage::{
+    self, GeneratorProfileRecord, OpenPgpCardRecord, PasswordRecord, SshKeyRecord, StorageContent,
+};
+use crate::totp::{self, TotpAlgorithm};
 use crate::{storage::TotpRecord, ListItem, ListKey};
 use crate::{ItemLists, SelectedEntry, VaultMode};
 #[cfg(feature = "vaultperf")]
@@ -41,9 +48,16 @@ pub enum ActionOp {
     MenuAddnew,
     MenuEditStage2,
     MenuDeleteStage2,
+    MenuEntryLayoutStage2,
+    MenuEntryGeneratorStage2,
+    MenuRotateStage2,
     MenuClose,
     MenuUnlockBasis,
     MenuManageBasis,
+    MenuImport,
+    MenuSshAgent,
+    MenuOpenPgp,
+    MenuGeneratorProfiles,
     /// Internal ops
     UpdateMode,
     UpdateOneItem,
@@ -67,6 +81,7 @@ pub struct ActionManager<'a> {
     tt: ticktimer_server::Ticktimer,
     action_active: Arc<AtomicBool>,
     opensk_mutex: Arc<Mutex<i32>>,
+    lefty_mode: Arc<AtomicBool>,
     mode_cache: VaultMode,
     main_conn: xous::CID,
     #[cfg(feature = "vaultperf")]
@@ -85,6 +100,7 @@ impl<'a> ActionManager<'a> {
         item_lists: Arc<Mutex<ItemLists>>,
         action_active: Arc<AtomicBool>,
         opensk_mutex: Arc<Mutex<i32>>,
+        lefty_mode: Arc<AtomicBool>,
     ) -> ActionManager<'a> {
         let xns = xous_names::XousNames::new().unwrap();
         let storage_manager = storage::Manager::new(&xns);
@@ -118,6 +134,7 @@ impl<'a> ActionManager<'a> {
             tt: ticktimer_server::Ticktimer::new().unwrap(),
             action_active,
             opensk_mutex,
+            lefty_mode,
             main_conn,
             #[cfg(feature = "vaultperf")]
             perfbuf,
@@ -188,163 +205,23 @@ impl<'a> ActionManager<'a> {
                 };
                 #[cfg(feature = "ux-swap-delay")]
                 self.tt.sleep_ms(SWAP_DELAY_MS).unwrap();
-                let mut approved = false;
-                let mut bip39 = false;
-                // Security note about PasswordGenerator. This is a 3rd party crate. It relies on `rand`'s
-                // implementation of ThreadRng to generate passwords. As of the version
-                // committed to the lockfile, I have evidenced the ThreadRng to request 8
-                // bytes of entropy from our TRNG to seed its state. If the docs are to be trusted,
-                // its thread-local RNG is a ChaCha CSPRNG, although the number of rounds used in it is not
-                // clear; code says 12 rounds, code comments say 20 and reference an issue
-                // about how this should be reduced. Audit path
-                // Cargo.lock is at:
-                //  rand-0.8.5
-                //  rand_core 0.6.3
-                //  getrandom 0.2.6 -> xous fork via Patch in top level Cargo.toml to map crates-io.getrandom
-                // to imports/getrandom  rand_chacha 0.3.1
-                //  passwords 3.1.9
-                //  random-pick 1.2.15
-                //  random-number 0.1.7
-                //  random-number-macro-mipl 0.1.6
-                //  proc-macro-hack : 0.5.19...and more (syn/quote also pulled in)
-                // - PasswordGenerator
-                //   - PasswordGeneratorIter::generate()
-                //   - random_pick::pick_multiple_from_multiple_slices()
-                //     - random_pick::gen_multiple_usize_with_weights()
-                //       - rng = random_number::rand::thread_rng()
-                //         - ThreadRng::thread_rng()
-                //         - Some crazy unsafe refcell construction that returns a clone of a
-                //           ReseedingRng<Core, OsRng>
-                //           - rand-0.8.5::std line 13: pub(crate) use rand_chacha::ChaCha12Core as Core;
-                //           - confirm no feature flags gating this, it is always used
-                //           - OsRng::try_fill_bytes()
-                //             - getrandom() -> to Xous code
-                //               - getrandom Xous fork - imp::getrandom_inner()
-                //                 - getrandom Xous fork - ensure_trng_conn() then fill_bytes() native Xous
-                //                   call
-                //       - random_number::random!(0..high, rng)
-                //         - random_number::random_with_rng
-                //           - random_number::random_inclusively_with_rng()
-                //             - Uniform::new_inclusive().sample()
-                //               - dead end at Distribution Trait and UniformSampler Trait, let's hope this is
-                //                 correct?
-                let pg = PasswordGenerator {
-                    length: 20,
-                    numbers: true,
-                    lowercase_letters: true,
-                    uppercase_letters: true,
-                    symbols: true,
-                    spaces: false,
-                    exclude_similar_characters: true,
-                    strict: true,
+                let profiles = pwgen::profiles(&self.storage.borrow());
+                let (password, profile) = match self.choose_password(&profiles, None, None) {
+                    Some(choice) => choice,
+                    None => {
+                        self.action_active.store(false, Ordering::SeqCst);
+                        return;
+                    }
+                };
+                let bip39 = password == "bip39";
+                let password = if bip39 {
+                    match self.modals.input_bip39(Some(t!("vault.bip39.input", locales::LANG))) {
+                        Ok(data) => hex::encode(data),
+                        _ => "".to_string(),
+                    }
+                } else {
+                    password
                 };
-                let mut password = pg.generate_one().unwrap();
-                while !approved {
-                    let maybe_password = match self
-                        .modals
-                        .alert_builder(t!("vault.newitem.password", locales::LANG))
-                        .field(Some(password), Some(password_validator))
-                        .build()
-                    {
-                        Ok(text) => text.content()[0].content.as_str().unwrap().to_string(),
-                        _ => {
-                            log::error!("Name entry failed");
-                            self.action_active.store(false, Ordering::SeqCst);
-                            return;
-                        }
-                    };
-                    #[cfg(feature = "ux-swap-delay")]
-                    self.tt.sleep_ms(SWAP_DELAY_MS).unwrap();
-                    password = if maybe_password.len() == 0 {
-                        let length = match self
-                            .modals
-                            .alert_builder(t!("vault.newitem.configure_length", locales::LANG))
-                            .field(Some("20".to_string()), Some(length_validator))
-                            .build()
-                        {
-                            Ok(entry) => entry.content()[0].content.as_str().unwrap().parse::<u32>().unwrap(),
-                            _ => {
-                                log::error!("Length entry failed");
-                                self.action_active.store(false, Ordering::SeqCst);
-                                return;
-                            }
-                        };
-                        #[cfg(feature = "ux-swap-delay")]
-                        self.tt.sleep_ms(SWAP_DELAY_MS).unwrap();
-                        let mut upper = false;
-                        let mut number = false;
-                        let mut symbol = false;
-                        let mut lower = false;
-                        while !upper && !number && !symbol && !lower {
-                            self.modals
-                                .add_list(vec![
-                                    t!("vault.newitem.lowercase", locales::LANG),
-                                    t!("vault.newitem.uppercase", locales::LANG),
-                                    t!("vault.newitem.numbers", locales::LANG),
-                                    t!("vault.newitem.symbols", locales::LANG),
-                                ])
-                                .expect("couldn't create configuration modal");
-                            match self
-                                .modals
-                                .get_checkbox(t!("vault.newitem.configure_generator", locales::LANG))
-                            {
-                                Ok(options) => {
-                                    for opt in options {
-                                        if opt == t!("vault.newitem.lowercase", locales::LANG) {
-                                            lower = true;
-                                        }
-                                        if opt == t!("vault.newitem.uppercase", locales::LANG) {
-                                            upper = true;
-                                        }
-                                        if opt == t!("vault.newitem.numbers", locales::LANG) {
-                                            number = true;
-                                        }
-                                        if opt == t!("vault.newitem.symbols", locales::LANG) {
-                                            symbol = true;
-                                        }
-                                    }
-                                }
-                                _ => {
-                                    log::error!("Modal selection error");
-                                    self.action_active.store(false, Ordering::SeqCst);
-                                    return;
-                                }
-                            }
-                            if upper == false && lower == false && symbol == false && number == false {
-                                self.modals
-                                    .show_notification(
-                                        t!("vault.error.nothing_selected", locales::LANG),
-                                        None,
-                                    )
-                                    .ok();
-                            }
-                        }
-                        #[cfg(feature = "ux-swap-delay")]
-                        self.tt.sleep_ms(SWAP_DELAY_MS).unwrap();
-                        let pg2 = PasswordGenerator {
-                            length: length as usize,
-                            numbers: number,
-                            lowercase_letters: lower,
-                            uppercase_letters: upper,
-                            symbols: symbol,
-                            spaces: false,
-                            exclude_similar_characters: upper || lower,
-                            strict: true,
-                        };
-                        approved = false;
-                        pg2.generate_one().unwrap()
-                    } else if maybe_password == "bip39" {
-                        bip39 = true;
-                        approved = true;
-                        match self.modals.input_bip39(Some(t!("vault.bip39.input", locales::LANG))) {
-                            Ok(data) => hex::encode(data),
-                            _ => "".to_string(),
-                        }
-                    } else {
-                        approved = true;
-                        maybe_password
-                    };
-                }
                 let mut record = storage::PasswordRecord {
                     version: VAULT_PASSWORD_REC_VERSION,
                     description,
@@ -358,6 +235,12 @@ impl<'a> ActionManager<'a> {
                     ctime: 0,
                     atime: 0,
                     count: 0,
+                    layout: None,
+                    tags: Vec::new(),
+                    history: Vec::new(),
+                    rotating: false,
+                    profile,
+                    policy: None,
                 };
 
                 match self.storage.borrow_mut().new_record(&mut record, None, true) {
@@ -396,16 +279,15 @@ impl<'a> ActionManager<'a> {
                     .add_list(vec![
                         t!("vault.newitem.totp", locales::LANG),
                         t!("vault.newitem.hotp", locales::LANG),
+                        t!("vault.newitem.steam", locales::LANG),
                     ])
                     .expect("couldn't create configuration modal");
                 let is_totp: bool;
+                let is_steam: bool;
                 match self.modals.get_radiobutton(t!("vault.newitem.is_t_or_h_otp", locales::LANG)) {
                     Ok(response) => {
-                        if &response == t!("vault.newitem.totp", locales::LANG) {
-                            is_totp = true;
-                        } else {
-                            is_totp = false;
-                        }
+                        is_totp = &response != t!("vault.newitem.hotp", locales::LANG);
+                        is_steam = &response == t!("vault.newitem.steam", locales::LANG);
                     }
                     _ => {
                         log::error!("Modal selection error");
@@ -467,21 +349,57 @@ impl<'a> ActionManager<'a> {
                             return;
                         }
                     }
+                } else if is_steam {
+                    30 // Steam Guard codes always change every 30 seconds
                 } else {
-                    30 // default TOTP timestep otherwise
+                    #[cfg(feature = "ux-swap-delay")]
+                    self.tt.sleep_ms(SWAP_DELAY_MS).unwrap();
+                    match self
+                        .modals
+                        .alert_builder(t!("vault.newitem.totp_period", locales::LANG))
+                        .field(Some("30".to_string()), Some(period_validator))
+                        .build()
+                    {
+                        Ok(entry) => entry.content()[0].content.as_str().unwrap().parse::<u64>().unwrap(),
+                        _ => {
+                            log::error!("Period entry failed");
+                            self.action_active.store(false, Ordering::SeqCst);
+                            return;
+                        }
+                    }
+                };
+                let digits = if is_steam {
+                    totp::STEAM_DIGITS
+                } else {
+                    #[cfg(feature = "ux-swap-delay")]
+                    self.tt.sleep_ms(SWAP_DELAY_MS).unwrap();
+                    match self
+                        .modals
+                        .alert_builder(t!("vault.newitem.digits", locales::LANG))
+                        .field(Some(totp::MIN_DIGITS.to_string()), Some(digits_validator))
+                        .build()
+                    {
+                        Ok(entry) => entry.content()[0].content.as_str().unwrap().parse::<u32>().unwrap(),
+                        _ => {
+                            log::error!("Digits entry failed");
+                            self.action_active.store(false, Ordering::SeqCst);
+                            return;
+                        }
+                    }
                 };
 
-                // time, hash, etc. are all the "expected defaults" -- if you want to change them, edit the
-                // record after entering it.
+                // the hash is the "expected default" -- if you want to change it, edit the record after
+                // entering it.
                 let mut totp = storage::TotpRecord {
                     version: VAULT_TOTP_REC_VERSION,
                     name: description,
                     secret: validated_secret,
-                    algorithm: TotpAlgorithm::HmacSha1,
-                    digits: 6,
+                    algorithm: if is_steam { TotpAlgorithm::Steam } else { TotpAlgorithm::HmacSha1 },
+                    digits,
                     timestep,
                     ctime: 0,
                     is_hotp: !is_totp,
+                    tags: Vec::new(),
                     notes: t!("vault.notes", locales::LANG).to_string(),
                 };
 
@@ -745,39 +663,46 @@ impl<'a> ActionManager<'a> {
                 };
 
                 let edit_data = if pw.notes != t!("vault.notes", locales::LANG) {
-                    self.modals
-                        .alert_builder(t!("vault.edit_dialog", locales::LANG))
-                        .field_placeholder_persist(Some(pw.name), Some(password_validator))
-                        .field_placeholder_persist(Some(pw.secret), Some(password_validator))
-                        .field_placeholder_persist(Some(pw.notes), Some(password_validator))
-                        .field(Some(pw.timestep.to_string()), Some(password_validator))
-                        .field(Some(pw.algorithm.to_string()), Some(password_validator))
-                        .field(Some(pw.digits.to_string()), Some(password_validator))
-                        .field(
-                            Some(if pw.is_hotp { "HOTP".to_string() } else { "TOTP".to_string() }),
-                            Some(password_validator),
-                        )
-                        .build()
-                        .expect("modals error in edit")
+                    tags_field(
+                        self.modals
+                            .alert_builder(t!("vault.edit_dialog", locales::LANG))
+                            .field_placeholder_persist(Some(pw.name), Some(password_validator))
+                            .field_placeholder_persist(Some(pw.secret), Some(password_validator))
+                            .field_placeholder_persist(Some(pw.notes), Some(password_validator))
+                            .field(Some(pw.timestep.to_string()), Some(count_validator))
+                            .field(Some(pw.algorithm.to_string()), Some(algorithm_validator))
+                            .field(Some(pw.digits.to_string()), Some(edit_digits_validator))
+                            .field(
+                                Some(if pw.is_hotp { "HOTP".to_string() } else { "TOTP".to_string() }),
+                                Some(password_validator),
+                            ),
+                        &pw.tags,
+                    )
+                    .build()
+                    .expect("modals error in edit")
                 } else {
-                    self.modals
-                        .alert_builder(t!("vault.edit_dialog", locales::LANG))
-                        .field_placeholder_persist(Some(pw.name), Some(password_validator))
-                        .field_placeholder_persist(Some(pw.secret), Some(password_validator))
-                        .field(Some(pw.notes), Some(password_validator))
-                        .field(Some(pw.timestep.to_string()), Some(password_validator))
-                        .field(Some(pw.algorithm.to_string()), Some(password_validator))
-                        .field(Some(pw.digits.to_string()), Some(password_validator))
-                        .field(
-                            Some(if pw.is_hotp { "HOTP".to_string() } else { "TOTP".to_string() }),
-                            Some(password_validator),
-                        )
-                        .build()
-                        .expect("modals error in edit")
+                    tags_field(
+                        self.modals
+                            .alert_builder(t!("vault.edit_dialog", locales::LANG))
+                            .field_placeholder_persist(Some(pw.name), Some(password_validator))
+                            .field_placeholder_persist(Some(pw.secret), Some(password_validator))
+                            .field(Some(pw.notes), Some(password_validator))
+                            .field(Some(pw.timestep.to_string()), Some(count_validator))
+                            .field(Some(pw.algorithm.to_string()), Some(algorithm_validator))
+                            .field(Some(pw.digits.to_string()), Some(edit_digits_validator))
+                            .field(
+                                Some(if pw.is_hotp { "HOTP".to_string() } else { "TOTP".to_string() }),
+                                Some(password_validator),
+                            ),
+                        &pw.tags,
+                    )
+                    .build()
+                    .expect("modals error in edit")
                 };
                 pw.name = edit_data.content()[0].content.as_str().unwrap().to_string();
                 pw.secret = edit_data.content()[1].content.as_str().unwrap().to_string();
                 pw.notes = edit_data.content()[2].content.as_str().unwrap().to_string();
+                pw.tags = storage::tags_from_str(edit_data.content()[7].content.as_str().unwrap());
                 pw.is_hotp = if edit_data.content()[6].content.as_str().unwrap().to_string().to_uppercase()
                     == "HOTP"
                 {
@@ -786,13 +711,27 @@ impl<'a> ActionManager<'a> {
                     false
                 };
                 if let Ok(t) = u64::from_str_radix(edit_data.content()[3].content.as_str().unwrap(), 10) {
-                    pw.timestep = t;
+                    // a TOTP period of 0 would never roll over; the HOTP count can be anything
+                    if pw.is_hotp || t != 0 {
+                        pw.timestep = t;
+                    }
                 }
-                if let Ok(alg) = TotpAlgorithm::try_from(edit_data.content()[4].content.as_str().unwrap()) {
+                if let Ok(alg) = TotpAlgorithm::try_from(
+                    edit_data.content()[4].content.as_str().unwrap().to_uppercase().as_str(),
+                ) {
                     pw.algorithm = alg;
                 }
                 if let Ok(d) = u32::from_str_radix(edit_data.content()[5].content.as_str().unwrap(), 10) {
-                    pw.digits = d;
+                    if let TotpAlgorithm::Steam = pw.algorithm {
+                        // Steam Guard codes are always 5 characters, whatever the field says
+                        pw.digits = totp::STEAM_DIGITS;
+                    } else if (totp::MIN_DIGITS..=totp::MAX_DIGITS).contains(&d) {
+                        pw.digits = d;
+                    } else {
+                        self.report_err(t!("vault.illegal_digits", locales::LANG), None::<std::io::Error>);
+                        self.action_active.store(false, Ordering::SeqCst);
+                        return;
+                    }
                 }
                 // update the disk
                 let ret = storage.update(&choice, key_guid, &mut pw);
@@ -811,6 +750,7 @@ impl<'a> ActionManager<'a> {
                         return;
                     }
                 };
+                let previous_password = pw.password.clone();
                 // remove the entry from the old UX list
                 let mut desc = String::new();
                 make_pw_name(&pw.description, &pw.username, &mut desc);
@@ -826,32 +766,39 @@ impl<'a> ActionManager<'a> {
 
                 // display previous data for edit
                 let edit_data = if pw.notes != t!("vault.notes", locales::LANG) {
-                    self.modals
-                        .alert_builder(t!("vault.edit_dialog", locales::LANG))
-                        .field_placeholder_persist(Some(pw.description), Some(password_validator))
-                        .field_placeholder_persist(Some(pw.username), Some(password_validator))
-                        .field_placeholder_persist(Some(pw.password), Some(password_validator))
-                        .field_placeholder_persist(Some(pw.notes), Some(password_validator))
-                        .set_growable()
-                        .build()
-                        .expect("modals error in edit")
+                    tags_field(
+                        self.modals
+                            .alert_builder(t!("vault.edit_dialog", locales::LANG))
+                            .field_placeholder_persist(Some(pw.description), Some(password_validator))
+                            .field_placeholder_persist(Some(pw.username), Some(password_validator))
+                            .field_placeholder_persist(Some(pw.password), Some(password_validator))
+                            .field_placeholder_persist(Some(pw.notes), Some(password_validator)),
+                        &pw.tags,
+                    )
+                    .set_growable()
+                    .build()
+                    .expect("modals error in edit")
                 } else {
                     // note is placeholder text, treat it as such
-                    self.modals
-                        .alert_builder(t!("vault.edit_dialog", locales::LANG))
-                        .field_placeholder_persist(Some(pw.description), Some(password_validator))
-                        .field_placeholder_persist(Some(pw.username), Some(password_validator))
-                        .field_placeholder_persist(Some(pw.password), Some(password_validator))
-                        .field(Some(pw.notes), Some(password_validator))
-                        .set_growable()
-                        .build()
-                        .expect("modals error in edit")
+                    tags_field(
+                        self.modals
+                            .alert_builder(t!("vault.edit_dialog", locales::LANG))
+                            .field_placeholder_persist(Some(pw.description), Some(password_validator))
+                            .field_placeholder_persist(Some(pw.username), Some(password_validator))
+                            .field_placeholder_persist(Some(pw.password), Some(password_validator))
+                            .field(Some(pw.notes), Some(password_validator)),
+                        &pw.tags,
+                    )
+                    .set_growable()
+                    .build()
+                    .expect("modals error in edit")
                 };
 
                 pw.description = edit_data.content()[0].content.as_str().unwrap().to_string();
                 pw.username = edit_data.content()[1].content.as_str().unwrap().to_string();
                 pw.password = edit_data.content()[2].content.as_str().unwrap().to_string();
                 pw.notes = edit_data.content()[3].content.as_str().unwrap().to_string();
+                pw.tags = storage::tags_from_str(edit_data.content()[4].content.as_str().unwrap());
 
                 // if the notes field starts with the word "bip39" (case insensitive), use BIP39 to
                 // display/edit the password field
@@ -890,125 +837,23 @@ impl<'a> ActionManager<'a> {
                     }
                 } else if pw.password.len() == 0 && !pw.notes.to_ascii_lowercase().starts_with("bip39") {
                     // if the password is empty, prompt to generate a new password
-                    let pg = PasswordGenerator {
-                        length: 20,
-                        numbers: true,
-                        lowercase_letters: true,
-                        uppercase_letters: true,
-                        symbols: true,
-                        spaces: false,
-                        exclude_similar_characters: true,
-                        strict: true,
-                    };
-                    let mut password = pg.generate_one().unwrap();
-                    let mut approved = false;
-                    while !approved {
-                        let maybe_password = match self
-                            .modals
-                            .alert_builder(t!("vault.newitem.password", locales::LANG))
-                            .field(Some(password), Some(password_validator))
-                            .build()
-                        {
-                            Ok(text) => text.content()[0].content.as_str().unwrap().to_string(),
-                            _ => {
-                                log::error!("Name entry failed");
-                                self.action_active.store(false, Ordering::SeqCst);
-                                return;
-                            }
-                        };
-                        #[cfg(feature = "ux-swap-delay")]
-                        self.tt.sleep_ms(SWAP_DELAY_MS).unwrap();
-                        password = if maybe_password.len() == 0 {
-                            let length = match self
-                                .modals
-                                .alert_builder(t!("vault.newitem.configure_length", locales::LANG))
-                                .field(Some("20".to_string()), Some(length_validator))
-                                .build()
-                            {
-                                Ok(entry) => {
-                                    entry.content()[0].content.as_str().unwrap().parse::<u32>().unwrap()
-                                }
-                                _ => {
-                                    log::error!("Length entry failed");
-                                    self.action_active.store(false, Ordering::SeqCst);
-                                    return;
-                                }
-                            };
-                            #[cfg(feature = "ux-swap-delay")]
-                            self.tt.sleep_ms(SWAP_DELAY_MS).unwrap();
-                            let mut upper = false;
-                            let mut number = false;
-                            let mut symbol = false;
-                            let mut lower = false;
-                            while !upper && !number && !symbol && !lower {
-                                self.modals
-                                    .add_list(vec![
-                                        t!("vault.newitem.lowercase", locales::LANG),
-                                        t!("vault.newitem.uppercase", locales::LANG),
-                                        t!("vault.newitem.numbers", locales::LANG),
-                                        t!("vault.newitem.symbols", locales::LANG),
-                                    ])
-                                    .expect("couldn't create configuration modal");
-                                match self
-                                    .modals
-                                    .get_checkbox(t!("vault.newitem.configure_generator", locales::LANG))
-                                {
-                                    Ok(options) => {
-                                        for opt in options {
-                                            if opt == t!("vault.newitem.lowercase", locales::LANG) {
-                                                lower = true;
-                                            }
-                                            if opt == t!("vault.newitem.uppercase", locales::LANG) {
-                                                upper = true;
-                                            }
-                                            if opt == t!("vault.newitem.numbers", locales::LANG) {
-                                                number = true;
-                                            }
-                                            if opt == t!("vault.newitem.symbols", locales::LANG) {
-                                                symbol = true;
-                                            }
-                                        }
-                                    }
-                                    _ => {
-                                        log::error!("Modal selection error");
-                                        self.action_active.store(false, Ordering::SeqCst);
-                                        return;
-                                    }
-                                }
-                                if upper == false && lower == false && symbol == false && number == false {
-                                    self.modals
-                                        .show_notification(
-                                            t!("vault.error.nothing_selected", locales::LANG),
-                                            None,
-                                        )
-                                        .ok();
-                                }
-                            }
-                            #[cfg(feature = "ux-swap-delay")]
-                            self.tt.sleep_ms(SWAP_DELAY_MS).unwrap();
-                            let pg2 = PasswordGenerator {
-                                length: length as usize,
-                                numbers: number,
-                                lowercase_letters: lower,
-                                uppercase_letters: upper,
-                                symbols: symbol,
-                                spaces: false,
-                                exclude_similar_characters: upper || lower,
-                                strict: true,
-                            };
-                            approved = false;
-                            pg2.generate_one().unwrap()
-                        } else {
-                            approved = true;
-                            maybe_password
-                        };
+                    let profiles = pwgen::profiles(&storage);
+                    match self.choose_password(&profiles, pw.profile.clone(), pw.policy.as_ref()) {
+                        Some((password, profile)) => {
+                            pw.password = password;
+                            pw.profile = profile;
+                        }
+                        None => {
+                            self.action_active.store(false, Ordering::SeqCst);
+                            return;
+                        }
                     }
-                    pw.password = password;
                 }
 
                 // note the edit access, this counts as an access since the password was revealed
                 pw.count += 1;
                 pw.atime = utc_now().timestamp() as u64;
+                pw.retire_password(previous_password, pw.atime);
                 // update disk
                 let ret = storage.update(&choice, key_guid, &mut pw);
                 if ret.is_ok() {
@@ -1026,6 +871,190 @@ impl<'a> ActionManager<'a> {
         }
     }
 
+    /// Picks the host keyboard layout that a password entry is autotyped with
+    pub(crate) fn menu_entry_layout(&mut self, entry: SelectedEntry) {
+        if entry.mode != VaultMode::Password {
+            self.modals.show_notification(t!("vault.error.layout_passwords_only", locales::LANG), None).ok();
+            return;
+        }
+        let choice = storage::ContentKind::Password;
+        let key_guid = entry.key_guid.as_str().unwrap();
+        let mut storage = self.storage.borrow_mut();
+        let mut pw: storage::PasswordRecord = match storage.get_record(&choice, key_guid) {
+            Ok(record) => record,
+            Err(error) => {
+                self.report_err(t!("vault.error.internal_error", locales::LANG), Some(error));
+                return;
+            }
+        };
+
+        let global = t!("vault.layout_global", locales::LANG);
+        let names: Vec<String> = storage::host_layouts().map(|layout| layout.to_string()).collect();
+        let mut list = vec![global];
+        list.extend(names.iter().map(|name| name.as_str()));
+        self.modals.add_list(list).expect("couldn't build layout list");
+        let current = pw.layout.map(|layout| layout.to_string()).unwrap_or(global.to_string());
+        let selection = match self.modals.get_radiobutton(&format!(
+            "{} {}",
+            t!("vault.layout_current", locales::LANG),
+            current
+        )) {
+            Ok(selection) => selection,
+            _ => {
+                log::error!("Modal selection error");
+                return;
+            }
+        };
+        pw.layout = storage::host_layouts().find(|layout| layout.to_string() == selection);
+        if let Err(e) = storage.update(&choice, key_guid, &mut pw) {
+            self.report_err(t!("vault.error.internal_error", locales::LANG), Some(e));
+        }
+    }
+
+    /// Sets the profile and the site policy that new passwords for an entry are generated with
+    pub(crate) fn menu_entry_generator(&mut self, entry: SelectedEntry) {
+        if entry.mode != VaultMode::Password {
+            self.modals
+                .show_notification(t!("vault.error.generator_passwords_only", locales::LANG), None)
+                .ok();
+            return;
+        }
+        let choice = storage::ContentKind::Password;
+        let key_guid = entry.key_guid.as_str().unwrap();
+        let mut storage = self.storage.borrow_mut();
+        let mut pw: storage::PasswordRecord = match storage.get_record(&choice, key_guid) {
+            Ok(record) => record,
+            Err(error) => {
+                self.report_err(t!("vault.error.internal_error", locales::LANG), Some(error));
+                return;
+            }
+        };
+
+        let profiles = pwgen::profiles(&storage);
+        self.modals
+            .add_list(profiles.iter().map(|profile| profile.name.as_str()).collect())
+            .expect("couldn't build profile list");
+        let current = pwgen::find_profile(&profiles, pw.profile.as_deref());
+        let selection = match self.modals.get_radiobutton(&format!(
+            "{} {}",
+            t!("vault.pwgen.profile_current", locales::LANG),
+            current.name
+        )) {
+            Ok(selection) => selection,
+            _ => {
+                log::error!("Modal selection error");
+                return;
+            }
+        };
+        pw.profile = if selection == pwgen::DEFAULT_PROFILE { None } else { Some(selection) };
+        #[cfg(feature = "ux-swap-delay")]
+        self.tt.sleep_ms(SWAP_DELAY_MS).unwrap();
+
+        let policy = match self
+            .modals
+            .alert_builder(t!("vault.pwgen.policy", locales::LANG))
+            .field_placeholder_persist(pw.policy.as_ref().map(|p| p.to_string()), Some(policy_validator))
+            .build()
+        {
+            Ok(text) => text.content()[0].content.as_str().unwrap().to_string(),
+            _ => {
+                log::error!("Policy entry failed");
+                return;
+            }
+        };
+        // the validator already turned down anything that doesn't parse
+        pw.policy = if policy.trim().is_empty() { None } else { SitePolicy::try_from(policy.as_str()).ok() };
+        if let Err(e) = storage.update(&choice, key_guid, &mut pw) {
+            self.report_err(t!("vault.error.internal_error", locales::LANG), Some(e));
+        }
+    }
+
+    /// Rotating a password saves a newly generated one, and keeps the one it replaces at the top of the
+    /// history until the rotation is confirmed. If the site didn't take the new password, undoing the
+    /// rotation puts the previous one back.
+    pub(crate) fn menu_rotate(&mut self, entry: SelectedEntry) {
+        if entry.mode != VaultMode::Password {
+            self.modals.show_notification(t!("vault.error.rotate_passwords_only", locales::LANG), None).ok();
+            return;
+        }
+        let choice = storage::ContentKind::Password;
+        let key_guid = entry.key_guid.as_str().unwrap();
+        let mut storage = self.storage.borrow_mut();
+        let mut pw: storage::PasswordRecord = match storage.get_record(&choice, key_guid) {
+            Ok(record) => record,
+            Err(error) => {
+                self.report_err(t!("vault.error.internal_error", locales::LANG), Some(error));
+                return;
+            }
+        };
+
+        let mut options = if pw.rotating {
+            vec![t!("vault.rotate.confirm", locales::LANG), t!("vault.rotate.undo", locales::LANG)]
+        } else {
+            vec![t!("vault.rotate.new", locales::LANG)]
+        };
+        if !pw.history.is_empty() {
+            options.push(t!("vault.rotate.history", locales::LANG));
+        }
+        self.modals.add_list(options).expect("couldn't build rotation options");
+        let selection = match self.modals.get_radiobutton(t!("vault.rotate.prompt", locales::LANG)) {
+            Ok(selection) => selection,
+            _ => {
+                log::error!("Modal selection error");
+                return;
+            }
+        };
+        #[cfg(feature = "ux-swap-delay")]
+        self.tt.sleep_ms(SWAP_DELAY_MS).unwrap();
+
+        let now = utc_now().timestamp() as u64;
+        let mut started = false;
+        if selection == t!("vault.rotate.new", locales::LANG) {
+            let profiles = pwgen::profiles(&storage);
+            // the site policy of the record shapes what is suggested, and it can still be adjusted by hand
+            let (password, profile) =
+                match self.choose_password(&profiles, pw.profile.clone(), pw.policy.as_ref()) {
+                    Some(choice) => choice,
+                    None => return,
+                };
+            pw.profile = profile;
+            let previous = std::mem::replace(&mut pw.password, password);
+            // only a password that went into the history can be gone back to: a blank or unchanged one
+            // doesn't, and undoing would then restore an even older password
+            if pw.retire_password(previous, now) {
+                pw.rotating = true;
+                started = true;
+            }
+        } else if selection == t!("vault.rotate.confirm", locales::LANG) {
+            pw.rotating = false;
+        } else if selection == t!("vault.rotate.undo", locales::LANG) {
+            // the unconfirmed password was never in use, so it isn't worth remembering
+            if !pw.history.is_empty() {
+                pw.password = pw.history.remove(0).password;
+            }
+            pw.rotating = false;
+        } else {
+            let mut history = String::from(t!("vault.rotate.history_title", locales::LANG));
+            for prev in pw.history.iter() {
+                let retired = NaiveDateTime::from_timestamp_opt(prev.retired as i64, 0)
+                    .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_default();
+                history.push_str(&format!("\n{} {}", retired, prev.password));
+            }
+            self.modals.show_notification(&history, None).ok();
+            return;
+        }
+        if let Err(e) = storage.update(&choice, key_guid, &mut pw) {
+            self.report_err(t!("vault.error.internal_error", locales::LANG), Some(e));
+            return;
+        }
+        if started {
+            #[cfg(feature = "ux-swap-delay")]
+            self.tt.sleep_ms(SWAP_DELAY_MS).unwrap();
+            self.modals.show_notification(t!("vault.rotate.started", locales::LANG), None).ok();
+        }
+    }
+
     fn yes_no_approval(&self, query: &str) -> bool {
         self.modals
             .add_list(vec![t!("vault.yes", locales::LANG), t!("vault.no", locales::LANG)])
@@ -1045,6 +1074,193 @@ impl<'a> ActionManager<'a> {
         }
     }
 
+    /// Suggests a password made with the profile called `profile`, until one is accepted. Leaving the
+    /// password blank picks another profile to make one with. Returns the password, and the profile the
+    /// record should remember.
+    fn choose_password(
+        &self,
+        profiles: &[GeneratorProfileRecord],
+        mut profile: Option<String>,
+        policy: Option<&SitePolicy>,
+    ) -> Option<(String, Option<String>)> {
+        let xns = xous_names::XousNames::new().unwrap();
+        let mut rng = ctap_crypto::rng256::XousRng256::new(&xns);
+        let mut generator = pwgen::find_profile(profiles, profile.as_deref()).clone();
+        let mut password = pwgen::generate(&generator, policy, &mut rng);
+        loop {
+            let text = match self
+                .modals
+                .alert_builder(t!("vault.newitem.password", locales::LANG))
+                .field(Some(password), Some(password_validator))
+                .build()
+            {
+                Ok(text) => text.content()[0].content.as_str().unwrap().to_string(),
+                _ => {
+                    log::error!("Password entry failed");
+                    return None;
+                }
+            };
+            #[cfg(feature = "ux-swap-delay")]
+            self.tt.sleep_ms(SWAP_DELAY_MS).unwrap();
+            if !text.is_empty() {
+                return Some((text, profile));
+            }
+            generator = self.pick_profile(profiles, &generator.name)?;
+            // a one-off configuration has no name to remember
+            if !generator.name.is_empty() {
+                profile = Some(generator.name.clone());
+            }
+            password = pwgen::generate(&generator, policy, &mut rng);
+        }
+    }
+
+    /// Picks one of `profiles`, or makes a one-off configuration, which has no name
+    fn pick_profile(
+        &self,
+        profiles: &[GeneratorProfileRecord],
+        current: &str,
+    ) -> Option<GeneratorProfileRecord> {
+        let custom = t!("vault.pwgen.custom", locales::LANG);
+        let mut list: Vec<&str> = profiles.iter().map(|profile| profile.name.as_str()).collect();
+        list.push(custom);
+        self.modals.add_list(list).expect("couldn't build profile list");
+        let current = if current.is_empty() { custom } else { current };
+        let selection = match self.modals.get_radiobutton(&format!(
+            "{} {}",
+            t!("vault.pwgen.profile_current", locales::LANG),
+            current
+        )) {
+            Ok(selection) => selection,
+            _ => {
+                log::error!("Modal selection error");
+                return None;
+            }
+        };
+        #[cfg(feature = "ux-swap-delay")]
+        self.tt.sleep_ms(SWAP_DELAY_MS).unwrap();
+        if selection == custom {
+            self.profile_dialog(String::new())
+        } else {
+            profiles.iter().find(|profile| profile.name == selection).cloned()
+        }
+    }
+
+    /// Asks for the settings of a generator profile called `name`
+    fn profile_dialog(&self, name: String) -> Option<GeneratorProfileRecord> {
+        let characters = t!("vault.pwgen.characters", locales::LANG);
+        let passphrase = t!("vault.pwgen.passphrase", locales::LANG);
+        self.modals.add_list(vec![characters, passphrase]).expect("couldn't build profile type list");
+        let kind = match self.modals.get_radiobutton(t!("vault.pwgen.kind", locales::LANG)) {
+            Ok(kind) => kind,
+            _ => {
+                log::error!("Modal selection error");
+                return None;
+            }
+        };
+        #[cfg(feature = "ux-swap-delay")]
+        self.tt.sleep_ms(SWAP_DELAY_MS).unwrap();
+        let mut profile = GeneratorProfileRecord {
+            version: storage::VAULT_PWGEN_PROFILE_REC_VERSION,
+            name,
+            passphrase: kind == passphrase,
+            ..Default::default()
+        };
+
+        if profile.passphrase {
+            profile.length = match self
+                .modals
+                .alert_builder(t!("vault.pwgen.words", locales::LANG))
+                .field(Some("6".to_string()), Some(words_validator))
+                .build()
+            {
+                Ok(entry) => entry.content()[0].content.as_str().unwrap().parse::<u32>().unwrap(),
+                _ => {
+                    log::error!("Length entry failed");
+                    return None;
+                }
+            };
+            #[cfg(feature = "ux-swap-delay")]
+            self.tt.sleep_ms(SWAP_DELAY_MS).unwrap();
+            profile.separator = match self
+                .modals
+                .alert_builder(t!("vault.pwgen.separator", locales::LANG))
+                .field_placeholder_persist(Some("-".to_string()), Some(password_validator))
+                .build()
+            {
+                Ok(entry) => entry.content()[0].content.as_str().unwrap().to_string(),
+                _ => {
+                    log::error!("Separator entry failed");
+                    return None;
+                }
+            };
+            #[cfg(feature = "ux-swap-delay")]
+            self.tt.sleep_ms(SWAP_DELAY_MS).unwrap();
+            self.modals
+                .add_list(vec![
+                    t!("vault.pwgen.capitalize", locales::LANG),
+                    t!("vault.pwgen.add_digit", locales::LANG),
+                ])
+                .expect("couldn't create configuration modal");
+            match self.modals.get_checkbox(t!("vault.newitem.configure_generator", locales::LANG)) {
+                Ok(options) => {
+                    profile.uppercase =
+                        options.iter().any(|opt| opt == t!("vault.pwgen.capitalize", locales::LANG));
+                    profile.numbers =
+                        options.iter().any(|opt| opt == t!("vault.pwgen.add_digit", locales::LANG));
+                }
+                _ => {
+                    log::error!("Modal selection error");
+                    return None;
+                }
+            }
+            return Some(profile);
+        }
+
+        profile.length = match self
+            .modals
+            .alert_builder(t!("vault.newitem.configure_length", locales::LANG))
+            .field(Some("20".to_string()), Some(length_validator))
+            .build()
+        {
+            Ok(entry) => entry.content()[0].content.as_str().unwrap().parse::<u32>().unwrap(),
+            _ => {
+                log::error!("Length entry failed");
+                return None;
+            }
+        };
+        #[cfg(feature = "ux-swap-delay")]
+        self.tt.sleep_ms(SWAP_DELAY_MS).unwrap();
+        while !(profile.lowercase || profile.uppercase || profile.numbers || profile.symbols) {
+            self.modals
+                .add_list(vec![
+                    t!("vault.newitem.lowercase", locales::LANG),
+                    t!("vault.newitem.uppercase", locales::LANG),
+                    t!("vault.newitem.numbers", locales::LANG),
+                    t!("vault.newitem.symbols", locales::LANG),
+                    t!("vault.pwgen.exclude_ambiguous", locales::LANG),
+                ])
+                .expect("couldn't create configuration modal");
+            match self.modals.get_checkbox(t!("vault.newitem.configure_generator", locales::LANG)) {
+                Ok(options) => {
+                    let selected = |label: &str| options.iter().any(|opt| opt == label);
+                    profile.lowercase = selected(t!("vault.newitem.lowercase", locales::LANG));
+                    profile.uppercase = selected(t!("vault.newitem.uppercase", locales::LANG));
+                    profile.numbers = selected(t!("vault.newitem.numbers", locales::LANG));
+                    profile.symbols = selected(t!("vault.newitem.symbols", locales::LANG));
+                    profile.exclude_ambiguous = selected(t!("vault.pwgen.exclude_ambiguous", locales::LANG));
+                }
+                _ => {
+                    log::error!("Modal selection error");
+                    return None;
+                }
+            }
+            if !(profile.lowercase || profile.uppercase || profile.numbers || profile.symbols) {
+                self.modals.show_notification(t!("vault.error.nothing_selected", locales::LANG), None).ok();
+            }
+        }
+        Some(profile)
+    }
+
     #[cfg(feature = "vaultperf")]
     #[inline]
     /// create performance logger entries
@@ -1154,6 +1370,7 @@ impl<'a> ActionManager<'a> {
                                             prev_entry.guid.clear();
                                             prev_entry.guid.push_str(&key.name);
                                         }
+                                        prev_entry.set_details(searchable_notes(&pw_rec.notes), &pw_rec.tags);
                                         #[cfg(feature = "vaultperf")]
                                         self.perfentry(&self.pm, PERFMETA_ENDBLOCK, 3, std::line!());
                                     } else {
@@ -1167,7 +1384,7 @@ impl<'a> ActionManager<'a> {
                                         extra.push_str(t!("vault.u2f.appinfo.authcount", locales::LANG));
                                         extra.push_str(&pw_rec.count.to_string());
 
-                                        let li = ListItem::new(
+                                        let mut li = ListItem::new(
                                             desc.to_string(), /* these allocs will be slow, but we do it
                                                                * only once on boot */
                                             extra.to_string(),
@@ -1176,6 +1393,7 @@ impl<'a> ActionManager<'a> {
                                             pw_rec.atime,
                                             pw_rec.count,
                                         );
+                                        li.set_details(searchable_notes(&pw_rec.notes), &pw_rec.tags);
                                         il.push(self.mode_cache, li);
                                         #[cfg(feature = "vaultperf")]
                                         self.perfentry(&self.pm, PERFMETA_ENDBLOCK, 4, std::line!());
@@ -1545,6 +1763,429 @@ impl<'a> ActionManager<'a> {
         }
     }
 
+    pub(crate) fn menu_import(&mut self) {
+        // without mass storage support, serial is the only way in and there's nothing to choose
+        #[cfg(not(feature = "mass-storage"))]
+        let received = import::receive_serial(&self.modals);
+        #[cfg(feature = "mass-storage")]
+        let received = {
+            let serial = t!("vault.import.source_serial", locales::LANG);
+            let disk = t!("vault.import.source_disk", locales::LANG);
+            self.modals.add_list(vec![serial, disk]).expect("couldn't build import source dialog");
+            let source = match self.modals.get_radiobutton(t!("vault.import.source", locales::LANG)) {
+                Ok(source) => source,
+                _ => {
+                    log::error!("Modal selection error");
+                    return;
+                }
+            };
+            #[cfg(feature = "ux-swap-delay")]
+            self.tt.sleep_ms(SWAP_DELAY_MS).unwrap();
+            if source == serial {
+                import::receive_serial(&self.modals)
+            } else {
+                import::receive_disk(&self.modals)
+            }
+        };
+        let data = match received {
+            Ok(data) => data,
+            Err(e) => {
+                self.report_err(t!("vault.error.usb_error", locales::LANG), Some(e));
+                return;
+            }
+        };
+        let batch = match import::parse(&data) {
+            Ok(batch) => batch,
+            Err(e) => {
+                match e {
+                    import::ImportError::Encrypted => {
+                        self.report_err(t!("vault.import.encrypted", locales::LANG), None::<&str>)
+                    }
+                    import::ImportError::UnknownFormat => {
+                        self.report_err(t!("vault.import.unknown_format", locales::LANG), None::<&str>)
+                    }
+                    import::ImportError::Malformed(format) => {
+                        self.report_err(t!("vault.import.malformed", locales::LANG), Some(format))
+                    }
+                    import::ImportError::Empty => {
+                        self.report_err(t!("vault.import.empty", locales::LANG), None::<&str>)
+                    }
+                }
+                return;
+            }
+        };
+
+        // dry run: nothing is written until the user has seen what the export holds
+        let existing =
+            batch.records.iter().filter(|record| self.storage.borrow().exists(record.as_ref())).count();
+        // passwords with line breaks can't be stored as they are, so they're named rather than changed
+        let multiline = if batch.multiline.is_empty() {
+            String::new()
+        } else {
+            format!("\n{}{}", t!("vault.import.multiline", locales::LANG), batch.multiline.join(", "))
+        };
+        let summary = format!(
+            "{}\n{}{}\n{}{}\n{}{}\n{}{}{}\n\n{}",
+            t!("vault.import.summary", locales::LANG),
+            t!("vault.import.passwords", locales::LANG),
+            batch.passwords,
+            t!("vault.import.totp", locales::LANG),
+            batch.totp,
+            t!("vault.import.existing", locales::LANG),
+            existing,
+            t!("vault.import.skipped", locales::LANG),
+            batch.skipped,
+            multiline,
+            t!("vault.import.confirm", locales::LANG),
+        );
+        #[cfg(feature = "ux-swap-delay")]
+        self.tt.sleep_ms(SWAP_DELAY_MS).unwrap();
+        if !self.yes_no_approval(&summary) {
+            return;
+        }
+
+        self.modals.dynamic_notification(Some(t!("vault.import.working", locales::LANG)), None).ok();
+        // records that are already in the vault are left as they are
+        let result = self.storage.borrow_mut().new_records(batch.records, None, false);
+        self.modals.dynamic_notification_close().ok();
+        match result {
+            Ok(()) => {}
+            Err(storage::Error::DupesExist(dupes)) => {
+                log::info!("{} imported records already existed", dupes.len())
+            }
+            Err(e) => self.report_err(t!("vault.error.internal_error", locales::LANG), Some(e)),
+        }
+    }
+
+    pub(crate) fn menu_ssh_agent(&mut self) {
+        let start = t!("vault.ssh.start", locales::LANG);
+        let new_key = t!("vault.ssh.new_key", locales::LANG);
+        let show_key = t!("vault.ssh.show_key", locales::LANG);
+        let delete_key = t!("vault.ssh.delete_key", locales::LANG);
+        self.modals
+            .add_list(vec![start, new_key, show_key, delete_key])
+            .expect("couldn't build SSH agent menu");
+        let choice = match self.modals.get_radiobutton(t!("vault.ssh.menu", locales::LANG)) {
+            Ok(choice) => choice,
+            _ => {
+                log::error!("Modal selection error");
+                return;
+            }
+        };
+        #[cfg(feature = "ux-swap-delay")]
+        self.tt.sleep_ms(SWAP_DELAY_MS).unwrap();
+
+        if choice == new_key {
+            self.new_ssh_key();
+            return;
+        }
+        let records: Vec<SshKeyRecord> = match self.storage.borrow().all(storage::ContentKind::SshKey) {
+            Ok(records) => records,
+            // the dictionary only exists once the first key has been made
+            Err(storage::Error::IoError(e)) if e.kind() == ErrorKind::NotFound => vec![],
+            Err(e) => {
+                self.report_err(t!("vault.error.internal_error", locales::LANG), Some(e));
+                return;
+            }
+        };
+        if records.is_empty() {
+            self.modals.show_notification(t!("vault.ssh.no_keys", locales::LANG), None).ok();
+            return;
+        }
+
+        if choice == start {
+            let keys: Vec<SshKey> = records
+                .iter()
+                .filter_map(|record| match SshKey::try_from(record) {
+                    Ok(key) => Some(key),
+                    Err(e) => {
+                        log::warn!("SSH key {} can't be used: {:?}", record.comment, e);
+                        None
+                    }
+                })
+                .collect();
+            if let Err(e) = ssh_agent::serve(&self.modals, &keys, |query| self.yes_no_approval(query)) {
+                self.report_err(t!("vault.error.usb_error", locales::LANG), Some(e));
+            }
+            return;
+        }
+
+        let comments: Vec<&str> = records.iter().map(|record| record.comment.as_str()).collect();
+        self.modals.add_list(comments).expect("couldn't build SSH key list");
+        let comment = match self.modals.get_radiobutton(t!("vault.ssh.pick_key", locales::LANG)) {
+            Ok(comment) => comment,
+            _ => {
+                log::error!("Modal selection error");
+                return;
+            }
+        };
+        let record = records.iter().find(|record| record.comment == comment).unwrap();
+        #[cfg(feature = "ux-swap-delay")]
+        self.tt.sleep_ms(SWAP_DELAY_MS).unwrap();
+        if choice == show_key {
+            match SshKey::try_from(record) {
+                Ok(key) => self.show_ssh_key(&key),
+                Err(e) => self.report_err(t!("vault.error.record_error", locales::LANG), Some(e)),
+            }
+        } else if choice == delete_key {
+            if self.yes_no_approval(&format!(
+                "{}\n{}",
+                t!("vault.ssh.delete_confirm", locales::LANG),
+                comment
+            )) {
+                if let Err(e) = self
+                    .storage
+                    .borrow_mut()
+                    .delete(storage::ContentKind::SshKey, &storage::hex(record.hash()))
+                {
+                    self.report_err(t!("vault.error.internal_error", locales::LANG), Some(e));
+                }
+            }
+        }
+    }
+
+    fn new_ssh_key(&mut self) {
+        let algorithms = ssh_agent::algorithms();
+        let names: Vec<String> = algorithms.iter().map(|a| a.to_string()).collect();
+        self.modals
+            .add_list(names.iter().map(|name| name.as_str()).collect())
+            .expect("couldn't build SSH key type list");
+        let algorithm = match self.modals.get_radiobutton(t!("vault.ssh.key_type", locales::LANG)) {
+            Ok(name) => algorithms.into_iter().find(|a| a.to_string() == name).unwrap(),
+            _ => {
+                log::error!("Modal selection error");
+                return;
+            }
+        };
+        #[cfg(feature = "ux-swap-delay")]
+        self.tt.sleep_ms(SWAP_DELAY_MS).unwrap();
+        let comment = match self
+            .modals
+            .alert_builder(t!("vault.ssh.comment", locales::LANG))
+            .field(None, Some(ssh_comment_validator))
+            .build()
+        {
+            Ok(text) => text.content()[0].content.as_str().unwrap_or("UTF-8 error").to_string(),
+            _ => {
+                log::error!("Name entry failed");
+                return;
+            }
+        };
+
+        let xns = xous_names::XousNames::new().unwrap();
+        let mut rng = ctap_crypto::rng256::XousRng256::new(&xns);
+        let key = SshKey::generate(algorithm, &mut rng, &comment);
+        match self.storage.borrow_mut().new_record(&mut key.to_record(), None, false) {
+            Ok(()) => {}
+            Err(storage::Error::KeyExists) => {
+                self.modals.show_notification(t!("vault.ssh.key_exists", locales::LANG), None).ok();
+                return;
+            }
+            Err(e) => {
+                self.report_err(t!("vault.error.internal_error", locales::LANG), Some(e));
+                return;
+            }
+        }
+        #[cfg(feature = "ux-swap-delay")]
+        self.tt.sleep_ms(SWAP_DELAY_MS).unwrap();
+        self.show_ssh_key(&key);
+    }
+
+    /// Shows the `authorized_keys` line of `key`, as text and as a QR code
+    fn show_ssh_key(&self, key: &SshKey) {
+        let authorized_key = key.authorized_key();
+        self.modals.show_notification(&authorized_key, Some(&authorized_key)).ok();
+    }
+
+    pub(crate) fn menu_openpgp(&mut self) {
+        let start = t!("vault.openpgp.start", locales::LANG);
+        let setup = t!("vault.openpgp.setup", locales::LANG);
+        self.modals.add_list(vec![start, setup]).expect("couldn't build OpenPGP card menu");
+        let choice = match self.modals.get_radiobutton(t!("vault.openpgp.menu", locales::LANG)) {
+            Ok(choice) => choice,
+            _ => {
+                log::error!("Modal selection error");
+                return;
+            }
+        };
+        #[cfg(feature = "ux-swap-delay")]
+        self.tt.sleep_ms(SWAP_DELAY_MS).unwrap();
+
+        let key = storage::hex(OpenPgpCardRecord::default().hash());
+        let record = match self
+            .storage
+            .borrow()
+            .get_record::<OpenPgpCardRecord>(&storage::ContentKind::OpenPgpCard, &key)
+        {
+            Ok(record) => Some(record),
+            // the dictionary only exists once the card has been set up
+            Err(storage::Error::IoError(e)) if e.kind() == ErrorKind::NotFound => None,
+            Err(e) => {
+                self.report_err(t!("vault.error.internal_error", locales::LANG), Some(e));
+                return;
+            }
+        };
+
+        if choice == setup {
+            if record.is_some() && !self.yes_no_approval(t!("vault.openpgp.wipe_confirm", locales::LANG)) {
+                return;
+            }
+            self.setup_openpgp(record.is_some());
+            return;
+        }
+        let record = match record {
+            Some(record) => record,
+            None => {
+                self.modals.show_notification(t!("vault.openpgp.no_card", locales::LANG), None).ok();
+                return;
+            }
+        };
+        let xns = xous_names::XousNames::new().unwrap();
+        let mut card = openpgp::Card::new(record, ctap_crypto::rng256::XousRng256::new(&xns));
+        if let Err(e) =
+            openpgp::serve(&self.modals, &mut self.storage.borrow_mut(), &mut card, &self.lefty_mode)
+        {
+            self.report_err(t!("vault.error.usb_error", locales::LANG), Some(e));
+        }
+    }
+
+    /// Makes a new card, without keys, replacing the existing one if `exists`
+    fn setup_openpgp(&mut self, exists: bool) {
+        let pw1 = match self
+            .modals
+            .alert_builder(t!("vault.openpgp.pw1", locales::LANG))
+            .field(None, Some(openpgp_pw1_validator))
+            .build()
+        {
+            Ok(text) => text.content()[0].content.as_str().unwrap_or("UTF-8 error").to_string(),
+            _ => {
+                log::error!("PIN entry failed");
+                return;
+            }
+        };
+        #[cfg(feature = "ux-swap-delay")]
+        self.tt.sleep_ms(SWAP_DELAY_MS).unwrap();
+        let pw3 = match self
+            .modals
+            .alert_builder(t!("vault.openpgp.pw3", locales::LANG))
+            .field(None, Some(openpgp_pw3_validator))
+            .build()
+        {
+            Ok(text) => text.content()[0].content.as_str().unwrap_or("UTF-8 error").to_string(),
+            _ => {
+                log::error!("PIN entry failed");
+                return;
+            }
+        };
+
+        let xns = xous_names::XousNames::new().unwrap();
+        let mut rng = ctap_crypto::rng256::XousRng256::new(&xns);
+        let mut record = openpgp::new_record(&mut rng, &pw1, &pw3);
+        let mut storage = self.storage.borrow_mut();
+        let ret = if exists {
+            storage.update(&storage::ContentKind::OpenPgpCard, &storage::hex(record.hash()), &mut record)
+        } else {
+            storage.new_record(&mut record, None, false)
+        };
+        drop(storage);
+        #[cfg(feature = "ux-swap-delay")]
+        self.tt.sleep_ms(SWAP_DELAY_MS).unwrap();
+        match ret {
+            Ok(()) => {
+                self.modals.show_notification(t!("vault.openpgp.setup_done", locales::LANG), None).ok();
+            }
+            Err(e) => self.report_err(t!("vault.error.internal_error", locales::LANG), Some(e)),
+        }
+    }
+
+    pub(crate) fn menu_generator_profiles(&mut self) {
+        let new_profile = t!("vault.pwgen.new_profile", locales::LANG);
+        let delete_profile = t!("vault.pwgen.delete_profile", locales::LANG);
+        self.modals.add_list(vec![new_profile, delete_profile]).expect("couldn't build profile menu");
+        let choice = match self.modals.get_radiobutton(t!("vault.pwgen.menu", locales::LANG)) {
+            Ok(choice) => choice,
+            _ => {
+                log::error!("Modal selection error");
+                return;
+            }
+        };
+        #[cfg(feature = "ux-swap-delay")]
+        self.tt.sleep_ms(SWAP_DELAY_MS).unwrap();
+
+        if choice == new_profile {
+            let name = match self
+                .modals
+                .alert_builder(t!("vault.pwgen.profile_name", locales::LANG))
+                .field(None, Some(profile_name_validator))
+                .build()
+            {
+                Ok(text) => text.content()[0].content.as_str().unwrap_or("UTF-8 error").trim().to_string(),
+                _ => {
+                    log::error!("Name entry failed");
+                    return;
+                }
+            };
+            #[cfg(feature = "ux-swap-delay")]
+            self.tt.sleep_ms(SWAP_DELAY_MS).unwrap();
+            let mut profile = match self.profile_dialog(name) {
+                Some(profile) => profile,
+                None => return,
+            };
+            match self.storage.borrow_mut().new_record(&mut profile, None, false) {
+                Ok(()) => {}
+                Err(storage::Error::KeyExists) => {
+                    self.modals.show_notification(t!("vault.pwgen.profile_exists", locales::LANG), None).ok();
+                    return;
+                }
+                Err(e) => {
+                    self.report_err(t!("vault.error.internal_error", locales::LANG), Some(e));
+                    return;
+                }
+            }
+            #[cfg(feature = "ux-swap-delay")]
+            self.tt.sleep_ms(SWAP_DELAY_MS).unwrap();
+            // an example, to check the profile makes what was intended
+            let xns = xous_names::XousNames::new().unwrap();
+            let sample = pwgen::generate(&profile, None, &mut ctap_crypto::rng256::XousRng256::new(&xns));
+            self.modals
+                .show_notification(&format!("{}\n{}", t!("vault.pwgen.sample", locales::LANG), sample), None)
+                .ok();
+            return;
+        }
+
+        let profiles: Vec<GeneratorProfileRecord> = pwgen::profiles(&self.storage.borrow())
+            .into_iter()
+            .filter(|profile| !pwgen::is_builtin(&profile.name))
+            .collect();
+        if profiles.is_empty() {
+            self.modals.show_notification(t!("vault.pwgen.no_profiles", locales::LANG), None).ok();
+            return;
+        }
+        self.modals
+            .add_list(profiles.iter().map(|profile| profile.name.as_str()).collect())
+            .expect("couldn't build profile list");
+        let name = match self.modals.get_radiobutton(t!("vault.pwgen.pick_profile", locales::LANG)) {
+            Ok(name) => name,
+            _ => {
+                log::error!("Modal selection error");
+                return;
+            }
+        };
+        #[cfg(feature = "ux-swap-delay")]
+        self.tt.sleep_ms(SWAP_DELAY_MS).unwrap();
+        let profile = profiles.iter().find(|profile| profile.name == name).unwrap();
+        // records that use it go back to the default profile
+        if self.yes_no_approval(&format!("{}\n{}", t!("vault.pwgen.delete_confirm", locales::LANG), name)) {
+            if let Err(e) = self
+                .storage
+                .borrow_mut()
+                .delete(storage::ContentKind::GeneratorProfile, &storage::hex(profile.hash()))
+            {
+                self.report_err(t!("vault.error.internal_error", locales::LANG), Some(e));
+            }
+        }
+    }
+
     #[cfg(feature = "vault-testing")]
     pub(crate) fn populate_tests(&mut self) {
         self.modals.dynamic_notification(Some("Creating test entries..."), None).ok();
@@ -1593,6 +2234,9 @@ impl<'a> ActionManager<'a> {
         let pws = self.pddb.borrow().list_keys(VAULT_PASSWORD_DICT, None).unwrap_or(Vec::new());
         if pws.len() < TARGET_ENTRIES_PW {
             let extra_count = TARGET_ENTRIES_PW - pws.len();
+            let xns = xous_names::XousNames::new().unwrap();
+            let mut rng = ctap_crypto::rng256::XousRng256::new(&xns);
+            let default_profile = pwgen::find_profile(&pwgen::builtin_profiles(), None).clone();
             for _index in 0..extra_count {
                 let desc = random_pick::pick_multiple_from_slice(&words, &weights, 3);
                 // this exposes raw unicode and symbols to the sorting list
@@ -1610,17 +2254,7 @@ impl<'a> ActionManager<'a> {
                 );
                 let username = random_pick::pick_from_slice(&words, &weights).unwrap().to_string();
                 let notes = random_pick::pick_from_slice(&words, &weights).unwrap().to_string();
-                let pg = PasswordGenerator {
-                    length: 20,
-                    numbers: true,
-                    lowercase_letters: true,
-                    uppercase_letters: true,
-                    symbols: true,
-                    spaces: false,
-                    exclude_similar_characters: true,
-                    strict: true,
-                };
-                let password = pg.generate_one().unwrap();
+                let password = pwgen::generate(&default_profile, None, &mut rng);
                 let mut record = storage::PasswordRecord {
                     version: VAULT_PASSWORD_REC_VERSION,
                     description,
@@ -1630,6 +2264,12 @@ impl<'a> ActionManager<'a> {
                     ctime: 0,
                     atime: 0,
                     count: 0,
+                    layout: None,
+                    tags: Vec::new(),
+                    history: Vec::new(),
+                    rotating: false,
+                    profile: None,
+                    policy: None,
                 };
 
                 match self.storage.borrow_mut().new_record(&mut record, None, true) {
@@ -1758,6 +2398,7 @@ impl<'a> ActionManager<'a> {
                     timestep: 30,
                     ctime: 0,
                     is_hotp: false,
+                    tags: Vec::new(),
                 };
 
                 match self.storage.borrow_mut().new_record(&mut record, None, true) {
@@ -1776,6 +2417,7 @@ impl<'a> ActionManager<'a> {
                 timestep: 30,
                 ctime: 0,
                 is_hotp: false,
+                tags: Vec::new(),
             };
 
             match self.storage.borrow_mut().new_record(&mut record, None, true) {
@@ -1798,6 +2440,18 @@ impl<'a> ActionManager<'a> {
     }
 }
 
+/// Adds the tags field at the end of an edit dialog: the record's tags to edit, or a hint of what goes there
+fn tags_field<'a>(
+    builder: &'a mut modals::AlertModalBuilder<'a>,
+    tags: &[String],
+) -> &'a mut modals::AlertModalBuilder<'a> {
+    if tags.is_empty() {
+        builder.field(Some(t!("vault.tags", locales::LANG).to_string()), Some(password_validator))
+    } else {
+        builder.field_placeholder_persist(Some(storage::tags_to_str(tags)), Some(password_validator))
+    }
+}
+
 pub(crate) fn totp_ss_validator(input: TextEntryPayload) -> Option<xous_ipc::String<256>> {
     let proposed_ss = input.as_str().to_uppercase();
     if let Some(ss) = base32::decode(base32::Alphabet::RFC4648 { padding: false }, &proposed_ss) {
@@ -1832,6 +2486,59 @@ pub(crate) fn name_validator(input: TextEntryPayload) -> Option<xous_ipc::String
         None
     }
 }
+fn ssh_comment_validator(input: TextEntryPayload) -> Option<xous_ipc::String<256>> {
+    let comment = input.as_str();
+    if comment.trim().is_empty() {
+        Some(xous_ipc::String::<256>::from_str(t!("vault.ssh.comment_empty", locales::LANG)))
+    } else if comment.contains(['\n']) {
+        Some(xous_ipc::String::<256>::from_str(t!("vault.illegal_char", locales::LANG)))
+    } else {
+        None
+    }
+}
+fn openpgp_pin_validator(input: &TextEntryPayload, min_len: usize) -> Option<xous_ipc::String<256>> {
+    let pin = input.as_str();
+    if pin.len() < min_len || pin.len() > openpgp::MAX_PIN_LEN {
+        Some(xous_ipc::String::<256>::from_str(&format!(
+            "{} {}-{}",
+            t!("vault.openpgp.pin_length", locales::LANG),
+            min_len,
+            openpgp::MAX_PIN_LEN
+        )))
+    } else {
+        None
+    }
+}
+fn openpgp_pw1_validator(input: TextEntryPayload) -> Option<xous_ipc::String<256>> {
+    openpgp_pin_validator(&input, openpgp::MIN_PW1_LEN)
+}
+fn openpgp_pw3_validator(input: TextEntryPayload) -> Option<xous_ipc::String<256>> {
+    openpgp_pin_validator(&input, openpgp::MIN_PW3_LEN)
+}
+fn profile_name_validator(input: TextEntryPayload) -> Option<xous_ipc::String<256>> {
+    let name = input.as_str().trim();
+    if name.is_empty() {
+        Some(xous_ipc::String::<256>::from_str(t!("vault.pwgen.name_empty", locales::LANG)))
+    } else if name.contains(['\n']) {
+        Some(xous_ipc::String::<256>::from_str(t!("vault.illegal_char", locales::LANG)))
+    } else if pwgen::is_builtin(name) || name == t!("vault.pwgen.custom", locales::LANG) {
+        Some(xous_ipc::String::<256>::from_str(t!("vault.pwgen.profile_exists", locales::LANG)))
+    } else {
+        None
+    }
+}
+fn policy_validator(input: TextEntryPayload) -> Option<xous_ipc::String<256>> {
+    match SitePolicy::try_from(input.as_str()) {
+        Ok(_) => None,
+        Err(_) => Some(xous_ipc::String::<256>::from_str(t!("vault.pwgen.policy_error", locales::LANG))),
+    }
+}
+fn words_validator(input: TextEntryPayload) -> Option<xous_ipc::String<256>> {
+    match input.as_str().parse::<u32>() {
+        Ok(words) if (pwgen::MIN_PASSPHRASE_WORDS..=pwgen::MAX_PASSPHRASE_WORDS).contains(&words) => None,
+        _ => Some(xous_ipc::String::<256>::from_str(t!("vault.illegal_number", locales::LANG))),
+    }
+}
 pub(crate) fn password_validator(input: TextEntryPayload) -> Option<xous_ipc::String<256>> {
     let proposed_name = input.as_str();
     if proposed_name.contains(['\n']) {
@@ -1861,6 +2568,32 @@ fn count_validator(input: TextEntryPayload) -> Option<xous_ipc::String<256>> {
         _ => Some(xous_ipc::String::<256>::from_str(t!("vault.illegal_count", locales::LANG))),
     }
 }
+fn period_validator(input: TextEntryPayload) -> Option<xous_ipc::String<256>> {
+    match input.as_str().parse::<u64>() {
+        Ok(period) if period > 0 => None,
+        _ => Some(xous_ipc::String::<256>::from_str(t!("vault.illegal_period", locales::LANG))),
+    }
+}
+fn digits_validator(input: TextEntryPayload) -> Option<xous_ipc::String<256>> {
+    match input.as_str().parse::<u32>() {
+        Ok(digits) if (totp::MIN_DIGITS..=totp::MAX_DIGITS).contains(&digits) => None,
+        _ => Some(xous_ipc::String::<256>::from_str(t!("vault.illegal_digits", locales::LANG))),
+    }
+}
+/// The edit dialog can switch a record to Steam Guard, so the 5 characters of its codes get past here;
+/// the edit is turned down afterwards if Steam wasn't chosen.
+fn edit_digits_validator(input: TextEntryPayload) -> Option<xous_ipc::String<256>> {
+    match input.as_str().parse::<u32>() {
+        Ok(digits) if digits == totp::STEAM_DIGITS => None,
+        _ => digits_validator(input),
+    }
+}
+fn algorithm_validator(input: TextEntryPayload) -> Option<xous_ipc::String<256>> {
+    match TotpAlgorithm::try_from(input.as_str().to_uppercase().as_str()) {
+        Ok(_) => None,
+        _ => Some(xous_ipc::String::<256>::from_str(t!("vault.illegal_algorithm", locales::LANG))),
+    }
+}
 
 #[cfg(feature = "vaultperf")]
 fn build_perf_mgr<'a>(bufptr: *mut u8) -> PerfMgr<'a> {
@@ -1917,7 +2650,7 @@ fn make_u2f_item_from_record(guid: &str, ai: AppInfo) -> ListItem {
         ai.count,
     );
     let desc: String = format!("{} (U2F)", ai.name);
-    ListItem::new(desc, extra, true, guid.to_owned(), ai.count, ai.atime)
+    ListItem::new(desc, extra, true, guid.to_owned(), ai.atime, ai.count)
 }
 fn make_fido_item_from_record(guid: &str, result: PublicKeyCredentialSource) -> ListItem {
     let name = if let Some(display_name) = result.user_display_name {
@@ -1925,10 +2658,17 @@ fn make_fido_item_from_record(guid: &str, result: PublicKeyCredentialSource) ->
     } else {
         String::from_utf8(result.user_handle).unwrap_or("".to_string())
     };
+    // ES256 is what nearly everything uses, so only the exception is called out
+    let kind = match result.private_key.signature_algorithm() {
+        #[cfg(feature = "ed25519")]
+        SignatureAlgorithm::Eddsa => "FIDO2, Ed25519",
+        _ => "FIDO2",
+    };
     let desc = format!(
-        "{} / {} (FIDO2)",
+        "{} / {} ({})",
         result.rp_id,
-        String::from_utf8(result.credential_id).unwrap_or("---".to_string())
+        String::from_utf8(result.credential_id).unwrap_or("---".to_string()),
+        kind
     );
     let extra = format!("{}", name);
     ListItem::new(desc, extra, true, guid.to_owned(), 0, 0)
@@ -1943,7 +2683,9 @@ fn make_totp_item_from_record(guid: &str, totp: TotpRecord) -> ListItem {
         if totp.is_hotp { "HOTP" } else { "TOTP" }
     );
     let desc = format!("{}", totp.name);
-    ListItem::new(desc, extra, true, guid.to_owned(), 0, 0)
+    let mut li = ListItem::new(desc, extra, true, guid.to_owned(), 0, 0);
+    li.set_details(searchable_notes(&totp.notes), &totp.tags);
+    li
 }
 fn make_pw_item_from_record(guid: &str, pw: PasswordRecord) -> ListItem {
     // create the list item from the updated entry
@@ -1955,12 +2697,18 @@ fn make_pw_item_from_record(guid: &str, pw: PasswordRecord) -> ListItem {
     extra.push_str("; ");
     extra.push_str(t!("vault.u2f.appinfo.authcount", locales::LANG));
     extra.push_str(&pw.count.to_string());
-    ListItem::new(
+    let mut li = ListItem::new(
         desc.to_string(), // these allocs will be slow, but we do it only once on boot
         extra.to_string(),
         true,
         guid.to_string(),
         pw.atime,
         pw.count,
-    )
+    );
+    li.set_details(searchable_notes(&pw.notes), &pw.tags);
+    li
+}
+/// New records start out with a placeholder for notes, which shouldn't turn up in searches
+fn searchable_notes(notes: &str) -> &str {
+    if notes == t!("vault.notes", locales::LANG) { "" } else { notes }
 }
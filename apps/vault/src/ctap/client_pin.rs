@@ -73,6 +73,13 @@ fn decrypt_pin(
     Ok(decrypted_pin.into_iter().take_while(|&c| c != 0).collect())
 }
 
+/// Hashes and truncates a PIN the way it is kept in persistent storage.
+pub fn hash_pin(pin: &[u8]) -> [u8; PIN_AUTH_LENGTH] {
+    let mut pin_hash = [0u8; PIN_AUTH_LENGTH];
+    pin_hash.copy_from_slice(&Sha256::hash(pin)[..PIN_AUTH_LENGTH]);
+    pin_hash
+}
+
 /// Stores a hash prefix of the new PIN in the persistent storage, if correct.
 ///
 /// The new PIN is passed encrypted, so it is first decrypted and stripped from
@@ -89,8 +96,7 @@ fn check_and_store_new_pin(
     if pin_length < min_pin_length || pin.len() == PIN_PADDED_LENGTH {
         return Err(Ctap2StatusCode::CTAP2_ERR_PIN_POLICY_VIOLATION);
     }
-    let mut pin_hash = [0u8; PIN_AUTH_LENGTH];
-    pin_hash.copy_from_slice(&Sha256::hash(&pin[..])[..PIN_AUTH_LENGTH]);
+    let pin_hash = hash_pin(&pin);
     // The PIN length is always < PIN_PADDED_LENGTH < 256.
     storage::set_pin(env, &pin_hash, pin_length as u8)?;
     Ok(())
@@ -810,12 +810,22 @@ impl From<CoseKey> for cbor::Value {
             curve,
         } = cose_key;
 
-        cbor_map! {
-            1 => key_type,
-            3 => algorithm,
-            -1 => curve,
-            -2 => x_bytes,
-            -3 => y_bytes,
+        match key_type {
+            // OKP keys (RFC 8152, section 13.2) are just the x coordinate, they have no y.
+            #[cfg(feature = "ed25519")]
+            CoseKey::OKP_KEY_TYPE => cbor_map! {
+                1 => key_type,
+                3 => algorithm,
+                -1 => curve,
+                -2 => x_bytes,
+            },
+            _ => cbor_map! {
+                1 => key_type,
+                3 => algorithm,
+                -1 => curve,
+                -2 => x_bytes,
+                -3 => y_bytes,
+            },
         }
     }
 }
@@ -1958,6 +1968,22 @@ mod test {
         assert_eq!(cose_key.algorithm, ES256_ALGORITHM);
     }
 
+    #[test]
+    #[cfg(feature = "ed25519")]
+    fn test_into_cose_key_ed25519() {
+        let seed = ed25519_compact::Seed::new([0x55; 32]);
+        let pk = ed25519_compact::KeyPair::from_seed(seed).pk;
+        let cose_key = CoseKey::from(pk);
+        assert_eq!(cose_key.algorithm, EDDSA_ALGORITHM);
+        let expected_cbor_value = cbor_map! {
+            1 => CoseKey::OKP_KEY_TYPE,
+            3 => EDDSA_ALGORITHM,
+            -1 => CoseKey::ED25519_CURVE,
+            -2 => *pk,
+        };
+        assert_eq!(cbor::Value::from(cose_key), expected_cbor_value);
+    }
+
     #[test]
     fn test_from_pin_uv_auth_protocol() {
         let cbor_protocol: cbor::Value = cbor_int!(0x01);
@@ -40,6 +40,7 @@ mod token_state;
 pub mod vendor_hid;
 
 use self::client_pin::{ClientPin, PinPermission};
+pub use self::client_pin::{hash_pin, PIN_AUTH_LENGTH};
 use self::command::{
     AuthenticatorGetAssertionParameters, AuthenticatorMakeCredentialParameters,
     AuthenticatorVendorConfigureParameters, AuthenticatorVendorUpgradeParameters, Command,
@@ -129,8 +130,8 @@ pub const U2F_VERSION_STRING: &str = "U2F_V2";
 // TODO(#106) change to final string when ready
 pub const FIDO2_1_VERSION_STRING: &str = "FIDO_2_1_PRE";
 
-// We currently only support one algorithm for signatures: ES256.
-// This algorithm is requested in MakeCredential and advertized in GetInfo.
+// Credentials are signed with ES256, or with EdDSA over Ed25519 when built with the `ed25519`
+// feature. These algorithms are requested in MakeCredential and advertized in GetInfo.
 pub const ES256_CRED_PARAM: PublicKeyCredentialParameter = PublicKeyCredentialParameter {
     cred_type: PublicKeyCredentialType::PublicKey,
     alg: SignatureAlgorithm::Es256,
@@ -148,6 +149,8 @@ const SUPPORTED_CRED_PARAMS: &[PublicKeyCredentialParameter] = &[
     EDDSA_CRED_PARAM,
 ];
 
+/// Picks the first of the relying party's `pubKeyCredParams` that we support, since the list is in
+/// its order of preference.
 fn get_preferred_cred_param(
     params: &[PublicKeyCredentialParameter],
 ) -> Option<&PublicKeyCredentialParameter> {
@@ -764,7 +767,6 @@ impl CtapState {
 
         self.pin_uv_auth_precheck(env, &pin_uv_auth_param, pin_uv_auth_protocol, channel)?;
 
-        // When more algorithms are supported, iterate and pick the first match.
         let cred_param = get_preferred_cred_param(&pub_key_cred_params)
             .ok_or(Ctap2StatusCode::CTAP2_ERR_UNSUPPORTED_ALGORITHM)?;
         let algorithm = cred_param.alg;
@@ -1832,6 +1834,71 @@ mod test {
         );
     }
 
+    #[test]
+    #[cfg(feature = "ed25519")]
+    fn test_get_preferred_cred_param() {
+        let unknown_cred_param = PublicKeyCredentialParameter {
+            cred_type: PublicKeyCredentialType::PublicKey,
+            alg: SignatureAlgorithm::Unknown,
+        };
+        assert_eq!(
+            get_preferred_cred_param(&[EDDSA_CRED_PARAM, ES256_CRED_PARAM]),
+            Some(&EDDSA_CRED_PARAM)
+        );
+        assert_eq!(
+            get_preferred_cred_param(&[
+                unknown_cred_param.clone(),
+                ES256_CRED_PARAM,
+                EDDSA_CRED_PARAM
+            ]),
+            Some(&ES256_CRED_PARAM)
+        );
+        assert_eq!(get_preferred_cred_param(&[unknown_cred_param]), None);
+    }
+
+    #[test]
+    #[cfg(feature = "ed25519")]
+    fn test_resident_process_make_credential_eddsa() {
+        let mut env = TestEnv::new();
+        let mut ctap_state = CtapState::new(&mut env, Instant::new(0));
+
+        let mut make_credential_params = create_minimal_make_credential_parameters();
+        make_credential_params.pub_key_cred_params = vec![EDDSA_CRED_PARAM, ES256_CRED_PARAM];
+        let make_credential_response =
+            ctap_state.process_make_credential(&mut env, make_credential_params, DUMMY_CHANNEL);
+
+        check_make_response(
+            &make_credential_response,
+            0x41,
+            &storage::aaguid(&mut env).unwrap(),
+            0x20,
+            &[],
+        );
+        let auth_data = match make_credential_response.unwrap() {
+            ResponseData::AuthenticatorMakeCredential(make_credential_response) => {
+                make_credential_response.auth_data
+            }
+            _ => panic!("Invalid response type"),
+        };
+        let offset = 37 + storage::aaguid(&mut env).unwrap().len() + 2;
+        let credential_id = &auth_data[offset..offset + 0x20];
+        let credential = storage::find_credential(&mut env, "example.com", credential_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            credential.private_key.signature_algorithm(),
+            SignatureAlgorithm::Eddsa
+        );
+        // The attested public key is an OKP key, without a y coordinate.
+        let public_key = credential.private_key.get_pub_key(&mut env).unwrap();
+        let attested_public_key = cbor::read(&auth_data[offset + 0x20..]).unwrap();
+        assert_eq!(attested_public_key, cbor::Value::from(public_key));
+        match attested_public_key {
+            cbor::Value::Map(map) => assert_eq!(map.len(), 4),
+            _ => panic!("Invalid public key"),
+        }
+    }
+
     #[test]
     fn test_process_make_credential_credential_excluded() {
         let mut env = TestEnv::new();
@@ -2519,6 +2586,58 @@ mod test {
         check_assertion_response(get_assertion_response, vec![0x1D], signature_counter, None);
     }
 
+    #[test]
+    #[cfg(feature = "ed25519")]
+    fn test_resident_process_get_assertion_eddsa() {
+        let mut env = TestEnv::new();
+        let mut ctap_state = CtapState::new(&mut env, Instant::new(0));
+
+        let mut make_credential_params = create_minimal_make_credential_parameters();
+        make_credential_params.pub_key_cred_params = vec![EDDSA_CRED_PARAM];
+        assert!(ctap_state
+            .process_make_credential(&mut env, make_credential_params, DUMMY_CHANNEL)
+            .is_ok());
+
+        let get_assertion_params = AuthenticatorGetAssertionParameters {
+            rp_id: String::from("example.com"),
+            client_data_hash: vec![0xCD],
+            allow_list: None,
+            extensions: GetAssertionExtensions::default(),
+            options: GetAssertionOptions {
+                up: false,
+                uv: false,
+            },
+            pin_uv_auth_param: None,
+            pin_uv_auth_protocol: None,
+        };
+        let get_assertion_response = ctap_state.process_get_assertion(
+            &mut env,
+            get_assertion_params,
+            DUMMY_CHANNEL,
+            Instant::new(0),
+        );
+        let (credential_id, auth_data, signature) = match get_assertion_response.unwrap() {
+            ResponseData::AuthenticatorGetAssertion(response) => (
+                response.credential.unwrap().key_id,
+                response.auth_data,
+                response.signature,
+            ),
+            _ => panic!("Invalid response type"),
+        };
+
+        let credential = storage::find_credential(&mut env, "example.com", &credential_id)
+            .unwrap()
+            .unwrap();
+        let public_key = match credential.private_key {
+            PrivateKey::Ed25519(private_key) => private_key.public_key(),
+            _ => panic!("Expected an Ed25519 credential"),
+        };
+        let mut signed_data = auth_data;
+        signed_data.push(0xCD);
+        let signature = ed25519_compact::Signature::from_slice(&signature).unwrap();
+        assert!(public_key.verify(&signed_data, &signature).is_ok());
+    }
+
     fn get_assertion_hmac_secret_params(
         key_agreement_key: ctap_crypto::ecdh::SecKey,
         key_agreement_response: ResponseData,
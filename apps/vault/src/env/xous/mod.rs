@@ -54,6 +54,57 @@ impl XousHidConnection {
     pub fn u2f_send(&self, msg: RawFidoReport) -> Result<(), xous::Error> {
         self.endpoint.u2f_send(msg)
     }
+
+    fn send_keepalive_up_needed(
+        &mut self,
+        timeout: Duration,
+        cid: [u8; 4]
+    ) -> Result<(), UserPresenceError> {
+        let keepalive_msg = CtapHid::keepalive(cid, KeepaliveStatus::UpNeeded);
+        for mut pkt in keepalive_msg {
+            match self.send_and_maybe_recv(&mut pkt, timeout) {
+                Ok(SendOrRecvStatus::Timeout) => {
+                    log::debug!("Sending a KEEPALIVE packet timed out");
+                    // TODO: abort user presence test?
+                }
+                Err(_) => panic!("Error sending KEEPALIVE packet"),
+                Ok(SendOrRecvStatus::Sent) => {
+                    log::trace!("Sent KEEPALIVE packet");
+                }
+                Ok(SendOrRecvStatus::Received) => {
+                    // We only parse one packet, because we only care about CANCEL.
+                    let (received_cid, processed_packet) = CtapHid::process_single_packet(&pkt);
+                    if received_cid != cid {
+                        log::debug!(
+                            "Received a packet on channel ID {:?} while sending a KEEPALIVE packet",
+                            received_cid,
+                        );
+                        return Ok(());
+                    }
+                    match processed_packet {
+                        ProcessedPacket::InitPacket { cmd, .. } => {
+                            if cmd == CtapHidCommand::Cancel as u8 {
+                                // We ignore the payload, we can't answer with an error code anyway.
+                                log::debug!("User presence check cancelled");
+                                return Err(UserPresenceError::Canceled);
+                            } else {
+                                log::debug!(
+                                    "Discarded packet with command {} received while sending a KEEPALIVE packet",
+                                    cmd,
+                                );
+                            }
+                        }
+                        ProcessedPacket::ContinuationPacket { .. } => {
+                            log::debug!(
+                                "Discarded continuation packet received while sending a KEEPALIVE packet",
+                            );
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 impl HidConnection for XousHidConnection {
@@ -529,58 +580,109 @@ impl XousEnv {
     pub fn is_soc_compatible(&self) -> bool {
         self.main_connection.endpoint.is_soc_compatible()
     }
+}
 
-    fn send_keepalive_up_needed(
-        &mut self,
-        timeout: Duration,
-        cid: [u8; 4]
-    ) -> Result<(), UserPresenceError> {
-        let keepalive_msg = CtapHid::keepalive(cid, KeepaliveStatus::UpNeeded);
-        for mut pkt in keepalive_msg {
-            match self.main_connection.send_and_maybe_recv(&mut pkt, timeout) {
-                Ok(SendOrRecvStatus::Timeout) => {
-                    log::debug!("Sending a KEEPALIVE packet timed out");
-                    // TODO: abort user presence test?
-                }
-                Err(_) => panic!("Error sending KEEPALIVE packet"),
-                Ok(SendOrRecvStatus::Sent) => {
-                    log::trace!("Sent KEEPALIVE packet");
-                }
-                Ok(SendOrRecvStatus::Received) => {
-                    // We only parse one packet, because we only care about CANCEL.
-                    let (received_cid, processed_packet) = CtapHid::process_single_packet(&pkt);
-                    if received_cid != cid {
-                        log::debug!(
-                            "Received a packet on channel ID {:?} while sending a KEEPALIVE packet",
-                            received_cid,
-                        );
-                        return Ok(());
-                    }
-                    match processed_packet {
-                        ProcessedPacket::InitPacket { cmd, .. } => {
-                            if cmd == CtapHidCommand::Cancel as u8 {
-                                // We ignore the payload, we can't answer with an error code anyway.
-                                log::debug!("User presence check cancelled");
-                                return Err(UserPresenceError::Canceled);
-                            } else {
-                                log::debug!(
-                                    "Discarded packet with command {} received while sending a KEEPALIVE packet",
-                                    cmd,
-                                );
-                            }
-                        }
-                        ProcessedPacket::ContinuationPacket { .. } => {
-                            log::debug!(
-                                "Discarded continuation packet received while sending a KEEPALIVE packet",
-                            );
-                        }
-                    }
-                }
+/// Asks the user to approve `reason` by hitting any key but the decline key within `timeout`,
+/// with a countdown. The titles explain which key declines, F1 or F4 in lefty mode. `keepalive` is
+/// called while waiting, every `KEEPALIVE_DELAY`; an error from it aborts the request. This is the
+/// FIDO user presence check, shared with vault's other authenticators.
+pub fn wait_for_user_presence(
+    modals: &Modals,
+    lefty_mode: &AtomicBool,
+    title: &str,
+    title_lefty: &str,
+    reason: &str,
+    timeout: Duration,
+    mut keepalive: impl FnMut() -> Result<(), UserPresenceError>,
+) -> UserPresenceResult {
+    log::info!("{}VAULT.PERMISSION,{}", xous::BOOKEND_START, xous::BOOKEND_END);
+    let kbhit = Arc::new(AtomicU32::new(0));
+    let expiration = Instant::now().checked_add(timeout).expect("duration bug");
+    modals.dynamic_notification(
+        Some(
+            if lefty_mode.load(Ordering::SeqCst) {
+                title_lefty
+            } else {
+                title
+            }
+        ),
+        None,
+    ).unwrap();
+    // start the keyboard hit listener thread
+    let _ = std::thread::spawn({
+        let token = modals.token().clone();
+        let conn = modals.conn().clone();
+        let kbhit = kbhit.clone();
+        move || {
+            // note that if no key is hit, we get None back on dialog box close automatically
+            match modals::dynamic_notification_blocking_listener(token, conn) {
+                Ok(Some(c)) => {
+                    log::trace!("kbhit got {}", c);
+                    kbhit.store(c as u32, Ordering::SeqCst)
+                },
+                Ok(None) => {
+                    log::trace!("kbhit exited or had no characters");
+                    kbhit.store(0, Ordering::SeqCst)
+                },
+                Err(e) => log::error!("error waiting for keyboard hit from blocking listener: {:?}", e),
             }
         }
-        Ok(())
-    }
+    });
+
+    let mut last_remaining = u64::MAX;
+    loop {
+        let mut request_str = String::from(reason);
+        let remaining = expiration.duration_since(Instant::now()).as_secs();
+        if last_remaining != remaining {
+            log::info!("countdown: {}", remaining);
+            // only update the UX once per second
+            request_str.push_str(
+                &format!("\n\n⚠   {}{}   ⚠\n",
+                remaining,
+                t!("vault.fido.countdown", locales::LANG)
+            ));
+            modals.dynamic_notification_update(
+                Some(
+                    if lefty_mode.load(Ordering::SeqCst) {
+                        title_lefty
+                    } else {
+                        title
+                    }
+                ),
+                Some(&request_str),
+            ).unwrap();
+            last_remaining = remaining;
+        }
+
+        // handle exit cases
+        if remaining == 0 {
+            modals.dynamic_notification_close().ok();
+            return Err(UserPresenceError::Timeout)
+        }
+        let key_hit = kbhit.load(Ordering::SeqCst);
+        if key_hit != 0
+        && ( // approve
+            (!lefty_mode.load(Ordering::SeqCst) && (key_hit != 0x11)) // 0x11 is the F1 key
+            || (lefty_mode.load(Ordering::SeqCst) && (key_hit != 0x14)) // 0x14 is the F4 key
+        )
+        {
+            modals.dynamic_notification_close().ok();
+            return Ok(())
+        } else if // deny
+            (!lefty_mode.load(Ordering::SeqCst) && (key_hit == 0x11))
+            || (lefty_mode.load(Ordering::SeqCst) && (key_hit == 0x14))
+        {
+            modals.dynamic_notification_close().ok();
+            return Err(UserPresenceError::Declined)
+        }
 
+        // delay, and keepalive
+        if let Err(e) = keepalive() {
+            modals.dynamic_notification_close().ok();
+            return Err(e)
+        }
+        std::thread::sleep(KEEPALIVE_DELAY);
+    }
 }
 
 impl UserPresence for XousEnv {
@@ -589,93 +691,17 @@ impl UserPresence for XousEnv {
     }
     /// Implements FIDO behavior (CTAP2 protocol)
     fn wait_with_timeout(&mut self, timeout: Duration, reason: Option::<String>, cid: [u8; 4]) -> UserPresenceResult {
-        log::info!("{}VAULT.PERMISSION,{}", xous::BOOKEND_START, xous::BOOKEND_END);
         let reason = reason.unwrap_or(String::new());
-        let kbhit = Arc::new(AtomicU32::new(0));
-        let expiration = Instant::now().checked_add(timeout).expect("duration bug");
-        self.modals.dynamic_notification(
-            Some(
-                if self.lefty_mode.load(Ordering::SeqCst) {
-                    t!("vault.u2freq_lefty", locales::LANG)
-                } else {
-                    t!("vault.u2freq", locales::LANG)
-                }
-            ),
-            None,
-        ).unwrap();
-        // start the keyboard hit listener thread
-        let _ = std::thread::spawn({
-            let token = self.modals.token().clone();
-            let conn = self.modals.conn().clone();
-            let kbhit = kbhit.clone();
-            move || {
-                // note that if no key is hit, we get None back on dialog box close automatically
-                match modals::dynamic_notification_blocking_listener(token, conn) {
-                    Ok(Some(c)) => {
-                        log::trace!("kbhit got {}", c);
-                        kbhit.store(c as u32, Ordering::SeqCst)
-                    },
-                    Ok(None) => {
-                        log::trace!("kbhit exited or had no characters");
-                        kbhit.store(0, Ordering::SeqCst)
-                    },
-                    Err(e) => log::error!("error waiting for keyboard hit from blocking listener: {:?}", e),
-                }
-            }
-        });
-
-        let mut last_remaining = u64::MAX;
-        loop {
-            let mut request_str = String::from(&reason);
-            let remaining = expiration.duration_since(Instant::now()).as_secs();
-            if last_remaining != remaining {
-                log::info!("countdown: {}", remaining);
-                // only update the UX once per second
-                request_str.push_str(
-                    &format!("\n\n⚠   {}{}   ⚠\n",
-                    remaining,
-                    t!("vault.fido.countdown", locales::LANG)
-                ));
-                self.modals.dynamic_notification_update(
-                    Some(
-                        if self.lefty_mode.load(Ordering::SeqCst) {
-                            t!("vault.u2freq_lefty", locales::LANG)
-                        } else {
-                            t!("vault.u2freq", locales::LANG)
-                        }
-                    ),
-                    Some(&request_str),
-                ).unwrap();
-                last_remaining = remaining;
-            }
-
-            // handle exit cases
-            if remaining == 0 {
-                self.modals.dynamic_notification_close().ok();
-                return Err(UserPresenceError::Timeout)
-            }
-            let key_hit = kbhit.load(Ordering::SeqCst);
-            if key_hit != 0
-            && ( // approve
-                (!self.lefty_mode.load(Ordering::SeqCst) && (key_hit != 0x11)) // 0x11 is the F1 key
-                || (self.lefty_mode.load(Ordering::SeqCst) && (key_hit != 0x14)) // 0x14 is the F4 key
-            )
-            {
-                self.modals.dynamic_notification_close().ok();
-                return Ok(())
-            } else if // deny
-                (!self.lefty_mode.load(Ordering::SeqCst) && (key_hit == 0x11))
-                || (self.lefty_mode.load(Ordering::SeqCst) && (key_hit == 0x14))
-            {
-                self.modals.dynamic_notification_close().ok();
-                return Err(UserPresenceError::Declined)
-            }
-
-            // delay, and keepalive
-            self.send_keepalive_up_needed(KEEPALIVE_DELAY, cid)
-            .map_err(|e| e.into())?;
-            std::thread::sleep(KEEPALIVE_DELAY);
-        }
+        let connection = &mut self.main_connection;
+        wait_for_user_presence(
+            &self.modals,
+            &self.lefty_mode,
+            t!("vault.u2freq", locales::LANG),
+            t!("vault.u2freq_lefty", locales::LANG),
+            &reason,
+            timeout,
+            || connection.send_keepalive_up_needed(KEEPALIVE_DELAY, cid),
+        )
     }
 
     /// A ctap1-specific call to see if a request was recently made
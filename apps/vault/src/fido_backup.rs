@@ -0,0 +1,173 @@
+//! Sealing of resident FIDO2 credentials for host backups.
+//!
+//! Unlike TOTP and password backups, FIDO credentials carry private keys, so they never leave the
+//! device in the clear: each serialized credential is encrypted and authenticated under a key derived
+//! from a passphrase the user types on the device when entering FIDO backup mode.
+//!
+//! Sealed credentials are `IV || AES-256-CBC(PKCS#7 padded credential) || HMAC-SHA256`, with the
+//! encryption and MAC keys expanded by HKDF from a PBKDF2-HMAC-SHA256 master key.
+
+use core::convert::TryInto;
+
+use ctap_crypto::aes256::{DecryptionKey, EncryptionKey};
+use ctap_crypto::cbc::{cbc_decrypt, cbc_encrypt};
+use ctap_crypto::hkdf::hkdf_empty_salt_256;
+use ctap_crypto::hmac::{hmac_256, verify_hmac_256};
+use ctap_crypto::rng256::Rng256;
+use ctap_crypto::sha256::Sha256;
+
+/// PBKDF2 iteration count for new backups. Restores use whatever count the backup records. The key is
+/// derived once per backup or restore, so the wait on the device is a small price for slowing down
+/// guesses at the passphrase of a stolen backup.
+pub const PBKDF2_ITERATIONS: u64 = 200_000;
+/// Restores refuse backups claiming more work than this, so a tampered file can't hang the device.
+pub const MAX_PBKDF2_ITERATIONS: u64 = 1_000_000;
+const SALT_LEN: usize = 16;
+const BLOCK_LEN: usize = 16;
+const MAC_LEN: usize = 32;
+
+pub struct SealingKey {
+    enc: EncryptionKey,
+    mac: [u8; 32],
+}
+
+impl SealingKey {
+    pub fn derive(passphrase: &str, salt: &[u8], iterations: u64) -> Self {
+        let master = pbkdf2_sha256(passphrase.as_bytes(), salt, iterations);
+        let enc = hkdf_empty_salt_256::<Sha256>(&master, b"vault fido backup encryption");
+        let mac = hkdf_empty_salt_256::<Sha256>(&master, b"vault fido backup authentication");
+        SealingKey { enc: EncryptionKey::new(&enc), mac }
+    }
+
+    pub fn seal(&self, rng: &mut impl Rng256, plaintext: &[u8]) -> Vec<u8> {
+        let mut iv = [0u8; BLOCK_LEN];
+        iv.copy_from_slice(&rng.gen_uniform_u8x32()[..BLOCK_LEN]);
+        let pad = BLOCK_LEN - plaintext.len() % BLOCK_LEN;
+
+        let mut sealed = Vec::with_capacity(BLOCK_LEN + plaintext.len() + pad + MAC_LEN);
+        sealed.extend_from_slice(&iv);
+        sealed.extend_from_slice(plaintext);
+        sealed.resize(BLOCK_LEN + plaintext.len() + pad, pad as u8);
+        cbc_encrypt(&self.enc, iv, &mut sealed[BLOCK_LEN..]);
+        let tag = hmac_256::<Sha256>(&self.mac, &sealed);
+        sealed.extend_from_slice(&tag);
+        sealed
+    }
+
+    /// Returns `None` if the credential was sealed under a different passphrase, or tampered with.
+    pub fn open(&self, sealed: &[u8]) -> Option<Vec<u8>> {
+        if sealed.len() < 2 * BLOCK_LEN + MAC_LEN || (sealed.len() - MAC_LEN) % BLOCK_LEN != 0 {
+            return None;
+        }
+        let (body, tag) = sealed.split_at(sealed.len() - MAC_LEN);
+        if !verify_hmac_256::<Sha256>(&self.mac, body, tag.try_into().ok()?) {
+            return None;
+        }
+        let (iv, ciphertext) = body.split_at(BLOCK_LEN);
+        let mut plaintext = ciphertext.to_vec();
+        cbc_decrypt(&DecryptionKey::new(&self.enc), iv.try_into().ok()?, &mut plaintext);
+
+        let pad = *plaintext.last()? as usize;
+        if pad == 0
+            || pad > BLOCK_LEN
+            || plaintext[plaintext.len() - pad..].iter().any(|&b| b as usize != pad)
+        {
+            return None;
+        }
+        plaintext.truncate(plaintext.len() - pad);
+        Some(plaintext)
+    }
+}
+
+pub fn new_salt(rng: &mut impl Rng256) -> Vec<u8> { rng.gen_uniform_u8x32()[..SALT_LEN].to_vec() }
+
+/// PBKDF2-HMAC-SHA256 with a single 32-byte output block.
+fn pbkdf2_sha256(password: &[u8], salt: &[u8], iterations: u64) -> [u8; 32] {
+    let mut block = salt.to_vec();
+    block.extend_from_slice(&1u32.to_be_bytes());
+    let mut u = hmac_256::<Sha256>(password, &block);
+    let mut derived = u;
+    for _ in 1..iterations {
+        u = hmac_256::<Sha256>(password, &u);
+        for (d, x) in derived.iter_mut().zip(u.iter()) {
+            *d ^= x;
+        }
+    }
+    derived
+}
+
+#[cfg(test)]
+mod tests {
+    use ctap_crypto::rng256::ThreadRng256;
+
+    use super::*;
+
+    #[test]
+    fn test_pbkdf2_sha256() {
+        // the PBKDF2-HMAC-SHA256 counterparts of the RFC 6070 vectors
+        let vectors: [(&[u8], &[u8], u64, &str); 4] = [
+            (b"password", b"salt", 1, "120fb6cffcf8b32c43e7225256c4f837a86548c92ccc35480805987cb70be17b"),
+            (b"password", b"salt", 2, "ae4d0c95af6b46d32d0adff928f06dd02a303f8ef3c251dfd6e2d85a95474c43"),
+            (b"password", b"salt", 4096, "c5e478d59288c841aa530db6845c4c8d962893a001ce4e11a4963873aa98134a"),
+            (
+                b"passwordPASSWORDpassword",
+                b"saltSALTsaltSALTsaltSALTsaltSALTsalt",
+                4096,
+                "348c89dbcbd32b2f32d814b8116e84cf2b17347ebc1800181c4e2a1fb8dd53e1",
+            ),
+        ];
+        for (password, salt, iterations, expected) in vectors.iter() {
+            assert_eq!(hex::encode(pbkdf2_sha256(password, salt, *iterations)), *expected);
+        }
+    }
+
+    #[test]
+    fn test_seal_open() {
+        let mut rng = ThreadRng256 {};
+        let salt = new_salt(&mut rng);
+        let key = SealingKey::derive("correct horse battery staple", &salt, 16);
+        // around the padding boundary, where a whole block of padding is added
+        for len in [0, 1, 15, 16, 17, 100] {
+            let plaintext: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let sealed = key.seal(&mut rng, &plaintext);
+            assert_eq!((sealed.len() - MAC_LEN) % BLOCK_LEN, 0);
+            assert_eq!(key.open(&sealed), Some(plaintext));
+        }
+        // a fresh IV every time
+        assert_ne!(key.seal(&mut rng, b"credential"), key.seal(&mut rng, b"credential"));
+    }
+
+    #[test]
+    fn test_wrong_passphrase() {
+        let mut rng = ThreadRng256 {};
+        let salt = new_salt(&mut rng);
+        let sealed =
+            SealingKey::derive("correct horse battery staple", &salt, 16).seal(&mut rng, b"credential");
+        assert_eq!(SealingKey::derive("correct horse battery stable", &salt, 16).open(&sealed), None);
+        // same passphrase, but not the salt or iteration count the backup was made with
+        assert_eq!(
+            SealingKey::derive("correct horse battery staple", &[0; SALT_LEN], 16).open(&sealed),
+            None
+        );
+        assert_eq!(SealingKey::derive("correct horse battery staple", &salt, 17).open(&sealed), None);
+    }
+
+    #[test]
+    fn test_tampering() {
+        let mut rng = ThreadRng256 {};
+        let key = SealingKey::derive("correct horse battery staple", &new_salt(&mut rng), 16);
+        let sealed = key.seal(&mut rng, b"a resident credential");
+        // any flipped bit: IV, ciphertext or MAC
+        for i in 0..sealed.len() {
+            let mut tampered = sealed.clone();
+            tampered[i] ^= 0x01;
+            assert_eq!(key.open(&tampered), None, "flipped byte {}", i);
+        }
+        // truncated, or with a block dropped
+        assert_eq!(key.open(&sealed[..sealed.len() - 1]), None);
+        let mut shortened = sealed[..BLOCK_LEN].to_vec();
+        shortened.extend_from_slice(&sealed[2 * BLOCK_LEN..]);
+        assert_eq!(key.open(&shortened), None);
+        assert_eq!(key.open(&[]), None);
+    }
+}
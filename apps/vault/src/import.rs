@@ -0,0 +1,275 @@
+//! Importing records exported by other password managers and authenticator apps.
+//!
+//! The supported formats are KeePass XML, Bitwarden JSON, CSV as written by most password managers and
+//! browsers, and lists of `otpauth://` and `otpauth-migration://` URIs. KDBX databases and encrypted
+//! Bitwarden exports are recognized, but have to be exported again in plain form first.
+//!
+//! The export is received over USB (see `transfer`), parsed into a `Batch` of records, and only written
+//! to the PDDB once the user has approved the summary of what was found.
+
+mod csv;
+#[cfg(feature = "mass-storage")]
+mod fat;
+mod json;
+mod keepass;
+mod otpauth;
+mod transfer;
+
+use locales::t;
+pub(crate) use transfer::*;
+
+use crate::storage::{PasswordRecord, StorageContent, TotpRecord, VAULT_PASSWORD_REC_VERSION};
+
+/// Signature at the start of every KDBX file, version 1 and later.
+const KDBX_SIGNATURE: [u8; 8] = [0x03, 0xd9, 0xa2, 0x9a, 0x67, 0xfb, 0x4b, 0xb5];
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum ImportError {
+    /// The export is encrypted, and has to be exported again without a password.
+    Encrypted,
+    /// The data isn't in any of the formats we know.
+    UnknownFormat,
+    /// The data is in a known format, but couldn't be read. The string names what went wrong.
+    Malformed(&'static str),
+    /// The export was read, but it held no passwords or one-time password secrets.
+    Empty,
+}
+
+/// An entry of a password manager export, before it is turned into records
+#[derive(Default, Debug)]
+pub(crate) struct Entry {
+    pub title: String,
+    pub username: String,
+    pub password: String,
+    pub url: String,
+    pub notes: String,
+    /// an `otpauth://` URI, or a bare base32 secret
+    pub otp: String,
+}
+
+/// The records read from one export
+#[derive(Default)]
+pub(crate) struct Batch {
+    pub records: Vec<Box<dyn StorageContent>>,
+    pub passwords: usize,
+    pub totp: usize,
+    /// entries that were read, but can't be stored in the vault
+    pub skipped: usize,
+    /// entries whose password was left out because it spans several lines, which a record can't hold
+    pub multiline: Vec<String>,
+}
+
+impl Batch {
+    pub(crate) fn add_password(&mut self, pw: PasswordRecord) {
+        self.passwords += 1;
+        self.records.push(Box::new(pw));
+    }
+
+    pub(crate) fn add_totp(&mut self, totp: TotpRecord) {
+        self.totp += 1;
+        self.records.push(Box::new(totp));
+    }
+
+    /// Adds the password of an exported entry, and its one-time password secret if it has one.
+    /// Entries with neither are counted as skipped. The password is stored as it was exported, and left
+    /// out if it has a line break in it.
+    pub(crate) fn add_entry(&mut self, entry: Entry) {
+        let description =
+            if !entry.title.trim().is_empty() { single_line(&entry.title) } else { host_of(&entry.url) };
+        let mut notes = single_line(&entry.notes);
+        if !entry.url.trim().is_empty() {
+            notes = if notes.is_empty() {
+                single_line(&entry.url)
+            } else {
+                format!("{} {}", single_line(&entry.url), notes)
+            };
+        }
+        let mut found = false;
+        if entry.password.contains(['\n', '\r']) {
+            log::warn!("the password of {} spans several lines, not imported", description);
+            self.multiline.push(description.clone());
+        } else if !entry.password.is_empty() && !description.is_empty() {
+            self.add_password(PasswordRecord {
+                version: VAULT_PASSWORD_REC_VERSION,
+                description: description.clone(),
+                username: single_line(&entry.username),
+                password: entry.password,
+                notes: if notes.is_empty() { t!("vault.notes", locales::LANG).to_string() } else { notes },
+                ctime: import_time(),
+                atime: 0,
+                count: 0,
+                layout: None,
+                tags: Vec::new(),
+                history: Vec::new(),
+                rotating: false,
+                profile: None,
+                policy: None,
+            });
+            found = true;
+        }
+        let otp = entry.otp.trim();
+        if !otp.is_empty() {
+            let name = if entry.username.trim().is_empty() {
+                description.clone()
+            } else {
+                format!("{}:{}", description, single_line(&entry.username))
+            };
+            match otpauth::record_for_entry(otp, &name) {
+                Some(totp) => {
+                    self.add_totp(totp);
+                    found = true;
+                }
+                None => log::warn!("couldn't read the one-time password of {}", description),
+            }
+        }
+        if !found {
+            self.skipped += 1;
+        }
+    }
+}
+
+/// Reads an export in any of the supported formats
+pub(crate) fn parse(data: &[u8]) -> Result<Batch, ImportError> {
+    if data.starts_with(&KDBX_SIGNATURE) {
+        return Err(ImportError::Encrypted);
+    }
+    let text = std::str::from_utf8(data).or(Err(ImportError::Malformed("UTF-8")))?;
+    // strip a byte order mark, and the end-of-transmission character that may close a serial transfer
+    let text =
+        text.trim_start_matches('\u{feff}').trim_end_matches(|c: char| c == '\u{4}' || c.is_whitespace());
+    let text = text.trim_start();
+
+    let batch = if text.starts_with("<?xml") || text.starts_with("<KeePassFile") {
+        keepass::parse(text)?
+    } else if text.starts_with('{') {
+        parse_bitwarden(text)?
+    } else if text.starts_with("otpauth") {
+        otpauth::parse(text)?
+    } else if !text.is_empty() {
+        csv::parse(text)?
+    } else {
+        return Err(ImportError::Empty);
+    };
+    if batch.records.is_empty() { Err(ImportError::Empty) } else { Ok(batch) }
+}
+
+/// Reads a Bitwarden JSON export
+fn parse_bitwarden(text: &str) -> Result<Batch, ImportError> {
+    let root = json::parse(text)?;
+    if root.get("encrypted").and_then(json::Value::as_bool).unwrap_or(false) {
+        return Err(ImportError::Encrypted);
+    }
+    let items = root.get("items").and_then(json::Value::as_array).ok_or(ImportError::UnknownFormat)?;
+    let mut batch = Batch::default();
+    for item in items {
+        // only logins (type 1) have anything we can store
+        let login = match item.get("login") {
+            Some(login) if item.get("type").and_then(json::Value::as_number) == Some(1) => login,
+            _ => {
+                batch.skipped += 1;
+                continue;
+            }
+        };
+        let text =
+            |value: Option<&json::Value>| value.and_then(json::Value::as_str).unwrap_or("").to_string();
+        batch.add_entry(Entry {
+            title: text(item.get("name")),
+            username: text(login.get("username")),
+            password: text(login.get("password")),
+            url: text(
+                login
+                    .get("uris")
+                    .and_then(json::Value::as_array)
+                    .and_then(|uris| uris.first())
+                    .and_then(|uri| uri.get("uri")),
+            ),
+            notes: text(item.get("notes")),
+            otp: text(login.get("totp")),
+        });
+    }
+    Ok(batch)
+}
+
+/// Imported records are created at the time of the import
+fn import_time() -> u64 { vault::utc_now().timestamp() as u64 }
+
+/// Record fields are stored one per line, so line breaks inside a field are turned into spaces.
+fn single_line(s: &str) -> String {
+    s.trim().split(['\n', '\r']).filter(|l| !l.is_empty()).collect::<Vec<&str>>().join(" ")
+}
+
+/// The host name of `url`, to name entries that were exported without a title
+fn host_of(url: &str) -> String {
+    let url = url.trim();
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let host = rest.split(['/', '?', '#']).next().unwrap_or("");
+    let host = host.rsplit_once('@').map_or(host, |(_, host)| host);
+    single_line(host)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_formats() {
+        assert_eq!(parse(&KDBX_SIGNATURE).err(), Some(ImportError::Encrypted));
+        assert_eq!(parse(b"{\"encrypted\": true, \"items\": []}").err(), Some(ImportError::Encrypted));
+        assert_eq!(parse(b"   \n").err(), Some(ImportError::Empty));
+        assert_eq!(parse(b"just some text\n").err(), Some(ImportError::UnknownFormat));
+        assert_eq!(parse(&[0xff, 0xfe, 0x00]).err(), Some(ImportError::Malformed("UTF-8")));
+    }
+
+    #[test]
+    fn bitwarden() {
+        let export = r#"{
+            "encrypted": false,
+            "folders": [],
+            "items": [
+                {
+                    "type": 1,
+                    "name": "Example",
+                    "notes": "first line\nsecond line",
+                    "login": {
+                        "uris": [{ "match": null, "uri": "https://example.com/login" }],
+                        "username": "alice",
+                        "password": "päss\"word",
+                        "totp": "otpauth://totp/Example:alice?secret=JBSWY3DPEHPK3PXP&issuer=Example"
+                    }
+                },
+                { "type": 2, "name": "A note", "notes": "secret", "secureNote": { "type": 0 } },
+                { "type": 1, "name": "", "login": { "uris": [{ "uri": "https://user@forum.example.org/" }],
+                  "username": "bob", "password": "hunter2", "totp": null } }
+            ]
+        }"#;
+        let batch = parse(export.as_bytes()).unwrap();
+        assert_eq!((batch.passwords, batch.totp, batch.skipped), (2, 1, 1));
+        let first = String::from_utf8(batch.records[0].to_vec()).unwrap();
+        assert!(first.contains("description:Example\n"));
+        assert!(first.contains("password:päss\"word\n"));
+        assert!(first.contains("notes:https://example.com/login first line second line\n"));
+        let totp = String::from_utf8(batch.records[1].to_vec()).unwrap();
+        assert!(totp.contains("name:Example:alice\n"));
+        assert!(totp.contains("secret:JBSWY3DPEHPK3PXP\n"));
+        let third = String::from_utf8(batch.records[2].to_vec()).unwrap();
+        assert!(third.contains("description:forum.example.org\n"));
+        assert!(!third.contains("ctime:0\n"));
+    }
+
+    #[test]
+    fn passwords_are_verbatim() {
+        let mut batch = Batch::default();
+        let entry = |title: &str, password: &str| Entry {
+            title: title.to_string(),
+            password: password.to_string(),
+            ..Default::default()
+        };
+        batch.add_entry(entry("spaces", "  two  spaces  "));
+        batch.add_entry(entry("lines", "first\nsecond"));
+        batch.add_entry(entry("crlf", "first\r\n"));
+        assert_eq!((batch.passwords, batch.skipped), (1, 2));
+        assert_eq!(batch.multiline, vec!["lines", "crlf"]);
+        let record = String::from_utf8(batch.records[0].to_vec()).unwrap();
+        assert!(record.contains("password:  two  spaces  \n"));
+    }
+}
@@ -0,0 +1,135 @@
+//! CSV exports, as written by browsers and most password managers (Bitwarden, KeePass, KeePassXC, LastPass,
+//! 1Password, Chrome, Firefox...). They all start with a header row, but disagree on the column names,
+//! so columns are matched against the names any of them use.
+
+use super::{Batch, Entry, ImportError};
+
+const TITLE: &[&str] = &["title", "name", "description", "account"];
+const USERNAME: &[&str] =
+    &["username", "login_username", "user name", "login name", "user", "login", "email"];
+const PASSWORD: &[&str] = &["password", "login_password"];
+const URL: &[&str] = &["url", "login_uri", "uri", "website", "web site"];
+const NOTES: &[&str] = &["notes", "note", "extra", "comments"];
+const OTP: &[&str] = &["totp", "login_totp", "otp", "otpauth"];
+
+pub(crate) fn parse(text: &str) -> Result<Batch, ImportError> {
+    let mut rows = records(text)?.into_iter();
+    let header: Vec<String> =
+        rows.next().unwrap_or_default().iter().map(|h| h.trim().to_lowercase()).collect();
+    let column = |names: &[&str]| names.iter().find_map(|name| header.iter().position(|h| h == name));
+    let password = column(PASSWORD);
+    let otp = column(OTP);
+    if password.is_none() && otp.is_none() {
+        return Err(ImportError::UnknownFormat);
+    }
+    let (title, username, url, notes) = (column(TITLE), column(USERNAME), column(URL), column(NOTES));
+
+    let mut batch = Batch::default();
+    for row in rows {
+        if row.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+        let field = |index: Option<usize>| index.and_then(|i| row.get(i)).cloned().unwrap_or_default();
+        batch.add_entry(Entry {
+            title: field(title),
+            username: field(username),
+            password: field(password),
+            url: field(url),
+            notes: field(notes),
+            otp: field(otp),
+        });
+    }
+    Ok(batch)
+}
+
+/// Splits `text` into rows of fields, following RFC 4180: fields may be quoted, and quoted fields can
+/// hold commas, line breaks and doubled quotes.
+fn records(text: &str) -> Result<Vec<Vec<String>>, ImportError> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => quoted = false,
+                c => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.trim().is_empty() => {
+                field.clear();
+                quoted = true;
+            }
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => (),
+            '\n' | '\r' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(ImportError::Malformed("CSV"));
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields() {
+        assert_eq!(
+            records("a,\"b,\"\"c\"\"\",d\r\n\"multi\nline\",,\n").unwrap(),
+            vec![vec!["a", "b,\"c\"", "d"], vec!["multi\nline", "", ""]]
+        );
+        assert_eq!(records("a,\"b").err(), Some(ImportError::Malformed("CSV")));
+    }
+
+    #[test]
+    fn exports() {
+        // Chrome
+        let batch =
+            parse("name,url,username,password,note\nExample,https://example.com/,alice,pw1,\n").unwrap();
+        assert_eq!((batch.passwords, batch.totp, batch.skipped), (1, 0, 0));
+        let record = String::from_utf8(batch.records[0].to_vec()).unwrap();
+        assert!(
+            record
+                .contains("description:Example\nusername:alice\npassword:pw1\nnotes:https://example.com/\n")
+        );
+
+        // KeePassXC, with a one-time password and an entry that has nothing to store
+        let batch = parse(
+            "\"Group\",\"Title\",\"Username\",\"Password\",\"URL\",\"Notes\",\"TOTP\"\n\
+             \"Root\",\"Mail\",\"bob\",\"pw2\",\"\",\"\",\"otpauth://totp/Mail:bob?secret=JBSWY3DPEHPK3PXP\"\n\
+             \"Root\",\"Empty\",\"\",\"\",\"\",\"just a note\",\"\"\n",
+        )
+        .unwrap();
+        assert_eq!((batch.passwords, batch.totp, batch.skipped), (1, 1, 1));
+
+        // KeePass, "KeePass CSV (1.x)"
+        let batch = parse(
+            "\"Account\",\"Login Name\",\"Password\",\"Web Site\",\"Comments\"\n\
+             \"Forum\",\"carol\",\"pw3\",\"https://forum.example.org/\",\"\"\n",
+        )
+        .unwrap();
+        assert_eq!((batch.passwords, batch.totp, batch.skipped), (1, 0, 0));
+        let record = String::from_utf8(batch.records[0].to_vec()).unwrap();
+        assert!(record.contains("description:Forum\nusername:carol\npassword:pw3\n"));
+
+        assert_eq!(parse("a,b,c\n1,2,3\n").err(), Some(ImportError::UnknownFormat));
+    }
+}
@@ -0,0 +1,167 @@
+//! The RAM disk that exports are copied onto when importing over USB mass storage.
+//!
+//! The disk is handed to the host already formatted as FAT12, so it mounts without questions. Once the
+//! host is done, the largest file in the root directory is taken to be the export; hosts like to leave
+//! small hidden files of their own behind. FAT16 is read as well, in case the host formatted the disk
+//! again.
+
+pub(crate) const SECTOR_SIZE: usize = 512;
+/// 1 MiB, comfortably more than an export of a few thousand entries
+pub(crate) const DISK_SECTORS: usize = 2048;
+const RESERVED_SECTORS: usize = 1;
+const FATS: usize = 2;
+const FAT_SECTORS: usize = 6;
+const ROOT_ENTRIES: usize = 224;
+const DIR_ENTRY_SIZE: usize = 32;
+
+const ATTR_HIDDEN: u8 = 0x02;
+const ATTR_SYSTEM: u8 = 0x04;
+const ATTR_VOLUME: u8 = 0x08;
+const ATTR_DIRECTORY: u8 = 0x10;
+const DELETED: u8 = 0xe5;
+
+/// A blank disk image, labelled `VAULT`
+pub(crate) fn format() -> Vec<u8> {
+    let mut image = vec![0u8; DISK_SECTORS * SECTOR_SIZE];
+    let boot = &mut image[..SECTOR_SIZE];
+    boot[..3].copy_from_slice(&[0xeb, 0x3c, 0x90]);
+    boot[3..11].copy_from_slice(b"MSDOS5.0");
+    boot[11..13].copy_from_slice(&(SECTOR_SIZE as u16).to_le_bytes());
+    boot[13] = 1; // sectors per cluster
+    boot[14..16].copy_from_slice(&(RESERVED_SECTORS as u16).to_le_bytes());
+    boot[16] = FATS as u8;
+    boot[17..19].copy_from_slice(&(ROOT_ENTRIES as u16).to_le_bytes());
+    boot[19..21].copy_from_slice(&(DISK_SECTORS as u16).to_le_bytes());
+    boot[21] = 0xf8; // fixed disk
+    boot[22..24].copy_from_slice(&(FAT_SECTORS as u16).to_le_bytes());
+    boot[24..26].copy_from_slice(&32u16.to_le_bytes()); // sectors per track
+    boot[26..28].copy_from_slice(&2u16.to_le_bytes()); // heads
+    boot[36] = 0x80; // drive number
+    boot[38] = 0x29; // extended boot signature
+    boot[39..43].copy_from_slice(&0x5641_554cu32.to_le_bytes()); // volume serial number
+    boot[43..54].copy_from_slice(b"VAULT      ");
+    boot[54..62].copy_from_slice(b"FAT12   ");
+    boot[510..512].copy_from_slice(&[0x55, 0xaa]);
+    for fat in 0..FATS {
+        let start = (RESERVED_SECTORS + fat * FAT_SECTORS) * SECTOR_SIZE;
+        // the media descriptor, and the end of chain marker of the reserved cluster 1
+        image[start..start + 3].copy_from_slice(&[0xf8, 0xff, 0xff]);
+    }
+    let root = (RESERVED_SECTORS + FATS * FAT_SECTORS) * SECTOR_SIZE;
+    image[root..root + 11].copy_from_slice(b"VAULT      ");
+    image[root + 11] = ATTR_VOLUME;
+    image
+}
+
+/// The contents of the largest visible file in the root directory of `image`
+pub(crate) fn largest_file(image: &[u8]) -> Option<Vec<u8>> {
+    let le16 =
+        |offset: usize| image.get(offset..offset + 2).map(|b| u16::from_le_bytes([b[0], b[1]]) as usize);
+    let le32 = |offset: usize| {
+        image.get(offset..offset + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+    };
+    let sector_size = le16(11)?;
+    let cluster_sectors = *image.get(13)? as usize;
+    let reserved = le16(14)?;
+    let fats = *image.get(16)? as usize;
+    let root_entries = le16(17)?;
+    let total = match le16(19)? {
+        0 => le32(32)?,
+        total => total,
+    };
+    let fat_sectors = le16(22)?;
+    if sector_size == 0 || cluster_sectors == 0 || fat_sectors == 0 {
+        return None;
+    }
+
+    let fat_start = reserved * sector_size;
+    let root_start = (reserved + fats * fat_sectors) * sector_size;
+    let root_sectors = (root_entries * DIR_ENTRY_SIZE).div_ceil(sector_size);
+    let data_start = root_start + root_sectors * sector_size;
+    let cluster_size = cluster_sectors * sector_size;
+    let clusters = total.checked_sub(data_start / sector_size)? / cluster_sectors;
+    let fat12 = match clusters {
+        0..=4084 => true,
+        4085..=65524 => false,
+        // FAT32 keeps its root directory in a cluster chain, and a 1 MiB disk is never formatted that way
+        _ => return None,
+    };
+
+    let (cluster, size) = (0..root_entries)
+        .filter_map(|i| image.get(root_start + i * DIR_ENTRY_SIZE..root_start + (i + 1) * DIR_ENTRY_SIZE))
+        .take_while(|entry| entry[0] != 0)
+        .filter(|entry| {
+            entry[0] != DELETED
+                && entry[0] != b'.'
+                && entry[0] != b'_'
+                && entry[11] & (ATTR_HIDDEN | ATTR_SYSTEM | ATTR_VOLUME | ATTR_DIRECTORY) == 0
+        })
+        .map(|entry| {
+            let cluster = u16::from_le_bytes([entry[26], entry[27]]) as usize;
+            let size = u32::from_le_bytes([entry[28], entry[29], entry[30], entry[31]]) as usize;
+            (cluster, size)
+        })
+        .max_by_key(|&(_, size)| size)?;
+
+    let mut contents = Vec::with_capacity(size);
+    let mut cluster = cluster;
+    while contents.len() < size {
+        if cluster < 2 || cluster >= clusters + 2 {
+            return None;
+        }
+        let start = data_start + (cluster - 2) * cluster_size;
+        let len = cluster_size.min(size - contents.len());
+        contents.extend_from_slice(image.get(start..start + len)?);
+        cluster = if fat12 {
+            let entry = le16(fat_start + cluster + cluster / 2)?;
+            if cluster & 1 == 1 { entry >> 4 } else { entry & 0xfff }
+        } else {
+            le16(fat_start + cluster * 2)?
+        };
+    }
+    Some(contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set_fat12(image: &mut [u8], cluster: usize, value: u16) {
+        for fat in 0..FATS {
+            let offset = (RESERVED_SECTORS + fat * FAT_SECTORS) * SECTOR_SIZE + cluster + cluster / 2;
+            let mut entry = u16::from_le_bytes([image[offset], image[offset + 1]]);
+            entry = if cluster & 1 == 1 { (entry & 0x000f) | (value << 4) } else { (entry & 0xf000) | value };
+            image[offset..offset + 2].copy_from_slice(&entry.to_le_bytes());
+        }
+    }
+
+    fn add_file(image: &mut [u8], slot: usize, name: &[u8; 11], attr: u8, first: usize, contents: &[u8]) {
+        let root = (RESERVED_SECTORS + FATS * FAT_SECTORS) * SECTOR_SIZE;
+        let entry = &mut image[root + slot * DIR_ENTRY_SIZE..root + (slot + 1) * DIR_ENTRY_SIZE];
+        entry[..11].copy_from_slice(name);
+        entry[11] = attr;
+        entry[26..28].copy_from_slice(&(first as u16).to_le_bytes());
+        entry[28..32].copy_from_slice(&(contents.len() as u32).to_le_bytes());
+        let data = root + ROOT_ENTRIES * DIR_ENTRY_SIZE;
+        let clusters = contents.len().div_ceil(SECTOR_SIZE);
+        for (i, chunk) in contents.chunks(SECTOR_SIZE).enumerate() {
+            let start = data + (first + i - 2) * SECTOR_SIZE;
+            image[start..start + chunk.len()].copy_from_slice(chunk);
+            set_fat12(image, first + i, if i + 1 == clusters { 0xfff } else { (first + i + 1) as u16 });
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut image = format();
+        assert_eq!(largest_file(&image), None);
+
+        let export: Vec<u8> = (0..1300).map(|i| (i % 251) as u8).collect();
+        add_file(&mut image, 1, b"_EXPORT CSV", 0, 2, &[1; 4096]);
+        add_file(&mut image, 2, b"EXPORT  CSV", 0, 10, &export);
+        add_file(&mut image, 3, b"TRASHES    ", ATTR_HIDDEN | ATTR_DIRECTORY, 20, &[]);
+        add_file(&mut image, 4, b"NOTES   TXT", 0, 21, b"small");
+        image[(RESERVED_SECTORS + FATS * FAT_SECTORS) * SECTOR_SIZE + 4 * DIR_ENTRY_SIZE] = DELETED;
+        assert_eq!(largest_file(&image), Some(export));
+    }
+}
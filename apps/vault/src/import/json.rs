@@ -0,0 +1,223 @@
+//! Just enough of a JSON reader for Bitwarden exports. Numbers are kept as written, since the only
+//! ones we look at are small item types.
+
+use super::ImportError;
+
+const MALFORMED: ImportError = ImportError::Malformed("JSON");
+/// Exports are shallow; this keeps a hostile file from exhausting the stack
+const MAX_DEPTH: usize = 32;
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Value {
+    Null,
+    Bool(bool),
+    Number(String),
+    Str(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    pub(crate) fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_number(&self) -> Option<i64> {
+        match self {
+            Value::Number(n) => n.parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_array(&self) -> Option<&Vec<Value>> {
+        match self {
+            Value::Array(a) => Some(a),
+            _ => None,
+        }
+    }
+}
+
+pub(crate) fn parse(text: &str) -> Result<Value, ImportError> {
+    let mut parser = Parser { chars: text.chars().peekable() };
+    let value = parser.value(0)?;
+    parser.skip_whitespace();
+    if parser.chars.next().is_some() {
+        return Err(MALFORMED);
+    }
+    Ok(value)
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.peek().map_or(false, |c| c.is_whitespace()) {
+            self.chars.next();
+        }
+    }
+
+    fn expect(&mut self, word: &str) -> Result<(), ImportError> {
+        for expected in word.chars() {
+            if self.chars.next() != Some(expected) {
+                return Err(MALFORMED);
+            }
+        }
+        Ok(())
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value, ImportError> {
+        if depth > MAX_DEPTH {
+            return Err(MALFORMED);
+        }
+        self.skip_whitespace();
+        match self.chars.peek().ok_or(MALFORMED)? {
+            '{' => {
+                self.chars.next();
+                let mut members = Vec::new();
+                self.skip_whitespace();
+                if self.chars.peek() == Some(&'}') {
+                    self.chars.next();
+                    return Ok(Value::Object(members));
+                }
+                loop {
+                    self.skip_whitespace();
+                    self.expect("\"")?;
+                    let key = self.string()?;
+                    self.skip_whitespace();
+                    self.expect(":")?;
+                    members.push((key, self.value(depth + 1)?));
+                    self.skip_whitespace();
+                    match self.chars.next() {
+                        Some(',') => continue,
+                        Some('}') => return Ok(Value::Object(members)),
+                        _ => return Err(MALFORMED),
+                    }
+                }
+            }
+            '[' => {
+                self.chars.next();
+                let mut items = Vec::new();
+                self.skip_whitespace();
+                if self.chars.peek() == Some(&']') {
+                    self.chars.next();
+                    return Ok(Value::Array(items));
+                }
+                loop {
+                    items.push(self.value(depth + 1)?);
+                    self.skip_whitespace();
+                    match self.chars.next() {
+                        Some(',') => continue,
+                        Some(']') => return Ok(Value::Array(items)),
+                        _ => return Err(MALFORMED),
+                    }
+                }
+            }
+            '"' => {
+                self.chars.next();
+                Ok(Value::Str(self.string()?))
+            }
+            't' => self.expect("true").map(|_| Value::Bool(true)),
+            'f' => self.expect("false").map(|_| Value::Bool(false)),
+            'n' => self.expect("null").map(|_| Value::Null),
+            c if *c == '-' || c.is_ascii_digit() => {
+                let mut number = String::new();
+                while let Some(&c) = self.chars.peek() {
+                    if c.is_ascii_digit() || "+-.eE".contains(c) {
+                        number.push(c);
+                        self.chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                Ok(Value::Number(number))
+            }
+            _ => Err(MALFORMED),
+        }
+    }
+
+    /// Reads the rest of a string whose opening quote has been read
+    fn string(&mut self) -> Result<String, ImportError> {
+        let mut s = String::new();
+        loop {
+            match self.chars.next().ok_or(MALFORMED)? {
+                '"' => return Ok(s),
+                '\\' => match self.chars.next().ok_or(MALFORMED)? {
+                    'n' => s.push('\n'),
+                    'r' => s.push('\r'),
+                    't' => s.push('\t'),
+                    'b' => s.push('\u{8}'),
+                    'f' => s.push('\u{c}'),
+                    'u' => {
+                        let unit = self.hex4()?;
+                        let c = if (0xd800..0xdc00).contains(&unit) {
+                            // a surrogate pair
+                            self.expect("\\u")?;
+                            let low = self.hex4()?;
+                            if !(0xdc00..0xe000).contains(&low) {
+                                return Err(MALFORMED);
+                            }
+                            char::from_u32(0x10000 + ((unit - 0xd800) << 10) + (low - 0xdc00))
+                        } else {
+                            char::from_u32(unit)
+                        };
+                        s.push(c.ok_or(MALFORMED)?);
+                    }
+                    c => s.push(c),
+                },
+                c => s.push(c),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, ImportError> {
+        let mut value = 0;
+        for _ in 0..4 {
+            value = value * 16 + self.chars.next().and_then(|c| c.to_digit(16)).ok_or(MALFORMED)?;
+        }
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values() {
+        let value =
+            parse(r#" { "a": [1, -2.5e3, true, null], "b": "x\"é\ud83d\ude00\n", "c": {} } "#).unwrap();
+        assert_eq!(
+            value.get("a"),
+            Some(&Value::Array(vec![
+                Value::Number("1".to_string()),
+                Value::Number("-2.5e3".to_string()),
+                Value::Bool(true),
+                Value::Null
+            ]))
+        );
+        assert_eq!(value.get("b").and_then(Value::as_str), Some("x\"é😀\n"));
+        assert_eq!(value.get("c"), Some(&Value::Object(vec![])));
+        assert_eq!(parse("[1, 2").err(), Some(MALFORMED));
+        assert_eq!(parse("{\"a\": 1} x").err(), Some(MALFORMED));
+        assert_eq!(parse(&"[".repeat(100)).err(), Some(MALFORMED));
+    }
+}
@@ -0,0 +1,263 @@
+//! KeePass XML exports ("KeePass XML (2.x)" in KeePass, "XML" in KeePassXC).
+//!
+//! The file is read as a flat sequence of tags and text rather than parsed into a tree. Only the
+//! current version of each entry is imported: the old versions in `<History>` and the entries in the
+//! recycle bin are skipped.
+
+use super::{Batch, Entry, ImportError};
+
+const MALFORMED: ImportError = ImportError::Malformed("XML");
+
+enum Token<'a> {
+    Start(&'a str),
+    End(&'a str),
+    /// a tag with no content, like `<Value/>`
+    Empty(&'a str),
+    Text(&'a str),
+}
+
+pub(crate) fn parse(text: &str) -> Result<Batch, ImportError> {
+    let mut batch = Batch::default();
+    // the names of the open elements
+    let mut path: Vec<&str> = Vec::new();
+    // for each open group, whether it is the recycle bin or inside it
+    let mut groups: Vec<bool> = Vec::new();
+    let mut recycle_bin = String::new();
+    let mut entry: Option<Entry> = None;
+    let mut history = 0;
+    let (mut key, mut value) = (String::new(), String::new());
+    let mut text_buf = String::new();
+
+    if !text.contains("<KeePassFile") {
+        return Err(ImportError::UnknownFormat);
+    }
+    let tokens = Tokens { rest: text };
+    for token in tokens {
+        match token? {
+            Token::Start(name) => {
+                match name {
+                    "Group" => groups.push(groups.last().copied().unwrap_or(false)),
+                    "History" => history += 1,
+                    "Entry" if history == 0 => entry = Some(Entry::default()),
+                    "String" => {
+                        key.clear();
+                        value.clear();
+                    }
+                    _ => (),
+                }
+                path.push(name);
+                text_buf.clear();
+            }
+            Token::Text(t) => text_buf.push_str(&unescape(t)?),
+            Token::Empty(_) => (),
+            Token::End(name) => {
+                if path.pop() != Some(name) {
+                    return Err(MALFORMED);
+                }
+                let parent = path.last().copied().unwrap_or("");
+                match (parent, name) {
+                    ("Meta", "RecycleBinUUID") => recycle_bin = text_buf.trim().to_string(),
+                    ("Group", "UUID") if !recycle_bin.is_empty() && text_buf.trim() == recycle_bin => {
+                        if let Some(group) = groups.last_mut() {
+                            *group = true;
+                        }
+                    }
+                    ("String", "Key") => key = text_buf.clone(),
+                    ("String", "Value") => value = text_buf.clone(),
+                    (_, "String") if history == 0 => {
+                        if let Some(entry) = entry.as_mut() {
+                            let field = match key.as_str() {
+                                "Title" => Some(&mut entry.title),
+                                "UserName" => Some(&mut entry.username),
+                                "Password" => Some(&mut entry.password),
+                                "URL" => Some(&mut entry.url),
+                                "Notes" => Some(&mut entry.notes),
+                                // KeePassXC, and KeePass 2.47 and later
+                                "otp" | "TimeOtp-Secret-Base32" => Some(&mut entry.otp),
+                                _ => None,
+                            };
+                            if let Some(field) = field {
+                                *field = std::mem::take(&mut value);
+                            }
+                        }
+                    }
+                    (_, "Entry") if history == 0 => {
+                        if let Some(entry) = entry.take() {
+                            if groups.last().copied().unwrap_or(false) {
+                                log::debug!("skipping entry in the recycle bin");
+                            } else {
+                                batch.add_entry(entry);
+                            }
+                        }
+                    }
+                    (_, "History") => history -= 1,
+                    (_, "Group") => {
+                        groups.pop();
+                    }
+                    _ => (),
+                }
+                text_buf.clear();
+            }
+        }
+    }
+    if !path.is_empty() {
+        return Err(MALFORMED);
+    }
+    Ok(batch)
+}
+
+struct Tokens<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Result<Token<'a>, ImportError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.rest.is_empty() {
+                return None;
+            }
+            if !self.rest.starts_with('<') {
+                let end = self.rest.find('<').unwrap_or(self.rest.len());
+                let (text, rest) = self.rest.split_at(end);
+                self.rest = rest;
+                return Some(Ok(Token::Text(text)));
+            }
+            // declarations, comments and processing instructions carry nothing we need
+            let skip_to = if self.rest.starts_with("<!--") {
+                Some("-->")
+            } else if self.rest.starts_with("<?") {
+                Some("?>")
+            } else if self.rest.starts_with("<!") {
+                Some(">")
+            } else {
+                None
+            };
+            if let Some(terminator) = skip_to {
+                match self.rest.find(terminator) {
+                    Some(end) => {
+                        self.rest = &self.rest[end + terminator.len()..];
+                        continue;
+                    }
+                    None => return Some(Err(MALFORMED)),
+                }
+            }
+            let end = match self.rest.find('>') {
+                Some(end) => end,
+                None => return Some(Err(MALFORMED)),
+            };
+            let tag = &self.rest[1..end];
+            self.rest = &self.rest[end + 1..];
+            let (closing, tag) = match tag.strip_prefix('/') {
+                Some(tag) => (true, tag),
+                None => (false, tag),
+            };
+            let (empty, tag) = match tag.strip_suffix('/') {
+                Some(tag) => (true, tag),
+                None => (false, tag),
+            };
+            // drop the attributes, like `ProtectInMemory="True"`
+            let name = tag.split_whitespace().next().unwrap_or("");
+            if name.is_empty() {
+                return Some(Err(MALFORMED));
+            }
+            return Some(Ok(if closing {
+                Token::End(name)
+            } else if empty {
+                Token::Empty(name)
+            } else {
+                Token::Start(name)
+            }));
+        }
+    }
+}
+
+/// Replaces the character and entity references in XML text
+fn unescape(text: &str) -> Result<String, ImportError> {
+    let mut s = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        s.push_str(&rest[..start]);
+        let end = rest[start..].find(';').ok_or(MALFORMED)? + start;
+        let entity = &rest[start + 1..end];
+        let c = match entity {
+            "lt" => '<',
+            "gt" => '>',
+            "amp" => '&',
+            "quot" => '"',
+            "apos" => '\'',
+            _ => {
+                let code = if let Some(hex) = entity.strip_prefix("#x") {
+                    u32::from_str_radix(hex, 16).ok()
+                } else if let Some(dec) = entity.strip_prefix('#') {
+                    dec.parse().ok()
+                } else {
+                    None
+                };
+                code.and_then(char::from_u32).ok_or(MALFORMED)?
+            }
+        };
+        s.push(c);
+        rest = &rest[end + 1..];
+    }
+    s.push_str(rest);
+    Ok(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = r#"<?xml version="1.0" encoding="utf-8" standalone="yes"?>
+<!-- KeePass export -->
+<KeePassFile>
+    <Meta>
+        <Generator>KeePass</Generator>
+        <RecycleBinUUID>bin/uuid==</RecycleBinUUID>
+    </Meta>
+    <Root>
+        <Group>
+            <UUID>root/uuid==</UUID>
+            <Name>Database</Name>
+            <Entry>
+                <UUID>e1</UUID>
+                <String><Key>Notes</Key><Value/></String>
+                <String><Key>Password</Key><Value ProtectInMemory="True">a&lt;b&amp;c&#x20AC;</Value></String>
+                <String><Key>Title</Key><Value>Forum</Value></String>
+                <String><Key>URL</Key><Value>https://forum.example.org</Value></String>
+                <String><Key>UserName</Key><Value>carol</Value></String>
+                <String><Key>otp</Key><Value>otpauth://totp/Forum:carol?secret=JBSWY3DPEHPK3PXP&amp;period=30</Value></String>
+                <History>
+                    <Entry>
+                        <String><Key>Title</Key><Value>Old forum</Value></String>
+                        <String><Key>Password</Key><Value>old</Value></String>
+                    </Entry>
+                </History>
+            </Entry>
+            <Group>
+                <UUID>bin/uuid==</UUID>
+                <Name>Recycle Bin</Name>
+                <Entry>
+                    <String><Key>Title</Key><Value>Deleted</Value></String>
+                    <String><Key>Password</Key><Value>gone</Value></String>
+                </Entry>
+            </Group>
+        </Group>
+    </Root>
+</KeePassFile>
+"#;
+
+    #[test]
+    fn export() {
+        let batch = parse(EXPORT).unwrap();
+        assert_eq!((batch.passwords, batch.totp, batch.skipped), (1, 1, 0));
+        let record = String::from_utf8(batch.records[0].to_vec()).unwrap();
+        assert!(record.contains("description:Forum\nusername:carol\npassword:a<b&c€\n"));
+        let totp = String::from_utf8(batch.records[1].to_vec()).unwrap();
+        assert!(totp.contains("name:Forum:carol\n"));
+
+        assert_eq!(parse("<KeePassFile><Root></KeePassFile>").err(), Some(MALFORMED));
+        assert_eq!(parse("<?xml version=\"1.0\"?><svg></svg>").err(), Some(ImportError::UnknownFormat));
+    }
+}
@@ -0,0 +1,393 @@
+//! `otpauth://` URIs, as encoded in the QR codes of most sites, and the `otpauth-migration://` URIs
+//! that Google Authenticator exports its accounts with.
+//!
+//! The migration format is a base64 encoded protobuf message, `MigrationPayload`, which holds one
+//! `OtpParameters` message per account. Only the fields we can store are read.
+
+use core::convert::TryFrom;
+
+use locales::t;
+
+use super::{Batch, ImportError};
+use crate::storage::{TotpRecord, VAULT_TOTP_REC_VERSION};
+use crate::totp::{self, TotpAlgorithm};
+
+const DEFAULT_DIGITS: u32 = 6;
+const DEFAULT_TIMESTEP: u64 = 30;
+
+/// Reads a list of URIs, one per line
+pub(crate) fn parse(text: &str) -> Result<Batch, ImportError> {
+    let mut batch = Batch::default();
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        if line.starts_with("otpauth-migration://") {
+            let (records, skipped) = parse_migration(line)?;
+            for record in records {
+                batch.add_totp(record);
+            }
+            batch.skipped += skipped;
+        } else {
+            match parse_uri(line, "") {
+                Some(record) => batch.add_totp(record),
+                None => batch.skipped += 1,
+            }
+        }
+    }
+    Ok(batch)
+}
+
+/// The one-time password of a password manager entry called `name`. Exports either have an
+/// `otpauth://` URI, or just the base32 secret of a TOTP code with the usual settings.
+pub(crate) fn record_for_entry(otp: &str, name: &str) -> Option<TotpRecord> {
+    if otp.starts_with("otpauth://") {
+        parse_uri(otp, name)
+    } else if let Some(secret) = otp.strip_prefix("steam://") {
+        // Bitwarden's notation for Steam Guard secrets
+        let mut record = new_record(name.to_string(), normalize_secret(secret)?);
+        record.algorithm = TotpAlgorithm::Steam;
+        record.digits = totp::STEAM_DIGITS;
+        Some(record)
+    } else if otp.contains("://") {
+        // another scheme, which we don't know how to generate codes for
+        None
+    } else {
+        Some(new_record(name.to_string(), normalize_secret(otp)?))
+    }
+}
+
+/// Reads an `otpauth://totp/` or `otpauth://hotp/` URI. `name` is used if the URI has no label.
+fn parse_uri(uri: &str, name: &str) -> Option<TotpRecord> {
+    let rest = uri.trim().strip_prefix("otpauth://")?;
+    let (kind, rest) = rest.split_once('/')?;
+    // Aegis writes `otpauth://steam/` for Steam Guard, KeePassXC adds `encoder=steam` to a TOTP URI
+    let mut steam = false;
+    let is_hotp = match kind.to_ascii_lowercase().as_str() {
+        "totp" => false,
+        "hotp" => true,
+        "steam" => {
+            steam = true;
+            false
+        }
+        _ => return None,
+    };
+    let (label, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let mut record = new_record(String::new(), String::new());
+    record.is_hotp = is_hotp;
+    let mut issuer = String::new();
+    let mut counter = 0;
+    for (key, value) in query_pairs(query) {
+        let value = percent_decode(value)?;
+        match key.to_ascii_lowercase().as_str() {
+            "secret" => record.secret = normalize_secret(&value)?,
+            "issuer" => issuer = value,
+            "algorithm" => record.algorithm = TotpAlgorithm::try_from(value.to_uppercase().as_str()).ok()?,
+            "digits" => record.digits = value.parse().ok()?,
+            "period" => record.timestep = value.parse().ok().filter(|&p| p > 0)?,
+            "counter" => counter = value.parse().ok()?,
+            "encoder" => steam |= value.eq_ignore_ascii_case("steam"),
+            _ => (),
+        }
+    }
+    if record.secret.is_empty() {
+        return None;
+    }
+    // the 5 characters of Steam Guard codes are checked once the whole query has said whether it's Steam
+    if !(totp::MIN_DIGITS..=totp::MAX_DIGITS).contains(&record.digits)
+        && !(steam && record.digits == totp::STEAM_DIGITS)
+    {
+        return None;
+    }
+    if steam {
+        record.algorithm = TotpAlgorithm::Steam;
+        record.digits = totp::STEAM_DIGITS;
+    }
+    if is_hotp {
+        // the timestep field holds the count of HOTP records
+        record.timestep = counter;
+    }
+    record.name = account_name(&percent_decode(label)?, &issuer, name);
+    Some(record)
+}
+
+/// Reads an `otpauth-migration://offline?data=` URI. Returns the records, and how many accounts were
+/// skipped because they use settings we can't generate codes for.
+fn parse_migration(uri: &str) -> Result<(Vec<TotpRecord>, usize), ImportError> {
+    const MALFORMED: ImportError = ImportError::Malformed("otpauth-migration");
+    let query = uri.split_once('?').map(|(_, q)| q).ok_or(MALFORMED)?;
+    let data = query_pairs(query).find(|(key, _)| *key == "data").ok_or(MALFORMED)?.1;
+    let payload = base64_decode(&percent_decode(data).ok_or(MALFORMED)?).ok_or(MALFORMED)?;
+
+    let mut records = Vec::new();
+    let mut skipped = 0;
+    for field in Protobuf::new(&payload) {
+        if let (1, Wire::Bytes(parameters)) = field.ok_or(MALFORMED)? {
+            match otp_parameters(parameters).ok_or(MALFORMED)? {
+                Some(record) => records.push(record),
+                None => skipped += 1,
+            }
+        }
+    }
+    Ok((records, skipped))
+}
+
+/// Reads an `OtpParameters` message. Gives `Some(None)` for an account we can't store.
+fn otp_parameters(data: &[u8]) -> Option<Option<TotpRecord>> {
+    let mut record = new_record(String::new(), String::new());
+    let mut secret = Vec::new();
+    let mut name = String::new();
+    let mut issuer = String::new();
+    let mut supported = true;
+    let mut counter = 0;
+    for field in Protobuf::new(data) {
+        match field? {
+            (1, Wire::Bytes(bytes)) => secret = bytes.to_vec(),
+            (2, Wire::Bytes(bytes)) => name = String::from_utf8(bytes.to_vec()).ok()?,
+            (3, Wire::Bytes(bytes)) => issuer = String::from_utf8(bytes.to_vec()).ok()?,
+            (4, Wire::Varint(algorithm)) => match algorithm {
+                0 | 1 => record.algorithm = TotpAlgorithm::HmacSha1,
+                2 => record.algorithm = TotpAlgorithm::HmacSha256,
+                3 => record.algorithm = TotpAlgorithm::HmacSha512,
+                // MD5
+                _ => supported = false,
+            },
+            (5, Wire::Varint(digits)) => match digits {
+                0 | 1 => record.digits = 6,
+                2 => record.digits = 8,
+                _ => supported = false,
+            },
+            (6, Wire::Varint(kind)) => match kind {
+                0 | 2 => record.is_hotp = false,
+                1 => record.is_hotp = true,
+                _ => supported = false,
+            },
+            (7, Wire::Varint(count)) => counter = count,
+            _ => (),
+        }
+    }
+    if !supported || secret.is_empty() {
+        return Some(None);
+    }
+    if record.is_hotp {
+        record.timestep = counter;
+    }
+    record.secret = base32::encode(base32::Alphabet::RFC4648 { padding: false }, &secret);
+    record.name = account_name(&name, &issuer, "");
+    Some(Some(record))
+}
+
+fn new_record(name: String, secret: String) -> TotpRecord {
+    TotpRecord {
+        version: VAULT_TOTP_REC_VERSION,
+        secret,
+        name,
+        algorithm: TotpAlgorithm::HmacSha1,
+        notes: t!("vault.notes", locales::LANG).to_string(),
+        digits: DEFAULT_DIGITS,
+        timestep: DEFAULT_TIMESTEP,
+        ctime: super::import_time(),
+        is_hotp: false,
+        tags: Vec::new(),
+    }
+}
+
+/// Names a record `issuer:account`, like authenticator apps show it
+fn account_name(label: &str, issuer: &str, fallback: &str) -> String {
+    let label = super::single_line(label);
+    let issuer = super::single_line(issuer);
+    if label.is_empty() {
+        if issuer.is_empty() { super::single_line(fallback) } else { issuer }
+    } else if issuer.is_empty() || label.contains(':') {
+        label
+    } else {
+        format!("{}:{}", issuer, label)
+    }
+}
+
+/// Checks a base32 secret, and gives it back in the form records store it: upper case, no padding
+fn normalize_secret(secret: &str) -> Option<String> {
+    let secret: String = secret
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '=' && *c != '-')
+        .collect::<String>()
+        .to_uppercase();
+    let bytes = base32::decode(base32::Alphabet::RFC4648 { padding: false }, &secret)?;
+    if bytes.is_empty() {
+        return None;
+    }
+    Some(base32::encode(base32::Alphabet::RFC4648 { padding: false }, &bytes))
+}
+
+fn query_pairs(query: &str) -> impl Iterator<Item = (&str, &str)> {
+    query.split('&').filter_map(|pair| pair.split_once('='))
+}
+
+fn percent_decode(s: &str) -> Option<String> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = s.get(i + 1..i + 3)?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// Decodes base64 in either the standard or the URL-safe alphabet, with or without padding
+fn base64_decode(s: &str) -> Option<Vec<u8>> {
+    let mut decoded = Vec::with_capacity(s.len() * 3 / 4);
+    let mut acc: u32 = 0;
+    let mut bits = 0;
+    for c in s.bytes().filter(|c| !c.is_ascii_whitespace() && *c != b'=') {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => return None,
+        };
+        acc = (acc << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((acc >> bits) as u8);
+        }
+    }
+    Some(decoded)
+}
+
+enum Wire<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    Fixed,
+}
+
+/// Walks the fields of a protobuf message
+struct Protobuf<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Protobuf<'a> {
+    fn new(data: &'a [u8]) -> Self { Protobuf { data, pos: 0 } }
+
+    fn varint(&mut self) -> Option<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = *self.data.get(self.pos)?;
+            self.pos += 1;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    fn field(&mut self) -> Option<(u64, Wire<'a>)> {
+        let key = self.varint()?;
+        let wire = match key & 7 {
+            0 => Wire::Varint(self.varint()?),
+            1 | 5 => {
+                self.pos += if key & 7 == 1 { 8 } else { 4 };
+                if self.pos > self.data.len() {
+                    return None;
+                }
+                Wire::Fixed
+            }
+            2 => {
+                let len = self.varint()? as usize;
+                let bytes = self.data.get(self.pos..self.pos.checked_add(len)?)?;
+                self.pos += len;
+                Wire::Bytes(bytes)
+            }
+            _ => return None,
+        };
+        Some((key >> 3, wire))
+    }
+}
+
+impl<'a> Iterator for Protobuf<'a> {
+    /// `None` inside the `Some` if the message is malformed
+    type Item = Option<(u64, Wire<'a>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos >= self.data.len() {
+            return None;
+        }
+        let field = self.field();
+        if field.is_none() {
+            // stop after the first error
+            self.pos = self.data.len();
+        }
+        Some(field)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uris() {
+        let totp = parse_uri(
+            "otpauth://totp/ACME%20Co:john@example.com?secret=jbsw%20y3dp ehpk3pxp&issuer=ACME%20Co&algorithm=SHA256&digits=8&period=60",
+            "",
+        )
+        .unwrap();
+        assert_eq!(totp.name, "ACME Co:john@example.com");
+        assert_eq!(totp.secret, "JBSWY3DPEHPK3PXP");
+        assert_eq!(totp.algorithm.to_string(), "SHA256");
+        assert_eq!((totp.digits, totp.timestep, totp.is_hotp), (8, 60, false));
+
+        let hotp =
+            parse_uri("otpauth://hotp/alice?secret=JBSWY3DPEHPK3PXP&issuer=Example&counter=42", "").unwrap();
+        assert_eq!(hotp.name, "Example:alice");
+        assert_eq!((hotp.digits, hotp.timestep, hotp.is_hotp), (6, 42, true));
+
+        assert!(parse_uri("otpauth://totp/nosecret?issuer=Example", "").is_none());
+        assert!(parse_uri("otpauth://totp/x?secret=JBSWY3DPEHPK3PXP&algorithm=MD5", "").is_none());
+        assert!(parse_uri("otpauth://foo/x?secret=JBSWY3DPEHPK3PXP", "").is_none());
+
+        assert_eq!(record_for_entry("jbsw y3dp ehpk 3pxp", "Site").unwrap().secret, "JBSWY3DPEHPK3PXP");
+        assert_eq!(record_for_entry("otpauth://totp/?secret=JBSWY3DPEHPK3PXP", "Site").unwrap().name, "Site");
+        assert!(record_for_entry("ftp://JBSWY3DPEHPK3PXP", "Site").is_none());
+
+        let steam = record_for_entry("steam://JBSWY3DPEHPK3PXP", "Steam").unwrap();
+        assert_eq!((steam.algorithm.to_string(), steam.digits, steam.timestep), ("STEAM".to_string(), 5, 30));
+        let steam = parse_uri("otpauth://totp/Steam:bob?secret=JBSWY3DPEHPK3PXP&encoder=steam", "").unwrap();
+        assert_eq!((steam.algorithm.to_string(), steam.digits), ("STEAM".to_string(), 5));
+        assert_eq!(parse_uri("otpauth://steam/bob?secret=JBSWY3DPEHPK3PXP", "").unwrap().digits, 5);
+        assert!(parse_uri("otpauth://steam/bob?digits=5&secret=JBSWY3DPEHPK3PXP", "").is_some());
+        assert!(parse_uri("otpauth://totp/bob?digits=5&secret=JBSWY3DPEHPK3PXP", "").is_none());
+        assert!(parse_uri("otpauth://totp/bob?digits=11&secret=JBSWY3DPEHPK3PXP", "").is_none());
+    }
+
+    #[test]
+    fn migration() {
+        // one TOTP account, one HOTP account with a count of 300, and one MD5 account
+        let batch = parse(
+            "otpauth-migration://offline?data=Ci4KCkhlbGxvId6tvu8SEWFsaWNlQGV4YW1wbGUuY29tGgdFeGFtcGxlIAEoATACCh0KBQECAwQFEgtDb3VudGVyOmJvYiACKAIwATisAgoOCgUBAgMEBRIDbWQ1IAQQARgBIAAouWA%3D\n",
+        )
+        .unwrap();
+        assert_eq!((batch.totp, batch.skipped), (2, 1));
+        let first = String::from_utf8(batch.records[0].to_vec()).unwrap();
+        assert!(first.contains("name:Example:alice@example.com\n"));
+        assert!(first.contains("secret:JBSWY3DPEHPK3PXP\n"));
+        let second = String::from_utf8(batch.records[1].to_vec()).unwrap();
+        assert!(second.contains("name:Counter:bob\n"));
+        assert!(second.contains("secret:AEBAGBAF\n"));
+        assert!(second.contains("algorithm:SHA256\n"));
+        assert!(second.contains("digits:8\ntimestep:300\nhotp:1\n"));
+
+        assert_eq!(
+            parse("otpauth-migration://offline?data=Ci4K").err(),
+            Some(ImportError::Malformed("otpauth-migration"))
+        );
+    }
+}
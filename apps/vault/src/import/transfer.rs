@@ -0,0 +1,135 @@
+//! Receiving an export from the host over USB, either as a stream over the serial port, or as a file
+//! copied onto a small RAM disk. Either way the USB core is switched for the duration of the transfer,
+//! so FIDO and autotype are unavailable until it is put back.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use locales::t;
+use usb_device_xous::{UsbDeviceType, UsbHid};
+
+#[cfg(feature = "mass-storage")]
+use super::fat;
+
+/// How often the serial buffers are flushed while draining the end of a transfer
+const SERIAL_DRAIN_MS: usize = 100;
+
+/// Receives everything sent over the USB serial port until the user confirms that the transfer is done
+pub(crate) fn receive_serial(modals: &modals::Modals) -> Result<Vec<u8>, xous::Error> {
+    let usb = UsbHid::new();
+    let tt = ticktimer_server::Ticktimer::new().unwrap();
+    let previous_core = usb.get_current_core()?;
+    usb.switch_to_core(UsbDeviceType::Serial)?;
+
+    let data = Arc::new(Mutex::new(Vec::<u8>::new()));
+    let stop = Arc::new(AtomicBool::new(false));
+    let finished = Arc::new(AtomicBool::new(false));
+    thread::spawn({
+        let data = data.clone();
+        let stop = stop.clone();
+        let finished = finished.clone();
+        move || {
+            let usb = UsbHid::new();
+            loop {
+                let chunk = usb.serial_wait_binary();
+                // a flush with nothing left to hand over comes back empty
+                if chunk.is_empty() && stop.load(Ordering::SeqCst) {
+                    break;
+                }
+                data.lock().unwrap().extend_from_slice(&chunk);
+            }
+            finished.store(true, Ordering::SeqCst);
+        }
+    });
+
+    modals.show_notification(t!("vault.import.serial_prompt", locales::LANG), None).ok();
+    // the receiver only wakes up for full chunks, so flush out the tail of the transfer
+    stop.store(true, Ordering::SeqCst);
+    while !finished.load(Ordering::SeqCst) {
+        usb.serial_flush()?;
+        tt.sleep_ms(SERIAL_DRAIN_MS).unwrap();
+    }
+    usb.serial_clear_input_hooks();
+    usb.switch_to_core(previous_core)?;
+
+    let data = std::mem::take(&mut *data.lock().unwrap());
+    log::info!("received {} bytes over serial", data.len());
+    Ok(data)
+}
+
+#[cfg(feature = "mass-storage")]
+#[derive(Debug, num_derive::FromPrimitive, num_derive::ToPrimitive)]
+enum DiskOp {
+    Read,
+    Write,
+    MaxLba,
+    Quit,
+}
+
+/// Offers a blank RAM disk to the host, and gives back the file copied onto it once the user confirms
+/// that the disk has been ejected
+#[cfg(feature = "mass-storage")]
+pub(crate) fn receive_disk(modals: &modals::Modals) -> Result<Vec<u8>, xous::Error> {
+    use num_traits::*;
+
+    let usb = UsbHid::new();
+    let previous_core = usb.get_current_core()?;
+    let sid = xous::create_server()?;
+    let disk = thread::spawn(move || {
+        let mut image = fat::format();
+        loop {
+            let mut msg = xous::receive_message(sid).unwrap();
+            match FromPrimitive::from_usize(msg.body.id()) {
+                Some(DiskOp::Read) => {
+                    let body = msg.body.memory_message_mut().expect("incorrect message type received");
+                    let lba = body.offset.map(|v| v.get()).unwrap_or_default();
+                    // Safety: all values of `[u8]` are valid
+                    let data = unsafe { body.buf.as_slice_mut::<u8>() };
+                    if let Some(sector) = image.get(lba * fat::SECTOR_SIZE..(lba + 1) * fat::SECTOR_SIZE) {
+                        data[..fat::SECTOR_SIZE].copy_from_slice(sector);
+                    }
+                }
+                Some(DiskOp::Write) => {
+                    let body = msg.body.memory_message_mut().expect("incorrect message type received");
+                    let lba = body.offset.map(|v| v.get()).unwrap_or_default();
+                    // Safety: all values of `[u8]` are valid
+                    let data = unsafe { body.buf.as_slice::<u8>() };
+                    if let Some(sector) = image.get_mut(lba * fat::SECTOR_SIZE..(lba + 1) * fat::SECTOR_SIZE)
+                    {
+                        sector.copy_from_slice(&data[..fat::SECTOR_SIZE]);
+                    }
+                }
+                Some(DiskOp::MaxLba) => xous::return_scalar(msg.sender, fat::DISK_SECTORS - 1).unwrap(),
+                Some(DiskOp::Quit) => {
+                    xous::return_scalar(msg.sender, 0).unwrap();
+                    break;
+                }
+                None => log::error!("unknown disk opcode: {:?}", msg),
+            }
+        }
+        xous::destroy_server(sid).ok();
+        image
+    });
+
+    usb.set_block_device(
+        DiskOp::Read.to_usize().unwrap(),
+        DiskOp::Write.to_usize().unwrap(),
+        DiskOp::MaxLba.to_usize().unwrap(),
+    );
+    usb.set_block_device_sid(sid);
+    usb.switch_to_core(UsbDeviceType::MassStorage)?;
+
+    modals.show_notification(t!("vault.import.disk_prompt", locales::LANG), None).ok();
+    usb.reset_block_device();
+    usb.switch_to_core(previous_core)?;
+
+    let cid = xous::connect(sid)?;
+    xous::send_message(
+        cid,
+        xous::Message::new_blocking_scalar(DiskOp::Quit.to_usize().unwrap(), 0, 0, 0, 0),
+    )?;
+    unsafe { xous::disconnect(cid).ok() };
+    let image = disk.join().or(Err(xous::Error::InternalError))?;
+    Ok(fat::largest_file(&image).unwrap_or_default())
+}
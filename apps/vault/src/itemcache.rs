@@ -1,5 +1,5 @@
 use core::num::NonZeroUsize;
-use std::cmp::Ordering;
+use std::cmp::{Ordering, Reverse};
 use std::ops::Range;
 
 use crate::ux::framework::NavDir;
@@ -71,6 +71,9 @@ pub struct ListItem {
     /// hasn't changed.
     pub atime: u64,
     pub count: u64,
+    /// lower-cased copies of the record's notes and tags, which searches look through besides the name
+    notes: String,
+    tags: Vec<String>,
 }
 impl Ord for ListItem {
     fn cmp(&self, other: &Self) -> Ordering {
@@ -107,7 +110,40 @@ impl Eq for ListItem {}
 impl ListItem {
     pub fn new(name: String, extra: String, dirty: bool, guid: String, atime: u64, count: u64) -> Self {
         let sortable_name = name.to_lowercase();
-        Self { name, sortable_name, extra, dirty, guid, atime, count }
+        Self { name, sortable_name, extra, dirty, guid, atime, count, notes: String::new(), tags: Vec::new() }
+    }
+
+    /// Sets the notes and tags that searches look through, besides the name
+    pub fn set_details(&mut self, notes: &str, tags: &[String]) {
+        self.notes.clear();
+        self.notes.push_str(&notes.to_lowercase());
+        self.tags.clear();
+        self.tags.extend(tags.iter().map(|tag| tag.to_lowercase()));
+    }
+
+    /// How the item ranks in a search for the lower-cased `query`, split into `terms`. Every term has to be
+    /// found in the name, the notes or the tags; a term starting with `#` names a tag, so `#work` finds the
+    /// items tagged `work`, and those in folders under it like `work/servers`, but not `workshop`.
+    /// Items whose name starts with the whole query come first; `None` if the item doesn't match.
+    fn rank(&self, query: &str, terms: &[&str]) -> Option<u8> {
+        for term in terms {
+            let found = match term.strip_prefix('#') {
+                Some(tag) => {
+                    let tag = tag.trim_end_matches('/');
+                    let folder = format!("{}/", tag);
+                    self.tags.iter().any(|t| t == tag || t.starts_with(&folder))
+                }
+                None => {
+                    self.sortable_name.contains(term)
+                        || self.notes.contains(term)
+                        || self.tags.iter().any(|t| t.contains(term))
+                }
+            };
+            if !found {
+                return None;
+            }
+        }
+        Some(if self.sortable_name.starts_with(query) { 0 } else { 1 })
     }
 
     /// This is made available for edit/delete routines to generate the key without having to
@@ -129,13 +165,25 @@ impl ListItem {
     }
 }
 
+/// The order items are listed in
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SortOrder {
+    /// alphabetically, by name
+    Name,
+    /// most recently used first; items used at the same time (or never) go by how often they were used
+    Recent,
+}
+
 pub struct FilteredListView {
     // You might be thinking "why use a vec when a BTreeMap can guarantee uniqueness and sorting?"
     // See PR #389 for an explanation of why that's a terrible idea. The TL;DR is that sorting is cheap
     // compared to copying data. Or more precisely, sorting is cheap compared to figuring out where
     // on the heap to copy data.
     list: Vec<ListItem>,
+    /// whether `list` is sorted by name, which the lookups rely on. Searches and the `Recent` order
+    /// rearrange the list, and it is sorted again on the next lookup.
     sorted: bool,
+    order: SortOrder,
     selection_index: usize,
     items_per_screen: NonZeroUsize,
     filter_range: Option<Range<usize>>,
@@ -145,6 +193,7 @@ impl FilteredListView {
         Self {
             list: Vec::new(),
             sorted: false,
+            order: SortOrder::Name,
             selection_index: 0,
             items_per_screen: NonZeroUsize::new(1).unwrap(),
             filter_range: None,
@@ -208,6 +257,7 @@ impl FilteredListView {
         if !self.sorted {
             self.list.sort();
             self.sorted = true;
+            self.filter_range = None;
         }
         match self.list.binary_search_by(|probe| probe.partial_cmp(item).unwrap()) {
             Ok(index) => Some(&mut self.list[index]),
@@ -215,16 +265,16 @@ impl FilteredListView {
         }
     }
 
+    pub fn set_order(&mut self, order: SortOrder) { self.order = order; }
+
+    /// Narrows the list down to the items that match `criteria` (see `ListItem::rank()`), in the current
+    /// order. The matches are moved to the front of the list, so the filter stays a range.
     pub fn filter(&mut self, criteria: &String) {
-        //let tt = ticktimer_server::Ticktimer::new().unwrap();
-        //let mut ts = [0u64; 6];
-        //ts[0] = tt.elapsed_ms();
         // always ensure the list is sorted
         if !self.sorted {
             self.list.sort();
             self.sorted = true;
         }
-        //ts[1] = tt.elapsed_ms();
         // sanity checks on the request
         if criteria.len() == 0 {
             self.filter_reset();
@@ -234,48 +284,40 @@ impl FilteredListView {
             log::debug!("zero-length list!");
             return;
         }
-        // step 1. binary search to find if the criteria is even anywhere in the list.
-        match self.list.binary_search_by(|probe| probe.sortable_name.partial_cmp(criteria).unwrap()) {
-            Ok(mut index) | Err(mut index) => {
-                if !self.list[index].name.to_lowercase().starts_with(criteria) {
-                    self.filter_range = None
-                } else {
-                    // step 2. we have to go backwards in the list because if we have several matches, we are
-                    // not guaranteed to be at the first match. Find that first match with a linear search.
-                    //ts[2] = tt.elapsed_ms();
-                    while index.saturating_sub(1) > 0 {
-                        if self.list[index - 1].name.to_lowercase().starts_with(criteria) {
-                            index -= 1;
-                        } else {
-                            break;
-                        }
-                    }
-                    // the index now starts at the range of matches. Find the end of matches with another
-                    // linear search.
-                    let mut end_index = index + 1;
-                    //ts[3] = tt.elapsed_ms();
-                    while end_index < self.list.len() {
-                        if self.list[end_index].name.to_lowercase().starts_with(criteria) {
-                            end_index += 1;
-                        } else {
-                            break;
-                        }
-                    }
-                    self.filter_range = Some(index..end_index)
-                }
-            }
+        self.arrange(&criteria.to_lowercase());
+    }
+
+    fn arrange(&mut self, query: &str) {
+        let terms = query.split_whitespace().collect::<Vec<&str>>();
+        let order = self.order;
+        // the sort below is stable, and the list starts out sorted by name, so ties stay in name order
+        let mut ranked = self
+            .list
+            .drain(..)
+            .map(|item| {
+                let rank = if terms.is_empty() { Some(0) } else { item.rank(query, &terms) };
+                let key = match (rank, order) {
+                    (None, _) => (u8::MAX, Reverse(0), Reverse(0)),
+                    (Some(rank), SortOrder::Name) => (rank, Reverse(0), Reverse(0)),
+                    (Some(_), SortOrder::Recent) => (0, Reverse(item.atime), Reverse(item.count)),
+                };
+                (key, item)
+            })
+            .collect::<Vec<_>>();
+        let matches = ranked.iter().filter(|(key, _)| key.0 != u8::MAX).count();
+        if !ranked.windows(2).all(|pair| pair[0].0 <= pair[1].0) {
+            ranked.sort_by(|a, b| a.0.cmp(&b.0));
+            self.sorted = false;
         }
-        //ts[4] = tt.elapsed_ms();
+        self.list.extend(ranked.into_iter().map(|(_, item)| item));
+
+        self.filter_range = if matches > 0 { Some(0..matches) } else { None };
         if let Some(r) = &self.filter_range {
             if self.selection_index >= r.len() {
                 self.selection_index = 0;
             }
         }
         self.mark_filtered_as_dirty();
-        //ts[5] = tt.elapsed_ms();
-        //for(index, &elapsed) in ts[1..].iter().enumerate() {
-        //    log::info!("{}: {}", index + 1, elapsed.saturating_sub(ts[0]));
-        //}
     }
 
     fn mark_filtered_as_dirty(&mut self) {
@@ -287,8 +329,16 @@ impl FilteredListView {
     }
 
     pub fn filter_reset(&mut self) {
-        self.filter_range = Some(0..self.list.len());
-        self.mark_filtered_as_dirty();
+        if !self.sorted {
+            self.list.sort();
+            self.sorted = true;
+        }
+        if self.order == SortOrder::Name || self.list.len() == 0 {
+            self.filter_range = Some(0..self.list.len());
+            self.mark_filtered_as_dirty();
+        } else {
+            self.arrange("");
+        }
     }
 
     pub fn filter_len(&self) -> usize {
@@ -488,6 +538,12 @@ impl ItemLists {
         self.pw.set_items_per_screen(ips as usize);
     }
 
+    pub fn set_order(&mut self, order: SortOrder) {
+        self.fido.set_order(order);
+        self.totp.set_order(order);
+        self.pw.set_order(order);
+    }
+
     pub fn mark_all_dirty(&mut self) {
         self.fido.mark_all_dirty();
         self.totp.mark_all_dirty();
@@ -538,3 +594,128 @@ impl ItemLists {
         self.li_mut(list_type).selected_page()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(name: &str, atime: u64, count: u64) -> ListItem {
+        ListItem::new(name.to_string(), String::new(), false, format!("guid-{}", name), atime, count)
+    }
+
+    fn view_of(items: Vec<ListItem>) -> FilteredListView {
+        let mut view = FilteredListView::new();
+        for item in items {
+            view.push(item);
+        }
+        view
+    }
+
+    /// names of the items in the filter range, in display order
+    fn filtered(view: &FilteredListView) -> Vec<&str> {
+        match &view.filter_range {
+            Some(r) => view.list[r.clone()].iter().map(|item| item.name().as_str()).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    #[test]
+    fn rank_terms_and_tags() {
+        let mut github = item("GitHub", 0, 0);
+        github.set_details("Work account", &["Work/Servers".to_string(), "code".to_string()]);
+        let rank = |query: &str| github.rank(query, &query.split_whitespace().collect::<Vec<&str>>());
+
+        assert_eq!(rank("git"), Some(0), "name prefix ranks first");
+        assert_eq!(rank("hub"), Some(1), "name substring");
+        assert_eq!(rank("account"), Some(1), "notes are searched");
+        assert_eq!(rank("serv"), Some(1), "tags are searched by substring");
+        assert_eq!(rank("hub account"), Some(1), "every term has to match");
+        assert_eq!(rank("hub nope"), None);
+
+        assert_eq!(rank("#work"), Some(1), "a folder finds what's filed under it");
+        assert_eq!(rank("#work/servers"), Some(1));
+        assert_eq!(rank("#code"), Some(1));
+        assert_eq!(rank("#servers"), None, "#tag only matches from the start of a tag");
+        assert_eq!(rank("#account"), None, "#tag doesn't look at the notes");
+        assert_eq!(rank("#hub"), None, "#tag doesn't look at the name");
+    }
+
+    #[test]
+    fn filter_by_name() {
+        let mut view =
+            view_of(vec![item("Beta", 0, 0), item("Zalpha", 0, 0), item("alpha", 0, 0), item("Gamma", 0, 0)]);
+        view.filter(&"ALPHA".to_string());
+        // the name prefix match first, then the rest in name order
+        assert_eq!(filtered(&view), vec!["alpha", "Zalpha"]);
+        assert_eq!(view.filter_range, Some(0..2));
+        assert_eq!(view.filter_len(), 2);
+
+        view.filter(&"a".to_string());
+        assert_eq!(filtered(&view), vec!["alpha", "Beta", "Gamma", "Zalpha"]);
+
+        view.filter(&"nope".to_string());
+        assert_eq!(view.filter_range, None);
+        assert_eq!(view.filter_len(), 0);
+
+        view.filter_reset();
+        assert_eq!(filtered(&view), vec!["alpha", "Beta", "Gamma", "Zalpha"]);
+    }
+
+    #[test]
+    fn filter_by_tag() {
+        let mut mail = item("mail", 0, 0);
+        mail.set_details("", &["work".to_string()]);
+        let mut ssh = item("ssh", 0, 0);
+        ssh.set_details("", &["work/servers".to_string()]);
+        let mut bank = item("bank", 0, 0);
+        bank.set_details("", &["home".to_string()]);
+        let mut lathe = item("lathe", 0, 0);
+        lathe.set_details("", &["workshop".to_string()]);
+        let mut view = view_of(vec![mail, ssh, bank, lathe]);
+
+        view.filter(&"#work".to_string());
+        assert_eq!(filtered(&view), vec!["mail", "ssh"]);
+        view.filter(&"#work/".to_string());
+        assert_eq!(filtered(&view), vec!["mail", "ssh"]);
+        view.filter(&"#workshop".to_string());
+        assert_eq!(filtered(&view), vec!["lathe"]);
+        view.filter(&"#work/servers".to_string());
+        assert_eq!(filtered(&view), vec!["ssh"]);
+        view.filter(&"#home".to_string());
+        assert_eq!(filtered(&view), vec!["bank"]);
+    }
+
+    #[test]
+    fn recent_order() {
+        let mut view = view_of(vec![
+            item("never", 0, 0),
+            item("old", 100, 9),
+            item("new", 300, 1),
+            item("busy", 200, 7),
+            item("idle", 200, 2),
+        ]);
+        view.set_order(SortOrder::Recent);
+        view.filter_reset();
+        // most recent first; ties go to the most used
+        assert_eq!(filtered(&view), vec!["new", "busy", "idle", "old", "never"]);
+        assert_eq!(view.filter_range, Some(0..5));
+
+        // a search keeps that order
+        view.filter(&"e".to_string());
+        assert_eq!(filtered(&view), vec!["new", "idle", "never"]);
+
+        // lookups still work after the list has been rearranged
+        assert!(view.get(&ListKey::key_from_parts("Old", "guid-old")).is_some());
+        assert!(view.get(&ListKey::key_from_parts("gone", "guid-gone")).is_none());
+    }
+
+    #[test]
+    fn selection_stays_in_range() {
+        let mut view = view_of((0..10).map(|i| item(&format!("item{}", i), 0, 0)).collect());
+        view.filter_reset();
+        view.selection_index = 7;
+        view.filter(&"item1".to_string());
+        assert_eq!(view.filter_len(), 1);
+        assert_eq!(view.selection_index, 0);
+    }
+}
@@ -2,9 +2,14 @@
 #![cfg_attr(target_os = "none", no_main)]
 
 mod actions;
+mod fido_backup;
+mod import;
 mod itemcache;
 mod migration_v1;
+mod openpgp;
 mod prereqs;
+mod pwgen;
+mod ssh_agent;
 mod storage;
 mod submenu;
 mod totp;
@@ -19,6 +24,7 @@ use actions::ActionOp;
 use itemcache::*;
 use locales::t;
 use num_traits::*;
+use usb_device_xous::{HostLayout, UnicodeEntry};
 use ux::framework::{name_to_style, VaultUx, DEFAULT_FONT, FONT_LIST};
 use vault::ctap::main_hid::HidIterType;
 use vault::env::xous::XousEnv;
@@ -27,8 +33,10 @@ use vault::{Transport, VaultOp, SELF_CONN};
 use xous::{msg_blocking_scalar_unpack, msg_scalar_unpack, send_message, Message};
 use xous_ipc::Buffer;
 use xous_usb_hid::device::fido::*;
+use zeroize::Zeroizing;
 
 use crate::prereqs::ntp_updater;
+use crate::storage::host_layouts;
 use crate::ux::framework::NavDir;
 use crate::vendor_commands::VendorSession;
 
@@ -74,8 +82,10 @@ UI concept:
 
   List filter:
     - Any regular keys hit here appear in the search input. It automatically
-      filters the content in the list view area to the set of strings that match
-      the search input
+      filters the content in the list view area to the items whose name, username,
+      notes or tags contain every word of the search input. A word starting with `#`
+      only matches tags, and `#work` also matches tags in the folder `work/`.
+      Items are listed by name, or most recently used first ("Sort order" menu)
 
   Up/down arrow: picks a list view item
   Left/right arrow: moves up or down the list view in pages
@@ -122,6 +132,8 @@ fn main() -> ! {
     let item_lists = Arc::new(Mutex::new(ItemLists::new()));
     let action_active = Arc::new(AtomicBool::new(false));
     let allow_host = Arc::new(AtomicBool::new(false));
+    // set to the backup passphrase while FIDO backup mode is open; FIDO credentials don't move without it
+    let fido_passphrase: Arc<Mutex<Option<Zeroizing<String>>>> = Arc::new(Mutex::new(None));
     let allow_totp_rendering = Arc::new(AtomicBool::new(true));
     // Protects access to the openSK PDDB entries from simultaneous readout on the UX while OpenSK is updating
     // it
@@ -141,9 +153,16 @@ fn main() -> ! {
         let item_lists = item_lists.clone();
         let action_active = action_active.clone();
         let opensk_mutex = opensk_mutex.clone();
+        let lefty_mode = lefty_mode.clone();
         move || {
-            let mut manager =
-                crate::actions::ActionManager::new(main_conn, mode, item_lists, action_active, opensk_mutex);
+            let mut manager = crate::actions::ActionManager::new(
+                main_conn,
+                mode,
+                item_lists,
+                action_active,
+                opensk_mutex,
+                lefty_mode,
+            );
             loop {
                 let msg = xous::receive_message(sid).unwrap();
                 let opcode: Option<ActionOp> = FromPrimitive::from_usize(msg.body.id());
@@ -171,6 +190,30 @@ fn main() -> ! {
                         manager.menu_edit(entry); // this is responsible for updating the item cache
                         manager.deactivate();
                     }
+                    Some(ActionOp::MenuEntryLayoutStage2) => {
+                        let buffer =
+                            unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                        let entry = buffer.to_original::<SelectedEntry, _>().unwrap();
+                        manager.activate();
+                        manager.menu_entry_layout(entry);
+                        manager.deactivate();
+                    }
+                    Some(ActionOp::MenuEntryGeneratorStage2) => {
+                        let buffer =
+                            unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                        let entry = buffer.to_original::<SelectedEntry, _>().unwrap();
+                        manager.activate();
+                        manager.menu_entry_generator(entry);
+                        manager.deactivate();
+                    }
+                    Some(ActionOp::MenuRotateStage2) => {
+                        let buffer =
+                            unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                        let entry = buffer.to_original::<SelectedEntry, _>().unwrap();
+                        manager.activate();
+                        manager.menu_rotate(entry);
+                        manager.deactivate();
+                    }
                     Some(ActionOp::MenuUnlockBasis) => {
                         manager.activate();
                         manager.unlock_basis();
@@ -185,6 +228,28 @@ fn main() -> ! {
                         manager.retrieve_db();
                         manager.deactivate();
                     }
+                    Some(ActionOp::MenuImport) => {
+                        manager.activate();
+                        manager.menu_import();
+                        manager.item_lists.lock().unwrap().clear_all(); // imports can add to any of the lists
+                        manager.retrieve_db();
+                        manager.deactivate();
+                    }
+                    Some(ActionOp::MenuSshAgent) => {
+                        manager.activate();
+                        manager.menu_ssh_agent();
+                        manager.deactivate();
+                    }
+                    Some(ActionOp::MenuOpenPgp) => {
+                        manager.activate();
+                        manager.menu_openpgp();
+                        manager.deactivate();
+                    }
+                    Some(ActionOp::MenuGeneratorProfiles) => {
+                        manager.activate();
+                        manager.menu_generator_profiles();
+                        manager.deactivate();
+                    }
                     Some(ActionOp::MenuClose) => {
                         // dummy activate/de-activate cycle because we have to trigger a redraw of the
                         // underlying UX
@@ -289,6 +354,7 @@ fn main() -> ! {
     // spawn the FIDO2 USB handler
     let _ = thread::spawn({
         let allow_host = allow_host.clone();
+        let fido_passphrase = fido_passphrase.clone();
         let opensk_mutex = opensk_mutex.clone();
         let conn = conn.clone();
         let lefty_mode = lefty_mode.clone();
@@ -357,9 +423,12 @@ fn main() -> ! {
                                                 Some(data) => data,
                                                 None => {
                                                     log::debug!("starting processing of vendor data...");
+                                                    let passphrase = fido_passphrase.lock().unwrap().clone();
                                                     let resp = vendor_commands::handle_vendor_command(
                                                         &mut vendor_session,
                                                         allow_host.load(Ordering::SeqCst),
+                                                        passphrase.as_ref().map(|p| p.as_str()),
+                                                        ctap.env(),
                                                     );
                                                     log::debug!("finished processing of vendor data!");
 
@@ -466,6 +535,7 @@ fn main() -> ! {
     );
     vaultux.update_mode();
     vaultux.get_glyph_style();
+    vaultux.get_sort_order();
 
     // starts a thread to keep NTP up-to-date
     ntp_updater(time_conn);
@@ -474,6 +544,10 @@ fn main() -> ! {
     let prefs = userprefs::Manager::new();
     let mut autotype_delay_ms = prefs.autotype_rate_or_value(30).unwrap();
     vaultux.set_autotype_delay_ms(autotype_delay_ms);
+    vaultux.set_host_layout(
+        HostLayout::from_u32(prefs.autotype_layout_or_default().unwrap()).unwrap_or_default(),
+        UnicodeEntry::from_u32(prefs.autotype_unicode_or_default().unwrap()).unwrap_or_default(),
+    );
     lefty_mode.store(prefs.lefty_mode_or_value(false).unwrap(), Ordering::SeqCst);
 
     let modals = modals::Modals::new(&xns).unwrap();
@@ -662,6 +736,25 @@ fn main() -> ! {
                 allow_totp_rendering.store(true, Ordering::SeqCst);
                 vaultux.update_mode();
             }
+            Some(VaultOp::MenuSortOrder) => {
+                let name = t!("vault.sort_name", locales::LANG);
+                let recent = t!("vault.sort_recent", locales::LANG);
+                modals.add_list_item(name).expect("couldn't build radio item list");
+                modals.add_list_item(recent).expect("couldn't build radio item list");
+                allow_totp_rendering.store(false, Ordering::SeqCst);
+                match modals.get_radiobutton(t!("vault.sort_prompt", locales::LANG)) {
+                    Ok(order) => {
+                        vaultux.set_sort_order(if order == recent {
+                            SortOrder::Recent
+                        } else {
+                            SortOrder::Name
+                        });
+                    }
+                    _ => log::error!("get_radiobutton failed"),
+                }
+                allow_totp_rendering.store(true, Ordering::SeqCst);
+                vaultux.update_mode();
+            }
             Some(VaultOp::MenuAutotype) => msg_scalar_unpack!(msg, select_username, _, _, _, {
                 allow_totp_rendering.store(false, Ordering::SeqCst);
                 modals.dynamic_notification(Some(t!("vault.autotyping", locales::LANG)), None).ok();
@@ -770,6 +863,48 @@ fn main() -> ! {
                     allow_totp_rendering.store(true, Ordering::SeqCst);
                 }
             }
+            Some(VaultOp::MenuEntryLayoutStage1) => {
+                // stage 1 happens here because the filtered list and selection entry are in the responsive UX
+                // section.
+                if let Some(entry) = vaultux.selected_entry() {
+                    let buf = Buffer::into_buf(entry).expect("IPC error");
+                    buf.send(actions_conn, ActionOp::MenuEntryLayoutStage2.to_u32().unwrap())
+                        .expect("messaging error");
+                } else {
+                    // this will block redraws
+                    allow_totp_rendering.store(false, Ordering::SeqCst);
+                    modals.show_notification(t!("vault.error.nothing_selected", locales::LANG), None).ok();
+                    allow_totp_rendering.store(true, Ordering::SeqCst);
+                }
+            }
+            Some(VaultOp::MenuEntryGeneratorStage1) => {
+                // stage 1 happens here because the filtered list and selection entry are in the responsive UX
+                // section.
+                if let Some(entry) = vaultux.selected_entry() {
+                    let buf = Buffer::into_buf(entry).expect("IPC error");
+                    buf.send(actions_conn, ActionOp::MenuEntryGeneratorStage2.to_u32().unwrap())
+                        .expect("messaging error");
+                } else {
+                    // this will block redraws
+                    allow_totp_rendering.store(false, Ordering::SeqCst);
+                    modals.show_notification(t!("vault.error.nothing_selected", locales::LANG), None).ok();
+                    allow_totp_rendering.store(true, Ordering::SeqCst);
+                }
+            }
+            Some(VaultOp::MenuRotateStage1) => {
+                // stage 1 happens here because the filtered list and selection entry are in the responsive UX
+                // section.
+                if let Some(entry) = vaultux.selected_entry() {
+                    let buf = Buffer::into_buf(entry).expect("IPC error");
+                    buf.send(actions_conn, ActionOp::MenuRotateStage2.to_u32().unwrap())
+                        .expect("messaging error");
+                } else {
+                    // this will block redraws
+                    allow_totp_rendering.store(false, Ordering::SeqCst);
+                    modals.show_notification(t!("vault.error.nothing_selected", locales::LANG), None).ok();
+                    allow_totp_rendering.store(true, Ordering::SeqCst);
+                }
+            }
             Some(VaultOp::MenuReadoutMode) => {
                 modals.dynamic_notification(Some(t!("vault.readout_switchover", locales::LANG)), None).ok();
                 vaultux.readout_mode(true);
@@ -791,6 +926,61 @@ fn main() -> ! {
                 )
                 .ok();
             }
+            Some(VaultOp::MenuFidoBackupMode) => {
+                allow_totp_rendering.store(false, Ordering::SeqCst);
+                // typing the passphrase twice on the device is the user presence check for FIDO transfers
+                let entries = match modals
+                    .form_builder(t!("vault.fido_backup.passphrase", locales::LANG))
+                    .password(
+                        t!("vault.fido_backup.passphrase_label", locales::LANG),
+                        Some(backup_passphrase_validator),
+                    )
+                    .password(t!("vault.fido_backup.repeat", locales::LANG), None)
+                    .build()
+                {
+                    Ok(form) => Some((
+                        Zeroizing::new(form.text(0).unwrap_or("").to_string()),
+                        Zeroizing::new(form.text(1).unwrap_or("").to_string()),
+                    )),
+                    _ => None,
+                };
+                match entries {
+                    // cancelled
+                    None => (),
+                    Some((passphrase, repeat)) if passphrase == repeat => {
+                        *fido_passphrase.lock().unwrap() = Some(passphrase);
+
+                        modals
+                            .dynamic_notification(Some(t!("vault.readout_switchover", locales::LANG)), None)
+                            .ok();
+                        vaultux.readout_mode(true);
+                        modals.dynamic_notification_close().ok();
+
+                        allow_host.store(true, Ordering::SeqCst);
+                        modals.show_notification(t!("vault.fido_backup.active", locales::LANG), None).ok();
+                        allow_host.store(false, Ordering::SeqCst);
+                        // dropping it zeroes the passphrase
+                        fido_passphrase.lock().unwrap().take();
+
+                        modals
+                            .dynamic_notification(Some(t!("vault.readout_switchover", locales::LANG)), None)
+                            .ok();
+                        vaultux.readout_mode(false);
+                        modals.dynamic_notification_close().ok();
+                    }
+                    Some(_) => {
+                        modals.show_notification(t!("vault.fido_backup.mismatch", locales::LANG), None).ok();
+                    }
+                }
+                allow_totp_rendering.store(true, Ordering::SeqCst);
+
+                // restored credentials show up in the FIDO list
+                xous::send_message(
+                    conn,
+                    xous::Message::new_scalar(VaultOp::ReloadDbAndFullRedraw.to_usize().unwrap(), 0, 0, 0, 0),
+                )
+                .ok();
+            }
             Some(VaultOp::MenuAutotypeRate) => {
                 let cv = {
                     let mut rate = prefs.autotype_rate_or_default().unwrap();
@@ -799,23 +989,53 @@ fn main() -> ! {
                     }
                     rate
                 };
-                let raw = modals
-                    .alert_builder(t!("prefs.autotype_rate_in_ms", locales::LANG))
-                    .field(
-                        Some(cv.to_string()),
-                        Some(|tf| match tf.as_str().parse::<usize>() {
-                            Ok(_) => None,
-                            Err(_) => {
-                                Some(xous_ipc::String::from_str(t!("prefs.autobacklight_err", locales::LANG)))
-                            }
-                        }),
+                autotype_delay_ms = modals
+                    .get_number(
+                        t!("prefs.autotype_rate_in_ms", locales::LANG),
+                        1,
+                        1000,
+                        5,
+                        cv.min(1000) as i32,
+                        Some("ms"),
                     )
-                    .build()
-                    .unwrap();
-                autotype_delay_ms = raw.first().as_str().parse::<usize>().unwrap(); // we know this is a number, we checked with validator;
+                    .unwrap() as usize;
                 prefs.set_autotype_rate(autotype_delay_ms).unwrap();
                 vaultux.set_autotype_delay_ms(autotype_delay_ms);
             }
+            Some(VaultOp::MenuAutotypeLayout) => {
+                let layout =
+                    HostLayout::from_u32(prefs.autotype_layout_or_default().unwrap()).unwrap_or_default();
+                let names: Vec<String> = host_layouts().map(|l| l.to_string()).collect();
+                modals.add_list(names.iter().map(|name| name.as_str()).collect()).unwrap();
+                allow_totp_rendering.store(false, Ordering::SeqCst);
+                let selection = modals
+                    .get_radiobutton(&format!("{} {}", t!("prefs.current_setting", locales::LANG), layout))
+                    .unwrap();
+                let layout = host_layouts().find(|l| l.to_string() == selection).unwrap_or_default();
+
+                let unicode =
+                    UnicodeEntry::from_u32(prefs.autotype_unicode_or_default().unwrap()).unwrap_or_default();
+                let names: Vec<String> =
+                    (0..).map_while(UnicodeEntry::from_u32).map(|u| u.to_string()).collect();
+                modals.add_list(names.iter().map(|name| name.as_str()).collect()).unwrap();
+                let selection = modals
+                    .get_radiobutton(&format!(
+                        "{}\n{} {}",
+                        t!("prefs.autotype_unicode", locales::LANG),
+                        t!("prefs.current_setting", locales::LANG),
+                        unicode
+                    ))
+                    .unwrap();
+                let unicode = (0..)
+                    .map_while(UnicodeEntry::from_u32)
+                    .find(|u| u.to_string() == selection)
+                    .unwrap_or_default();
+                allow_totp_rendering.store(true, Ordering::SeqCst);
+
+                prefs.set_autotype_layout(layout.to_u32().unwrap()).unwrap();
+                prefs.set_autotype_unicode(unicode.to_u32().unwrap()).unwrap();
+                vaultux.set_host_layout(layout, unicode);
+            }
             Some(VaultOp::MenuLeftyMode) => {
                 let cv = prefs.lefty_mode_or_default().unwrap();
 
@@ -869,3 +1089,10 @@ fn yes_no_to_bool(val: &str) -> bool {
         unreachable!("cannot go here!");
     }
 }
+fn backup_passphrase_validator(input: &modals::FormValue) -> Option<xous_ipc::String<256>> {
+    if input.as_str().unwrap_or("").chars().count() < 8 {
+        Some(xous_ipc::String::<256>::from_str(t!("vault.fido_backup.too_short", locales::LANG)))
+    } else {
+        None
+    }
+}
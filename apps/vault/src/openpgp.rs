@@ -0,0 +1,872 @@
+//! An OpenPGP card (version 3.4 of the specification) for keys that live in the PDDB, served through
+//! the USB smart card reader (CCID) interface.
+//!
+//! gpg talks to it like to any other card, through pcscd or through its own CCID driver. The card has
+//! the usual three keys, for signing, decryption and authentication, all NIST P-256 and generated on
+//! the device; keys can't be imported or exported. The user (PW1) and admin (PW3) PINs are checked and
+//! rate limited the way FIDO client PINs are, and on top of the PINs, every use of a private key and
+//! every key generation has to be approved on the device.
+
+use core::convert::TryInto;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+use ctap_crypto::ecdh;
+use ctap_crypto::ecdsa;
+use ctap_crypto::rng256::Rng256;
+use locales::t;
+use subtle::ConstantTimeEq;
+use usb_device_xous::{UsbDeviceType, UsbHid};
+use vault::ctap::{hash_pin, TOUCH_TIMEOUT};
+use vault::env::xous::wait_for_user_presence;
+
+use crate::storage::{self, ContentKind, OpenPgpCardRecord, StorageContent, VAULT_OPENPGP_REC_VERSION};
+
+/// The registered application identifier of OpenPGP cards, followed by the specification version
+const AID_PREFIX: [u8; 8] = [0xd2, 0x76, 0x00, 0x01, 0x24, 0x01, 0x03, 0x04];
+/// The manufacturer ID set aside for test cards and hobbyist implementations
+const MANUFACTURER: [u8; 2] = [0xff, 0xfe];
+/// Same as in the ATR of the reader: command chaining, no extended lengths, card operational
+const HISTORICAL_BYTES: [u8; 8] = [0x00, 0x73, 0x00, 0x00, 0x80, 0x05, 0x90, 0x00];
+/// Only the PW1 status byte can be changed; no secure messaging, GET CHALLENGE, key import,
+/// cardholder certificate or private DOs
+const EXTENDED_CAPABILITIES: [u8; 10] = [0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x00, 0x00];
+/// Algorithm attributes: ECDSA, and ECDH, with the OID of NIST P-256
+const ECDSA_P256: [u8; 9] = [0x13, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+const ECDH_P256: [u8; 9] = [0x12, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+
+const PIN_RETRIES: u8 = 3;
+pub(crate) const MIN_PW1_LEN: usize = 6;
+pub(crate) const MIN_PW3_LEN: usize = 8;
+pub(crate) const MAX_PIN_LEN: usize = 64;
+/// The largest data object the host can store, so that the constructed ones fit a short response
+const MAX_OBJECT_LEN: usize = 128;
+/// Chained commands only carry a little more than a public key
+const MAX_CHAIN_LEN: usize = 1024;
+/// How often the running notice is checked for a key press
+const POLL_MS: u64 = 100;
+
+const CLA_CHAINING: u8 = 0x10;
+const INS_SELECT: u8 = 0xa4;
+const INS_GET_DATA: u8 = 0xca;
+const INS_PUT_DATA: u8 = 0xda;
+const INS_VERIFY: u8 = 0x20;
+const INS_CHANGE_REFERENCE_DATA: u8 = 0x24;
+const INS_RESET_RETRY_COUNTER: u8 = 0x2c;
+const INS_GENERATE_ASYMMETRIC_KEY_PAIR: u8 = 0x47;
+const INS_PERFORM_SECURITY_OPERATION: u8 = 0x2a;
+const INS_INTERNAL_AUTHENTICATE: u8 = 0x88;
+
+const SW_OK: u16 = 0x9000;
+/// Or'ed with the number of retries left
+const SW_PIN_WRONG: u16 = 0x63c0;
+const SW_MEMORY_FAILURE: u16 = 0x6581;
+const SW_WRONG_LENGTH: u16 = 0x6700;
+const SW_SECURITY_STATUS: u16 = 0x6982;
+const SW_PIN_BLOCKED: u16 = 0x6983;
+const SW_CONDITIONS: u16 = 0x6985;
+const SW_WRONG_DATA: u16 = 0x6a80;
+const SW_NOT_FOUND: u16 = 0x6a82;
+const SW_DATA_NOT_FOUND: u16 = 0x6a88;
+const SW_WRONG_P1P2: u16 = 0x6b00;
+const SW_INS_NOT_SUPPORTED: u16 = 0x6d00;
+const SW_CLA_NOT_SUPPORTED: u16 = 0x6e00;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Slot {
+    Sig = 0,
+    Dec = 1,
+    Aut = 2,
+}
+
+impl Slot {
+    const ALL: [Slot; 3] = [Slot::Sig, Slot::Dec, Slot::Aut];
+
+    /// From the control reference template naming the key in GENERATE ASYMMETRIC KEY PAIR
+    fn from_crt(tag: u8) -> Option<Slot> {
+        match tag {
+            0xb6 => Some(Slot::Sig),
+            0xb8 => Some(Slot::Dec),
+            0xa4 => Some(Slot::Aut),
+            _ => None,
+        }
+    }
+
+    fn attributes(self) -> &'static [u8] {
+        match self {
+            Slot::Dec => &ECDH_P256,
+            _ => &ECDSA_P256,
+        }
+    }
+
+    fn fingerprint_tag(self) -> u16 { 0xc7 + self as u16 }
+
+    fn ca_fingerprint_tag(self) -> u16 { 0xca + self as u16 }
+
+    fn time_tag(self) -> u16 { 0xce + self as u16 }
+
+    fn name(self) -> &'static str {
+        match self {
+            Slot::Sig => t!("vault.openpgp.key_sig", locales::LANG),
+            Slot::Dec => t!("vault.openpgp.key_dec", locales::LANG),
+            Slot::Aut => t!("vault.openpgp.key_aut", locales::LANG),
+        }
+    }
+}
+
+/// A freshly set up card, without keys
+pub(crate) fn new_record(rng: &mut impl Rng256, pw1: &str, pw3: &str) -> OpenPgpCardRecord {
+    OpenPgpCardRecord {
+        version: VAULT_OPENPGP_REC_VERSION,
+        serial: u32::from_be_bytes(rng.gen_uniform_u8x32()[..4].try_into().unwrap()),
+        pw1_hash: hash_pin(pw1.as_bytes()).to_vec(),
+        pw1_len: pw1.len() as u8,
+        pw1_retries: PIN_RETRIES,
+        pw3_hash: hash_pin(pw3.as_bytes()).to_vec(),
+        pw3_len: pw3.len() as u8,
+        pw3_retries: PIN_RETRIES,
+        ..Default::default()
+    }
+}
+
+/// The card application. It answers one command APDU at a time; saving what changed is up to the
+/// caller.
+pub(crate) struct Card<R: Rng256> {
+    record: OpenPgpCardRecord,
+    rng: R,
+    /// PW1 verified for signatures (mode 81)
+    pw1_signing: bool,
+    /// PW1 verified for everything else (mode 82)
+    pw1_other: bool,
+    pw3: bool,
+    /// the data of chained commands, until the last one comes in
+    chain: Vec<u8>,
+    changed: bool,
+}
+
+impl<R: Rng256> Card<R> {
+    pub(crate) fn new(record: OpenPgpCardRecord, rng: R) -> Self {
+        Card { record, rng, pw1_signing: false, pw1_other: false, pw3: false, chain: vec![], changed: false }
+    }
+
+    /// The reader powered the card down, or was reset: PINs have to be verified again.
+    pub(crate) fn reset(&mut self) {
+        self.pw1_signing = false;
+        self.pw1_other = false;
+        self.pw3 = false;
+        self.chain.clear();
+    }
+
+    /// The record, if it changed since the last call
+    pub(crate) fn take_changes(&mut self) -> Option<&mut OpenPgpCardRecord> {
+        if std::mem::take(&mut self.changed) { Some(&mut self.record) } else { None }
+    }
+
+    /// Answers a command APDU. `confirm` is asked, with what is about to happen, before a private key
+    /// is used or generated.
+    pub(crate) fn process(&mut self, apdu: &[u8], confirm: &mut dyn FnMut(&str) -> bool) -> Vec<u8> {
+        let result = match parse(apdu) {
+            None => Err(SW_WRONG_LENGTH),
+            Some(command) if command.cla & !CLA_CHAINING != 0 => Err(SW_CLA_NOT_SUPPORTED),
+            Some(command) if command.cla & CLA_CHAINING != 0 => {
+                if self.chain.len() + command.data.len() > MAX_CHAIN_LEN {
+                    self.chain.clear();
+                    Err(SW_WRONG_LENGTH)
+                } else {
+                    self.chain.extend_from_slice(command.data);
+                    Ok(vec![])
+                }
+            }
+            Some(command) => {
+                let mut data = std::mem::take(&mut self.chain);
+                data.extend_from_slice(command.data);
+                self.dispatch(command.ins, command.p1, command.p2, &data, confirm)
+            }
+        };
+        let (mut response, sw) = match result {
+            Ok(data) => (data, SW_OK),
+            Err(sw) => (vec![], sw),
+        };
+        response.extend_from_slice(&sw.to_be_bytes());
+        response
+    }
+
+    fn dispatch(
+        &mut self,
+        ins: u8,
+        p1: u8,
+        p2: u8,
+        data: &[u8],
+        confirm: &mut dyn FnMut(&str) -> bool,
+    ) -> Result<Vec<u8>, u16> {
+        let tag = u16::from_be_bytes([p1, p2]);
+        match ins {
+            INS_SELECT => self.select(p1, data),
+            INS_GET_DATA => self.get_data(tag),
+            INS_PUT_DATA => self.put_data(tag, data),
+            INS_VERIFY => self.verify(p1, p2, data),
+            INS_CHANGE_REFERENCE_DATA => self.change_reference_data(p1, p2, data),
+            INS_RESET_RETRY_COUNTER => self.reset_retry_counter(p1, p2, data),
+            INS_GENERATE_ASYMMETRIC_KEY_PAIR => self.key_pair(p1, p2, data, confirm),
+            INS_PERFORM_SECURITY_OPERATION => match tag {
+                0x9e9a => self.sign(data, confirm),
+                0x8086 => self.decipher(data, confirm),
+                _ => Err(SW_WRONG_P1P2),
+            },
+            INS_INTERNAL_AUTHENTICATE if tag == 0 => self.authenticate(data, confirm),
+            INS_INTERNAL_AUTHENTICATE => Err(SW_WRONG_P1P2),
+            _ => Err(SW_INS_NOT_SUPPORTED),
+        }
+    }
+
+    fn aid(&self) -> Vec<u8> {
+        let mut aid = AID_PREFIX.to_vec();
+        aid.extend_from_slice(&MANUFACTURER);
+        aid.extend_from_slice(&self.record.serial.to_be_bytes());
+        aid.extend_from_slice(&[0, 0]);
+        aid
+    }
+
+    fn select(&mut self, p1: u8, name: &[u8]) -> Result<Vec<u8>, u16> {
+        // by name, which hosts usually truncate to the registered part
+        if p1 == 0x04 && name.len() >= 6 && self.aid().starts_with(name) {
+            Ok(vec![])
+        } else {
+            Err(SW_NOT_FOUND)
+        }
+    }
+
+    fn get_data(&self, tag: u16) -> Result<Vec<u8>, u16> {
+        let value = match tag {
+            0x4f => self.aid(),
+            0x5f52 => HISTORICAL_BYTES.to_vec(),
+            0x5b | 0x5e | 0x5f2d | 0x5f35 | 0x5f50 => {
+                self.record.objects.get(&tag).cloned().unwrap_or_default()
+            }
+            // cardholder related data
+            0x65 => self.constructed(&[0x5b, 0x5f2d, 0x5f35])?,
+            // application related data
+            0x6e => {
+                let mut value = self.constructed(&[0x4f, 0x5f52])?;
+                let discretionary =
+                    self.constructed(&[0xc0, 0xc1, 0xc2, 0xc3, 0xc4, 0xc5, 0xc6, 0xcd, 0xde])?;
+                value.extend(tlv(0x73, &discretionary));
+                value
+            }
+            // security support template, with the digital signature counter
+            0x7a => tlv(0x93, &self.record.signature_count.to_be_bytes()[1..]),
+            // there's no room for a cardholder certificate
+            0x7f21 => vec![],
+            0xc0 => EXTENDED_CAPABILITIES.to_vec(),
+            0xc1..=0xc3 => Slot::ALL[(tag - 0xc1) as usize].attributes().to_vec(),
+            0xc4 => self.pw_status(),
+            0xc5 => self.concat(Slot::fingerprint_tag, 20),
+            0xc6 => self.concat(Slot::ca_fingerprint_tag, 20),
+            0xcd => self.concat(Slot::time_tag, 4),
+            // key information: generated on the card, or not there
+            0xde => Slot::ALL
+                .iter()
+                .flat_map(|&slot| vec![slot as u8 + 1, self.record.keys[slot as usize].is_some() as u8])
+                .collect(),
+            _ => return Err(SW_DATA_NOT_FOUND),
+        };
+        Ok(value)
+    }
+
+    fn constructed(&self, tags: &[u16]) -> Result<Vec<u8>, u16> {
+        let mut value = vec![];
+        for &tag in tags {
+            value.extend(tlv(tag, &self.get_data(tag)?));
+        }
+        Ok(value)
+    }
+
+    /// The per key objects of all three keys, with zeroes for what is missing
+    fn concat(&self, tag: fn(Slot) -> u16, len: usize) -> Vec<u8> {
+        Slot::ALL
+            .iter()
+            .flat_map(|&slot| self.record.objects.get(&tag(slot)).cloned().unwrap_or_else(|| vec![0; len]))
+            .collect()
+    }
+
+    fn pw_status(&self) -> Vec<u8> {
+        let max_len = MAX_PIN_LEN as u8;
+        // there's no resetting code
+        vec![
+            self.record.pw1_multiple as u8,
+            max_len,
+            0,
+            max_len,
+            self.record.pw1_retries,
+            0,
+            self.record.pw3_retries,
+        ]
+    }
+
+    fn put_data(&mut self, tag: u16, data: &[u8]) -> Result<Vec<u8>, u16> {
+        if !self.pw3 {
+            return Err(SW_SECURITY_STATUS);
+        }
+        let max_len = match tag {
+            0x5b => 39,
+            0x5f2d => 8,
+            0x5f35 => 1,
+            0x5e | 0x5f50 => MAX_OBJECT_LEN,
+            0xc7..=0xcc => 20,
+            0xce..=0xd0 => 4,
+            // of the PW status bytes, only whether PW1 is good for several signatures can be changed
+            0xc4 => match data.first() {
+                Some(&multiple) if multiple <= 1 => {
+                    self.record.pw1_multiple = multiple == 1;
+                    self.changed = true;
+                    return Ok(vec![]);
+                }
+                _ => return Err(SW_WRONG_DATA),
+            },
+            // the keys are always P-256, but hosts may write back what they read
+            0xc1..=0xc3 if data == Slot::ALL[(tag - 0xc1) as usize].attributes() => return Ok(vec![]),
+            0xc1..=0xc3 => return Err(SW_WRONG_DATA),
+            _ => return Err(SW_DATA_NOT_FOUND),
+        };
+        if data.len() > max_len {
+            return Err(SW_WRONG_LENGTH);
+        }
+        if data.is_empty() {
+            self.record.objects.remove(&tag);
+        } else {
+            self.record.objects.insert(tag, data.to_vec());
+        }
+        self.changed = true;
+        Ok(vec![])
+    }
+
+    fn verify(&mut self, p1: u8, p2: u8, pin: &[u8]) -> Result<Vec<u8>, u16> {
+        let verified = match p2 {
+            0x81 => self.pw1_signing,
+            0x82 => self.pw1_other,
+            0x83 => self.pw3,
+            _ => return Err(SW_WRONG_P1P2),
+        };
+        match (p1, pin.is_empty()) {
+            (0x00, true) if verified => Ok(vec![]),
+            (0x00, true) => {
+                let retries = if p2 == 0x83 { self.record.pw3_retries } else { self.record.pw1_retries };
+                Err(SW_PIN_WRONG | retries as u16)
+            }
+            (0x00, false) => {
+                self.check_pin(p2 == 0x83, pin)?;
+                self.set_verified(p2, true);
+                Ok(vec![])
+            }
+            (0xff, true) => {
+                self.set_verified(p2, false);
+                Ok(vec![])
+            }
+            _ => Err(SW_WRONG_P1P2),
+        }
+    }
+
+    fn set_verified(&mut self, p2: u8, verified: bool) {
+        match p2 {
+            0x81 => self.pw1_signing = verified,
+            0x82 => self.pw1_other = verified,
+            _ => self.pw3 = verified,
+        }
+    }
+
+    /// Checks a PIN the way `ClientPin` checks FIDO PINs: a retry is used up before comparing the
+    /// hashes, and only given back by a match.
+    fn check_pin(&mut self, admin: bool, pin: &[u8]) -> Result<(), u16> {
+        let record = &mut self.record;
+        let (hash, retries) = if admin {
+            (&record.pw3_hash, &mut record.pw3_retries)
+        } else {
+            (&record.pw1_hash, &mut record.pw1_retries)
+        };
+        if *retries == 0 {
+            return Err(SW_PIN_BLOCKED);
+        }
+        *retries -= 1;
+        self.changed = true;
+        if !bool::from(hash_pin(pin)[..].ct_eq(&hash[..])) {
+            return Err(SW_PIN_WRONG | *retries as u16);
+        }
+        *retries = PIN_RETRIES;
+        Ok(())
+    }
+
+    fn set_pin(&mut self, admin: bool, pin: &[u8]) -> Result<(), u16> {
+        let min_len = if admin { MIN_PW3_LEN } else { MIN_PW1_LEN };
+        if pin.len() < min_len || pin.len() > MAX_PIN_LEN {
+            return Err(SW_WRONG_DATA);
+        }
+        let record = &mut self.record;
+        if admin {
+            record.pw3_hash = hash_pin(pin).to_vec();
+            record.pw3_len = pin.len() as u8;
+            record.pw3_retries = PIN_RETRIES;
+        } else {
+            record.pw1_hash = hash_pin(pin).to_vec();
+            record.pw1_len = pin.len() as u8;
+            record.pw1_retries = PIN_RETRIES;
+        }
+        self.changed = true;
+        Ok(())
+    }
+
+    /// The old PIN followed by the new one; they are told apart by the length of the old one.
+    fn change_reference_data(&mut self, p1: u8, p2: u8, data: &[u8]) -> Result<Vec<u8>, u16> {
+        let admin = match (p1, p2) {
+            (0x00, 0x81) => false,
+            (0x00, 0x83) => true,
+            _ => return Err(SW_WRONG_P1P2),
+        };
+        let old_len = if admin { self.record.pw3_len } else { self.record.pw1_len } as usize;
+        // too short to hold the old PIN counts as a wrong PIN, so the length isn't given away
+        let (old, new) = data.split_at(old_len.min(data.len()));
+        self.check_pin(admin, old)?;
+        self.set_pin(admin, new)?;
+        Ok(vec![])
+    }
+
+    fn reset_retry_counter(&mut self, p1: u8, p2: u8, pin: &[u8]) -> Result<Vec<u8>, u16> {
+        match (p1, p2) {
+            (0x02, 0x81) if self.pw3 => {
+                self.set_pin(false, pin)?;
+                Ok(vec![])
+            }
+            (0x02, 0x81) => Err(SW_SECURITY_STATUS),
+            // with a resetting code, which this card doesn't have
+            (0x00, 0x81) => Err(SW_PIN_BLOCKED),
+            _ => Err(SW_WRONG_P1P2),
+        }
+    }
+
+    fn key(&self, slot: Slot) -> Result<ecdsa::SecKey, u16> {
+        self.record.keys[slot as usize]
+            .as_ref()
+            .and_then(|secret| ecdsa::SecKey::from_bytes(secret.as_slice().try_into().ok()?))
+            .ok_or(SW_DATA_NOT_FOUND)
+    }
+
+    /// Generates a key (P1 80), or reads its public key (P1 81).
+    fn key_pair(
+        &mut self,
+        p1: u8,
+        p2: u8,
+        crt: &[u8],
+        confirm: &mut dyn FnMut(&str) -> bool,
+    ) -> Result<Vec<u8>, u16> {
+        if p2 != 0 {
+            return Err(SW_WRONG_P1P2);
+        }
+        let slot = crt.first().and_then(|&tag| Slot::from_crt(tag)).ok_or(SW_WRONG_DATA)?;
+        match p1 {
+            0x80 if !self.pw3 => return Err(SW_SECURITY_STATUS),
+            0x80 => {
+                if !confirm(&format!(
+                    "{}\n{}",
+                    t!("vault.openpgp.approve_generate", locales::LANG),
+                    slot.name()
+                )) {
+                    return Err(SW_CONDITIONS);
+                }
+                let mut secret = [0u8; 32];
+                ecdsa::SecKey::gensk(&mut self.rng).to_bytes(&mut secret);
+                self.record.keys[slot as usize] = Some(secret.to_vec());
+                // the host sets them for the new key
+                self.record.objects.remove(&slot.fingerprint_tag());
+                self.record.objects.remove(&slot.time_tag());
+                if slot == Slot::Sig {
+                    self.record.signature_count = 0;
+                }
+                self.changed = true;
+            }
+            0x81 => {}
+            _ => return Err(SW_WRONG_P1P2),
+        }
+        let (mut x, mut y) = ([0u8; 32], [0u8; 32]);
+        self.key(slot)?.genpk().to_coordinates(&mut x, &mut y);
+        let mut point = vec![0x04];
+        point.extend_from_slice(&x);
+        point.extend_from_slice(&y);
+        Ok(tlv(0x7f49, &tlv(0x86, &point)))
+    }
+
+    /// PSO: COMPUTE DIGITAL SIGNATURE, over a digest made by the host
+    fn sign(&mut self, digest: &[u8], confirm: &mut dyn FnMut(&str) -> bool) -> Result<Vec<u8>, u16> {
+        if !self.pw1_signing {
+            return Err(SW_SECURITY_STATUS);
+        }
+        let signature = self.key(Slot::Sig)?.sign_prehash(digest).ok_or(SW_WRONG_DATA)?;
+        if !confirm(t!("vault.openpgp.approve_sign", locales::LANG)) {
+            return Err(SW_CONDITIONS);
+        }
+        if !self.record.pw1_multiple {
+            self.pw1_signing = false;
+        }
+        // the counter is three bytes long, and stops at the top
+        self.record.signature_count = (self.record.signature_count + 1).min(0xff_ffff);
+        self.changed = true;
+        let mut rs = [0u8; 64];
+        signature.to_bytes(&mut rs);
+        Ok(rs.to_vec())
+    }
+
+    /// PSO: DECIPHER, which for ECDH is the key agreement with the ephemeral key of the sender
+    fn decipher(&mut self, data: &[u8], confirm: &mut dyn FnMut(&str) -> bool) -> Result<Vec<u8>, u16> {
+        if !self.pw1_other {
+            return Err(SW_SECURITY_STATUS);
+        }
+        let secret: [u8; 32] = self.record.keys[Slot::Dec as usize]
+            .as_ref()
+            .and_then(|secret| secret.as_slice().try_into().ok())
+            .ok_or(SW_DATA_NOT_FOUND)?;
+        // A6 { 7F49 { 86 <the public key of the sender> } }
+        let point = find_tlv(data, 0xa6)
+            .and_then(|cipher| find_tlv(cipher, 0x7f49))
+            .and_then(|key| find_tlv(key, 0x86))
+            .ok_or(SW_WRONG_DATA)?;
+        let shared = ecdh::exchange_x_static(&secret, point).ok_or(SW_WRONG_DATA)?;
+        if !confirm(t!("vault.openpgp.approve_decipher", locales::LANG)) {
+            return Err(SW_CONDITIONS);
+        }
+        Ok(shared.to_vec())
+    }
+
+    /// INTERNAL AUTHENTICATE, a signature with the authentication key, e.g. for SSH
+    fn authenticate(&mut self, digest: &[u8], confirm: &mut dyn FnMut(&str) -> bool) -> Result<Vec<u8>, u16> {
+        if !self.pw1_other {
+            return Err(SW_SECURITY_STATUS);
+        }
+        let signature = self.key(Slot::Aut)?.sign_prehash(digest).ok_or(SW_WRONG_DATA)?;
+        if !confirm(t!("vault.openpgp.approve_authenticate", locales::LANG)) {
+            return Err(SW_CONDITIONS);
+        }
+        let mut rs = [0u8; 64];
+        signature.to_bytes(&mut rs);
+        Ok(rs.to_vec())
+    }
+}
+
+struct Command<'a> {
+    cla: u8,
+    ins: u8,
+    p1: u8,
+    p2: u8,
+    data: &'a [u8],
+}
+
+/// Splits a command APDU of ISO 7816-4 cases 1 to 4; only short lengths are supported.
+fn parse(apdu: &[u8]) -> Option<Command> {
+    let header = apdu.get(..4)?;
+    let body = &apdu[4..];
+    let data = match body.len() {
+        // no data, maybe Le
+        0 | 1 => &body[..0],
+        len => {
+            let lc = body[0] as usize;
+            if lc == 0 || (len != 1 + lc && len != 2 + lc) {
+                return None;
+            }
+            &body[1..1 + lc]
+        }
+    };
+    Some(Command { cla: header[0], ins: header[1], p1: header[2], p2: header[3], data })
+}
+
+fn tlv(tag: u16, value: &[u8]) -> Vec<u8> {
+    let mut encoded = if tag > 0xff { tag.to_be_bytes().to_vec() } else { vec![tag as u8] };
+    match value.len() {
+        len if len < 0x80 => encoded.push(len as u8),
+        len if len <= 0xff => encoded.extend_from_slice(&[0x81, len as u8]),
+        len => encoded.extend_from_slice(&[0x82, (len >> 8) as u8, len as u8]),
+    }
+    encoded.extend_from_slice(value);
+    encoded
+}
+
+/// The value of the first `tag` among the BER-TLV encoded objects in `data`
+fn find_tlv(mut data: &[u8], tag: u16) -> Option<&[u8]> {
+    while !data.is_empty() {
+        let tag_len = if data[0] & 0x1f == 0x1f { 2 } else { 1 };
+        let this_tag = data.get(..tag_len)?.iter().fold(0u16, |t, &b| t << 8 | b as u16);
+        let (len, len_len) = match *data.get(tag_len)? {
+            len if len < 0x80 => (len as usize, 1),
+            0x81 => (*data.get(tag_len + 1)? as usize, 2),
+            0x82 => (u16::from_be_bytes(data.get(tag_len + 1..tag_len + 3)?.try_into().ok()?) as usize, 3),
+            _ => return None,
+        };
+        let start = tag_len + len_len;
+        let value = data.get(start..start + len)?;
+        if this_tag == tag {
+            return Some(value);
+        }
+        data = &data[start + len..];
+    }
+    None
+}
+
+fn save(storage: &mut storage::Manager, record: &mut OpenPgpCardRecord) -> Result<(), storage::Error> {
+    if storage.exists(record) {
+        storage.update(&ContentKind::OpenPgpCard, &storage::hex(record.hash()), record)
+    } else {
+        storage.new_record(record, None, false)
+    }
+}
+
+/// Serves `card` through the USB smart card reader until the user stops it. Whatever changed on the
+/// card is saved before the host gets the response, so pulling the plug can't undo a used up PIN retry.
+pub(crate) fn serve<R: Rng256>(
+    modals: &modals::Modals,
+    storage: &mut storage::Manager,
+    card: &mut Card<R>,
+    lefty_mode: &AtomicBool,
+) -> Result<(), xous::Error> {
+    let usb = UsbHid::new();
+    let previous_core = usb.get_current_core()?;
+    usb.switch_to_core(UsbDeviceType::Ccid)?;
+
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let usb = UsbHid::new();
+        loop {
+            // the reader turns the wait down once it's switched away from
+            let apdu = usb.ccid_wait_apdu();
+            let denied = apdu == Err(xous::Error::AccessDenied);
+            if tx.send(apdu).is_err() || denied {
+                break;
+            }
+        }
+    });
+
+    let result = run(modals, storage, card, lefty_mode, &usb, &rx);
+    modals.dynamic_notification_close().ok();
+    usb.switch_to_core(previous_core)?;
+    result
+}
+
+fn run<R: Rng256>(
+    modals: &modals::Modals,
+    storage: &mut storage::Manager,
+    card: &mut Card<R>,
+    lefty_mode: &AtomicBool,
+    usb: &UsbHid,
+    rx: &mpsc::Receiver<Result<Vec<u8>, xous::Error>>,
+) -> Result<(), xous::Error> {
+    let mut stop = show_running(modals);
+    while !stop.load(Ordering::SeqCst) {
+        let apdu = match rx.recv_timeout(Duration::from_millis(POLL_MS)) {
+            Ok(Ok(apdu)) => apdu,
+            Ok(Err(xous::Error::ProcessTerminated)) => {
+                card.reset();
+                continue;
+            }
+            Ok(Err(e)) => return Err(e),
+            Err(mpsc::RecvTimeoutError::Timeout) => continue,
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        };
+        let mut response = card.process(&apdu, &mut |query| {
+            modals.dynamic_notification_close().ok();
+            let approved = wait_for_user_presence(
+                modals,
+                lefty_mode,
+                t!("vault.openpgp.request", locales::LANG),
+                t!("vault.openpgp.request_lefty", locales::LANG),
+                query,
+                TOUCH_TIMEOUT,
+                || Ok(()),
+            )
+            .is_ok();
+            stop = show_running(modals);
+            approved
+        });
+        if let Some(record) = card.take_changes() {
+            if let Err(e) = save(storage, record) {
+                log::error!("couldn't save the OpenPGP card: {:?}", e);
+                response = SW_MEMORY_FAILURE.to_be_bytes().to_vec();
+            }
+        }
+        usb.ccid_send_response(&response)?;
+    }
+    Ok(())
+}
+
+/// Puts up the notice that the card is running; the flag is set once a key is hit to stop it.
+fn show_running(modals: &modals::Modals) -> Arc<AtomicBool> {
+    modals
+        .dynamic_notification(
+            Some(t!("vault.openpgp.running", locales::LANG)),
+            Some(t!("vault.openpgp.running_stop", locales::LANG)),
+        )
+        .ok();
+    let stop = Arc::new(AtomicBool::new(false));
+    thread::spawn({
+        let token = modals.token();
+        let conn = modals.conn();
+        let stop = stop.clone();
+        move || {
+            // closing the notice to ask for an approval also ends up here, with no key
+            if let Ok(Some(_)) = modals::dynamic_notification_blocking_listener(token, conn) {
+                stop.store(true, Ordering::SeqCst);
+            }
+        }
+    });
+    stop
+}
+
+#[cfg(test)]
+mod tests {
+    use ctap_crypto::rng256::ThreadRng256;
+
+    use super::*;
+
+    const PW1: &str = "123456";
+    const PW3: &str = "12345678";
+
+    fn card() -> Card<ThreadRng256> {
+        let mut rng = ThreadRng256 {};
+        Card::new(new_record(&mut rng, PW1, PW3), rng)
+    }
+
+    fn apdu(ins: u8, p1: u8, p2: u8, data: &[u8]) -> Vec<u8> {
+        let mut apdu = vec![0x00, ins, p1, p2];
+        if !data.is_empty() {
+            apdu.push(data.len() as u8);
+            apdu.extend_from_slice(data);
+        }
+        apdu.push(0x00);
+        apdu
+    }
+
+    fn sw(response: &[u8]) -> u16 { u16::from_be_bytes(response[response.len() - 2..].try_into().unwrap()) }
+
+    fn send(card: &mut Card<ThreadRng256>, ins: u8, p1: u8, p2: u8, data: &[u8]) -> Vec<u8> {
+        card.process(&apdu(ins, p1, p2, data), &mut |_| true)
+    }
+
+    #[test]
+    fn test_parse() {
+        let command = parse(&[0x00, 0xca, 0x00, 0x6e, 0x00]).unwrap();
+        assert_eq!((command.ins, command.p1, command.p2, command.data), (0xca, 0x00, 0x6e, &[][..]));
+        let command = parse(&[0x00, 0x20, 0x00, 0x81, 0x02, 0x31, 0x32]).unwrap();
+        assert_eq!(command.data, b"12");
+        assert_eq!(parse(&[0x00, 0x20, 0x00, 0x81, 0x02, 0x31, 0x32, 0x00]).unwrap().data, b"12");
+        assert!(parse(&[0x00, 0x20, 0x00, 0x81, 0x03, 0x31]).is_none());
+        assert!(parse(&[0x00, 0x20, 0x00]).is_none());
+    }
+
+    #[test]
+    fn test_select_and_get_data() {
+        let mut card = card();
+        assert_eq!(sw(&send(&mut card, INS_SELECT, 0x04, 0x00, &AID_PREFIX[..6])), SW_OK);
+        assert_eq!(
+            sw(&send(&mut card, INS_SELECT, 0x04, 0x00, &[0xa0, 0x00, 0x00, 0x03, 0x08, 0x00])),
+            SW_NOT_FOUND
+        );
+
+        let response = send(&mut card, INS_GET_DATA, 0x00, 0x6e, &[]);
+        assert_eq!(sw(&response), SW_OK);
+        let data = &response[..response.len() - 2];
+        assert_eq!(find_tlv(data, 0x4f), Some(&card.aid()[..]));
+        let discretionary = find_tlv(data, 0x73).unwrap();
+        assert_eq!(find_tlv(discretionary, 0xc1), Some(&ECDSA_P256[..]));
+        assert_eq!(find_tlv(discretionary, 0xc4), Some(&[0, 64, 0, 64, 3, 0, 3][..]));
+        assert_eq!(find_tlv(discretionary, 0xde), Some(&[1, 0, 2, 0, 3, 0][..]));
+        assert_eq!(sw(&send(&mut card, INS_GET_DATA, 0x01, 0x01, &[])), SW_DATA_NOT_FOUND);
+    }
+
+    #[test]
+    fn test_verify_retries() {
+        let mut card = card();
+        assert_eq!(sw(&send(&mut card, INS_VERIFY, 0x00, 0x82, &[])), SW_PIN_WRONG | 3);
+        assert_eq!(sw(&send(&mut card, INS_VERIFY, 0x00, 0x82, b"654321")), SW_PIN_WRONG | 2);
+        assert!(card.take_changes().is_some());
+        assert_eq!(sw(&send(&mut card, INS_VERIFY, 0x00, 0x82, PW1.as_bytes())), SW_OK);
+        assert_eq!(sw(&send(&mut card, INS_VERIFY, 0x00, 0x82, &[])), SW_OK);
+        assert_eq!(card.record.pw1_retries, PIN_RETRIES);
+
+        for retries in (0..3).rev() {
+            assert_eq!(sw(&send(&mut card, INS_VERIFY, 0x00, 0x83, b"87654321")), SW_PIN_WRONG | retries);
+        }
+        assert_eq!(sw(&send(&mut card, INS_VERIFY, 0x00, 0x83, PW3.as_bytes())), SW_PIN_BLOCKED);
+        assert_eq!(sw(&send(&mut card, INS_PUT_DATA, 0x00, 0x5b, b"Alice")), SW_SECURITY_STATUS);
+    }
+
+    #[test]
+    fn test_change_pin() {
+        let mut card = card();
+        let mut data = PW1.as_bytes().to_vec();
+        data.extend_from_slice(b"abc");
+        assert_eq!(sw(&send(&mut card, INS_CHANGE_REFERENCE_DATA, 0x00, 0x81, &data)), SW_WRONG_DATA);
+        data.extend_from_slice(b"def");
+        assert_eq!(sw(&send(&mut card, INS_CHANGE_REFERENCE_DATA, 0x00, 0x81, &data)), SW_OK);
+        assert_eq!(sw(&send(&mut card, INS_VERIFY, 0x00, 0x81, b"abcdef")), SW_OK);
+        assert_eq!(sw(&send(&mut card, INS_CHANGE_REFERENCE_DATA, 0x00, 0x81, b"abc")), SW_PIN_WRONG | 2);
+    }
+
+    #[test]
+    fn test_generate_sign_and_decipher() {
+        let mut card = card();
+        let crt = [0xb6, 0x00];
+        assert_eq!(
+            sw(&send(&mut card, INS_GENERATE_ASYMMETRIC_KEY_PAIR, 0x80, 0x00, &crt)),
+            SW_SECURITY_STATUS
+        );
+        assert_eq!(sw(&send(&mut card, INS_VERIFY, 0x00, 0x83, PW3.as_bytes())), SW_OK);
+        let declined =
+            card.process(&apdu(INS_GENERATE_ASYMMETRIC_KEY_PAIR, 0x80, 0x00, &crt), &mut |_| false);
+        assert_eq!(sw(&declined), SW_CONDITIONS);
+        let generated = send(&mut card, INS_GENERATE_ASYMMETRIC_KEY_PAIR, 0x80, 0x00, &crt);
+        assert_eq!(sw(&generated), SW_OK);
+        assert_eq!(send(&mut card, INS_GENERATE_ASYMMETRIC_KEY_PAIR, 0x81, 0x00, &crt), generated);
+        let point = find_tlv(find_tlv(&generated, 0x7f49).unwrap(), 0x86).unwrap();
+        assert_eq!(point.len(), 65);
+
+        let digest = [0x5a; 32];
+        assert_eq!(
+            sw(&send(&mut card, INS_PERFORM_SECURITY_OPERATION, 0x9e, 0x9a, &digest)),
+            SW_SECURITY_STATUS
+        );
+        assert_eq!(sw(&send(&mut card, INS_VERIFY, 0x00, 0x81, PW1.as_bytes())), SW_OK);
+        let signature = send(&mut card, INS_PERFORM_SECURITY_OPERATION, 0x9e, 0x9a, &digest);
+        assert_eq!((signature.len(), sw(&signature)), (66, SW_OK));
+        assert_eq!(card.record.signature_count, 1);
+        // PW1 is only good for one signature
+        assert_eq!(
+            sw(&send(&mut card, INS_PERFORM_SECURITY_OPERATION, 0x9e, 0x9a, &digest)),
+            SW_SECURITY_STATUS
+        );
+
+        send(&mut card, INS_GENERATE_ASYMMETRIC_KEY_PAIR, 0x80, 0x00, &[0xb8, 0x00]);
+        assert_eq!(sw(&send(&mut card, INS_VERIFY, 0x00, 0x82, PW1.as_bytes())), SW_OK);
+        let ephemeral = ecdsa::SecKey::gensk(&mut ThreadRng256 {});
+        let (mut x, mut y) = ([0u8; 32], [0u8; 32]);
+        ephemeral.genpk().to_coordinates(&mut x, &mut y);
+        let mut sender = vec![0x04];
+        sender.extend_from_slice(&x);
+        sender.extend_from_slice(&y);
+        let cipher = tlv(0xa6, &tlv(0x7f49, &tlv(0x86, &sender)));
+        // sent in two parts, with command chaining
+        let mut first = apdu(INS_PERFORM_SECURITY_OPERATION, 0x80, 0x86, &cipher[..40]);
+        first[0] = CLA_CHAINING;
+        assert_eq!(sw(&card.process(&first, &mut |_| true)), SW_OK);
+        let shared = send(&mut card, INS_PERFORM_SECURITY_OPERATION, 0x80, 0x86, &cipher[40..]);
+        assert_eq!(sw(&shared), SW_OK);
+
+        let dec_key: [u8; 32] = card.record.keys[Slot::Dec as usize].clone().unwrap().try_into().unwrap();
+        let mut ephemeral_secret = [0u8; 32];
+        ephemeral.to_bytes(&mut ephemeral_secret);
+        let dec_point = find_tlv(
+            find_tlv(&send(&mut card, INS_GENERATE_ASYMMETRIC_KEY_PAIR, 0x81, 0x00, &[0xb8, 0x00]), 0x7f49)
+                .unwrap(),
+            0x86,
+        )
+        .unwrap()
+        .to_vec();
+        assert_eq!(ecdh::exchange_x_static(&ephemeral_secret, &dec_point).unwrap()[..], shared[..32]);
+        assert_eq!(ecdh::exchange_x_static(&dec_key, &sender).unwrap()[..], shared[..32]);
+    }
+}
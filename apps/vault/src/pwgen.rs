@@ -0,0 +1,410 @@
+//! The password generator: named profiles, and the rules a site has for its passwords.
+//!
+//! A profile says what a password is made of, either characters from a choice of classes or a diceware
+//! style passphrase of words from the BIP-39 list. A few profiles are built in, and the user can add
+//! their own, stored in the PDDB. A site policy narrows down what a profile makes, so that the site
+//! takes it: the generator stretches or shortens the password to fit, adds the character classes the
+//! site asks for, and keeps to the symbols it accepts.
+//!
+//! Randomness comes straight from the TRNG, through `Rng256`, with rejection sampling so that every
+//! character and word is equally likely.
+
+use core::convert::TryFrom;
+use std::fmt;
+use std::io::ErrorKind;
+
+use ctap_crypto::rng256::Rng256;
+use gam::BIP39_TABLE;
+
+use crate::storage::{self, ContentKind, GeneratorProfileRecord, VAULT_PWGEN_PROFILE_REC_VERSION};
+
+/// The profile of records that don't name one, and what passwords were always made with before
+pub(crate) const DEFAULT_PROFILE: &str = "Default";
+pub(crate) const MAX_LENGTH: u32 = 128;
+pub(crate) const MIN_PASSPHRASE_WORDS: u32 = 2;
+pub(crate) const MAX_PASSPHRASE_WORDS: u32 = 16;
+/// A passphrase is only shortened this far to fit a site's maximum length; past that, a character
+/// password is made instead.
+const MIN_FITTED_WORDS: usize = 4;
+const SYMBOLS: &str = "!#$%&()*+,-./:;<=>?@[]^_{}~";
+const AMBIGUOUS: &str = "iIl1LoO0";
+
+pub(crate) fn builtin_profiles() -> Vec<GeneratorProfileRecord> {
+    let profile = |name: &str| GeneratorProfileRecord {
+        version: VAULT_PWGEN_PROFILE_REC_VERSION,
+        name: name.to_string(),
+        ..Default::default()
+    };
+    vec![
+        GeneratorProfileRecord {
+            length: 20,
+            lowercase: true,
+            uppercase: true,
+            numbers: true,
+            symbols: true,
+            exclude_ambiguous: true,
+            ..profile(DEFAULT_PROFILE)
+        },
+        GeneratorProfileRecord {
+            length: 6,
+            passphrase: true,
+            separator: "-".to_string(),
+            ..profile("Passphrase")
+        },
+        GeneratorProfileRecord { length: 6, numbers: true, ..profile("PIN") },
+    ]
+}
+
+pub(crate) fn is_builtin(name: &str) -> bool { builtin_profiles().iter().any(|p| p.name == name) }
+
+/// The built-in profiles, followed by the user's own in name order
+pub(crate) fn profiles(storage: &storage::Manager) -> Vec<GeneratorProfileRecord> {
+    let mut profiles = builtin_profiles();
+    match storage.all::<GeneratorProfileRecord>(ContentKind::GeneratorProfile) {
+        Ok(mut stored) => {
+            stored.retain(|p| !is_builtin(&p.name));
+            stored.sort_by(|a, b| a.name.cmp(&b.name));
+            profiles.extend(stored);
+        }
+        // the dictionary only exists once the first profile has been saved
+        Err(storage::Error::IoError(e)) if e.kind() == ErrorKind::NotFound => {}
+        Err(e) => log::error!("couldn't read generator profiles: {:?}", e),
+    }
+    profiles
+}
+
+/// The profile called `name` among `profiles`, or the default one if there's no such profile anymore
+pub(crate) fn find_profile<'a>(
+    profiles: &'a [GeneratorProfileRecord],
+    name: Option<&str>,
+) -> &'a GeneratorProfileRecord {
+    let default =
+        || profiles.iter().find(|p| p.name == DEFAULT_PROFILE).expect("default profile is built in");
+    match name {
+        Some(name) => profiles.iter().find(|p| p.name == name).unwrap_or_else(default),
+        None => default(),
+    }
+}
+
+/// What a site accepts in a password. Written as space separated rules, e.g.
+/// `min=8 max=16 require=upper,digit symbols=!#$`; an empty `symbols=` means no symbols at all.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct SitePolicy {
+    pub min_length: Option<u32>,
+    pub max_length: Option<u32>,
+    pub require_lowercase: bool,
+    pub require_uppercase: bool,
+    pub require_numbers: bool,
+    pub require_symbols: bool,
+    /// the only symbols the site takes, if it's picky about them
+    pub symbols: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum PolicyError {
+    UnknownRule,
+    BadNumber,
+    /// the rules can't all be met, like a minimum length over the maximum
+    Contradiction,
+}
+
+impl TryFrom<&str> for SitePolicy {
+    type Error = PolicyError;
+
+    fn try_from(data: &str) -> Result<Self, Self::Error> {
+        let mut policy = SitePolicy::default();
+        let number = |value: &str| match u32::from_str_radix(value, 10) {
+            Ok(n) if n > 0 && n <= MAX_LENGTH => Ok(n),
+            _ => Err(PolicyError::BadNumber),
+        };
+        for rule in data.split_whitespace() {
+            match rule.split_once('=').ok_or(PolicyError::UnknownRule)? {
+                ("min", value) => policy.min_length = Some(number(value)?),
+                ("max", value) => policy.max_length = Some(number(value)?),
+                ("require", classes) => {
+                    for class in classes.split(',').filter(|class| !class.is_empty()) {
+                        match class {
+                            "lower" => policy.require_lowercase = true,
+                            "upper" => policy.require_uppercase = true,
+                            "digit" => policy.require_numbers = true,
+                            "symbol" => policy.require_symbols = true,
+                            _ => return Err(PolicyError::UnknownRule),
+                        }
+                    }
+                }
+                ("symbols", symbols) => {
+                    if symbols.chars().any(|c| c.is_alphanumeric() || !c.is_ascii_graphic()) {
+                        return Err(PolicyError::UnknownRule);
+                    }
+                    policy.symbols = Some(symbols.to_string());
+                }
+                _ => return Err(PolicyError::UnknownRule),
+            }
+        }
+        let classes = [
+            policy.require_lowercase,
+            policy.require_uppercase,
+            policy.require_numbers,
+            policy.require_symbols,
+        ]
+        .iter()
+        .filter(|&&required| required)
+        .count() as u32;
+        let too_short =
+            policy.max_length.map_or(false, |max| max < policy.min_length.unwrap_or(0) || max < classes);
+        let no_symbols = policy.require_symbols && policy.symbols.as_deref() == Some("");
+        if too_short || no_symbols {
+            return Err(PolicyError::Contradiction);
+        }
+        Ok(policy)
+    }
+}
+
+impl fmt::Display for SitePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut rules = vec![];
+        if let Some(min) = self.min_length {
+            rules.push(format!("min={}", min));
+        }
+        if let Some(max) = self.max_length {
+            rules.push(format!("max={}", max));
+        }
+        let required: Vec<&str> = [
+            (self.require_lowercase, "lower"),
+            (self.require_uppercase, "upper"),
+            (self.require_numbers, "digit"),
+            (self.require_symbols, "symbol"),
+        ]
+        .iter()
+        .filter(|(required, _)| *required)
+        .map(|(_, class)| *class)
+        .collect();
+        if !required.is_empty() {
+            rules.push(format!("require={}", required.join(",")));
+        }
+        if let Some(symbols) = &self.symbols {
+            rules.push(format!("symbols={}", symbols));
+        }
+        write!(f, "{}", rules.join(" "))
+    }
+}
+
+/// A new password made with `profile`, within the rules of `policy`
+pub(crate) fn generate(
+    profile: &GeneratorProfileRecord,
+    policy: Option<&SitePolicy>,
+    rng: &mut impl Rng256,
+) -> String {
+    let policy = policy.cloned().unwrap_or_default();
+    if profile.passphrase {
+        if let Some(passphrase) = passphrase(profile, &policy, rng) {
+            return passphrase;
+        }
+        // the site doesn't take passphrases long enough to be worth it
+        let fallback = GeneratorProfileRecord {
+            length: policy.max_length.unwrap_or(MAX_LENGTH),
+            lowercase: true,
+            uppercase: true,
+            numbers: true,
+            exclude_ambiguous: true,
+            ..Default::default()
+        };
+        return characters(&fallback, &policy, rng);
+    }
+    characters(profile, &policy, rng)
+}
+
+fn characters(profile: &GeneratorProfileRecord, policy: &SitePolicy, rng: &mut impl Rng256) -> String {
+    let symbols = policy.symbols.as_deref().unwrap_or(SYMBOLS);
+    let mut classes: Vec<Vec<char>> = vec![];
+    for (wanted, chars) in [
+        (profile.lowercase || policy.require_lowercase, ('a'..='z').collect::<Vec<char>>()),
+        (profile.uppercase || policy.require_uppercase, ('A'..='Z').collect()),
+        (profile.numbers || policy.require_numbers, ('0'..='9').collect()),
+        (profile.symbols || policy.require_symbols, symbols.chars().collect()),
+    ] {
+        let chars: Vec<char> =
+            chars.into_iter().filter(|&c| !(profile.exclude_ambiguous && AMBIGUOUS.contains(c))).collect();
+        if wanted && !chars.is_empty() {
+            classes.push(chars);
+        }
+    }
+    if classes.is_empty() {
+        // only symbols were asked for, and the site takes none
+        classes.push(('a'..='z').collect());
+    }
+
+    let mut length = profile.length as usize;
+    if let Some(min) = policy.min_length {
+        length = length.max(min as usize);
+    }
+    if let Some(max) = policy.max_length {
+        length = length.min(max as usize);
+    }
+    let length = length.max(classes.len());
+
+    // one of every class, so none is missing, then anything
+    let mut password: Vec<char> = classes.iter().map(|class| class[uniform(rng, class.len())]).collect();
+    let all: Vec<char> = classes.concat();
+    while password.len() < length {
+        password.push(all[uniform(rng, all.len())]);
+    }
+    for i in (1..password.len()).rev() {
+        password.swap(i, uniform(rng, i + 1));
+    }
+    password.into_iter().collect()
+}
+
+/// `None` if no passphrase of at least `MIN_FITTED_WORDS` words fits in the site's maximum length
+fn passphrase(
+    profile: &GeneratorProfileRecord,
+    policy: &SitePolicy,
+    rng: &mut impl Rng256,
+) -> Option<String> {
+    let capitalize = profile.uppercase || policy.require_uppercase;
+    let digit = if profile.numbers || policy.require_numbers {
+        Some((b'0' + uniform(rng, 10) as u8) as char)
+    } else {
+        None
+    };
+    let separator = separator(profile, policy);
+    let join = |words: &[String]| {
+        let mut passphrase = words.join(&separator);
+        passphrase.extend(digit);
+        passphrase
+    };
+
+    let mut words: Vec<String> =
+        (0..profile.length.max(MIN_PASSPHRASE_WORDS)).map(|_| word(rng, capitalize)).collect();
+    if let Some(min) = policy.min_length {
+        while join(&words).len() < min as usize {
+            words.push(word(rng, capitalize));
+        }
+    }
+    if let Some(max) = policy.max_length {
+        while join(&words).len() > max as usize && words.len() > MIN_FITTED_WORDS {
+            words.pop();
+        }
+        if join(&words).len() > max as usize {
+            return None;
+        }
+    }
+    Some(join(&words))
+}
+
+/// The profile's separator, unless the site wants a symbol or doesn't take the one in it
+fn separator(profile: &GeneratorProfileRecord, policy: &SitePolicy) -> String {
+    let accepted =
+        |c: char| c.is_ascii_alphanumeric() || policy.symbols.as_deref().map_or(true, |s| s.contains(c));
+    let has_symbol = profile.separator.chars().any(|c| !c.is_ascii_alphanumeric());
+    if profile.separator.chars().all(accepted) && (has_symbol || !policy.require_symbols) {
+        return profile.separator.clone();
+    }
+    match policy.symbols.as_deref() {
+        Some(symbols) => symbols.chars().take(1).collect(),
+        None => "-".to_string(),
+    }
+}
+
+fn word(rng: &mut impl Rng256, capitalize: bool) -> String {
+    let word = BIP39_TABLE[uniform(rng, BIP39_TABLE.len())];
+    if capitalize {
+        let mut chars = word.chars();
+        chars.next().map(|first| first.to_ascii_uppercase()).into_iter().chain(chars).collect()
+    } else {
+        word.to_string()
+    }
+}
+
+/// A uniformly random number below `n`
+fn uniform(rng: &mut impl Rng256, n: usize) -> usize {
+    let n = n as u32;
+    // the top of the range that `n` doesn't divide evenly would favor the low numbers
+    let limit = u32::MAX - u32::MAX % n;
+    loop {
+        if let Some(r) = rng.gen_uniform_u32x8().iter().find(|&&r| r < limit) {
+            return (r % n) as usize;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ctap_crypto::rng256::ThreadRng256;
+
+    use super::*;
+
+    fn profile(name: &str) -> GeneratorProfileRecord {
+        builtin_profiles().into_iter().find(|p| p.name == name).unwrap()
+    }
+
+    fn policy(rules: &str) -> SitePolicy { SitePolicy::try_from(rules).unwrap() }
+
+    #[test]
+    fn test_policy_round_trip() {
+        let rules = "min=8 max=16 require=upper,digit symbols=!#$";
+        let parsed = policy(rules);
+        assert_eq!(parsed.max_length, Some(16));
+        assert!(parsed.require_uppercase && parsed.require_numbers && !parsed.require_symbols);
+        assert_eq!(parsed.to_string(), rules);
+        assert_eq!(policy("symbols=").symbols.as_deref(), Some(""));
+        assert_eq!(policy("").to_string(), "");
+
+        assert_eq!(SitePolicy::try_from("max=abc"), Err(PolicyError::BadNumber));
+        assert_eq!(SitePolicy::try_from("require=emoji"), Err(PolicyError::UnknownRule));
+        assert_eq!(SitePolicy::try_from("symbols=ab"), Err(PolicyError::UnknownRule));
+        assert_eq!(SitePolicy::try_from("min=12 max=8"), Err(PolicyError::Contradiction));
+        assert_eq!(SitePolicy::try_from("require=symbol symbols="), Err(PolicyError::Contradiction));
+    }
+
+    #[test]
+    fn test_default_profile() {
+        let profiles = builtin_profiles();
+        assert_eq!(find_profile(&profiles, Some("deleted")).name, DEFAULT_PROFILE);
+        for _ in 0..32 {
+            let password = generate(find_profile(&profiles, None), None, &mut ThreadRng256 {});
+            assert_eq!(password.chars().count(), 20);
+            assert!(password.chars().any(|c| c.is_ascii_lowercase()));
+            assert!(password.chars().any(|c| c.is_ascii_uppercase()));
+            assert!(password.chars().any(|c| c.is_ascii_digit()));
+            assert!(password.chars().any(|c| SYMBOLS.contains(c)));
+            assert!(!password.chars().any(|c| AMBIGUOUS.contains(c)));
+        }
+    }
+
+    #[test]
+    fn test_policy_shapes_characters() {
+        let site = policy("max=12 require=symbol symbols=_");
+        for _ in 0..32 {
+            let password = generate(&profile(DEFAULT_PROFILE), Some(&site), &mut ThreadRng256 {});
+            assert_eq!(password.len(), 12);
+            assert!(password.contains('_'));
+            assert!(password.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'));
+        }
+        let pin = generate(&profile("PIN"), Some(&policy("min=8 require=upper")), &mut ThreadRng256 {});
+        assert_eq!(pin.len(), 8);
+        assert!(pin.chars().all(|c| c.is_ascii_digit() || c.is_ascii_uppercase()));
+        assert!(pin.chars().any(|c| c.is_ascii_uppercase()));
+    }
+
+    #[test]
+    fn test_passphrase() {
+        let passphrase = generate(&profile("Passphrase"), None, &mut ThreadRng256 {});
+        let words: Vec<&str> = passphrase.split('-').collect();
+        assert_eq!(words.len(), 6);
+        assert!(words.iter().all(|word| BIP39_TABLE.iter().any(|w| w == word)));
+
+        let site = policy("require=upper,digit,symbol symbols=.");
+        let passphrase = generate(&profile("Passphrase"), Some(&site), &mut ThreadRng256 {});
+        let (words, digit) = passphrase.split_at(passphrase.len() - 1);
+        assert!(digit.chars().all(|c| c.is_ascii_digit()));
+        let words: Vec<&str> = words.split('.').collect();
+        assert_eq!(words.len(), 6);
+        assert!(words.iter().all(|word| word.starts_with(|c: char| c.is_ascii_uppercase())));
+
+        // four words of the BIP-39 list never fit in 12 characters, with separators
+        let short = generate(&profile("Passphrase"), Some(&policy("max=12")), &mut ThreadRng256 {});
+        assert_eq!(short.len(), 12);
+        assert!(!short.contains('-'));
+    }
+}
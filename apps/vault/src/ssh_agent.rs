@@ -0,0 +1,587 @@
+//! An OpenSSH agent for keys that live in the PDDB, served over the USB serial port.
+//!
+//! The host talks to it like to any other agent, once the agent socket is bridged to the serial port,
+//! e.g. `socat UNIX-LISTEN:$HOME/.ssh/precursor.sock,fork /dev/ttyACM0,raw,echo=0` and
+//! `SSH_AUTH_SOCK=$HOME/.ssh/precursor.sock`. Only the part of the protocol needed to log in is spoken
+//! (draft-miller-ssh-agent): listing the keys, and signing with them. Keys are made on the device and
+//! can't be added, removed or exported over the wire, and every signature has to be approved on the
+//! device.
+
+use core::convert::{TryFrom, TryInto};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+use ctap_crypto::ecdsa;
+use ctap_crypto::rng256::Rng256;
+use ctap_crypto::sha256::Sha256;
+use locales::t;
+use usb_device_xous::{UsbDeviceType, UsbHid};
+
+use crate::storage::{SshKeyRecord, VAULT_SSH_KEY_REC_VERSION};
+
+const SSH_AGENT_FAILURE: u8 = 5;
+const SSH_AGENTC_REQUEST_IDENTITIES: u8 = 11;
+const SSH_AGENT_IDENTITIES_ANSWER: u8 = 12;
+const SSH_AGENTC_SIGN_REQUEST: u8 = 13;
+const SSH_AGENT_SIGN_RESPONSE: u8 = 14;
+/// What clients ask the agent to sign when logging in, see RFC 4252 section 7
+const SSH_MSG_USERAUTH_REQUEST: u8 = 50;
+/// A sign request for a login is a few hundred bytes; anything much longer is garbage on the line
+const MAX_MESSAGE_LEN: usize = 16 * 1024;
+/// How long to wait for more of a request before flushing the serial buffers
+const SERIAL_POLL_MS: u64 = 50;
+const NISTP256: &str = "nistp256";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SshKeyAlgorithm {
+    Ed25519,
+    EcdsaP256,
+}
+
+impl Default for SshKeyAlgorithm {
+    fn default() -> Self { Self::EcdsaP256 }
+}
+
+/// The names are the ones used in the keys and signatures on the wire
+impl core::fmt::Display for SshKeyAlgorithm {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            SshKeyAlgorithm::Ed25519 => write!(f, "ssh-ed25519"),
+            SshKeyAlgorithm::EcdsaP256 => write!(f, "ecdsa-sha2-nistp256"),
+        }
+    }
+}
+
+impl TryFrom<&str> for SshKeyAlgorithm {
+    type Error = xous::Error;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "ssh-ed25519" => Ok(SshKeyAlgorithm::Ed25519),
+            "ecdsa-sha2-nistp256" => Ok(SshKeyAlgorithm::EcdsaP256),
+            _ => Err(xous::Error::InvalidString),
+        }
+    }
+}
+
+/// The algorithms new keys can be made with in this build
+pub(crate) fn algorithms() -> Vec<SshKeyAlgorithm> {
+    let mut algorithms = vec![];
+    #[cfg(feature = "ed25519")]
+    algorithms.push(SshKeyAlgorithm::Ed25519);
+    algorithms.push(SshKeyAlgorithm::EcdsaP256);
+    algorithms
+}
+
+pub(crate) struct SshKey {
+    algorithm: SshKeyAlgorithm,
+    secret: [u8; 32],
+    pub comment: String,
+}
+
+impl SshKey {
+    pub(crate) fn generate(algorithm: SshKeyAlgorithm, rng: &mut impl Rng256, comment: &str) -> Self {
+        let secret = match algorithm {
+            SshKeyAlgorithm::Ed25519 => rng.gen_uniform_u8x32(),
+            SshKeyAlgorithm::EcdsaP256 => {
+                let mut secret = [0u8; 32];
+                ecdsa::SecKey::gensk(rng).to_bytes(&mut secret);
+                secret
+            }
+        };
+        SshKey { algorithm, secret, comment: comment.to_string() }
+    }
+
+    pub(crate) fn to_record(&self) -> SshKeyRecord {
+        SshKeyRecord {
+            version: VAULT_SSH_KEY_REC_VERSION,
+            algorithm: self.algorithm,
+            secret: hex::encode(self.secret),
+            comment: self.comment.clone(),
+            ctime: 0,
+        }
+    }
+
+    /// The key blob, as it appears in `authorized_keys` and on the wire
+    fn public_blob(&self) -> Vec<u8> {
+        let mut blob = vec![];
+        put_string(&mut blob, self.algorithm.to_string().as_bytes());
+        match self.algorithm {
+            #[cfg(feature = "ed25519")]
+            SshKeyAlgorithm::Ed25519 => {
+                let seed = ed25519_compact::Seed::new(self.secret);
+                put_string(&mut blob, &*ed25519_compact::KeyPair::from_seed(seed).pk);
+            }
+            #[cfg(not(feature = "ed25519"))]
+            SshKeyAlgorithm::Ed25519 => unreachable!("Ed25519 keys are refused when loading"),
+            SshKeyAlgorithm::EcdsaP256 => {
+                let mut x = [0u8; 32];
+                let mut y = [0u8; 32];
+                self.ecdsa_key().genpk().to_coordinates(&mut x, &mut y);
+                let mut point = vec![0x04];
+                point.extend_from_slice(&x);
+                point.extend_from_slice(&y);
+                put_string(&mut blob, NISTP256.as_bytes());
+                put_string(&mut blob, &point);
+            }
+        }
+        blob
+    }
+
+    /// The line to add to `~/.ssh/authorized_keys` on the servers this key logs in to
+    pub(crate) fn authorized_key(&self) -> String {
+        format!("{} {} {}", self.algorithm, base64_encode(&self.public_blob()), self.comment)
+    }
+
+    fn sign(&self, data: &[u8]) -> Vec<u8> {
+        let mut signature = vec![];
+        put_string(&mut signature, self.algorithm.to_string().as_bytes());
+        match self.algorithm {
+            #[cfg(feature = "ed25519")]
+            SshKeyAlgorithm::Ed25519 => {
+                let seed = ed25519_compact::Seed::new(self.secret);
+                let sk = ed25519_compact::KeyPair::from_seed(seed).sk;
+                put_string(&mut signature, &sk.sign(data, None).to_vec());
+            }
+            #[cfg(not(feature = "ed25519"))]
+            SshKeyAlgorithm::Ed25519 => unreachable!("Ed25519 keys are refused when loading"),
+            SshKeyAlgorithm::EcdsaP256 => {
+                let der = self.ecdsa_key().sign_rfc6979::<Sha256>(data).to_asn1_der();
+                put_string(&mut signature, &ecdsa_der_to_mpints(&der).expect("malformed ECDSA signature"));
+            }
+        }
+        signature
+    }
+
+    fn ecdsa_key(&self) -> ecdsa::SecKey {
+        // the secret was checked when the key was made or loaded
+        ecdsa::SecKey::from_bytes(&self.secret).unwrap()
+    }
+}
+
+impl TryFrom<&SshKeyRecord> for SshKey {
+    type Error = xous::Error;
+
+    fn try_from(record: &SshKeyRecord) -> Result<Self, Self::Error> {
+        let secret: [u8; 32] = hex::decode(&record.secret)
+            .ok()
+            .and_then(|secret| secret.try_into().ok())
+            .ok_or(xous::Error::InvalidString)?;
+        match record.algorithm {
+            #[cfg(not(feature = "ed25519"))]
+            SshKeyAlgorithm::Ed25519 => return Err(xous::Error::InvalidString),
+            SshKeyAlgorithm::EcdsaP256 if ecdsa::SecKey::from_bytes(&secret).is_none() => {
+                return Err(xous::Error::InvalidString);
+            }
+            _ => {}
+        }
+        Ok(SshKey { algorithm: record.algorithm, secret, comment: record.comment.clone() })
+    }
+}
+
+/// Serves `keys` over the USB serial port until the user stops the agent. `approve` is asked before
+/// every signature, with the key and the user being logged in as, if that can be told.
+pub(crate) fn serve(
+    modals: &modals::Modals,
+    keys: &[SshKey],
+    approve: impl FnMut(&str) -> bool,
+) -> Result<(), xous::Error> {
+    let usb = UsbHid::new();
+    let tt = ticktimer_server::Ticktimer::new().unwrap();
+    let previous_core = usb.get_current_core()?;
+    usb.switch_to_core(UsbDeviceType::Serial)?;
+
+    let (tx, rx) = mpsc::channel();
+    let stop = Arc::new(AtomicBool::new(false));
+    let finished = Arc::new(AtomicBool::new(false));
+    thread::spawn({
+        let stop = stop.clone();
+        let finished = finished.clone();
+        move || {
+            let usb = UsbHid::new();
+            loop {
+                let chunk = usb.serial_wait_binary();
+                if stop.load(Ordering::SeqCst) || tx.send(chunk).is_err() {
+                    break;
+                }
+            }
+            finished.store(true, Ordering::SeqCst);
+        }
+    });
+
+    let result = run(modals, keys, approve, &usb, &rx);
+    modals.dynamic_notification_close().ok();
+
+    stop.store(true, Ordering::SeqCst);
+    while !finished.load(Ordering::SeqCst) {
+        usb.serial_flush()?;
+        tt.sleep_ms(SERIAL_POLL_MS as usize).unwrap();
+    }
+    usb.serial_clear_input_hooks();
+    usb.switch_to_core(previous_core)?;
+    result
+}
+
+fn run(
+    modals: &modals::Modals,
+    keys: &[SshKey],
+    mut approve: impl FnMut(&str) -> bool,
+    usb: &UsbHid,
+    rx: &mpsc::Receiver<Vec<u8>>,
+) -> Result<(), xous::Error> {
+    let mut pending = Vec::new();
+    let mut stop = show_running(modals);
+    while !stop.load(Ordering::SeqCst) {
+        match rx.recv_timeout(Duration::from_millis(SERIAL_POLL_MS)) {
+            Ok(chunk) => pending.extend_from_slice(&chunk),
+            // the receiver only wakes up for full chunks, so ask for the tail of the request
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                usb.serial_flush()?;
+                continue;
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+        while let Some(message) = next_message(&mut pending) {
+            let response = handle(keys, &message, |key, user| {
+                modals.dynamic_notification_close().ok();
+                let query = match user {
+                    Some(user) => format!(
+                        "{}\n{}\n{} {}",
+                        t!("vault.ssh.approve", locales::LANG),
+                        key.comment,
+                        t!("vault.ssh.approve_user", locales::LANG),
+                        user
+                    ),
+                    None => format!("{}\n{}", t!("vault.ssh.approve", locales::LANG), key.comment),
+                };
+                let approved = approve(&query);
+                stop = show_running(modals);
+                approved
+            });
+            usb.serial_write_binary(&frame(&response))?;
+        }
+    }
+    Ok(())
+}
+
+/// Puts up the notice that the agent is running; the flag is set once a key is hit to stop it.
+fn show_running(modals: &modals::Modals) -> Arc<AtomicBool> {
+    modals
+        .dynamic_notification(
+            Some(t!("vault.ssh.running", locales::LANG)),
+            Some(t!("vault.ssh.running_stop", locales::LANG)),
+        )
+        .ok();
+    let stop = Arc::new(AtomicBool::new(false));
+    thread::spawn({
+        let token = modals.token();
+        let conn = modals.conn();
+        let stop = stop.clone();
+        move || {
+            // closing the notice to ask for an approval also ends up here, with no key
+            if let Ok(Some(_)) = modals::dynamic_notification_blocking_listener(token, conn) {
+                stop.store(true, Ordering::SeqCst);
+            }
+        }
+    });
+    stop
+}
+
+/// Takes the first complete message off `pending`, without its length prefix
+fn next_message(pending: &mut Vec<u8>) -> Option<Vec<u8>> {
+    if pending.len() < 4 {
+        return None;
+    }
+    let len = u32::from_be_bytes(pending[..4].try_into().unwrap()) as usize;
+    if len == 0 || len > MAX_MESSAGE_LEN {
+        log::warn!("dropping {} bytes of garbage received by the SSH agent", pending.len());
+        pending.clear();
+        return None;
+    }
+    if pending.len() < 4 + len {
+        return None;
+    }
+    let message = pending[4..4 + len].to_vec();
+    pending.drain(..4 + len);
+    Some(message)
+}
+
+fn frame(message: &[u8]) -> Vec<u8> {
+    let mut framed = Vec::with_capacity(4 + message.len());
+    put_string(&mut framed, message);
+    framed
+}
+
+/// Answers one agent request. `approve` is asked before signing anything.
+fn handle(
+    keys: &[SshKey],
+    message: &[u8],
+    mut approve: impl FnMut(&SshKey, Option<&str>) -> bool,
+) -> Vec<u8> {
+    let mut reader = Reader(message);
+    match reader.byte() {
+        Some(SSH_AGENTC_REQUEST_IDENTITIES) => {
+            let mut response = vec![SSH_AGENT_IDENTITIES_ANSWER];
+            response.extend_from_slice(&(keys.len() as u32).to_be_bytes());
+            for key in keys {
+                put_string(&mut response, &key.public_blob());
+                put_string(&mut response, key.comment.as_bytes());
+            }
+            response
+        }
+        Some(SSH_AGENTC_SIGN_REQUEST) => {
+            let request = reader.string().zip(reader.string());
+            let key = request.and_then(|(blob, _)| keys.iter().find(|key| key.public_blob() == blob));
+            match (key, request) {
+                (Some(key), Some((_, data))) => {
+                    let user = login_user(data);
+                    if approve(key, user.as_deref()) {
+                        log::info!("SSH signature with {} approved", key.comment);
+                        let mut response = vec![SSH_AGENT_SIGN_RESPONSE];
+                        put_string(&mut response, &key.sign(data));
+                        response
+                    } else {
+                        log::info!("SSH signature with {} declined", key.comment);
+                        vec![SSH_AGENT_FAILURE]
+                    }
+                }
+                _ => vec![SSH_AGENT_FAILURE],
+            }
+        }
+        other => {
+            log::debug!("unsupported SSH agent request {:?}", other);
+            vec![SSH_AGENT_FAILURE]
+        }
+    }
+}
+
+/// The user name in the `SSH_MSG_USERAUTH_REQUEST` a client asks to have signed, if that's what it is
+fn login_user(data: &[u8]) -> Option<String> {
+    let mut reader = Reader(data);
+    let _session_id = reader.string()?;
+    if reader.byte()? != SSH_MSG_USERAUTH_REQUEST {
+        return None;
+    }
+    Some(String::from_utf8_lossy(reader.string()?).into_owned())
+}
+
+/// An ECDSA signature is a DER `SEQUENCE { INTEGER r, INTEGER s }`, and SSH wants the two integers as
+/// mpints. Both formats use minimal two's complement big endian, so the integers carry over as they are.
+fn ecdsa_der_to_mpints(der: &[u8]) -> Option<Vec<u8>> {
+    const DER_SEQUENCE: u8 = 0x30;
+    const DER_INTEGER: u8 = 0x02;
+    let mut reader = Reader(der);
+    if reader.byte()? != DER_SEQUENCE || reader.byte()? as usize != reader.0.len() {
+        return None;
+    }
+    let mut mpints = vec![];
+    for _ in 0..2 {
+        if reader.byte()? != DER_INTEGER {
+            return None;
+        }
+        let len = reader.byte()? as usize;
+        put_string(&mut mpints, reader.take(len)?);
+    }
+    if !reader.0.is_empty() {
+        return None;
+    }
+    Some(mpints)
+}
+
+fn put_string(out: &mut Vec<u8>, data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out.extend_from_slice(data);
+}
+
+/// Reads the SSH wire encoding, see RFC 4251 section 5
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.0.len() < len {
+            return None;
+        }
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Some(head)
+    }
+
+    fn byte(&mut self) -> Option<u8> { self.take(1).map(|b| b[0]) }
+
+    fn string(&mut self) -> Option<&'a [u8]> {
+        let len = u32::from_be_bytes(self.take(4)?.try_into().ok()?) as usize;
+        self.take(len)
+    }
+}
+
+fn base64_encode(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity((data.len() + 2) / 3 * 4);
+    for chunk in data.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, &b)| bits | ((b as u32) << (16 - 8 * i)));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[((bits >> (18 - 6 * i)) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(parts: &[&[u8]]) -> Vec<u8> {
+        let mut message = vec![];
+        for part in parts {
+            put_string(&mut message, part);
+        }
+        message
+    }
+
+    /// The key blobs listed in an identities answer
+    fn identities(answer: &[u8]) -> Vec<Vec<u8>> {
+        let mut reader = Reader(answer);
+        assert_eq!(reader.byte(), Some(SSH_AGENT_IDENTITIES_ANSWER));
+        let count = u32::from_be_bytes(reader.take(4).unwrap().try_into().unwrap());
+        (0..count)
+            .map(|_| {
+                let blob = reader.string().unwrap().to_vec();
+                reader.string().unwrap();
+                blob
+            })
+            .collect()
+    }
+
+    /// Has the agent sign `data` with the key of `blob`, and returns the signature's algorithm and blob
+    fn agent_sign(keys: &[SshKey], blob: &[u8], data: &[u8]) -> (Vec<u8>, Vec<u8>) {
+        let mut request = vec![SSH_AGENTC_SIGN_REQUEST];
+        request.extend_from_slice(&message(&[blob, data]));
+        // no flags
+        request.extend_from_slice(&0u32.to_be_bytes());
+        let response = handle(keys, &request, |_, _| true);
+        let mut reader = Reader(&response);
+        assert_eq!(reader.byte(), Some(SSH_AGENT_SIGN_RESPONSE));
+        let mut signature = Reader(reader.string().unwrap());
+        (signature.string().unwrap().to_vec(), signature.string().unwrap().to_vec())
+    }
+
+    /// An mpint as the 32 bytes of a P-256 scalar
+    fn scalar(mpint: &[u8]) -> [u8; 32] {
+        let mpint = &mpint[mpint.len().saturating_sub(32)..];
+        let mut scalar = [0u8; 32];
+        scalar[32 - mpint.len()..].copy_from_slice(mpint);
+        scalar
+    }
+
+    #[test]
+    fn test_sign_request() {
+        let mut rng = ctap_crypto::rng256::ThreadRng256 {};
+        let mut keys = vec![SshKey::generate(SshKeyAlgorithm::EcdsaP256, &mut rng, "p256")];
+        #[cfg(feature = "ed25519")]
+        keys.push(SshKey::generate(SshKeyAlgorithm::Ed25519, &mut rng, "ed25519"));
+        let blobs = identities(&handle(&keys, &[SSH_AGENTC_REQUEST_IDENTITIES], |_, _| true));
+        assert_eq!(blobs.len(), keys.len());
+
+        let mut data = message(&[b"session id"]);
+        data.push(SSH_MSG_USERAUTH_REQUEST);
+        data.extend_from_slice(&message(&[b"git", b"ssh-connection", b"publickey"]));
+        for blob in blobs {
+            let mut public = Reader(&blob);
+            let algorithm = public.string().unwrap();
+            let (signed_with, signature) = agent_sign(&keys, &blob, &data);
+            assert_eq!(signed_with, algorithm);
+            match SshKeyAlgorithm::try_from(&*String::from_utf8_lossy(algorithm)).unwrap() {
+                SshKeyAlgorithm::EcdsaP256 => {
+                    assert_eq!(public.string(), Some(NISTP256.as_bytes()));
+                    let point = public.string().unwrap();
+                    assert_eq!((point.len(), point[0]), (65, 0x04));
+                    let pk = ecdsa::PubKey::from_coordinates(
+                        point[1..33].try_into().unwrap(),
+                        point[33..].try_into().unwrap(),
+                    )
+                    .unwrap();
+                    let mut mpints = Reader(&signature);
+                    let rs = [scalar(mpints.string().unwrap()), scalar(mpints.string().unwrap())].concat();
+                    let signature = ecdsa::Signature::from_bytes(&rs).unwrap();
+                    assert!(pk.verify_vartime::<Sha256>(&data, &signature));
+                    assert!(!pk.verify_vartime::<Sha256>(b"something else", &signature));
+                }
+                #[cfg(feature = "ed25519")]
+                SshKeyAlgorithm::Ed25519 => {
+                    let pk = ed25519_compact::PublicKey::from_slice(public.string().unwrap()).unwrap();
+                    let signature = ed25519_compact::Signature::from_slice(&signature).unwrap();
+                    assert!(pk.verify(&data, &signature).is_ok());
+                    assert!(pk.verify(b"something else", &signature).is_err());
+                }
+                #[cfg(not(feature = "ed25519"))]
+                SshKeyAlgorithm::Ed25519 => unreachable!("no Ed25519 keys were made"),
+            }
+            assert!(public.0.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_next_message() {
+        let mut pending = frame(&[SSH_AGENTC_REQUEST_IDENTITIES]);
+        pending.extend_from_slice(&frame(&[SSH_AGENTC_SIGN_REQUEST, 1, 2])[..5]);
+        assert_eq!(next_message(&mut pending), Some(vec![SSH_AGENTC_REQUEST_IDENTITIES]));
+        assert_eq!(next_message(&mut pending), None);
+        pending.extend_from_slice(&[1, 2]);
+        assert_eq!(next_message(&mut pending), Some(vec![SSH_AGENTC_SIGN_REQUEST, 1, 2]));
+        assert!(pending.is_empty());
+
+        pending.extend_from_slice(&[0xff; 8]);
+        assert_eq!(next_message(&mut pending), None);
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn test_login_user() {
+        let mut data = message(&[b"session id"]);
+        data.push(SSH_MSG_USERAUTH_REQUEST);
+        data.extend_from_slice(&message(&[b"git", b"ssh-connection", b"publickey"]));
+        assert_eq!(login_user(&data).as_deref(), Some("git"));
+
+        let mut data = message(&[b"session id"]);
+        data.push(SSH_MSG_USERAUTH_REQUEST + 1);
+        assert_eq!(login_user(&data), None);
+        assert_eq!(login_user(b"\x00\x00\x01"), None);
+    }
+
+    #[test]
+    fn test_ecdsa_der_to_mpints() {
+        // a short r, and an s with the high bit set that keeps its leading zero
+        let der = [0x30, 0x07, 0x02, 0x01, 0x01, 0x02, 0x02, 0x00, 0xFF];
+        assert_eq!(ecdsa_der_to_mpints(&der), Some(vec![0, 0, 0, 1, 0x01, 0, 0, 0, 2, 0x00, 0xFF]));
+        assert_eq!(ecdsa_der_to_mpints(&der[..8]), None);
+        assert_eq!(ecdsa_der_to_mpints(&[0x31, 0x00]), None);
+    }
+
+    #[test]
+    fn test_base64_encode() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foo"), "Zm9v");
+        assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+    }
+
+    #[test]
+    fn test_unsupported_requests_fail() {
+        // SSH_AGENTC_ADD_IDENTITY
+        assert_eq!(handle(&[], &[17], |_, _| true), vec![SSH_AGENT_FAILURE]);
+        assert_eq!(handle(&[], &[], |_, _| true), vec![SSH_AGENT_FAILURE]);
+        let request = [&[SSH_AGENTC_SIGN_REQUEST][..], &message(&[b"unknown key", b"data"])].concat();
+        assert_eq!(handle(&[], &request, |_, _| panic!("nothing to approve")), vec![SSH_AGENT_FAILURE]);
+        assert_eq!(
+            handle(&[], &[SSH_AGENTC_REQUEST_IDENTITIES], |_, _| true),
+            vec![SSH_AGENT_IDENTITIES_ANSWER, 0, 0, 0, 0]
+        );
+    }
+}
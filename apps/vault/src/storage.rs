@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::convert::TryFrom;
 use std::{
     io::Read,
@@ -7,13 +8,20 @@ use std::{
 
 use chrono::{DateTime, NaiveDateTime, Utc};
 use ctap_crypto::Hash256;
+use num_traits::*;
+use usb_device_xous::HostLayout;
 
+use crate::pwgen::SitePolicy;
+use crate::ssh_agent::SshKeyAlgorithm;
 use crate::totp::TotpAlgorithm;
 
 const VAULT_PASSWORD_DICT: &'static str = "vault.passwords";
 const VAULT_TOTP_DICT: &'static str = "vault.totp";
+const VAULT_SSH_KEY_DICT: &'static str = "vault.sshkeys";
+const VAULT_OPENPGP_DICT: &'static str = "vault.openpgp";
+const VAULT_PWGEN_PROFILE_DICT: &'static str = "vault.pwgen";
 const VAULT_TOTP_ALLOC_HINT: usize = 128;
-const VAULT_PASSWORD_REC_VERSION: u32 = 1;
+pub(crate) const VAULT_PASSWORD_REC_VERSION: u32 = 1;
 
 // Version history TOTP record:
 //  - v1 created, basic record for TOTP
@@ -21,13 +29,19 @@ const VAULT_PASSWORD_REC_VERSION: u32 = 1;
 //    - `hotp` field added. If 1, then HOTP record. If not existent or not 1, then TOTP
 //    - If HOTP, then the `timestep` field is re-purposed as the `count` field.
 //    - v1 records read directly onto v2 records, and `hotp` is always `false` for v1 records
-const VAULT_TOTP_REC_VERSION: u32 = 2;
+pub(crate) const VAULT_TOTP_REC_VERSION: u32 = 2;
+pub(crate) const VAULT_SSH_KEY_REC_VERSION: u32 = 1;
+pub(crate) const VAULT_OPENPGP_REC_VERSION: u32 = 1;
+pub(crate) const VAULT_PWGEN_PROFILE_REC_VERSION: u32 = 1;
 
 #[derive(Debug)]
 pub enum Error {
     IoError(std::io::Error),
     TotpSerError(TOTPSerializationError),
     PasswordSerError(PasswordSerializationError),
+    SshKeySerError(SshKeySerializationError),
+    OpenPgpSerError(OpenPgpSerializationError),
+    GeneratorProfileSerError(GeneratorProfileSerializationError),
     KeyExists,
     DupesExist(Vec<usize>),
 }
@@ -44,6 +58,18 @@ impl From<PasswordSerializationError> for Error {
     fn from(e: PasswordSerializationError) -> Self { Self::PasswordSerError(e) }
 }
 
+impl From<SshKeySerializationError> for Error {
+    fn from(e: SshKeySerializationError) -> Self { Self::SshKeySerError(e) }
+}
+
+impl From<OpenPgpSerializationError> for Error {
+    fn from(e: OpenPgpSerializationError) -> Self { Self::OpenPgpSerError(e) }
+}
+
+impl From<GeneratorProfileSerializationError> for Error {
+    fn from(e: GeneratorProfileSerializationError) -> Self { Self::GeneratorProfileSerError(e) }
+}
+
 pub struct Manager {
     pddb: pddb::Pddb,
 }
@@ -68,6 +94,9 @@ pub struct ContentPDDBSettings {
 pub enum ContentKind {
     TOTP,
     Password,
+    SshKey,
+    OpenPgpCard,
+    GeneratorProfile,
 }
 
 impl ContentKind {
@@ -75,6 +104,9 @@ impl ContentKind {
         match self {
             ContentKind::TOTP => TotpRecord::default().settings(),
             ContentKind::Password => PasswordRecord::default().settings(),
+            ContentKind::SshKey => SshKeyRecord::default().settings(),
+            ContentKind::OpenPgpCard => OpenPgpCardRecord::default().settings(),
+            ContentKind::GeneratorProfile => GeneratorProfileRecord::default().settings(),
         }
     }
 }
@@ -169,6 +201,12 @@ impl Manager {
         )
     }
 
+    /// Whether a record stored under the same key as `record` already exists, in any open basis
+    pub fn exists(&self, record: &dyn StorageContent) -> bool {
+        let settings = record.settings();
+        self.pddb_exists(&settings.dict, &hex(record.hash()), None)
+    }
+
     pub fn new_records(
         &mut self,
         records: Vec<Box<dyn StorageContent>>,
@@ -283,6 +321,8 @@ pub struct TotpRecord {
     pub timestep: u64,
     pub ctime: u64,
     pub is_hotp: bool,
+    /// see `tags_from_str()`
+    pub tags: Vec<String>,
 }
 
 #[derive(Debug)]
@@ -329,6 +369,7 @@ impl StorageContent for TotpRecord {
                         }
                     }
                     "notes" => pr.notes.push_str(data),
+                    "tags" => pr.tags = tags_from_str(data),
                     "digits" => {
                         if let Ok(digits) = u32::from_str_radix(data, 10) {
                             pr.digits = digits;
@@ -381,7 +422,7 @@ impl StorageContent for TotpRecord {
 
     fn to_vec(&self) -> Vec<u8> {
         format!(
-            "{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n",
+            "{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n",
             "version",
             self.version,
             "secret",
@@ -400,6 +441,8 @@ impl StorageContent for TotpRecord {
             if self.is_hotp { 1 } else { 0 },
             "ctime",
             self.ctime,
+            "tags",
+            tags_to_str(&self.tags),
         )
         .into_bytes()
     }
@@ -427,6 +470,7 @@ impl TryFrom<Vec<u8>> for TotpRecord {
             ctime: 0,
             timestep: 0,
             is_hotp: false,
+            tags: Vec::new(),
         };
         let lines = desc_str.split('\n');
         for line in lines {
@@ -449,6 +493,7 @@ impl TryFrom<Vec<u8>> for TotpRecord {
                         }
                     }
                     "notes" => pr.notes.push_str(data),
+                    "tags" => pr.tags = tags_from_str(data),
                     "digits" => {
                         if let Ok(digits) = u32::from_str_radix(data, 10) {
                             pr.digits = digits;
@@ -501,7 +546,7 @@ impl TryFrom<Vec<u8>> for TotpRecord {
 impl From<TotpRecord> for Vec<u8> {
     fn from(tr: TotpRecord) -> Self {
         format!(
-            "{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n",
+            "{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n",
             "version",
             tr.version,
             "secret",
@@ -520,6 +565,8 @@ impl From<TotpRecord> for Vec<u8> {
             if tr.is_hotp { 1 } else { 0 },
             "ctime",
             tr.ctime,
+            "tags",
+            tags_to_str(&tr.tags),
         )
         .into_bytes()
     }
@@ -544,6 +591,19 @@ pub struct PasswordRecord {
     pub ctime: u64,
     pub atime: u64,
     pub count: u64,
+    /// host keyboard layout to autotype this entry with, overriding the global setting
+    pub layout: Option<HostLayout>,
+    /// see `tags_from_str()`
+    pub tags: Vec<String>,
+    /// the passwords this record used before, newest first
+    pub history: Vec<PreviousPassword>,
+    /// set while a rotation waits to be confirmed: the password before it is the newest in `history`
+    pub rotating: bool,
+    /// the name of the generator profile new passwords for this record are made with; the default one
+    /// if `None`
+    pub profile: Option<String>,
+    /// what the site accepts in a password, so generated ones don't get turned down
+    pub policy: Option<SitePolicy>,
 }
 impl PasswordRecord {
     pub fn alloc() -> Self {
@@ -559,6 +619,12 @@ impl PasswordRecord {
             ctime: 0,
             atime: 0,
             count: 0,
+            layout: None,
+            tags: Vec::new(),
+            history: Vec::with_capacity(PASSWORD_HISTORY_LEN),
+            rotating: false,
+            profile: None,
+            policy: None,
         }
     }
 
@@ -571,9 +637,98 @@ impl PasswordRecord {
         self.ctime = 0;
         self.atime = 0;
         self.count = 0;
+        self.layout = None;
+        self.tags.clear();
+        self.history.clear();
+        self.rotating = false;
+        self.profile = None;
+        self.policy = None;
+    }
+
+    /// Remembers `previous`, the password the record had until `now`. Blank passwords aren't worth
+    /// remembering, and neither is one that didn't actually change. A new password settles any rotation
+    /// that was waiting to be confirmed. Returns `true` if `previous` went into the history, i.e. if
+    /// there is something to go back to.
+    pub fn retire_password(&mut self, previous: String, now: u64) -> bool {
+        if previous == self.password {
+            return false;
+        }
+        self.rotating = false;
+        if previous.is_empty() {
+            return false;
+        }
+        self.history.insert(0, PreviousPassword { password: previous, retired: now });
+        self.history.truncate(PASSWORD_HISTORY_LEN);
+        true
+    }
+}
+
+/// How many previous passwords a record keeps
+pub const PASSWORD_HISTORY_LEN: usize = 8;
+
+/// A password that a record used before, and when it was replaced
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreviousPassword {
+    pub password: String,
+    /// seconds since the epoch
+    pub retired: u64,
+}
+
+/// Each previous password is stored on a line of its own, as `history:<retired>:<password>`
+fn history_to_str(history: &[PreviousPassword]) -> String {
+    history.iter().map(|prev| format!("history:{}:{}\n", prev.retired, prev.password)).collect()
+}
+
+fn previous_from_str(data: &str) -> Option<PreviousPassword> {
+    let (retired, password) = data.split_once(':')?;
+    let retired = u64::from_str_radix(retired, 10).ok()?;
+    Some(PreviousPassword { password: password.to_string(), retired })
+}
+
+/// An empty profile name means the default profile
+fn profile_from_str(data: &str) -> Option<String> {
+    if data.is_empty() { None } else { Some(data.to_string()) }
+}
+
+/// Stored as written by `SitePolicy`'s `Display`; empty when the site has no rules on record
+fn policy_from_str(data: &str) -> Option<SitePolicy> {
+    if data.is_empty() {
+        return None;
+    }
+    let policy = SitePolicy::try_from(data).ok();
+    if policy.is_none() {
+        log::warn!("malformed password policy {}, ignoring", data);
     }
+    policy
 }
 
+/// All the host layouts that autotype can be set to, in menu order
+pub(crate) fn host_layouts() -> impl Iterator<Item = HostLayout> { (0..).map_while(HostLayout::from_u32) }
+
+/// An entry's layout is stored by its index; an empty value means the global setting applies
+fn layout_to_str(layout: Option<HostLayout>) -> String {
+    layout.and_then(|l| l.to_u32()).map(|l| l.to_string()).unwrap_or_default()
+}
+
+fn layout_from_str(data: &str) -> Option<HostLayout> {
+    if data.is_empty() {
+        return None;
+    }
+    let layout = u32::from_str_radix(data, 10).ok().and_then(HostLayout::from_u32);
+    if layout.is_none() {
+        log::warn!("unknown autotype layout {}, using the global setting", data);
+    }
+    layout
+}
+
+/// Tags are stored as one comma-separated line. A tag with slashes, like `work/servers`, files the record in
+/// a folder: searching for `#work` lists everything in it and in its subfolders.
+pub(crate) fn tags_from_str(data: &str) -> Vec<String> {
+    data.split(',').map(str::trim).filter(|tag| !tag.is_empty()).map(String::from).collect()
+}
+
+pub(crate) fn tags_to_str(tags: &[String]) -> String { tags.join(",") }
+
 impl StorageContent for PasswordRecord {
     fn settings(&self) -> ContentPDDBSettings {
         ContentPDDBSettings { dict: VAULT_PASSWORD_DICT.to_string(), alloc_hint: Some(VAULT_TOTP_ALLOC_HINT) }
@@ -602,6 +757,15 @@ impl StorageContent for PasswordRecord {
                     "username" => self.username.push_str(data),
                     "password" => self.password.push_str(data),
                     "notes" => self.notes.push_str(data),
+                    "layout" => self.layout = layout_from_str(data),
+                    "tags" => self.tags = tags_from_str(data),
+                    "rotating" => self.rotating = data == "1",
+                    "profile" => self.profile = profile_from_str(data),
+                    "policy" => self.policy = policy_from_str(data),
+                    "history" => match previous_from_str(data) {
+                        Some(previous) => self.history.push(previous),
+                        None => log::warn!("malformed password history entry, ignoring"),
+                    },
                     "ctime" => {
                         if let Ok(ctime) = u64::from_str_radix(data, 10) {
                             self.ctime = ctime;
@@ -639,7 +803,7 @@ impl StorageContent for PasswordRecord {
 
     fn to_vec(&self) -> Vec<u8> {
         format!(
-            "{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n",
+            "{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}",
             "version",
             self.version,
             "description",
@@ -656,6 +820,17 @@ impl StorageContent for PasswordRecord {
             self.atime,
             "count",
             self.count,
+            "layout",
+            layout_to_str(self.layout),
+            "tags",
+            tags_to_str(&self.tags),
+            "rotating",
+            if self.rotating { 1 } else { 0 },
+            "profile",
+            self.profile.as_deref().unwrap_or(""),
+            "policy",
+            self.policy.as_ref().map(|policy| policy.to_string()).unwrap_or_default(),
+            history_to_str(&self.history),
         )
         .into_bytes()
     }
@@ -683,6 +858,12 @@ impl TryFrom<Vec<u8>> for PasswordRecord {
             ctime: 0,
             atime: 0,
             count: 0,
+            layout: None,
+            tags: Vec::new(),
+            history: Vec::new(),
+            rotating: false,
+            profile: None,
+            policy: None,
         };
 
         let lines = desc_str.split('\n');
@@ -701,6 +882,15 @@ impl TryFrom<Vec<u8>> for PasswordRecord {
                     "username" => pr.username.push_str(data),
                     "password" => pr.password.push_str(data),
                     "notes" => pr.notes.push_str(data),
+                    "layout" => pr.layout = layout_from_str(data),
+                    "tags" => pr.tags = tags_from_str(data),
+                    "rotating" => pr.rotating = data == "1",
+                    "profile" => pr.profile = profile_from_str(data),
+                    "policy" => pr.policy = policy_from_str(data),
+                    "history" => match previous_from_str(data) {
+                        Some(previous) => pr.history.push(previous),
+                        None => log::warn!("malformed password history entry, ignoring"),
+                    },
                     "ctime" => {
                         if let Ok(ctime) = u64::from_str_radix(data, 10) {
                             pr.ctime = ctime;
@@ -740,7 +930,7 @@ impl TryFrom<Vec<u8>> for PasswordRecord {
 impl From<PasswordRecord> for Vec<u8> {
     fn from(pr: PasswordRecord) -> Self {
         format!(
-            "{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n",
+            "{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}",
             "version",
             pr.version,
             "description",
@@ -757,11 +947,355 @@ impl From<PasswordRecord> for Vec<u8> {
             pr.atime,
             "count",
             pr.count,
+            "layout",
+            layout_to_str(pr.layout),
+            "tags",
+            tags_to_str(&pr.tags),
+            "rotating",
+            if pr.rotating { 1 } else { 0 },
+            "profile",
+            pr.profile.as_deref().unwrap_or(""),
+            "policy",
+            pr.policy.as_ref().map(|policy| policy.to_string()).unwrap_or_default(),
+            history_to_str(&pr.history),
         )
         .into_bytes()
     }
 }
 
+/// A key for the SSH agent. Unlike the other records it never leaves the device: it isn't part of
+/// host backups, and only its public half is ever shown.
+#[derive(Default)]
+pub struct SshKeyRecord {
+    pub version: u32,
+    pub algorithm: SshKeyAlgorithm,
+    /// hex encoded; the Ed25519 seed or the P-256 private scalar
+    pub secret: String,
+    /// shown by `ssh-add -l`, and the name of the key on the device
+    pub comment: String,
+    pub ctime: u64,
+}
+
+#[derive(Debug)]
+pub enum SshKeySerializationError {
+    MalformedInput,
+    BadVersion,
+    BadAlgorithm,
+    BadCtime,
+}
+
+impl StorageContent for SshKeyRecord {
+    fn settings(&self) -> ContentPDDBSettings {
+        ContentPDDBSettings { dict: VAULT_SSH_KEY_DICT.to_string(), alloc_hint: Some(VAULT_TOTP_ALLOC_HINT) }
+    }
+
+    fn set_ctime(&mut self, value: u64) { self.ctime = value; }
+
+    fn from_vec(&mut self, data: Vec<u8>) -> Result<(), Error> {
+        let desc_str = std::str::from_utf8(&data).or(Err(SshKeySerializationError::MalformedInput))?;
+
+        let mut kr = SshKeyRecord::default();
+
+        for line in desc_str.split('\n') {
+            if let Some((tag, data)) = line.split_once(':') {
+                match tag {
+                    "version" => {
+                        kr.version =
+                            u32::from_str_radix(data, 10).or(Err(SshKeySerializationError::BadVersion))?
+                    }
+                    "algorithm" => {
+                        kr.algorithm =
+                            SshKeyAlgorithm::try_from(data).or(Err(SshKeySerializationError::BadAlgorithm))?
+                    }
+                    "secret" => kr.secret.push_str(data),
+                    "comment" => kr.comment.push_str(data),
+                    "ctime" => {
+                        kr.ctime =
+                            u64::from_str_radix(data, 10).or(Err(SshKeySerializationError::BadCtime))?
+                    }
+                    _ => {
+                        log::warn!("unexpected tag {} encountered parsing SSH key info, ignoring", tag);
+                    }
+                }
+            } else {
+                log::trace!("invalid line skipped: {:?}", line);
+            }
+        }
+
+        *self = kr;
+
+        Ok(())
+    }
+
+    fn to_vec(&self) -> Vec<u8> {
+        format!(
+            "{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n",
+            "version",
+            self.version,
+            "algorithm",
+            self.algorithm,
+            "secret",
+            self.secret,
+            "comment",
+            self.comment,
+            "ctime",
+            self.ctime,
+        )
+        .into_bytes()
+    }
+
+    fn hash(&self) -> Vec<u8> {
+        let mut h = ctap_crypto::sha256::Sha256::new();
+        h.update(self.comment.as_bytes());
+        h.finalize().to_vec()
+    }
+}
+
+/// The state of the OpenPGP card: its PINs, keys and data objects. There is only ever one, and like
+/// SSH keys it never leaves the device.
+#[derive(Default, Clone)]
+pub struct OpenPgpCardRecord {
+    pub version: u32,
+    /// the card's serial number, part of its application identifier
+    pub serial: u32,
+    /// PW1 (user PIN) and PW3 (admin PIN) are kept like FIDO client PINs: the first 16 bytes of their
+    /// SHA-256, their length, and the retries left
+    pub pw1_hash: Vec<u8>,
+    pub pw1_len: u8,
+    pub pw1_retries: u8,
+    pub pw3_hash: Vec<u8>,
+    pub pw3_len: u8,
+    pub pw3_retries: u8,
+    /// whether a verified PW1 is good for more than one signature
+    pub pw1_multiple: bool,
+    pub signature_count: u32,
+    /// the P-256 private scalars of the signature, decryption and authentication keys, if generated
+    pub keys: [Option<Vec<u8>>; 3],
+    /// data objects the host stores on the card, such as the cardholder name and key fingerprints
+    pub objects: BTreeMap<u16, Vec<u8>>,
+    pub ctime: u64,
+}
+
+#[derive(Debug)]
+pub enum OpenPgpSerializationError {
+    MalformedInput,
+    BadVersion,
+    BadNumber,
+    BadHex,
+}
+
+impl StorageContent for OpenPgpCardRecord {
+    fn settings(&self) -> ContentPDDBSettings {
+        ContentPDDBSettings { dict: VAULT_OPENPGP_DICT.to_string(), alloc_hint: None }
+    }
+
+    fn set_ctime(&mut self, value: u64) { self.ctime = value; }
+
+    fn from_vec(&mut self, data: Vec<u8>) -> Result<(), Error> {
+        let desc_str = std::str::from_utf8(&data).or(Err(OpenPgpSerializationError::MalformedInput))?;
+        let number = |data: &str| u64::from_str_radix(data, 10).or(Err(OpenPgpSerializationError::BadNumber));
+        let bytes = |data: &str| hex::decode(data).or(Err(OpenPgpSerializationError::BadHex));
+
+        let mut cr = OpenPgpCardRecord::default();
+
+        for line in desc_str.split('\n') {
+            if let Some((tag, data)) = line.split_once(':') {
+                match tag {
+                    "version" => {
+                        cr.version =
+                            u32::from_str_radix(data, 10).or(Err(OpenPgpSerializationError::BadVersion))?
+                    }
+                    "serial" => cr.serial = number(data)? as u32,
+                    "pw1" => cr.pw1_hash = bytes(data)?,
+                    "pw1_len" => cr.pw1_len = number(data)? as u8,
+                    "pw1_retries" => cr.pw1_retries = number(data)? as u8,
+                    "pw3" => cr.pw3_hash = bytes(data)?,
+                    "pw3_len" => cr.pw3_len = number(data)? as u8,
+                    "pw3_retries" => cr.pw3_retries = number(data)? as u8,
+                    "pw1_multiple" => cr.pw1_multiple = number(data)? == 1,
+                    "signature_count" => cr.signature_count = number(data)? as u32,
+                    "key.sig" => cr.keys[0] = Some(bytes(data)?),
+                    "key.dec" => cr.keys[1] = Some(bytes(data)?),
+                    "key.aut" => cr.keys[2] = Some(bytes(data)?),
+                    "ctime" => cr.ctime = number(data)?,
+                    _ => match tag.strip_prefix("do.").map(|t| u16::from_str_radix(t, 16)) {
+                        Some(Ok(object)) => {
+                            cr.objects.insert(object, bytes(data)?);
+                        }
+                        _ => log::warn!("unexpected tag {} encountered parsing OpenPGP card, ignoring", tag),
+                    },
+                }
+            } else {
+                log::trace!("invalid line skipped: {:?}", line);
+            }
+        }
+
+        *self = cr;
+
+        Ok(())
+    }
+
+    fn to_vec(&self) -> Vec<u8> {
+        let mut s = format!(
+            "{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n",
+            "version",
+            self.version,
+            "serial",
+            self.serial,
+            "pw1",
+            hex::encode(&self.pw1_hash),
+            "pw1_len",
+            self.pw1_len,
+            "pw1_retries",
+            self.pw1_retries,
+            "pw3",
+            hex::encode(&self.pw3_hash),
+            "pw3_len",
+            self.pw3_len,
+            "pw3_retries",
+            self.pw3_retries,
+            "pw1_multiple",
+            if self.pw1_multiple { 1 } else { 0 },
+            "signature_count",
+            self.signature_count,
+            "ctime",
+            self.ctime,
+        );
+        for (name, key) in ["key.sig", "key.dec", "key.aut"].iter().zip(self.keys.iter()) {
+            if let Some(key) = key {
+                s.push_str(&format!("{}:{}\n", name, hex::encode(key)));
+            }
+        }
+        for (object, data) in self.objects.iter() {
+            s.push_str(&format!("do.{:04x}:{}\n", object, hex::encode(data)));
+        }
+        s.into_bytes()
+    }
+
+    /// Constant, as there is only one card.
+    fn hash(&self) -> Vec<u8> {
+        let mut h = ctap_crypto::sha256::Sha256::new();
+        h.update(b"openpgp card");
+        h.finalize().to_vec()
+    }
+}
+
+/// A named configuration of the password generator. A few profiles are built in, see
+/// `pwgen::builtin_profiles()`; the ones the user makes are stored here.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct GeneratorProfileRecord {
+    pub version: u32,
+    pub name: String,
+    /// in characters, or in words for a passphrase
+    pub length: u32,
+    pub lowercase: bool,
+    pub uppercase: bool,
+    pub numbers: bool,
+    pub symbols: bool,
+    /// leave out characters that are easily mistaken for one another, such as `l`, `1` and `I`
+    pub exclude_ambiguous: bool,
+    /// a diceware style passphrase of `length` words, joined by `separator`. `uppercase` capitalizes the
+    /// words and `numbers` adds a digit; the other character classes don't apply.
+    pub passphrase: bool,
+    pub separator: String,
+    pub ctime: u64,
+}
+
+#[derive(Debug)]
+pub enum GeneratorProfileSerializationError {
+    MalformedInput,
+    BadVersion,
+    BadLength,
+    BadCtime,
+}
+
+impl StorageContent for GeneratorProfileRecord {
+    fn settings(&self) -> ContentPDDBSettings {
+        ContentPDDBSettings { dict: VAULT_PWGEN_PROFILE_DICT.to_string(), alloc_hint: None }
+    }
+
+    fn set_ctime(&mut self, value: u64) { self.ctime = value; }
+
+    fn from_vec(&mut self, data: Vec<u8>) -> Result<(), Error> {
+        let desc_str =
+            std::str::from_utf8(&data).or(Err(GeneratorProfileSerializationError::MalformedInput))?;
+
+        let mut gp = GeneratorProfileRecord::default();
+
+        for line in desc_str.split('\n') {
+            if let Some((tag, data)) = line.split_once(':') {
+                match tag {
+                    "version" => {
+                        gp.version = u32::from_str_radix(data, 10)
+                            .or(Err(GeneratorProfileSerializationError::BadVersion))?
+                    }
+                    "name" => gp.name.push_str(data),
+                    "length" => {
+                        gp.length = u32::from_str_radix(data, 10)
+                            .or(Err(GeneratorProfileSerializationError::BadLength))?
+                    }
+                    "lowercase" => gp.lowercase = data == "1",
+                    "uppercase" => gp.uppercase = data == "1",
+                    "numbers" => gp.numbers = data == "1",
+                    "symbols" => gp.symbols = data == "1",
+                    "exclude_ambiguous" => gp.exclude_ambiguous = data == "1",
+                    "passphrase" => gp.passphrase = data == "1",
+                    "separator" => gp.separator.push_str(data),
+                    "ctime" => {
+                        gp.ctime = u64::from_str_radix(data, 10)
+                            .or(Err(GeneratorProfileSerializationError::BadCtime))?
+                    }
+                    _ => {
+                        log::warn!("unexpected tag {} encountered parsing generator profile, ignoring", tag);
+                    }
+                }
+            } else {
+                log::trace!("invalid line skipped: {:?}", line);
+            }
+        }
+
+        *self = gp;
+
+        Ok(())
+    }
+
+    fn to_vec(&self) -> Vec<u8> {
+        let flag = |set: bool| if set { 1 } else { 0 };
+        format!(
+            "{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n{}:{}\n",
+            "version",
+            self.version,
+            "name",
+            self.name,
+            "length",
+            self.length,
+            "lowercase",
+            flag(self.lowercase),
+            "uppercase",
+            flag(self.uppercase),
+            "numbers",
+            flag(self.numbers),
+            "symbols",
+            flag(self.symbols),
+            "exclude_ambiguous",
+            flag(self.exclude_ambiguous),
+            "passphrase",
+            flag(self.passphrase),
+            "separator",
+            self.separator,
+            "ctime",
+            self.ctime,
+        )
+        .into_bytes()
+    }
+
+    fn hash(&self) -> Vec<u8> {
+        let mut h = ctap_crypto::sha256::Sha256::new();
+        h.update(self.name.as_bytes());
+        h.finalize().to_vec()
+    }
+}
+
 /// because we don't get Utc::now, as the crate checks your architecture and xous is not recognized as a valid
 /// target
 fn utc_now() -> DateTime<Utc> {
@@ -779,3 +1313,104 @@ pub fn hex(data: Vec<u8>) -> String {
 
     s
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags_round_trip() {
+        assert_eq!(tags_from_str("work, work/servers ,,home"), vec!["work", "work/servers", "home"]);
+        assert!(tags_from_str("").is_empty());
+        assert!(tags_from_str(" , ").is_empty());
+        let tags = vec!["work".to_string(), "work/servers".to_string()];
+        assert_eq!(tags_from_str(&tags_to_str(&tags)), tags);
+    }
+
+    #[test]
+    fn history_round_trip() {
+        let history = vec![
+            PreviousPassword { password: "was:here".to_string(), retired: 1_700_000_000 },
+            PreviousPassword { password: ":".to_string(), retired: 1 },
+            PreviousPassword { password: "plain".to_string(), retired: 0 },
+        ];
+        // parsed the way `from_vec()` does it: the tag is split off at the first colon
+        let parsed = history_to_str(&history)
+            .lines()
+            .map(|line| {
+                let (tag, data) = line.split_once(':').unwrap();
+                assert_eq!(tag, "history");
+                previous_from_str(data).unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(parsed, history);
+
+        assert_eq!(previous_from_str("notanumber:pw"), None);
+        assert_eq!(previous_from_str("12345"), None);
+        assert_eq!(previous_from_str("5:"), Some(PreviousPassword { password: String::new(), retired: 5 }));
+    }
+
+    #[test]
+    fn record_round_trip() {
+        let mut record = PasswordRecord::alloc();
+        record.description.push_str("example.com");
+        record.password.push_str("first:pw");
+        record.retire_password("first:pw".to_string(), 100);
+        assert!(record.history.is_empty(), "an unchanged password isn't history");
+
+        record.password = "second:pw".to_string();
+        record.retire_password("first:pw".to_string(), 200);
+        record.password = "third".to_string();
+        record.retire_password("second:pw".to_string(), 300);
+        record.retire_password(String::new(), 400);
+        assert_eq!(
+            record.history,
+            vec![
+                PreviousPassword { password: "second:pw".to_string(), retired: 300 },
+                PreviousPassword { password: "first:pw".to_string(), retired: 200 },
+            ]
+        );
+
+        let mut restored = PasswordRecord::alloc();
+        restored.from_vec(record.to_vec()).unwrap();
+        assert_eq!(restored.password, "third");
+        assert_eq!(restored.history, record.history);
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let mut record = PasswordRecord::alloc();
+        for i in 0..PASSWORD_HISTORY_LEN as u64 + 2 {
+            record.retire_password(format!("pw{}", i), i);
+        }
+        assert_eq!(record.history.len(), PASSWORD_HISTORY_LEN);
+        assert_eq!(record.history[0].password, format!("pw{}", PASSWORD_HISTORY_LEN + 1));
+        assert_eq!(record.history.last().unwrap().password, "pw2");
+    }
+
+    #[test]
+    fn retiring_settles_a_rotation() {
+        let mut record = PasswordRecord::alloc();
+        record.password.push_str("new");
+        record.rotating = true;
+        assert!(record.retire_password("old".to_string(), 1));
+        assert!(!record.rotating);
+    }
+
+    #[test]
+    fn keeping_the_password_retires_nothing() {
+        let mut record = PasswordRecord::alloc();
+        record.password.push_str("current");
+        assert!(record.retire_password("older".to_string(), 1));
+        // choosing the same password again leaves nothing to undo to, and the history untouched
+        assert!(!record.retire_password("current".to_string(), 2));
+        assert_eq!(record.history.len(), 1);
+        assert_eq!(record.history[0].password, "older");
+        // ...and doesn't settle a rotation that was already waiting
+        record.rotating = true;
+        assert!(!record.retire_password("current".to_string(), 3));
+        assert!(record.rotating);
+        assert!(!record.retire_password(String::new(), 4));
+        assert!(!record.rotating);
+    }
+}
@@ -15,6 +15,27 @@ pub fn create_submenu(vault_conn: xous::CID, actions_conn: xous::CID, menu_mgr:
         action_payload: MenuPayload::Scalar([0, 0, 0, 0]),
         close_on_select: true,
     });
+    menu_items.push(MenuItem {
+        name: xous_ipc::String::from_str(t!("vault.menu_entry_layout", locales::LANG)),
+        action_conn: Some(vault_conn),
+        action_opcode: VaultOp::MenuEntryLayoutStage1.to_u32().unwrap(),
+        action_payload: MenuPayload::Scalar([0, 0, 0, 0]),
+        close_on_select: true,
+    });
+    menu_items.push(MenuItem {
+        name: xous_ipc::String::from_str(t!("vault.menu_entry_generator", locales::LANG)),
+        action_conn: Some(vault_conn),
+        action_opcode: VaultOp::MenuEntryGeneratorStage1.to_u32().unwrap(),
+        action_payload: MenuPayload::Scalar([0, 0, 0, 0]),
+        close_on_select: true,
+    });
+    menu_items.push(MenuItem {
+        name: xous_ipc::String::from_str(t!("vault.menu_rotate", locales::LANG)),
+        action_conn: Some(vault_conn),
+        action_opcode: VaultOp::MenuRotateStage1.to_u32().unwrap(),
+        action_payload: MenuPayload::Scalar([0, 0, 0, 0]),
+        close_on_select: true,
+    });
     menu_items.push(MenuItem {
         name: xous_ipc::String::from_str(t!("vault.menu_delete", locales::LANG)),
         action_conn: Some(vault_conn),
@@ -36,6 +57,13 @@ pub fn create_submenu(vault_conn: xous::CID, actions_conn: xous::CID, menu_mgr:
         action_payload: MenuPayload::Scalar([0, 0, 0, 0]),
         close_on_select: true,
     });
+    menu_items.push(MenuItem {
+        name: xous_ipc::String::from_str(t!("vault.menu_import", locales::LANG)),
+        action_conn: Some(actions_conn),
+        action_opcode: ActionOp::MenuImport.to_u32().unwrap(),
+        action_payload: MenuPayload::Scalar([0, 0, 0, 0]),
+        close_on_select: true,
+    });
     menu_items.push(MenuItem {
         name: xous_ipc::String::from_str(t!("vault.menu_change_font", locales::LANG)),
         action_conn: Some(vault_conn),
@@ -43,6 +71,13 @@ pub fn create_submenu(vault_conn: xous::CID, actions_conn: xous::CID, menu_mgr:
         action_payload: MenuPayload::Scalar([0, 0, 0, 0]),
         close_on_select: true,
     });
+    menu_items.push(MenuItem {
+        name: xous_ipc::String::from_str(t!("vault.menu_sort_order", locales::LANG)),
+        action_conn: Some(vault_conn),
+        action_opcode: VaultOp::MenuSortOrder.to_u32().unwrap(),
+        action_payload: MenuPayload::Scalar([0, 0, 0, 0]),
+        close_on_select: true,
+    });
     #[cfg(feature = "vault-testing")]
     menu_items.push(MenuItem {
         name: xous_ipc::String::from_str("Generate test vectors"),
@@ -58,6 +93,34 @@ pub fn create_submenu(vault_conn: xous::CID, actions_conn: xous::CID, menu_mgr:
         action_payload: MenuPayload::Scalar([0, 0, 0, 0]),
         close_on_select: true,
     });
+    menu_items.push(MenuItem {
+        name: xous_ipc::String::from_str(t!("vault.menu_fido_backup_mode", locales::LANG)),
+        action_conn: Some(vault_conn),
+        action_opcode: VaultOp::MenuFidoBackupMode.to_u32().unwrap(),
+        action_payload: MenuPayload::Scalar([0, 0, 0, 0]),
+        close_on_select: true,
+    });
+    menu_items.push(MenuItem {
+        name: xous_ipc::String::from_str(t!("vault.menu_ssh_agent", locales::LANG)),
+        action_conn: Some(actions_conn),
+        action_opcode: ActionOp::MenuSshAgent.to_u32().unwrap(),
+        action_payload: MenuPayload::Scalar([0, 0, 0, 0]),
+        close_on_select: true,
+    });
+    menu_items.push(MenuItem {
+        name: xous_ipc::String::from_str(t!("vault.menu_openpgp", locales::LANG)),
+        action_conn: Some(actions_conn),
+        action_opcode: ActionOp::MenuOpenPgp.to_u32().unwrap(),
+        action_payload: MenuPayload::Scalar([0, 0, 0, 0]),
+        close_on_select: true,
+    });
+    menu_items.push(MenuItem {
+        name: xous_ipc::String::from_str(t!("vault.menu_pwgen_profiles", locales::LANG)),
+        action_conn: Some(actions_conn),
+        action_opcode: ActionOp::MenuGeneratorProfiles.to_u32().unwrap(),
+        action_payload: MenuPayload::Scalar([0, 0, 0, 0]),
+        close_on_select: true,
+    });
     menu_items.push(MenuItem {
         name: xous_ipc::String::from_str(t!("prefs.autotype_rate", locales::LANG)),
         action_conn: Some(vault_conn),
@@ -65,6 +128,13 @@ pub fn create_submenu(vault_conn: xous::CID, actions_conn: xous::CID, menu_mgr:
         action_payload: MenuPayload::Scalar([0, 0, 0, 0]),
         close_on_select: true,
     });
+    menu_items.push(MenuItem {
+        name: xous_ipc::String::from_str(t!("prefs.autotype_layout", locales::LANG)),
+        action_conn: Some(vault_conn),
+        action_opcode: VaultOp::MenuAutotypeLayout.to_u32().unwrap(),
+        action_payload: MenuPayload::Scalar([0, 0, 0, 0]),
+        close_on_select: true,
+    });
     menu_items.push(MenuItem {
         name: xous_ipc::String::from_str(t!("vault.menu_set_lefty_mode", locales::LANG)),
         action_conn: Some(vault_conn),
@@ -18,9 +18,19 @@ pub enum TotpAlgorithm {
     HmacSha1,
     HmacSha256,
     HmacSha512,
+    /// Steam Guard: HMAC-SHA1, with the code spelled out in `STEAM_ALPHABET` instead of decimal digits
+    Steam,
     None,
 }
 
+/// Steam Guard codes are spelled with these characters, which can't be mistaken for one another
+const STEAM_ALPHABET: &[u8; 26] = b"23456789BCDFGHJKMNPQRTVWXY";
+/// Steam Guard codes are always this long
+pub const STEAM_DIGITS: u32 = 5;
+/// The range of code lengths accepted for standard TOTP and HOTP records. RFC 4226 asks for at least 6.
+pub const MIN_DIGITS: u32 = 6;
+pub const MAX_DIGITS: u32 = 10;
+
 impl Default for TotpAlgorithm {
     fn default() -> Self { Self::None }
 }
@@ -31,6 +41,7 @@ impl std::fmt::Debug for TotpAlgorithm {
             TotpAlgorithm::HmacSha1 => write!(f, "SHA1"),
             TotpAlgorithm::HmacSha256 => write!(f, "SHA256"),
             TotpAlgorithm::HmacSha512 => write!(f, "SHA512"),
+            TotpAlgorithm::Steam => write!(f, "STEAM"),
             TotpAlgorithm::None => write!(f, "None"),
         }
     }
@@ -44,6 +55,7 @@ impl TryFrom<&str> for TotpAlgorithm {
             "SHA1" => Ok(TotpAlgorithm::HmacSha1),
             "SHA256" => Ok(TotpAlgorithm::HmacSha256),
             "SHA512" => Ok(TotpAlgorithm::HmacSha512),
+            "STEAM" => Ok(TotpAlgorithm::Steam),
             _ => Err(xous::Error::InvalidString),
         }
     }
@@ -54,6 +66,7 @@ impl core::fmt::Display for TotpAlgorithm {
             TotpAlgorithm::HmacSha1 => write!(f, "SHA1"),
             TotpAlgorithm::HmacSha256 => write!(f, "SHA256"),
             TotpAlgorithm::HmacSha512 => write!(f, "SHA512"),
+            TotpAlgorithm::Steam => write!(f, "STEAM"),
             TotpAlgorithm::None => write!(f, "None"),
         }
     }
@@ -91,15 +104,14 @@ fn generate_hmac_bytes(unix_timestamp: u64, totp_entry: &TotpEntry) -> Result<Ve
     match totp_entry.algorithm {
         // The OpenTitan HMAC core does not support hmac-sha1. Fall back to
         // a software implementation.
-        TotpAlgorithm::HmacSha1 => {
+        TotpAlgorithm::HmacSha1 | TotpAlgorithm::Steam => {
             let mut mac: Hmac<Sha1> =
                 Hmac::new_from_slice(&totp_entry.shared_secret).map_err(|_| xous::Error::InternalError)?;
             mac.update(&unpack_u64(unix_timestamp / checked_step));
             let hash: &[u8] = &mac.finalize().into_bytes();
             computed_hmac.extend_from_slice(hash);
         }
-        // note: sha256/sha512 implementations not yet tested, as we have yet to find a site that uses this to
-        // test against.
+        // sha256/sha512 have yet to be seen in the wild; they are checked against the RFC 6238 vectors
         TotpAlgorithm::HmacSha256 => {
             let mut mac: Hmac<sha2::Sha256> =
                 Hmac::new_from_slice(&totp_entry.shared_secret).map_err(|_| xous::Error::InternalError)?;
@@ -130,11 +142,23 @@ pub fn generate_totp_code(unix_timestamp: u64, totp_entry: &TotpEntry) -> Result
         | ((hash[offset + 2] as u64) << 8)
         | (hash[offset + 3] as u64);
 
-    let truncated_code = format!(
-        "{:01$}",
-        binary % (10_u64.pow(totp_entry.digit_count as u32)),
-        totp_entry.digit_count as usize
-    );
+    let truncated_code = match totp_entry.algorithm {
+        TotpAlgorithm::Steam => {
+            let mut remainder = binary;
+            (0..totp_entry.digit_count)
+                .map(|_| {
+                    let c = STEAM_ALPHABET[(remainder % STEAM_ALPHABET.len() as u64) as usize] as char;
+                    remainder /= STEAM_ALPHABET.len() as u64;
+                    c
+                })
+                .collect()
+        }
+        _ => format!(
+            "{:01$}",
+            binary % 10_u64.checked_pow(totp_entry.digit_count as u32).ok_or(xous::Error::InvalidString)?,
+            totp_entry.digit_count as usize
+        ),
+    };
 
     Ok(truncated_code)
 }
@@ -194,3 +218,52 @@ pub(crate) fn pumper(
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(secret: &[u8], digit_count: u8, algorithm: TotpAlgorithm) -> TotpEntry {
+        TotpEntry { step_seconds: 30, shared_secret: secret.to_vec(), digit_count, algorithm }
+    }
+
+    #[test]
+    fn rfc6238_vectors() {
+        // RFC 6238 appendix B. Each hash uses a seed of its own output length.
+        let sha1 = entry(b"12345678901234567890", 8, TotpAlgorithm::HmacSha1);
+        let sha256 = entry(b"12345678901234567890123456789012", 8, TotpAlgorithm::HmacSha256);
+        let sha512 = entry(
+            b"1234567890123456789012345678901234567890123456789012345678901234",
+            8,
+            TotpAlgorithm::HmacSha512,
+        );
+        let vectors: [(u64, &str, &str, &str); 6] = [
+            (59, "94287082", "46119246", "90693936"),
+            (1111111109, "07081804", "68084774", "25091201"),
+            (1111111111, "14050471", "67062674", "99943326"),
+            (1234567890, "89005924", "91819424", "93441116"),
+            (2000000000, "69279037", "90698825", "38618901"),
+            (20000000000, "65353130", "77737706", "47863826"),
+        ];
+        for (time, code1, code256, code512) in vectors.iter() {
+            assert_eq!(generate_totp_code(*time, &sha1).unwrap(), *code1, "SHA1 at {}", time);
+            assert_eq!(generate_totp_code(*time, &sha256).unwrap(), *code256, "SHA256 at {}", time);
+            assert_eq!(generate_totp_code(*time, &sha512).unwrap(), *code512, "SHA512 at {}", time);
+        }
+    }
+
+    #[test]
+    fn short_codes_keep_leading_zeros() {
+        // the 8-digit code at this time is 07081804
+        let sha1 = entry(b"12345678901234567890", 6, TotpAlgorithm::HmacSha1);
+        assert_eq!(generate_totp_code(1111111109, &sha1).unwrap(), "081804");
+    }
+
+    #[test]
+    fn steam_guard_vector() {
+        // from the test suite of the ValvePython `steam` library
+        let steam = entry(b"superdupersecret", STEAM_DIGITS as u8, TotpAlgorithm::Steam);
+        assert_eq!(generate_totp_code(3000030, &steam).unwrap(), "YRGQJ");
+        assert_eq!(generate_totp_code(3000029, &steam).unwrap(), "94R9D");
+    }
+}
@@ -11,10 +11,11 @@ use graphics_server::{DrawStyle, Gid, PixelColor, Point, Rectangle, TextView};
 use locales::t;
 use num_traits::*;
 use pddb::Pddb;
-use usb_device_xous::UsbDeviceType;
+use usb_device_xous::{HostLayout, UnicodeEntry, UsbDeviceType};
 use vault::{utc_now, VaultOp};
 
 use crate::actions::ActionOp;
+use crate::itemcache::SortOrder;
 use crate::totp::{generate_totp_code, get_current_unix_time, TotpAlgorithm, TotpEntry};
 use crate::{ItemLists, SelectedEntry, VaultMode};
 
@@ -100,6 +101,9 @@ fn style_to_name(style: &GlyphStyle) -> String {
 const TITLE_HEIGHT: i16 = 26;
 const VAULT_CONFIG_DICT: &'static str = "vault.config";
 const VAULT_CONFIG_KEY_FONT: &'static str = "fontstyle";
+const VAULT_CONFIG_KEY_SORT: &'static str = "sortorder";
+const SORT_NAME: &'static str = "name";
+const SORT_RECENT: &'static str = "recent";
 
 impl VaultUx {
     pub(crate) fn new(
@@ -288,6 +292,59 @@ impl VaultUx {
         self.get_glyph_style();
     }
 
+    pub(crate) fn get_sort_order(&mut self) {
+        let order = match self.pddb.borrow().get(
+            VAULT_CONFIG_DICT,
+            VAULT_CONFIG_KEY_SORT,
+            Some(pddb::PDDB_DEFAULT_SYSTEM_BASIS),
+            true,
+            true,
+            Some(32),
+            Some(vault::basis_change),
+        ) {
+            Ok(mut order_key) => {
+                let mut name_bytes = Vec::<u8>::new();
+                match order_key.read_to_end(&mut name_bytes) {
+                    Ok(_len) if name_bytes == SORT_RECENT.as_bytes() => SortOrder::Recent,
+                    _ => SortOrder::Name,
+                }
+            }
+            _ => {
+                log::warn!("PDDB access error reading sort order");
+                SortOrder::Name
+            }
+        };
+        self.item_lists.lock().unwrap().set_order(order);
+    }
+
+    pub(crate) fn set_sort_order(&mut self, order: SortOrder) {
+        self.pddb
+            .borrow()
+            .delete_key(VAULT_CONFIG_DICT, VAULT_CONFIG_KEY_SORT, Some(pddb::PDDB_DEFAULT_SYSTEM_BASIS))
+            .ok();
+
+        match self.pddb.borrow().get(
+            VAULT_CONFIG_DICT,
+            VAULT_CONFIG_KEY_SORT,
+            Some(pddb::PDDB_DEFAULT_SYSTEM_BASIS),
+            true,
+            true,
+            Some(32),
+            Some(vault::basis_change),
+        ) {
+            Ok(mut order_key) => {
+                let name = match order {
+                    SortOrder::Name => SORT_NAME,
+                    SortOrder::Recent => SORT_RECENT,
+                };
+                order_key.write(name.as_bytes()).ok();
+            }
+            _ => log::error!("PDDB access error writing sort order"),
+        };
+        self.pddb.borrow().sync().ok();
+        self.item_lists.lock().unwrap().set_order(order);
+    }
+
     pub(crate) fn nav(&mut self, dir: NavDir) {
         self.item_lists.lock().unwrap().nav((*self.mode.lock().unwrap()).clone(), dir);
     }
@@ -424,6 +481,7 @@ impl VaultUx {
         // this could lead to some race conditions that lead to awkward problems, we'll see, but it
         // is probably worth the performance improvement.
         let mode_at_entry = (*self.mode.lock().unwrap()).clone();
+        let previous_time = self.current_time;
 
         if mode_at_entry == VaultMode::Totp {
             // always redraw the title in TOTP mode
@@ -521,6 +579,20 @@ impl VaultUx {
                 // -1 because of the overlapping border
                 break;
             }
+            if mode_at_entry == VaultMode::Totp && !item.dirty {
+                // records with a period other than the 30 seconds of the duration bar roll over on their own
+                // schedule
+                let fields = item.extra.split(':').collect::<Vec<&str>>();
+                if fields.len() == 5 && fields[4].to_uppercase() == "TOTP" {
+                    if let Ok(step_seconds) = u64::from_str_radix(fields[2], 10) {
+                        if step_seconds != 0
+                            && previous_time / step_seconds != self.current_time / step_seconds
+                        {
+                            item.dirty = true;
+                        }
+                    }
+                }
+            }
             if item.dirty {
                 log::debug!("drawing {}", item.name());
                 let mut box_text = TextView::new(
@@ -607,6 +679,10 @@ impl VaultUx {
 
     pub(crate) fn set_autotype_delay_ms(&self, rate: usize) { self.usb_dev.set_autotype_delay_ms(rate); }
 
+    pub(crate) fn set_host_layout(&self, layout: HostLayout, unicode: UnicodeEntry) {
+        self.usb_dev.set_host_layout(layout, unicode);
+    }
+
     pub(crate) fn autotype(&mut self, type_username: bool) -> Result<(), xous::Error> {
         let mode_cache = (*self.mode.lock().unwrap()).clone();
         match mode_cache {
@@ -629,7 +705,11 @@ impl VaultUx {
                             Ok(_len) => {
                                 if let Some(mut pw) = crate::storage::PasswordRecord::try_from(data).ok() {
                                     let to_type = if type_username { &pw.username } else { &pw.password };
-                                    match self.usb_dev.send_str(to_type) {
+                                    let typed = match pw.layout {
+                                        Some(layout) => self.usb_dev.send_str_layout(to_type, layout),
+                                        None => self.usb_dev.send_str(to_type),
+                                    };
+                                    match typed {
                                         Ok(_) => {
                                             pw.count += 1;
                                             pw.atime = atime;
@@ -35,10 +35,16 @@ pub enum VaultOp {
     MenuChangeFont,
     MenuDeleteStage1,
     MenuEditStage1,
+    MenuEntryLayoutStage1,
+    MenuEntryGeneratorStage1,
+    MenuRotateStage1,
     MenuAutotype,
     MenuReadoutMode,
+    MenuFidoBackupMode,
     MenuAutotypeRate,
+    MenuAutotypeLayout,
     MenuLeftyMode,
+    MenuSortOrder,
 
     /// PDDB basis change
     BasisChange,
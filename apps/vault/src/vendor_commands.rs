@@ -3,9 +3,15 @@ use core::convert::TryFrom;
 use cbor::reader::DecoderError;
 use locales::t;
 use vault::ctap::hid::{send::HidPacketIterator, ChannelID, CtapHidCommand, Message};
+use vault::ctap::status_code::Ctap2StatusCode;
+use vault::ctap::storage::{deserialize_credential, iter_credentials, serialize_credential, store_credential};
+use vault::env::xous::XousEnv;
+use vault::env::Env;
 use vault::vault_api::{COMMAND_BACKUP_TOTP_CODES, COMMAND_RESET_SESSION, COMMAND_RESTORE_TOTP_CODES};
+use zeroize::Zeroizing;
 
-use crate::storage::{Error, PasswordRecord, TotpRecord};
+use crate::fido_backup::{SealingKey, MAX_PBKDF2_ITERATIONS, PBKDF2_ITERATIONS};
+use crate::storage::{Error, PASSWORD_HISTORY_LEN, PasswordRecord, PreviousPassword, TotpRecord};
 use crate::totp::TotpAlgorithm;
 // TODO(gsora): add something that checks whether or not a command works.
 
@@ -30,6 +36,8 @@ pub struct VendorSession {
 
     // TODO: write backup kind here
     backup_kind: Option<backup::PayloadType>,
+    /// the backup is only for listing the entries, so it leaves out their secrets
+    names_only: bool,
     is_backup: bool,
     backup_chunks: Option<backup::Wires>,
 }
@@ -108,6 +116,7 @@ pub fn handle_vendor_data(
         log::debug!("vendor data is of backup kind");
         current_state.is_backup = true;
         current_state.backup_kind = backup_type;
+        current_state.names_only = payload.get(1) == Some(&backup::NAMES_ONLY);
         return Ok(None); // Handle Backup
     }
 
@@ -149,7 +158,14 @@ pub fn handle_vendor_data(
     }
 }
 
-pub fn handle_vendor_command(session: &mut VendorSession, allow_host: bool) -> HidPacketIterator {
+/// `fido_passphrase` is only set while the user has FIDO backup mode open on the device; FIDO
+/// credentials are neither exported nor imported without it.
+pub fn handle_vendor_command(
+    session: &mut VendorSession,
+    allow_host: bool,
+    fido_passphrase: Option<&str>,
+    env: &mut XousEnv,
+) -> HidPacketIterator {
     let cmd = session.command;
     let payload = session.data.clone();
     let channel_id = session.channel_id;
@@ -159,14 +175,17 @@ pub fn handle_vendor_command(session: &mut VendorSession, allow_host: bool) -> H
 
     let payload = if allow_host {
         match session.command {
-            COMMAND_RESTORE_TOTP_CODES => match handle_restore(payload, &xns) {
+            COMMAND_RESTORE_TOTP_CODES => match handle_restore(payload, &xns, fido_passphrase, env) {
                 Ok(payload) => Message { cid: channel_id, cmd: cmd.into(), payload },
+                Err(BackupError::FidoBackupModeDisabled) => {
+                    error_message(channel_id, backup::FIDO_BACKUP_MODE_DISABLED)
+                }
                 Err(error) => {
                     log::error!("error while restoring codes: {:?}", error);
-                    error_message(channel_id, 41)
+                    error_message(channel_id, backup::RESTORE_FAILED)
                 }
             },
-            COMMAND_BACKUP_TOTP_CODES => match handle_backup(&xns, session) {
+            COMMAND_BACKUP_TOTP_CODES => match handle_backup(&xns, session, fido_passphrase, env) {
                 Ok(payload) => {
                     log::debug!("sending over chunk: {:?}", payload);
                     Message { cid: channel_id, cmd: cmd.into(), payload }
@@ -175,11 +194,14 @@ pub fn handle_vendor_command(session: &mut VendorSession, allow_host: bool) -> H
                     match error {
                         BackupError::NoMoreChunks => {
                             log::debug!("no more chunks to send via backup!");
-                            error_message(channel_id, 88) // TODO(gsora): make this a constant
+                            error_message(channel_id, backup::NO_MORE_CHUNKS)
+                        }
+                        BackupError::FidoBackupModeDisabled => {
+                            error_message(channel_id, backup::FIDO_BACKUP_MODE_DISABLED)
                         }
                         _ => {
                             log::error!("error while restoring codes: {:?}", error);
-                            error_message(channel_id, 42)
+                            error_message(channel_id, backup::BACKUP_FAILED)
                         }
                     }
                 }
@@ -187,7 +209,7 @@ pub fn handle_vendor_command(session: &mut VendorSession, allow_host: bool) -> H
             _ => error_message(channel_id, 0x33),
         }
     } else {
-        error_message(channel_id, 44)
+        error_message(channel_id, backup::HOST_READOUT_DISABLED)
     };
 
     HidPacketIterator::new(payload).unwrap()
@@ -200,7 +222,12 @@ enum BackupError {
     CborConversionError(backup::CborConversionError),
     PddbError(std::io::Error),
     StorageError(crate::storage::Error),
+    CtapError(Ctap2StatusCode),
     NoMoreChunks,
+    /// FIDO credentials were requested outside of FIDO backup mode
+    FidoBackupModeDisabled,
+    /// A sealed FIDO credential didn't open: wrong passphrase, or a damaged backup
+    FidoUnsealFailed,
 }
 
 impl From<DecoderError> for BackupError {
@@ -216,11 +243,20 @@ impl From<crate::storage::Error> for BackupError {
     }
 }
 
+impl From<Ctap2StatusCode> for BackupError {
+    fn from(sc: Ctap2StatusCode) -> Self { BackupError::CtapError(sc) }
+}
+
 impl From<backup::CborConversionError> for BackupError {
     fn from(cbe: backup::CborConversionError) -> Self { BackupError::CborConversionError(cbe) }
 }
 
-fn handle_restore(data: Vec<u8>, xns: &xous_names::XousNames) -> Result<Vec<u8>, BackupError> {
+fn handle_restore(
+    data: Vec<u8>,
+    xns: &xous_names::XousNames,
+    fido_passphrase: Option<&str>,
+    env: &mut XousEnv,
+) -> Result<Vec<u8>, BackupError> {
     log::debug!("handling restore");
     let mut storage = crate::storage::Manager::new(xns);
 
@@ -240,6 +276,7 @@ fn handle_restore(data: Vec<u8>, xns: &xous_names::XousNames) -> Result<Vec<u8>,
                     name: elem.name,
                     secret: elem.shared_secret,
                     algorithm: match elem.algorithm {
+                        _ if elem.steam => TotpAlgorithm::Steam,
                         backup::HashAlgorithms::SHA1 => TotpAlgorithm::HmacSha1,
                         backup::HashAlgorithms::SHA256 => TotpAlgorithm::HmacSha256,
                         backup::HashAlgorithms::SHA512 => TotpAlgorithm::HmacSha512,
@@ -248,7 +285,8 @@ fn handle_restore(data: Vec<u8>, xns: &xous_names::XousNames) -> Result<Vec<u8>,
                     timestep: elem.step_seconds,
                     ctime: 0, // Will be filled in later by storage::new_totp_record();
                     notes: t!("vault.notes", locales::LANG).to_string(),
-                    is_hotp: false,
+                    is_hotp: elem.hotp,
+                    tags: Vec::new(),
                 };
                 entries.push(Box::new(totp));
             }
@@ -266,11 +304,26 @@ fn handle_restore(data: Vec<u8>, xns: &xous_names::XousNames) -> Result<Vec<u8>,
                     count: 0,
                     ctime: 0,
                     atime: 0,
+                    layout: None,
+                    tags: Vec::new(),
+                    history: elem
+                        .history
+                        .into_iter()
+                        .take(PASSWORD_HISTORY_LEN)
+                        .map(|prev| PreviousPassword { password: prev.password, retired: prev.retired })
+                        .collect(),
+                    rotating: false,
+                    profile: None,
+                    policy: None,
                 };
 
                 entries.push(Box::new(password));
             }
         }
+        backup::DataPacket::Fido(fido_entries) => {
+            log::debug!("restoring fido");
+            return handle_fido_restore(fido_entries, fido_passphrase, env);
+        }
     };
 
     match storage.new_records(entries, None, false) {
@@ -286,7 +339,39 @@ fn handle_restore(data: Vec<u8>, xns: &xous_names::XousNames) -> Result<Vec<u8>,
     }
 }
 
-fn handle_backup(xns: &xous_names::XousNames, session: &mut VendorSession) -> Result<Vec<u8>, BackupError> {
+fn handle_fido_restore(
+    fido_entries: backup::FidoEntries,
+    fido_passphrase: Option<&str>,
+    env: &mut XousEnv,
+) -> Result<Vec<u8>, BackupError> {
+    let passphrase = fido_passphrase.ok_or(BackupError::FidoBackupModeDisabled)?;
+    if fido_entries.iterations == 0 || fido_entries.iterations > MAX_PBKDF2_ITERATIONS {
+        return Err(BackupError::FidoUnsealFailed);
+    }
+    let key = SealingKey::derive(passphrase, &fido_entries.salt, fido_entries.iterations);
+
+    // open everything before storing anything, so a wrong passphrase doesn't leave a partial restore
+    let mut credentials = vec![];
+    for (idx, sealed) in fido_entries.credentials.iter().enumerate() {
+        log::debug!("unsealing element {}", idx);
+        // the credential holds its private key
+        let serialized = Zeroizing::new(key.open(sealed).ok_or(BackupError::FidoUnsealFailed)?);
+        credentials.push(deserialize_credential(&serialized).ok_or(BackupError::FidoUnsealFailed)?);
+    }
+    // a credential with the same RP ID and user handle is replaced, so restoring twice is harmless
+    for credential in credentials {
+        store_credential(env, credential)?;
+    }
+
+    Ok(vec![0xca, 0xfe, 0xba, 0xbe])
+}
+
+fn handle_backup(
+    xns: &xous_names::XousNames,
+    session: &mut VendorSession,
+    fido_passphrase: Option<&str>,
+    env: &mut XousEnv,
+) -> Result<Vec<u8>, BackupError> {
     log::debug!(
         "entering backup handler, is_backup: {} has_backup_data: {}",
         session.is_backup(),
@@ -323,13 +408,14 @@ fn handle_backup(xns: &xous_names::XousNames, session: &mut VendorSession) -> Re
                     shared_secret: raw_code.secret,
                     digit_count: raw_code.digits,
                     algorithm: match raw_code.algorithm {
-                        TotpAlgorithm::HmacSha1 => backup::HashAlgorithms::SHA1,
+                        TotpAlgorithm::HmacSha1 | TotpAlgorithm::Steam => backup::HashAlgorithms::SHA1,
                         TotpAlgorithm::HmacSha256 => backup::HashAlgorithms::SHA256,
                         TotpAlgorithm::HmacSha512 => backup::HashAlgorithms::SHA512,
                         _ => panic!("invalid algorithm"),
                     },
                     name: raw_code.name,
                     hotp: raw_code.is_hotp,
+                    steam: matches!(raw_code.algorithm, TotpAlgorithm::Steam),
                 });
             }
 
@@ -347,12 +433,54 @@ fn handle_backup(xns: &xous_names::XousNames, session: &mut VendorSession) -> Re
                     username: raw_pass.username,
                     password: raw_pass.password,
                     notes: raw_pass.notes,
+                    history: raw_pass
+                        .history
+                        .into_iter()
+                        .map(|prev| backup::PreviousPassword {
+                            password: prev.password,
+                            retired: prev.retired,
+                        })
+                        .collect(),
                 });
             }
 
             backup::DataPacket::Password(backup::PasswordEntries(ret))
         }
+        backup::PayloadType::Fido => {
+            let passphrase = fido_passphrase.ok_or(BackupError::FidoBackupModeDisabled)?;
+
+            let mut iter_result = Ok(());
+            let credentials: Vec<_> =
+                iter_credentials(env, &mut iter_result)?.map(|(_, credential)| credential).collect();
+            iter_result?;
+
+            if session.names_only {
+                // the credentials are all sealed anyway, so a listing only has their count
+                let fido = backup::FidoEntries {
+                    credentials: vec![vec![]; credentials.len()],
+                    ..Default::default()
+                };
+                backup::DataPacket::Fido(fido)
+            } else {
+                let salt = crate::fido_backup::new_salt(env.rng());
+                let key = SealingKey::derive(passphrase, &salt, PBKDF2_ITERATIONS);
+
+                let mut ret = vec![];
+
+                for credential in credentials {
+                    let serialized = Zeroizing::new(serialize_credential(credential)?);
+                    ret.push(key.seal(env.rng(), &serialized));
+                }
+
+                backup::DataPacket::Fido(backup::FidoEntries {
+                    salt,
+                    iterations: PBKDF2_ITERATIONS,
+                    credentials: ret,
+                })
+            }
+        }
     };
+    let data = if session.names_only { data.without_secrets() } else { data };
 
     log::debug!("loading newly created backup data in session");
     session.load_backup_data(data);
@@ -39,6 +39,10 @@ pub fn otpauth_to_entry(uri: &url::Url) -> Result<backup::TotpEntry, anyhow::Err
             "period" => {
                 t.step_seconds = v.parse::<u64>()?;
             }
+            "encoder" if v == "steam" => {
+                t.steam = true;
+                t.digit_count = 5;
+            }
             k => {
                 bail!("unexpected parameter {} in URI: {}", k, uri)
             }
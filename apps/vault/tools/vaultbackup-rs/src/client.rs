@@ -0,0 +1,334 @@
+//! The host side of vault's CTAPHID vendor commands (`vendor_commands.rs` on the device),
+//! independent of how the bytes reach the device.
+
+use anyhow::Result;
+
+/// Vendor commands understood by vault.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    /// 0x71, `COMMAND_RESTORE_TOTP_CODES`
+    Restore,
+    /// 0x72, `COMMAND_BACKUP_TOTP_CODES`
+    Backup,
+    /// 0x74, `COMMAND_RESET_SESSION`
+    ResetSession,
+}
+
+#[derive(Debug)]
+pub enum TransportError {
+    /// The device answered with a CTAPHID error carrying this code
+    Device(u8),
+    Other(anyhow::Error),
+}
+
+pub trait Transport {
+    fn command(&mut self, command: Command, payload: &[u8]) -> Result<Vec<u8>, TransportError>;
+}
+
+#[derive(Debug)]
+pub enum ClientError {
+    HostReadoutDisabled,
+    FidoBackupModeDisabled,
+    RestoreFailed,
+    BackupFailed,
+    Device(u8),
+    UnexpectedReply(Vec<u8>),
+}
+
+impl std::fmt::Display for ClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClientError::HostReadoutDisabled => write!(
+                f,
+                "Host readout is not enabled, unable to proceed!\nPlease select 'Enable host readout' from the vault context menu first."
+            ),
+            ClientError::FidoBackupModeDisabled => write!(
+                f,
+                "FIDO backup mode is not enabled, unable to proceed!\nPlease select 'Back up or restore FIDO keys' from the vault context menu first."
+            ),
+            ClientError::RestoreFailed => {
+                write!(f, "the device couldn't restore the data, check its logs for details")
+            }
+            ClientError::BackupFailed => {
+                write!(f, "the device couldn't back up the data, check its logs for details")
+            }
+            ClientError::Device(code) => write!(f, "device returned code {}", code),
+            ClientError::UnexpectedReply(reply) => write!(f, "unexpected reply from device: {:?}", reply),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<TransportError> for anyhow::Error {
+    fn from(te: TransportError) -> Self {
+        match te {
+            TransportError::Device(backup::HOST_READOUT_DISABLED) => ClientError::HostReadoutDisabled.into(),
+            TransportError::Device(backup::FIDO_BACKUP_MODE_DISABLED) => {
+                ClientError::FidoBackupModeDisabled.into()
+            }
+            TransportError::Device(backup::RESTORE_FAILED) => ClientError::RestoreFailed.into(),
+            TransportError::Device(backup::BACKUP_FAILED) => ClientError::BackupFailed.into(),
+            TransportError::Device(code) => ClientError::Device(code).into(),
+            TransportError::Other(error) => error,
+        }
+    }
+}
+
+pub struct Client<T: Transport> {
+    transport: T,
+}
+
+impl<T: Transport> Client<T> {
+    /// Resets the device's vendor session, so a transfer interrupted earlier doesn't get in the way.
+    pub fn new(mut transport: T) -> Result<Self> {
+        transport.command(Command::ResetSession, &[])?;
+        log::debug!("sent session reset command");
+        Ok(Client { transport })
+    }
+
+    pub fn backup(&mut self, target: &backup::PayloadType) -> Result<backup::DataPacket> {
+        self.fetch(&[target.into()])
+    }
+
+    /// Like `backup`, but the device leaves the secrets out: enough to see what's on it.
+    pub fn list(&mut self, target: &backup::PayloadType) -> Result<backup::DataPacket> {
+        self.fetch(&[target.into(), backup::NAMES_ONLY])
+    }
+
+    fn fetch(&mut self, request: &[u8]) -> Result<backup::DataPacket> {
+        let mut data = vec![];
+        let mut idx = 0;
+        loop {
+            let reply = match self.transport.command(Command::Backup, request) {
+                Ok(reply) => reply,
+                Err(TransportError::Device(backup::NO_MORE_CHUNKS)) => break,
+                Err(error) => return Err(error.into()),
+            };
+
+            let raw_cbor = cbor::read(&reply).map_err(|_| ClientError::UnexpectedReply(reply.clone()))?;
+            let mut wire_data = backup::Wire::try_from(raw_cbor)?;
+
+            data.append(&mut wire_data.data);
+            log::debug!("received chunk {}", idx);
+            idx += 1;
+
+            if !wire_data.more_data {
+                break;
+            }
+        }
+
+        let raw_cbor = cbor::read(&data).map_err(|_| backup::CborConversionError::BadCbor)?;
+        Ok(backup::DataPacket::try_from(raw_cbor)?)
+    }
+
+    pub fn restore(&mut self, packet: backup::DataPacket) -> Result<()> {
+        let chunks = backup::Wires::from(packet);
+        log::debug!("preparing to send {} chunks", chunks.len());
+
+        for (idx, chunk) in chunks.iter().enumerate() {
+            log::debug!("sending chunk {}", idx);
+            let chunk_bytes: Vec<u8> = chunk.into();
+            let reply = self.transport.command(Command::Restore, &chunk_bytes)?;
+
+            if reply.eq(backup::CONTINUE_RESPONSE) {
+                log::debug!("received CONTINUE response");
+                continue;
+            }
+
+            if reply.ne(backup::OKAY_CANARY) {
+                return Err(ClientError::UnexpectedReply(reply))?;
+            }
+        }
+
+        log::debug!("finished!");
+        Ok(())
+    }
+}
+
+/// One line per entry, without any secrets.
+pub fn describe(packet: &backup::DataPacket) -> Vec<String> {
+    match packet {
+        backup::DataPacket::Password(passwords) => {
+            passwords.0.iter().map(|pw| format!("{} ({})", pw.description, pw.username)).collect()
+        }
+        backup::DataPacket::TOTP(totps) => totps
+            .0
+            .iter()
+            .map(|t| {
+                let kind = if t.steam {
+                    "Steam"
+                } else if t.hotp {
+                    "HOTP"
+                } else {
+                    "TOTP"
+                };
+                format!("{} ({})", t.name, kind)
+            })
+            .collect(),
+        // the relying parties are sealed along with the keys
+        backup::DataPacket::Fido(fido) => {
+            vec![format!("{} sealed FIDO2 credentials", fido.credentials.len())]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Stands in for vault's vendor command handler.
+    #[derive(Default)]
+    struct MockDevice {
+        readout: bool,
+        passwords: Vec<backup::PasswordEntry>,
+        totps: Vec<backup::TotpEntry>,
+        outgoing: Option<backup::Wires>,
+        incoming: Vec<u8>,
+    }
+
+    impl Transport for &mut MockDevice {
+        fn command(&mut self, command: Command, payload: &[u8]) -> Result<Vec<u8>, TransportError> {
+            if command == Command::ResetSession {
+                self.outgoing = None;
+                self.incoming.clear();
+                return Ok(backup::OKAY_CANARY.to_vec());
+            }
+            if !self.readout {
+                return Err(TransportError::Device(backup::HOST_READOUT_DISABLED));
+            }
+            match command {
+                Command::Backup => {
+                    if self.outgoing.is_none() {
+                        let packet = match backup::PayloadType::try_from(&payload.to_vec()) {
+                            Ok(backup::PayloadType::Password) => {
+                                backup::DataPacket::Password(backup::PasswordEntries(self.passwords.clone()))
+                            }
+                            Ok(backup::PayloadType::TOTP) => {
+                                backup::DataPacket::TOTP(backup::TotpEntries(self.totps.clone()))
+                            }
+                            Ok(backup::PayloadType::Fido) => {
+                                return Err(TransportError::Device(backup::FIDO_BACKUP_MODE_DISABLED));
+                            }
+                            Err(_) => return Err(TransportError::Device(backup::BACKUP_FAILED)),
+                        };
+                        let packet = if payload.get(1) == Some(&backup::NAMES_ONLY) {
+                            packet.without_secrets()
+                        } else {
+                            packet
+                        };
+                        let mut wires = backup::Wires::from(packet);
+                        wires.reverse();
+                        self.outgoing = Some(wires);
+                    }
+                    match self.outgoing.as_mut().unwrap().pop() {
+                        Some(wire) => Ok((&wire).into()),
+                        None => Err(TransportError::Device(backup::NO_MORE_CHUNKS)),
+                    }
+                }
+                Command::Restore => {
+                    let wire = backup::Wire::try_from(cbor::read(payload).unwrap()).unwrap();
+                    self.incoming.extend_from_slice(&wire.data);
+                    if wire.more_data {
+                        return Ok(backup::CONTINUE_RESPONSE.to_vec());
+                    }
+                    let packet = backup::DataPacket::try_from(cbor::read(&self.incoming).unwrap()).unwrap();
+                    self.incoming.clear();
+                    match packet {
+                        backup::DataPacket::Password(passwords) => self.passwords.extend(passwords.0),
+                        backup::DataPacket::TOTP(totps) => self.totps.extend(totps.0),
+                        backup::DataPacket::Fido(_) => {
+                            return Err(TransportError::Device(backup::FIDO_BACKUP_MODE_DISABLED));
+                        }
+                    }
+                    Ok(backup::OKAY_CANARY.to_vec())
+                }
+                Command::ResetSession => unreachable!(),
+            }
+        }
+    }
+
+    fn password(idx: usize) -> backup::PasswordEntry {
+        backup::PasswordEntry {
+            description: format!("site {}", idx),
+            username: format!("user {}", idx),
+            password: format!("hunter{}", idx),
+            // long enough that a couple hundred of them take several chunks
+            notes: "n".repeat(100),
+            history: vec![],
+        }
+    }
+
+    #[test]
+    fn test_backup_multiple_chunks() {
+        let mut device =
+            MockDevice { readout: true, passwords: (0..200).map(password).collect(), ..Default::default() };
+        let mut client = Client::new(&mut device).unwrap();
+
+        let packet = client.backup(&backup::PayloadType::Password).unwrap();
+        match &packet {
+            backup::DataPacket::Password(passwords) => {
+                assert_eq!(passwords.0.len(), 200);
+                assert_eq!(passwords.0[199].password, "hunter199");
+            }
+            other => panic!("expected passwords, got {:?}", other),
+        }
+        assert_eq!(describe(&packet)[0], "site 0 (user 0)");
+    }
+
+    #[test]
+    fn test_list_leaves_out_secrets() {
+        let mut device =
+            MockDevice { readout: true, passwords: (0..200).map(password).collect(), ..Default::default() };
+        let mut client = Client::new(&mut device).unwrap();
+
+        let packet = client.list(&backup::PayloadType::Password).unwrap();
+        match &packet {
+            backup::DataPacket::Password(passwords) => {
+                assert_eq!(passwords.0.len(), 200);
+                assert!(passwords.0.iter().all(|pw| pw.password.is_empty() && pw.notes.is_empty()));
+            }
+            other => panic!("expected passwords, got {:?}", other),
+        }
+        assert_eq!(describe(&packet)[199], "site 199 (user 199)");
+    }
+
+    #[test]
+    fn test_restore_then_backup() {
+        let mut device = MockDevice { readout: true, ..Default::default() };
+        let totp = backup::TotpEntry {
+            step_seconds: 30,
+            shared_secret: "JBSWY3DPEHPK3PXP".to_string(),
+            digit_count: 6,
+            name: "example".to_string(),
+            ..Default::default()
+        };
+        {
+            let mut client = Client::new(&mut device).unwrap();
+            client.restore(backup::DataPacket::TOTP(backup::TotpEntries(vec![totp]))).unwrap();
+            client
+                .restore(backup::DataPacket::Password(backup::PasswordEntries(
+                    (0..200).map(password).collect(),
+                )))
+                .unwrap();
+        }
+        assert_eq!(device.passwords.len(), 200);
+
+        let mut client = Client::new(&mut device).unwrap();
+        let packet = client.backup(&backup::PayloadType::TOTP).unwrap();
+        assert_eq!(describe(&packet), vec!["example (TOTP)".to_string()]);
+    }
+
+    #[test]
+    fn test_device_errors() {
+        let mut device = MockDevice::default();
+        let mut client = Client::new(&mut device).unwrap();
+        let error = client.backup(&backup::PayloadType::TOTP).unwrap_err();
+        assert!(matches!(error.downcast_ref(), Some(ClientError::HostReadoutDisabled)));
+
+        device.readout = true;
+        let mut client = Client::new(&mut device).unwrap();
+        let error = client.backup(&backup::PayloadType::Fido).unwrap_err();
+        assert!(matches!(error.downcast_ref(), Some(ClientError::FidoBackupModeDisabled)));
+    }
+}
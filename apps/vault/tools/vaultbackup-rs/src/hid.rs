@@ -0,0 +1,54 @@
+use anyhow::Result;
+
+use crate::client::{Command, Transport, TransportError};
+use crate::ProgramError;
+
+const PRECURSOR_VENDOR_ID: u16 = 0x1209;
+const PRECURSOR_PRODUCT_ID: u16 = 0x3613;
+
+/// A Precursor reached over USB HID.
+pub struct HidTransport {
+    device: ctaphid::Device,
+}
+
+impl HidTransport {
+    pub fn open() -> Result<Self> {
+        let ha = hidapi::HidApi::new()?;
+        let dl = ha.device_list();
+
+        let mut precursor: Option<&hidapi::DeviceInfo> = None;
+        for i in dl {
+            if i.product_id() == PRECURSOR_PRODUCT_ID && i.vendor_id() == PRECURSOR_VENDOR_ID {
+                precursor = Some(i);
+                break;
+            }
+        }
+
+        if precursor.is_none() {
+            return Err(ProgramError::NoDevicesFound)?;
+        }
+
+        log::info!("connecting to device...");
+        let device = ctaphid::Device::connect(&ha, precursor.unwrap())?;
+        log::info!("connected!");
+
+        Ok(HidTransport { device })
+    }
+}
+
+impl Transport for HidTransport {
+    fn command(&mut self, command: Command, payload: &[u8]) -> Result<Vec<u8>, TransportError> {
+        let vc = match command {
+            Command::Restore => ctaphid::command::VendorCommand::H71,
+            Command::Backup => ctaphid::command::VendorCommand::H72,
+            Command::ResetSession => ctaphid::command::VendorCommand::H74,
+        };
+
+        self.device.vendor_command(vc, payload).map_err(|error| match error {
+            ctaphid::error::Error::DeviceError(ctaphid::error::DeviceError::Unknown(value)) => {
+                TransportError::Device(value)
+            }
+            other => TransportError::Other(other.into()),
+        })
+    }
+}
@@ -47,6 +47,8 @@ pub enum ChatOp {
     UpdateBusy,
     /// Force update the busy bar, without rate throttling. Internal opcode.
     UpdateBusyForced,
+    /// Post a notification to the GAM on behalf of the Chat App
+    Notify,
     /// exit the application
     Quit,
 }
@@ -130,3 +132,9 @@ pub enum AuthorFlag {
 pub struct BusyMessage {
     pub busy_msg: xous_ipc::String<128>,
 }
+
+#[derive(Archive, Serialize, Deserialize, Debug)]
+pub struct Notify {
+    pub title: xous_ipc::String<64>,
+    pub body: xous_ipc::String<256>,
+}
@@ -429,6 +429,11 @@ pub fn server(
                 let s = buffer.to_original::<BusyMessage, _>().unwrap();
                 ui.set_status_text(s.busy_msg.as_str().unwrap());
             }
+            Some(ChatOp::Notify) => {
+                let buffer = unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                let n = buffer.to_original::<Notify, _>().unwrap();
+                ui.notify(n.title.as_str().unwrap_or(""), n.body.as_str().unwrap_or(""));
+            }
             Some(ChatOp::SetStatusIdleText) => {
                 let buffer = unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
                 let s = buffer.to_original::<BusyMessage, _>().unwrap();
@@ -655,6 +660,16 @@ pub fn cf_set_status_text(chat_cid: xous::CID, msg: &str) {
         .expect("internal error");
 }
 
+/// Posts a notification to the GAM, labelled with the Chat App's name. Unlike the Chat UI,
+/// this shows even when the Chat App isn't in the foreground.
+pub fn cf_notify(chat_cid: xous::CID, title: &str, body: &str) {
+    let notify = Notify { title: xous_ipc::String::from_str(title), body: xous_ipc::String::from_str(body) };
+    Buffer::into_buf(notify)
+        .expect("internal error")
+        .send(chat_cid, ChatOp::Notify as u32)
+        .expect("internal error");
+}
+
 pub fn cf_set_busy_state(chat_cid: xous::CID, run: bool) {
     xous::send_message(
         chat_cid,
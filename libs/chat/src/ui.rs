@@ -558,6 +558,10 @@ impl Ui {
     pub(crate) fn is_busy(&self) -> bool { self.status_tv.busy_animation_state.is_some() }
 
     /// Set the status bar text
+    pub(crate) fn notify(&self, title: &str, body: &str) {
+        self.gam.post_notification(self.token, title, body, gam::NotificationPriority::Low).ok();
+    }
+
     pub(crate) fn set_status_text(&mut self, msg: &str) {
         self.status_tv.clear_str();
         write!(self.status_tv, "{}", msg).ok();
@@ -13,6 +13,9 @@ const TIME_SERVER_UTC_OFFSET: &'static str = "utc_offset";
 /// This is the offset from UTC to the display time zone. This can vary when the user changes time zones.
 const TIME_SERVER_TZ_OFFSET: &'static str = "tz_offset";
 
+/// Number of commands the shell IME plugin remembers for each app, unless the user picks another
+pub const DEFAULT_SHELL_HISTORY_LEN: u32 = 32;
+
 #[derive(Debug)]
 pub enum Error {
     EncodeError(EncodeError),
@@ -49,7 +52,16 @@ pub struct UserPrefs {
     pub earpiece_volume: u32,
     pub headset_volume: u32,
     pub autotype_rate: usize,
+    /// `usb_device_xous::HostLayout` index that vault autotypes for
+    pub autotype_layout: u32,
+    /// `usb_device_xous::UnicodeEntry` index for characters that aren't on the autotype layout
+    pub autotype_unicode: u32,
     pub lefty_mode: bool,
+    pub custom_keymap: String,
+    /// bitmask of the keymaps with dead keys turned on, by `keyboard::KeyMap` index
+    pub dead_keys: u32,
+    /// commands the shell IME plugin remembers for each app; see `DEFAULT_SHELL_HISTORY_LEN`
+    pub shell_history_len: u32,
 }
 
 pub struct Manager {
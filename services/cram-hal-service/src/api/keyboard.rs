@@ -1,6 +1,10 @@
 use std::fmt::Display;
 
 pub const SERVER_NAME_KBD: &str = "_Matrix keyboard driver_";
+/// Emitted when the menu key is held down. The GAM interprets it as a request to lock the screen.
+pub const LOCK_SCREEN_KEY: char = '🔒';
+/// Emitted when F4 is held down. In a split view, the GAM moves input focus to the other pane.
+pub const SWITCH_PANE_KEY: char = '⇅';
 
 #[derive(Debug, Default, Copy, Clone)]
 pub struct ScanCode {
@@ -3,6 +3,7 @@ use xous::{send_message, Message};
 use xous_ipc::{Buffer, String};
 
 use crate::api::keyboard::*;
+pub use crate::api::keyboard::{LOCK_SCREEN_KEY, SWITCH_PANE_KEY};
 
 #[derive(Debug)]
 pub struct Keyboard {
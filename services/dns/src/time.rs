@@ -693,68 +693,30 @@ pub(crate) fn start_time_ux() {
                             }
                         }
 
-                        let mut secs: u8 = 0;
-                        let mut mins: u8 = 0;
-                        let mut hours: u8 = 0;
-                        let mut months: u8 = 0;
-                        let mut days: u8 = 0;
-                        let mut years: u8 = 0;
-
-                        let date = modals
-                            .alert_builder(t!("rtc.set_time_modal", locales::LANG))
-                            .field(
-                                Some(String::from(t!("rtc.month", locales::LANG))),
-                                Some(rtc_ux_validate_month),
+                        let tz = chrono::FixedOffset::east_opt((tz_offset_ms / 1000) as i32).unwrap();
+                        // start the spinners at whatever the clock currently says; it's often only a little
+                        // bit off
+                        let now = std::time::SystemTime::now()
+                            .duration_since(std::time::SystemTime::UNIX_EPOCH)
+                            .map(|d| tz.timestamp_millis_opt(d.as_millis() as i64).single())
+                            .unwrap_or(None);
+                        let (years, months, days) = modals
+                            .get_date(
+                                t!("rtc.set_date_modal", locales::LANG),
+                                now.map(|n| (n.year() as u32, n.month(), n.day())),
                             )
-                            .field(
-                                Some(String::from(t!("rtc.day", locales::LANG))),
-                                Some(rtc_ux_validate_day),
-                            )
-                            .field(
-                                Some(String::from(t!("rtc.year", locales::LANG))),
-                                Some(rtc_ux_validate_year),
-                            )
-                            .field(
-                                Some(String::from(t!("rtc.hour", locales::LANG))),
-                                Some(rtc_ux_validate_hour),
-                            )
-                            .field(
-                                Some(String::from(t!("rtc.minute", locales::LANG))),
-                                Some(rtc_ux_validate_minute),
-                            )
-                            .field(
-                                Some(String::from(t!("rtc.seconds", locales::LANG))),
-                                Some(rtc_ux_validate_seconds),
-                            )
-                            .build()
                             .expect("cannot get date from user");
-
-                        for (index, elem) in date.content().iter().enumerate() {
-                            let elem =
-                                elem.as_str().parse::<u8>().expect("pre-validated input failed to re-parse!");
-                            match index {
-                                0 => months = elem,
-                                1 => days = elem,
-                                2 => years = elem,
-                                3 => hours = elem,
-                                4 => mins = elem,
-                                5 => secs = elem,
-                                _ => {}
-                            }
-                        }
+                        let (hours, mins, secs) = modals
+                            .get_time(
+                                t!("rtc.set_time_modal", locales::LANG),
+                                now.map(|n| (n.hour(), n.minute(), n.second())),
+                            )
+                            .expect("cannot get time from user");
 
                         log::info!("Setting time: {}/{}/{} {}:{}:{}", months, days, years, hours, mins, secs);
-                        let new_dt = chrono::FixedOffset::east_opt((tz_offset_ms / 1000) as i32)
-                            .unwrap()
-                            .with_ymd_and_hms(
-                                years as i32 + 2000,
-                                months as u32,
-                                days as u32,
-                                hours as u32,
-                                mins as u32,
-                                secs as u32,
-                            )
-                            .unwrap();
+                        // the spinners only return valid dates and times
+                        let new_dt =
+                            tz.with_ymd_and_hms(years as i32, months, days, hours, mins, secs).unwrap();
                         xous::send_message(
                             timeserver_cid,
                             Message::new_scalar(
@@ -811,17 +773,6 @@ pub(crate) fn start_time_ux() {
     });
 }
 
-// RTC Ux helper functions
-#[derive(Debug, num_derive::FromPrimitive, num_derive::ToPrimitive)]
-pub(crate) enum ValidatorOp {
-    UxMonth,
-    UxDay,
-    UxYear,
-    UxHour,
-    UxMinute,
-    UxSeconds,
-}
-
 fn tz_ux_validator(input: TextEntryPayload) -> Option<ValidatorErr> {
     let text_str = input.as_str();
 
@@ -835,93 +786,3 @@ fn tz_ux_validator(input: TextEntryPayload) -> Option<ValidatorErr> {
     }
     None
 }
-
-fn rtc_ux_validate_month(input: TextEntryPayload) -> Option<ValidatorErr> {
-    let text_str = input.as_str();
-
-    let input = match text_str.parse::<u32>() {
-        Ok(input_int) => input_int,
-        _ => return Some(ValidatorErr::from_str(t!("rtc.integer_err", locales::LANG))),
-    };
-
-    if input < 1 || input > 12 {
-        return Some(ValidatorErr::from_str(t!("rtc.range_err", locales::LANG)));
-    }
-
-    None
-}
-
-fn rtc_ux_validate_day(input: TextEntryPayload) -> Option<ValidatorErr> {
-    let text_str = input.as_str();
-
-    let input = match text_str.parse::<u32>() {
-        Ok(input_int) => input_int,
-        _ => return Some(ValidatorErr::from_str(t!("rtc.integer_err", locales::LANG))),
-    };
-
-    if input < 1 || input > 31 {
-        return Some(ValidatorErr::from_str(t!("rtc.range_err", locales::LANG)));
-    }
-
-    None
-}
-
-fn rtc_ux_validate_year(input: TextEntryPayload) -> Option<ValidatorErr> {
-    let text_str = input.as_str();
-
-    let input = match text_str.parse::<u32>() {
-        Ok(input_int) => input_int,
-        _ => return Some(ValidatorErr::from_str(t!("rtc.integer_err", locales::LANG))),
-    };
-
-    if input > 99 {
-        return Some(ValidatorErr::from_str(t!("rtc.range_err", locales::LANG)));
-    }
-
-    None
-}
-
-fn rtc_ux_validate_hour(input: TextEntryPayload) -> Option<ValidatorErr> {
-    let text_str = input.as_str();
-
-    let input = match text_str.parse::<u32>() {
-        Ok(input_int) => input_int,
-        _ => return Some(ValidatorErr::from_str(t!("rtc.integer_err", locales::LANG))),
-    };
-
-    if input > 23 {
-        return Some(ValidatorErr::from_str(t!("rtc.range_err", locales::LANG)));
-    }
-
-    None
-}
-
-fn rtc_ux_validate_minute(input: TextEntryPayload) -> Option<ValidatorErr> {
-    let text_str = input.as_str();
-
-    let input = match text_str.parse::<u32>() {
-        Ok(input_int) => input_int,
-        _ => return Some(ValidatorErr::from_str(t!("rtc.integer_err", locales::LANG))),
-    };
-
-    if input > 59 {
-        return Some(ValidatorErr::from_str(t!("rtc.range_err", locales::LANG)));
-    }
-
-    None
-}
-
-fn rtc_ux_validate_seconds(input: TextEntryPayload) -> Option<ValidatorErr> {
-    let text_str = input.as_str();
-
-    let input = match text_str.parse::<u32>() {
-        Ok(input_int) => input_int,
-        _ => return Some(ValidatorErr::from_str(t!("rtc.integer_err", locales::LANG))),
-    };
-
-    if input > 59 {
-        return Some(ValidatorErr::from_str(t!("rtc.range_err", locales::LANG)));
-    }
-
-    None
-}
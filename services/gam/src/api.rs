@@ -7,6 +7,8 @@ pub use rkyv_enum::*;
 use xous_ipc::String;
 
 pub(crate) const SERVER_NAME_GAM: &str = "_Graphical Abstraction Manager_";
+/// Takes the key events of the keyboard driver, and nobody else's
+pub(crate) const SERVER_NAME_GAM_KEYBOARD: &str = "_GAM keyboard listener_";
 
 #[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Copy, Clone)]
 pub struct GamObject {
@@ -118,6 +120,58 @@ pub struct NameRegistration {
     pub auth_token: [u32; 4],
 }
 
+/// Maximum number of notifications retained in the GAM's history. Older entries are discarded first.
+pub const NOTIFICATION_HISTORY_LEN: usize = 16;
+
+#[derive(Debug, Copy, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct Notification {
+    /// name the posting app registered with the GAM; filled in by the GAM, and the only part of a
+    /// toast drawn as trusted text
+    pub source: String<64>,
+    pub title: String<64>,
+    pub body: String<256>,
+    pub priority: NotificationPriority,
+    /// milliseconds since boot at which the GAM received the notification; filled in by the GAM
+    pub timestamp_ms: u64,
+}
+
+#[derive(Debug, Copy, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub(crate) struct NotificationPost {
+    /// the token of the posting app, which the GAM turns into the notification's `source`
+    pub token: [u32; 4],
+    pub notification: Notification,
+}
+
+#[derive(Debug, Copy, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub(crate) struct NotificationHistory {
+    /// newest notification first
+    pub list: [Option<Notification>; NOTIFICATION_HISTORY_LEN],
+}
+
+#[derive(Debug, Copy, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub(crate) struct ScreenLockRegistration {
+    pub token: [u32; 4],
+    /// SID of the lock owner's server, and the opcode it wants lock requests (from the keyboard) sent to
+    pub listener: [u32; 4],
+    pub request_id: u32,
+}
+
+#[derive(Debug, Copy, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub(crate) struct ScreenLockState {
+    pub token: [u32; 4],
+    pub locked: bool,
+}
+
+#[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub(crate) struct SplitViewRequest {
+    pub token: [u32; 4],
+    /// context names of the apps to tile; `None` returns the focused app to the full screen
+    pub top: Option<String<128>>,
+    pub bottom: Option<String<128>>,
+    /// filled in by the GAM
+    pub result: Option<ActivationResult>,
+}
+
 #[derive(Debug, num_derive::FromPrimitive, num_derive::ToPrimitive)]
 pub(crate) enum Opcode {
     // clears a canvas with a given GID
@@ -177,7 +231,8 @@ pub(crate) enum Opcode {
     /// this is used internally to route input lines from the IMEF
     InputLine = 16,
 
-    /// passed to the keyboard server to notify me of a keyboard event
+    /// passed to the keyboard server to notify me of a keyboard event. It goes to the keyboard listener,
+    /// which relays it; the main loop drops it from any other sender.
     KeyboardEvent = 17,
 
     /// used to turn keyboard vibrate on and off
@@ -227,6 +282,29 @@ pub(crate) enum Opcode {
     /// Register a name that can acquire a token. This is only intended to be used with pre-registered apps
     #[cfg(feature = "unsafe-app-loading")]
     RegisterName = 34,
+
+    /// Post a notification to the notification center. Renders as a non-modal toast over the status bar.
+    PostNotification = 35,
+    /// Retrieve the notification history, newest first
+    NotificationHistory = 36,
+    /// Clear the notifications posted by the app holding the given token
+    ClearNotifications = 37,
+
+    /// Register the owner of the screen lock; only the status bar may do this, and only once
+    RegisterScreenLock = 38,
+    /// Lock or unlock the screen. Only accepted from the registered lock owner.
+    SetScreenLock = 39,
+    /// Query if the screen is locked
+    IsScreenLocked = 40,
+    /// Inject characters as if they were typed. Unlike `KeyboardEvent`, these are dropped while the
+    /// screen is locked.
+    TypeChars = 41,
+
+    /// Tile two chat apps one above the other, or return to a single app. Only the main menu and status
+    /// bar may do this.
+    SplitView = 42,
+    /// Query if the named app can be a pane of a split view, i.e. it has registered a chat layout
+    CanSplit = 43,
 }
 
 // small wart -- we have to reset the size of a modal to max size for resize computations
@@ -269,6 +347,7 @@ pub enum CanvasType {
     Modal,
     Menu,
     Status,
+    ScreenLock,
 }
 impl CanvasType {
     pub fn is_content(&self) -> bool {
@@ -57,3 +57,15 @@ pub enum MenuPayload {
     /// about it for now, it's mostly apirational...
     Memory(([u8; 256], usize)),
 }
+
+/// Priority of a notification posted to the GAM's notification center. The priority controls
+/// how long the toast stays up over the status bar, and how prominently it is drawn.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub enum NotificationPriority {
+    /// informational; shown briefly and recorded in the history
+    Low,
+    /// the default priority
+    Normal,
+    /// something the user should act on soon (e.g. battery critical). Drawn inverted, and held longer.
+    Urgent,
+}
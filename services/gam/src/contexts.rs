@@ -99,6 +99,18 @@ pub(crate) struct UxContext {
 }
 pub(crate) const BOOT_CONTEXT_TRUSTLEVEL: u8 = 254;
 
+/// Two chat apps tiled one above the other. Both panes are onscreen; only one has input focus.
+#[derive(Debug, Copy, Clone)]
+struct SplitView {
+    top: [u32; 4],
+    bottom: [u32; 4],
+}
+impl SplitView {
+    fn contains(&self, token: [u32; 4]) -> bool { self.top == token || self.bottom == token }
+
+    fn other(&self, token: [u32; 4]) -> [u32; 4] { if self.top == token { self.bottom } else { self.top } }
+}
+
 /*
   For now, app focus from menus is cooperative (menu items must relinquish focus).
   However, later on, I think it would be good to implement a press-hold to feature to
@@ -121,12 +133,14 @@ pub(crate) struct ContextManager {
     tt: ticktimer_server::Ticktimer,
     /// used to suppress the main menu from activating until the boot PIN has been requested
     allow_mainmenu: bool,
+    /// set when two apps share the screen
+    split: Option<SplitView>,
 }
 impl ContextManager {
     pub fn new(xns: &xous_names::XousNames) -> Self {
         // hook the keyboard event server and have it forward keys to our local main loop
         let kbd = keyboard::Keyboard::new(&xns).expect("can't connect to KBD");
-        kbd.register_listener(crate::api::SERVER_NAME_GAM, Opcode::KeyboardEvent as usize);
+        kbd.register_listener(crate::api::SERVER_NAME_GAM_KEYBOARD, Opcode::KeyboardEvent as usize);
 
         info!("acquiring connection to IMEF...");
         let mut imef = ime_plugin_api::ImeFrontEnd::new(&xns).expect("Couldn't connect to IME front end");
@@ -144,6 +158,7 @@ impl ContextManager {
             trng: trng::Trng::new(&xns).expect("couldn't connect to trng"),
             tt: ticktimer_server::Ticktimer::new().unwrap(),
             allow_mainmenu: false,
+            split: None,
         }
     }
 
@@ -399,6 +414,19 @@ impl ContextManager {
         clear: bool,
     ) -> Result<(), xous::Error> {
         // log::set_max_level(log::LevelFilter::Trace);
+        if let Some(split) = self.split {
+            // raising any other app takes the screen back from the split view
+            if !split.contains(token)
+                && self.get_context_by_token(token).map(|c| c.layout.behavior()) == Some(LayoutBehavior::App)
+            {
+                self.unsplit(canvases);
+            }
+        }
+        // moving focus between the panes of a split view leaves both of them onscreen
+        let switching_panes = match (self.split, self.focused_context) {
+            (Some(split), Some(focused)) => split.contains(token) && split.contains(focused),
+            _ => false,
+        };
         self.notify_app_switch(token).ok();
 
         let mut leaving_visibility: bool = false;
@@ -438,7 +466,7 @@ impl ContextManager {
                         {
                             log::debug!("resolved: app covering app");
                             context.layout.set_visibility_state(true, canvases);
-                            leaving_visibility = false;
+                            leaving_visibility = switching_panes;
                             self.context_stack.pop();
                             self.context_stack.push(token);
                         } else if
@@ -520,6 +548,7 @@ impl ContextManager {
                         predictor: context.predictor,
                         token: context.gam_token,
                         predictor_token: context.pred_token,
+                        app_name: self.tm.lookup_name(&token).map(|name| String::<64>::from_str(&name)),
                     };
                     log::debug!(
                         "context gam token: {:x?}, pred token: {:x?}",
@@ -667,34 +696,43 @@ impl ContextManager {
     }
 
     pub(crate) fn redraw(&self) -> Result<(), xous::Error> {
-        // redraws the currently focused context
+        // redraws the currently focused context, and its neighbor if it is in a split view
         if let Some(token) = self.focused_app() {
-            if let Some(context) = self.contexts.get(&token) {
-                log::debug!(
-                    "redraw msg to {:?} ({}, id {})",
-                    self.tm.lookup_name(&token),
-                    context.listener,
-                    context.redraw_id
-                );
-                let ret = match xous::try_send_message(
-                    context.listener,
-                    xous::Message::new_scalar(context.redraw_id as usize, 0, 0, 0, 0),
-                ) {
-                    Err(xous::Error::ServerQueueFull) => {
-                        log::warn!("server queue full, redraw skipped");
-                        Ok(())
-                    }
-                    Ok(_r) => Ok(()),
-                    Err(e) => Err(e),
-                };
-                // this delay helps ensure that the previously requested UX redraw has time to complete
-                // in particular, this helps sequence the case where one modal is erased, and the next one is
-                // raised, in quick succession.
-                self.tt.sleep_ms(20).unwrap();
-                return ret;
+            if let Some(split) = self.split {
+                if split.contains(token) {
+                    self.redraw_context(split.other(token)).ok();
+                }
             }
+            self.redraw_context(token)
         } else {
-            return Err(xous::Error::UseBeforeInit);
+            Err(xous::Error::UseBeforeInit)
+        }
+    }
+
+    fn redraw_context(&self, token: [u32; 4]) -> Result<(), xous::Error> {
+        if let Some(context) = self.contexts.get(&token) {
+            log::debug!(
+                "redraw msg to {:?} ({}, id {})",
+                self.tm.lookup_name(&token),
+                context.listener,
+                context.redraw_id
+            );
+            let ret = match xous::try_send_message(
+                context.listener,
+                xous::Message::new_scalar(context.redraw_id as usize, 0, 0, 0, 0),
+            ) {
+                Err(xous::Error::ServerQueueFull) => {
+                    log::warn!("server queue full, redraw skipped");
+                    Ok(())
+                }
+                Ok(_r) => Ok(()),
+                Err(e) => Err(e),
+            };
+            // this delay helps ensure that the previously requested UX redraw has time to complete
+            // in particular, this helps sequence the case where one modal is erased, and the next one is
+            // raised, in quick succession.
+            self.tt.sleep_ms(20).unwrap();
+            return ret;
         }
         Err(xous::Error::ServerNotFound)
     }
@@ -711,8 +749,25 @@ impl ContextManager {
 
     pub(crate) fn find_app_token_by_name(&self, name: &str) -> Option<[u32; 4]> { self.tm.find_token(name) }
 
+    pub(crate) fn app_name_by_token(&self, token: [u32; 4]) -> Option<std::string::String> {
+        self.tm.lookup_name(&token)
+    }
+
     pub(crate) fn focused_app(&self) -> Option<[u32; 4]> { self.focused_context }
 
+    /// True if the focused context is one of the password boxes, which are allowed to take input while the
+    /// screen is locked (that's how the unlock PIN gets entered).
+    pub(crate) fn is_password_modal_focused(&self) -> bool {
+        if let Some(token) = self.focused_context {
+            match self.tm.lookup_name(&token) {
+                Some(name) => name == gam::ROOTKEY_MODAL_NAME || name == gam::PDDB_MODAL_NAME,
+                None => false,
+            }
+        } else {
+            false
+        }
+    }
+
     pub(crate) fn forward_input(&self, input: String<4000>) -> Result<(), xous::Error> {
         if let Some(token) = self.focused_app() {
             if let Some(context) = self.contexts.get(&token) {
@@ -731,10 +786,22 @@ impl ContextManager {
 
     pub(crate) fn key_event(
         &mut self,
-        keys: [char; 4],
+        mut keys: [char; 4],
         gfx: &graphics_server::Gfx,
         canvases: &mut HashMap<Gid, Canvas>,
     ) {
+        if keys[0] == keyboard::SWITCH_PANE_KEY {
+            match (self.split, self.focused_context) {
+                (Some(split), Some(focused)) if split.contains(focused) => {
+                    if self.activate(gfx, canvases, split.other(focused), false).is_err() {
+                        log::warn!("Couldn't switch split view panes");
+                    }
+                    return;
+                }
+                // outside of a split view, a long press on F4 is just F4
+                _ => keys[0] = 0x14_u8.into(),
+            }
+        }
         // only pop up the menu if the primary key hit is the menu key (search just the first entry of keys);
         // reject multi-key hits only pop up the menu if it isn't already popped up
         if keys[0] == '∴' {
@@ -834,4 +901,112 @@ impl ContextManager {
         }
         Err(xous::Error::ProcessNotFound)
     }
+
+    /// True if the named context exists and can be tiled by `split_view`.
+    pub(crate) fn can_split(&self, name: &str) -> bool {
+        match self.find_app_token_by_name(name).and_then(|token| self.get_context_by_token(token)) {
+            Some(context) => matches!(context.layout, UxLayout::ChatLayout(_)),
+            None => false,
+        }
+    }
+
+    /// Tiles two chat apps one above the other, with input focus on the bottom one. `None` returns the
+    /// focused pane to the full screen. Trust levels are unchanged; the panes just don't overlap.
+    pub(crate) fn split_view(
+        &mut self,
+        gfx: &graphics_server::Gfx,
+        canvases: &mut HashMap<Gid, Canvas>,
+        status_cliprect: &Rectangle,
+        panes: Option<([u32; 4], [u32; 4])>,
+    ) -> Result<(), xous::Error> {
+        // only rearrange the screen from under an app, not from under a menu or modal
+        match self.focused_context() {
+            Some(context) if context.layout.behavior() == LayoutBehavior::App => (),
+            _ => return Err(xous::Error::ShareViolation),
+        }
+        let (top, bottom) = match panes {
+            Some((top, bottom)) => {
+                if top == bottom {
+                    return Err(xous::Error::InvalidString);
+                }
+                (top, bottom)
+            }
+            None => {
+                if self.split.is_some() {
+                    self.unsplit(canvases);
+                    if let Some(context) = self.focused_context() {
+                        context.layout.clear(gfx, canvases)?;
+                    }
+                    self.refresh_panes(gfx, canvases)?;
+                }
+                return Ok(());
+            }
+        };
+        for token in [top, bottom] {
+            match self.get_context_by_token(token).map(|c| c.layout) {
+                Some(UxLayout::ChatLayout(_)) => (),
+                _ => return Err(xous::Error::AccessDenied),
+            }
+        }
+        self.unsplit(canvases);
+
+        let screensize = gfx.screen_size()?;
+        let area_top = status_cliprect.br().y + 1;
+        let middle = area_top + (screensize.y - area_top) / 2;
+        let bands = [
+            (top, Rectangle::new_coords(0, area_top, screensize.x, middle - 1)),
+            (bottom, Rectangle::new_coords(0, middle + 1, screensize.x, screensize.y)),
+        ];
+        for (token, band) in bands {
+            if let Some(context) = self.get_context_by_token_mut(token) {
+                if let UxLayout::ChatLayout(chat) = &mut context.layout {
+                    chat.tile(Some(band), canvases);
+                }
+                context.layout.set_visibility_state(true, canvases);
+                context.layout.clear(gfx, canvases)?;
+            }
+        }
+        // the one-pixel gap between the bands belongs to no canvas; use it as a divider
+        gfx.draw_line(Line::new_with_style(
+            Point::new(0, middle),
+            Point::new(screensize.x, middle),
+            DrawStyle::new(PixelColor::Dark, PixelColor::Dark, 1),
+        ))?;
+
+        self.split = Some(SplitView { top, bottom });
+        if self.focused_context != Some(bottom) {
+            self.activate(gfx, canvases, bottom, false)
+        } else {
+            self.refresh_panes(gfx, canvases)
+        }
+    }
+
+    /// Returns both panes of a split view to full size, and takes the one without focus offscreen.
+    fn unsplit(&mut self, canvases: &mut HashMap<Gid, Canvas>) {
+        if let Some(split) = self.split.take() {
+            for token in [split.top, split.bottom] {
+                let focused = self.focused_context == Some(token);
+                if let Some(context) = self.get_context_by_token_mut(token) {
+                    if let UxLayout::ChatLayout(chat) = &mut context.layout {
+                        chat.tile(None, canvases);
+                    }
+                    if !focused {
+                        context.layout.set_visibility_state(false, canvases);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Redraws after the panes were rearranged without a change of focus.
+    fn refresh_panes(
+        &self,
+        gfx: &graphics_server::Gfx,
+        canvases: &mut HashMap<Gid, Canvas>,
+    ) -> Result<(), xous::Error> {
+        recompute_canvases(canvases);
+        deface(gfx, &self.trng, canvases);
+        self.redraw_imef()?;
+        self.redraw()
+    }
 }
@@ -19,6 +19,9 @@ pub(crate) struct ChatLayout {
     // my internal bookkeeping records. Allow input area to grow into content area
     min_content_height: i16,
     min_input_height: i16,
+    /// the horizontal strip of the screen the layout is tiled into when in split view
+    band: Option<Rectangle>,
+    status_cliprect: Rectangle,
     _screensize: Point,
     _small_height: i16,
     _regular_height: i16,
@@ -85,11 +88,40 @@ impl ChatLayout {
             input: input_gid,
             min_content_height: 64,
             min_input_height,
+            band: None,
+            status_cliprect: *status_cliprect,
             _screensize: screensize,
             _small_height: small_height,
             _regular_height: regular_height,
         })
     }
+
+    /// Lays the chat out inside `band` for split view, or back over the whole area under the status bar
+    /// if `band` is `None`. The input area snaps back to its minimum height; trust levels are not touched.
+    pub fn tile(&mut self, band: Option<Rectangle>, canvases: &mut HashMap<Gid, Canvas>) {
+        self.band = band;
+        let bottom = if let Some(band) = band { band.br.y } else { self._screensize.y };
+        // the predictive area is the same height as the minimum input area
+        let predictive_rect =
+            Rectangle::new_coords(0, bottom - self.min_input_height, self._screensize.x, bottom);
+        let input_rect = Rectangle::new_v_stack(predictive_rect, -self.min_input_height);
+        let content_rect = Rectangle::new_v_span(self.above(), input_rect);
+        canvases
+            .get_mut(&self.predictive)
+            .expect("couldn't find predictive canvas")
+            .set_clip(predictive_rect);
+        canvases.get_mut(&self.input).expect("couldn't find input canvas").set_clip(input_rect);
+        canvases.get_mut(&self.content).expect("couldn't find content canvas").set_clip(content_rect);
+    }
+
+    /// the rectangle that the content area hangs under: the status bar, or the line just above our band
+    fn above(&self) -> Rectangle {
+        if let Some(band) = self.band {
+            Rectangle::new_coords(band.tl.x, band.tl.y - 1, band.br.x, band.tl.y - 1)
+        } else {
+            self.status_cliprect
+        }
+    }
 }
 impl LayoutApi for ChatLayout {
     fn behavior(&self) -> LayoutBehavior { LayoutBehavior::App }
@@ -121,7 +153,7 @@ impl LayoutApi for ChatLayout {
         &mut self,
         gfx: &graphics_server::Gfx,
         new_height: i16,
-        status_canvas: &Rectangle,
+        _status_canvas: &Rectangle,
         canvases: &mut HashMap<Gid, Canvas>,
     ) -> Result<Point, xous::Error> {
         let input_canvas = canvases.get(&self.input).expect("couldn't find input canvas");
@@ -129,7 +161,7 @@ impl LayoutApi for ChatLayout {
 
         let height: i16 = if new_height < self.min_input_height { self.min_input_height } else { new_height };
         let mut new_input_rect = Rectangle::new_v_stack(predictive_canvas.clip_rect(), -height);
-        let mut new_content_rect = Rectangle::new_v_span(*status_canvas, new_input_rect);
+        let mut new_content_rect = Rectangle::new_v_span(self.above(), new_input_rect);
         if (new_content_rect.br.y - new_content_rect.tl.y) > self.min_content_height {
             {
                 let input_canvas_mut = canvases.get_mut(&self.input).expect("couldn't find input canvas");
@@ -12,6 +12,10 @@ pub mod apps;
 pub use apps::*;
 #[cfg(feature = "ditherpunk")]
 pub mod bitmap;
+/// The BIP-39 wordlist of the GAM server, for apps that need the words themselves
+#[path = "bip39/en.rs"]
+mod bip39_wordlist;
+pub use bip39_wordlist::BIP39_TABLE;
 use api::Opcode; // if you prefer to map the api into your local namespace
 #[cfg(feature = "ditherpunk")]
 pub use bitmap::{Bitmap, DecodePng, Img, PixelType};
@@ -21,7 +25,7 @@ pub use graphics_server::api::PixelColor;
 pub use graphics_server::api::Tile;
 pub use graphics_server::api::{Circle, Gid, Line, RoundedRectangle, TokenClaim};
 pub use graphics_server::api::{Point, Rectangle};
-pub use graphics_server::api::{TextOp, TextView};
+pub use graphics_server::api::{TextDirection, TextOp, TextView};
 use ime_plugin_api::{ApiToken, ImefCallback};
 use num_traits::*;
 use xous::{send_message, Message, CID};
@@ -307,7 +311,7 @@ impl Gam {
             send_message(
                 self.conn,
                 Message::new_scalar(
-                    Opcode::KeyboardEvent.to_usize().unwrap(),
+                    Opcode::TypeChars.to_usize().unwrap(),
                     chunk.chars().nth(0).unwrap() as usize,
                     if chunk.len() > 1 { chunk.chars().nth(1).unwrap() } else { '\u{0000}' } as usize,
                     if chunk.len() > 2 { chunk.chars().nth(2).unwrap() } else { '\u{0000}' } as usize,
@@ -453,6 +457,49 @@ impl Gam {
             .map(|_| ())
     }
 
+    /// Tiles the `top` and `bottom` apps so both are visible at once, with input focus on `bottom`. The
+    /// focus can be moved between them with a long press on F4. Both apps must use a chat layout.
+    /// Returns `ShareViolation` if the GAM refused, e.g. because an app is not running or a menu is up.
+    pub fn split_view(&self, top: &str, bottom: &str, token: [u32; 4]) -> Result<(), xous::Error> {
+        self.request_split_view(SplitViewRequest {
+            token,
+            top: Some(String::<128>::from_str(top)),
+            bottom: Some(String::<128>::from_str(bottom)),
+            result: None,
+        })
+    }
+
+    /// Returns the focused pane of a split view to the full screen.
+    pub fn unsplit_view(&self, token: [u32; 4]) -> Result<(), xous::Error> {
+        self.request_split_view(SplitViewRequest { token, top: None, bottom: None, result: None })
+    }
+
+    fn request_split_view(&self, request: SplitViewRequest) -> Result<(), xous::Error> {
+        let mut buf = Buffer::into_buf(request).or(Err(xous::Error::InternalError))?;
+        buf.lend_mut(self.conn, Opcode::SplitView.to_u32().unwrap()).or(Err(xous::Error::InternalError))?;
+        match buf.to_original::<SplitViewRequest, _>().or(Err(xous::Error::InternalError))?.result {
+            Some(ActivationResult::Success) => Ok(()),
+            Some(ActivationResult::Failure) => Err(xous::Error::ShareViolation),
+            None => Err(xous::Error::InternalError),
+        }
+    }
+
+    /// True if `app_name` can be given to `split_view`: it is running and uses a chat layout.
+    pub fn can_split(&self, app_name: &str) -> bool {
+        let query = GamActivation { name: String::<128>::from_str(app_name), result: None };
+        let mut buf = match Buffer::into_buf(query) {
+            Ok(buf) => buf,
+            Err(_) => return false,
+        };
+        if buf.lend_mut(self.conn, Opcode::CanSplit.to_u32().unwrap()).is_err() {
+            return false;
+        }
+        match buf.to_original::<GamActivation, _>() {
+            Ok(query) => query.result == Some(ActivationResult::Success),
+            Err(_) => false,
+        }
+    }
+
     pub fn raise_menu(&self, menu_name_str: &str) -> Result<(), xous::Error> {
         let menu_name = GamActivation { name: String::<128>::from_str(menu_name_str), result: None };
         let mut buf = Buffer::into_buf(menu_name).or(Err(xous::Error::InternalError))?;
@@ -503,6 +550,97 @@ impl Gam {
         .expect("couldn't set debug level");
     }
 
+    /// Posts a notification to the GAM's notification center. The notification is shown as a toast
+    /// over the status bar without taking focus away from the current app, and recorded in the
+    /// notification history. `token` is the caller's GAM token: the toast is labelled with the name
+    /// the caller registered under, and the title and body are drawn as untrusted text.
+    pub fn post_notification(
+        &self,
+        token: [u32; 4],
+        title: &str,
+        body: &str,
+        priority: NotificationPriority,
+    ) -> Result<(), xous::Error> {
+        let post = NotificationPost {
+            token,
+            notification: Notification {
+                source: String::<64>::new(), // filled in by the GAM
+                title: String::<64>::from_str(title),
+                body: String::<256>::from_str(body),
+                priority,
+                timestamp_ms: 0, // filled in by the GAM
+            },
+        };
+        let buf = Buffer::into_buf(post).or(Err(xous::Error::InternalError))?;
+        buf.send(self.conn, Opcode::PostNotification.to_u32().unwrap())
+            .or(Err(xous::Error::InternalError))
+            .map(|_| ())
+    }
+
+    /// Returns the notification history, newest first.
+    pub fn notification_history(&self) -> Result<Vec<Notification>, xous::Error> {
+        let history = NotificationHistory { list: Default::default() };
+        let mut buf = Buffer::into_buf(history).or(Err(xous::Error::InternalError))?;
+        buf.lend_mut(self.conn, Opcode::NotificationHistory.to_u32().unwrap())
+            .or(Err(xous::Error::InternalError))?;
+        let result = buf.to_original::<NotificationHistory, _>().unwrap();
+        Ok(result.list.iter().filter_map(|n| *n).collect())
+    }
+
+    /// Clears the notifications posted by the app holding `token`. The status bar, which shows the
+    /// history to the user, clears all of them.
+    pub fn clear_notifications(&self, token: [u32; 4]) -> Result<(), xous::Error> {
+        send_message(
+            self.conn,
+            Message::new_scalar(
+                Opcode::ClearNotifications.to_usize().unwrap(),
+                token[0] as usize,
+                token[1] as usize,
+                token[2] as usize,
+                token[3] as usize,
+            ),
+        )
+        .map(|_| ())
+    }
+
+    /// Registers the caller as the owner of the screen lock. `sid` and `request_id` name the
+    /// server and opcode that receive a scalar message when the user asks for a lock from the keyboard.
+    /// Only the status bar can do this, and only once per boot.
+    pub fn register_screen_lock(
+        &self,
+        token: [u32; 4],
+        sid: xous::SID,
+        request_id: u32,
+    ) -> Result<(), xous::Error> {
+        let registration = ScreenLockRegistration { token, listener: sid.to_array(), request_id };
+        let buf = Buffer::into_buf(registration).or(Err(xous::Error::InternalError))?;
+        buf.send(self.conn, Opcode::RegisterScreenLock.to_u32().unwrap())
+            .or(Err(xous::Error::InternalError))
+            .map(|_| ())
+    }
+
+    /// Raises or drops the lock curtain. While locked, no canvas under the curtain can be drawn,
+    /// key events only reach the password modals, and `type_chars` is ignored.
+    pub fn set_screen_lock(&self, token: [u32; 4], locked: bool) -> Result<(), xous::Error> {
+        let state = ScreenLockState { token, locked };
+        let buf = Buffer::into_buf(state).or(Err(xous::Error::InternalError))?;
+        buf.send(self.conn, Opcode::SetScreenLock.to_u32().unwrap())
+            .or(Err(xous::Error::InternalError))
+            .map(|_| ())
+    }
+
+    pub fn is_screen_locked(&self) -> Result<bool, xous::Error> {
+        let response = send_message(
+            self.conn,
+            Message::new_blocking_scalar(Opcode::IsScreenLocked.to_usize().unwrap(), 0, 0, 0, 0),
+        )?;
+        if let xous::Result::Scalar1(locked) = response {
+            Ok(locked != 0)
+        } else {
+            Err(xous::Error::InternalError)
+        }
+    }
+
     pub fn bytes_to_bip39(&self, bytes: &Vec<u8>) -> Result<Vec<std::string::String>, xous::Error> {
         match bytes.len() {
             16 | 20 | 24 | 28 | 32 => (),
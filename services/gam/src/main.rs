@@ -13,13 +13,17 @@ use layouts::*;
 mod contexts;
 use contexts::*;
 mod bip39;
+mod notifications;
+use notifications::*;
+mod screenlock;
+use screenlock::*;
 
 use core::sync::atomic::{AtomicU32, Ordering};
 use std::collections::HashMap;
 
 use api::Opcode;
 #[cfg(feature = "cramium-soc")]
-use cram_hal_service::trng;
+use cram_hal_service::{keyboard, trng};
 use gam::{MAIN_MENU_NAME, ROOTKEY_MODAL_NAME};
 use graphics_server::*;
 use log::info;
@@ -47,6 +51,32 @@ fn main() -> ! {
     #[cfg(feature = "ditherpunk")]
     std::thread::Builder::new().stack_size(stack_size).spawn(wrapped_main).unwrap().join().unwrap()
 }
+/*
+    Key events come in on a server of their own, which only takes one connection, claimed by the
+    keyboard driver as the context manager registers with it:
+      - keyboard (key events, relayed to the main loop as `KeyboardEvent`)
+    The relay runs in this process, so the main loop can tell real key events from injected ones.
+*/
+fn start_keyboard_listener(xns: &xous_names::XousNames, gam_sid: xous::SID) {
+    let kbd_sid = xns.register_name(api::SERVER_NAME_GAM_KEYBOARD, Some(1)).expect("can't register server");
+    let gam_cid = xous::connect(gam_sid).unwrap();
+    std::thread::spawn(move || {
+        loop {
+            let msg = xous::receive_message(kbd_sid).unwrap();
+            match FromPrimitive::from_usize(msg.body.id()) {
+                Some(Opcode::KeyboardEvent) => msg_scalar_unpack!(msg, k1, k2, k3, k4, {
+                    xous::send_message(
+                        gam_cid,
+                        xous::Message::new_scalar(Opcode::KeyboardEvent.to_usize().unwrap(), k1, k2, k3, k4),
+                    )
+                    .ok();
+                }),
+                _ => log::error!("unhandled keyboard listener message {:?}", msg),
+            }
+        }
+    });
+}
+
 fn wrapped_main() -> ! {
     log_server::init_wait().unwrap();
     log::set_max_level(log::LevelFilter::Info);
@@ -62,6 +92,7 @@ fn wrapped_main() -> ! {
     let gfx = graphics_server::Gfx::new(&xns).expect("can't connect to GFX");
     let trng = trng::Trng::new(&xns).expect("can't connect to TRNG");
 
+    start_keyboard_listener(&xns, gam_sid);
     let mut context_mgr = ContextManager::new(&xns);
 
     // a map of canvases accessable by Gid
@@ -92,7 +123,9 @@ fn wrapped_main() -> ! {
     status_canvas.set_onscreen(true);
     status_canvas.set_drawable(true);
     let status_gid = status_canvas.gid().gid();
+    let status_gid_record = status_canvas.gid();
     canvases.insert(status_canvas.gid(), status_canvas);
+    let mut screen_lock = ScreenLock::new(&gfx, &trng, &status_cliprect, &mut canvases);
     recompute_canvases(&canvases);
 
     // initialize the status bar -- this needs to start late, after the IMEF and most other things are
@@ -124,6 +157,7 @@ fn wrapped_main() -> ! {
     let mut powerdown_requested = false;
     let mut last_time: u64 = ticktimer.elapsed_ms();
     let mut did_test = false; // allow one go at the test pattern
+    let mut notification_center = NotificationCenter::new();
     log::trace!("entering main loop");
 
     #[cfg(not(target_os = "xous"))]
@@ -217,6 +251,18 @@ fn wrapped_main() -> ! {
                             log::trace!("deface redraw");
                             context_mgr.redraw().expect("couldn't redraw after defacement");
                         }
+                        // the curtain goes down after the defacement, so nothing underneath it leaks out
+                        screen_lock.render(&gfx, &canvases);
+                        // toasts ride on top of the status bar, so they have to be re-applied after every
+                        // status update, right before the flush
+                        if notification_center.render_toast(
+                            &gfx,
+                            status_gid_record,
+                            &status_cliprect,
+                            elapsed_time,
+                        ) {
+                            log::debug!("notification toast expired");
+                        }
                         log::trace!("flushing...");
                         gfx.flush().expect("couldn't flush buffer to screen");
 
@@ -609,6 +655,33 @@ fn wrapped_main() -> ! {
                 log::debug!("returned from forward_input");
             }
             Some(Opcode::KeyboardEvent) => msg_scalar_unpack!(msg, k1, k2, k3, k4, {
+                // only the keyboard listener passes real key events on; anything else is injected, and has
+                // to go through `TypeChars`
+                if msg.sender.pid().map(|p| p.get()).unwrap_or_default() as u32 != xous::process::id() {
+                    log::warn!("KeyboardEvent from outside the GAM, ignored");
+                    continue;
+                }
+                let keys = [
+                    core::char::from_u32(k1 as u32).unwrap_or('\u{0000}'),
+                    core::char::from_u32(k2 as u32).unwrap_or('\u{0000}'),
+                    core::char::from_u32(k3 as u32).unwrap_or('\u{0000}'),
+                    core::char::from_u32(k4 as u32).unwrap_or('\u{0000}'),
+                ];
+                if keys[0] == keyboard::LOCK_SCREEN_KEY {
+                    screen_lock.request_lock();
+                } else if screen_lock.is_locked() && !context_mgr.is_password_modal_focused() {
+                    // swallow the key. The status bar sees the keypress independently and raises the PIN
+                    // prompt.
+                    log::debug!("screen is locked, key event ignored");
+                } else {
+                    context_mgr.key_event(keys, &gfx, &mut canvases);
+                }
+            }),
+            Some(Opcode::TypeChars) => msg_scalar_unpack!(msg, k1, k2, k3, k4, {
+                if screen_lock.is_locked() {
+                    log::warn!("screen is locked, injected characters dropped");
+                    continue;
+                }
                 let keys = [
                     core::char::from_u32(k1 as u32).unwrap_or('\u{0000}'),
                     core::char::from_u32(k2 as u32).unwrap_or('\u{0000}'),
@@ -796,6 +869,114 @@ fn wrapped_main() -> ! {
                 gfx.set_devboot(true).ok(); // indicate to users that we are no longer in a codebase that is exclusively trusted code
                 context_mgr.register_name(registration.name.to_str(), &registration.auth_token);
             }
+            Some(Opcode::PostNotification) => {
+                let buffer = unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                let post = buffer.to_original::<NotificationPost, _>().unwrap();
+                if let Some(source) = context_mgr.app_name_by_token(post.token) {
+                    let mut notification = post.notification;
+                    notification.source = xous_ipc::String::from_str(&source);
+                    log::debug!("notification posted: {:?}", notification);
+                    notification_center.post(notification, ticktimer.elapsed_ms());
+                } else {
+                    log::warn!("notification posted with an invalid token, ignored");
+                }
+                // the toast is drawn on the next redraw; the status bar's pump guarantees one within a second
+            }
+            Some(Opcode::NotificationHistory) => {
+                let mut buffer =
+                    unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                buffer.replace(notification_center.history()).unwrap();
+            }
+            Some(Opcode::ClearNotifications) => msg_scalar_unpack!(msg, t1, t2, t3, t4, {
+                let token = [t1 as u32, t2 as u32, t3 as u32, t4 as u32];
+                if Some(token) == context_mgr.find_app_token_by_name(gam::STATUS_BAR_NAME) {
+                    notification_center.clear(None);
+                } else if let Some(source) = context_mgr.app_name_by_token(token) {
+                    notification_center.clear(Some(&source));
+                } else {
+                    log::warn!("attempt to clear notifications with an invalid token, ignored");
+                }
+            }),
+            Some(Opcode::RegisterScreenLock) => {
+                let buffer = unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                let registration = buffer.to_original::<ScreenLockRegistration, _>().unwrap();
+                if Some(registration.token) == context_mgr.find_app_token_by_name(gam::STATUS_BAR_NAME) {
+                    if !screen_lock.register_listener(registration.listener, registration.request_id) {
+                        log::warn!("attempt to re-register the screen lock owner, ignored");
+                    }
+                } else {
+                    log::warn!("screen lock registration with an invalid token, ignored");
+                }
+            }
+            Some(Opcode::SetScreenLock) => {
+                let buffer = unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                let state = buffer.to_original::<ScreenLockState, _>().unwrap();
+                if Some(state.token) != context_mgr.find_app_token_by_name(gam::STATUS_BAR_NAME) {
+                    log::warn!("attempt to change the screen lock with an invalid token, ignored");
+                    continue;
+                }
+                if state.locked == screen_lock.is_locked() {
+                    continue;
+                }
+                log::info!("screen lock: {:?}", state.locked);
+                screen_lock.set_locked(state.locked, &mut canvases);
+                if !state.locked {
+                    // everything under the curtain is drawable again; have the foreground repaint itself
+                    context_mgr.redraw().ok();
+                    context_mgr.redraw_imef().ok();
+                }
+            }
+            Some(Opcode::IsScreenLocked) => msg_blocking_scalar_unpack!(msg, _, _, _, _, {
+                xous::return_scalar(msg.sender, if screen_lock.is_locked() { 1 } else { 0 }).ok();
+            }),
+            Some(Opcode::SplitView) => {
+                let mut buffer =
+                    unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                let mut request = buffer.to_original::<SplitViewRequest, _>().unwrap();
+                let authorized = [MAIN_MENU_NAME, gam::STATUS_BAR_NAME]
+                    .iter()
+                    .any(|&name| context_mgr.find_app_token_by_name(name) == Some(request.token));
+                let result = if !authorized {
+                    log::warn!("split view request from unauthorized context, ignoring");
+                    Err(xous::Error::AccessDenied)
+                } else {
+                    let panes = match (request.top.take(), request.bottom.take()) {
+                        (Some(top), Some(bottom)) => {
+                            match (
+                                context_mgr.find_app_token_by_name(top.as_str().unwrap_or("")),
+                                context_mgr.find_app_token_by_name(bottom.as_str().unwrap_or("")),
+                            ) {
+                                (Some(top), Some(bottom)) => Ok(Some((top, bottom))),
+                                _ => {
+                                    log::warn!("split view requested for unknown apps {} / {}", top, bottom);
+                                    Err(xous::Error::ServerNotFound)
+                                }
+                            }
+                        }
+                        _ => Ok(None),
+                    };
+                    panes.and_then(|panes| {
+                        context_mgr.split_view(&gfx, &mut canvases, &status_cliprect, panes)
+                    })
+                };
+                if let Err(e) = result {
+                    log::warn!("couldn't change split view: {:?}", e);
+                }
+                request.result =
+                    Some(if result.is_ok() { ActivationResult::Success } else { ActivationResult::Failure });
+                buffer.replace(request).unwrap();
+            }
+            Some(Opcode::CanSplit) => {
+                let mut buffer =
+                    unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                let mut query = buffer.to_original::<GamActivation, _>().unwrap();
+                query.result = Some(if context_mgr.can_split(query.name.as_str().unwrap_or("")) {
+                    ActivationResult::Success
+                } else {
+                    ActivationResult::Failure
+                });
+                buffer.replace(query).unwrap();
+            }
             Some(Opcode::Quit) => break,
             None => {
                 log::error!("unhandled message {:?}", msg);
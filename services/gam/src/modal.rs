@@ -10,6 +10,10 @@ mod notification;
 pub use notification::*;
 mod slider;
 pub use slider::*;
+mod spinner;
+pub use spinner::*;
+mod form;
+pub use form::*;
 mod progressbar;
 pub use progressbar::*;
 mod consoleinput;
@@ -41,6 +45,8 @@ pub enum ActionType {
     RadioButtons,
     CheckBoxes,
     Slider,
+    Spinner,
+    Form,
     Notification,
     #[cfg(feature = "ditherpunk")]
     Image,
@@ -127,6 +133,14 @@ impl SliderPayload {
     pub fn new(value: u32) -> Self { SliderPayload(value) }
 }
 
+/// Returns the fields of a spinner: (year, month, day), (hours, minutes, seconds), or (number, 0, 0).
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct SpinnerPayload(pub [i32; MAX_SPINNER_FIELDS]);
+
+/// Returns the fields of a form with the user's entries filled in. Fields are packed from the start.
+#[derive(Debug, Copy, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct FormPayload(pub [Option<FormField>; MAX_FORM_FIELDS]);
+
 #[derive(Debug, Copy, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 pub struct RadioButtonPayload(pub ItemName); // returns the name of the item corresponding to the radio button selection
 impl RadioButtonPayload {
@@ -0,0 +1,378 @@
+use core::fmt::Write;
+
+use graphics_server::api::*;
+use locales::t;
+use xous_ipc::Buffer;
+
+use crate::*;
+
+/// Maximum number of fields in a form
+pub const MAX_FORM_FIELDS: usize = 6;
+/// Maximum number of choices in a form's radio group
+pub const MAX_FORM_CHOICES: usize = 6;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub enum FormFieldKind {
+    /// free-form text, entered in `text`
+    Text,
+    /// like `Text`, but masked on screen
+    Password,
+    /// `value` is 1 if checked, 0 if not
+    CheckBox,
+    /// `value` is the index into `choices` of the selection
+    Radio,
+    /// `value` is between `min` and `max`, moved in increments of `step`
+    Slider { min: u32, max: u32, step: u32 },
+}
+
+/// One field of a form. The same structure carries the field's description and initial value into the
+/// modal, and the user's entry back out of it.
+#[derive(Debug, Copy, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct FormField {
+    pub kind: FormFieldKind,
+    pub label: String<64>,
+    pub text: String<256>,
+    pub choices: [Option<String<64>>; MAX_FORM_CHOICES],
+    pub value: u32,
+}
+impl FormField {
+    pub fn new(kind: FormFieldKind, label: &str) -> Self {
+        FormField {
+            kind,
+            label: String::<64>::from_str(label),
+            text: String::<256>::new(),
+            choices: [None; MAX_FORM_CHOICES],
+            value: 0,
+        }
+    }
+
+    pub fn choice_count(&self) -> usize { self.choices.iter().filter(|c| c.is_some()).count() }
+
+    /// The selected choice of a radio group
+    pub fn choice(&self) -> Option<&str> {
+        self.choices.get(self.value as usize).and_then(|c| c.as_ref()).map(|c| c.as_str().unwrap_or(""))
+    }
+
+    /// Ensures that 0's are written to the text storage, and not optimized out; important for passwords.
+    pub fn volatile_clear(&mut self) { self.text.volatile_clear(); }
+
+    fn value_text(&self) -> std::string::String {
+        match self.kind {
+            FormFieldKind::Text => self.text.as_str().unwrap_or("").to_string(),
+            FormFieldKind::Password => "*".repeat(self.text.as_str().unwrap_or("").chars().count()),
+            FormFieldKind::CheckBox => std::string::String::new(),
+            FormFieldKind::Radio => format!("\u{2190} {} \u{2192}", self.choice().unwrap_or("")),
+            FormFieldKind::Slider { .. } => format!("\u{2190} {} \u{2192}", self.value),
+        }
+    }
+}
+
+/// A modal that stacks several fields of different kinds. ↑/↓ move between the fields and the "okay"
+/// line; printable keys edit text fields, ←/→ change radio groups and sliders, and select toggles
+/// checkboxes, advances from a text field, or submits from the "okay" line.
+#[derive(Debug, Copy, Clone)]
+pub struct Form {
+    pub action_conn: xous::CID,
+    pub action_opcode: u32,
+    pub action_payload: FormPayload,
+    /// the field with focus; one past the last field is the "okay" line
+    pub select_index: usize,
+}
+impl Form {
+    pub fn new(action_conn: xous::CID, action_opcode: u32) -> Self {
+        Form {
+            action_conn,
+            action_opcode,
+            action_payload: FormPayload([None; MAX_FORM_FIELDS]),
+            select_index: 0,
+        }
+    }
+
+    pub fn set_fields(&mut self, fields: [Option<FormField>; MAX_FORM_FIELDS], focus: usize) {
+        self.action_payload = FormPayload(fields);
+        self.select_index = focus.min(self.field_count());
+    }
+
+    pub fn field_count(&self) -> usize { self.action_payload.0.iter().take_while(|f| f.is_some()).count() }
+
+    fn focused(&mut self) -> Option<&mut FormField> {
+        self.action_payload.0.get_mut(self.select_index).and_then(|f| f.as_mut())
+    }
+}
+impl ActionApi for Form {
+    fn set_action_opcode(&mut self, op: u32) { self.action_opcode = op }
+
+    fn height(&self, glyph_height: i16, margin: i16, _modal: &Modal) -> i16 {
+        // each field is a label line and a value line (checkboxes only need one), then a blank line and
+        // the "okay" line
+        let lines: i16 = self
+            .action_payload
+            .0
+            .iter()
+            .flatten()
+            .map(|f| if f.kind == FormFieldKind::CheckBox { 1 } else { 2 })
+            .sum();
+        (lines + 2) * glyph_height + 2 * margin
+    }
+
+    fn redraw(&self, at_height: i16, modal: &Modal) {
+        // prime a textview with the correct general style parameters
+        let mut tv = TextView::new(modal.canvas, TextBounds::BoundingBox(Rectangle::new_coords(0, 0, 1, 1)));
+        tv.ellipsis = true;
+        tv.style = modal.style;
+        tv.invert = false;
+        tv.draw_border = false;
+        tv.margin = Point::new(0, 0);
+        tv.insertion = None;
+
+        let cursor_x = modal.margin;
+        let text_x = modal.margin + 20;
+        let emoji_slop = 2; // tweaked for a non-emoji glyph
+
+        let mut cur_y = at_height + modal.margin;
+        let post = |tv: &mut TextView, x: i16, y: i16, text: &str| {
+            tv.text.clear();
+            tv.bounds_computed = None;
+            tv.bounds_hint = TextBounds::BoundingBox(Rectangle::new(
+                Point::new(x, y),
+                Point::new(modal.canvas_width - modal.margin, y + modal.line_height),
+            ));
+            write!(tv, "{}", text).unwrap();
+            modal.gam.post_textview(tv).expect("couldn't post tv");
+        };
+        for (index, field) in self.action_payload.0.iter().flatten().enumerate() {
+            if index == self.select_index {
+                post(&mut tv, cursor_x, cur_y - emoji_slop, "\u{25B6}");
+            }
+            match field.kind {
+                FormFieldKind::CheckBox => {
+                    let mark = if field.value != 0 { "\u{d7}" } else { "\u{25A1}" }; // multiplication sign, box
+                    post(&mut tv, text_x, cur_y, &format!("{} {}", mark, field.label.as_str().unwrap_or("")));
+                    cur_y += modal.line_height;
+                }
+                _ => {
+                    post(&mut tv, text_x, cur_y, field.label.as_str().unwrap_or(""));
+                    cur_y += modal.line_height;
+                    tv.insertion = if index == self.select_index
+                        && (field.kind == FormFieldKind::Text || field.kind == FormFieldKind::Password)
+                    {
+                        Some(field.value_text().chars().count() as i32)
+                    } else {
+                        None
+                    };
+                    post(&mut tv, text_x + 20, cur_y, &field.value_text());
+                    tv.insertion = None;
+                    cur_y += modal.line_height;
+                }
+            }
+        }
+        // blank line above OK
+        cur_y += modal.line_height;
+        if self.select_index >= self.field_count() {
+            post(&mut tv, cursor_x, cur_y - emoji_slop, "\u{25B6}");
+        }
+        post(&mut tv, text_x, cur_y, t!("radio.select_and_close", locales::LANG));
+
+        // divider lines
+        modal
+            .gam
+            .draw_line(
+                modal.canvas,
+                Line::new_with_style(
+                    Point::new(modal.margin, at_height),
+                    Point::new(modal.canvas_width - modal.margin, at_height),
+                    DrawStyle::new(PixelColor::Dark, PixelColor::Dark, 1),
+                ),
+            )
+            .expect("couldn't draw entry line");
+    }
+
+    fn close(&mut self) {
+        for field in self.action_payload.0.iter_mut().flatten() {
+            field.volatile_clear();
+        }
+    }
+
+    fn key_action(&mut self, k: char) -> Option<ValidatorErr> {
+        log::trace!("key_action: {}", k);
+        let field_count = self.field_count();
+        match k {
+            '↑' => {
+                if self.select_index > 0 {
+                    self.select_index -= 1;
+                }
+            }
+            '↓' => {
+                if self.select_index < field_count {
+                    // the last position is the "OK" button
+                    self.select_index += 1;
+                }
+            }
+            '←' | '→' => {
+                if let Some(field) = self.focused() {
+                    match field.kind {
+                        FormFieldKind::Radio => {
+                            let count = field.choice_count() as u32;
+                            if count > 0 {
+                                field.value = if k == '→' {
+                                    (field.value + 1) % count
+                                } else {
+                                    (field.value + count - 1) % count
+                                };
+                            }
+                        }
+                        FormFieldKind::Slider { min, max, step } => {
+                            field.value = if k == '→' {
+                                field.value.saturating_add(step).min(max)
+                            } else {
+                                field.value.saturating_sub(step).max(min)
+                            };
+                        }
+                        _ => {
+                            // no cursor movement within text fields
+                        }
+                    }
+                }
+            }
+            '\u{0}' => {
+                // ignore null messages
+            }
+            '∴' | '\u{d}' => {
+                if self.select_index < field_count {
+                    if let Some(field) = self.focused() {
+                        if field.kind == FormFieldKind::CheckBox {
+                            field.value = if field.value == 0 { 1 } else { 0 };
+                            return None;
+                        }
+                    }
+                    // select on any other field moves on to the next one
+                    self.select_index += 1;
+                } else {
+                    // relinquish focus before returning the result
+                    let gam = crate::Gam::new(&xous_names::XousNames::new().unwrap()).unwrap();
+                    gam.relinquish_focus().unwrap();
+                    xous::yield_slice();
+
+                    let buf =
+                        Buffer::into_buf(self.action_payload).expect("couldn't convert message to payload");
+                    buf.send(self.action_conn, self.action_opcode)
+                        .map(|_| ())
+                        .expect("couldn't send action message");
+                    self.close();
+                }
+            }
+            '\u{8}' => {
+                if let Some(field) = self.focused() {
+                    if field.kind == FormFieldKind::Text || field.kind == FormFieldKind::Password {
+                        field.text.pop();
+                    }
+                }
+            }
+            _ => {
+                if let Some(field) = self.focused() {
+                    if field.kind == FormFieldKind::Text || field.kind == FormFieldKind::Password {
+                        if field.text.push(k).is_err() {
+                            log::warn!("form field is full, ignoring input");
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn form() -> Form {
+        let mut fields = [None; MAX_FORM_FIELDS];
+        fields[0] = Some(FormField::new(FormFieldKind::Text, "name"));
+        fields[1] = Some(FormField::new(FormFieldKind::CheckBox, "remember"));
+        let mut radio = FormField::new(FormFieldKind::Radio, "color");
+        radio.choices[0] = Some(String::<64>::from_str("red"));
+        radio.choices[1] = Some(String::<64>::from_str("green"));
+        radio.choices[2] = Some(String::<64>::from_str("blue"));
+        fields[2] = Some(radio);
+        let mut slider = FormField::new(FormFieldKind::Slider { min: 10, max: 50, step: 15 }, "volume");
+        slider.value = 10;
+        fields[3] = Some(slider);
+        let mut form = Form::new(0, 0);
+        form.set_fields(fields, 0);
+        form
+    }
+
+    fn field(form: &Form, index: usize) -> &FormField { form.action_payload.0[index].as_ref().unwrap() }
+
+    #[test]
+    fn navigation() {
+        let mut form = form();
+        assert_eq!(form.field_count(), 4);
+        form.key_action('↑');
+        assert_eq!(form.select_index, 0);
+        // typing only edits the focused text field, and select moves on to the next field
+        for k in "zoë".chars() {
+            form.key_action(k);
+        }
+        form.key_action('\u{8}');
+        form.key_action('e');
+        assert_eq!(field(&form, 0).text.as_str().unwrap(), "zoe");
+        form.key_action('∴');
+        assert_eq!(form.select_index, 1);
+        form.key_action('x');
+        assert_eq!(field(&form, 1).text.as_str().unwrap(), "");
+        // down stops at the "okay" line
+        for _ in 0..10 {
+            form.key_action('↓');
+        }
+        assert_eq!(form.select_index, 4);
+        form.key_action('↑');
+        assert_eq!(form.select_index, 3);
+        // focus past the end is pulled back to the "okay" line
+        let fields = form.action_payload.0;
+        form.set_fields(fields, 9);
+        assert_eq!(form.select_index, 4);
+    }
+
+    #[test]
+    fn checkbox_and_radio() {
+        let mut form = form();
+        form.select_index = 1;
+        form.key_action('∴');
+        assert_eq!(field(&form, 1).value, 1);
+        // toggling a checkbox keeps the focus on it
+        assert_eq!(form.select_index, 1);
+        form.key_action('\u{d}');
+        assert_eq!(field(&form, 1).value, 0);
+
+        form.select_index = 2;
+        form.key_action('→');
+        assert_eq!(field(&form, 2).choice(), Some("green"));
+        form.key_action('→');
+        form.key_action('→');
+        assert_eq!(field(&form, 2).choice(), Some("red"));
+        form.key_action('←');
+        assert_eq!(field(&form, 2).choice(), Some("blue"));
+    }
+
+    #[test]
+    fn slider_bounds() {
+        let mut form = form();
+        form.select_index = 3;
+        form.key_action('←');
+        assert_eq!(field(&form, 3).value, 10);
+        form.key_action('→');
+        form.key_action('→');
+        assert_eq!(field(&form, 3).value, 40);
+        // the last step is cut short at the maximum
+        form.key_action('→');
+        assert_eq!(field(&form, 3).value, 50);
+        form.key_action('→');
+        assert_eq!(field(&form, 3).value, 50);
+        for _ in 0..4 {
+            form.key_action('←');
+        }
+        assert_eq!(field(&form, 3).value, 10);
+    }
+}
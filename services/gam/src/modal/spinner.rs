@@ -0,0 +1,367 @@
+use core::fmt::Write;
+
+use graphics_server::api::*;
+
+use crate::*;
+
+/// Years selectable in a date spinner. The RTC only stores a two-digit year, so this is the range it
+/// can represent.
+pub const SPINNER_MIN_YEAR: i32 = 2000;
+pub const SPINNER_MAX_YEAR: i32 = 2099;
+/// Maximum number of fields a spinner can have (year/month/day or hours/minutes/seconds)
+pub const MAX_SPINNER_FIELDS: usize = 3;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub enum SpinnerKind {
+    /// year, month, day
+    Date,
+    /// hours, minutes, seconds
+    Time,
+    /// a single number between `min` and `max`, in increments of `step`
+    Number,
+}
+
+pub fn days_in_month(year: i32, month: i32) -> i32 {
+    match month {
+        4 | 6 | 9 | 11 => 30,
+        2 => {
+            if (year % 4 == 0 && year % 100 != 0) || year % 400 == 0 {
+                29
+            } else {
+                28
+            }
+        }
+        _ => 31,
+    }
+}
+
+// Like the Slider, this is "shallow copy capable" so it can be handed to the modal by value.
+#[derive(Debug, Copy, Clone)]
+pub struct Spinner {
+    pub kind: SpinnerKind,
+    pub action_conn: xous::CID,
+    pub action_opcode: u32,
+    pub action_payload: SpinnerPayload,
+    /// bounds and increment for `SpinnerKind::Number`; ignored for dates and times
+    pub min: i32,
+    pub max: i32,
+    pub step: i32,
+    pub units: xous_ipc::String<8>,
+    /// index of the field that the up/down keys act on
+    pub focus: usize,
+    /// true once a digit has been typed into the focused field, so further digits append to it
+    typing: bool,
+    pub is_password: bool,
+}
+impl Spinner {
+    pub fn new(
+        action_conn: xous::CID,
+        action_opcode: u32,
+        kind: SpinnerKind,
+        initial: [i32; MAX_SPINNER_FIELDS],
+    ) -> Self {
+        let mut spinner = Spinner {
+            kind,
+            action_conn,
+            action_opcode,
+            action_payload: SpinnerPayload(initial),
+            min: i32::MIN,
+            max: i32::MAX,
+            step: 1,
+            units: String::<8>::new(),
+            focus: 0,
+            typing: false,
+            is_password: false,
+        };
+        spinner.clamp_all();
+        spinner
+    }
+
+    /// Configures the range of a `SpinnerKind::Number` spinner.
+    pub fn set_range(&mut self, min: i32, max: i32, step: i32, units: Option<&str>) {
+        self.min = min.min(max);
+        self.max = max.max(min);
+        self.step = step.max(1);
+        self.units = match units {
+            Some(unit_str) if unit_str.len() < 8 => String::<8>::from_str(unit_str),
+            Some(unit_str) => {
+                log::error!(
+                    "Unit string must be less than 8 *bytes* long (are you using unicode?), ignoring length {} string",
+                    unit_str.len()
+                );
+                String::<8>::new()
+            }
+            None => String::<8>::new(),
+        };
+        self.clamp_all();
+    }
+
+    pub fn set_is_password(&mut self, setting: bool) { self.is_password = setting; }
+
+    pub fn fields(&self) -> usize {
+        match self.kind {
+            SpinnerKind::Date | SpinnerKind::Time => 3,
+            SpinnerKind::Number => 1,
+        }
+    }
+
+    /// Inclusive range of the given field. The day's range depends on the current year and month.
+    pub fn field_range(&self, field: usize) -> (i32, i32) {
+        let v = &self.action_payload.0;
+        match (self.kind, field) {
+            (SpinnerKind::Date, 0) => (SPINNER_MIN_YEAR, SPINNER_MAX_YEAR),
+            (SpinnerKind::Date, 1) => (1, 12),
+            (SpinnerKind::Date, _) => (1, days_in_month(v[0], v[1])),
+            (SpinnerKind::Time, 0) => (0, 23),
+            (SpinnerKind::Time, _) => (0, 59),
+            (SpinnerKind::Number, _) => (self.min, self.max),
+        }
+    }
+
+    fn clamp_all(&mut self) {
+        for field in 0..MAX_SPINNER_FIELDS {
+            if field >= self.fields() {
+                self.action_payload.0[field] = 0;
+                continue;
+            }
+            let (lo, hi) = self.field_range(field);
+            self.action_payload.0[field] = self.action_payload.0[field].clamp(lo, hi);
+        }
+    }
+
+    /// Moves the focused field by `steps` increments. Date and time fields wrap around like a clock;
+    /// numbers stop at their bounds.
+    pub fn spin(&mut self, steps: i32) {
+        let (lo, hi) = self.field_range(self.focus);
+        let value = self.action_payload.0[self.focus];
+        self.action_payload.0[self.focus] = match self.kind {
+            SpinnerKind::Number => value.saturating_add(steps.saturating_mul(self.step)).clamp(lo, hi),
+            _ => lo + (value - lo + steps).rem_euclid(hi - lo + 1),
+        };
+        self.typing = false;
+        // changing the year or month can leave the day out of range
+        self.clamp_all();
+    }
+
+    /// Enters a digit into the focused field. Digits accumulate while the result stays in range; once it
+    /// wouldn't, entry starts over with the new digit.
+    pub fn type_digit(&mut self, digit: i32) {
+        let (_, hi) = self.field_range(self.focus);
+        let value = &mut self.action_payload.0[self.focus];
+        let appended =
+            if self.typing { value.checked_mul(10).and_then(|v| v.checked_add(digit)) } else { None };
+        // a partially typed value (e.g. the "20" of a year) can be below the field's minimum; it's pulled
+        // back into range when the focus moves on, or on submit
+        *value = match appended {
+            Some(v) if v <= hi => v,
+            _ => digit.min(hi),
+        };
+        self.typing = true;
+    }
+
+    pub fn backspace(&mut self) {
+        let value = &mut self.action_payload.0[self.focus];
+        *value /= 10;
+        self.typing = true;
+    }
+
+    pub fn move_focus(&mut self, forward: bool) {
+        self.clamp_all();
+        self.typing = false;
+        let fields = self.fields();
+        self.focus = if forward { (self.focus + 1) % fields } else { (self.focus + fields - 1) % fields };
+    }
+
+    /// The text segments of the display, each tagged with the field it shows (separators are `None`).
+    fn segments(&self) -> Vec<(std::string::String, Option<usize>)> {
+        let v = &self.action_payload.0;
+        match self.kind {
+            SpinnerKind::Date => vec![
+                (format!("{:04}", v[0]), Some(0)),
+                ("-".to_string(), None),
+                (format!("{:02}", v[1]), Some(1)),
+                ("-".to_string(), None),
+                (format!("{:02}", v[2]), Some(2)),
+            ],
+            SpinnerKind::Time => vec![
+                (format!("{:02}", v[0]), Some(0)),
+                (":".to_string(), None),
+                (format!("{:02}", v[1]), Some(1)),
+                (":".to_string(), None),
+                (format!("{:02}", v[2]), Some(2)),
+            ],
+            SpinnerKind::Number => {
+                let mut segs = vec![(format!("{}", v[0]), Some(0))];
+                if self.units.len() > 0 {
+                    segs.push((format!(" {}", self.units.to_str()), None));
+                }
+                segs
+            }
+        }
+    }
+}
+impl ActionApi for Spinner {
+    fn height(&self, glyph_height: i16, margin: i16, _modal: &Modal) -> i16 {
+        /*
+        margin
+              ↑
+          2026-10-18     <- glyph height, focused field underlined
+              ↓
+        margin
+        */
+        glyph_height * 3 + margin * 2
+    }
+
+    fn set_action_opcode(&mut self, op: u32) { self.action_opcode = op }
+
+    fn redraw(&self, at_height: i16, modal: &Modal) {
+        let color = if self.is_password { PixelColor::Light } else { PixelColor::Dark };
+
+        // prime a textview with the correct general style parameters
+        let mut tv = TextView::new(modal.canvas, TextBounds::BoundingBox(Rectangle::new_coords(0, 0, 1, 1)));
+        tv.ellipsis = false;
+        tv.style = modal.style;
+        tv.invert = self.is_password;
+        tv.draw_border = false;
+        tv.margin = Point::new(0, 0);
+        tv.insertion = None;
+
+        let maxwidth = (modal.canvas_width - modal.margin * 2) as u16;
+        let segments = self.segments();
+        // measure each segment so the whole line can be centered and the focused field located
+        let mut widths = Vec::new();
+        for (text, _) in segments.iter() {
+            tv.bounds_computed = None;
+            tv.bounds_hint = TextBounds::GrowableFromTl(Point::new(0, 0), maxwidth);
+            tv.text.clear();
+            write!(tv, "{}", text).unwrap();
+            modal.gam.bounds_compute_textview(&mut tv).expect("couldn't simulate text size");
+            widths.push(if let Some(bounds) = tv.bounds_computed { bounds.br.x - bounds.tl.x } else { 0 });
+        }
+        let total_width: i16 = widths.iter().sum();
+        let value_y = at_height + modal.margin + modal.line_height;
+        let mut x = (modal.canvas_width - total_width) / 2;
+        let mut focus_span = None;
+        for ((text, field), width) in segments.iter().zip(widths.iter()) {
+            tv.bounds_computed = None;
+            tv.bounds_hint = TextBounds::GrowableFromTl(Point::new(x, value_y), maxwidth);
+            tv.text.clear();
+            write!(tv, "{}", text).unwrap();
+            modal.gam.post_textview(&mut tv).expect("couldn't post tv");
+            if *field == Some(self.focus) {
+                focus_span = Some((x, x + width));
+            }
+            x += width;
+        }
+
+        if let Some((left, right)) = focus_span {
+            // arrows above and below the focused field hint at the up/down keys
+            let center = (left + right) / 2;
+            for (arrow, y) in [('↑', at_height + modal.margin), ('↓', value_y + modal.line_height)] {
+                tv.bounds_computed = None;
+                tv.bounds_hint = TextBounds::GrowableFromTl(Point::new(0, 0), maxwidth);
+                tv.text.clear();
+                write!(tv, "{}", arrow).unwrap();
+                modal.gam.bounds_compute_textview(&mut tv).expect("couldn't simulate text size");
+                let arrow_width =
+                    if let Some(bounds) = tv.bounds_computed { bounds.br.x - bounds.tl.x } else { 0 };
+                tv.bounds_computed = None;
+                tv.bounds_hint =
+                    TextBounds::GrowableFromTl(Point::new(center - arrow_width / 2, y), maxwidth);
+                modal.gam.post_textview(&mut tv).expect("couldn't post tv");
+            }
+
+            let mut draw_list = GamObjectList::new(modal.canvas);
+            draw_list
+                .push(GamObjectType::Line(Line::new_with_style(
+                    Point::new(left, value_y + modal.line_height - 1),
+                    Point::new(right, value_y + modal.line_height - 1),
+                    DrawStyle::new(color, color, 2),
+                )))
+                .unwrap();
+            modal.gam.draw_list(draw_list).expect("couldn't execute draw list");
+        }
+    }
+
+    fn key_action(&mut self, k: char) -> Option<ValidatorErr> {
+        log::trace!("key_action: {}", k);
+        match k {
+            '↑' => self.spin(1),
+            '↓' => self.spin(-1),
+            '←' => self.move_focus(false),
+            '→' => self.move_focus(true),
+            '0'..='9' => self.type_digit(k as i32 - '0' as i32),
+            '\u{8}' => self.backspace(),
+            '\u{0}' => {
+                // ignore null messages
+            }
+            '∴' | '\u{d}' => {
+                self.clamp_all();
+                // relinquish focus before returning the result
+                let gam = crate::Gam::new(&xous_names::XousNames::new().unwrap()).unwrap();
+                gam.relinquish_focus().unwrap();
+                xous::yield_slice();
+
+                let buf = Buffer::into_buf(self.action_payload).expect("couldn't convert message to payload");
+                buf.send(self.action_conn, self.action_opcode)
+                    .map(|_| ())
+                    .expect("couldn't send action message");
+            }
+            _ => {
+                // ignore all other messages
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn date_fields_wrap_and_clamp() {
+        let mut date = Spinner::new(0, 0, SpinnerKind::Date, [2024, 1, 31]);
+        date.focus = 1;
+        date.spin(1);
+        // February 2024 only has 29 days
+        assert_eq!(date.action_payload.0, [2024, 2, 29]);
+        date.focus = 0;
+        date.spin(1);
+        assert_eq!(date.action_payload.0, [2025, 2, 28]);
+        date.focus = 1;
+        date.spin(-2);
+        assert_eq!(date.action_payload.0, [2025, 12, 28]);
+        // out of range initial values are pulled into range
+        assert_eq!(Spinner::new(0, 0, SpinnerKind::Date, [1970, 0, 0]).action_payload.0, [2000, 1, 1]);
+    }
+
+    #[test]
+    fn time_and_number_spin() {
+        let mut time = Spinner::new(0, 0, SpinnerKind::Time, [23, 59, 0]);
+        time.spin(1);
+        assert_eq!(time.action_payload.0, [0, 59, 0]);
+        time.move_focus(false);
+        assert_eq!(time.focus, 2);
+
+        let mut number = Spinner::new(0, 0, SpinnerKind::Number, [10, 0, 0]);
+        number.set_range(5, 100, 10, Some("s"));
+        number.spin(20);
+        assert_eq!(number.action_payload.0[0], 100);
+        number.spin(-1);
+        assert_eq!(number.action_payload.0[0], 90);
+    }
+
+    #[test]
+    fn digit_entry() {
+        let mut time = Spinner::new(0, 0, SpinnerKind::Time, [12, 0, 0]);
+        time.type_digit(1);
+        time.type_digit(7);
+        assert_eq!(time.action_payload.0[0], 17);
+        // 173 is not an hour, so entry starts over
+        time.type_digit(3);
+        assert_eq!(time.action_payload.0[0], 3);
+        time.backspace();
+        assert_eq!(time.action_payload.0[0], 0);
+    }
+}
@@ -0,0 +1,211 @@
+use std::collections::VecDeque;
+use std::fmt::Write;
+
+use graphics_server::*;
+
+use crate::api::*;
+
+/// how long a toast stays over the status bar, by priority
+const TOAST_LOW_MS: u64 = 2_000;
+const TOAST_NORMAL_MS: u64 = 4_000;
+const TOAST_URGENT_MS: u64 = 8_000;
+/// share of the status bar's width given to the name of the app that posted a toast
+const TOAST_SOURCE_FRACTION: i16 = 4;
+
+/// Domiciled in the GAM: holds the notification history, and the toast that is currently being
+/// rendered over the status bar (if any).
+///
+/// Toasts never take focus. Instead, they are re-rendered on top of the status canvas just before every
+/// flush, so the status bar's own periodic redraws can't wipe them out. Once the toast expires, the status
+/// area is blanked and the status bar repaints itself on its next pump cycle.
+pub(crate) struct NotificationCenter {
+    history: VecDeque<Notification>,
+    toast: Option<Notification>,
+    toast_expiry_ms: u64,
+}
+
+impl NotificationCenter {
+    pub(crate) fn new() -> Self {
+        NotificationCenter {
+            history: VecDeque::with_capacity(NOTIFICATION_HISTORY_LEN),
+            toast: None,
+            toast_expiry_ms: 0,
+        }
+    }
+
+    /// Records a notification and makes it the current toast. A new notification always replaces the
+    /// current toast, unless the current toast is urgent and the new one isn't.
+    pub(crate) fn post(&mut self, mut notification: Notification, now_ms: u64) {
+        notification.timestamp_ms = now_ms;
+        if self.history.len() >= NOTIFICATION_HISTORY_LEN {
+            self.history.pop_back();
+        }
+        self.history.push_front(notification);
+
+        if let Some(current) = self.toast {
+            if current.priority == NotificationPriority::Urgent
+                && notification.priority != NotificationPriority::Urgent
+                && now_ms < self.toast_expiry_ms
+            {
+                return;
+            }
+        }
+        self.toast = Some(notification);
+        self.toast_expiry_ms = now_ms
+            + match notification.priority {
+                NotificationPriority::Low => TOAST_LOW_MS,
+                NotificationPriority::Normal => TOAST_NORMAL_MS,
+                NotificationPriority::Urgent => TOAST_URGENT_MS,
+            };
+    }
+
+    pub(crate) fn history(&self) -> NotificationHistory {
+        let mut ret = NotificationHistory { list: Default::default() };
+        for (src, dst) in self.history.iter().zip(ret.list.iter_mut()) {
+            *dst = Some(*src);
+        }
+        ret
+    }
+
+    /// Clears the notifications posted by `source`, or all of them if it's `None`.
+    pub(crate) fn clear(&mut self, source: Option<&str>) {
+        match source {
+            Some(source) => self.history.retain(|n| n.source.as_str().unwrap_or("") != source),
+            None => self.history.clear(),
+        }
+    }
+
+    /// Draws the current toast over the status canvas, if one is active. Returns `true` if a toast
+    /// just expired, in which case the status area has been blanked and needs a redraw from its owner.
+    ///
+    /// Only the name of the posting app, which the GAM looked up from its token, is drawn as trusted
+    /// text. The title and body come from the app, and may have come from further afield than that (e.g.
+    /// a chat message), so they are drawn untrusted: otherwise anyone could put a fake system prompt up.
+    pub(crate) fn render_toast(
+        &mut self,
+        gfx: &graphics_server::Gfx,
+        status_gid: Gid,
+        status_cliprect: &Rectangle,
+        now_ms: u64,
+    ) -> bool {
+        if let Some(toast) = self.toast {
+            if now_ms >= self.toast_expiry_ms {
+                self.toast = None;
+                let mut rect = *status_cliprect;
+                rect.style =
+                    DrawStyle { fill_color: Some(PixelColor::Light), stroke_color: None, stroke_width: 0 };
+                gfx.draw_rectangle(rect).expect("can't clear status area");
+                return true;
+            }
+            let mut bounds = *status_cliprect;
+            bounds.normalize();
+            let split = bounds.tl().x + bounds.width() as i16 / TOAST_SOURCE_FRACTION;
+            let source_bounds = Rectangle::new(bounds.tl(), Point::new(split, bounds.br().y));
+            let text_bounds = Rectangle::new(Point::new(split + 1, bounds.tl().y), bounds.br());
+
+            let mut source_tv = TextView::new(status_gid, TextBounds::BoundingBox(source_bounds));
+            source_tv.untrusted = false;
+            source_tv.style = GlyphStyle::Bold;
+            write!(source_tv, "{}", toast.source.as_str().unwrap_or("")).ok();
+
+            let mut text_tv = TextView::new(status_gid, TextBounds::BoundingBox(text_bounds));
+            text_tv.untrusted = true;
+            text_tv.style = GlyphStyle::Regular;
+            write!(text_tv, "{}\n{}", toast.title.as_str().unwrap_or(""), toast.body.as_str().unwrap_or(""))
+                .ok();
+
+            for tv in [&mut source_tv, &mut text_tv].iter_mut() {
+                tv.clip_rect = Some(*status_cliprect);
+                tv.ellipsis = true;
+                tv.draw_border = true;
+                tv.rounded_border = Some(4);
+                tv.margin = Point::new(4, 0);
+                tv.invert = toast.priority == NotificationPriority::Urgent;
+                gfx.draw_textview(tv).expect("couldn't draw notification toast");
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(title: &str, priority: NotificationPriority) -> Notification {
+        Notification {
+            source: xous_ipc::String::from_str("status"),
+            title: xous_ipc::String::from_str(title),
+            body: xous_ipc::String::new(),
+            priority,
+            timestamp_ms: 0,
+        }
+    }
+
+    fn toast_title(nc: &NotificationCenter) -> Option<&str> {
+        nc.toast.as_ref().map(|t| t.title.as_str().unwrap_or(""))
+    }
+
+    #[test]
+    fn history_is_newest_first_and_bounded() {
+        let mut nc = NotificationCenter::new();
+        for i in 0..NOTIFICATION_HISTORY_LEN + 3 {
+            nc.post(note(&format!("n{}", i), NotificationPriority::Low), i as u64 * 10);
+        }
+        let history = nc.history();
+        assert!(history.list.iter().all(|n| n.is_some()));
+        let newest = history.list[0].unwrap();
+        assert_eq!(newest.title.as_str().unwrap(), format!("n{}", NOTIFICATION_HISTORY_LEN + 2));
+        assert_eq!(newest.timestamp_ms, (NOTIFICATION_HISTORY_LEN as u64 + 2) * 10);
+        // the three oldest fell off the end
+        assert_eq!(history.list[NOTIFICATION_HISTORY_LEN - 1].unwrap().title.as_str().unwrap(), "n3");
+
+        nc.clear(None);
+        assert!(nc.history().list.iter().all(|n| n.is_none()));
+    }
+
+    #[test]
+    fn newer_toast_replaces_current() {
+        let mut nc = NotificationCenter::new();
+        nc.post(note("first", NotificationPriority::Normal), 0);
+        assert_eq!(nc.toast_expiry_ms, TOAST_NORMAL_MS);
+        nc.post(note("second", NotificationPriority::Low), 100);
+        assert_eq!(toast_title(&nc), Some("second"));
+        assert_eq!(nc.toast_expiry_ms, 100 + TOAST_LOW_MS);
+    }
+
+    #[test]
+    fn urgent_toast_is_not_displaced() {
+        let mut nc = NotificationCenter::new();
+        nc.post(note("alarm", NotificationPriority::Urgent), 0);
+        assert_eq!(nc.toast_expiry_ms, TOAST_URGENT_MS);
+        nc.post(note("chatter", NotificationPriority::Normal), 100);
+        assert_eq!(toast_title(&nc), Some("alarm"));
+        // ...but the displaced notification is still recorded
+        assert_eq!(nc.history().list[0].unwrap().title.as_str().unwrap(), "chatter");
+
+        // another urgent notification does replace it
+        nc.post(note("fire", NotificationPriority::Urgent), 200);
+        assert_eq!(toast_title(&nc), Some("fire"));
+
+        // and once it has expired, anything goes
+        nc.post(note("later", NotificationPriority::Low), 200 + TOAST_URGENT_MS);
+        assert_eq!(toast_title(&nc), Some("later"));
+    }
+
+    #[test]
+    fn clearing_is_per_source() {
+        let mut nc = NotificationCenter::new();
+        let mut chat = note("message", NotificationPriority::Low);
+        chat.source = xous_ipc::String::from_str("mtxchat");
+        nc.post(note("battery", NotificationPriority::Urgent), 0);
+        nc.post(chat, 10);
+        nc.clear(Some("mtxchat"));
+        let history = nc.history();
+        assert_eq!(history.list[0].unwrap().title.as_str().unwrap(), "battery");
+        assert!(history.list[1].is_none());
+        // an app can't clear what it didn't post
+        nc.clear(Some("vault"));
+        assert!(nc.history().list[0].is_some());
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+#[cfg(feature = "cramium-soc")]
+use cram_hal_service::trng;
+use graphics_server::*;
+use locales::t;
+
+use crate::api::CanvasType;
+use crate::canvas::*;
+use crate::contexts::BOOT_CONTEXT_TRUSTLEVEL;
+
+/// The curtain sits just under the password modals (BOOT_CONTEXT_TRUSTLEVEL - 1), so that the
+/// rootkeys PIN prompt can draw over it, while every app, menu and regular modal is occluded.
+const CURTAIN_TRUST: u8 = BOOT_CONTEXT_TRUSTLEVEL - 2;
+
+/// Domiciled in the GAM: tracks whether the screen is locked, and owns the "curtain" canvas that
+/// covers everything below the status bar while it is.
+///
+/// The lock policy (idle timeout, PIN verification) lives in the status bar, which is the only
+/// context allowed to change the lock state. The GAM just enforces it: the curtain makes all other
+/// content canvases non-drawable, and key events are swallowed unless a password modal has focus.
+pub(crate) struct ScreenLock {
+    curtain: Gid,
+    locked: bool,
+    /// connection and opcode on the lock owner, used to relay a lock request from the keyboard
+    listener: Option<(xous::CID, u32)>,
+}
+
+impl ScreenLock {
+    pub(crate) fn new(
+        gfx: &graphics_server::Gfx,
+        trng: &trng::Trng,
+        status_cliprect: &Rectangle,
+        canvases: &mut HashMap<Gid, Canvas>,
+    ) -> Self {
+        let screensize = gfx.screen_size().expect("Couldn't get screen size");
+        let curtain = Canvas::new(
+            Rectangle::new_coords(0, status_cliprect.br().y + 1, screensize.x, screensize.y),
+            CURTAIN_TRUST,
+            trng,
+            None,
+            CanvasType::ScreenLock,
+        )
+        .expect("couldn't create screen lock canvas");
+        let gid = curtain.gid();
+        canvases.insert(gid, curtain);
+        ScreenLock { curtain: gid, locked: false, listener: None }
+    }
+
+    /// The listener can only be set once; the status bar claims it early in boot.
+    pub(crate) fn register_listener(&mut self, sid: [u32; 4], opcode: u32) -> bool {
+        if self.listener.is_some() {
+            return false;
+        }
+        match xous::connect(xous::SID::from_array(sid)) {
+            Ok(cid) => {
+                self.listener = Some((cid, opcode));
+                true
+            }
+            Err(e) => {
+                log::error!("couldn't connect to screen lock listener: {:?}", e);
+                false
+            }
+        }
+    }
+
+    /// Asks the lock owner to lock the screen. The owner does the bookkeeping (e.g. locking out USB
+    /// autotype) and then calls back with `SetScreenLock`.
+    pub(crate) fn request_lock(&self) {
+        if self.locked {
+            return;
+        }
+        if let Some((cid, opcode)) = self.listener {
+            xous::send_message(cid, xous::Message::new_scalar(opcode as usize, 0, 0, 0, 0)).ok();
+        } else {
+            log::warn!("screen lock requested, but no lock owner is registered");
+        }
+    }
+
+    pub(crate) fn is_locked(&self) -> bool { self.locked }
+
+    pub(crate) fn set_locked(&mut self, locked: bool, canvases: &mut HashMap<Gid, Canvas>) {
+        self.locked = locked;
+        if let Some(curtain) = canvases.get_mut(&self.curtain) {
+            curtain.set_onscreen(locked);
+        }
+        recompute_canvases(canvases);
+    }
+
+    /// Repaints the curtain. Skipped while a more trusted canvas (i.e. the PIN prompt) is on screen,
+    /// so the prompt isn't painted over by the periodic redraw.
+    pub(crate) fn render(&self, gfx: &graphics_server::Gfx, canvases: &HashMap<Gid, Canvas>) {
+        if !self.locked {
+            return;
+        }
+        let curtain = match canvases.get(&self.curtain) {
+            Some(c) => c,
+            None => return,
+        };
+        for c in canvases.values() {
+            if c.is_onscreen()
+                && c.trust_level() > CURTAIN_TRUST
+                && c.trust_level() < 255
+                && c.intersects(curtain)
+            {
+                return;
+            }
+        }
+        let cliprect = curtain.clip_rect();
+        let mut rect = cliprect;
+        rect.style = DrawStyle { fill_color: Some(PixelColor::Light), stroke_color: None, stroke_width: 0 };
+        gfx.draw_rectangle(rect).expect("can't draw screen lock curtain");
+
+        let mut bounds = cliprect;
+        bounds.normalize();
+        let mut tv = TextView::new(self.curtain, TextBounds::CenteredTop(bounds));
+        tv.clip_rect = Some(cliprect);
+        tv.untrusted = false;
+        tv.style = GlyphStyle::Bold;
+        tv.margin = Point::new(8, bounds.br().y / 3);
+        tv.draw_border = false;
+        write!(tv, "{}\n\n{}", t!("screenlock.locked", locales::LANG), t!("screenlock.help", locales::LANG))
+            .ok();
+        gfx.draw_textview(&mut tv).expect("couldn't draw screen lock message");
+    }
+}
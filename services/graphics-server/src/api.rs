@@ -120,6 +120,13 @@ pub(crate) enum Opcode {
     /// draw the boot logo (for continuity as apps initialize)
     DrawBootLogo,
 
+    /// hosted mode only: write the framebuffer to a file on the host
+    #[cfg(not(target_os = "xous"))]
+    Snapshot,
+    /// hosted mode only: compare the framebuffer against a golden image on the host
+    #[cfg(not(target_os = "xous"))]
+    CompareSnapshot,
+
     Quit,
 }
 
@@ -162,6 +169,24 @@ impl ClipObjectList {
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub enum SnapshotFormat {
+    /// binary PBM; this is also the format of golden images
+    Pbm,
+    Png,
+}
+
+/// Hosted mode framebuffer capture, for screenshot regression tests.
+#[derive(Debug, Copy, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct Snapshot {
+    /// path on the host to write the image to, or to read the golden image from
+    pub path: xous_ipc::String<256>,
+    pub format: SnapshotFormat,
+    /// filled in by the server with the number of pixels that differ from the golden image (always 0
+    /// when just saving). `None` if the file couldn't be written or read.
+    pub mismatches: Option<u32>,
+}
+
 #[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Copy, Clone)]
 pub struct TokenClaim {
     pub token: Option<[u32; 4]>,
@@ -71,6 +71,16 @@ impl From<usize> for TextOp {
     }
 }
 
+/// Paragraph direction of a TextView
+#[derive(Debug, Copy, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, PartialEq, Eq)]
+pub enum TextDirection {
+    /// each paragraph takes the direction of its first letter, as with the Unicode Bidirectional
+    /// Algorithm; left-to-right if it has none
+    Auto,
+    LeftToRight,
+    RightToLeft,
+}
+
 // roughly 168 bytes to represent the rest of the struct, and we want to fill out the 4096 byte page with text
 const TEXTVIEW_LEN: usize = 3072;
 pub const TEXTVIEW_DEFAULT_STYLE: GlyphStyle = GlyphStyle::Regular;
@@ -103,6 +113,7 @@ pub struct TextView {
     pub cursor: Cursor,
     pub insertion: Option<i32>, // this is the insertion point offset, if it's to be drawn, on the string
     pub ellipsis: bool,
+    pub direction: TextDirection,
 
     pub draw_border: bool,
     pub clear_area: bool, // you almost always want this to be true
@@ -134,6 +145,7 @@ impl TextView {
             cursor: Cursor::new(0, 0, 0),
             insertion: None,
             ellipsis: false,
+            direction: TextDirection::Auto,
             draw_border: true,
             border_width: 1,
             rounded_border: None,
@@ -181,6 +193,7 @@ impl TextView {
         self.clip_rect = t.clip_rect;
         self.dry_run = t.dry_run;
         self.insertion = t.insertion;
+        self.direction = t.direction;
     }
 }
 
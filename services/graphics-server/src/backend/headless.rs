@@ -0,0 +1,73 @@
+#![cfg_attr(not(target_os = "none"), allow(dead_code))]
+
+use crate::api::Point;
+use crate::api::{LINES, WIDTH};
+
+const HEIGHT: i16 = LINES;
+
+/// Width of the screen in 32-bit words
+const WIDTH_WORDS: usize = 11;
+pub const FB_WIDTH_WORDS: usize = WIDTH_WORDS;
+pub const FB_WIDTH_PIXELS: usize = WIDTH as usize;
+pub const FB_LINES: usize = HEIGHT as usize;
+pub const FB_SIZE: usize = WIDTH_WORDS * HEIGHT as usize; // 44 bytes by 536 lines
+
+/// The headless backend has no event loop, so there is nothing to hand back to the main thread. The
+/// token is kept so the backends stay interchangeable.
+pub struct MainThreadToken(());
+
+/// A substitute for the native never type (`!`), which is still unstable on
+/// `Fn` bounds.
+pub enum Never {}
+
+/// There is no GUI to run, so the closure just runs on the calling thread.
+pub fn claim_main_thread(f: impl FnOnce(MainThreadToken) -> Never + Send + 'static) -> ! {
+    match f(MainThreadToken(())) {}
+}
+
+/// An in-memory stand-in for the LCD, for running hosted mode without a display (e.g. in CI). Nothing
+/// is ever shown; the contents can be inspected with the `Snapshot` opcodes.
+pub struct XousDisplay {
+    emulated_buffer: [u32; FB_SIZE],
+    srfb: [u32; FB_SIZE],
+    devboot: bool,
+}
+
+impl XousDisplay {
+    pub fn new(_main_thread_token: MainThreadToken) -> XousDisplay {
+        log::info!("GFX|headless: no display attached, framebuffer is kept in memory only");
+        XousDisplay { emulated_buffer: [0u32; FB_SIZE], srfb: [0u32; FB_SIZE], devboot: true }
+    }
+
+    pub fn set_devboot(&mut self, ena: bool) {
+        if ena {
+            self.devboot = true;
+        }
+        // ignore attempts to turn off devboot
+    }
+
+    pub fn suspend(&self) {}
+
+    pub fn resume(&self) {}
+
+    pub fn stash(&mut self) { self.srfb.copy_from_slice(&self.emulated_buffer); }
+
+    pub fn pop(&mut self) {
+        self.emulated_buffer[FB_WIDTH_WORDS * 32..].copy_from_slice(&self.srfb[FB_WIDTH_WORDS * 32..]);
+        self.redraw();
+    }
+
+    pub fn screen_size(&self) -> Point { Point::new(WIDTH as i16, HEIGHT as i16) }
+
+    pub fn blit_screen(&mut self, bmp: &[u32]) {
+        for (dest, src) in self.emulated_buffer.iter_mut().zip(bmp.iter()) {
+            *dest = *src;
+        }
+    }
+
+    pub fn as_slice(&self) -> &[u32] { &self.emulated_buffer }
+
+    pub fn native_buffer(&mut self) -> &mut [u32; FB_SIZE] { &mut self.emulated_buffer }
+
+    pub fn redraw(&mut self) {}
+}
@@ -241,6 +241,8 @@ impl XousKeyboardHandler {
                 Key::Up => '↑',
                 Key::Down => '↓',
                 Key::Home => '∴',
+                // stands in for a long press on F4 (keyboard::SWITCH_PANE_KEY)
+                Key::Tab => '⇅',
                 Key::Backspace => '\u{0008}',
                 Key::Delete => '\u{0008}',
                 Key::Enter => 0xd_u8.into(),
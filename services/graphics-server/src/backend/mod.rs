@@ -1,8 +1,13 @@
-#[cfg(all(not(target_os = "xous")))]
+#[cfg(all(not(target_os = "xous"), not(feature = "headless")))]
 mod minifb;
-#[cfg(all(not(target_os = "xous")))]
+#[cfg(all(not(target_os = "xous"), not(feature = "headless")))]
 pub use crate::backend::minifb::*;
 
+#[cfg(all(not(target_os = "xous"), feature = "headless"))]
+mod headless;
+#[cfg(all(not(target_os = "xous"), feature = "headless"))]
+pub use crate::backend::headless::*;
+
 #[cfg(any(feature = "precursor", feature = "renode"))]
 mod betrusted;
 #[cfg(any(feature = "precursor", feature = "renode"))]
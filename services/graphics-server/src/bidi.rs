@@ -0,0 +1,777 @@
+//! The Unicode Bidirectional Algorithm (UAX #9), for laying out text that mixes left-to-right and
+//! right-to-left scripts.
+//!
+//! `Levels::new` splits text into paragraphs and resolves the embedding level of every character
+//! (rules P2 to I2). Once the text is broken into lines, `Levels::line` gives the levels of one line
+//! (L1), `visual_order` the order to draw its characters in (L2), and `mirror` the glyph that a
+//! character shows in right-to-left text (L4).
+//!
+//! The character classes cover Latin, Hebrew, Arabic, and the common punctuation, symbols and
+//! controls. Anything else is taken to be left-to-right.
+
+use std::cmp::Ordering;
+
+/// Bidi_Class values, named as in UAX #9
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum BidiClass {
+    L,
+    R,
+    AL,
+    EN,
+    ES,
+    ET,
+    AN,
+    CS,
+    NSM,
+    BN,
+    B,
+    S,
+    WS,
+    ON,
+    LRE,
+    LRO,
+    RLE,
+    RLO,
+    PDF,
+    LRI,
+    RLI,
+    FSI,
+    PDI,
+}
+use BidiClass::*;
+
+/// Deepest embedding level allowed by rule BD2
+const MAX_DEPTH: u8 = 125;
+
+/// Ranges of codepoints that aren't class L, sorted and not overlapping
+const CLASSES: &[(u32, u32, BidiClass)] = &[
+    (0x0000, 0x0008, BN),
+    (0x0009, 0x0009, S),
+    (0x000A, 0x000A, B),
+    (0x000B, 0x000B, S),
+    (0x000C, 0x000C, WS),
+    (0x000D, 0x000D, B),
+    (0x000E, 0x001B, BN),
+    (0x001C, 0x001E, B),
+    (0x001F, 0x001F, S),
+    (0x0020, 0x0020, WS),
+    (0x0021, 0x0022, ON),
+    (0x0023, 0x0025, ET),
+    (0x0026, 0x002A, ON),
+    (0x002B, 0x002B, ES),
+    (0x002C, 0x002C, CS),
+    (0x002D, 0x002D, ES),
+    (0x002E, 0x002F, CS),
+    (0x0030, 0x0039, EN),
+    (0x003A, 0x003A, CS),
+    (0x003B, 0x0040, ON),
+    (0x005B, 0x0060, ON),
+    (0x007B, 0x007E, ON),
+    (0x007F, 0x0084, BN),
+    (0x0085, 0x0085, B),
+    (0x0086, 0x009F, BN),
+    (0x00A0, 0x00A0, CS),
+    (0x00A1, 0x00A1, ON),
+    (0x00A2, 0x00A5, ET),
+    (0x00A6, 0x00A9, ON),
+    (0x00AB, 0x00AC, ON),
+    (0x00AD, 0x00AD, BN),
+    (0x00AE, 0x00AF, ON),
+    (0x00B0, 0x00B1, ET),
+    (0x00B2, 0x00B3, EN),
+    (0x00B4, 0x00B4, ON),
+    (0x00B6, 0x00B8, ON),
+    (0x00B9, 0x00B9, EN),
+    (0x00BB, 0x00BF, ON),
+    (0x00D7, 0x00D7, ON),
+    (0x00F7, 0x00F7, ON),
+    (0x0300, 0x036F, NSM),
+    (0x0590, 0x0590, R),
+    (0x0591, 0x05BD, NSM),
+    (0x05BE, 0x05BE, R),
+    (0x05BF, 0x05BF, NSM),
+    (0x05C0, 0x05C0, R),
+    (0x05C1, 0x05C2, NSM),
+    (0x05C3, 0x05C3, R),
+    (0x05C4, 0x05C5, NSM),
+    (0x05C6, 0x05C6, R),
+    (0x05C7, 0x05C7, NSM),
+    (0x05C8, 0x05FF, R),
+    (0x0600, 0x0605, AN),
+    (0x0606, 0x0607, ON),
+    (0x0608, 0x0608, AL),
+    (0x0609, 0x060A, ET),
+    (0x060B, 0x060B, AL),
+    (0x060C, 0x060C, CS),
+    (0x060D, 0x060D, AL),
+    (0x060E, 0x060F, ON),
+    (0x0610, 0x061A, NSM),
+    (0x061B, 0x064A, AL),
+    (0x064B, 0x065F, NSM),
+    (0x0660, 0x0669, AN),
+    (0x066A, 0x066A, ET),
+    (0x066B, 0x066C, AN),
+    (0x066D, 0x066F, AL),
+    (0x0670, 0x0670, NSM),
+    (0x0671, 0x06D5, AL),
+    (0x06D6, 0x06DC, NSM),
+    (0x06DD, 0x06DD, AN),
+    (0x06DE, 0x06DE, ON),
+    (0x06DF, 0x06E4, NSM),
+    (0x06E5, 0x06E6, AL),
+    (0x06E7, 0x06E8, NSM),
+    (0x06E9, 0x06E9, ON),
+    (0x06EA, 0x06ED, NSM),
+    (0x06EE, 0x06EF, AL),
+    (0x06F0, 0x06F9, EN),
+    (0x06FA, 0x07BF, AL),
+    (0x07C0, 0x085F, R),
+    (0x0860, 0x08D2, AL),
+    (0x08D3, 0x08FF, NSM),
+    (0x2000, 0x200A, WS),
+    (0x200B, 0x200D, BN),
+    (0x200F, 0x200F, R),
+    (0x2010, 0x2027, ON),
+    (0x2028, 0x2028, WS),
+    (0x2029, 0x2029, B),
+    (0x202A, 0x202A, LRE),
+    (0x202B, 0x202B, RLE),
+    (0x202C, 0x202C, PDF),
+    (0x202D, 0x202D, LRO),
+    (0x202E, 0x202E, RLO),
+    (0x202F, 0x202F, CS),
+    (0x2030, 0x2034, ET),
+    (0x2035, 0x2043, ON),
+    (0x2044, 0x2044, CS),
+    (0x2045, 0x205E, ON),
+    (0x205F, 0x205F, WS),
+    (0x2060, 0x2065, BN),
+    (0x2066, 0x2066, LRI),
+    (0x2067, 0x2067, RLI),
+    (0x2068, 0x2068, FSI),
+    (0x2069, 0x2069, PDI),
+    (0x206A, 0x206F, BN),
+    (0x2070, 0x2070, EN),
+    (0x2074, 0x2079, EN),
+    (0x207A, 0x207B, ES),
+    (0x207C, 0x207E, ON),
+    (0x2080, 0x2089, EN),
+    (0x208A, 0x208B, ES),
+    (0x208C, 0x208E, ON),
+    (0x20A0, 0x20CF, ET),
+    (0x2190, 0x2211, ON),
+    (0x2212, 0x2212, ES),
+    (0x2213, 0x2213, ET),
+    (0x2214, 0x2335, ON),
+    (0x237B, 0x2394, ON),
+    (0x2396, 0x2487, ON),
+    (0x2488, 0x249B, EN),
+    (0x24EA, 0x26AB, ON),
+    (0x26AD, 0x27FF, ON),
+    (0x2900, 0x2B73, ON),
+    (0x3000, 0x3000, WS),
+    (0x3001, 0x3004, ON),
+    (0x3008, 0x3020, ON),
+    (0xFB1D, 0xFB1D, R),
+    (0xFB1E, 0xFB1E, NSM),
+    (0xFB1F, 0xFB28, R),
+    (0xFB29, 0xFB29, ES),
+    (0xFB2A, 0xFB4F, R),
+    (0xFB50, 0xFD3D, AL),
+    (0xFD3E, 0xFD3F, ON),
+    (0xFD40, 0xFDFF, AL),
+    (0xFE00, 0xFE0F, NSM),
+    (0xFE70, 0xFEFE, AL),
+    (0xFEFF, 0xFEFF, BN),
+    (0xFF01, 0xFF02, ON),
+    (0xFF03, 0xFF05, ET),
+    (0xFF06, 0xFF0A, ON),
+    (0xFF0B, 0xFF0B, ES),
+    (0xFF0C, 0xFF0C, CS),
+    (0xFF0D, 0xFF0D, ES),
+    (0xFF0E, 0xFF0F, CS),
+    (0xFF10, 0xFF19, EN),
+    (0xFF1A, 0xFF1A, CS),
+    (0xFF1B, 0xFF20, ON),
+    (0xFF3B, 0xFF40, ON),
+    (0xFF5B, 0xFF65, ON),
+    (0x10800, 0x10FFF, R),
+    (0x1E800, 0x1EFFF, R),
+    (0x1F000, 0x1FAFF, ON),
+];
+
+/// Characters that are mirrored in right-to-left text, in pairs
+const MIRRORS: &[(char, char)] = &[
+    ('(', ')'),
+    ('<', '>'),
+    ('[', ']'),
+    ('{', '}'),
+    ('«', '»'),
+    ('‹', '›'),
+    ('⁅', '⁆'),
+    ('⁽', '⁾'),
+    ('₍', '₎'),
+    ('≤', '≥'),
+    ('≪', '≫'),
+    ('⊂', '⊃'),
+    ('⊆', '⊇'),
+    ('〈', '〉'),
+    ('《', '》'),
+    ('「', '」'),
+    ('『', '』'),
+    ('【', '】'),
+    ('（', '）'),
+    ('［', '］'),
+    ('｛', '｝'),
+];
+
+/// Opening and closing paired brackets, for rule N0
+const BRACKETS: &[(char, char)] =
+    &[('(', ')'), ('[', ']'), ('{', '}'), ('〈', '〉'), ('（', '）'), ('［', '］'), ('｛', '｝')];
+
+/// Deepest nesting of brackets looked at by rule N0
+const MAX_BRACKET_DEPTH: usize = 63;
+
+pub(crate) fn class(ch: char) -> BidiClass {
+    let cp = ch as u32;
+    match CLASSES.binary_search_by(|&(lo, hi, _)| {
+        if hi < cp {
+            Ordering::Less
+        } else if lo > cp {
+            Ordering::Greater
+        } else {
+            Ordering::Equal
+        }
+    }) {
+        Ok(i) => CLASSES[i].2,
+        Err(_) => L,
+    }
+}
+
+/// True for the characters that control layout but aren't drawn: the bidi formatting characters and
+/// the Hebrew and Arabic marks, which the fonts don't have.
+pub(crate) fn is_invisible(ch: char) -> bool {
+    matches!(ch as u32, 0x061C | 0x200B..=0x200F | 0x202A..=0x202E | 0x2060..=0x2069 | 0xFEFF)
+        || (matches!(ch as u32, 0x0590..=0x08FF) && class(ch) == NSM)
+}
+
+/// True if `ch` starts right-to-left text
+pub(crate) fn is_rtl(ch: char) -> bool { matches!(class(ch), R | AL | AN | RLE | RLO | RLI | FSI) }
+
+/// The glyph to show for `ch` in right-to-left text
+pub(crate) fn mirror(ch: char) -> char {
+    for &(a, b) in MIRRORS {
+        if ch == a {
+            return b;
+        } else if ch == b {
+            return a;
+        }
+    }
+    ch
+}
+
+/// The resolved levels of a text
+pub(crate) struct Levels {
+    /// Embedding level of each character; odd levels are right-to-left
+    pub levels: Vec<u8>,
+    /// Level of the paragraph that each character is in
+    pub paragraph: Vec<u8>,
+    classes: Vec<BidiClass>,
+}
+
+impl Levels {
+    /// Resolves the levels of `chars`. Each paragraph takes its direction from its first strong
+    /// character, unless `level` is given.
+    pub fn new(chars: &[char], level: Option<u8>) -> Self {
+        let classes: Vec<BidiClass> = chars.iter().map(|&c| class(c)).collect();
+        let mut levels = Vec::with_capacity(chars.len());
+        let mut paragraph = Vec::with_capacity(chars.len());
+        let mut start = 0;
+        while start < chars.len() {
+            let end = classes[start..].iter().position(|&c| c == B).map_or(chars.len(), |i| start + i + 1);
+            let (para_level, para_levels) = resolve(&chars[start..end], &classes[start..end], level);
+            levels.extend(para_levels);
+            paragraph.resize(end, para_level);
+            start = end;
+        }
+        Levels { levels, paragraph, classes }
+    }
+
+    /// The paragraph level and the levels of a line, given as the indices of its characters in
+    /// logical order. Whitespace at the end of the line, and before tabs, goes back to the paragraph
+    /// level (rule L1). A paragraph separator at the start of a line ends the paragraph before, but
+    /// is drawn with this one, so it takes this paragraph's level.
+    pub fn line(&self, indices: &[usize]) -> (u8, Vec<u8>) {
+        let level = indices
+            .iter()
+            .rev()
+            .find(|&&i| self.classes[i] != B)
+            .or_else(|| indices.first())
+            .map_or(0, |&i| self.paragraph[i]);
+        let mut levels: Vec<u8> = indices.iter().map(|&i| self.levels[i]).collect();
+        let mut trailing = true;
+        for (k, &i) in indices.iter().enumerate().rev() {
+            match self.classes[i] {
+                B | S => {
+                    levels[k] = level;
+                    trailing = true;
+                }
+                WS | BN | LRE | RLE | LRO | RLO | PDF | LRI | RLI | FSI | PDI if trailing => {
+                    levels[k] = level
+                }
+                _ => trailing = false,
+            }
+        }
+        (level, levels)
+    }
+}
+
+/// The order to draw a line in: the index into `levels` of each position from the left (rule L2)
+pub(crate) fn visual_order(levels: &[u8]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..levels.len()).collect();
+    let highest = levels.iter().copied().max().unwrap_or(0);
+    let lowest_odd = match levels.iter().copied().filter(|l| l & 1 == 1).min() {
+        Some(l) => l,
+        None => return order,
+    };
+    for level in (lowest_odd..=highest).rev() {
+        let mut k = 0;
+        while k < order.len() {
+            if levels[order[k]] >= level {
+                let start = k;
+                while k < order.len() && levels[order[k]] >= level {
+                    k += 1;
+                }
+                order[start..k].reverse();
+            } else {
+                k += 1;
+            }
+        }
+    }
+    order
+}
+
+fn next_level(level: u8, rtl: bool) -> u8 { if rtl { (level + 1) | 1 } else { (level + 2) & !1 } }
+
+fn direction(level: u8) -> BidiClass { if level & 1 == 0 { L } else { R } }
+
+/// The strong direction of a resolved class, counting numbers as right-to-left (rules N0 and N1)
+fn strong(class: BidiClass) -> Option<BidiClass> {
+    match class {
+        L => Some(L),
+        R | AL | EN | AN => Some(R),
+        _ => None,
+    }
+}
+
+/// The first strong class between `start` and `end`, skipping isolates (rules P2 and P3)
+fn first_strong(
+    classes: &[BidiClass],
+    matching: &[Option<usize>],
+    start: usize,
+    end: usize,
+) -> Option<BidiClass> {
+    let mut i = start;
+    while i < end {
+        match classes[i] {
+            L => return Some(L),
+            R | AL => return Some(R),
+            LRI | RLI | FSI => match matching[i] {
+                Some(pdi) => i = pdi,
+                None => return None,
+            },
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Resolves one paragraph, returning its level and the level of each of its characters
+fn resolve(chars: &[char], original: &[BidiClass], level: Option<u8>) -> (u8, Vec<u8>) {
+    let n = chars.len();
+    // BD9: the PDI that closes each isolate initiator
+    let mut matching: Vec<Option<usize>> = vec![None; n];
+    let mut opened_by: Vec<Option<usize>> = vec![None; n];
+    let mut open = Vec::new();
+    for (i, &c) in original.iter().enumerate() {
+        match c {
+            LRI | RLI | FSI => open.push(i),
+            PDI => {
+                if let Some(o) = open.pop() {
+                    matching[o] = Some(i);
+                    opened_by[i] = Some(o);
+                }
+            }
+            _ => {}
+        }
+    }
+    let para_level = level.unwrap_or_else(|| match first_strong(original, &matching, 0, n) {
+        Some(R) => 1,
+        _ => 0,
+    });
+
+    // X1-X8: explicit embeddings, overrides and isolates
+    #[derive(Copy, Clone)]
+    struct Status {
+        level: u8,
+        over: Option<BidiClass>,
+        isolate: bool,
+    }
+    let mut stack = vec![Status { level: para_level, over: None, isolate: false }];
+    let mut overflow_isolates = 0;
+    let mut overflow_embeddings = 0;
+    let mut valid_isolates = 0;
+    let mut levels = vec![para_level; n];
+    let mut classes = original.to_vec();
+    for i in 0..n {
+        let last = *stack.last().unwrap();
+        match original[i] {
+            RLE | LRE | RLO | LRO => {
+                let new_level = next_level(last.level, matches!(original[i], RLE | RLO));
+                if new_level <= MAX_DEPTH && overflow_isolates == 0 && overflow_embeddings == 0 {
+                    let over = match original[i] {
+                        RLO => Some(R),
+                        LRO => Some(L),
+                        _ => None,
+                    };
+                    stack.push(Status { level: new_level, over, isolate: false });
+                } else if overflow_isolates == 0 {
+                    overflow_embeddings += 1;
+                }
+                levels[i] = last.level;
+                // X9: removed from here on
+                classes[i] = BN;
+            }
+            RLI | LRI | FSI => {
+                levels[i] = last.level;
+                if let Some(over) = last.over {
+                    classes[i] = over;
+                }
+                let rtl = match original[i] {
+                    RLI => true,
+                    LRI => false,
+                    _ => first_strong(original, &matching, i + 1, matching[i].unwrap_or(n)) == Some(R),
+                };
+                let new_level = next_level(last.level, rtl);
+                if new_level <= MAX_DEPTH && overflow_isolates == 0 && overflow_embeddings == 0 {
+                    valid_isolates += 1;
+                    stack.push(Status { level: new_level, over: None, isolate: true });
+                } else {
+                    overflow_isolates += 1;
+                }
+            }
+            PDI => {
+                if overflow_isolates > 0 {
+                    overflow_isolates -= 1;
+                } else if valid_isolates > 0 {
+                    overflow_embeddings = 0;
+                    while !stack.last().unwrap().isolate {
+                        stack.pop();
+                    }
+                    stack.pop();
+                    valid_isolates -= 1;
+                }
+                let last = *stack.last().unwrap();
+                levels[i] = last.level;
+                if let Some(over) = last.over {
+                    classes[i] = over;
+                }
+            }
+            PDF => {
+                if overflow_isolates == 0 {
+                    if overflow_embeddings > 0 {
+                        overflow_embeddings -= 1;
+                    } else if !last.isolate && stack.len() >= 2 {
+                        stack.pop();
+                    }
+                }
+                levels[i] = last.level;
+                classes[i] = BN;
+            }
+            B => levels[i] = para_level,
+            BN => levels[i] = last.level,
+            _ => {
+                levels[i] = last.level;
+                if let Some(over) = last.over {
+                    classes[i] = over;
+                }
+            }
+        }
+    }
+
+    // X10: level runs, joined into isolating run sequences across isolates
+    let mut runs: Vec<Vec<usize>> = Vec::new();
+    let mut run_of = vec![usize::MAX; n];
+    let mut run_level = None;
+    for i in (0..n).filter(|&i| classes[i] != BN) {
+        if run_level != Some(levels[i]) {
+            runs.push(Vec::new());
+            run_level = Some(levels[i]);
+        }
+        runs.last_mut().unwrap().push(i);
+        run_of[i] = runs.len() - 1;
+    }
+    let continues = |run: &Vec<usize>| {
+        opened_by[run[0]].map_or(false, |o| run_of[o] != usize::MAX && *runs[run_of[o]].last().unwrap() == o)
+    };
+    for r in 0..runs.len() {
+        if continues(&runs[r]) {
+            continue;
+        }
+        let mut sequence = Vec::new();
+        let mut cur = r;
+        loop {
+            sequence.extend_from_slice(&runs[cur]);
+            let last = *runs[cur].last().unwrap();
+            match matching[last] {
+                Some(pdi) if matches!(original[last], LRI | RLI | FSI) && run_of[pdi] != usize::MAX => {
+                    let next = run_of[pdi];
+                    if runs[next][0] != pdi {
+                        break;
+                    }
+                    cur = next;
+                }
+                _ => break,
+            }
+        }
+        resolve_sequence(chars, original, &classes, &mut levels, &sequence, para_level);
+    }
+
+    // characters removed by X9 take the level of the one before, so they stay with it
+    for i in 0..n {
+        if classes[i] == BN {
+            levels[i] = if i == 0 { para_level } else { levels[i - 1] };
+        }
+    }
+    (para_level, levels)
+}
+
+/// Resolves the weak and neutral types of an isolating run sequence, then its levels (W1-I2)
+fn resolve_sequence(
+    chars: &[char],
+    original: &[BidiClass],
+    classes: &[BidiClass],
+    levels: &mut [u8],
+    sequence: &[usize],
+    para_level: u8,
+) {
+    let len = sequence.len();
+    let first = sequence[0];
+    let last = sequence[len - 1];
+    let level = levels[first];
+    let before = (0..first).rev().find(|&i| classes[i] != BN).map_or(para_level, |i| levels[i]);
+    let sos = direction(level.max(before));
+    let after = if matches!(original[last], LRI | RLI | FSI) {
+        para_level
+    } else {
+        (last + 1..classes.len()).find(|&i| classes[i] != BN).map_or(para_level, |i| levels[i])
+    };
+    let eos = direction(levels[last].max(after));
+    let e = direction(level);
+    let mut types: Vec<BidiClass> = sequence.iter().map(|&i| classes[i]).collect();
+
+    // W1: marks take the type of what they're on
+    let mut prev = sos;
+    for t in types.iter_mut() {
+        if *t == NSM {
+            *t = match prev {
+                LRI | RLI | FSI | PDI => ON,
+                p => p,
+            };
+        }
+        prev = *t;
+    }
+    // W2 and W3: European numbers after Arabic letters are Arabic numbers
+    let mut last_strong = sos;
+    for t in types.iter_mut() {
+        match *t {
+            L | R => last_strong = *t,
+            AL => {
+                last_strong = AL;
+                *t = R;
+            }
+            EN if last_strong == AL => *t = AN,
+            _ => {}
+        }
+    }
+    // W4: single separators between numbers
+    for k in 1..len.saturating_sub(1) {
+        let (p, n) = (types[k - 1], types[k + 1]);
+        match types[k] {
+            ES if p == EN && n == EN => types[k] = EN,
+            CS if p == n && matches!(p, EN | AN) => types[k] = p,
+            _ => {}
+        }
+    }
+    // W5: terminators next to European numbers
+    let mut k = 0;
+    while k < len {
+        if types[k] == ET {
+            let start = k;
+            while k < len && types[k] == ET {
+                k += 1;
+            }
+            if (start > 0 && types[start - 1] == EN) || (k < len && types[k] == EN) {
+                types[start..k].iter_mut().for_each(|t| *t = EN);
+            }
+        } else {
+            k += 1;
+        }
+    }
+    // W6 and W7
+    let mut last_strong = sos;
+    for t in types.iter_mut() {
+        match *t {
+            ES | ET | CS => *t = ON,
+            L | R => last_strong = *t,
+            EN if last_strong == L => *t = L,
+            _ => {}
+        }
+    }
+
+    // N0: paired brackets
+    let mut pairs = Vec::new();
+    let mut open: Vec<(char, usize)> = Vec::new();
+    for k in 0..len {
+        if types[k] != ON {
+            continue;
+        }
+        let ch = chars[sequence[k]];
+        if let Some(&(_, close)) = BRACKETS.iter().find(|(o, _)| *o == ch) {
+            if open.len() == MAX_BRACKET_DEPTH {
+                break;
+            }
+            open.push((close, k));
+        } else if BRACKETS.iter().any(|(_, c)| *c == ch) {
+            if let Some(pos) = open.iter().rposition(|(c, _)| *c == ch) {
+                pairs.push((open[pos].1, k));
+                open.truncate(pos);
+            }
+        }
+    }
+    pairs.sort_unstable();
+    for (o, c) in pairs {
+        let inside: Vec<BidiClass> = types[o + 1..c].iter().filter_map(|&t| strong(t)).collect();
+        let dir = if inside.contains(&e) {
+            Some(e)
+        } else if let Some(&opposite) = inside.first() {
+            let context = types[..o].iter().rev().find_map(|&t| strong(t)).unwrap_or(sos);
+            Some(if context == opposite { opposite } else { e })
+        } else {
+            None
+        };
+        if let Some(dir) = dir {
+            for b in [o, c] {
+                types[b] = dir;
+                for k in b + 1..len {
+                    if original[sequence[k]] != NSM {
+                        break;
+                    }
+                    types[k] = dir;
+                }
+            }
+        }
+    }
+
+    // N1 and N2: runs of neutrals take the direction around them if it's the same on both sides, and
+    // the embedding direction otherwise
+    let neutral = |t: BidiClass| matches!(t, B | S | WS | ON | LRI | RLI | FSI | PDI);
+    let mut k = 0;
+    while k < len {
+        if neutral(types[k]) {
+            let start = k;
+            while k < len && neutral(types[k]) {
+                k += 1;
+            }
+            let before = if start == 0 { sos } else { strong(types[start - 1]).unwrap_or(e) };
+            let after = if k == len { eos } else { strong(types[k]).unwrap_or(e) };
+            let dir = if before == after { before } else { e };
+            types[start..k].iter_mut().for_each(|t| *t = dir);
+        } else {
+            k += 1;
+        }
+    }
+
+    // I1 and I2
+    for (k, &i) in sequence.iter().enumerate() {
+        levels[i] = match (levels[i] & 1, types[k]) {
+            (0, R) => levels[i] + 1,
+            (0, AN | EN) => levels[i] + 2,
+            (1, L | EN | AN) => levels[i] + 1,
+            _ => levels[i],
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn levels(s: &str, level: Option<u8>) -> Vec<u8> {
+        let chars: Vec<char> = s.chars().collect();
+        Levels::new(&chars, level).levels
+    }
+
+    /// `s` as drawn, left to right
+    fn visual(s: &str) -> String {
+        let chars: Vec<char> = s.chars().collect();
+        let resolved = Levels::new(&chars, None);
+        let indices: Vec<usize> = (0..chars.len()).collect();
+        let (_, line) = resolved.line(&indices);
+        visual_order(&line)
+            .iter()
+            .map(|&i| if line[i] & 1 == 1 { mirror(chars[i]) } else { chars[i] })
+            .collect()
+    }
+
+    #[test]
+    fn classes_are_sorted() {
+        for pair in CLASSES.windows(2) {
+            assert!(pair[0].0 <= pair[0].1 && pair[0].1 < pair[1].0, "{:x?}", pair);
+        }
+        assert_eq!(class('a'), L);
+        assert_eq!(class('א'), R);
+        assert_eq!(class('ب'), AL);
+        assert_eq!(class('٣'), AN);
+        assert_eq!(class('\u{05B4}'), NSM);
+    }
+
+    #[test]
+    fn paragraphs() {
+        assert_eq!(levels("ab אב", None), vec![0, 0, 0, 1, 1]);
+        assert_eq!(levels("אב ab", None), vec![1, 1, 1, 2, 2]);
+        assert_eq!(levels("ab", Some(1)), vec![2, 2]);
+        // each paragraph has its own direction
+        assert_eq!(levels("אב\nab", None), vec![1, 1, 1, 0, 0]);
+        // numbers
+        assert_eq!(levels("אב 12", None), vec![1, 1, 1, 2, 2]);
+        assert_eq!(levels("بب ١٢", None), vec![1, 1, 1, 2, 2]);
+        assert_eq!(levels("بب 12", None), vec![1, 1, 1, 2, 2]);
+    }
+
+    #[test]
+    fn reordering() {
+        assert_eq!(visual("abc אבג def"), "abc גבא def");
+        assert_eq!(visual("אבג abc דה"), "הד abc גבא");
+        assert_eq!(visual("אב 123 גד"), "דג 123 בא");
+        assert_eq!(visual("abc (אבג)"), "abc (גבא)");
+        assert_eq!(visual("אב (cd) גד."), ".דג (cd) בא");
+        assert_eq!(visual("אב 1.5%"), "1.5% בא");
+        // overrides and isolates
+        assert_eq!(visual("\u{202E}abc\u{202C}"), "\u{202E}cba\u{202C}");
+        assert_eq!(visual("אב \u{2066}cd ef\u{2069}"), "\u{2069}cd ef\u{2066} בא");
+        assert_eq!(visual("a \u{2068}אב\u{2069} c"), "a \u{2068}בא\u{2069} c");
+    }
+
+    #[test]
+    fn trailing_whitespace() {
+        let chars: Vec<char> = "אב  ".chars().collect();
+        let resolved = Levels::new(&chars, Some(0));
+        let (level, line) = resolved.line(&[0, 1, 2, 3]);
+        assert_eq!(level, 0);
+        assert_eq!(line, vec![1, 1, 0, 0]);
+        assert_eq!(visual_order(&line), vec![1, 0, 2, 3]);
+    }
+}
@@ -0,0 +1,47 @@
+/*
+ * Rasterizes glyphs of an outline font into 16x16 1-bit cells for rtl_font.py.
+ *
+ * usage: rasterize font.ttf pixelsize baseline codepoint...
+ *
+ * Codepoints are hex. For each one, prints the codepoint, the advance width in pixels, and the 16 rows
+ * of the cell as hex u16 with bit 0 as the leftmost pixel; or the codepoint and MISSING if the font
+ * doesn't have it. The baseline is the row that the glyph origin is placed on.
+ */
+#include <ft2build.h>
+#include FT_FREETYPE_H
+#include <stdio.h>
+#include <stdlib.h>
+int main(int argc, char **argv) {
+  FT_Library lib; FT_Face face;
+  FT_Init_FreeType(&lib);
+  if (FT_New_Face(lib, argv[1], 0, &face)) { fprintf(stderr, "face\n"); return 1; }
+  FT_Set_Pixel_Sizes(face, 0, atoi(argv[2]));
+  int baseline = atoi(argv[3]);
+  for (int i = 4; i < argc; i++) {
+    unsigned long cp = strtoul(argv[i], 0, 16);
+    if (!FT_Get_Char_Index(face, cp)) { printf("%05lx MISSING\n", cp); continue; }
+    FT_Load_Char(face, cp, FT_LOAD_RENDER | FT_LOAD_TARGET_MONO | FT_LOAD_MONOCHROME);
+    FT_GlyphSlot g = face->glyph;
+    FT_Bitmap *b = &g->bitmap;
+    unsigned rows[16] = {0};
+    int left = g->bitmap_left < 0 ? 0 : g->bitmap_left;
+    int width = (int)(g->advance.x >> 6);
+    for (int y = 0; y < (int)b->rows; y++) {
+      int ty = baseline - g->bitmap_top + y;
+      if (ty < 0 || ty >= 16) continue;
+      for (int x = 0; x < (int)b->width; x++) {
+        int on = b->buffer[y * b->pitch + (x >> 3)] & (0x80 >> (x & 7));
+        int tx = left + x;
+        if (on && tx < 16) rows[ty] |= 1u << tx;
+      }
+    }
+    int maxx = 0;
+    for (int y = 0; y < 16; y++) for (int x = 0; x < 16; x++) if (rows[y] & (1u<<x)) if (x+1 > maxx) maxx = x+1;
+    if (width < maxx) width = maxx;
+    if (width > 16) width = 16;
+    printf("%05lx %d", cp, width);
+    for (int y = 0; y < 16; y++) printf(" %04x", rows[y]);
+    printf("\n");
+  }
+  return 0;
+}
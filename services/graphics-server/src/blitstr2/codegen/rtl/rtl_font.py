@@ -0,0 +1,133 @@
+#!/usr/bin/env python3
+"""Generates ../../fonts/rtl.rs, the Hebrew and Arabic glyphs used for right-to-left text.
+
+The glyphs are rasterized from DejaVu Sans at 12px by rasterize.c, which needs FreeType:
+
+    cc -O2 -o rasterize rasterize.c $(pkg-config --cflags --libs freetype2)
+    python3 rtl_font.py /usr/share/fonts/truetype/dejavu/DejaVuSans.ttf
+
+Arabic is drawn with the contextual forms in the Arabic Presentation Forms blocks, which the
+typesetter picks while shaping. Arabic letters keep the font's advance width and get no kerning,
+so that joined letters touch; everything else is trimmed to its ink and kerned like the other
+blitstr2 fonts.
+"""
+
+import os
+import subprocess
+import sys
+
+PIXEL_SIZE = 12
+BASELINE = 12
+MAX_HEIGHT = 15
+
+RANGES = [
+    # Hebrew letters and punctuation
+    (0x05BE, 0x05BE), (0x05C0, 0x05C0), (0x05C3, 0x05C3), (0x05D0, 0x05EA), (0x05F3, 0x05F4),
+    # Arabic punctuation, letters and digits
+    (0x060C, 0x060C), (0x061B, 0x061B), (0x061F, 0x061F), (0x0621, 0x063A), (0x0640, 0x064A),
+    (0x0660, 0x066D), (0x067E, 0x067E), (0x0686, 0x0686), (0x0698, 0x0698), (0x06A9, 0x06A9),
+    (0x06AF, 0x06AF), (0x06CC, 0x06CC), (0x06F0, 0x06F9),
+    # Arabic Presentation Forms-A, for the Persian letters
+    (0xFB56, 0xFB59), (0xFB7A, 0xFB7D), (0xFB8A, 0xFB8B), (0xFB8E, 0xFB95), (0xFBFC, 0xFBFF),
+    # Arabic Presentation Forms-B, including the lam-alef ligatures
+    (0xFE80, 0xFEFC),
+]
+
+HEADER = """#![cfg_attr(rustfmt, rustfmt_skip)]
+// DO NOT MAKE EDITS HERE because this file is automatically generated.
+// To make changes, see <xous_root>/services/graphics-server/src/blitstr2/codegen/rtl/rtl_font.py
+//
+// This code includes bitmap glyphs rasterized from the DejaVu Sans typeface,
+// DejaVuSans.ttf version 2.37, as packaged by Debian in fonts-dejavu-core.
+//
+// Copyright (c) 2003 by Bitstream, Inc. All Rights Reserved. Bitstream Vera is
+// a trademark of Bitstream, Inc. DejaVu changes are in public domain.
+//
+// See <xous_root>/services/graphics-server/src/blitstr2/LEGAL.md for the license
+// of the Bitstream Vera and DejaVu fonts.
+//
+//! Hebrew and Arabic Font
+#![allow(dead_code)]
+
+/// Maximum height of glyph patterns in this bitmap typeface.
+pub const MAX_HEIGHT: u8 = {max_height};
+
+/// Unicode character codepoints corresponding to glyph sprites in GLYPHS array.
+/// Indended use:
+///  1. Do binary search on CODEPOINTS to find index of the codepoint corresponding
+///     to the glyph you want to locate
+///  2. Multiply resulting CODEPOINTS index by 8 (<<3) to get index into GLYPHS for
+///     the corresponding glyph sprite (because 16*16px sprite size is 8*u32)
+"""
+
+
+def is_arabic_letter(cp):
+    return 0x0621 <= cp <= 0x064A or cp in (0x067E, 0x0686, 0x0698, 0x06A9, 0x06AF, 0x06CC) \
+        or 0xFB50 <= cp <= 0xFDFF or 0xFE70 <= cp <= 0xFEFF
+
+
+def rasterize(tool, font, cps):
+    args = [tool, font, str(PIXEL_SIZE), str(BASELINE)] + ["%x" % cp for cp in cps]
+    out = subprocess.run(args, check=True, capture_output=True, text=True).stdout
+    glyphs = []
+    for line in out.splitlines():
+        fields = line.split()
+        cp = int(fields[0], 16)
+        if fields[1] == "MISSING":
+            sys.exit("U+%04X is not in %s" % (cp, font))
+        rows = [int(r, 16) for r in fields[2:]]
+        glyphs.append((cp, int(fields[1]), rows))
+    return glyphs
+
+
+def fit(cp, advance, rows):
+    """Returns the width, kern and rows of a glyph as it goes in the font"""
+    if any(rows[MAX_HEIGHT:]):
+        sys.exit("U+%04X is taller than %d rows" % (cp, MAX_HEIGHT))
+    if is_arabic_letter(cp):
+        return advance, 0, rows
+    ink = 0
+    for r in rows:
+        ink |= r
+    if ink == 0:
+        return advance, 1, rows
+    left = (ink & -ink).bit_length() - 1
+    width = ink.bit_length() - left
+    return width, 1, [r >> left for r in rows]
+
+
+def main():
+    here = os.path.dirname(os.path.abspath(__file__))
+    font = sys.argv[1] if len(sys.argv) > 1 else "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf"
+    tool = os.path.join(here, "rasterize")
+    cps = [cp for lo, hi in RANGES for cp in range(lo, hi + 1)]
+    glyphs = [(cp,) + fit(cp, advance, rows) for cp, advance, rows in rasterize(tool, font, cps)]
+
+    out = [HEADER.replace("{max_height}", str(MAX_HEIGHT))]
+    out.append("pub const CODEPOINTS: [u32; %d] = [\n" % len(glyphs))
+    out += ["0x%05X,\n" % g[0] for g in glyphs]
+    out.append("];\n\n/// Width of each glyph in pixels, in the same order as CODEPOINTS.\n")
+    out.append("pub const WIDTHS: [u8; %d] = [\n" % len(glyphs))
+    out += ["%d,\n" % g[1] for g in glyphs]
+    out.append("];\n\n/// Space after each glyph in pixels, in the same order as CODEPOINTS.\n")
+    out.append("/// Arabic letters have none, so that joined letters touch.\n")
+    out.append("pub const KERNS: [u8; %d] = [\n" % len(glyphs))
+    out += ["%d,\n" % g[2] for g in glyphs]
+    out.append("];\n\n")
+    out.append("pub(crate) fn glyphs() -> &'static [u32] { &GLYPHS }\n\n")
+    out.append("/// Packed 16px * 16px glyph pattern data.\n")
+    out.append("/// Pixels are packed in row-major order with LSB of first pixel word\n")
+    out.append("/// containing the top left pixel. Bit of 0 means clear, 1 means set\n")
+    out.append("pub const GLYPHS: [u32; %d] = [\n" % (len(glyphs) * 8))
+    for _, _, _, rows in glyphs:
+        words = ["0x%08x" % (rows[i] | (rows[i + 1] << 16)) for i in range(0, 16, 2)]
+        out.append("    " + ", ".join(words) + ",\n")
+    out.append("];\n")
+
+    with open(os.path.join(here, "..", "..", "fonts", "rtl.rs"), "w") as f:
+        f.write("".join(out))
+    print("wrote %d glyphs" % len(glyphs))
+
+
+if __name__ == "__main__":
+    main()
@@ -8,6 +8,7 @@ pub mod ja;
 pub mod kr;
 pub mod mono;
 pub mod regular;
+pub mod rtl;
 pub mod small;
 pub mod tall;
 pub mod zh;
@@ -232,6 +233,54 @@ pub fn emoji_large_glyph(ch: char) -> Result<GlyphSprite, usize> {
     }
 }
 
+pub fn rtl_glyph(ch: char) -> Result<GlyphSprite, usize> {
+    match rtl::CODEPOINTS.binary_search(&(ch as u32)) {
+        Ok(n) => {
+            let offset = n << 3;
+            let end = offset + 8;
+            match end <= rtl::glyphs().len() {
+                true => Ok(GlyphSprite {
+                    glyph: &rtl::glyphs()[offset..end],
+                    wide: rtl::WIDTHS[n],
+                    high: rtl::MAX_HEIGHT,
+                    kern: rtl::KERNS[n],
+                    ch,
+                    invert: false,
+                    insert: false,
+                    double: false,
+                    large: false,
+                }),
+                false => Err(0),
+            }
+        }
+        _ => Err(1),
+    }
+}
+
+pub fn rtl_large_glyph(ch: char) -> Result<GlyphSprite, usize> {
+    match rtl::CODEPOINTS.binary_search(&(ch as u32)) {
+        Ok(n) => {
+            let offset = n << 3;
+            let end = offset + 8;
+            match end <= rtl::glyphs().len() {
+                true => Ok(GlyphSprite {
+                    glyph: &rtl::glyphs()[offset..end],
+                    wide: rtl::WIDTHS[n] * 2,
+                    high: rtl::MAX_HEIGHT * 2,
+                    kern: rtl::KERNS[n],
+                    ch,
+                    invert: false,
+                    insert: false,
+                    double: true,
+                    large: false,
+                }),
+                false => Err(0),
+            }
+        }
+        _ => Err(1),
+    }
+}
+
 pub fn zh_glyph(ch: char) -> Result<GlyphSprite, usize> {
     match zh::CODEPOINTS.binary_search(&(ch as u32)) {
         Ok(n) => {
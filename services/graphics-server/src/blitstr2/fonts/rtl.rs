@@ -0,0 +1,1040 @@
+#![cfg_attr(rustfmt, rustfmt_skip)]
+// DO NOT MAKE EDITS HERE because this file is automatically generated.
+// To make changes, see <xous_root>/services/graphics-server/src/blitstr2/codegen/rtl/rtl_font.py
+//
+// This code includes bitmap glyphs rasterized from the DejaVu Sans typeface,
+// DejaVuSans.ttf version 2.37, as packaged by Debian in fonts-dejavu-core.
+//
+// Copyright (c) 2003 by Bitstream, Inc. All Rights Reserved. Bitstream Vera is
+// a trademark of Bitstream, Inc. DejaVu changes are in public domain.
+//
+// See <xous_root>/services/graphics-server/src/blitstr2/LEGAL.md for the license
+// of the Bitstream Vera and DejaVu fonts.
+//
+//! Hebrew and Arabic Font
+#![allow(dead_code)]
+
+/// Maximum height of glyph patterns in this bitmap typeface.
+pub const MAX_HEIGHT: u8 = 15;
+
+/// Unicode character codepoints corresponding to glyph sprites in GLYPHS array.
+/// Indended use:
+///  1. Do binary search on CODEPOINTS to find index of the codepoint corresponding
+///     to the glyph you want to locate
+///  2. Multiply resulting CODEPOINTS index by 8 (<<3) to get index into GLYPHS for
+///     the corresponding glyph sprite (because 16*16px sprite size is 8*u32)
+pub const CODEPOINTS: [u32; 249] = [
+0x005BE,
+0x005C0,
+0x005C3,
+0x005D0,
+0x005D1,
+0x005D2,
+0x005D3,
+0x005D4,
+0x005D5,
+0x005D6,
+0x005D7,
+0x005D8,
+0x005D9,
+0x005DA,
+0x005DB,
+0x005DC,
+0x005DD,
+0x005DE,
+0x005DF,
+0x005E0,
+0x005E1,
+0x005E2,
+0x005E3,
+0x005E4,
+0x005E5,
+0x005E6,
+0x005E7,
+0x005E8,
+0x005E9,
+0x005EA,
+0x005F3,
+0x005F4,
+0x0060C,
+0x0061B,
+0x0061F,
+0x00621,
+0x00622,
+0x00623,
+0x00624,
+0x00625,
+0x00626,
+0x00627,
+0x00628,
+0x00629,
+0x0062A,
+0x0062B,
+0x0062C,
+0x0062D,
+0x0062E,
+0x0062F,
+0x00630,
+0x00631,
+0x00632,
+0x00633,
+0x00634,
+0x00635,
+0x00636,
+0x00637,
+0x00638,
+0x00639,
+0x0063A,
+0x00640,
+0x00641,
+0x00642,
+0x00643,
+0x00644,
+0x00645,
+0x00646,
+0x00647,
+0x00648,
+0x00649,
+0x0064A,
+0x00660,
+0x00661,
+0x00662,
+0x00663,
+0x00664,
+0x00665,
+0x00666,
+0x00667,
+0x00668,
+0x00669,
+0x0066A,
+0x0066B,
+0x0066C,
+0x0066D,
+0x0067E,
+0x00686,
+0x00698,
+0x006A9,
+0x006AF,
+0x006CC,
+0x006F0,
+0x006F1,
+0x006F2,
+0x006F3,
+0x006F4,
+0x006F5,
+0x006F6,
+0x006F7,
+0x006F8,
+0x006F9,
+0x0FB56,
+0x0FB57,
+0x0FB58,
+0x0FB59,
+0x0FB7A,
+0x0FB7B,
+0x0FB7C,
+0x0FB7D,
+0x0FB8A,
+0x0FB8B,
+0x0FB8E,
+0x0FB8F,
+0x0FB90,
+0x0FB91,
+0x0FB92,
+0x0FB93,
+0x0FB94,
+0x0FB95,
+0x0FBFC,
+0x0FBFD,
+0x0FBFE,
+0x0FBFF,
+0x0FE80,
+0x0FE81,
+0x0FE82,
+0x0FE83,
+0x0FE84,
+0x0FE85,
+0x0FE86,
+0x0FE87,
+0x0FE88,
+0x0FE89,
+0x0FE8A,
+0x0FE8B,
+0x0FE8C,
+0x0FE8D,
+0x0FE8E,
+0x0FE8F,
+0x0FE90,
+0x0FE91,
+0x0FE92,
+0x0FE93,
+0x0FE94,
+0x0FE95,
+0x0FE96,
+0x0FE97,
+0x0FE98,
+0x0FE99,
+0x0FE9A,
+0x0FE9B,
+0x0FE9C,
+0x0FE9D,
+0x0FE9E,
+0x0FE9F,
+0x0FEA0,
+0x0FEA1,
+0x0FEA2,
+0x0FEA3,
+0x0FEA4,
+0x0FEA5,
+0x0FEA6,
+0x0FEA7,
+0x0FEA8,
+0x0FEA9,
+0x0FEAA,
+0x0FEAB,
+0x0FEAC,
+0x0FEAD,
+0x0FEAE,
+0x0FEAF,
+0x0FEB0,
+0x0FEB1,
+0x0FEB2,
+0x0FEB3,
+0x0FEB4,
+0x0FEB5,
+0x0FEB6,
+0x0FEB7,
+0x0FEB8,
+0x0FEB9,
+0x0FEBA,
+0x0FEBB,
+0x0FEBC,
+0x0FEBD,
+0x0FEBE,
+0x0FEBF,
+0x0FEC0,
+0x0FEC1,
+0x0FEC2,
+0x0FEC3,
+0x0FEC4,
+0x0FEC5,
+0x0FEC6,
+0x0FEC7,
+0x0FEC8,
+0x0FEC9,
+0x0FECA,
+0x0FECB,
+0x0FECC,
+0x0FECD,
+0x0FECE,
+0x0FECF,
+0x0FED0,
+0x0FED1,
+0x0FED2,
+0x0FED3,
+0x0FED4,
+0x0FED5,
+0x0FED6,
+0x0FED7,
+0x0FED8,
+0x0FED9,
+0x0FEDA,
+0x0FEDB,
+0x0FEDC,
+0x0FEDD,
+0x0FEDE,
+0x0FEDF,
+0x0FEE0,
+0x0FEE1,
+0x0FEE2,
+0x0FEE3,
+0x0FEE4,
+0x0FEE5,
+0x0FEE6,
+0x0FEE7,
+0x0FEE8,
+0x0FEE9,
+0x0FEEA,
+0x0FEEB,
+0x0FEEC,
+0x0FEED,
+0x0FEEE,
+0x0FEEF,
+0x0FEF0,
+0x0FEF1,
+0x0FEF2,
+0x0FEF3,
+0x0FEF4,
+0x0FEF5,
+0x0FEF6,
+0x0FEF7,
+0x0FEF8,
+0x0FEF9,
+0x0FEFA,
+0x0FEFB,
+0x0FEFC,
+];
+
+/// Width of each glyph in pixels, in the same order as CODEPOINTS.
+pub const WIDTHS: [u8; 249] = [
+3,
+1,
+1,
+6,
+6,
+4,
+5,
+6,
+1,
+3,
+5,
+6,
+1,
+5,
+5,
+5,
+6,
+6,
+1,
+3,
+6,
+6,
+5,
+6,
+5,
+6,
+6,
+5,
+7,
+6,
+2,
+5,
+2,
+2,
+5,
+6,
+3,
+3,
+6,
+3,
+9,
+3,
+11,
+6,
+11,
+11,
+8,
+8,
+8,
+5,
+5,
+6,
+6,
+15,
+15,
+15,
+15,
+11,
+11,
+7,
+7,
+4,
+12,
+9,
+10,
+9,
+7,
+9,
+6,
+6,
+9,
+9,
+1,
+2,
+5,
+5,
+4,
+5,
+6,
+6,
+5,
+5,
+5,
+4,
+2,
+4,
+11,
+8,
+6,
+11,
+11,
+9,
+1,
+2,
+5,
+5,
+5,
+5,
+4,
+6,
+5,
+5,
+11,
+12,
+3,
+4,
+8,
+8,
+7,
+8,
+6,
+8,
+11,
+12,
+6,
+7,
+11,
+12,
+6,
+7,
+9,
+10,
+3,
+4,
+6,
+3,
+4,
+3,
+4,
+6,
+7,
+3,
+4,
+9,
+10,
+3,
+4,
+3,
+4,
+11,
+12,
+3,
+4,
+6,
+7,
+11,
+12,
+3,
+4,
+11,
+12,
+3,
+4,
+8,
+8,
+7,
+8,
+8,
+8,
+7,
+8,
+8,
+8,
+7,
+8,
+5,
+6,
+5,
+6,
+6,
+8,
+6,
+8,
+15,
+15,
+10,
+11,
+15,
+15,
+10,
+11,
+15,
+15,
+10,
+11,
+15,
+15,
+10,
+11,
+11,
+12,
+10,
+10,
+11,
+12,
+10,
+10,
+7,
+7,
+7,
+6,
+7,
+7,
+6,
+6,
+12,
+13,
+6,
+6,
+9,
+10,
+6,
+6,
+10,
+10,
+6,
+7,
+9,
+9,
+4,
+4,
+7,
+8,
+6,
+7,
+9,
+9,
+3,
+4,
+6,
+7,
+6,
+6,
+6,
+7,
+9,
+10,
+9,
+10,
+3,
+4,
+7,
+8,
+7,
+7,
+7,
+7,
+7,
+7,
+];
+
+/// Space after each glyph in pixels, in the same order as CODEPOINTS.
+/// Arabic letters have none, so that joined letters touch.
+pub const KERNS: [u8; 249] = [
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+0,
+0,
+0,
+0,
+0,
+0,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+1,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+0,
+];
+
+pub(crate) fn glyphs() -> &'static [u32] { &GLYPHS }
+
+/// Packed 16px * 16px glyph pattern data.
+/// Pixels are packed in row-major order with LSB of first pixel word
+/// containing the top left pixel. Bit of 0 means clear, 1 means set
+pub const GLYPHS: [u32; 1992] = [
+    0x00000000, 0x00000000, 0x00070000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00010001, 0x00010001, 0x00010001, 0x00010001, 0x00000001, 0x00000000,
+    0x00000000, 0x00000000, 0x00010000, 0x00000000, 0x00000000, 0x00010000, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00210000, 0x00240022, 0x0009001a, 0x00210011, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x000f0000, 0x00100010, 0x00100010, 0x003f0010, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00030000, 0x00040004, 0x00040004, 0x000b0004, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x001f0000, 0x00080008, 0x00080008, 0x00080008, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x001f0000, 0x00200020, 0x00210021, 0x00210021, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00010000, 0x00010001, 0x00010001, 0x00010001, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00070000, 0x00020002, 0x00020002, 0x00020002, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x000f0000, 0x00110011, 0x00110011, 0x00110011, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00190000, 0x00210021, 0x00210021, 0x001e0021, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00010000, 0x00010001, 0x00010001, 0x00000000, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x000f0000, 0x00100010, 0x00100010, 0x00100010, 0x00100010, 0x00000010,
+    0x00000000, 0x00000000, 0x000f0000, 0x00100010, 0x00100010, 0x000f0010, 0x00000000, 0x00000000,
+    0x00000000, 0x00010000, 0x001f0001, 0x00180010, 0x00080008, 0x0004000c, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x001f0000, 0x00210021, 0x00210021, 0x003f0021, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x001d0000, 0x00220023, 0x00210022, 0x00390021, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00010000, 0x00010001, 0x00010001, 0x00010001, 0x00010001, 0x00000001,
+    0x00000000, 0x00000000, 0x00030000, 0x00040004, 0x00040004, 0x00070004, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x001f0000, 0x00210021, 0x00210021, 0x001e0021, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00210000, 0x00220021, 0x00220022, 0x000c0014, 0x00000003, 0x00000000,
+    0x00000000, 0x00000000, 0x000f0000, 0x00110011, 0x00100013, 0x00100010, 0x00100010, 0x00000010,
+    0x00000000, 0x00000000, 0x001f0000, 0x00210021, 0x00200023, 0x001f0020, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00110000, 0x00120012, 0x0004000c, 0x00040004, 0x00040004, 0x00000004,
+    0x00000000, 0x00000000, 0x00210000, 0x00240022, 0x00100018, 0x003f0010, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x003f0000, 0x00100020, 0x00110011, 0x00090009, 0x00010001, 0x00000001,
+    0x00000000, 0x00000000, 0x000f0000, 0x00100010, 0x00100010, 0x00100010, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00490000, 0x00490049, 0x00220027, 0x000e0012, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x001f0000, 0x00220022, 0x00220022, 0x00230022, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00020000, 0x00000001, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00120000, 0x00000009, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00020000, 0x00030002, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00020002, 0x00000003, 0x00000000, 0x00030000, 0x00000000, 0x00000000,
+    0x00000000, 0x000e0000, 0x00010011, 0x00060003, 0x00040004, 0x00040000, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x0002000c, 0x001e0002, 0x0000000e, 0x00000000, 0x00000000,
+    0x00060001, 0x00020000, 0x00020002, 0x00020002, 0x00020002, 0x00020002, 0x00000000, 0x00000000,
+    0x00020000, 0x00020006, 0x00020002, 0x00020002, 0x00020002, 0x00020002, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x0018000c, 0x00340018, 0x00380024, 0x00180030, 0x0000000f,
+    0x00000000, 0x00020000, 0x00020002, 0x00020002, 0x00020002, 0x00020002, 0x00020000, 0x00000006,
+    0x00000000, 0x00000000, 0x00000000, 0x00ec0006, 0x00700190, 0x00820082, 0x00180076, 0x00000000,
+    0x00000000, 0x00020000, 0x00020002, 0x00020002, 0x00020002, 0x00020002, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x02020202, 0x00fc0302, 0x00200000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x0000000a, 0x0012000e, 0x001e0012, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00100000, 0x02020202, 0x00fc0302, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00100020, 0x02020202, 0x00fc0302, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x007e0000, 0x00040018, 0x00020002, 0x00840002, 0x00000078,
+    0x00000000, 0x00000000, 0x00000000, 0x007e0000, 0x00040018, 0x00020002, 0x00840002, 0x00000078,
+    0x00000000, 0x00000000, 0x00080000, 0x007e0000, 0x00040018, 0x00020002, 0x00840002, 0x00000078,
+    0x00000000, 0x00000000, 0x00000000, 0x000c0000, 0x00100008, 0x000e0018, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00040000, 0x000c0000, 0x00100008, 0x000e0018, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00200000, 0x00200020, 0x00180030, 0x00000007,
+    0x00000000, 0x00000000, 0x00000000, 0x00000020, 0x00200000, 0x00200020, 0x00180030, 0x00000007,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x26402440, 0x1fc236c2, 0x00620042, 0x0000003c,
+    0x00000000, 0x00000000, 0x01000000, 0x00000280, 0x26402440, 0x1fc236c2, 0x00620042, 0x0000003c,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x23401e00, 0x0fc211c2, 0x00620042, 0x0000003c,
+    0x00000000, 0x00000000, 0x00000000, 0x00000080, 0x23401e00, 0x0fc211c2, 0x00620042, 0x0000003c,
+    0x00000000, 0x00080000, 0x00080008, 0x00080008, 0x022803c8, 0x01fe0318, 0x00000000, 0x00000000,
+    0x00000000, 0x00080000, 0x00080008, 0x00080048, 0x022803c8, 0x01fe0318, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x0002001c, 0x000c0032, 0x00020006, 0x00060002, 0x0000007c,
+    0x00000000, 0x00000000, 0x00000004, 0x0002001c, 0x000c0032, 0x00020006, 0x00060002, 0x0000007c,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x000f0000, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000200, 0x05000200, 0x07020500, 0x03fe0402, 0x00000030, 0x00000000,
+    0x00000000, 0x00000000, 0x00000060, 0x00a00060, 0x00e200a0, 0x00820082, 0x003e0042, 0x0000000c,
+    0x00000000, 0x01000000, 0x01200100, 0x01200110, 0x01000110, 0x00fe0082, 0x00000000, 0x00000000,
+    0x00000000, 0x00800000, 0x00800080, 0x00800080, 0x00800080, 0x004200c2, 0x001c0062, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x006c0038, 0x0012003e, 0x00020002, 0x00000002,
+    0x00000000, 0x00000000, 0x00000000, 0x00000008, 0x00820080, 0x00c20082, 0x003c0046, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x0012000e, 0x001e0012, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00340018, 0x00380024, 0x00180030, 0x0000000f,
+    0x00000000, 0x00000000, 0x00000000, 0x00e00000, 0x00700190, 0x00820082, 0x00180076, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00e00000, 0x00700190, 0x00820082, 0x00180076, 0x00000018,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000001, 0x00000000, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00010001, 0x00020003, 0x00020002, 0x00020002, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00110011, 0x0002000f, 0x00020002, 0x00020002, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00150015, 0x0003001f, 0x00020002, 0x00020002, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00060008, 0x00060002, 0x00010003, 0x000e0001, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x000e0004, 0x00090009, 0x00110011, 0x000f0009, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x001e0011, 0x00100010, 0x00100010, 0x00200030, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00320023, 0x00140012, 0x000c000c, 0x0008000c, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00040004, 0x00060006, 0x000b000a, 0x00110009, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x000d0006, 0x000f0009, 0x00080008, 0x00100018, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00090011, 0x00040008, 0x00020004, 0x00110001, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00080008, 0x00040004, 0x00000003, 0x00000000,
+    0x00000000, 0x00020000, 0x00010003, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00040004, 0x000e000f, 0x00000000, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x02020202, 0x00fc0302, 0x00000010, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x007e0000, 0x00040018, 0x00020032, 0x00840002, 0x00000078,
+    0x00000000, 0x00000000, 0x00200000, 0x00000030, 0x00200000, 0x00200020, 0x00180030, 0x00000007,
+    0x00000000, 0x06000000, 0x00400180, 0x00c00040, 0x01020180, 0x00fe0102, 0x00000010, 0x00000000,
+    0x04000000, 0x06c00300, 0x00400180, 0x00c00040, 0x01020180, 0x00fe0102, 0x00000010, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00e00000, 0x00700190, 0x00820082, 0x00180076, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000001, 0x00000000, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00010001, 0x00020003, 0x00020002, 0x00020002, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00110011, 0x0002000f, 0x00020002, 0x00020002, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00150015, 0x0003001f, 0x00020002, 0x00020002, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x0005000d, 0x0002001f, 0x00020002, 0x00020002, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x000e0004, 0x00190009, 0x00110011, 0x000f0015, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x0002000c, 0x000e0002, 0x0002000c, 0x00010002, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00320023, 0x00140012, 0x000c000c, 0x0008000c, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00040004, 0x00060006, 0x000b000a, 0x00110009, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x000d0006, 0x000f0009, 0x00080008, 0x00100018, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x02020202, 0x00fc0302, 0x00000010, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x02020202, 0x0cfc0702, 0x00000010, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00020002, 0x00030002, 0x00000004, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00020002, 0x000f0002, 0x00000004, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x007e0000, 0x00040018, 0x00020032, 0x00840002, 0x00000078,
+    0x00000000, 0x00000000, 0x00000000, 0x007e0000, 0x00240038, 0x00ca0042, 0x00840002, 0x00000078,
+    0x00000000, 0x00000000, 0x00000000, 0x000e0000, 0x00300070, 0x00070008, 0x00000014, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x000e0000, 0x00300070, 0x00c70028, 0x00000014, 0x00000000,
+    0x00000000, 0x00000000, 0x00200000, 0x00000030, 0x00200000, 0x00200020, 0x00180030, 0x00000007,
+    0x00000000, 0x00000000, 0x00200000, 0x00000030, 0x00200000, 0x00e00020, 0x00180030, 0x00000007,
+    0x00000000, 0x06000000, 0x00400180, 0x00c00040, 0x01020180, 0x00fe0102, 0x00000010, 0x00000000,
+    0x00000000, 0x06000000, 0x00400180, 0x00c00040, 0x01020180, 0x0cfe0302, 0x00000010, 0x00000000,
+    0x00000000, 0x00300000, 0x0002000c, 0x00060002, 0x0008000c, 0x00070008, 0x00000000, 0x00000000,
+    0x00000000, 0x00300000, 0x0002000c, 0x00060002, 0x0008000c, 0x00670018, 0x00000000, 0x00000000,
+    0x04000000, 0x06c00300, 0x00400180, 0x00c00040, 0x01020180, 0x00fe0102, 0x00000010, 0x00000000,
+    0x04000000, 0x06c00300, 0x00400180, 0x00c00040, 0x01020180, 0x0cfe0302, 0x00000010, 0x00000000,
+    0x00200000, 0x00360018, 0x0002000c, 0x00060002, 0x0008000c, 0x00070008, 0x00000000, 0x00000000,
+    0x00200000, 0x00360018, 0x0002000c, 0x00060002, 0x0008000c, 0x00670018, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00e00000, 0x00700190, 0x00820082, 0x00180076, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x01800000, 0x02820142, 0x003800ee, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00020002, 0x00030002, 0x00040000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00020002, 0x000f0002, 0x00040000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x0002000c, 0x001e0002, 0x0000000e, 0x00000000, 0x00000000,
+    0x00060001, 0x00020000, 0x00020002, 0x00020002, 0x00020002, 0x00020002, 0x00000000, 0x00000000,
+    0x00060001, 0x00020000, 0x00020002, 0x00020002, 0x00020002, 0x000c0002, 0x00000000, 0x00000000,
+    0x00060002, 0x00020000, 0x00020002, 0x00020002, 0x00020002, 0x00020002, 0x00000000, 0x00000000,
+    0x00060002, 0x00020000, 0x00020002, 0x00020002, 0x00020002, 0x000c0002, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x0018000c, 0x00340018, 0x00380024, 0x00180030, 0x0000000f,
+    0x00000000, 0x00000000, 0x00000000, 0x0018000c, 0x00340018, 0x00780024, 0x00180030, 0x0000000f,
+    0x00000000, 0x00020000, 0x00020002, 0x00020002, 0x00020002, 0x00020002, 0x00020000, 0x00000006,
+    0x00000000, 0x00020000, 0x00020002, 0x00020002, 0x00020002, 0x000c0002, 0x00020000, 0x00000006,
+    0x00000000, 0x00000000, 0x00000000, 0x00ec0006, 0x00700190, 0x00820082, 0x00180076, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x000c0000, 0x01800008, 0x02820142, 0x003800ee, 0x00000000,
+    0x00000000, 0x00000000, 0x00030000, 0x00000006, 0x00020002, 0x00030002, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00030000, 0x00000006, 0x00020002, 0x000f0002, 0x00000000, 0x00000000,
+    0x00000000, 0x00020000, 0x00020002, 0x00020002, 0x00020002, 0x00020002, 0x00000000, 0x00000000,
+    0x00000000, 0x00020000, 0x00020002, 0x00020002, 0x00020002, 0x000c0002, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x02020202, 0x00fc0302, 0x00200000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x02020202, 0x0cfc0702, 0x00200000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00020002, 0x00030002, 0x00020000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00020002, 0x000f0002, 0x00020000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x0000000a, 0x0012000e, 0x001e0012, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000004, 0x0012001c, 0x006c0012, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00100000, 0x02020202, 0x00fc0302, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00100000, 0x02020202, 0x0cfc0702, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000004, 0x00020002, 0x00030002, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000004, 0x00020002, 0x000f0002, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00100020, 0x02020202, 0x00fc0302, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00100020, 0x02020202, 0x0cfc0702, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00020000, 0x00000004, 0x00020002, 0x00030002, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00020000, 0x00000004, 0x00020002, 0x000f0002, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x007e0000, 0x00040018, 0x00020002, 0x00840002, 0x00000078,
+    0x00000000, 0x00000000, 0x00000000, 0x007e0000, 0x00240038, 0x00d20042, 0x00840002, 0x00000078,
+    0x00000000, 0x00000000, 0x00000000, 0x000e0000, 0x00300070, 0x00070008, 0x00080000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x000e0000, 0x00300070, 0x00c70028, 0x00080000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x007e0000, 0x00040018, 0x00020002, 0x00840002, 0x00000078,
+    0x00000000, 0x00000000, 0x00000000, 0x007e0000, 0x00240038, 0x00c20042, 0x00840002, 0x00000078,
+    0x00000000, 0x00000000, 0x00000000, 0x000e0000, 0x00300070, 0x00070008, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x000e0000, 0x00300070, 0x00c70028, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00080000, 0x007e0000, 0x00040018, 0x00020002, 0x00840002, 0x00000078,
+    0x00000000, 0x00000000, 0x00080000, 0x007e0000, 0x00240038, 0x00c20042, 0x00840002, 0x00000078,
+    0x00000000, 0x00000000, 0x00080000, 0x000e0000, 0x00300070, 0x00070008, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00080000, 0x000e0000, 0x00300070, 0x00c70028, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x000c0000, 0x00100008, 0x000e0018, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x000c0000, 0x00100008, 0x003e0018, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00040000, 0x000c0000, 0x00100008, 0x000e0018, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00040000, 0x000c0000, 0x00100008, 0x003e0018, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00200000, 0x00200020, 0x00180030, 0x00000007,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00200000, 0x00e00020, 0x00180030, 0x00000007,
+    0x00000000, 0x00000000, 0x00000000, 0x00000020, 0x00200000, 0x00200020, 0x00180030, 0x00000007,
+    0x00000000, 0x00000000, 0x00000000, 0x00000020, 0x00200000, 0x00e00020, 0x00180030, 0x00000007,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x26402440, 0x1fc236c2, 0x00620042, 0x0000003c,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x26402440, 0x7fc236c2, 0x00620042, 0x0000003c,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x01240124, 0x00df0126, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x01240124, 0x06df0126, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x01000000, 0x00000280, 0x26402440, 0x1fc236c2, 0x00620042, 0x0000003c,
+    0x00000000, 0x00000000, 0x01000000, 0x00000280, 0x26402440, 0x7fc236c2, 0x00620042, 0x0000003c,
+    0x00000000, 0x00000000, 0x00000000, 0x00000030, 0x01240124, 0x00df0126, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000030, 0x01240124, 0x06df0126, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x23401e00, 0x0fc211c2, 0x00620042, 0x0000003c,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x23401e00, 0x6fc211c2, 0x00620042, 0x0000003c,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x011401e0, 0x00fb018e, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x011401e0, 0x07fb018e, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000080, 0x23401e00, 0x0fc211c2, 0x00620042, 0x0000003c,
+    0x00000000, 0x00000000, 0x00000000, 0x00000080, 0x23401e00, 0x6fc211c2, 0x00620042, 0x0000003c,
+    0x00000000, 0x00000000, 0x00000000, 0x00000008, 0x011401e0, 0x00fb018e, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000008, 0x011401e0, 0x07fb018e, 0x00000000, 0x00000000,
+    0x00000000, 0x00080000, 0x00080008, 0x00080008, 0x022803c8, 0x01fe0318, 0x00000000, 0x00000000,
+    0x00000000, 0x00080000, 0x00080008, 0x00080008, 0x022803c8, 0x0ffe0318, 0x00000000, 0x00000000,
+    0x00000000, 0x00060000, 0x00060006, 0x00060006, 0x011e00f6, 0x007f018e, 0x00000000, 0x00000000,
+    0x00000000, 0x00060000, 0x00060006, 0x00060006, 0x011e00f6, 0x037f018e, 0x00000000, 0x00000000,
+    0x00000000, 0x00080000, 0x00080008, 0x00080048, 0x022803c8, 0x01fe0318, 0x00000000, 0x00000000,
+    0x00000000, 0x00080000, 0x00080008, 0x00080048, 0x022803c8, 0x0ffe0318, 0x00000000, 0x00000000,
+    0x00000000, 0x00060000, 0x00060006, 0x00060016, 0x011e00f6, 0x007f018e, 0x00000000, 0x00000000,
+    0x00000000, 0x00060000, 0x00060006, 0x00060016, 0x011e00f6, 0x037f018e, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x0002001c, 0x000c0032, 0x00020006, 0x00060002, 0x0000007c,
+    0x00000000, 0x00000000, 0x00000000, 0x000c0000, 0x001e001e, 0x0072000e, 0x00020002, 0x0000007c,
+    0x00000000, 0x00000000, 0x00000000, 0x0002001c, 0x00360002, 0x0003001c, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x000c0000, 0x000e001e, 0x0033000c, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000004, 0x0002001c, 0x000c0032, 0x00020006, 0x00060002, 0x0000007c,
+    0x00000000, 0x00000000, 0x00040000, 0x000c0000, 0x001e001e, 0x0072000e, 0x00020002, 0x0000007c,
+    0x00000000, 0x00000000, 0x00000004, 0x0002001c, 0x00360002, 0x0003001c, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00040000, 0x000c0000, 0x000e001e, 0x0033000c, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000200, 0x05000200, 0x07020500, 0x03fe0402, 0x00000030, 0x00000000,
+    0x00000000, 0x00000000, 0x02000000, 0x02000000, 0x05020700, 0x1ffe0502, 0x00000030, 0x00000000,
+    0x00000000, 0x00000000, 0x00000004, 0x001e000c, 0x001c0012, 0x000f0018, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x0012001e, 0x003f001c, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000060, 0x00a00060, 0x00e200a0, 0x00820082, 0x003e0042, 0x0000000c,
+    0x00000000, 0x00000000, 0x00000000, 0x000000a0, 0x00a000e0, 0x03c200a0, 0x00620082, 0x0000003c,
+    0x00000000, 0x00000000, 0x0000000c, 0x001e000c, 0x001c0012, 0x000f0018, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x000c0000, 0x00000000, 0x0012001e, 0x003f001c, 0x00000000, 0x00000000,
+    0x00000000, 0x01000000, 0x01200100, 0x01200110, 0x01000110, 0x00fe0082, 0x00000000, 0x00000000,
+    0x00000000, 0x01000000, 0x01200100, 0x01200110, 0x01000110, 0x03fe0182, 0x00000000, 0x00000000,
+    0x00000000, 0x00300000, 0x0002000c, 0x00060002, 0x0008000c, 0x00070008, 0x00000000, 0x00000000,
+    0x00000000, 0x00300000, 0x0002000c, 0x00060002, 0x0008000c, 0x00670018, 0x00000000, 0x00000000,
+    0x00000000, 0x00800000, 0x00800080, 0x00800080, 0x00800080, 0x004200c2, 0x001c0062, 0x00000000,
+    0x00000000, 0x00800000, 0x00800080, 0x00800080, 0x00800080, 0x01c200c2, 0x001c0066, 0x00000000,
+    0x00000000, 0x00060000, 0x00060006, 0x00060006, 0x00060006, 0x00030002, 0x00000000, 0x00000000,
+    0x00000000, 0x00060000, 0x00060006, 0x00060006, 0x00060006, 0x000f0006, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x006c0038, 0x0012003e, 0x00020002, 0x00000002,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00380010, 0x00fe006c, 0x00020002, 0x00000002,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x001c0008, 0x001f0012, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x001c0008, 0x007f0032, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000008, 0x00820080, 0x00c20082, 0x003c0046, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00800008, 0x01820082, 0x004600c2, 0x0000003c,
+    0x00000000, 0x00000000, 0x00000000, 0x00000002, 0x00020002, 0x00030002, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000002, 0x00020002, 0x000f0002, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x0012000e, 0x001e0012, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x0012001c, 0x006c0012, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x000c0002, 0x001a001a, 0x001f002e, 0x00000000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x0012000c, 0x003f000a, 0x0012000a, 0x0000000c,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00340018, 0x00380024, 0x00180030, 0x0000000f,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00340018, 0x00780024, 0x00180030, 0x0000000f,
+    0x00000000, 0x00000000, 0x00000000, 0x00e00000, 0x00700190, 0x00820082, 0x00180076, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x01800000, 0x02820142, 0x003800ee, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00e00000, 0x00700190, 0x00820082, 0x00180076, 0x00000018,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x01800000, 0x02820142, 0x003800ee, 0x00000018,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00020002, 0x00030002, 0x00040000, 0x00000000,
+    0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00020002, 0x000f0002, 0x00040000, 0x00000000,
+    0x00000000, 0x00400007, 0x00440044, 0x00480048, 0x00300030, 0x000c0030, 0x00000000, 0x00000000,
+    0x00000000, 0x00400007, 0x00440044, 0x00480048, 0x00700070, 0x00cc0070, 0x00000000, 0x00000000,
+    0x00010000, 0x00200003, 0x00220022, 0x00240024, 0x00180018, 0x00060018, 0x00000000, 0x00000000,
+    0x00010000, 0x00200003, 0x00220022, 0x00240024, 0x00380038, 0x00660038, 0x00000000, 0x00000000,
+    0x00000000, 0x00200000, 0x00220022, 0x00240024, 0x00180018, 0x00060018, 0x00030000, 0x00000002,
+    0x00000000, 0x00200000, 0x00220022, 0x00240024, 0x00380038, 0x00660038, 0x00030000, 0x00000002,
+    0x00000000, 0x00200000, 0x00220022, 0x00240024, 0x00180018, 0x00060018, 0x00000000, 0x00000000,
+    0x00000000, 0x00200000, 0x00220022, 0x00240024, 0x00380038, 0x00660038, 0x00000000, 0x00000000,
+];
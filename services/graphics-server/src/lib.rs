@@ -13,6 +13,8 @@ pub mod op;
 pub mod fontmap;
 pub use api::ArchivedBulkRead;
 pub use api::BulkRead;
+#[cfg(not(target_os = "xous"))]
+pub use api::SnapshotFormat;
 use api::Opcode; // if you prefer to map the api into your local namespace
 pub use fontmap::*;
 use num_traits::ToPrimitive;
@@ -250,6 +252,34 @@ impl Gfx {
                 .expect("couldn't pop");
         }
     }
+
+    /// Hosted mode only: writes the current framebuffer to `path` on the host.
+    #[cfg(not(target_os = "xous"))]
+    pub fn snapshot(&self, path: &str, format: SnapshotFormat) -> Result<(), xous::Error> {
+        let snap = api::Snapshot { path: xous_ipc::String::from_str(path), format, mismatches: None };
+        let mut buf = Buffer::into_buf(snap).or(Err(xous::Error::InternalError))?;
+        buf.lend_mut(self.conn, Opcode::Snapshot.to_u32().unwrap()).or(Err(xous::Error::InternalError))?;
+        match buf.to_original::<api::Snapshot, _>().unwrap().mismatches {
+            Some(_) => Ok(()),
+            None => Err(xous::Error::InternalError),
+        }
+    }
+
+    /// Hosted mode only: compares the current framebuffer against the golden PBM at `path` on the host,
+    /// and returns the number of pixels that differ. If `XOUS_UPDATE_GOLDEN` is set in the environment,
+    /// the golden image is overwritten with the current framebuffer instead.
+    #[cfg(not(target_os = "xous"))]
+    pub fn compare_snapshot(&self, path: &str) -> Result<u32, xous::Error> {
+        let snap = api::Snapshot {
+            path: xous_ipc::String::from_str(path),
+            format: SnapshotFormat::Pbm,
+            mismatches: None,
+        };
+        let mut buf = Buffer::into_buf(snap).or(Err(xous::Error::InternalError))?;
+        buf.lend_mut(self.conn, Opcode::CompareSnapshot.to_u32().unwrap())
+            .or(Err(xous::Error::InternalError))?;
+        buf.to_original::<api::Snapshot, _>().unwrap().mismatches.ok_or(xous::Error::InternalError)
+    }
 }
 
 use core::sync::atomic::{AtomicU32, Ordering};
@@ -14,7 +14,9 @@ mod sleep_note;
 
 use api::*;
 
+mod bidi;
 mod blitstr2;
+mod shaping;
 mod wordwrap;
 #[macro_use]
 mod style_macros;
@@ -39,6 +41,11 @@ use crate::wordwrap::*;
 #[cfg(feature = "gfx-testing")]
 mod testing;
 
+#[cfg(not(target_os = "xous"))]
+mod snapshot;
+#[cfg(not(target_os = "xous"))]
+mod uiscript;
+
 fn draw_boot_logo(display: &mut XousDisplay) { display.blit_screen(&poweron::LOGO_MAP); }
 
 #[cfg(any(feature = "precursor", feature = "renode"))]
@@ -219,12 +226,22 @@ fn wrapped_main(main_thread_token: backend::MainThreadToken) -> ! {
 
     #[cfg(feature = "gfx-testing")]
     testing::tests();
+
+    // counts drawing requests, so a UI script can wait for the screen to settle
+    #[cfg(not(target_os = "xous"))]
+    let activity = Arc::new(core::sync::atomic::AtomicU32::new(0));
+    #[cfg(not(target_os = "xous"))]
+    uiscript::start(sid, activity.clone(), display.as_slice().to_vec());
     loop {
         if !is_panic.load(Ordering::Relaxed) {
             // non-panic graphics operations if we are in a panic situation
             let mut msg = xous::receive_message(sid).unwrap();
             let op = FromPrimitive::from_usize(msg.body.id());
             log::trace!("{:?}", op);
+            #[cfg(not(target_os = "xous"))]
+            if !matches!(op, Some(Opcode::Snapshot) | Some(Opcode::CompareSnapshot)) {
+                activity.fetch_add(1, Ordering::SeqCst);
+            }
             match op {
                 #[cfg(not(feature = "cramium-soc"))]
                 Some(Opcode::SuspendResume) => xous::msg_scalar_unpack!(msg, token, _, _, _, {
@@ -338,6 +355,7 @@ fn wrapped_main(main_thread_token: backend::MainThreadToken) -> ! {
                         &typeset_extent,
                         &tv.style,
                         if let Some(i) = tv.insertion { Some(i as usize) } else { None },
+                        tv.direction,
                     );
                     let composition = typesetter.typeset(if tv.ellipsis {
                         OverflowStrategy::Ellipsis
@@ -346,7 +364,16 @@ fn wrapped_main(main_thread_token: backend::MainThreadToken) -> ! {
                     });
 
                     let composition_top_left = match tv.bounds_hint {
-                        TextBounds::BoundingBox(r) => r.tl().add(tv.margin),
+                        TextBounds::BoundingBox(r) => {
+                            if composition.is_rtl() {
+                                // right-to-left text is set against the right side of the box
+                                r.tl()
+                                    .add(tv.margin)
+                                    .add(Point::new(typeset_extent.x - composition.bb_width(), 0))
+                            } else {
+                                r.tl().add(tv.margin)
+                            }
+                        }
                         TextBounds::GrowableFromBr(br, _width) => Point::new(
                             br.x - (composition.bb_width() as i16 + tv.margin.x),
                             br.y - (composition.bb_height() as i16 + tv.margin.y),
@@ -746,6 +773,32 @@ fn wrapped_main(main_thread_token: backend::MainThreadToken) -> ! {
                         _ => (),
                     }
                 }
+                #[cfg(not(target_os = "xous"))]
+                Some(Opcode::Snapshot) => {
+                    let mut buffer =
+                        unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                    let mut snap = buffer.to_original::<Snapshot, _>().unwrap();
+                    snap.mismatches = match snapshot::save(
+                        display.as_slice(),
+                        snap.path.as_str().unwrap_or(""),
+                        snap.format,
+                    ) {
+                        Ok(_) => Some(0),
+                        Err(e) => {
+                            log::warn!("couldn't write snapshot to {}: {:?}", snap.path, e);
+                            None
+                        }
+                    };
+                    buffer.replace(snap).unwrap();
+                }
+                #[cfg(not(target_os = "xous"))]
+                Some(Opcode::CompareSnapshot) => {
+                    let mut buffer =
+                        unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                    let mut snap = buffer.to_original::<Snapshot, _>().unwrap();
+                    snap.mismatches = snapshot::compare(display.as_slice(), snap.path.as_str().unwrap_or(""));
+                    buffer.replace(snap).unwrap();
+                }
                 Some(Opcode::Quit) => break,
                 None => {
                     log::error!("received opcode scalar that is not handled");
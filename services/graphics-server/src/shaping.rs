@@ -0,0 +1,161 @@
+//! Arabic shaping: each letter is drawn in its isolated, initial, medial or final form, depending on
+//! the letters it joins to, using the forms in the Arabic Presentation Forms blocks. Lam followed by
+//! alef becomes a single ligature.
+//!
+//! Shaping works on the text in logical order and gives back one character for each one it was given,
+//! so positions in the text, like the insertion point, stay the same.
+
+use crate::bidi::{self, BidiClass};
+
+/// Stands in for the alef of a lam-alef ligature, which is drawn as part of the lam. It is not drawn.
+pub(crate) const LIGATURE_TAIL: char = '\u{200B}';
+
+const LAM: char = '\u{0644}';
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Joining {
+    None,
+    /// joins to the letter before it only
+    Right,
+    /// joins on both sides
+    Dual,
+    /// joins on both sides, but has no forms of its own, like the tatweel
+    Causing,
+    /// marks, which are skipped over
+    Transparent,
+}
+
+/// Letters that have presentation forms: the letter, its isolated form, and how it joins. The final
+/// form follows the isolated one, and dual-joining letters then have their initial and medial forms.
+const FORMS: &[(char, u32, Joining)] = &[
+    ('\u{0621}', 0xFE80, Joining::None),
+    ('\u{0622}', 0xFE81, Joining::Right),
+    ('\u{0623}', 0xFE83, Joining::Right),
+    ('\u{0624}', 0xFE85, Joining::Right),
+    ('\u{0625}', 0xFE87, Joining::Right),
+    ('\u{0626}', 0xFE89, Joining::Dual),
+    ('\u{0627}', 0xFE8D, Joining::Right),
+    ('\u{0628}', 0xFE8F, Joining::Dual),
+    ('\u{0629}', 0xFE93, Joining::Right),
+    ('\u{062A}', 0xFE95, Joining::Dual),
+    ('\u{062B}', 0xFE99, Joining::Dual),
+    ('\u{062C}', 0xFE9D, Joining::Dual),
+    ('\u{062D}', 0xFEA1, Joining::Dual),
+    ('\u{062E}', 0xFEA5, Joining::Dual),
+    ('\u{062F}', 0xFEA9, Joining::Right),
+    ('\u{0630}', 0xFEAB, Joining::Right),
+    ('\u{0631}', 0xFEAD, Joining::Right),
+    ('\u{0632}', 0xFEAF, Joining::Right),
+    ('\u{0633}', 0xFEB1, Joining::Dual),
+    ('\u{0634}', 0xFEB5, Joining::Dual),
+    ('\u{0635}', 0xFEB9, Joining::Dual),
+    ('\u{0636}', 0xFEBD, Joining::Dual),
+    ('\u{0637}', 0xFEC1, Joining::Dual),
+    ('\u{0638}', 0xFEC5, Joining::Dual),
+    ('\u{0639}', 0xFEC9, Joining::Dual),
+    ('\u{063A}', 0xFECD, Joining::Dual),
+    ('\u{0641}', 0xFED1, Joining::Dual),
+    ('\u{0642}', 0xFED5, Joining::Dual),
+    ('\u{0643}', 0xFED9, Joining::Dual),
+    ('\u{0644}', 0xFEDD, Joining::Dual),
+    ('\u{0645}', 0xFEE1, Joining::Dual),
+    ('\u{0646}', 0xFEE5, Joining::Dual),
+    ('\u{0647}', 0xFEE9, Joining::Dual),
+    ('\u{0648}', 0xFEED, Joining::Right),
+    // dual-joining, but Presentation Forms-B only has its isolated and final forms
+    ('\u{0649}', 0xFEEF, Joining::Right),
+    ('\u{064A}', 0xFEF1, Joining::Dual),
+    ('\u{067E}', 0xFB56, Joining::Dual),
+    ('\u{0686}', 0xFB7A, Joining::Dual),
+    ('\u{0698}', 0xFB8A, Joining::Right),
+    ('\u{06A9}', 0xFB8E, Joining::Dual),
+    ('\u{06AF}', 0xFB92, Joining::Dual),
+    ('\u{06CC}', 0xFBFC, Joining::Dual),
+];
+
+/// The alefs that make a ligature with lam, and the isolated form of the ligature; the final form
+/// follows it.
+const LAM_ALEF: &[(char, u32)] =
+    &[('\u{0622}', 0xFEF5), ('\u{0623}', 0xFEF7), ('\u{0625}', 0xFEF9), ('\u{0627}', 0xFEFB)];
+
+fn joining(ch: char) -> Joining {
+    match FORMS.binary_search_by_key(&ch, |&(c, _, _)| c) {
+        Ok(i) => FORMS[i].2,
+        Err(_) => match ch {
+            '\u{0640}' | '\u{200D}' => Joining::Causing,
+            _ if bidi::class(ch) == BidiClass::NSM => Joining::Transparent,
+            _ => Joining::None,
+        },
+    }
+}
+
+/// The characters to draw for `chars`, with the Arabic letters in their joined forms
+pub(crate) fn shape(chars: &[char]) -> Vec<char> {
+    let mut shaped = chars.to_vec();
+    for i in 0..chars.len() {
+        if shaped[i] == LIGATURE_TAIL && chars[i] != LIGATURE_TAIL {
+            // the alef was drawn with the lam before it
+            continue;
+        }
+        let (isolated, kind) = match FORMS.binary_search_by_key(&chars[i], |&(c, _, _)| c) {
+            Ok(f) => (FORMS[f].1, FORMS[f].2),
+            Err(_) => continue,
+        };
+        let prev = (0..i).rev().find(|&k| joining(chars[k]) != Joining::Transparent);
+        let next = (i + 1..chars.len()).find(|&k| joining(chars[k]) != Joining::Transparent);
+        let joins_prev = kind != Joining::None
+            && prev.map_or(false, |k| matches!(joining(chars[k]), Joining::Dual | Joining::Causing));
+        let joins_next = kind == Joining::Dual
+            && next.map_or(false, |k| {
+                matches!(joining(chars[k]), Joining::Right | Joining::Dual | Joining::Causing)
+            });
+        if chars[i] == LAM {
+            if let Some(k) = next {
+                if let Some(&(_, ligature)) = LAM_ALEF.iter().find(|&&(alef, _)| alef == chars[k]) {
+                    shaped[i] = char::from_u32(ligature + joins_prev as u32).unwrap_or(chars[i]);
+                    shaped[k] = LIGATURE_TAIL;
+                    continue;
+                }
+            }
+        }
+        let form = match (joins_prev, joins_next) {
+            (true, true) => 3,
+            (false, true) => 2,
+            (true, false) => 1,
+            (false, false) => 0,
+        };
+        shaped[i] = char::from_u32(isolated + form).unwrap_or(chars[i]);
+    }
+    shaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shaped(s: &str) -> Vec<u32> {
+        let chars: Vec<char> = s.chars().collect();
+        shape(&chars).iter().map(|&c| c as u32).collect()
+    }
+
+    #[test]
+    fn forms_are_sorted() {
+        assert!(FORMS.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+
+    #[test]
+    fn joined_forms() {
+        // beh beh beh: initial, medial, final
+        assert_eq!(shaped("ببب"), vec![0xFE91, 0xFE92, 0xFE90]);
+        // waw doesn't join the letter after it
+        assert_eq!(shaped("وب"), vec![0xFEED, 0xFE8F]);
+        // marks don't break the joining
+        assert_eq!(shaped("بَب"), vec![0xFE91, 0x064E, 0xFE90]);
+        // words are shaped on their own
+        assert_eq!(shaped("ب ب"), vec![0xFE8F, 0x20, 0xFE8F]);
+        // salaam: seen, lam-alef, meem
+        assert_eq!(shaped("سلام"), vec![0xFEB3, 0xFEFC, LIGATURE_TAIL as u32, 0xFEE1]);
+        assert_eq!(shaped("لا"), vec![0xFEFB, LIGATURE_TAIL as u32]);
+        assert_eq!(shaped("abc"), vec![0x61, 0x62, 0x63]);
+    }
+}
@@ -0,0 +1,269 @@
+//! Hosted-mode framebuffer snapshots, for screenshot regression tests.
+//!
+//! Snapshots are written as PBM (1-bit, trivially diffable) or PNG (for viewing in a browser or a CI
+//! artifact viewer). Golden images for comparison are PBM only, so no image decoder is needed here.
+
+use std::io::Write;
+
+use crate::api::SnapshotFormat;
+use crate::backend::{FB_LINES, FB_WIDTH_PIXELS, FB_WIDTH_WORDS};
+
+/// When set in the environment, `compare` (re)writes the golden image instead of checking it.
+pub const UPDATE_GOLDEN_ENV: &str = "XOUS_UPDATE_GOLDEN";
+
+/// Bytes per packed row of a 1-bit image
+const ROW_BYTES: usize = (FB_WIDTH_PIXELS + 7) / 8;
+
+/// True if the pixel is light. This matches `op::put_pixel`, which sets the bit for `PixelColor::Light`.
+fn is_light(fb: &[u32], x: usize, y: usize) -> bool { fb[y * FB_WIDTH_WORDS + x / 32] & (1 << (x % 32)) != 0 }
+
+/// Packs the framebuffer into MSB-first rows. `dark_is_one` selects the PBM convention (1 = black) over
+/// the PNG grayscale one (1 = white).
+fn pack_rows(fb: &[u32], dark_is_one: bool) -> Vec<u8> {
+    let mut rows = vec![0u8; ROW_BYTES * FB_LINES];
+    for y in 0..FB_LINES {
+        for x in 0..FB_WIDTH_PIXELS {
+            if is_light(fb, x, y) != dark_is_one {
+                rows[y * ROW_BYTES + x / 8] |= 0x80 >> (x % 8);
+            }
+        }
+    }
+    rows
+}
+
+pub fn to_pbm(fb: &[u32]) -> Vec<u8> {
+    let mut pbm = format!("P4\n{} {}\n", FB_WIDTH_PIXELS, FB_LINES).into_bytes();
+    pbm.extend_from_slice(&pack_rows(fb, true));
+    pbm
+}
+
+pub fn to_png(fb: &[u32]) -> Vec<u8> {
+    let rows = pack_rows(fb, false);
+    // each scanline is prefixed with a filter type byte (0 = none)
+    let mut raw = Vec::with_capacity((ROW_BYTES + 1) * FB_LINES);
+    for row in rows.chunks(ROW_BYTES) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+
+    let mut png = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+    let mut ihdr = Vec::new();
+    ihdr.extend_from_slice(&(FB_WIDTH_PIXELS as u32).to_be_bytes());
+    ihdr.extend_from_slice(&(FB_LINES as u32).to_be_bytes());
+    // bit depth 1, grayscale, deflate, adaptive filtering, no interlace
+    ihdr.extend_from_slice(&[1, 0, 0, 0, 0]);
+    png_chunk(&mut png, b"IHDR", &ihdr);
+    png_chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+    png_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+/// Counts the pixels that differ between the framebuffer and a golden PBM. Returns `None` if the golden
+/// image can't be parsed or is not the size of the screen.
+pub fn compare_pbm(fb: &[u32], golden: &[u8]) -> Option<u32> {
+    let bits = parse_pbm(golden)?;
+    let current = pack_rows(fb, true);
+    let mut mismatches = 0;
+    for y in 0..FB_LINES {
+        let row = y * ROW_BYTES;
+        for x in 0..FB_WIDTH_PIXELS {
+            let mask = 0x80 >> (x % 8);
+            if (bits[row + x / 8] & mask) != (current[row + x / 8] & mask) {
+                mismatches += 1;
+            }
+        }
+    }
+    Some(mismatches)
+}
+
+/// Writes the framebuffer to `path` in the requested format.
+pub fn save(fb: &[u32], path: &str, format: SnapshotFormat) -> std::io::Result<()> {
+    let data = match format {
+        SnapshotFormat::Pbm => to_pbm(fb),
+        SnapshotFormat::Png => to_png(fb),
+    };
+    let mut file = std::fs::File::create(path)?;
+    file.write_all(&data)
+}
+
+/// Compares the framebuffer against the golden PBM at `path`, returning the number of differing pixels.
+pub fn compare(fb: &[u32], path: &str) -> Option<u32> {
+    if std::env::var_os(UPDATE_GOLDEN_ENV).is_some() {
+        log::info!("updating golden image {}", path);
+        return save(fb, path, SnapshotFormat::Pbm).ok().map(|_| 0);
+    }
+    match std::fs::read(path) {
+        Ok(golden) => compare_pbm(fb, &golden),
+        Err(e) => {
+            log::warn!("couldn't read golden image {}: {:?}", path, e);
+            None
+        }
+    }
+}
+
+/// Returns the packed rows of a binary (P4) PBM that is exactly the size of the screen.
+fn parse_pbm(data: &[u8]) -> Option<&[u8]> {
+    let mut pos = 0;
+    let mut fields = [0usize; 2];
+    if data.get(0..2)? != b"P4" {
+        return None;
+    }
+    pos += 2;
+    for field in fields.iter_mut() {
+        // skip whitespace and comments
+        loop {
+            match data.get(pos)? {
+                b'#' => {
+                    while *data.get(pos)? != b'\n' {
+                        pos += 1;
+                    }
+                }
+                c if c.is_ascii_whitespace() => pos += 1,
+                _ => break,
+            }
+        }
+        let start = pos;
+        while data.get(pos)?.is_ascii_digit() {
+            pos += 1;
+        }
+        *field = std::str::from_utf8(&data[start..pos]).ok()?.parse().ok()?;
+    }
+    // exactly one whitespace character separates the header from the raster
+    pos += 1;
+    if fields != [FB_WIDTH_PIXELS, FB_LINES] {
+        return None;
+    }
+    data.get(pos..pos + ROW_BYTES * FB_LINES)
+}
+
+fn png_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Wraps `data` in a zlib stream of uncompressed deflate blocks. The screen is only ~23kiB at 1bpp, so
+/// it's not worth carrying a compressor around for this.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let mut blocks = data.chunks(0xFFFF).peekable();
+    while let Some(block) = blocks.next() {
+        out.push(if blocks.peek().is_none() { 1 } else { 0 });
+        let len = block.len() as u16;
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryInto;
+
+    use super::*;
+    use crate::backend::FB_SIZE;
+
+    fn checkerboard() -> Vec<u32> {
+        let mut fb = vec![0u32; FB_SIZE];
+        for y in 0..FB_LINES {
+            for x in 0..FB_WIDTH_PIXELS {
+                if (x / 8 + y / 8) % 2 == 0 {
+                    fb[y * FB_WIDTH_WORDS + x / 32] |= 1 << (x % 32);
+                }
+            }
+        }
+        fb
+    }
+
+    #[test]
+    fn pbm_round_trip() {
+        let fb = checkerboard();
+        assert_eq!(compare_pbm(&fb, &to_pbm(&fb)), Some(0));
+    }
+
+    #[test]
+    fn pbm_counts_mismatches() {
+        let fb = checkerboard();
+        let golden = to_pbm(&fb);
+        let mut changed = fb.clone();
+        changed[0] ^= 0b101;
+        changed[FB_SIZE - FB_WIDTH_WORDS] ^= 1;
+        assert_eq!(compare_pbm(&changed, &golden), Some(3));
+    }
+
+    #[test]
+    fn pbm_ignores_dirty_bits() {
+        // the high bits of the last word in a line are not pixels (the hardware uses them as dirty flags)
+        let fb = checkerboard();
+        let golden = to_pbm(&fb);
+        let mut dirty = fb.clone();
+        dirty[FB_WIDTH_WORDS - 1] |= 0x1_0000;
+        assert_eq!(compare_pbm(&dirty, &golden), Some(0));
+    }
+
+    #[test]
+    fn pbm_header_with_comment() {
+        let fb = checkerboard();
+        let raster = &to_pbm(&fb)[format!("P4\n{} {}\n", FB_WIDTH_PIXELS, FB_LINES).len()..];
+        let mut golden = format!("P4\n# golden\n{} {}\n", FB_WIDTH_PIXELS, FB_LINES).into_bytes();
+        golden.extend_from_slice(raster);
+        assert_eq!(compare_pbm(&fb, &golden), Some(0));
+    }
+
+    #[test]
+    fn pbm_rejects_wrong_size() {
+        let fb = checkerboard();
+        assert_eq!(compare_pbm(&fb, b"P4\n8 8\n\0\0\0\0\0\0\0\0"), None);
+        assert_eq!(compare_pbm(&fb, b"P1\n"), None);
+    }
+
+    #[test]
+    fn png_structure() {
+        let png = to_png(&checkerboard());
+        assert_eq!(&png[..8], &[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n']);
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(u32::from_be_bytes(png[16..20].try_into().unwrap()), FB_WIDTH_PIXELS as u32);
+        assert_eq!(u32::from_be_bytes(png[20..24].try_into().unwrap()), FB_LINES as u32);
+        // IHDR crc covers the type and data
+        assert_eq!(u32::from_be_bytes(png[29..33].try_into().unwrap()), crc32(&png[12..29]));
+        assert_eq!(&png[png.len() - 8..png.len() - 4], b"IEND");
+    }
+
+    #[test]
+    fn checksums() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+    }
+
+    #[test]
+    fn boot_golden_is_the_logo() {
+        // hosted-ci checks the boot screen against this file; keep it in step with the power-on logo
+        let golden = include_bytes!("../ui-tests/golden/boot.pbm");
+        assert_eq!(compare_pbm(&crate::poweron::LOGO_MAP, golden), Some(0));
+    }
+}
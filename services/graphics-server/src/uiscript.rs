@@ -0,0 +1,319 @@
+//! Hosted-mode UI automation.
+//!
+//! If `XOUS_UI_SCRIPT` names a file, a thread plays it back once the graphics server is up: keys are
+//! injected into the keyboard server exactly as if they had been typed into the minifb window, and the
+//! framebuffer can be captured or checked against golden images between steps. Combined with the
+//! `headless` feature this allows end-to-end UI tests without a human at the keyboard.
+//!
+//! The script is plain text, one command per line; `#` starts a comment.
+//!
+//! ```text
+//! settle 1000 60000        # wait until nothing is drawn for 1s (give up after 60s)
+//! key menu                 # named key: menu, enter, backspace, up, down, left, right, f1-f4, ...
+//! key down 3               # ...optionally repeated
+//! type hello world         # the rest of the line is typed verbatim
+//! wait 500                 # fixed delay in ms
+//! snapshot shot.png        # save the screen; .png, otherwise PBM
+//! expect golden/menu.pbm 4 # compare against a golden PBM, allowing up to 4 differing pixels
+//! expect-boot golden/boot.pbm # compare the screen as it was when the graphics server started
+//! quit                     # end the hosted run
+//! ```
+//!
+//! Relative paths are resolved against the directory of the script, and directories for snapshots are
+//! created as needed. When the script ends, the number of failed checks (`expect`, `expect-boot` and
+//! `snapshot`) is written to the file named by `XOUS_UI_SCRIPT_RESULT`, if set, and `quit` shuts the whole
+//! hosted run down. This is how `xtask hosted-ci` tells whether a script passed; the scripts it runs live
+//! in `ui-tests/`, with `menu.uiscript` as a worked example.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use num_traits::ToPrimitive;
+use xous_ipc::Buffer;
+
+use crate::api::{Opcode, Snapshot, SnapshotFormat};
+
+pub const UI_SCRIPT_ENV: &str = "XOUS_UI_SCRIPT";
+pub const UI_SCRIPT_RESULT_ENV: &str = "XOUS_UI_SCRIPT_RESULT";
+
+/// Gap between injected keys, so the UI sees them as separate keystrokes and queues don't overflow
+const KEY_INTERVAL_MS: usize = 30;
+/// How often the draw activity counter is sampled while settling
+const SETTLE_POLL_MS: usize = 50;
+const DEFAULT_SETTLE_QUIET_MS: usize = 500;
+const DEFAULT_SETTLE_TIMEOUT_MS: usize = 10_000;
+
+#[derive(Debug, PartialEq, Eq)]
+enum Step {
+    Wait(usize),
+    Settle { quiet_ms: usize, timeout_ms: usize },
+    Type(String),
+    Key { key: char, count: usize },
+    Snapshot(PathBuf),
+    Expect { golden: PathBuf, tolerance: u32 },
+    ExpectBoot { golden: PathBuf, tolerance: u32 },
+    Quit,
+}
+
+/// Maps the names used in scripts onto the characters the keyboard server emits for them.
+fn key_by_name(name: &str) -> Option<char> {
+    Some(match name.to_ascii_lowercase().as_str() {
+        "menu" | "home" => '∴',
+        "enter" | "return" => 0xd_u8.into(),
+        "backspace" => 0x8_u8.into(),
+        "space" => ' ',
+        "up" => '↑',
+        "down" => '↓',
+        "left" => '←',
+        "right" => '→',
+        "f1" => 0x11_u8.into(),
+        "f2" => 0x12_u8.into(),
+        "f3" => 0x13_u8.into(),
+        "f4" => 0x14_u8.into(),
+        "lock" => keyboard::LOCK_SCREEN_KEY,
+        "switch" => keyboard::SWITCH_PANE_KEY,
+        _ => {
+            // a single character stands for itself
+            let mut chars = name.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => c,
+                _ => return None,
+            }
+        }
+    })
+}
+
+fn parse(script: &str, base: &Path) -> Result<Vec<Step>, String> {
+    let mut steps = Vec::new();
+    for (lineno, line) in script.lines().enumerate() {
+        let err = |what: &str| format!("line {}: {}: {}", lineno + 1, what, line);
+        // `type` takes the rest of the line verbatim, so comments are only stripped from other commands
+        let trimmed = line.trim_start();
+        if let Some(text) = trimmed.strip_prefix("type ") {
+            steps.push(Step::Type(text.to_string()));
+            continue;
+        }
+        let code = match trimmed.find('#') {
+            Some(i) => &trimmed[..i],
+            None => trimmed,
+        };
+        let mut args = code.split_whitespace();
+        let cmd = match args.next() {
+            Some(cmd) => cmd,
+            None => continue,
+        };
+        let args: Vec<&str> = args.collect();
+        let number = |i: usize, default: Option<usize>| -> Result<usize, String> {
+            match args.get(i) {
+                Some(a) => a.parse().map_err(|_| err("expected a number")),
+                None => default.ok_or_else(|| err("missing argument")),
+            }
+        };
+        let path = |i: usize| -> Result<PathBuf, String> {
+            args.get(i).map(|p| base.join(p)).ok_or_else(|| err("missing path"))
+        };
+        steps.push(match cmd {
+            "wait" => Step::Wait(number(0, None)?),
+            "settle" => Step::Settle {
+                quiet_ms: number(0, Some(DEFAULT_SETTLE_QUIET_MS))?,
+                timeout_ms: number(1, Some(DEFAULT_SETTLE_TIMEOUT_MS))?,
+            },
+            "key" => Step::Key {
+                key: key_by_name(args.get(0).ok_or_else(|| err("missing key name"))?)
+                    .ok_or_else(|| err("unknown key"))?,
+                count: number(1, Some(1))?,
+            },
+            "snapshot" => Step::Snapshot(path(0)?),
+            "expect" => Step::Expect { golden: path(0)?, tolerance: number(1, Some(0))? as u32 },
+            "expect-boot" => Step::ExpectBoot { golden: path(0)?, tolerance: number(1, Some(0))? as u32 },
+            "quit" => Step::Quit,
+            _ => return Err(err("unknown command")),
+        });
+    }
+    Ok(steps)
+}
+
+/// Starts the script runner if `XOUS_UI_SCRIPT` is set. `activity` is bumped by the main loop for every
+/// drawing request, which is how the runner tells that the screen has settled. `boot_screen` is a copy of
+/// the framebuffer taken before any client was served, which only ever shows the power-on logo and so is
+/// the one screen that can be checked without depending on timing.
+pub fn start(sid: xous::SID, activity: Arc<AtomicU32>, boot_screen: Vec<u32>) {
+    let script_path = match std::env::var(UI_SCRIPT_ENV) {
+        Ok(p) => PathBuf::from(p),
+        Err(_) => return,
+    };
+    let base = script_path.parent().map(|p| p.to_path_buf()).unwrap_or_default();
+    let steps = match std::fs::read_to_string(&script_path)
+        .map_err(|e| format!("{:?}", e))
+        .and_then(|s| parse(&s, &base))
+    {
+        Ok(steps) => steps,
+        Err(e) => {
+            log::error!("couldn't load UI script {}: {}", script_path.display(), e);
+            finish(1, true);
+            return;
+        }
+    };
+    log::info!("running UI script {} ({} steps)", script_path.display(), steps.len());
+    std::thread::spawn(move || {
+        let xns = xous_names::XousNames::new().unwrap();
+        let kbd = keyboard::Keyboard::new(&xns).expect("UI script can't connect to KBD");
+        let tt = ticktimer_server::Ticktimer::new().unwrap();
+        let gfx_conn = xous::connect(sid).expect("UI script can't connect to the graphics server");
+        let mut failures = 0;
+        for step in steps {
+            log::debug!("UI script: {:?}", step);
+            match step {
+                Step::Wait(ms) => tt.sleep_ms(ms).unwrap(),
+                Step::Settle { quiet_ms, timeout_ms } => {
+                    let start = tt.elapsed_ms();
+                    let mut last_count = activity.load(Ordering::SeqCst);
+                    let mut last_change = start;
+                    loop {
+                        tt.sleep_ms(SETTLE_POLL_MS).unwrap();
+                        let now = tt.elapsed_ms();
+                        let count = activity.load(Ordering::SeqCst);
+                        if count != last_count {
+                            last_count = count;
+                            last_change = now;
+                        } else if now - last_change >= quiet_ms as u64 {
+                            break;
+                        }
+                        if now - start >= timeout_ms as u64 {
+                            log::warn!("UI script: screen didn't settle within {}ms", timeout_ms);
+                            break;
+                        }
+                    }
+                }
+                Step::Type(text) => {
+                    for c in text.chars() {
+                        kbd.hostmode_inject_key(c);
+                        tt.sleep_ms(KEY_INTERVAL_MS).unwrap();
+                    }
+                }
+                Step::Key { key, count } => {
+                    for _ in 0..count {
+                        kbd.hostmode_inject_key(key);
+                        tt.sleep_ms(KEY_INTERVAL_MS).unwrap();
+                    }
+                }
+                Step::Snapshot(path) => {
+                    let format = match path.extension() {
+                        Some(ext) if ext.eq_ignore_ascii_case("png") => SnapshotFormat::Png,
+                        _ => SnapshotFormat::Pbm,
+                    };
+                    if let Some(dir) = path.parent() {
+                        std::fs::create_dir_all(dir).ok(); // saving will report the error, if any
+                    }
+                    if snapshot(gfx_conn, Opcode::Snapshot, &path, format).is_none() {
+                        log::error!("UI script: couldn't save snapshot {}", path.display());
+                        failures += 1;
+                    }
+                }
+                Step::Expect { golden, tolerance } => {
+                    let mismatches =
+                        snapshot(gfx_conn, Opcode::CompareSnapshot, &golden, SnapshotFormat::Pbm);
+                    if !check(&golden, mismatches, tolerance) {
+                        failures += 1;
+                    }
+                }
+                Step::ExpectBoot { golden, tolerance } => {
+                    let mismatches = crate::snapshot::compare(&boot_screen, golden.to_str().unwrap_or(""));
+                    if !check(&golden, mismatches, tolerance) {
+                        failures += 1;
+                    }
+                }
+                Step::Quit => {
+                    finish(failures, true);
+                    return;
+                }
+            }
+        }
+        finish(failures, false);
+    });
+}
+
+/// Logs the outcome of a comparison; returns true if it passed.
+fn check(golden: &Path, mismatches: Option<u32>, tolerance: u32) -> bool {
+    match mismatches {
+        Some(mismatches) if mismatches <= tolerance => {
+            log::info!("UI script: {} matches", golden.display());
+            true
+        }
+        Some(mismatches) => {
+            log::error!("UI script: {} differs in {} pixels", golden.display(), mismatches);
+            false
+        }
+        None => {
+            log::error!("UI script: couldn't compare against {}", golden.display());
+            false
+        }
+    }
+}
+
+/// Reports the number of failures to `XOUS_UI_SCRIPT_RESULT` and, if `shutdown` is set, ends the hosted
+/// run. Exiting just this process would leave the rest of the system running.
+fn finish(failures: u32, shutdown: bool) {
+    log::info!("UI script finished, {} failed", failures);
+    if let Some(path) = std::env::var_os(UI_SCRIPT_RESULT_ENV) {
+        if let Err(e) = std::fs::write(&path, format!("{}\n", failures)) {
+            log::error!("couldn't write UI script result to {:?}: {:?}", path, e);
+        }
+    }
+    if shutdown {
+        xous::rsyscall(xous::SysCall::Shutdown).ok();
+    }
+}
+
+/// Round-trips a snapshot request through the main loop, so it is ordered with respect to drawing.
+fn snapshot(conn: xous::CID, op: Opcode, path: &Path, format: SnapshotFormat) -> Option<u32> {
+    let snap =
+        Snapshot { path: xous_ipc::String::from_str(path.to_str().unwrap_or("")), format, mismatches: None };
+    let mut buf = Buffer::into_buf(snap).ok()?;
+    buf.lend_mut(conn, op.to_u32().unwrap()).ok()?;
+    buf.to_original::<Snapshot, _>().ok()?.mismatches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_commands() {
+        let script = "# boot\nsettle\nsettle 1000 60000\nkey menu\nkey down 3 # scroll\n\
+                      type hello # world\nwait 20\nsnapshot out.png\nexpect golden/menu.pbm 4\n\
+                      expect-boot golden/boot.pbm\nquit\n";
+        let steps = parse(script, Path::new("/tests")).unwrap();
+        assert_eq!(
+            steps,
+            vec![
+                Step::Settle { quiet_ms: DEFAULT_SETTLE_QUIET_MS, timeout_ms: DEFAULT_SETTLE_TIMEOUT_MS },
+                Step::Settle { quiet_ms: 1000, timeout_ms: 60000 },
+                Step::Key { key: '∴', count: 1 },
+                Step::Key { key: '↓', count: 3 },
+                Step::Type("hello # world".to_string()),
+                Step::Wait(20),
+                Step::Snapshot(PathBuf::from("/tests/out.png")),
+                Step::Expect { golden: PathBuf::from("/tests/golden/menu.pbm"), tolerance: 4 },
+                Step::ExpectBoot { golden: PathBuf::from("/tests/golden/boot.pbm"), tolerance: 0 },
+                Step::Quit,
+            ]
+        );
+    }
+
+    #[test]
+    fn single_characters_are_keys() {
+        assert_eq!(key_by_name("q"), Some('q'));
+        assert_eq!(key_by_name("Enter"), Some('\r'));
+        assert_eq!(key_by_name("nope"), None);
+    }
+
+    #[test]
+    fn reports_bad_lines() {
+        assert!(parse("wait\n", Path::new("")).unwrap_err().starts_with("line 1"));
+        assert!(parse("key\n", Path::new("")).is_err());
+        assert!(parse("key nope\n", Path::new("")).is_err());
+        assert!(parse("\n\nfrobnicate\n", Path::new("")).unwrap_err().starts_with("line 3"));
+    }
+}
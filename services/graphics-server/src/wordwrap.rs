@@ -27,8 +27,16 @@ use crate::backend::{FB_LINES, FB_SIZE, FB_WIDTH_PIXELS};
 ///
 /// If the overall string cannot fit within the absolute bounds defined by the `max` area and/or the
 /// `bounds`, the rendering is halted, and ellipses are inserted at the end.
+///
+/// Text that contains right-to-left characters is laid out with the Unicode Bidirectional Algorithm.
+/// Words are wrapped in logical order as above; each line is then reordered for display, and lines of
+/// a right-to-left paragraph are set flush with the right edge of the composition. Arabic is shaped
+/// into its joined forms before any of this, one glyph per character, so string positions such as the
+/// insertion point are unaffected.
+use crate::api::TextDirection;
 use crate::blitstr2::{self, *};
 use crate::style_macros::*;
+use crate::{bidi, shaping};
 
 impl TypesetWord {
     pub fn new(origin: Pt, strpos: usize) -> Self {
@@ -97,10 +105,11 @@ pub(crate) struct ComposedType {
     bounding_box: ClipRect,
     cursor: Cursor,
     overflow: bool,
+    rtl: bool,
 }
 impl ComposedType {
-    pub fn new(words: Vec<TypesetWord>, bounds: ClipRect, cursor: Cursor, overflow: bool) -> Self {
-        ComposedType { words, bounding_box: bounds, cursor, overflow }
+    pub fn new(words: Vec<TypesetWord>, bounds: ClipRect, cursor: Cursor, overflow: bool, rtl: bool) -> Self {
+        ComposedType { words, bounding_box: bounds, cursor, overflow, rtl }
     }
 
     pub fn bb_width(&self) -> i16 { self.bounding_box.max.x - self.bounding_box.min.x }
@@ -189,6 +198,10 @@ impl ComposedType {
     pub fn final_cursor(&self) -> Cursor { self.cursor }
 
     pub fn final_overflow(&self) -> bool { self.overflow }
+
+    /// True if the text starts with a right-to-left paragraph, in which case it should be set against
+    /// the right edge of the space it goes in
+    pub fn is_rtl(&self) -> bool { self.rtl }
 }
 /// Typesetter takes a string and attempts to lay it out within a region defined by
 /// a single point known as the "Extent". This is the maximum extent allowable for
@@ -221,14 +234,23 @@ pub(crate) struct Typesetter {
     ellipsis: GlyphSprite,
     large_space: GlyphSprite,
     insertion_point: Option<usize>,
-    s: String,
+    /// the characters to set, after shaping and mirroring
+    chars: Vec<char>,
+    /// resolved bidi levels, if the text has any right-to-left characters
+    bidi: Option<bidi::Levels>,
     base_style: GlyphStyle,
     overflow: bool,
     max_width: i16,
     last_line_height: usize, // scorecarding for the very last line on the loop exit
 }
 impl Typesetter {
-    pub fn setup(s: &str, extent: &Pt, base_style: &GlyphStyle, insertion_point: Option<usize>) -> Self {
+    pub fn setup(
+        s: &str,
+        extent: &Pt,
+        base_style: &GlyphStyle,
+        insertion_point: Option<usize>,
+        direction: TextDirection,
+    ) -> Self {
         let bb = ClipRect::new(0, 0, extent.x, extent.y);
         let mut space = style_glyph(' ', base_style);
         space.kern = 0;
@@ -246,6 +268,26 @@ impl Typesetter {
             large_space.wide = glyph_to_height_hint(GlyphStyle::Cjk) as u8;
         }
 
+        let mut chars: Vec<char> = s.chars().collect();
+        let level = match direction {
+            TextDirection::Auto => None,
+            TextDirection::LeftToRight => Some(0),
+            TextDirection::RightToLeft => Some(1),
+        };
+        // left-to-right text is set exactly as it was before bidi support, without the cost of resolving it
+        let bidi = if level == Some(1) || chars.iter().any(|&c| bidi::is_rtl(c)) {
+            let levels = bidi::Levels::new(&chars, level);
+            chars = shaping::shape(&chars);
+            for (ch, level) in chars.iter_mut().zip(levels.levels.iter()) {
+                if level & 1 == 1 {
+                    *ch = bidi::mirror(*ch);
+                }
+            }
+            Some(levels)
+        } else {
+            None
+        };
+
         Typesetter {
             charpos: 0,
             cursor: Cursor::new(0, 0, 0),
@@ -256,7 +298,8 @@ impl Typesetter {
             ellipsis,
             large_space,
             base_style: base_style.clone(),
-            s: String::from(s),
+            chars,
+            bidi,
             insertion_point,
             overflow: false,
             max_width: 0,
@@ -289,6 +332,7 @@ impl Typesetter {
                 ClipRect::new(self.bb.min.x, self.bb.min.y, self.bb.min.x, self.bb.min.y),
                 self.cursor,
                 true,
+                false,
             );
         }
         // algorithm:
@@ -305,10 +349,10 @@ impl Typesetter {
         // - If a newline:
         //   1. put the `candidate` into the `words` field
         //   2. Test to see if we can add a newline. If we can't, overflow.
-        let working_string = self.s.to_string(); // allocate a full copy to avoid interior mutability issues in the loop below. :-/ ugh.
+        let working_string = self.chars.clone(); // allocate a full copy to avoid interior mutability issues in the loop below. :-/ ugh.
         // there's probably a more space-efficient way to deal with this using interior mutability but fuck
         // it, I need to get this code working.
-        for ch in working_string.chars().skip(self.charpos) {
+        for &ch in working_string.iter().skip(self.charpos) {
             // .skip() allows us to resume typesetting where we last left off
             if ch == '\n' {
                 // handle the explicit newline case
@@ -381,8 +425,14 @@ impl Typesetter {
                 //    line for it.
                 // 5. The evolving word fits a line but doesn't fit this line, and there is no more space at
                 //    all.
-                let mut gs =
-                    if ch != '\t' { style_glyph(ch, &self.base_style) } else { self.large_space.clone() };
+                let mut gs = if ch == '\t' {
+                    self.large_space.clone()
+                } else if bidi::is_invisible(ch) {
+                    // formatting characters and marks take no space, but keep their place in the string
+                    GlyphSprite { wide: 0, kern: 0, ch, ..self.space }
+                } else {
+                    style_glyph(ch, &self.base_style)
+                };
                 if self.is_insert_point() {
                     gs.insert = true;
                 }
@@ -465,6 +515,13 @@ impl Typesetter {
         if self.candidate.gs.len() > 0 {
             self.commit_candidate_word(&mut composition);
         }
+        let rtl = match &self.bidi {
+            Some(levels) => {
+                composition = self.reorder(composition, levels);
+                levels.paragraph.first().map_or(false, |level| level & 1 == 1)
+            }
+            None => false,
+        };
         let ret = ComposedType::new(
             composition,
             ClipRect::new(
@@ -475,6 +532,7 @@ impl Typesetter {
             ),
             self.cursor,
             self.overflow,
+            rtl,
         );
         // cleanup any state based on the overflow strategy
         match strat {
@@ -603,6 +661,53 @@ impl Typesetter {
         }
     }
 
+    /// Puts each line of a composition in display order. The words of a line are merged into one, because
+    /// with mixed directions, a word can end up in pieces on either side of another.
+    fn reorder(&self, words: Vec<TypesetWord>, levels: &bidi::Levels) -> Vec<TypesetWord> {
+        let mut lines = Vec::<TypesetWord>::new();
+        let mut words = words.into_iter().peekable();
+        while let Some(first) = words.next() {
+            let origin = first.origin;
+            // (position in the string, glyph) in logical order
+            let mut glyphs = Vec::<(usize, GlyphSprite)>::new();
+            let mut line = Some(first);
+            while let Some(word) = line {
+                for (i, gs) in word.gs.iter().enumerate() {
+                    let mut gs = *gs;
+                    if word.non_drawable {
+                        gs.wide = 0;
+                        gs.kern = 0;
+                    }
+                    glyphs.push((word.strpos + i, gs));
+                }
+                line =
+                    if words.peek().map_or(false, |w| w.origin.y == origin.y) { words.next() } else { None };
+            }
+            // anything at or past `charpos` was not set from the string, i.e. it is the ellipsis
+            let text: Vec<usize> = glyphs.iter().map(|&(i, _)| i).filter(|&i| i < self.charpos).collect();
+            let (level, mut line_levels) = levels.line(&text);
+            line_levels.resize(glyphs.len(), level);
+            let width: i16 = glyphs.iter().map(|(_, gs)| (gs.wide + gs.kern) as i16).sum();
+            let x = if level & 1 == 1 { origin.x.max(self.max_width - width) } else { origin.x };
+            let order = bidi::visual_order(&line_levels);
+            let mut visual: Vec<GlyphSprite> = order.iter().map(|&k| glyphs[k].1).collect();
+            // the insertion point is drawn on the left of its glyph, but in right-to-left text it belongs on
+            // the right, which is the left of the glyph after it
+            if let Some(v) = order.iter().position(|&k| glyphs[k].1.insert && line_levels[k] & 1 == 1) {
+                if v + 1 < visual.len() {
+                    visual[v].insert = false;
+                    visual[v + 1].insert = true;
+                }
+            }
+            let mut word = TypesetWord::new(Pt::new(x, origin.y), glyphs.first().map_or(0, |&(i, _)| i));
+            for gs in visual {
+                word.push(gs);
+            }
+            lines.push(word);
+        }
+        lines
+    }
+
     /// resets the cursor state to the top left of the box for the next line to render.
     fn oneline_epilogue(&mut self) {
         self.cursor.pt.y = 0; // this should be redundant, as we never have more than one line in this mode
@@ -625,6 +730,14 @@ fn tsw_debug(tsw: &TypesetWord) {
 
 /// Find glyph for char using latin regular, emoji, ja, zh, and kr font data
 pub fn style_glyph(ch: char, base_style: &GlyphStyle) -> GlyphSprite {
+    // Hebrew and Arabic come from one font in every language and style, sized to match the base style
+    let rtl = match base_style {
+        GlyphStyle::Large | GlyphStyle::ExtraLarge => rtl_large_glyph(ch),
+        _ => rtl_glyph(ch),
+    };
+    if let Ok(g) = rtl {
+        return g;
+    }
     match locales::LANG {
         "zh" => {
             style_wrapper!(zh_rules, base_style, ch)
@@ -644,3 +757,54 @@ pub fn style_glyph(ch: char, base_style: &GlyphStyle) -> GlyphSprite {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WIDTH: i16 = 336;
+
+    /// Each line of `s` as drawn, left to right, with where it starts and how wide it is
+    fn lines(s: &str) -> (Vec<(String, i16, i16)>, bool) {
+        let mut typesetter =
+            Typesetter::setup(s, &Pt::new(WIDTH, 200), &GlyphStyle::Regular, None, TextDirection::Auto);
+        let composition = typesetter.typeset(OverflowStrategy::Ellipsis);
+        let lines = composition
+            .words
+            .iter()
+            .map(|word| {
+                let text: String = word.gs.iter().filter(|gs| gs.wide > 0).map(|gs| gs.ch).collect();
+                let width: i16 = word.gs.iter().map(|gs| (gs.wide + gs.kern) as i16).sum();
+                (text, word.origin.x, width)
+            })
+            .collect();
+        (lines, composition.is_rtl())
+    }
+
+    #[test]
+    fn mixed_lines() {
+        let (lines, rtl) = lines("abc אבג def\nאבג abc");
+        assert!(!rtl);
+        assert_eq!(lines.len(), 2);
+        // a left-to-right line with a right-to-left run in it
+        let (text, x, width) = &lines[0];
+        assert_eq!(text, "abc גבא def");
+        assert_eq!(*x, 0);
+        // the second paragraph starts right-to-left, so its runs swap and it ends where the first line does
+        assert_eq!(lines[1].0, "abc גבא");
+        assert_eq!(lines[1].1 + lines[1].2, x + width);
+        assert!(lines[1].1 > 0);
+    }
+
+    #[test]
+    fn rtl_first() {
+        let (lines, rtl) = lines("אבג abc דה\nabc");
+        assert!(rtl);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].0, "הד abc גבא");
+        assert_eq!(lines[0].1, 0);
+        // left-to-right lines stay on the left, even under a right-to-left one
+        assert_eq!(lines[1].0, "abc");
+        assert_eq!(lines[1].1, 0);
+    }
+}
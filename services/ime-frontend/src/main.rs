@@ -211,6 +211,31 @@ impl InputTracker {
         if debug1 {
             info!("IMEF|insert_prediction string {}, last_trigger {:?}", pred_str, self.last_trigger_char);
         }
+        let phrase_chars = self.pred_phrase.chars().count();
+        if phrase_chars > 0 && self.insertion == self.characters && phrase_chars <= self.characters {
+            // the prediction replaces the phrase that was given to the predictor, which is what was typed
+            // last. This is what lets an input method turn romanized input into the characters it stands for.
+            let keep = self.characters - phrase_chars;
+            let mut line: String = self.line.chars().take(keep).collect();
+            line.push_str(pred_str);
+            self.characters = keep + pred_str.chars().count();
+            self.insertion = self.characters;
+            self.line = line;
+            if self.pred_triggers.map_or(false, |t| t.newline) {
+                // a line predictor completes the whole line, so the picked line is what it continues from
+                self.pred_phrase = pred_str.to_string();
+            } else {
+                self.last_trigger_char = Some(self.insertion);
+                self.pred_phrase.clear();
+            }
+            // the predictor's input was consumed by the pick
+            if let Some(predictor) = self.predictor {
+                predictor
+                    .set_input(xous_ipc::String::<4000>::from_str(&self.pred_phrase))
+                    .expect("couldn't reset predictor input");
+            }
+            return;
+        }
         if let Some(offset) = self.last_trigger_char {
             if offset < self.characters {
                 // copy the bytes in the original string, up to the offset; and then copy the bytes in the
@@ -359,6 +384,7 @@ impl InputTracker {
                         if !self.menu_mode {
                             self.insert_prediction(0);
                             do_redraw = true;
+                            update_predictor = true;
                         } else {
                             retstring = Some(xous_ipc::String::<4000>::from_str("\u{0011}"));
                             do_redraw = true;
@@ -369,6 +395,7 @@ impl InputTracker {
                         if !self.menu_mode {
                             self.insert_prediction(1);
                             do_redraw = true;
+                            update_predictor = true;
                         } else {
                             retstring = Some(xous_ipc::String::<4000>::from_str("\u{0012}"));
                             do_redraw = true;
@@ -379,6 +406,7 @@ impl InputTracker {
                         if !self.menu_mode {
                             self.insert_prediction(2);
                             do_redraw = true;
+                            update_predictor = true;
                         } else {
                             retstring = Some(xous_ipc::String::<4000>::from_str("\u{0013}"));
                             do_redraw = true;
@@ -389,6 +417,7 @@ impl InputTracker {
                         if !self.menu_mode {
                             self.insert_prediction(3);
                             do_redraw = true;
+                            update_predictor = true;
                         } else {
                             retstring = Some(xous_ipc::String::<4000>::from_str("\u{0014}"));
                             do_redraw = true;
@@ -413,7 +442,16 @@ impl InputTracker {
                                     self.can_unpick = false;
                                     update_predictor = true;
                                 }
-                                self.pred_phrase.pop();
+                                if self.pred_phrase.pop().is_some() {
+                                    // keep the predictor in step with the phrase, including once it's all
+                                    // erased
+                                    if self.pred_phrase.is_empty() {
+                                        predictor
+                                            .set_input(xous_ipc::String::<4000>::new())
+                                            .expect("couldn't reset predictor input");
+                                    }
+                                    update_predictor = true;
+                                }
                                 if self.menu_mode {
                                     update_predictor = true;
                                 }
@@ -863,7 +901,10 @@ fn main() -> ! {
                     match xns.request_connection_with_token(s.as_str().unwrap()) {
                         Ok((pc, token)) => {
                             let pred = ime_plugin_api::PredictionPlugin { connection: Some(pc) };
-                            match pred.acquire(descriptor.predictor_token) {
+                            match pred.acquire(
+                                descriptor.predictor_token,
+                                descriptor.app_name.as_ref().and_then(|n| n.as_str().ok()),
+                            ) {
                                 Ok(confirmation) => {
                                     api_token = Some(ApiToken {
                                         api_token: confirmation,
@@ -16,6 +16,9 @@ pub struct Prediction {
 #[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 pub struct AcquirePredictor {
     pub token: Option<[u32; 4]>,
+    /// name of the app the predictor is acquired for. Unlike the token, it is the same from one boot to
+    /// the next, so predictors can use it to keep state that outlives a reboot.
+    pub app: Option<String<64>>,
 }
 #[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 pub struct ApiToken {
@@ -92,7 +95,8 @@ pub trait PredictionApi {
     fn feedback_picked(&self, s: String<4000>) -> Result<(), xous::Error>;
     fn get_prediction(&self, index: u32, api_token: [u32; 4]) -> Result<Option<String<4000>>, xous::Error>;
     /// gets an exclusive lock on the predictor. Returns an error if the predictor is already locked.
-    fn acquire(&self, api_token: Option<[u32; 4]>) -> Result<[u32; 4], xous::Error>;
+    /// `app` names the app the lock is taken for, if it is known.
+    fn acquire(&self, api_token: Option<[u32; 4]>, app: Option<&str>) -> Result<[u32; 4], xous::Error>;
     /// releases the lock. Also clears any sensitive data that may be in the predictor.
     fn release(&self, api_token: [u32; 4]);
 }
@@ -193,10 +197,10 @@ impl PredictionApi for PredictionPlugin {
         }
     }
 
-    fn acquire(&self, api_token: Option<[u32; 4]>) -> Result<[u32; 4], xous::Error> {
+    fn acquire(&self, api_token: Option<[u32; 4]>, app: Option<&str>) -> Result<[u32; 4], xous::Error> {
         match self.connection {
             Some(cid) => {
-                let request = AcquirePredictor { token: api_token };
+                let request = AcquirePredictor { token: api_token, app: app.map(String::<64>::from_str) };
                 let mut buf = Buffer::into_buf(request).unwrap();
                 buf.lend_mut(cid, Opcode::Acquire.to_u32().unwrap()).unwrap();
                 let ret = buf.to_original::<AcquirePredictor, _>().unwrap();
@@ -268,6 +272,8 @@ pub struct ImefDescriptor {
     pub predictor: Option<String<64>>,
     pub token: [u32; 4], // token used to lookup our connected app inside the GAM
     pub predictor_token: Option<[u32; 4]>,
+    /// name of the connected app, passed on to the predictor
+    pub app_name: Option<String<64>>,
 }
 
 pub trait ImeFrontEndApi {
@@ -0,0 +1,194 @@
+use std::collections::BTreeMap;
+
+/// Input beyond this length isn't converted
+const MAX_INPUT_LEN: usize = 48;
+/// Offset from a hiragana to the katakana for the same sound
+const KATAKANA_OFFSET: u32 = 0x60;
+
+/// Romaji to hiragana. Longer spellings are tried first, so `kya` wins over `ki` + `ya`.
+#[rustfmt::skip] // this is a lookup table. Allow wide columns.
+const ROMAJI: &[(&str, &str)] = &[
+    ("a", "あ"), ("i", "い"), ("u", "う"), ("e", "え"), ("o", "お"),
+    ("ka", "か"), ("ki", "き"), ("ku", "く"), ("ke", "け"), ("ko", "こ"),
+    ("ga", "が"), ("gi", "ぎ"), ("gu", "ぐ"), ("ge", "げ"), ("go", "ご"),
+    ("sa", "さ"), ("shi", "し"), ("si", "し"), ("su", "す"), ("se", "せ"), ("so", "そ"),
+    ("za", "ざ"), ("ji", "じ"), ("zi", "じ"), ("zu", "ず"), ("ze", "ぜ"), ("zo", "ぞ"),
+    ("ta", "た"), ("chi", "ち"), ("ti", "ち"), ("tsu", "つ"), ("tu", "つ"), ("te", "て"), ("to", "と"),
+    ("da", "だ"), ("di", "ぢ"), ("du", "づ"), ("de", "で"), ("do", "ど"),
+    ("na", "な"), ("ni", "に"), ("nu", "ぬ"), ("ne", "ね"), ("no", "の"),
+    ("ha", "は"), ("hi", "ひ"), ("fu", "ふ"), ("hu", "ふ"), ("he", "へ"), ("ho", "ほ"),
+    ("ba", "ば"), ("bi", "び"), ("bu", "ぶ"), ("be", "べ"), ("bo", "ぼ"),
+    ("pa", "ぱ"), ("pi", "ぴ"), ("pu", "ぷ"), ("pe", "ぺ"), ("po", "ぽ"),
+    ("ma", "ま"), ("mi", "み"), ("mu", "む"), ("me", "め"), ("mo", "も"),
+    ("ya", "や"), ("yu", "ゆ"), ("yo", "よ"),
+    ("ra", "ら"), ("ri", "り"), ("ru", "る"), ("re", "れ"), ("ro", "ろ"),
+    ("wa", "わ"), ("wo", "を"), ("vu", "ゔ"),
+    ("kya", "きゃ"), ("kyu", "きゅ"), ("kyo", "きょ"), ("gya", "ぎゃ"), ("gyu", "ぎゅ"), ("gyo", "ぎょ"),
+    ("sha", "しゃ"), ("shu", "しゅ"), ("sho", "しょ"), ("she", "しぇ"),
+    ("sya", "しゃ"), ("syu", "しゅ"), ("syo", "しょ"),
+    ("ja", "じゃ"), ("ju", "じゅ"), ("jo", "じょ"), ("je", "じぇ"),
+    ("jya", "じゃ"), ("jyu", "じゅ"), ("jyo", "じょ"), ("zya", "じゃ"), ("zyu", "じゅ"), ("zyo", "じょ"),
+    ("cha", "ちゃ"), ("chu", "ちゅ"), ("cho", "ちょ"), ("che", "ちぇ"),
+    ("tya", "ちゃ"), ("tyu", "ちゅ"), ("tyo", "ちょ"), ("cya", "ちゃ"), ("cyu", "ちゅ"), ("cyo", "ちょ"),
+    ("nya", "にゃ"), ("nyu", "にゅ"), ("nyo", "にょ"), ("hya", "ひゃ"), ("hyu", "ひゅ"), ("hyo", "ひょ"),
+    ("bya", "びゃ"), ("byu", "びゅ"), ("byo", "びょ"), ("pya", "ぴゃ"), ("pyu", "ぴゅ"), ("pyo", "ぴょ"),
+    ("mya", "みゃ"), ("myu", "みゅ"), ("myo", "みょ"), ("rya", "りゃ"), ("ryu", "りゅ"), ("ryo", "りょ"),
+    // sounds from loanwords
+    ("fa", "ふぁ"), ("fi", "ふぃ"), ("fe", "ふぇ"), ("fo", "ふぉ"),
+    ("wi", "うぃ"), ("we", "うぇ"), ("va", "ゔぁ"), ("vi", "ゔぃ"), ("ve", "ゔぇ"), ("vo", "ゔぉ"),
+    ("tsa", "つぁ"), ("thi", "てぃ"), ("dhi", "でぃ"),
+    // small kana
+    ("xa", "ぁ"), ("xi", "ぃ"), ("xu", "ぅ"), ("xe", "ぇ"), ("xo", "ぉ"),
+    ("la", "ぁ"), ("li", "ぃ"), ("lu", "ぅ"), ("le", "ぇ"), ("lo", "ぉ"),
+    ("xya", "ゃ"), ("xyu", "ゅ"), ("xyo", "ょ"), ("lya", "ゃ"), ("lyu", "ゅ"), ("lyo", "ょ"),
+    ("xtsu", "っ"), ("xtu", "っ"), ("ltu", "っ"), ("xwa", "ゎ"),
+];
+
+fn is_vowel(c: u8) -> bool { matches!(c, b'a' | b'i' | b'u' | b'e' | b'o') }
+
+/// The hiragana spelling of `romaji`, and whether the input ended part-way into a kana. The letters
+/// of an unfinished kana are kept as typed at the end. Returns `None` if the input isn't romaji.
+pub fn to_hiragana(romaji: &str) -> Option<(String, bool)> {
+    let s = romaji.as_bytes();
+    let mut out = String::new();
+    let mut i = 0;
+    while i < s.len() {
+        let c = s[i];
+        let next = s.get(i + 1).copied();
+        match c {
+            b'-' => {
+                out.push('ー');
+                i += 1;
+                continue;
+            }
+            b'\'' => {
+                i += 1;
+                continue;
+            }
+            b'n' if next.map_or(true, |n| !is_vowel(n) && n != b'y') => {
+                // `n` makes `ん` unless it starts a syllable; a doubled `n` or `n'` is consumed whole
+                // when it has to be, so that `konnichiha` and `kon'ya` both come out as meant
+                let after = s.get(i + 2).copied();
+                out.push('ん');
+                i += match next {
+                    Some(b'\'') => 2,
+                    Some(b'n') if !after.map_or(false, |a| is_vowel(a) || a == b'y') => 2,
+                    _ => 1,
+                };
+                continue;
+            }
+            _ => {}
+        }
+        if (next == Some(c) && c.is_ascii_lowercase() && !is_vowel(c)) || (c == b't' && next == Some(b'c')) {
+            // a doubled consonant, or the `tch` of `matcha`
+            out.push('っ');
+            i += 1;
+            continue;
+        }
+        let matched = (1..=4).rev().filter(|len| i + len <= s.len()).find_map(|len| {
+            let spelling = core::str::from_utf8(&s[i..i + len]).ok()?;
+            ROMAJI.iter().find(|(r, _)| *r == spelling).map(|(_, kana)| (len, *kana))
+        });
+        match matched {
+            Some((len, kana)) => {
+                out.push_str(kana);
+                i += len;
+            }
+            None => {
+                let rest = core::str::from_utf8(&s[i..]).ok()?;
+                if ROMAJI.iter().any(|(r, _)| r.starts_with(rest)) {
+                    out.push_str(rest);
+                    return Some((out, true));
+                }
+                return None;
+            }
+        }
+    }
+    Some((out, false))
+}
+
+/// The katakana spelling of a hiragana string; anything else is left as it is
+pub fn to_katakana(hiragana: &str) -> String {
+    hiragana
+        .chars()
+        .map(|c| match c {
+            'ぁ'..='ゖ' => core::char::from_u32(c as u32 + KATAKANA_OFFSET).unwrap_or(c),
+            _ => c,
+        })
+        .collect()
+}
+
+pub struct Dictionary {
+    /// reading in hiragana -> words, most frequent first
+    words: BTreeMap<&'static str, Vec<&'static str>>,
+}
+
+impl Dictionary {
+    pub fn new() -> Self {
+        let mut words = BTreeMap::new();
+        for line in include_str!("words.txt").lines().filter(|l| !l.is_empty() && !l.starts_with('#')) {
+            let mut fields = line.split_whitespace();
+            if let Some(reading) = fields.next() {
+                words.insert(reading, fields.collect());
+            }
+        }
+        Dictionary { words }
+    }
+
+    /// Returns up to `max` conversions of `input`: the hiragana, words with that reading, and the
+    /// katakana, which always gets a place. Input that isn't romaji has none.
+    pub fn candidates(&self, input: &str, max: usize) -> Vec<String> {
+        let input = input.to_ascii_lowercase();
+        if input.is_empty()
+            || input.len() > MAX_INPUT_LEN
+            || !input.bytes().all(|c| c.is_ascii_lowercase() || c == b'\'' || c == b'-')
+        {
+            return Vec::new();
+        }
+        let (hiragana, partial) = match to_hiragana(&input) {
+            Some(h) => h,
+            None => return Vec::new(),
+        };
+        let mut candidates = vec![hiragana.clone()];
+        if !partial {
+            if let Some(words) = self.words.get(hiragana.as_str()) {
+                candidates.extend(words.iter().take(max.saturating_sub(2)).map(|w| w.to_string()));
+            }
+        }
+        let katakana = to_katakana(&hiragana);
+        if katakana != hiragana {
+            candidates.push(katakana);
+        }
+        candidates.truncate(max);
+        candidates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn romaji() {
+        let hiragana = |r| to_hiragana(r).map(|(h, _)| h);
+        assert_eq!(hiragana("konnichiha").as_deref(), Some("こんにちは"));
+        assert_eq!(hiragana("kon'ya").as_deref(), Some("こんや"));
+        assert_eq!(hiragana("konya").as_deref(), Some("こにゃ"));
+        assert_eq!(hiragana("honn").as_deref(), Some("ほん"));
+        assert_eq!(hiragana("kitte").as_deref(), Some("きって"));
+        assert_eq!(hiragana("matcha").as_deref(), Some("まっちゃ"));
+        assert_eq!(hiragana("ra-men").as_deref(), Some("らーめん"));
+        assert_eq!(to_hiragana("toky"), Some(("とky".to_string(), true)));
+        assert_eq!(to_hiragana("qq"), None);
+        assert_eq!(to_katakana("こんぴゅーたー"), "コンピューター");
+    }
+
+    #[test]
+    fn conversion() {
+        let dict = Dictionary::new();
+        assert_eq!(dict.candidates("nihon", 4), vec!["にほん", "日本", "ニホン"]);
+        assert_eq!(dict.candidates("kami", 4), vec!["かみ", "紙", "神", "カミ"]);
+        assert_eq!(dict.candidates("Ne", 4), vec!["ね", "ネ"]);
+        assert!(dict.candidates("hello!", 4).is_empty());
+    }
+}
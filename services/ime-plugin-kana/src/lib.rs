@@ -0,0 +1,15 @@
+#![cfg_attr(target_os = "none", no_std)]
+
+//! Converts romaji into hiragana, katakana and kanji candidates.
+//!
+//! An app opts in by setting `UxRegistration::predictor` to `SERVER_NAME_IME_PLUGIN_KANA`. Hardware images
+//! leave the plugin out unless asked for, e.g. `cargo xtask app-image --service ime-plugin-kana`; the
+//! hosted `run` and `hosted-debug` images include it.
+//! Romaji follows the Hepburn and kunrei spellings: double a consonant for `っ`, use `nn` or `n'` for `ん`
+//! before a vowel, and `-` for the long vowel mark. F1-F4 replace the romaji with the chosen candidate;
+//! space ends the phrase and keeps it as typed.
+
+pub const SERVER_NAME_IME_PLUGIN_KANA: &str = "_IME kana plugin_";
+
+// just inherit all the default from the ime_plugin_api
+pub use ime_plugin_api::*;
@@ -0,0 +1,15 @@
+#![cfg_attr(target_os = "none", no_std)]
+
+//! Converts romanized pinyin into hanzi candidates.
+//!
+//! An app opts in by setting `UxRegistration::predictor` to `SERVER_NAME_IME_PLUGIN_PINYIN`. Hardware
+//! images leave the plugin out unless asked for, e.g. `cargo xtask app-image --service
+//! ime-plugin-pinyin`; the hosted `run` and `hosted-debug` images include it. Syllables are typed without tones, with `v` for `ü` and `'` to separate
+//! syllables where the split is ambiguous (`xi'an`). The candidates for what has been typed so far appear
+//! in the prediction bar, and F1-F4 replace the pinyin with the chosen candidate. Space ends the phrase
+//! and keeps it as typed.
+
+pub const SERVER_NAME_IME_PLUGIN_PINYIN: &str = "_IME pinyin plugin_";
+
+// just inherit all the default from the ime_plugin_api
+pub use ime_plugin_api::*;
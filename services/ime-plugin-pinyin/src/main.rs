@@ -0,0 +1,120 @@
+#![cfg_attr(target_os = "none", no_std)]
+#![cfg_attr(target_os = "none", no_main)]
+
+mod pinyin;
+
+use ime_plugin_api::*;
+use num_traits::FromPrimitive;
+use xous::msg_scalar_unpack;
+use xous_ipc::{Buffer, String};
+
+/// The prediction bar shows at most this many candidates
+const MAX_CANDIDATES: usize = 4;
+
+fn main() -> ! {
+    log_server::init_wait().unwrap();
+    log::set_max_level(log::LevelFilter::Info);
+    log::info!("my PID is {}", xous::process::id());
+
+    let xns = xous_names::XousNames::new().unwrap();
+    // one connection only, should be the GAM
+    let ime_py_sid = xns
+        .register_name(ime_plugin_pinyin::SERVER_NAME_IME_PLUGIN_PINYIN, None)
+        .expect("can't register server");
+    log::trace!("registered with NS -- {:?}", ime_py_sid);
+
+    let dictionary = pinyin::Dictionary::new();
+    let mut candidates: Vec<std::string::String> = Vec::new();
+
+    // only whitespace ends a phrase, so that `'` can separate syllables
+    let mytriggers = PredictionTriggers { newline: false, punctuation: false, whitespace: true };
+
+    log::trace!("ready to accept requests");
+    let mut api_token: Option<[u32; 4]> = None;
+    loop {
+        let mut msg = xous::receive_message(ime_py_sid).unwrap();
+        log::trace!("received message {:?}", msg);
+        match FromPrimitive::from_usize(msg.body.id()) {
+            Some(Opcode::Acquire) => {
+                let mut buffer =
+                    unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                let mut ret = buffer.to_original::<AcquirePredictor, _>().unwrap();
+                if api_token.is_none() {
+                    if let Some(token) = ret.token {
+                        api_token = Some(token);
+                    } else {
+                        let new_token = xous::create_server_id().unwrap().to_array();
+                        ret.token = Some(new_token);
+                        api_token = Some(new_token);
+                    }
+                } else {
+                    ret.token = None;
+                    log::warn!("attempt to acquire lock on a predictor that was already locked");
+                }
+                buffer.replace(ret).unwrap();
+            }
+            Some(Opcode::Release) => msg_scalar_unpack!(msg, t0, t1, t2, t3, {
+                let token = [t0 as u32, t1 as u32, t2 as u32, t3 as u32];
+                if let Some(t) = api_token {
+                    if t == token {
+                        api_token.take();
+                        candidates.clear();
+                    } else {
+                        log::warn!("Release called with an invalid token");
+                    }
+                } else {
+                    log::warn!("Release called on a predictor that was in a released state");
+                }
+            }),
+            Some(Opcode::Input) => {
+                let buffer = unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                let s = buffer.as_flat::<String<4000>, _>().unwrap();
+                candidates = dictionary.candidates(s.as_str(), MAX_CANDIDATES);
+                log::trace!("{} -> {:?}", s.as_str(), candidates);
+            }
+            Some(Opcode::Picked) => {
+                // the phrase was ended without picking a candidate, so it stays as typed
+                candidates.clear();
+            }
+            Some(Opcode::Prediction) => {
+                let mut buffer =
+                    unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                let mut prediction: Prediction = buffer.to_original::<Prediction, _>().unwrap();
+                prediction.valid = false;
+                if api_token == Some(prediction.api_token) {
+                    if let Some(candidate) = candidates.get(prediction.index as usize) {
+                        prediction.string.clear();
+                        for ch in candidate.chars() {
+                            if prediction.string.push(ch).is_err() {
+                                break;
+                            }
+                        }
+                        prediction.valid = true;
+                    }
+                } else {
+                    log::warn!("api token mismatch, ignoring");
+                }
+                buffer.replace(Return::Prediction(prediction)).expect("couldn't return Prediction");
+            }
+            Some(Opcode::Unpick) => {}
+            Some(Opcode::GetPredictionTriggers) => {
+                xous::return_scalar(msg.sender, mytriggers.into())
+                    .expect("couldn't return GetPredictionTriggers");
+            }
+            Some(Opcode::Quit) => {
+                if api_token.is_some() {
+                    log::error!("received quit, goodbye!");
+                    break;
+                }
+            }
+            None => {
+                log::error!("unknown Opcode");
+            }
+        }
+    }
+    log::trace!("main loop exit, destroying servers");
+    xns.unregister_server(ime_py_sid).unwrap();
+    xous::destroy_server(ime_py_sid).unwrap();
+    log::trace!("quitting");
+    xous::terminate_process(0)
+}
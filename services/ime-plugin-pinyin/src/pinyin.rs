@@ -0,0 +1,225 @@
+use std::collections::BTreeMap;
+use std::ops::Bound;
+
+/// Longest syllable in the table (`zhuang`, `chuang`, `shuang`)
+const MAX_SYLLABLE_LEN: usize = 6;
+/// Longest run of syllables looked up as one word
+const MAX_PHRASE_SYLLABLES: usize = 4;
+/// Input beyond this length isn't converted; it keeps the segmentation search bounded
+const MAX_INPUT_LEN: usize = 48;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Segment<'a> {
+    /// a complete syllable
+    Full(&'a str),
+    /// the start of a syllable that is still being typed; only ever the last segment
+    Partial(&'a str),
+}
+
+pub struct Dictionary {
+    /// syllable -> hanzi, most frequent first
+    syllables: BTreeMap<&'static str, Vec<char>>,
+    /// syllables run together -> words, most frequent first
+    phrases: BTreeMap<&'static str, Vec<&'static str>>,
+}
+
+impl Dictionary {
+    pub fn new() -> Self {
+        let mut syllables = BTreeMap::new();
+        for (syllable, chars) in entries(include_str!("syllables.txt")) {
+            syllables.insert(syllable, chars.flat_map(|c| c.chars()).collect());
+        }
+        let mut phrases = BTreeMap::new();
+        for (pinyin, words) in entries(include_str!("phrases.txt")) {
+            phrases.insert(pinyin, words.collect());
+        }
+        Dictionary { syllables, phrases }
+    }
+
+    /// Returns up to `max` conversions of `input`, best first. Input that isn't pinyin has none.
+    pub fn candidates(&self, input: &str, max: usize) -> Vec<String> {
+        let input = input.to_ascii_lowercase();
+        if input.is_empty()
+            || input.len() > MAX_INPUT_LEN
+            || !input.chars().all(|c| c.is_ascii_lowercase() || c == '\'')
+        {
+            return Vec::new();
+        }
+        let segments = match self.segment(&input) {
+            Some(s) if !s.is_empty() => s,
+            _ => return Vec::new(),
+        };
+        let fulls: Vec<&str> =
+            segments.iter().filter_map(|s| if let Segment::Full(f) = s { Some(*f) } else { None }).collect();
+        let partial = segments.iter().find_map(|s| if let Segment::Partial(p) = s { Some(*p) } else { None });
+
+        let mut candidates: Vec<String> = Vec::new();
+        // whole words first: an exact match, or words that the input is the start of
+        let joined: String = fulls.concat() + partial.unwrap_or("");
+        if partial.is_none() {
+            if let Some(words) = self.phrases.get(joined.as_str()) {
+                candidates.extend(words.iter().map(|w| w.to_string()));
+            }
+        } else {
+            for (_, words) in self
+                .phrases
+                .range::<str, _>((Bound::Included(joined.as_str()), Bound::Unbounded))
+                .take_while(|(k, _)| k.starts_with(&joined))
+            {
+                candidates.extend(words.iter().map(|w| w.to_string()));
+            }
+        }
+        if segments.len() > 1 {
+            candidates.push(self.sentence(&segments));
+        } else {
+            match segments[0] {
+                Segment::Full(syllable) => {
+                    candidates.extend(self.syllables[syllable].iter().map(|c| c.to_string()))
+                }
+                Segment::Partial(prefix) => candidates.extend(
+                    self.syllables_starting_with(prefix)
+                        .filter_map(|(_, chars)| chars.first())
+                        .map(|c| c.to_string()),
+                ),
+            }
+        }
+
+        let mut unique: Vec<String> = Vec::new();
+        for c in candidates {
+            if !unique.contains(&c) {
+                unique.push(c);
+            }
+            if unique.len() == max {
+                break;
+            }
+        }
+        unique
+    }
+
+    /// Converts every segment, preferring known words over single characters
+    fn sentence(&self, segments: &[Segment]) -> String {
+        let mut out = String::new();
+        let mut i = 0;
+        'next: while i < segments.len() {
+            for len in (2..=MAX_PHRASE_SYLLABLES.min(segments.len() - i)).rev() {
+                let run: Option<Vec<&str>> = segments[i..i + len]
+                    .iter()
+                    .map(|s| if let Segment::Full(f) = s { Some(*f) } else { None })
+                    .collect();
+                if let Some(word) = run.and_then(|r| self.phrases.get(r.concat().as_str())) {
+                    out.push_str(word[0]);
+                    i += len;
+                    continue 'next;
+                }
+            }
+            let first = match segments[i] {
+                Segment::Full(syllable) => self.syllables[syllable].first(),
+                Segment::Partial(prefix) => {
+                    self.syllables_starting_with(prefix).next().and_then(|(_, chars)| chars.first())
+                }
+            };
+            if let Some(c) = first {
+                out.push(*c);
+            }
+            i += 1;
+        }
+        out
+    }
+
+    fn syllables_starting_with<'a>(
+        &'a self,
+        prefix: &'a str,
+    ) -> impl Iterator<Item = (&'a &'static str, &'a Vec<char>)> + 'a {
+        self.syllables
+            .range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+            .take_while(move |(k, _)| k.starts_with(prefix))
+    }
+
+    /// Splits the input into syllables. `'` forces a split; otherwise the longest syllable that lets the
+    /// rest of the input split as well is taken, and the input may end part-way into a syllable.
+    fn segment<'a>(&self, input: &'a str) -> Option<Vec<Segment<'a>>> {
+        let pieces: Vec<&str> = input.split('\'').filter(|p| !p.is_empty()).collect();
+        let mut segments = Vec::new();
+        for (i, piece) in pieces.iter().enumerate() {
+            segments.extend(self.split(piece, i == pieces.len() - 1 && !input.ends_with('\''))?);
+        }
+        Some(segments)
+    }
+
+    fn split<'a>(&self, s: &'a str, allow_partial: bool) -> Option<Vec<Segment<'a>>> {
+        self.split_from(s, 0, allow_partial, &mut vec![false; s.len()])
+    }
+
+    /// Splits `s[start..]`. `failed[i]` records that `s[i..]` can't be split, so that input which
+    /// splits many ways before failing doesn't get retried once per way of reaching it.
+    fn split_from<'a>(
+        &self,
+        s: &'a str,
+        start: usize,
+        allow_partial: bool,
+        failed: &mut Vec<bool>,
+    ) -> Option<Vec<Segment<'a>>> {
+        let rest = &s[start..];
+        if rest.is_empty() {
+            return Some(Vec::new());
+        }
+        if failed[start] {
+            return None;
+        }
+        for len in (1..=MAX_SYLLABLE_LEN.min(rest.len())).rev() {
+            if self.syllables.contains_key(&rest[..len]) {
+                if let Some(mut tail) = self.split_from(s, start + len, allow_partial, failed) {
+                    tail.insert(0, Segment::Full(&rest[..len]));
+                    return Some(tail);
+                }
+            }
+        }
+        if allow_partial && self.syllables_starting_with(rest).next().is_some() {
+            return Some(vec![Segment::Partial(rest)]);
+        }
+        failed[start] = true;
+        None
+    }
+}
+
+/// Parses `key value value...` lines, skipping blank lines and `#` comments
+fn entries(text: &'static str) -> impl Iterator<Item = (&'static str, impl Iterator<Item = &'static str>)> {
+    text.lines().filter(|l| !l.is_empty() && !l.starts_with('#')).filter_map(|l| {
+        let mut fields = l.split_whitespace();
+        fields.next().map(|key| (key, fields))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn segmentation() {
+        let dict = Dictionary::new();
+        assert_eq!(dict.segment("xian"), Some(vec![Segment::Full("xian")]));
+        assert_eq!(dict.segment("xi'an"), Some(vec![Segment::Full("xi"), Segment::Full("an")]));
+        assert_eq!(
+            dict.segment("zhongguoren"),
+            Some(vec![Segment::Full("zhong"), Segment::Full("guo"), Segment::Full("ren")])
+        );
+        assert_eq!(dict.segment("nizh"), Some(vec![Segment::Full("ni"), Segment::Partial("zh")]));
+        assert_eq!(dict.segment("nizh'"), None);
+        assert_eq!(dict.segment("qqq"), None);
+        // splits a great many ways before failing at the end, which has to be quick
+        let ambiguous = format!("{}q'", "ang".repeat(15));
+        assert_eq!(dict.segment(&ambiguous), None);
+    }
+
+    #[test]
+    fn conversion() {
+        let dict = Dictionary::new();
+        assert_eq!(dict.candidates("nihao", 4)[0], "你好");
+        assert_eq!(dict.candidates("zhongguoren", 4), vec!["中国人"]);
+        assert!(dict.candidates("nih", 4).contains(&"你好".to_string()));
+        assert_eq!(dict.candidates("wo", 4)[0], "我");
+        assert_eq!(dict.candidates("lv", 1), vec!["绿"]);
+        assert_eq!(dict.candidates("Ni", 4)[0], "你");
+        assert!(dict.candidates("hello!", 4).is_empty());
+    }
+}
@@ -0,0 +1,263 @@
+use std::io::{Read, Write};
+
+/// PDDB dictionary that holds the command histories, one key per app
+pub(crate) const HISTORY_DICT: &str = "ime.shell.history";
+
+/// Commands that take a secret as an argument. Lines that run one of them are never recorded, wherever
+/// the command appears on the line.
+const SECRET_COMMANDS: [&[&str]; 3] = [
+    // shellchat
+    &["wlan", "setpass"],
+    // mtxcli
+    &["/set", "password"],
+    &["/set", "_token"],
+];
+
+/// The commands entered into one app, oldest first. Histories of apps that asked for it are saved to the
+/// PDDB, so they survive a reboot.
+pub(crate) struct History {
+    app: Option<String>,
+    entries: Vec<String>,
+    /// set if the history is kept in the PDDB
+    persistent: bool,
+    /// set once the saved history has been read back, or if there is nothing to read
+    loaded: bool,
+    /// how many changes were made since the history was last saved
+    unsaved: usize,
+    /// what the most recent push did, so it can be undone
+    last_push: Option<LastPush>,
+}
+
+struct LastPush {
+    /// where the command was before it moved to the front
+    moved_from: Option<usize>,
+    /// the oldest commands that were dropped to make room
+    dropped: Vec<String>,
+}
+
+impl History {
+    pub fn new(app: Option<String>) -> Self {
+        let loaded = app.is_none();
+        History { app, entries: Vec::new(), persistent: false, loaded, unsaved: 0, last_push: None }
+    }
+
+    /// Keeps the history in the PDDB, or only in memory. Only histories that belong to an app can be kept.
+    pub fn set_persistent(&mut self, persistent: bool) { self.persistent = persistent && self.app.is_some(); }
+
+    /// Reads the saved history back, if it is kept in the PDDB, the PDDB is mounted and it hasn't been read
+    /// yet. Commands entered before then are kept as the most recent ones.
+    pub fn load(&mut self, pddb: &pddb::Pddb, max_len: usize) {
+        if self.loaded || !self.persistent || !pddb.is_mounted_nonblocking() {
+            return;
+        }
+        self.loaded = true;
+        let app = self.app.as_deref().unwrap_or_default();
+        let mut text = String::new();
+        match pddb.get(HISTORY_DICT, app, None, false, false, None, None::<fn()>) {
+            Ok(mut key) => {
+                if let Err(e) = key.read_to_string(&mut text) {
+                    log::warn!("couldn't read the history of {}: {:?}", app, e);
+                    return;
+                }
+            }
+            // nothing saved yet
+            Err(_) => return,
+        }
+        self.merge(&text, max_len);
+    }
+
+    /// Puts the commands saved in `text` before the ones entered since
+    fn merge(&mut self, text: &str, max_len: usize) {
+        let recent = std::mem::take(&mut self.entries);
+        self.entries = text.lines().filter(|l| !l.is_empty()).map(String::from).collect();
+        for entry in recent {
+            self.push(&entry, max_len);
+        }
+        self.truncate(max_len);
+        self.last_push = None;
+    }
+
+    pub fn is_loaded(&self) -> bool { self.loaded }
+
+    pub fn app(&self) -> Option<&str> { self.app.as_deref() }
+
+    /// How many changes were made since the history was last saved
+    pub fn unsaved(&self) -> usize { if self.persistent && self.loaded { self.unsaved } else { 0 } }
+
+    /// Writes the history to the PDDB, if it changed. Does nothing for histories that aren't kept in the
+    /// PDDB, or until the saved history has been loaded, so as not to overwrite it.
+    pub fn save(&mut self, pddb: &pddb::Pddb) {
+        let app = match (&self.app, self.persistent && self.loaded && self.unsaved > 0) {
+            (Some(app), true) => app,
+            _ => return,
+        };
+        self.unsaved = 0;
+        let text = self.entries.join("\n");
+        // delete the key first, so no part of a longer history is left behind
+        pddb.delete_key(HISTORY_DICT, app, None).ok();
+        match pddb.get(HISTORY_DICT, app, None, true, true, Some(text.len()), None::<fn()>) {
+            Ok(mut key) => {
+                if let Err(e) = key.write_all(text.as_bytes()) {
+                    log::warn!("couldn't save the history of {}: {:?}", app, e);
+                }
+                pddb.sync().ok();
+            }
+            Err(e) => log::warn!("couldn't save the history of {}: {:?}", app, e),
+        }
+    }
+
+    /// Records a command as the most recent one. A command that was already in the history moves to the
+    /// front instead of being repeated, and the oldest commands are dropped past `max_len`. Commands that
+    /// carry a secret are not recorded.
+    pub fn push(&mut self, entry: &str, max_len: usize) {
+        // the history is saved one command per line
+        let entry = entry.lines().next().unwrap_or("").trim_end();
+        if entry.is_empty() || carries_secret(entry) {
+            self.last_push = None;
+            return;
+        }
+        let moved_from = self.entries.iter().position(|e| e == entry);
+        if let Some(i) = moved_from {
+            self.entries.remove(i);
+        }
+        self.entries.push(entry.to_string());
+        let dropped = self.truncate(max_len);
+        self.last_push = Some(LastPush { moved_from, dropped });
+        self.unsaved += 1;
+    }
+
+    /// Drops the oldest commands past `max_len`, and returns them
+    pub fn truncate(&mut self, max_len: usize) -> Vec<String> {
+        let excess = self.entries.len().saturating_sub(max_len);
+        self.entries.drain(..excess).collect()
+    }
+
+    /// Undoes the most recent push: the command goes back to where it was, or is forgotten if it was new,
+    /// and the commands it pushed out come back.
+    pub fn pop(&mut self) {
+        let last_push = match self.last_push.take() {
+            Some(last_push) => last_push,
+            None => return,
+        };
+        if let Some(entry) = self.entries.pop() {
+            if let Some(i) = last_push.moved_from {
+                self.entries.insert(i.min(self.entries.len()), entry);
+            }
+        }
+        let mut entries = last_push.dropped;
+        entries.append(&mut self.entries);
+        self.entries = entries;
+        self.unsaved += 1;
+    }
+
+    /// The commands that contain `query`, most recent first. An empty query matches every command.
+    pub fn search<'a>(&'a self, query: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.entries.iter().rev().map(|e| e.as_str()).filter(move |e| e.contains(query))
+    }
+}
+
+/// Whether `line` runs one of the `SECRET_COMMANDS`. Scripted lines can run several commands, so every
+/// command on the line is checked.
+fn carries_secret(line: &str) -> bool {
+    let words: Vec<String> = line
+        .split(|c: char| c.is_whitespace() || c == ';' || c == '&' || c == '|')
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect();
+    SECRET_COMMANDS
+        .iter()
+        .any(|cmd| words.windows(cmd.len()).any(|w| w.iter().zip(cmd.iter()).all(|(a, b)| a == b)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_deduplicates_and_trims() {
+        let mut history = History::new(None);
+        for cmd in ["ver xous", "net ping 1.1.1.1", "ver xous", "", "echo hi\n"] {
+            history.push(cmd, 3);
+        }
+        assert_eq!(history.search("").collect::<Vec<_>>(), vec!["echo hi", "ver xous", "net ping 1.1.1.1"]);
+        history.push("wlan status", 3);
+        assert_eq!(history.search("").collect::<Vec<_>>(), vec!["wlan status", "echo hi", "ver xous"]);
+        history.pop();
+        assert_eq!(history.search("").next(), Some("echo hi"));
+    }
+
+    #[test]
+    fn pop_undoes_push() {
+        let mut history = History::new(None);
+        for cmd in ["ver xous", "net ping 1.1.1.1", "echo hi"] {
+            history.push(cmd, 3);
+        }
+        // a repeated command goes back to where it was
+        history.push("ver xous", 3);
+        history.pop();
+        assert_eq!(history.search("").collect::<Vec<_>>(), vec!["echo hi", "net ping 1.1.1.1", "ver xous"]);
+        // a new command is forgotten, and the one it pushed out comes back
+        history.push("wlan status", 3);
+        history.pop();
+        assert_eq!(history.search("").collect::<Vec<_>>(), vec!["echo hi", "net ping 1.1.1.1", "ver xous"]);
+        // only the most recent push can be undone
+        history.pop();
+        assert_eq!(history.search("").count(), 3);
+    }
+
+    #[test]
+    fn secrets_are_not_recorded() {
+        let mut history = History::new(None);
+        for cmd in [
+            "wlan setpass hunter2",
+            "WLAN  SetPass hunter2",
+            "sh wlan setssid home; wlan setpass hunter2",
+            "/set password hunter2",
+            "/set _token abcd",
+            "wlan setssid home",
+            "echo setpass",
+        ] {
+            history.push(cmd, 10);
+        }
+        assert_eq!(history.search("").collect::<Vec<_>>(), vec!["echo setpass", "wlan setssid home"]);
+        // unpicking a secret leaves the history alone
+        history.push("wlan setpass hunter2", 10);
+        history.pop();
+        assert_eq!(history.search("").count(), 2);
+    }
+
+    #[test]
+    fn saves_are_counted() {
+        let mut history = History::new(Some("shellchat".to_string()));
+        history.push("ver xous", 10);
+        // kept in memory only, so there is never anything to save
+        assert_eq!(history.unsaved(), 0);
+        history.set_persistent(true);
+        // nor until the saved history has been read
+        assert_eq!(history.unsaved(), 0);
+        history.loaded = true;
+        history.merge("net ping 1.1.1.1\nver xous", 10);
+        assert_eq!(history.search("").collect::<Vec<_>>(), vec!["ver xous", "net ping 1.1.1.1"]);
+        history.unsaved = 0;
+        history.push("echo hi", 10);
+        history.pop();
+        history.push("wlan setpass hunter2", 10);
+        assert_eq!(history.unsaved(), 2);
+        // nothing to undo after the saved history was merged in
+        let mut history = History::new(Some("shellchat".to_string()));
+        history.push("ver xous", 10);
+        history.merge("echo hi", 10);
+        history.pop();
+        assert_eq!(history.search("").collect::<Vec<_>>(), vec!["ver xous", "echo hi"]);
+    }
+
+    #[test]
+    fn search_is_newest_first() {
+        let mut history = History::new(None);
+        for cmd in ["net ping 1.1.1.1", "ver xous", "net ping 8.8.8.8"] {
+            history.push(cmd, 10);
+        }
+        assert_eq!(history.search("ping").collect::<Vec<_>>(), vec!["net ping 8.8.8.8", "net ping 1.1.1.1"]);
+        assert_eq!(history.search("zz").count(), 0);
+    }
+}
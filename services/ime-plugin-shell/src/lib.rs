@@ -1,6 +1,84 @@
 #![cfg_attr(target_os = "none", no_std)]
 
+//! Predicts whole command lines from the commands previously entered into the same app.
+//!
+//! Each app has its own history. Repeated commands are only kept once, and the number of commands kept
+//! is set in the preferences menu. As a line is typed, the predictions narrow down to the commands that
+//! contain what has been typed so far, most recent first; F1-F4 replace the line with the chosen command.
+//! Commands that carry a secret, like `wlan setpass`, are never kept.
+//!
+//! Apps can register with `register_app()` to have their history saved to the PDDB under the app's name,
+//! so it is still there after a reboot, and to have a completer. The completer is asked to complete the
+//! line as it is typed, and its completions are offered ahead of the history. A completer sees every line
+//! typed into its app, so registrations go to a server of their own that only takes as many connections
+//! as there are apps that register in the boot image.
+
 pub const SERVER_NAME_IME_PLUGIN_SHELL: &str = "_IME shell plugin_";
+/// Takes app registrations
+pub const SERVER_NAME_IME_SHELL_REGISTRY: &str = "_IME shell app registry_";
 
 // just inherit all the default from the ime_plugin_api
 pub use ime_plugin_api::*;
+use num_traits::ToPrimitive;
+use xous_ipc::{Buffer, String};
+
+/// The prediction bar shows at most this many completions
+pub const MAX_COMPLETIONS: usize = 4;
+
+/// Opcodes of the app registry. They're numbered clear of `ime_plugin_api::Opcode`.
+#[derive(Debug, num_derive::FromPrimitive, num_derive::ToPrimitive)]
+pub enum ShellOpcode {
+    /// register an app (`AppRegistration`)
+    RegisterApp = 0x1000,
+}
+
+/// Opcodes a completer server has to handle
+#[derive(Debug, num_derive::FromPrimitive, num_derive::ToPrimitive)]
+pub enum CompleterOpcode {
+    /// complete the line in a lent `Completions`
+    Complete = 0,
+}
+
+#[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct AppRegistration {
+    /// the name the app registered its UX with
+    pub app: String<64>,
+    /// the server of the completer, if the app has one
+    pub completer: Option<[u32; 4]>,
+    /// save the history of the app to the PDDB
+    pub persist_history: bool,
+}
+
+#[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct Completions {
+    /// the line typed so far
+    pub line: String<1000>,
+    /// filled in by the completer, best first. Each completion is the whole line, not just what it adds.
+    pub completions: [Option<String<1000>>; MAX_COMPLETIONS],
+}
+
+/// Registers `app` with the shell plugin. Its history is only saved to the PDDB if `persist_history` is
+/// set, so an app that is typed secrets into, other than as the arguments of the commands the plugin
+/// knows to leave out, should leave it unset.
+///
+/// If there is a `completer`, it is asked to complete the lines typed into `app`. It receives
+/// `CompleterOpcode::Complete` messages, and must answer them without waiting on anything that could
+/// wait on the IME, such as the app's own main loop. Completions that take too long are dropped.
+///
+/// Only the apps counted in by the registry can connect to it, and only at boot; only the first
+/// registration of an app is kept, so a later one can't listen in on what is typed.
+pub fn register_app(
+    xns: &xous_names::XousNames,
+    app: &str,
+    completer: Option<xous::SID>,
+    persist_history: bool,
+) -> Result<(), xous::Error> {
+    let cid = xns.request_connection_blocking(SERVER_NAME_IME_SHELL_REGISTRY)?;
+    let registration = AppRegistration {
+        app: String::from_str(app),
+        completer: completer.map(|sid| sid.to_array()),
+        persist_history,
+    };
+    let buf = Buffer::into_buf(registration).or(Err(xous::Error::InternalError))?;
+    buf.lend(cid, ShellOpcode::RegisterApp.to_u32().unwrap()).map(|_| ())
+}
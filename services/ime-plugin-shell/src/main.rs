@@ -1,14 +1,28 @@
 #![cfg_attr(target_os = "none", no_std)]
 #![cfg_attr(target_os = "none", no_main)]
 
+mod history;
+
 use std::collections::HashMap;
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
 
+use history::History;
 use ime_plugin_api::*;
+use ime_plugin_shell::{AppRegistration, CompleterOpcode, Completions, ShellOpcode};
 use log::{error, info};
-use num_traits::FromPrimitive;
+use num_traits::{FromPrimitive, ToPrimitive};
 use xous::msg_scalar_unpack;
 use xous_ipc::{Buffer, String};
 
+/// How long typing waits on a completer before the predictions fall back to the history alone
+const COMPLETER_TIMEOUT_MS: u64 = 100;
+/// Saved histories are written out after this many changes, and when the app loses the IME, rather than
+/// on every command, to spare the flash
+const SAVE_BATCH: usize = 10;
+
 fn main() -> ! {
     log_server::init_wait().unwrap();
     log::set_max_level(log::LevelFilter::Info);
@@ -21,22 +35,25 @@ fn main() -> ! {
         .expect("can't register server");
     log::trace!("registered with NS -- {:?}", ime_sh_sid);
 
-    let mut history_store: HashMap<[u32; 4], Vec<String<64>>> = HashMap::new();
-    let mut active_history: Option<([u32; 4], Vec<String<64>>)> = None;
-    let history_max = 4;
-
-    /*
-        use core::fmt::Write as CoreWriter;
-        let mut test1: String::<64> = String::new();
-        write!(test1, "This〰should overflow the box").unwrap();
-        history.push(test1);
-        let mut test2: String::<64> = String::new();
-        write!(test2, "Another string too long").unwrap();
-        history.push(test2);
-        let mut test3: String::<64> = String::new();
-        write!(test3, "未雨绸缪").unwrap();
-        history.push(test3);
-    */
+    let pddb = pddb::Pddb::new();
+    let prefs = userprefs::Manager::new();
+    // histories of the apps that aren't in the foreground, by API token
+    let mut history_store: HashMap<[u32; 4], History> = HashMap::new();
+    let mut active_history: Option<([u32; 4], History)> = None;
+    // what has been typed so far on the current line; it narrows the predictions down to the commands
+    // that contain it, so a long command can be found again from any part of it
+    let mut query = std::string::String::new();
+    // the apps that registered, by app name
+    let apps: Arc<Mutex<HashMap<std::string::String, Registration>>> = Arc::new(Mutex::new(HashMap::new()));
+    start_registry(&xns, apps.clone());
+    let persists = |history: &History| {
+        history
+            .app()
+            .and_then(|app| apps.lock().unwrap().get(app).map(|r| r.persist_history))
+            .unwrap_or(false)
+    };
+    // what the completer of the active app made of the query
+    let mut completions: Vec<std::string::String> = Vec::new();
 
     let mytriggers = PredictionTriggers { newline: true, punctuation: false, whitespace: false };
 
@@ -59,9 +76,16 @@ fn main() -> ! {
                         }
                     } else {
                         let new_token = xous::create_server_id().unwrap().to_array();
-                        active_history = Some((new_token, Vec::new()));
+                        let app = ret.app.as_ref().and_then(|a| a.as_str().ok()).map(|a| a.to_string());
+                        active_history = Some((new_token, History::new(app)));
                         ret.token = Some(new_token);
                     }
+                    if let Some((_token, history)) = &mut active_history {
+                        history.set_persistent(persists(history));
+                        history.load(&pddb, history_len(&pddb, &prefs));
+                    }
+                    query.clear();
+                    completions.clear();
                 } else {
                     ret.token = None;
                     log::warn!("attempt to acquire lock on a predictor that was already locked");
@@ -70,9 +94,11 @@ fn main() -> ! {
             }
             Some(Opcode::Release) => msg_scalar_unpack!(msg, t0, t1, t2, t3, {
                 let token = [t0 as u32, t1 as u32, t2 as u32, t3 as u32];
-                if let Some((t, h)) = active_history.take() {
+                if let Some((t, mut h)) = active_history.take() {
                     if t == token {
+                        h.save(&pddb);
                         history_store.insert(token, h);
+                        completions.clear();
                     } else {
                         log::warn!("Release had inconsistent api token!");
                     }
@@ -81,75 +107,76 @@ fn main() -> ! {
                 }
             }),
             Some(Opcode::Input) => {
-                // shell does nothing with the input, it only keeps track of
-                // the picked results
+                let buffer = unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                let s = buffer.as_flat::<String<4000>, _>().unwrap();
+                query.clear();
+                query.push_str(s.as_str());
+                if let Some((_token, history)) = &mut active_history {
+                    // the PDDB may have been mounted, or the app registered, since the predictor was acquired
+                    if !history.is_loaded() {
+                        history.set_persistent(persists(history));
+                        history.load(&pddb, history_len(&pddb, &prefs));
+                    }
+                    completions = history
+                        .app()
+                        .and_then(|app| {
+                            apps.lock()
+                                .unwrap()
+                                .get_mut(app)
+                                .and_then(|r| r.completer.as_mut())
+                                .map(|c| c.complete(&query))
+                        })
+                        .unwrap_or_default();
+                }
             }
             Some(Opcode::Picked) => {
                 if let Some((_token, history)) = &mut active_history {
                     let buffer = unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
                     let s = buffer.as_flat::<String<4000>, _>().unwrap();
-                    // the API allows for large picked feedback, but this implementation only keeps the first
-                    // 64 characters
-                    let mut local_s: String<64> = String::new();
-                    use core::fmt::Write;
-                    write!(local_s, "{}", s.as_str()).expect("overflowed history variable");
                     log::trace!("storing history value | {}", s.as_str());
-                    if history.len() == history_max {
-                        history.remove(0);
+                    let max_len = history_len(&pddb, &prefs);
+                    history.load(&pddb, max_len);
+                    history.push(s.as_str(), max_len);
+                    if history.unsaved() >= SAVE_BATCH {
+                        history.save(&pddb);
                     }
-                    history.push(local_s);
-                    log::trace!("history has length {}", history.len());
                 } else {
                     log::warn!("predictor not acquired, ignoring");
                 }
+                query.clear();
+                completions.clear();
             }
             Some(Opcode::Prediction) => {
                 let mut buffer =
                     unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
                 let mut prediction: Prediction = buffer.to_original::<Prediction, _>().unwrap();
-                if let Some((token, history)) = &mut active_history {
+                prediction.valid = false;
+                if let Some((token, history)) = &active_history {
                     if *token == prediction.api_token {
                         log::trace!("querying prediction index {}", prediction.index);
-                        log::trace!("{:?}", prediction);
-                        if history.len() > 0 && ((prediction.index as usize) < history.len()) {
-                            let mut index = prediction.index;
-                            if index >= history.len() as u32 {
-                                index = history.len() as u32 - 1;
-                            }
-                            let mut i = 1;
-                            for &s in history.iter() {
-                                // iterator is from oldest to newest, so do some math to go from newest to
-                                // oldest TIL: there is a .rev() feature in
-                                // iterators. Next time maybe use that instead.
-                                if (history.len() as u32 - i) == index {
-                                    // decompose the string into a character-by-character sequence
-                                    // and then stuff byte-by-byte, as fits, into the return array
-                                    prediction.string.clear();
-                                    for ch in s.as_str().unwrap().chars() {
-                                        if let Ok(_) = prediction.string.push(ch) {
-                                            // it's ok, carry on.
-                                        } else {
-                                            // we ran out of space, stop copying
-                                            break;
-                                        }
-                                    }
-                                    prediction.valid = true;
+                        // completions first, then the commands from the history that they don't repeat
+                        let mut candidates = completions.iter().map(|c| c.as_str()).chain(
+                            history
+                                .search(&query)
+                                .filter(|entry| !completions.iter().any(|c| c.trim_end() == *entry)),
+                        );
+                        if let Some(entry) = candidates.nth(prediction.index as usize) {
+                            prediction.string.clear();
+                            for ch in entry.chars() {
+                                if prediction.string.push(ch).is_err() {
+                                    // we ran out of space, stop copying
                                     break;
                                 }
-                                i = i + 1;
                             }
+                            prediction.valid = true;
                         } else {
-                            // there is no history
-                            prediction.valid = false;
                             log::trace!("no prediction found");
                         }
                         log::trace!("returning index {} string {:?}", prediction.index, prediction.string);
                     } else {
-                        prediction.valid = false;
                         log::warn!("api token mismatch, ignoring");
                     }
                 } else {
-                    prediction.valid = false;
                     log::warn!("predictor not acquired, ignoring");
                 }
                 // pack our data back into the buffer to return
@@ -157,12 +184,7 @@ fn main() -> ! {
             }
             Some(Opcode::Unpick) => {
                 if let Some((_token, history)) = &mut active_history {
-                    if history.len() == 1 {
-                        let _ = history.remove(0);
-                    } else if history.len() > 1 {
-                        let _ = history.pop(); // discard the last entry
-                    }
-                    // in case of 0 length, do nothing
+                    history.pop();
                 } else {
                     log::warn!("predictor not acquired, ignoring");
                 }
@@ -172,7 +194,8 @@ fn main() -> ! {
                     .expect("couldn't return GetPredictionTriggers");
             }
             Some(Opcode::Quit) => {
-                if active_history.is_some() {
+                if let Some((_token, history)) = &mut active_history {
+                    history.save(&pddb);
                     error!("received quit, goodbye!");
                     break;
                 }
@@ -188,3 +211,138 @@ fn main() -> ! {
     log::trace!("quitting");
     xous::terminate_process(0)
 }
+
+/*
+    A registered app has its history saved to the PDDB and its completer told every line typed into it,
+    so the registry only takes one connection for each app in the boot image that registers, and they
+    claim them as they start:
+      - shellchat (saved history, command and argument completion)
+*/
+const REGISTRARS: u32 = 1;
+
+/// What an app registered
+struct Registration {
+    completer: Option<Completer>,
+    persist_history: bool,
+}
+
+/// Takes app registrations on a server of its own, so the connections to it can be limited
+fn start_registry(xns: &xous_names::XousNames, apps: Arc<Mutex<HashMap<std::string::String, Registration>>>) {
+    let sid = xns
+        .register_name(ime_plugin_shell::SERVER_NAME_IME_SHELL_REGISTRY, Some(REGISTRARS))
+        .expect("can't register server");
+    thread::spawn(move || {
+        loop {
+            let msg = xous::receive_message(sid).unwrap();
+            match FromPrimitive::from_usize(msg.body.id()) {
+                Some(ShellOpcode::RegisterApp) => {
+                    let buffer = unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                    let registration = buffer.to_original::<AppRegistration, _>().unwrap();
+                    let app = registration.app.as_str().unwrap_or_default().to_string();
+                    let mut apps = apps.lock().unwrap();
+                    if apps.contains_key(&app) {
+                        log::warn!("{} is already registered, ignoring", app);
+                        continue;
+                    }
+                    let completer =
+                        match registration.completer.map(|sid| xous::connect(xous::SID::from_array(sid))) {
+                            Some(Ok(cid)) => Some(Completer::new(cid)),
+                            Some(Err(e)) => {
+                                log::warn!("couldn't connect to the completer of {}: {:?}", app, e);
+                                None
+                            }
+                            None => None,
+                        };
+                    apps.insert(
+                        app,
+                        Registration { completer, persist_history: registration.persist_history },
+                    );
+                }
+                None => error!("unknown registry Opcode"),
+            }
+        }
+    });
+}
+
+/// The completer of an app. Lines are lent to it from a thread of its own, so a completer that is slow to
+/// answer, say while it waits on the network or the PDDB, can't hold up typing.
+struct Completer {
+    requests: Sender<std::string::String>,
+    responses: Receiver<Vec<std::string::String>>,
+    /// a line was sent that hasn't been answered yet
+    busy: bool,
+}
+impl Completer {
+    fn new(cid: xous::CID) -> Self {
+        let (requests, lines) = channel::<std::string::String>();
+        let (answers, responses) = channel();
+        thread::spawn(move || {
+            for line in lines {
+                if answers.send(complete(cid, &line)).is_err() {
+                    break;
+                }
+            }
+        });
+        Completer { requests, responses, busy: false }
+    }
+
+    /// The completions of `line`, or none if the completer doesn't answer in time
+    fn complete(&mut self, line: &str) -> Vec<std::string::String> {
+        if self.busy {
+            // a late answer is for an earlier line, so it's only good for knowing the completer is free
+            match self.responses.try_recv() {
+                Ok(_) => self.busy = false,
+                Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => return Vec::new(),
+            }
+        }
+        if self.requests.send(line.to_string()).is_err() {
+            return Vec::new();
+        }
+        match self.responses.recv_timeout(Duration::from_millis(COMPLETER_TIMEOUT_MS)) {
+            Ok(completions) => completions,
+            Err(_) => {
+                log::warn!("completer didn't answer in time, predicting from the history only");
+                self.busy = true;
+                Vec::new()
+            }
+        }
+    }
+}
+
+/// Asks a completer to complete `line`. Completions are whole lines.
+fn complete(cid: xous::CID, line: &str) -> Vec<std::string::String> {
+    if line.is_empty() {
+        return Vec::new();
+    }
+    let request = Completions { line: String::from_str(line), completions: Default::default() };
+    let mut buf = match Buffer::into_buf(request) {
+        Ok(buf) => buf,
+        Err(_) => return Vec::new(),
+    };
+    if let Err(e) = buf.lend_mut(cid, CompleterOpcode::Complete.to_u32().unwrap()) {
+        log::warn!("completer failed: {:?}", e);
+        return Vec::new();
+    }
+    let response = buf.to_original::<Completions, _>().unwrap();
+    response
+        .completions
+        .iter()
+        .flatten()
+        .filter_map(|c| c.as_str().ok())
+        .filter(|c| *c != line)
+        .map(|c| c.to_string())
+        .collect()
+}
+
+/// The number of commands to keep, as set in the preferences. Until the PDDB is mounted, the
+/// preferences can't be read, so the default applies.
+fn history_len(pddb: &pddb::Pddb, prefs: &userprefs::Manager) -> usize {
+    let len = if pddb.is_mounted_nonblocking() {
+        prefs
+            .shell_history_len_or_value(userprefs::DEFAULT_SHELL_HISTORY_LEN)
+            .unwrap_or(userprefs::DEFAULT_SHELL_HISTORY_LEN)
+    } else {
+        userprefs::DEFAULT_SHELL_HISTORY_LEN
+    };
+    len.max(1) as usize
+}
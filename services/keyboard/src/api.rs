@@ -1,6 +1,13 @@
 use std::fmt::Display;
 
 pub const SERVER_NAME_KBD: &str = "_Matrix keyboard driver_";
+/// Emitted when the menu key is held down. The GAM interprets it as a request to lock the screen.
+pub const LOCK_SCREEN_KEY: char = '🔒';
+/// Emitted when F4 is held down. In a split view, the GAM moves input focus to the other pane.
+pub const SWITCH_PANE_KEY: char = '⇅';
+/// Emitted when the period key is held down. The keyboard server consumes it: the next two keys are
+/// combined into one character, e.g. compose, `o`, `"` gives `ö`.
+pub const COMPOSE_KEY: char = '⎄';
 
 #[derive(Debug, Default, Copy, Clone)]
 pub struct ScanCode {
@@ -16,13 +23,15 @@ pub struct ScanCode {
 
 /// Maintainer note: there is a "BackupKeyboardLayout" serializer inside
 /// root-keys/api.rs that needs to be updated when this changes.
-#[derive(Debug, Copy, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 pub enum KeyMap {
     Qwerty,
     Azerty,
     Qwertz,
     Dvorak,
     Braille,
+    /// A user-defined map, installed with `Keyboard::set_custom_keymap()`
+    Custom,
     Undefined,
 }
 impl From<usize> for KeyMap {
@@ -33,6 +42,7 @@ impl From<usize> for KeyMap {
             2 => KeyMap::Qwertz,
             3 => KeyMap::Dvorak,
             4 => KeyMap::Braille,
+            5 => KeyMap::Custom,
             _ => KeyMap::Qwerty,
         }
     }
@@ -46,11 +56,16 @@ impl From<KeyMap> for usize {
             KeyMap::Qwertz => 2,
             KeyMap::Dvorak => 3,
             KeyMap::Braille => 4,
+            KeyMap::Custom => 5,
             KeyMap::Undefined => 255,
         }
     }
 }
 
+/// The bit for `map` in a mask of keymaps that have dead keys turned on. The keyboard server keeps such a
+/// mask, and so does the `dead_keys` device preference.
+pub fn dead_key_bit(map: KeyMap) -> u32 { 1u32.checked_shl(usize::from(map) as u32).unwrap_or(0) }
+
 impl Display for KeyMap {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
@@ -59,11 +74,89 @@ impl Display for KeyMap {
             Self::Qwertz => write!(f, "QWERTZ"),
             Self::Dvorak => write!(f, "Dvorak"),
             Self::Braille => write!(f, "Braille"),
+            Self::Custom => write!(f, "Custom"),
             Self::Undefined => write!(f, "Undefined"),
         }
     }
 }
 
+/// Dictionary in the PDDB that holds user-installed keymaps, one key per map. The key name is the map's
+/// name, and the contents are the text format described in `CustomKeyMap::parse()`.
+pub const CUSTOM_KEYMAP_DICT: &str = "kbd.keymaps";
+/// Dimensions of the key matrix, as addressed by `RowCol`
+pub const KEYMAP_ROWS: usize = 9;
+pub const KEYMAP_COLS: usize = 10;
+/// Maximum number of dead-key compositions in a custom keymap
+pub const MAX_DEAD_KEY_ENTRIES: usize = 64;
+/// Marks a layer of a custom keymap entry as having no character at all. Zero means "use the base map".
+pub const KEYMAP_NO_CHAR: u32 = u32::MAX;
+
+/// A keymap that is defined by data rather than compiled in. Every key that isn't overridden keeps
+/// the meaning it has in the `base` map; the base map is also what the keyboard falls back to before
+/// the PDDB is mounted and the custom map can be loaded.
+#[derive(Debug, Copy, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct CustomKeyMap {
+    pub name: xous_ipc::String<32>,
+    pub base: KeyMap,
+    /// `[key, shift, hold, alt]` code points for each key, indexed by row and column. 0 defers to the
+    /// base map, and `KEYMAP_NO_CHAR` removes the character.
+    pub keys: [[[u32; 4]; KEYMAP_COLS]; KEYMAP_ROWS],
+    /// `[dead key, next key, composed]` code points. Unused entries are all 0.
+    pub dead: [[u32; 3]; MAX_DEAD_KEY_ENTRIES],
+}
+impl CustomKeyMap {
+    pub fn new(name: &str, base: KeyMap) -> Self {
+        CustomKeyMap {
+            name: xous_ipc::String::from_str(name),
+            base,
+            keys: [[[0; 4]; KEYMAP_COLS]; KEYMAP_ROWS],
+            dead: [[0; 3]; MAX_DEAD_KEY_ENTRIES],
+        }
+    }
+
+    pub fn is_dead_key(&self, c: char) -> bool {
+        self.dead.iter().any(|entry| entry[0] != 0 && entry[0] == c as u32)
+    }
+
+    pub fn compose(&self, dead: char, next: char) -> Option<char> {
+        self.dead
+            .iter()
+            .find(|entry| entry[0] == dead as u32 && entry[1] == next as u32)
+            .and_then(|entry| core::char::from_u32(entry[2]))
+    }
+
+    /// Runs a sequence of keys through the dead-key table. `pending` holds a dead key that was typed
+    /// but not yet resolved, and is carried from one call to the next.
+    ///
+    /// A dead key followed by a key it composes with produces the composed character. Following it with
+    /// a space or with itself produces the dead key's own character, and backspace cancels it. Any other
+    /// key is emitted after the dead key's own character.
+    pub fn compose_keys(&self, pending: &mut Option<char>, keys: &[char]) -> Vec<char> {
+        let mut composed = Vec::with_capacity(keys.len() + 1);
+        for &k in keys {
+            if let Some(dead) = pending.take() {
+                if let Some(c) = self.compose(dead, k) {
+                    composed.push(c);
+                } else if k == ' ' || k == dead {
+                    composed.push(dead);
+                } else if k != '\u{8}' {
+                    composed.push(dead);
+                    if self.is_dead_key(k) {
+                        *pending = Some(k);
+                    } else {
+                        composed.push(k);
+                    }
+                }
+            } else if self.is_dead_key(k) {
+                *pending = Some(k);
+            } else {
+                composed.push(k);
+            }
+        }
+        composed
+    }
+}
+
 // Opcodes are pinned down to allow for unsafe FFI extraction of key hits
 #[derive(Debug, num_derive::FromPrimitive, num_derive::ToPrimitive)]
 pub(crate) enum Opcode {
@@ -101,6 +194,12 @@ pub(crate) enum Opcode {
 
     /// Suspend/resume callback
     SuspendResume = 10,
+
+    /// install a user-defined keymap and select it
+    SetCustomKeyMap = 13, //(CustomKeyMap)
+
+    /// turn the built-in dead keys on or off for a keymap
+    SetDeadKeys = 14, //(KeyMap, bool)
 }
 
 // this structure is used to register a keyboard listener. Currently, we only accept
@@ -0,0 +1,163 @@
+#![allow(dead_code)] // because hosted mode doesn't track keys
+use crate::{CustomKeyMap, COMPOSE_KEY};
+
+const BACKSPACE: char = '\u{8}';
+
+/// Accents that act as dead keys on the built-in maps, for maps that have dead keys turned on
+pub(crate) const BUILTIN_DEAD_KEYS: [char; 5] = ['\'', '`', '^', '"', '~'];
+
+/// `(accent or first key, letter, composed)`. Lookups try both orders, so `'` `e` and `e` `'` are the same.
+#[rustfmt::skip] // this is a lookup table. Allow wide columns.
+const COMPOSE_TABLE: &[(char, char, char)] = &[
+    ('\'', 'a', 'á'), ('\'', 'e', 'é'), ('\'', 'i', 'í'), ('\'', 'o', 'ó'), ('\'', 'u', 'ú'), ('\'', 'y', 'ý'),
+    ('\'', 'A', 'Á'), ('\'', 'E', 'É'), ('\'', 'I', 'Í'), ('\'', 'O', 'Ó'), ('\'', 'U', 'Ú'), ('\'', 'Y', 'Ý'),
+    ('\'', 'c', 'ć'), ('\'', 'n', 'ń'), ('\'', 's', 'ś'), ('\'', 'z', 'ź'),
+    ('\'', 'C', 'Ć'), ('\'', 'N', 'Ń'), ('\'', 'S', 'Ś'), ('\'', 'Z', 'Ź'),
+    ('`', 'a', 'à'), ('`', 'e', 'è'), ('`', 'i', 'ì'), ('`', 'o', 'ò'), ('`', 'u', 'ù'),
+    ('`', 'A', 'À'), ('`', 'E', 'È'), ('`', 'I', 'Ì'), ('`', 'O', 'Ò'), ('`', 'U', 'Ù'),
+    ('^', 'a', 'â'), ('^', 'e', 'ê'), ('^', 'i', 'î'), ('^', 'o', 'ô'), ('^', 'u', 'û'),
+    ('^', 'A', 'Â'), ('^', 'E', 'Ê'), ('^', 'I', 'Î'), ('^', 'O', 'Ô'), ('^', 'U', 'Û'),
+    ('"', 'a', 'ä'), ('"', 'e', 'ë'), ('"', 'i', 'ï'), ('"', 'o', 'ö'), ('"', 'u', 'ü'), ('"', 'y', 'ÿ'),
+    ('"', 'A', 'Ä'), ('"', 'E', 'Ë'), ('"', 'I', 'Ï'), ('"', 'O', 'Ö'), ('"', 'U', 'Ü'),
+    ('~', 'a', 'ã'), ('~', 'n', 'ñ'), ('~', 'o', 'õ'),
+    ('~', 'A', 'Ã'), ('~', 'N', 'Ñ'), ('~', 'O', 'Õ'),
+    // the rest are only reachable with the compose key
+    (',', 'c', 'ç'), (',', 'C', 'Ç'),
+    ('o', 'a', 'å'), ('o', 'A', 'Å'),
+    ('a', 'e', 'æ'), ('A', 'E', 'Æ'),
+    ('/', 'o', 'ø'), ('/', 'O', 'Ø'),
+    ('s', 's', 'ß'),
+    ('?', '?', '¿'), ('!', '!', '¡'),
+    ('=', 'e', '€'), ('-', 'l', '£'), ('=', 'y', '¥'),
+];
+
+/// Looks up a composition in the built-in table
+pub(crate) fn compose_builtin(first: char, second: char) -> Option<char> {
+    COMPOSE_TABLE
+        .iter()
+        .find(|&&(a, b, _)| (a == first && b == second) || (a == second && b == first))
+        .map(|&(_, _, composed)| composed)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Pending {
+    /// a dead key was typed
+    Dead(char),
+    /// the compose key was typed
+    Compose,
+    /// the compose key and one more key were typed
+    ComposeWith(char),
+}
+
+/// Tracks dead keys and compose sequences across key events.
+///
+/// A dead key followed by a key it composes with produces the composed character. Following it with
+/// a space or with itself produces the dead key's own character, and backspace cancels it. Any other
+/// key is emitted after the dead key's own character.
+///
+/// The compose key is followed by two keys, in either order, that make up a composition: `COMPOSE_KEY`
+/// `o` `"` produces `ö`. Backspace steps back through the sequence, and keys that don't compose are
+/// emitted as typed. The compose key itself never is: typed again, it starts a new sequence.
+#[derive(Debug, Default)]
+pub(crate) struct Composer {
+    pending: Option<Pending>,
+}
+impl Composer {
+    pub fn new() -> Self { Composer { pending: None } }
+
+    pub fn reset(&mut self) { self.pending = None; }
+
+    /// Runs a sequence of keys through the dead-key and compose state. `custom` supplies the dead keys
+    /// and compositions of a user-defined map; `dead_keys` turns on the built-in dead keys.
+    pub fn process(&mut self, keys: &[char], custom: Option<&CustomKeyMap>, dead_keys: bool) -> Vec<char> {
+        let mut out = Vec::with_capacity(keys.len() + 2);
+        for &k in keys {
+            match self.pending.take() {
+                None => self.start(k, custom, dead_keys, &mut out),
+                Some(Pending::Dead(dead)) => {
+                    if let Some(c) = compose(dead, k, custom) {
+                        out.push(c);
+                    } else if k == ' ' || k == dead {
+                        out.push(dead);
+                    } else if k != BACKSPACE {
+                        out.push(dead);
+                        self.start(k, custom, dead_keys, &mut out);
+                    }
+                }
+                Some(Pending::Compose) => {
+                    if k != BACKSPACE && k != COMPOSE_KEY {
+                        self.pending = Some(Pending::ComposeWith(k));
+                    }
+                }
+                Some(Pending::ComposeWith(first)) => {
+                    if k == BACKSPACE {
+                        self.pending = Some(Pending::Compose);
+                    } else if k == COMPOSE_KEY {
+                        // the compose key never composes: give up on this sequence and start over
+                        out.push(first);
+                        self.pending = Some(Pending::Compose);
+                    } else if let Some(c) = compose(first, k, custom) {
+                        out.push(c);
+                    } else {
+                        out.push(first);
+                        out.push(k);
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    fn start(&mut self, k: char, custom: Option<&CustomKeyMap>, dead_keys: bool, out: &mut Vec<char>) {
+        if k == COMPOSE_KEY {
+            self.pending = Some(Pending::Compose);
+        } else if custom.map_or(false, |map| map.is_dead_key(k))
+            || (dead_keys && BUILTIN_DEAD_KEYS.contains(&k))
+        {
+            self.pending = Some(Pending::Dead(k));
+        } else {
+            out.push(k);
+        }
+    }
+}
+
+fn compose(first: char, second: char, custom: Option<&CustomKeyMap>) -> Option<char> {
+    custom.and_then(|map| map.compose(first, second)).or_else(|| compose_builtin(first, second))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::KeyMap;
+
+    #[test]
+    fn builtin_dead_keys() {
+        let mut composer = Composer::new();
+        assert_eq!(composer.process(&['\'', 'e'], None, false), vec!['\'', 'e']);
+        assert_eq!(composer.process(&['\''], None, true), Vec::<char>::new());
+        assert_eq!(composer.process(&['e', '~', 'n'], None, true), vec!['é', 'ñ']);
+        assert_eq!(composer.process(&['^', ' ', '"', 'x', '`', '\u{8}'], None, true), vec!['^', '"', 'x']);
+    }
+
+    #[test]
+    fn compose_sequences() {
+        let mut composer = Composer::new();
+        assert_eq!(composer.process(&[COMPOSE_KEY, 'o', '"'], None, false), vec!['ö']);
+        assert_eq!(composer.process(&[COMPOSE_KEY, 's', 's'], None, false), vec!['ß']);
+        assert_eq!(composer.process(&[COMPOSE_KEY, 'x', '\u{8}', 'e', '\''], None, false), vec!['é']);
+        assert_eq!(composer.process(&[COMPOSE_KEY, 'q', 'z'], None, false), vec!['q', 'z']);
+        // a second compose key gives up on the first key and starts over
+        assert_eq!(composer.process(&[COMPOSE_KEY, 'q', COMPOSE_KEY], None, false), vec!['q']);
+        assert_eq!(composer.process(&['o', '"'], None, false), vec!['ö']);
+        assert_eq!(composer.process(&[COMPOSE_KEY, COMPOSE_KEY, 'x'], None, false), vec!['x']);
+    }
+
+    #[test]
+    fn custom_dead_keys() {
+        let mut map = CustomKeyMap::new("es", KeyMap::Qwerty);
+        map.dead[0] = ['´' as u32, 'e' as u32, 'é' as u32];
+        let mut composer = Composer::new();
+        assert_eq!(composer.process(&['´', 'e', '\'', 'e'], Some(&map), false), vec!['é', '\'', 'e']);
+        assert_eq!(composer.process(&['´', '´'], Some(&map), false), vec!['´']);
+    }
+}
@@ -0,0 +1,191 @@
+use std::fmt::Display;
+
+use crate::mappings::map_builtin;
+use crate::*;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyMapError {
+    /// 1-based line number of the offending line in the keymap text
+    pub line: usize,
+    pub reason: &'static str,
+}
+
+impl Display for KeyMapError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+
+impl CustomKeyMap {
+    /// Builds a keymap from its text description, as stored in the `CUSTOM_KEYMAP_DICT` PDDB dictionary.
+    ///
+    /// The text is a list of directives, one per line; `#` starts a comment, so a literal `#` is written
+    /// as `U+0023`.
+    ///
+    /// ```text
+    /// base qwerty
+    /// # key  base shift hold alt
+    /// key !  ñ    Ñ     -    -
+    /// key 3,4 ´   ¨
+    /// dead ´ a=á e=é i=í o=ó u=ú
+    /// dead ¨ u=ü U=Ü
+    /// ```
+    ///
+    /// * `base` names the compiled-in map (`qwerty`, `azerty`, `qwertz` or `dvorak`) that supplies every key
+    ///   not mentioned in the file. It must come before any `key` line, and defaults to `qwerty`.
+    /// * `key` overrides the characters of one physical key. The key is named either by the character it
+    ///   produces unshifted on the base map, or by its `row,col` in the key matrix. Up to four layers follow:
+    ///   base, shift, hold and alt. Layers that are left out or given as `-` keep the base map's character,
+    ///   and `none` removes it.
+    /// * `dead` makes a character a dead key, followed by the `next=composed` pairs it produces. The same
+    ///   pairs are also available through the compose key.
+    ///
+    /// Characters can be written literally, or as `U+XXXX` for ones that are awkward to type, such as
+    /// `U+0020` for space. The alt layer is reached with the orange shift key; on custom maps the yellow
+    /// key is always the (blue) shift.
+    pub fn parse(name: &str, text: &str) -> Result<CustomKeyMap, KeyMapError> {
+        let mut map = CustomKeyMap::new(name, KeyMap::Qwerty);
+        let mut seen_key = false;
+        let mut dead_count = 0;
+        for (index, raw_line) in text.lines().enumerate() {
+            let err = |reason| KeyMapError { line: index + 1, reason };
+            let line = raw_line.split('#').next().unwrap_or("").trim();
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                None => continue,
+                Some("base") => {
+                    if seen_key {
+                        return Err(err("base must come before any key"));
+                    }
+                    map.base = match tokens.next().map(|t| t.to_ascii_lowercase()).as_deref() {
+                        Some("qwerty") => KeyMap::Qwerty,
+                        Some("azerty") => KeyMap::Azerty,
+                        Some("qwertz") => KeyMap::Qwertz,
+                        Some("dvorak") => KeyMap::Dvorak,
+                        _ => return Err(err("unknown base map")),
+                    };
+                }
+                Some("key") => {
+                    seen_key = true;
+                    let rc = match tokens.next() {
+                        Some(key) => find_key(map.base, key).ok_or(err("no such key on the base map"))?,
+                        None => return Err(err("missing key")),
+                    };
+                    let layers = &mut map.keys[rc.r as usize][rc.c as usize];
+                    for (layer, token) in layers.iter_mut().zip(&mut tokens) {
+                        *layer = match token {
+                            "-" => 0,
+                            "none" => KEYMAP_NO_CHAR,
+                            _ => parse_char(token).ok_or(err("expected a single character"))? as u32,
+                        };
+                    }
+                    if tokens.next().is_some() {
+                        return Err(err("a key has at most four layers"));
+                    }
+                }
+                Some("dead") => {
+                    let dead =
+                        tokens.next().and_then(parse_char).ok_or(err("expected a dead key character"))?;
+                    for pair in tokens {
+                        let mut parts = pair.splitn(2, '=');
+                        let (next, composed) =
+                            match (parts.next().and_then(parse_char), parts.next().and_then(parse_char)) {
+                                (Some(next), Some(composed)) => (next, composed),
+                                _ => return Err(err("expected next=composed")),
+                            };
+                        if dead_count >= MAX_DEAD_KEY_ENTRIES {
+                            return Err(err("too many dead-key compositions"));
+                        }
+                        map.dead[dead_count] = [dead as u32, next as u32, composed as u32];
+                        dead_count += 1;
+                    }
+                }
+                Some(_) => return Err(err("unknown directive")),
+            }
+        }
+        Ok(map)
+    }
+}
+
+/// A single character, or a `U+XXXX` escape
+fn parse_char(token: &str) -> Option<char> {
+    if let Some(hex) = token.strip_prefix("U+") {
+        return u32::from_str_radix(hex, 16).ok().and_then(core::char::from_u32);
+    }
+    let mut chars = token.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
+}
+
+/// Finds a key by `row,col`, or by the character it produces unshifted on `base`
+fn find_key(base: KeyMap, token: &str) -> Option<RowCol> {
+    if let Some((r, c)) = token.split_once(',') {
+        if let (Ok(r), Ok(c)) = (r.parse::<u8>(), c.parse::<u8>()) {
+            return if (r as usize) < KEYMAP_ROWS && (c as usize) < KEYMAP_COLS {
+                Some(RowCol::new(r, c))
+            } else {
+                None
+            };
+        }
+    }
+    let key = parse_char(token)?;
+    for r in 0..KEYMAP_ROWS as u8 {
+        for c in 0..KEYMAP_COLS as u8 {
+            if map_builtin(base, RowCol::new(r, c)).key == Some(key) {
+                return Some(RowCol::new(r, c));
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPANISH: &str = "
+        # Spanish on a QWERTY keycap set
+        base qwerty
+        key !   ñ Ñ      # the ! key becomes ñ
+        key 3,4 ´ ¨ - none
+        dead ´ a=á e=é
+        dead ¨ u=ü
+    ";
+
+    #[test]
+    fn parse_overrides_and_dead_keys() {
+        let map = CustomKeyMap::parse("es", SPANISH).unwrap();
+        assert_eq!(map.base, KeyMap::Qwerty);
+        assert_eq!(map.keys[3][0], ['ñ' as u32, 'Ñ' as u32, 0, 0]);
+        assert_eq!(map.keys[3][4], ['´' as u32, '¨' as u32, 0, KEYMAP_NO_CHAR]);
+        assert!(map.is_dead_key('´'));
+        assert!(!map.is_dead_key('e'));
+        assert_eq!(map.compose('´', 'e'), Some('é'));
+        assert_eq!(map.compose('¨', 'e'), None);
+    }
+
+    #[test]
+    fn compose_keys_resolves_dead_keys() {
+        let map = CustomKeyMap::parse("es", SPANISH).unwrap();
+        let mut pending = None;
+        assert_eq!(map.compose_keys(&mut pending, &['´']), Vec::<char>::new());
+        assert_eq!(pending, Some('´'));
+        assert_eq!(map.compose_keys(&mut pending, &['a', 'b']), vec!['á', 'b']);
+        assert_eq!(map.compose_keys(&mut pending, &['´', ' ', '¨', 'x']), vec!['´', '¨', 'x']);
+        assert_eq!(map.compose_keys(&mut pending, &['¨', '\u{8}']), Vec::<char>::new());
+        assert_eq!(pending, None);
+    }
+
+    #[test]
+    fn parse_errors_report_line() {
+        assert_eq!(
+            CustomKeyMap::parse("bad", "key q a\nbase azerty").unwrap_err(),
+            KeyMapError { line: 2, reason: "base must come before any key" }
+        );
+        assert_eq!(CustomKeyMap::parse("bad", "\nkey q ab").unwrap_err().line, 2);
+        assert!(CustomKeyMap::parse("bad", "key 9,0 a").is_err());
+        assert!(CustomKeyMap::parse("bad", "dead ´ a").is_err());
+    }
+}
@@ -3,6 +3,9 @@
 use num_traits::*;
 
 pub mod api;
+mod keymap;
+pub use keymap::*;
+mod mappings;
 
 pub use api::*;
 use xous::{send_message, Message};
@@ -72,6 +75,30 @@ impl Keyboard {
         }
     }
 
+    /// Installs a user-defined keymap and selects it. The keyboard server keeps the map until reboot;
+    /// until it is installed again, the map's base is used.
+    pub fn set_custom_keymap(&self, map: &CustomKeyMap) -> Result<(), xous::Error> {
+        let buf = Buffer::into_buf(*map).or(Err(xous::Error::InternalError))?;
+        buf.lend(self.conn, Opcode::SetCustomKeyMap.to_u32().unwrap()).map(|_| ())
+    }
+
+    /// Turns the built-in dead keys (`'`, `` ` ``, `^`, `"` and `~`) on or off for `map`. While on, typing
+    /// one of them followed by a letter produces the accented letter. The setting is not persisted by
+    /// the keyboard server; dead keys defined by a custom map are always on.
+    pub fn set_dead_keys(&self, map: KeyMap, enable: bool) -> Result<(), xous::Error> {
+        send_message(
+            self.conn,
+            Message::new_scalar(
+                Opcode::SetDeadKeys.to_usize().unwrap(),
+                map.into(),
+                if enable { 1 } else { 0 },
+                0,
+                0,
+            ),
+        )
+        .map(|_| ())
+    }
+
     /// Blocks until a key is hit. Does not block the keyboard server, just the caller.
     /// Returns a `Vec::<char>`, as the user can press more than one key at a time.
     /// The specific order of a simultaneous key hit event is not defined.
@@ -3,6 +3,7 @@
 
 mod api;
 use api::*;
+mod compose;
 mod mappings;
 
 #[cfg(feature = "rawserial")]
@@ -25,6 +26,7 @@ mod implementation {
     use utralib::generated::*;
     use xous::CID;
 
+    use crate::compose::Composer;
     use crate::mappings::*;
     use crate::{api::*, KeyRawStates, RowCol};
 
@@ -43,6 +45,12 @@ mod implementation {
         ticktimer: Ticktimer,
         /// mapping for ScanCode translation
         map: KeyMap,
+        /// user-defined map, used when `map` is `KeyMap::Custom`
+        custom: Option<CustomKeyMap>,
+        /// dead key and compose sequence state
+        composer: Composer,
+        /// bitmask of the keymaps that have the built-in dead keys turned on, by `KeyMap` index
+        dead_keys: u32,
         /// delay in ms before a key is considered to be repeating
         delay: u32,
         /// rate in ms for repeating a key
@@ -180,6 +188,9 @@ mod implementation {
                 last_state: HashSet::with_capacity(16),
                 ticktimer,
                 map: default_map,
+                custom: None,
+                composer: Composer::new(),
+                dead_keys: 0,
                 delay: 500,
                 rate: 50, // ubuntu default rate is 90, windows is 30
                 shift_down: false,
@@ -234,6 +245,7 @@ mod implementation {
             self.alt_down = false;
             self.alt_up = false;
             self.repeating_key = None;
+            self.composer.reset();
             self.chord_captured = false;
             self.chord_active = 0;
             self.chord = [[false; KBD_COLS]; KBD_ROWS];
@@ -249,11 +261,43 @@ mod implementation {
         }
 
         pub(crate) fn set_map(&mut self, map: KeyMap) {
+            if map == KeyMap::Custom {
+                // the custom map itself can only be installed with `set_custom_map()`
+                if self.custom.is_some() {
+                    self.map = map;
+                } else {
+                    log::warn!("no custom keymap installed, ignoring selection");
+                }
+                return;
+            }
             self.early_settings.set_keymap(map.into()).expect("cannot set early keymap");
             self.map = map;
+            self.composer.reset();
+        }
+
+        pub(crate) fn set_dead_keys(&mut self, map: KeyMap, enable: bool) {
+            if enable {
+                self.dead_keys |= dead_key_bit(map);
+            } else {
+                self.dead_keys &= !dead_key_bit(map);
+            }
+            self.composer.reset();
+        }
+
+        pub(crate) fn set_custom_map(&mut self, custom: CustomKeyMap) {
+            // custom maps live in the PDDB, so early settings just get the base map, which is what
+            // the keyboard will use until the custom map is installed again after the next boot.
+            self.early_settings.set_keymap(custom.base.into()).expect("cannot set early keymap");
+            log::info!("installing custom keymap {} over {}", custom.name, custom.base);
+            self.custom = Some(custom);
+            self.map = KeyMap::Custom;
+            self.composer.reset();
         }
 
         pub(crate) fn get_map(&mut self) -> KeyMap {
+            if self.map == KeyMap::Custom && self.custom.is_some() {
+                return KeyMap::Custom;
+            }
             let kb_raw = self.early_settings.get_keymap().expect("cannot fetch early keymap");
             self.map = KeyMap::from(kb_raw);
             self.map
@@ -502,7 +546,7 @@ mod implementation {
             // first check for shift and alt keys
             for rc in krs.keydowns.iter() {
                 match self.map {
-                    KeyMap::Azerty => {
+                    KeyMap::Azerty | KeyMap::Custom => {
                         if (rc.r == 8) && (rc.c == 5) {
                             // left shift (orange)
                             if self.alt_up == false {
@@ -537,7 +581,7 @@ mod implementation {
             let mut keyups_noshift: Vec<RowCol> = Vec::new();
             for &rc in krs.keyups.iter() {
                 match self.map {
-                    KeyMap::Azerty => {
+                    KeyMap::Azerty | KeyMap::Custom => {
                         if (rc.r == 8) && (rc.c == 5) {
                             // left shift (orange)
                             if self.alt_down {
@@ -578,13 +622,7 @@ mod implementation {
                 self.chord_timestamp = self.ticktimer.elapsed_ms();
             }
             for &rc in krs.keydowns.iter() {
-                let code = match self.map {
-                    KeyMap::Qwerty => map_qwerty(rc),
-                    KeyMap::Dvorak => map_dvorak(rc),
-                    KeyMap::Azerty => map_azerty(rc),
-                    KeyMap::Qwertz => map_qwertz(rc),
-                    _ => ScanCode { key: None, shift: None, hold: None, alt: None },
-                };
+                let code = self.scancode(rc);
                 if code.hold == None && !((rc.r == 5) && (rc.c == 2))
                 // scan code for the menu key
                 {
@@ -609,13 +647,7 @@ mod implementation {
 
             for &rc in keyups_noshift.iter() {
                 // info!("interpreting keyups_noshift entry {:?}", rc);
-                let code = match self.map {
-                    KeyMap::Qwerty => map_qwerty(rc),
-                    KeyMap::Dvorak => map_dvorak(rc),
-                    KeyMap::Azerty => map_azerty(rc),
-                    KeyMap::Qwertz => map_qwertz(rc),
-                    _ => ScanCode { key: None, shift: None, hold: None, alt: None },
-                };
+                let code = self.scancode(rc);
                 // delete the key repeat if there is one
                 if code.hold == None {
                     if let Some(key) = code.key {
@@ -628,7 +660,7 @@ mod implementation {
                 }
 
                 match self.map {
-                    KeyMap::Azerty => {
+                    KeyMap::Azerty | KeyMap::Custom => {
                         if self.shift_down || self.shift_up {
                             if let Some(shiftcode) = code.shift {
                                 ks.push(shiftcode);
@@ -691,7 +723,16 @@ mod implementation {
                 }
             }
 
-            ks
+            let custom = if self.map == KeyMap::Custom { self.custom.as_ref() } else { None };
+            let dead_keys = self.dead_keys & dead_key_bit(self.map) != 0;
+            self.composer.process(&ks, custom, dead_keys)
+        }
+
+        fn scancode(&self, rc: RowCol) -> ScanCode {
+            match (self.map, &self.custom) {
+                (KeyMap::Custom, Some(custom)) => map_custom(custom, rc),
+                (map, _) => map_builtin(map, rc),
+            }
         }
 
         pub fn is_repeating_key(&self) -> bool { self.repeating_key.is_some() }
@@ -733,6 +774,10 @@ mod implementation {
 
         pub fn set_map(&mut self, map: KeyMap) { self.map = map; }
 
+        pub fn set_custom_map(&mut self, _custom: CustomKeyMap) { self.map = KeyMap::Custom; }
+
+        pub fn set_dead_keys(&mut self, _map: KeyMap, _enable: bool) {}
+
         pub fn get_map(&self) -> KeyMap { self.map }
 
         pub fn update(&self) -> KeyRawStates { KeyRawStates::new() }
@@ -770,12 +815,13 @@ fn main() -> ! {
     //  - status sub system (for setting the layout, autobacklight feature)
     //  - USB (for getting layout)
     //  - Preference manager
+    //  - graphics UI script runner (hosted mode)
     #[cfg(all(any(feature = "precursor", feature = "renode"), not(feature = "dvt")))]
     let kbd_sid = xns.register_name(api::SERVER_NAME_KBD, Some(5)).expect("can't register server");
     #[cfg(all(any(feature = "precursor", feature = "renode"), feature = "dvt"))] // dvt build has less in it
     let kbd_sid = xns.register_name(api::SERVER_NAME_KBD, Some(4)).expect("can't register server");
     #[cfg(not(target_os = "xous"))]
-    let kbd_sid = xns.register_name(api::SERVER_NAME_KBD, Some(5)).expect("can't register server");
+    let kbd_sid = xns.register_name(api::SERVER_NAME_KBD, Some(6)).expect("can't register server");
     log::trace!("registered with NS -- {:?}", kbd_sid);
 
     // Create a new kbd object
@@ -889,6 +935,14 @@ fn main() -> ! {
             Some(Opcode::SelectKeyMap) => {
                 msg_scalar_unpack!(msg, km, _, _, _, { kbd.set_map(KeyMap::from(km)) })
             }
+            Some(Opcode::SetCustomKeyMap) => {
+                let buffer = unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                let custom = buffer.to_original::<CustomKeyMap, _>().unwrap();
+                kbd.set_custom_map(custom);
+            }
+            Some(Opcode::SetDeadKeys) => msg_scalar_unpack!(msg, km, enable, _, _, {
+                kbd.set_dead_keys(KeyMap::from(km), enable != 0);
+            }),
             Some(Opcode::GetKeyMap) => msg_blocking_scalar_unpack!(msg, _, _, _, _, {
                 xous::return_scalar(msg.sender, kbd.get_map().into()).expect("can't retrieve keymap");
             }),
@@ -12,3 +12,34 @@ pub(crate) use azerty::*;
 #[rustfmt::skip] // this file is a lookup table. Allow wide columns.
 mod dvorak;
 pub(crate) use dvorak::*;
+
+use crate::{CustomKeyMap, KeyMap, RowCol, ScanCode};
+
+/// Look up a key in one of the compiled-in maps
+pub(crate) fn map_builtin(map: KeyMap, code: RowCol) -> ScanCode {
+    match map {
+        KeyMap::Qwerty => map_qwerty(code),
+        KeyMap::Dvorak => map_dvorak(code),
+        KeyMap::Azerty => map_azerty(code),
+        KeyMap::Qwertz => map_qwertz(code),
+        _ => ScanCode { key: None, shift: None, hold: None, alt: None },
+    }
+}
+
+/// Look up a key in a user-defined map, deferring to its base map for anything it doesn't override
+pub(crate) fn map_custom(custom: &CustomKeyMap, code: RowCol) -> ScanCode {
+    let base = map_builtin(custom.base, code);
+    let layers = match custom.keys.get(code.r as usize).and_then(|row| row.get(code.c as usize)) {
+        Some(layers) => layers,
+        None => return base,
+    };
+    let pick = |layer: u32, fallback: Option<char>| {
+        if layer == 0 { fallback } else { core::char::from_u32(layer) }
+    };
+    ScanCode {
+        key: pick(layers[0], base.key),
+        shift: pick(layers[1], base.shift),
+        hold: pick(layers[2], base.hold),
+        alt: pick(layers[3], base.alt),
+    }
+}
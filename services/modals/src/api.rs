@@ -77,6 +77,29 @@ pub struct ManagedProgress {
     pub step: u32,
 }
 
+#[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Copy, Clone)]
+pub struct ManagedSpinner {
+    pub token: [u32; 4],
+    pub prompt: xous_ipc::String<1024>,
+    pub kind: SpinnerKind,
+    /// initial values of the fields; see `SpinnerPayload` for their meaning
+    pub initial: [i32; MAX_SPINNER_FIELDS],
+    /// range and increment, only used by `SpinnerKind::Number`
+    pub min: i32,
+    pub max: i32,
+    pub step: i32,
+    pub units: Option<xous_ipc::String<8>>,
+}
+
+#[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Copy, Clone)]
+pub struct ManagedForm {
+    pub token: [u32; 4],
+    pub prompt: xous_ipc::String<1024>,
+    pub fields: [Option<FormField>; MAX_FORM_FIELDS],
+    /// index of the field that starts with focus, e.g. the one that failed validation
+    pub focus: u32,
+}
+
 /// This isn't a terribly useful notification -- it's basically read-only, no interactivity,
 /// but you can animate the text. Mainly used for testing routines. Might be modifiable
 /// into something more useful with a bit of thought, but for now, MVP.
@@ -106,6 +129,12 @@ pub(crate) enum Opcode {
     Bip39Return = 33, // ----- note op number
     SliderReturn = 34,
     Slider = 35,
+    /// date, time or number spinner
+    Spinner = 36,
+    SpinnerReturn = 37,
+    /// form with mixed field types; acknowledged with `TextResponseValid` like a text entry
+    Form = 38,
+    FormReturn = 39,
     /// display an image
     #[cfg(feature = "ditherpunk")]
     Image = 3,
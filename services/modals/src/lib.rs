@@ -170,6 +170,206 @@ impl<'a> AlertModalBuilder<'a> {
     }
 }
 
+/// A value entered into a form field; the variant matches the kind of field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormValue {
+    Text(String),
+    Password(String),
+    CheckBox(bool),
+    /// the selected choice
+    Radio(String),
+    Slider(u32),
+}
+impl FormValue {
+    /// The text of a text or password field, or the selected choice of a radio group
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            FormValue::Text(s) | FormValue::Password(s) | FormValue::Radio(s) => Some(s.as_str()),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> { if let FormValue::CheckBox(b) = self { Some(*b) } else { None } }
+
+    pub fn as_u32(&self) -> Option<u32> { if let FormValue::Slider(v) = self { Some(*v) } else { None } }
+
+    fn from_field(field: &FormField) -> Self {
+        let text = || field.text.as_str().unwrap_or("").to_string();
+        match field.kind {
+            FormFieldKind::Text => FormValue::Text(text()),
+            FormFieldKind::Password => FormValue::Password(text()),
+            FormFieldKind::CheckBox => FormValue::CheckBox(field.value != 0),
+            FormFieldKind::Radio => FormValue::Radio(field.choice().unwrap_or("").to_string()),
+            FormFieldKind::Slider { .. } => FormValue::Slider(field.value),
+        }
+    }
+}
+
+/// The values of a form, in the order the fields were added.
+#[derive(Debug, Clone)]
+pub struct FormResult(Vec<FormValue>);
+impl FormResult {
+    pub fn values(&self) -> &[FormValue] { &self.0 }
+
+    pub fn text(&self, index: usize) -> Option<&str> { self.0.get(index).and_then(|v| v.as_str()) }
+
+    pub fn checked(&self, index: usize) -> Option<bool> { self.0.get(index).and_then(|v| v.as_bool()) }
+
+    pub fn number(&self, index: usize) -> Option<u32> { self.0.get(index).and_then(|v| v.as_u32()) }
+}
+
+pub type FormValidationFn = fn(&FormValue) -> Option<ValidatorErr>;
+
+pub struct FormModalBuilder<'a> {
+    prompt: String,
+    fields: Vec<FormField>,
+    validators: Vec<Option<FormValidationFn>>,
+    /// set if a field couldn't be described, e.g. a radio group with too many choices
+    invalid: bool,
+    modals: &'a Modals,
+}
+
+impl<'a> FormModalBuilder<'a> {
+    fn push(&'a mut self, field: FormField, validator: Option<FormValidationFn>) -> &'a mut Self {
+        self.fields.push(field);
+        self.validators.push(validator);
+        self
+    }
+
+    /// A text field, optionally pre-filled with `initial`.
+    pub fn text(
+        &'a mut self,
+        label: &str,
+        initial: Option<&str>,
+        validator: Option<FormValidationFn>,
+    ) -> &'a mut Self {
+        let mut field = FormField::new(FormFieldKind::Text, label);
+        if let Some(text) = initial {
+            field.text = xous_ipc::String::from_str(text);
+        }
+        self.push(field, validator)
+    }
+
+    /// A text field whose contents are masked on screen.
+    pub fn password(&'a mut self, label: &str, validator: Option<FormValidationFn>) -> &'a mut Self {
+        self.push(FormField::new(FormFieldKind::Password, label), validator)
+    }
+
+    pub fn checkbox(&'a mut self, label: &str, checked: bool) -> &'a mut Self {
+        let mut field = FormField::new(FormFieldKind::CheckBox, label);
+        field.value = if checked { 1 } else { 0 };
+        self.push(field, None)
+    }
+
+    /// A radio group, cycled with the left and right keys. `selected` is the index of the initial choice.
+    pub fn radio(
+        &'a mut self,
+        label: &str,
+        choices: &[&str],
+        selected: usize,
+        validator: Option<FormValidationFn>,
+    ) -> &'a mut Self {
+        let mut field = FormField::new(FormFieldKind::Radio, label);
+        if choices.is_empty() || choices.len() > MAX_FORM_CHOICES {
+            log::error!(
+                "radio groups in forms need 1 to {} choices, got {}",
+                MAX_FORM_CHOICES,
+                choices.len()
+            );
+            self.invalid = true;
+        }
+        for (slot, choice) in field.choices.iter_mut().zip(choices.iter()) {
+            *slot = Some(xous_ipc::String::from_str(choice));
+        }
+        field.value = selected.min(choices.len().saturating_sub(1)) as u32;
+        self.push(field, validator)
+    }
+
+    pub fn slider(
+        &'a mut self,
+        label: &str,
+        min: u32,
+        max: u32,
+        step: u32,
+        initial: u32,
+        validator: Option<FormValidationFn>,
+    ) -> &'a mut Self {
+        let mut field = FormField::new(FormFieldKind::Slider { min, max, step: step.max(1) }, label);
+        field.value = initial.max(min).min(max);
+        self.push(field, validator)
+    }
+
+    /// Shows the form and blocks until the user submits it with every validator passing.
+    pub fn build(&self) -> Result<FormResult, xous::Error> {
+        if self.fields.is_empty() || self.fields.len() > MAX_FORM_FIELDS || self.invalid {
+            log::error!("forms need 1 to {} well-formed fields", MAX_FORM_FIELDS);
+            return Err(xous::Error::UnknownError);
+        }
+        self.modals.lock();
+        let mut spec = ManagedForm {
+            token: self.modals.token,
+            prompt: xous_ipc::String::from_str(&self.prompt),
+            fields: [None; MAX_FORM_FIELDS],
+            focus: 0,
+        };
+        for (slot, field) in spec.fields.iter_mut().zip(self.fields.iter()) {
+            *slot = Some(*field);
+        }
+
+        loop {
+            let mut buf = Buffer::into_buf(spec).or(Err(xous::Error::InternalError))?;
+            buf.lend_mut(self.modals.conn, Opcode::Form.to_u32().unwrap())
+                .or(Err(xous::Error::InternalError))?;
+            let response = buf.to_original::<FormPayload, _>();
+            let result = match response {
+                Ok(mut payload) => {
+                    let values: Vec<FormValue> =
+                        payload.0.iter().flatten().map(FormValue::from_field).collect();
+                    let failure = self.validators.iter().zip(values.iter()).enumerate().find_map(
+                        |(index, (validator, value))| {
+                            validator.and_then(|v| v(value)).map(|err| (index, err))
+                        },
+                    );
+                    if let Some((index, err_msg)) = failure {
+                        // show the form again as the user left it, with the error as the prompt and the
+                        // offending field focused
+                        spec.prompt.clear();
+                        spec.prompt.append(err_msg.as_str().unwrap_or("UTF-8 error")).ok();
+                        spec.fields = payload.0;
+                        spec.focus = index as u32;
+                        for field in payload.0.iter_mut().flatten() {
+                            field.volatile_clear();
+                        }
+                        continue;
+                    }
+                    for field in payload.0.iter_mut().flatten() {
+                        field.volatile_clear();
+                    }
+                    Ok(FormResult(values))
+                }
+                // the valid response token is sent even in this case so that the modals server moves on
+                Err(_) => Err(xous::Error::InternalError),
+            };
+            for field in spec.fields.iter_mut().flatten() {
+                field.volatile_clear();
+            }
+            send_message(
+                self.modals.conn,
+                Message::new_blocking_scalar(
+                    Opcode::TextResponseValid.to_usize().unwrap(),
+                    self.modals.token[0] as _,
+                    self.modals.token[1] as _,
+                    self.modals.token[2] as _,
+                    self.modals.token[3] as _,
+                ),
+            )
+            .expect("couldn't acknowledge form entry");
+            self.modals.unlock();
+            return result;
+        }
+    }
+}
+
 pub struct Modals {
     conn: CID,
     token: [u32; 4],
@@ -199,6 +399,45 @@ impl Modals {
         }
     }
 
+    /// A form stacking text, password, checkbox, radio and slider fields, returned in one round-trip.
+    ///
+    /// # Examples
+    /// ```
+    /// use modals::Modals;
+    /// use xous_names::XousNames;
+    /// let xns = XousNames::new().unwrap();
+    /// let modals = Modals::new(&xns).unwrap();
+    /// let result = modals
+    ///     .form_builder("New account")
+    ///     .text(
+    ///         "Name",
+    ///         None,
+    ///         Some(|v| {
+    ///             if v.as_str() == Some("") {
+    ///                 Some(xous_ipc::String::from_str("Name can't be empty"))
+    ///             } else {
+    ///                 None
+    ///             }
+    ///         }),
+    ///     )
+    ///     .password("Password", None)
+    ///     .checkbox("Remember me", true)
+    ///     .radio("Kind", &["personal", "work"], 0, None)
+    ///     .slider("Length", 8, 64, 1, 20, None)
+    ///     .build()
+    ///     .unwrap();
+    /// let name = result.text(0).unwrap();
+    /// ```
+    pub fn form_builder(&self, prompt: &str) -> FormModalBuilder {
+        FormModalBuilder {
+            prompt: String::from(prompt),
+            fields: vec![],
+            validators: vec![],
+            invalid: false,
+            modals: self,
+        }
+    }
+
     /// Text/QR code notification modal dialog.
     ///
     /// - `qrtext` turns submitted text into a qr code.
@@ -360,6 +599,83 @@ impl Modals {
         Ok(orig.0)
     }
 
+    /// Asks for a date with a year/month/day spinner. `initial` is `(year, month, day)`; out-of-range values
+    /// are pulled into range, and `None` starts at the earliest date the RTC can hold.
+    pub fn get_date(
+        &self,
+        prompt: &str,
+        initial: Option<(u32, u32, u32)>,
+    ) -> Result<(u32, u32, u32), xous::Error> {
+        let (y, m, d) = initial.unwrap_or((SPINNER_MIN_YEAR as u32, 1, 1));
+        let v = self.spinner(ManagedSpinner {
+            token: self.token,
+            prompt: xous_ipc::String::from_str(prompt),
+            kind: SpinnerKind::Date,
+            initial: [y as i32, m as i32, d as i32],
+            min: 0,
+            max: 0,
+            step: 1,
+            units: None,
+        })?;
+        Ok((v[0] as u32, v[1] as u32, v[2] as u32))
+    }
+
+    /// Asks for a time of day with an hours/minutes/seconds spinner. `initial` is `(hours, minutes,
+    /// seconds)` in 24-hour format; `None` starts at midnight.
+    pub fn get_time(
+        &self,
+        prompt: &str,
+        initial: Option<(u32, u32, u32)>,
+    ) -> Result<(u32, u32, u32), xous::Error> {
+        let (h, m, s) = initial.unwrap_or((0, 0, 0));
+        let v = self.spinner(ManagedSpinner {
+            token: self.token,
+            prompt: xous_ipc::String::from_str(prompt),
+            kind: SpinnerKind::Time,
+            initial: [h as i32, m as i32, s as i32],
+            min: 0,
+            max: 0,
+            step: 1,
+            units: None,
+        })?;
+        Ok((v[0] as u32, v[1] as u32, v[2] as u32))
+    }
+
+    /// Asks for a number between `min` and `max` (inclusive). The up/down keys move by `step`, and digits
+    /// can be typed directly. `units`, if any, is shown after the number and must be less than 8 bytes.
+    pub fn get_number(
+        &self,
+        prompt: &str,
+        min: i32,
+        max: i32,
+        step: i32,
+        initial: i32,
+        units: Option<&str>,
+    ) -> Result<i32, xous::Error> {
+        let v = self.spinner(ManagedSpinner {
+            token: self.token,
+            prompt: xous_ipc::String::from_str(prompt),
+            kind: SpinnerKind::Number,
+            initial: [initial, 0, 0],
+            min,
+            max,
+            step,
+            units: units.map(|u| xous_ipc::String::from_str(u)),
+        })?;
+        Ok(v[0])
+    }
+
+    fn spinner(&self, spec: ManagedSpinner) -> Result<[i32; MAX_SPINNER_FIELDS], xous::Error> {
+        self.lock();
+        let mut buf = Buffer::into_buf(spec).or(Err(xous::Error::InternalError))?;
+        buf.lend_mut(self.conn, Opcode::Spinner.to_u32().unwrap()).or(Err(xous::Error::InternalError))?;
+
+        let orig = buf.to_original::<SpinnerPayload, _>().unwrap();
+
+        self.unlock();
+        Ok(orig.0)
+    }
+
     /// note that this API is not atomically token-locked, so, someone could mess with the progress bar state
     /// but, progress updates are meant to be fast and frequent, and generally if a progress bar shows
     /// something whacky it's not going to affect a security outcome
@@ -23,7 +23,7 @@
 /// 7. once you are sure you're finished, call `token_lock = next_lock(&mut work_queue);` to pull any waiting
 ///    work from the work queue
 ///
-/// Between 5 & 7 is where the TextEntry is weird: because you can "fail" on the return,
+/// Between 5 & 7 is where the TextEntry (and Form) is weird: because you can "fail" on the return,
 /// it doesn't automatically do step 7. It's an extra step that the library implementation
 /// does after it does the text validation on its side, once it validates the caller sends
 /// a `TextResponseValid` message which pumps the work queue.
@@ -54,6 +54,8 @@ enum RendererState {
     RunCheckBox(ManagedPromptWithFixedResponse),
     RunText(ManagedPromptWithTextResponse),
     RunProgress(ManagedProgress),
+    RunSpinner(ManagedSpinner),
+    RunForm(ManagedForm),
     RunNotification(ManagedNotification),
     RunBip39(ManagedBip39),
     RunBip39Input(ManagedBip39),
@@ -328,6 +330,42 @@ fn wrapped_main() -> ! {
                 )
                 .expect("couldn't initiate UX op");
             }
+            Some(Opcode::Spinner) => {
+                let spec = {
+                    let buffer =
+                        unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                    buffer.to_original::<ManagedSpinner, _>().unwrap()
+                };
+                if spec.token != token_lock.unwrap_or(default_nonce) {
+                    log::warn!("Attempt to access modals without a mutex lock. Ignoring.");
+                    continue;
+                }
+                op = RendererState::RunSpinner(spec);
+                dr = Some(msg);
+                send_message(
+                    renderer_cid,
+                    Message::new_scalar(Opcode::InitiateOp.to_usize().unwrap(), 0, 0, 0, 0),
+                )
+                .expect("couldn't initiate UX op");
+            }
+            Some(Opcode::Form) => {
+                let spec = {
+                    let buffer =
+                        unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                    buffer.to_original::<ManagedForm, _>().unwrap()
+                };
+                if spec.token != token_lock.unwrap_or(default_nonce) {
+                    log::warn!("Attempt to access modals without a mutex lock. Ignoring.");
+                    continue;
+                }
+                op = RendererState::RunForm(spec);
+                dr = Some(msg);
+                send_message(
+                    renderer_cid,
+                    Message::new_scalar(Opcode::InitiateOp.to_usize().unwrap(), 0, 0, 0, 0),
+                )
+                .expect("couldn't initiate UX op");
+            }
             Some(Opcode::StopProgress) => msg_blocking_scalar_unpack!(msg, t0, t1, t2, t3, {
                 let token = [t0 as u32, t1 as u32, t2 as u32, t3 as u32];
                 if token != token_lock.unwrap_or(default_nonce) {
@@ -602,6 +640,48 @@ fn wrapped_main() -> ! {
 
                         renderer_modal.activate();
                     }
+                    RendererState::RunSpinner(config) => {
+                        let mut spinner = Spinner::new(
+                            renderer_cid,
+                            Opcode::SpinnerReturn.to_u32().unwrap(),
+                            config.kind,
+                            config.initial,
+                        );
+                        if config.kind == SpinnerKind::Number {
+                            spinner.set_range(
+                                config.min,
+                                config.max,
+                                config.step,
+                                config.units.as_ref().map(|u| u.as_str().unwrap()),
+                            );
+                        }
+                        #[cfg(feature = "tts")]
+                        tts.tts_simple(config.prompt.as_str().unwrap()).unwrap();
+                        renderer_modal.modify(
+                            Some(ActionType::Spinner(spinner)),
+                            Some(config.prompt.as_str().unwrap()),
+                            false,
+                            None,
+                            true,
+                            Some(DEFAULT_STYLE),
+                        );
+                        renderer_modal.activate();
+                    }
+                    RendererState::RunForm(config) => {
+                        let mut form = Form::new(renderer_cid, Opcode::FormReturn.to_u32().unwrap());
+                        form.set_fields(config.fields, config.focus as usize);
+                        #[cfg(feature = "tts")]
+                        tts.tts_simple(config.prompt.as_str().unwrap()).unwrap();
+                        renderer_modal.modify(
+                            Some(ActionType::Form(form)),
+                            Some(config.prompt.as_str().unwrap()),
+                            false,
+                            None,
+                            true,
+                            Some(DEFAULT_STYLE),
+                        );
+                        renderer_modal.activate();
+                    }
                     RendererState::RunRadio(config) => {
                         let mut radiobuttons = gam::modal::RadioButtons::new(
                             renderer_cid,
@@ -787,6 +867,29 @@ fn wrapped_main() -> ! {
                     log::warn!("got weird stuff on slider return, ignoring");
                 }
             },
+            Some(Opcode::SpinnerReturn) => match op {
+                RendererState::RunSpinner(_) => {
+                    let buffer = unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                    let item = buffer.to_original::<SpinnerPayload, _>().unwrap();
+
+                    if let Some(mut origin) = dr.take() {
+                        let mut response = unsafe {
+                            Buffer::from_memory_message_mut(origin.body.memory_message_mut().unwrap())
+                        };
+
+                        response.replace(item).unwrap();
+                        op = RendererState::None;
+
+                        token_lock = next_lock(&mut work_queue);
+                    } else {
+                        log::error!("Ux routine returned but no origin was recorded");
+                        panic!("Ux routine returned but no origin was recorded");
+                    }
+                }
+                _ => {
+                    log::warn!("got weird stuff on spinner return, ignoring");
+                }
+            },
             Some(Opcode::TextEntryReturn) => match op {
                 RendererState::RunText(_config) => {
                     renderer_modal.set_growable(false); // reset the growable state, it's assumed to be default false
@@ -816,6 +919,35 @@ fn wrapped_main() -> ! {
                     );
                 }
             },
+            Some(Opcode::FormReturn) => match op {
+                RendererState::RunForm(_) => {
+                    log::trace!("validating form modal");
+                    let buf = unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                    let form = buf.to_original::<FormPayload, _>().unwrap();
+                    if let Some(mut origin) = dr.take() {
+                        let mut response = unsafe {
+                            Buffer::from_memory_message_mut(origin.body.memory_message_mut().unwrap())
+                        };
+                        response.replace(form).unwrap();
+                        // like text entry, the work queue is pumped once the caller has validated the form
+                        op = RendererState::None;
+                    } else {
+                        log::error!("Ux routine returned but no origin was recorded");
+                        panic!("Ux routine returned but no origin was recorded");
+                    }
+                }
+                RendererState::None => {
+                    log::warn!("Form detected a fat finger event, ignoring.")
+                }
+                _ => {
+                    log::error!(
+                        "UX return opcode does not match our current operation in flight. This is a serious internal error."
+                    );
+                    panic!(
+                        "UX return opcode does not match our current operation in flight. This is a serious internal error."
+                    );
+                }
+            },
             Some(Opcode::TextResponseValid) => msg_blocking_scalar_unpack!(msg, t0, t1, t2, t3, {
                 let incoming_token = [t0 as u32, t1 as u32, t2 as u32, t3 as u32];
                 if incoming_token != token_lock.unwrap_or(default_nonce) {
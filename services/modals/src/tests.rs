@@ -104,6 +104,17 @@ pub fn spawn_test() {
                 .expect("cannot show slider result notification");
 
             log::info!("slider test done");
+
+            // 7. spinners
+            log::info!("testing spinners");
+            let date = modals.get_date("Pick a date", Some((2024, 2, 29))).expect("date spinner failed");
+            let time = modals.get_time("Pick a time", None).expect("time spinner failed");
+            let number =
+                modals.get_number("Pick a number", -10, 10, 2, 0, Some("dB")).expect("number spinner failed");
+            modals
+                .show_notification(&format!("Spinners: {:?} {:?} {}", date, time, number), None)
+                .expect("cannot show spinner result notification");
+            log::info!("spinner test done");
         }
     });
 
@@ -134,6 +145,22 @@ pub fn spawn_test() {
             }
             log::info!("text input test done");
 
+            // 2a. test a form mixing field types
+            log::info!("test form");
+            match modals
+                .form_builder("Test form")
+                .text("Name", Some("xous"), Some(test_form_validator))
+                .password("Password", None)
+                .checkbox("Remember", true)
+                .radio("Animal", &RADIO_TEST, 1, None)
+                .slider("Length", 8, 64, 4, 20, None)
+                .build()
+            {
+                Ok(result) => log::info!("Form: {:?}", result.values()),
+                _ => log::error!("form failed"),
+            }
+            log::info!("form test done");
+
             // 3. test notificatons
             log::info!("testing notification");
             modals.show_notification("这是一个测验!", Some("这是一个测验!")).expect("notification failed");
@@ -207,3 +234,10 @@ fn test_validator(input: TextEntryPayload) -> Option<xous_ipc::String<256>> {
         _ => return Some(xous_ipc::String::<256>::from_str("enter an integer value")),
     }
 }
+
+fn test_form_validator(input: &FormValue) -> Option<xous_ipc::String<256>> {
+    match input.as_str() {
+        Some(name) if name.len() >= 3 => None,
+        _ => Some(xous_ipc::String::<256>::from_str("name must be at least 3 characters")),
+    }
+}
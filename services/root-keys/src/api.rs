@@ -116,6 +116,11 @@ pub(crate) enum Opcode {
     EfuseRun = 49,
     #[cfg(feature = "efuse")]
     EfusePasswordReturn = 50,
+
+    /// Prompt for the unlock PIN and check it against the boot password entered at boot, without
+    /// touching the password cache. Used by the screen lock.
+    UxVerifyBootPassword = 51,
+    UxVerifyBootPasswordReturn = 52,
 }
 
 #[derive(Debug, num_derive::FromPrimitive, num_derive::ToPrimitive, PartialEq, Eq)]
@@ -318,6 +323,8 @@ impl From<KeyMap> for BackupKeyboardLayout {
             KeyMap::Dvorak => BackupKeyboardLayout::Dvorak,
             KeyMap::Qwertz => BackupKeyboardLayout::Qwertz,
             KeyMap::Braille => BackupKeyboardLayout::Braille,
+            // custom maps are restored from the PDDB; backups record the custom map's base instead
+            KeyMap::Custom => BackupKeyboardLayout::Qwerty,
             KeyMap::Undefined => BackupKeyboardLayout::Qwerty,
         }
     }
@@ -178,6 +178,9 @@ pub(crate) struct RootKeys {
     fake_key: [u8; 32], /* a base set of random numbers used to respond to invalid keyloc requests in AES
                          * operations */
     restore_running: bool,
+    /// A one-way image of the hashed boot password, used to check the screen lock PIN. Unlike the
+    /// password cache it isn't erased on suspend, but it can't be used to recover any key material.
+    lock_verifier: Option<[u8; 32]>,
 }
 
 impl<'a> RootKeys {
@@ -304,6 +307,7 @@ impl<'a> RootKeys {
             update_password_policy: PasswordRetentionPolicy::AlwaysPurge,
             boot_password_policy: PasswordRetentionPolicy::AlwaysKeep,
             cur_password_type: None,
+            lock_verifier: None,
             susres: susres::Susres::new_without_hook(&xns).expect("couldn't connect to susres without hook"),
             trng,
             gfx: graphics_server::Gfx::new(&xns).expect("couldn't connect to gfx"),
@@ -699,7 +703,12 @@ impl<'a> RootKeys {
 
     pub fn purge_user_password(&mut self, pw_type: AesRootkeyType) {
         match pw_type {
-            AesRootkeyType::User0 => self.purge_password(PasswordType::Boot),
+            AesRootkeyType::User0 => {
+                // an explicit purge means the password was rejected, so it can't be trusted for the
+                // screen lock either. Purges due to the suspend policy leave the verifier alone.
+                self.lock_verifier = None;
+                self.purge_password(PasswordType::Boot)
+            }
             _ => log::warn!("Requested to purge a password for a key that we don't have. Ignoring."),
         }
     }
@@ -843,29 +852,13 @@ impl<'a> RootKeys {
             log::error!("got an unexpected password from the UX");
             return false;
         };
-        let mut hashed_password: [u8; 24] = [0; 24];
-        let mut salt = self.get_salt();
-        // we change the salt ever-so-slightly for every password. This doesn't make any one password more
-        // secure; but it disallows guessing all the passwords with a single off-the-shelf hashcat
-        // run.
-        salt[0] ^= pw_type as u8;
-
-        let timer = ticktimer_server::Ticktimer::new().expect("couldn't connect to ticktimer");
-        // the bcrypt function takes the plaintext password and makes one copy to prime the blowfish bcrypt
-        // cipher. It is responsible for erasing this state.
-        let start_time = timer.elapsed_ms();
-        bcrypt(BCRYPT_COST, &salt, pw, &mut hashed_password); // note: this internally makes a copy of the password, and destroys it
-        let elapsed = timer.elapsed_ms() - start_time;
-        log::info!("bcrypt cost: {} time: {}ms", BCRYPT_COST, elapsed); // benchmark to figure out how to set cost parameter
-
-        // expand the 24-byte (192-bit) bcrypt result into 256 bits, so we can use it directly as XOR key
-        // material against 256-bit AES and curve25519 keys
-        let mut hasher = Sha512_256Sw::new();
-        hasher.update(hashed_password);
-        let digest = hasher.finalize();
+        let digest = self.hash_password(pw, pw_type);
 
         let pcache_ptr: *mut PasswordCache = self.pass_cache.as_mut_ptr() as *mut PasswordCache;
         if !verify {
+            if pw_type == PasswordType::Boot {
+                self.lock_verifier = Some(Self::lock_verifier_from(&digest));
+            }
             unsafe {
                 match pw_type {
                     PasswordType::Boot => {
@@ -915,6 +908,57 @@ impl<'a> RootKeys {
         }
     }
 
+    /// bcrypt the password with the salt for its type, then expand the result to 256 bits
+    fn hash_password(&mut self, pw: &str, pw_type: PasswordType) -> [u8; 32] {
+        let mut hashed_password: [u8; 24] = [0; 24];
+        let mut salt = self.get_salt();
+        // we change the salt ever-so-slightly for every password. This doesn't make any one password more
+        // secure; but it disallows guessing all the passwords with a single off-the-shelf hashcat
+        // run.
+        salt[0] ^= pw_type as u8;
+
+        let timer = ticktimer_server::Ticktimer::new().expect("couldn't connect to ticktimer");
+        // the bcrypt function takes the plaintext password and makes one copy to prime the blowfish bcrypt
+        // cipher. It is responsible for erasing this state.
+        let start_time = timer.elapsed_ms();
+        bcrypt(BCRYPT_COST, &salt, pw, &mut hashed_password); // note: this internally makes a copy of the password, and destroys it
+        let elapsed = timer.elapsed_ms() - start_time;
+        log::info!("bcrypt cost: {} time: {}ms", BCRYPT_COST, elapsed); // benchmark to figure out how to set cost parameter
+
+        // expand the 24-byte (192-bit) bcrypt result into 256 bits, so we can use it directly as XOR key
+        // material against 256-bit AES and curve25519 keys
+        let mut hasher = Sha512_256Sw::new();
+        hasher.update(hashed_password);
+        let mut digest = [0u8; 32];
+        digest.copy_from_slice(hasher.finalize().as_slice());
+        digest
+    }
+
+    fn lock_verifier_from(digest: &[u8; 32]) -> [u8; 32] {
+        let mut hasher = Sha512_256Sw::new();
+        hasher.update(b"screen lock");
+        hasher.update(digest);
+        let mut verifier = [0u8; 32];
+        verifier.copy_from_slice(hasher.finalize().as_slice());
+        verifier
+    }
+
+    pub fn has_lock_verifier(&self) -> bool { self.lock_verifier.is_some() }
+
+    /// Checks a PIN against the boot password that was last entered. Returns `None` if no boot
+    /// password has been entered since boot. Nothing is written to the password cache.
+    pub fn verify_lock_password(&mut self, pw: &str) -> Option<bool> {
+        let expected = self.lock_verifier?;
+        let digest = self.hash_password(pw, PasswordType::Boot);
+        let candidate = Self::lock_verifier_from(&digest);
+        // compare every byte, don't bail out early
+        let mut diff = 0u8;
+        for (a, b) in candidate.iter().zip(expected.iter()) {
+            diff |= a ^ b;
+        }
+        Some(diff == 0)
+    }
+
     /// Reads a 256-bit key at a given index offset
     fn read_key_256(&mut self, index: u8) -> [u8; 32] {
         let mut key: [u8; 32] = [0; 32];
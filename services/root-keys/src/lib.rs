@@ -51,6 +51,11 @@ impl RootKeys {
 
     pub fn get_try_selfsign_op(&self) -> u32 { Opcode::UxSelfSignXous.to_u32().unwrap() }
 
+    /// The blocking scalar behind `verify_boot_password()`, for callers that wait on the PIN prompt without
+    /// holding on to their `RootKeys`. It returns 1 for a correct PIN, 0 for an incorrect one and 2 if there
+    /// is nothing to check against.
+    pub fn get_verify_boot_password_op(&self) -> u32 { Opcode::UxVerifyBootPassword.to_u32().unwrap() }
+
     /// this initiates an attempt to update passwords. User must unlock their device first, and can cancel out
     /// if not expected.
     pub fn try_update_password(&mut self, _which: PasswordType) -> Result<(), xous::Error> {
@@ -293,6 +298,25 @@ impl RootKeys {
         }
     }
 
+    /// Prompts the user for the unlock PIN and checks it against the boot password. Returns `None`
+    /// if no boot password has been accepted since boot, in which case the only safe way to unlock
+    /// is a reboot.
+    pub fn verify_boot_password(&self) -> Result<Option<bool>, xous::Error> {
+        let response = send_message(
+            self.conn,
+            Message::new_blocking_scalar(Opcode::UxVerifyBootPassword.to_usize().unwrap(), 0, 0, 0, 0),
+        )?;
+        if let xous::Result::Scalar1(result) = response {
+            match result {
+                1 => Ok(Some(true)),
+                2 => Ok(None),
+                _ => Ok(Some(false)),
+            }
+        } else {
+            Err(xous::Error::InternalError)
+        }
+    }
+
     pub fn is_efuse_secured(&self) -> Result<Option<bool>, xous::Error> {
         let response = send_message(
             self.conn,
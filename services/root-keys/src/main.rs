@@ -158,6 +158,13 @@ mod implementation {
             true
         }
 
+        pub fn has_lock_verifier(&self) -> bool { true }
+
+        pub fn verify_lock_password(&mut self, pw: &str) -> Option<bool> {
+            log::info!("got lock password plaintext: {}", pw);
+            Some(true)
+        }
+
         pub fn set_ux_password_type(&mut self, cur_type: Option<PasswordType>) {
             self.password_type = cur_type;
         }
@@ -688,6 +695,7 @@ fn main() -> ! {
 
     let mut reboot_initiated = false;
     let mut aes_sender: Option<xous::MessageSender> = None;
+    let mut verify_sender: Option<xous::MessageSender> = None;
     let mut backup_header: Option<BackupHeader> = None;
     let mut deferred_response: Option<xous::MessageSender> = None;
     let mut checksums: Option<Checksums> = None; // storage for PDDB backup checksums
@@ -1573,6 +1581,44 @@ fn main() -> ! {
                     log::warn!("UxAesEnsureReturn detected a fat-finger event. Ignoring.");
                 }
             }
+            Some(Opcode::UxVerifyBootPassword) => msg_blocking_scalar_unpack!(msg, _, _, _, _, {
+                if !keys.has_lock_verifier() {
+                    // nothing to check against; the caller has to fall back to a full lock
+                    xous::return_scalar(msg.sender, 2).unwrap();
+                    continue;
+                }
+                if verify_sender.is_some() {
+                    log::error!("multiple concurrent requests to UxVerifyBootPassword, not allowed!");
+                    xous::return_scalar(msg.sender, 0).unwrap();
+                    continue;
+                }
+                verify_sender = Some(msg.sender);
+                password_action.set_action_opcode(Opcode::UxVerifyBootPasswordReturn.to_u32().unwrap());
+                rootkeys_modal.modify(
+                    Some(ActionType::TextEntry(password_action.clone())),
+                    Some(t!("rootkeys.get_unlock_password", locales::LANG)),
+                    false,
+                    None,
+                    true,
+                    None,
+                );
+                #[cfg(feature = "tts")]
+                tts.tts_blocking(t!("rootkeys.get_unlock_password", locales::LANG)).unwrap();
+                rootkeys_modal.activate();
+                // the scalar is returned by UxVerifyBootPasswordReturn
+            }),
+            Some(Opcode::UxVerifyBootPasswordReturn) => {
+                let mut buf = unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                let plaintext_pw = buf.to_original::<gam::modal::TextEntryPayloads, _>().unwrap();
+                let verified = keys.verify_lock_password(plaintext_pw.first().as_str()).unwrap_or(false);
+                plaintext_pw.first().volatile_clear(); // ensure the data is destroyed after sending to the keys enclave
+                buf.volatile_clear();
+                if let Some(sender) = verify_sender.take() {
+                    xous::return_scalar(sender, if verified { 1 } else { 0 }).unwrap();
+                } else {
+                    log::warn!("UxVerifyBootPasswordReturn detected a fat-finger event. Ignoring.");
+                }
+            }
             Some(Opcode::AesOracle) => {
                 let mut buffer =
                     unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
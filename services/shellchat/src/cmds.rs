@@ -5,6 +5,9 @@ use std::collections::HashMap;
 use utralib::generated::*;
 use xous::MessageEnvelope;
 use xous_ipc::String;
+
+use crate::script::{self, Chain, ScriptEnv, MAX_SCRIPT_DEPTH};
+
 /////////////////////////// Common items to all commands
 pub trait ShellCmdApi<'a> {
     // user implemented:
@@ -24,6 +27,10 @@ pub trait ShellCmdApi<'a> {
         Ok(None)
     }
 
+    // optionally implemented:
+    // lists the subcommands that can follow the verb, which are offered as completions while typing
+    fn subcommands(&self) -> &'static [SubCmd] { &[] }
+
     // created with cmd_api! macro
     // checks if the command matches the current verb in question
     fn matches(&self, verb: &str) -> bool;
@@ -38,6 +45,35 @@ macro_rules! cmd_api {
     };
 }
 
+/// What the argument of a subcommand can be completed with
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ArgKind {
+    /// nothing is suggested
+    None,
+    /// names of the PDDB bases
+    Basis,
+    /// names of the PDDB dictionaries
+    Dict,
+    /// `dict:key` names of the PDDB keys
+    DictKey,
+    /// SSIDs seen by the last wifi scan
+    Ssid,
+    /// names of the scripts
+    Script,
+}
+
+/// A subcommand, as listed by `ShellCmdApi::subcommands()`
+#[derive(Debug, Copy, Clone)]
+pub struct SubCmd {
+    pub name: &'static str,
+    pub arg: ArgKind,
+}
+impl SubCmd {
+    pub const fn new(name: &'static str) -> Self { SubCmd { name, arg: ArgKind::None } }
+
+    pub const fn with_arg(name: &'static str, arg: ArgKind) -> Self { SubCmd { name, arg } }
+}
+
 use trng::*;
 /////////////////////////// Command shell integration
 pub struct CommonEnv {
@@ -79,7 +115,8 @@ impl CommonEnv {
         1. mod/use the new command
         2. create an entry for the command's storage in the CmdEnv structure
         3. initialize the persistant storage here
-        4. add it to the "commands" array in the dispatch() routine below
+        4. add it to the `commands!` list below
+        5. optionally, list its subcommands in `subcommands()`, so they can be completed
 
     Side note: if your command doesn't require persistent storage, you could,
     technically, generate the command dynamically every time it's called. Echo
@@ -129,6 +166,8 @@ mod pddb_cmd;
 use pddb_cmd::*;
 mod usb;
 use usb::*;
+mod script_cmd;
+use script_cmd::*;
 
 #[cfg(not(feature = "no-codec"))]
 mod test;
@@ -155,9 +194,55 @@ use aes_cmd::*;
 //mod fcc;      use fcc::*;
 //mod pds; // dependency of the FCC file
 
+/// Every command, in the order they are listed when no verb matches. This is a macro rather than a
+/// method so that borrowing the commands doesn't also borrow the rest of `CmdEnv`. Commands without
+/// persistent storage are created on the spot; they're zero-sized, so this costs nothing.
+macro_rules! commands {
+    ($env:ident) => {
+        [
+            ///// 4. add your command to this list, so that it can be looked up and dispatched
+            &mut Echo {} as &mut dyn ShellCmdApi,
+            &mut $env.sleep_cmd,
+            &mut $env.sensors_cmd,
+            //&mut $env.callback_cmd,
+            &mut $env.rtc_cmd,
+            &mut $env.vibe_cmd,
+            &mut $env.ssid_cmd,
+            &mut Ver {},
+            //&mut $env.audio_cmd,
+            &mut Backlight {},
+            &mut Accel {},
+            #[cfg(feature = "dbg-ecupdate")]
+            &mut $env.ecup_cmd,
+            &mut $env.trng_cmd,
+            &mut Console {},
+            // &mut $env.memtest_cmd,
+            &mut $env.keys_cmd,
+            &mut $env.wlan_cmd,
+            &mut $env.jtag_cmd,
+            &mut $env.net_cmd,
+            &mut $env.pddb_cmd,
+            &mut $env.usb_cmd,
+            &mut $env.script_cmd,
+            #[cfg(not(feature = "no-codec"))]
+            &mut $env.test_cmd,
+            #[cfg(feature = "tts")]
+            &mut $env.tts_cmd,
+            #[cfg(feature = "hashtest")]
+            &mut $env.sha_cmd,
+            #[cfg(feature = "aestests")]
+            &mut $env.aes_cmd,
+            #[cfg(feature = "benchmarks")]
+            &mut $env.engine_cmd,
+            //&mut $env.fcc_cmd,
+        ]
+    };
+}
+
 pub struct CmdEnv {
     common_env: CommonEnv,
     lastverb: String<256>,
+    script_env: ScriptEnv,
     ///// 2. declare storage for your command here.
     sleep_cmd: Sleep,
     sensors_cmd: Sensors,
@@ -176,6 +261,7 @@ pub struct CmdEnv {
     pddb_cmd: PddbCmd,
     wlan_cmd: Wlan,
     usb_cmd: Usb,
+    script_cmd: ScriptCmd,
 
     #[cfg(not(feature = "no-codec"))]
     test_cmd: Test,
@@ -257,6 +343,7 @@ impl CmdEnv {
         CmdEnv {
             common_env: _common,
             lastverb: String::<256>::new(),
+            script_env: ScriptEnv::new(),
             ///// 3. initialize your storage, by calling new()
             sleep_cmd: {
                 log::debug!("sleep");
@@ -311,6 +398,10 @@ impl CmdEnv {
                 log::debug!("usb");
                 Usb::new()
             },
+            script_cmd: {
+                log::debug!("script");
+                ScriptCmd::new()
+            },
 
             #[cfg(not(feature = "no-codec"))]
             test_cmd: {
@@ -331,93 +422,33 @@ impl CmdEnv {
         }
     }
 
+    /// The verbs of all the commands and their subcommands, for completion
+    pub fn completion_table(&mut self) -> Vec<(&'static str, &'static [SubCmd])> {
+        let mut table: Vec<(&'static str, &'static [SubCmd])> =
+            commands!(self).iter().map(|cmd| (cmd.verb(), cmd.subcommands())).collect();
+        // the scripting built-ins
+        table.push(("run", &[]));
+        table.push(("set", &[]));
+        table.push((script::SCRIPT_PREFIX, &[]));
+        table
+    }
+
     pub fn dispatch(
         &mut self,
         maybe_cmdline: Option<&mut String<1024>>,
         maybe_callback: Option<&MessageEnvelope>,
     ) -> Result<Option<String<1024>>, xous::Error> {
-        let mut ret = String::<1024>::new();
-
-        let mut echo_cmd = Echo {}; // this command has no persistent storage, so we can "create" it every time we call dispatch (but it's a zero-cost absraction so this doesn't actually create any instructions)
-        let mut ver_cmd = Ver {};
-        let mut backlight_cmd = Backlight {};
-        let mut accel_cmd = Accel {};
-        let mut console_cmd = Console {};
-        let commands: &mut [&mut dyn ShellCmdApi] = &mut [
-            ///// 4. add your command to this array, so that it can be looked up and dispatched
-            &mut echo_cmd,
-            &mut self.sleep_cmd,
-            &mut self.sensors_cmd,
-            //&mut self.callback_cmd,
-            &mut self.rtc_cmd,
-            &mut self.vibe_cmd,
-            &mut self.ssid_cmd,
-            &mut ver_cmd,
-            //&mut self.audio_cmd,
-            &mut backlight_cmd,
-            &mut accel_cmd,
-            #[cfg(feature = "dbg-ecupdate")]
-            &mut self.ecup_cmd,
-            &mut self.trng_cmd,
-            &mut console_cmd,
-            // &mut self.memtest_cmd,
-            &mut self.keys_cmd,
-            &mut self.wlan_cmd,
-            &mut self.jtag_cmd,
-            &mut self.net_cmd,
-            &mut self.pddb_cmd,
-            &mut self.usb_cmd,
-            #[cfg(not(feature = "no-codec"))]
-            &mut self.test_cmd,
-            #[cfg(feature = "tts")]
-            &mut self.tts_cmd,
-            #[cfg(feature = "hashtest")]
-            &mut self.sha_cmd,
-            #[cfg(feature = "aestests")]
-            &mut self.aes_cmd,
-            #[cfg(feature = "benchmarks")]
-            &mut self.engine_cmd,
-            //&mut self.fcc_cmd,
-        ];
-
         if let Some(cmdline) = maybe_cmdline {
-            let maybe_verb = tokenize(cmdline);
-
-            let mut cmd_ret: Result<Option<String<1024>>, xous::Error> = Ok(None);
-            if let Some(verb_string) = maybe_verb {
-                let verb = verb_string.to_str();
-
-                // search through the list of commands linearly until one matches,
-                // then run it.
-                let mut match_found = false;
-                for cmd in commands.iter_mut() {
-                    if cmd.matches(verb) {
-                        match_found = true;
-                        cmd_ret = cmd.process(*cmdline, &mut self.common_env);
-                        self.lastverb.clear();
-                        write!(self.lastverb, "{}", verb).expect("SHCH: couldn't record last verb");
-                    };
-                }
-
-                // if none match, create a list of available commands
-                if !match_found {
-                    let mut first = true;
-                    write!(ret, "Commands: ").unwrap();
-                    for cmd in commands.iter() {
-                        if !first {
-                            ret.append(", ")?;
-                        }
-                        ret.append(cmd.verb())?;
-                        first = false;
-                    }
-                    Ok(Some(ret))
-                } else {
-                    cmd_ret
-                }
-            } else {
-                Ok(None)
-            }
+            let line = cmdline.to_str().to_string();
+            let (ok, ret) = match script::interactive(&line) {
+                Some(script_line) => self.run_line(script_line, &[], 0),
+                // anything else is run as it was typed, so that no argument is taken for an operator
+                None => self.run_command(&line, 0),
+            };
+            self.script_env.vars.insert("?".to_string(), if ok { "0" } else { "1" }.to_string());
+            Ok(ret)
         } else if let Some(callback) = maybe_callback {
+            let commands: &mut [&mut dyn ShellCmdApi] = &mut commands!(self);
             let mut cmd_ret: Result<Option<String<1024>>, xous::Error> = Ok(None);
             // first check and see if we have a callback registration; if not, just map to the last verb
             let verb = match self.common_env.cb_registrations.get(&(callback.body.id() as u32)) {
@@ -438,6 +469,153 @@ impl CmdEnv {
             Ok(None)
         }
     }
+
+    /// Runs a line of commands joined by `;` and `&&`. Returns whether the last command that ran
+    /// succeeded, and the output of all of them.
+    fn run_line(
+        &mut self,
+        line: &str,
+        args: &[std::string::String],
+        depth: usize,
+    ) -> (bool, Option<String<1024>>) {
+        // lines are added to scripts as they were typed, so they are passed on whole
+        if line.trim_start().split(' ').next() == Some("script") {
+            return self.run_command(line.trim_start(), depth);
+        }
+        let mut ret = String::<1024>::new();
+        let mut ok = true;
+        for (chain, step) in script::split(line) {
+            if chain == Chain::OnSuccess && !ok {
+                continue;
+            }
+            let step = script::expand(step, &self.script_env.vars, args);
+            let (cmd, redirect) = script::redirection(&step);
+            let (success, output) = self.run_command(&script::unquote(cmd), depth);
+            ok = success;
+            let text = output.as_ref().map(|o| o.to_str()).unwrap_or("");
+            let shown = match redirect {
+                Some((target, append)) => {
+                    match self.script_env.redirect(&script::unquote(target), text, append) {
+                        Ok(_) => None,
+                        Err(e) => {
+                            ok = false;
+                            Some(format!("Couldn't write to {}: {}", target, e))
+                        }
+                    }
+                }
+                None => Some(text.to_string()),
+            };
+            if let Some(shown) = shown.filter(|s| !s.is_empty()) {
+                if ret.len() > 0 {
+                    ret.append("\n").ok();
+                }
+                // output past the end of the buffer is dropped
+                ret.append(&shown).ok();
+            }
+            self.script_env.vars.insert("?".to_string(), if ok { "0" } else { "1" }.to_string());
+        }
+        (ok, if ret.len() > 0 { Some(ret) } else { None })
+    }
+
+    /// Runs a single command, or one of the scripting built-ins. Returns whether it succeeded, and its
+    /// output.
+    fn run_command(&mut self, cmdline: &str, depth: usize) -> (bool, Option<String<1024>>) {
+        let mut ret = String::<1024>::new();
+        let mut tokens = cmdline.split(' ').filter(|t| !t.is_empty());
+        match tokens.next() {
+            Some("set") => match tokens.next() {
+                Some(name) if script::is_var_name(name) => {
+                    let value = tokens.collect::<Vec<&str>>().join(" ");
+                    if value.is_empty() {
+                        self.script_env.vars.remove(name);
+                    } else {
+                        self.script_env.vars.insert(name.to_string(), value);
+                    }
+                    return (true, None);
+                }
+                Some(name) => {
+                    write!(ret, "Not a variable name: {}", name).ok();
+                    return (false, Some(ret));
+                }
+                None => {
+                    let mut vars: Vec<_> = self.script_env.vars.iter().filter(|(k, _)| *k != "?").collect();
+                    vars.sort();
+                    for (name, value) in vars {
+                        write!(ret, "{}={}\n", name, value).ok();
+                    }
+                    return (true, Some(ret));
+                }
+            },
+            Some("run") => {
+                let name = match tokens.next() {
+                    Some(name) => name,
+                    None => {
+                        write!(ret, "Usage: run <script> [args]").ok();
+                        return (false, Some(ret));
+                    }
+                };
+                if depth >= MAX_SCRIPT_DEPTH {
+                    write!(ret, "Scripts run too deep, not running {}", name).ok();
+                    return (false, Some(ret));
+                }
+                let lines = match self.script_env.load(name) {
+                    Ok(lines) => lines,
+                    Err(_) => {
+                        write!(ret, "No script {}", name).ok();
+                        return (false, Some(ret));
+                    }
+                };
+                let script_args: Vec<std::string::String> = tokens.map(|t| t.to_string()).collect();
+                let mut ok = true;
+                for line in lines {
+                    let (success, output) = self.run_line(&line, &script_args, depth + 1);
+                    ok = success;
+                    if let Some(output) = output {
+                        if ret.len() > 0 {
+                            ret.append("\n").ok();
+                        }
+                        ret.append(output.to_str()).ok();
+                    }
+                }
+                return (ok, if ret.len() > 0 { Some(ret) } else { None });
+            }
+            _ => {}
+        }
+        let commands: &mut [&mut dyn ShellCmdApi] = &mut commands!(self);
+        let mut cmdline = String::<1024>::from_str(cmdline);
+        let maybe_verb = tokenize(&mut cmdline);
+        if let Some(verb_string) = maybe_verb {
+            let verb = verb_string.to_str();
+
+            // search through the list of commands linearly until one matches,
+            // then run it.
+            for cmd in commands.iter_mut() {
+                if cmd.matches(verb) {
+                    let cmd_ret = cmd.process(cmdline, &mut self.common_env);
+                    self.lastverb.clear();
+                    write!(self.lastverb, "{}", verb).expect("SHCH: couldn't record last verb");
+                    return match cmd_ret {
+                        Ok(output) => (true, output),
+                        Err(e) => {
+                            write!(ret, "Error: {:?}", e).ok();
+                            (false, Some(ret))
+                        }
+                    };
+                };
+            }
+
+            // if none match, create a list of available commands
+            write!(ret, "Commands: ").unwrap();
+            for cmd in commands.iter() {
+                ret.append(cmd.verb()).ok();
+                ret.append(", ").ok();
+            }
+            ret.append("run, set, sh").ok();
+            (false, Some(ret))
+        } else {
+            (true, None)
+        }
+    }
 }
 
 /// extract the first token, as delimited by spaces
@@ -1,6 +1,6 @@
 use xous_ipc::String;
 
-use crate::{CommonEnv, ShellCmdApi};
+use crate::{CommonEnv, ShellCmdApi, SubCmd};
 
 #[derive(Debug)]
 pub struct Backlight {}
@@ -8,6 +8,19 @@ pub struct Backlight {}
 impl<'a> ShellCmdApi<'a> for Backlight {
     cmd_api!(backlight);
 
+    fn subcommands(&self) -> &'static [SubCmd] {
+        &[
+            SubCmd::new("on"),
+            SubCmd::new("off"),
+            SubCmd::new("0"),
+            SubCmd::new("1"),
+            SubCmd::new("2"),
+            SubCmd::new("3"),
+            SubCmd::new("4"),
+            SubCmd::new("5"),
+        ]
+    }
+
     // inserts boilerplate for command API
 
     fn process(
@@ -1,6 +1,6 @@
 use xous_ipc::String;
 
-use crate::{CommonEnv, ShellCmdApi};
+use crate::{CommonEnv, ShellCmdApi, SubCmd};
 
 #[derive(Debug)]
 pub struct Console {}
@@ -8,6 +8,10 @@ pub struct Console {}
 impl<'a> ShellCmdApi<'a> for Console {
     cmd_api!(console);
 
+    fn subcommands(&self) -> &'static [SubCmd] {
+        &[SubCmd::new("kernel"), SubCmd::new("log"), SubCmd::new("app")]
+    }
+
     // inserts boilerplate for command API
 
     fn process(
@@ -1,6 +1,6 @@
 use xous_ipc::String;
 
-use crate::{CommonEnv, ShellCmdApi};
+use crate::{CommonEnv, ShellCmdApi, SubCmd};
 
 #[derive(Debug)]
 pub struct JtagCmd {
@@ -15,6 +15,10 @@ impl JtagCmd {
 impl<'a> ShellCmdApi<'a> for JtagCmd {
     cmd_api!(jtag);
 
+    fn subcommands(&self) -> &'static [SubCmd] {
+        &[SubCmd::new("id"), SubCmd::new("dna"), SubCmd::new("efuse"), SubCmd::new("ir")]
+    }
+
     // inserts boilerplate for command API
 
     fn process(
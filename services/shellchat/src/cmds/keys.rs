@@ -1,7 +1,7 @@
 use root_keys::api::{AesRootkeyType, Block};
 use xous_ipc::String;
 
-use crate::{CommonEnv, ShellCmdApi};
+use crate::{CommonEnv, ShellCmdApi, SubCmd};
 
 #[derive(Debug)]
 #[cfg(feature = "spinortest")]
@@ -61,6 +61,10 @@ impl Keys {
 impl<'a> ShellCmdApi<'a> for Keys {
     cmd_api!(keys);
 
+    fn subcommands(&self) -> &'static [SubCmd] {
+        &[SubCmd::new("ux"), SubCmd::new("bbram"), SubCmd::new("aes"), SubCmd::new("pddbrecycle")]
+    }
+
     // inserts boilerplate for command API
 
     fn process(
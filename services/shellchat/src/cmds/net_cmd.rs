@@ -19,10 +19,10 @@ use {gam::DecodePng, std::str::FromStr};
 #[cfg(feature = "websocket")]
 use {
     tls::Tls,
-    tungstenite::{stream::MaybeTlsStream, WebSocket},
+    tungstenite::{WebSocket, stream::MaybeTlsStream},
 };
 
-use crate::{CommonEnv, ShellCmdApi};
+use crate::{CommonEnv, ShellCmdApi, SubCmd};
 
 pub struct NetCmd {
     callback_id: Option<u32>,
@@ -69,6 +69,17 @@ pub(crate) enum NetCmdDispatch {
 impl<'a> ShellCmdApi<'a> for NetCmd {
     cmd_api!(net);
 
+    fn subcommands(&self) -> &'static [SubCmd] {
+        #[cfg(any(feature = "precursor", feature = "renode"))]
+        {
+            &[SubCmd::new("udp"), SubCmd::new("ping"), SubCmd::new("tcpget"), SubCmd::new("dns")]
+        }
+        #[cfg(not(any(feature = "precursor", feature = "renode")))]
+        {
+            &[SubCmd::new("udp"), SubCmd::new("tcpget"), SubCmd::new("dns")]
+        }
+    }
+
     // inserts boilerplate for command API
 
     fn process(
@@ -6,7 +6,7 @@ use std::io::{Read, Seek, SeekFrom, Write};
 use pddb::PDDB_A_LEN;
 use xous_ipc::String;
 
-use crate::{CommonEnv, ShellCmdApi};
+use crate::{ArgKind, CommonEnv, ShellCmdApi, SubCmd};
 
 #[cfg(any(feature = "shellperf", not(target_os = "xous")))]
 const TEST_DICT: &'static str = "perftest";
@@ -58,6 +58,28 @@ impl PddbCmd {
 impl<'a> ShellCmdApi<'a> for PddbCmd {
     cmd_api!(pddb);
 
+    fn subcommands(&self) -> &'static [SubCmd] {
+        &[
+            SubCmd::new("basislist"),
+            SubCmd::new("basiscreate"),
+            SubCmd::with_arg("basisunlock", ArgKind::Basis),
+            SubCmd::with_arg("basislock", ArgKind::Basis),
+            SubCmd::with_arg("basisdelete", ArgKind::Basis),
+            SubCmd::new("default"),
+            SubCmd::new("dictlist"),
+            SubCmd::with_arg("keylist", ArgKind::Dict),
+            SubCmd::with_arg("write", ArgKind::DictKey),
+            SubCmd::with_arg("writeover", ArgKind::DictKey),
+            SubCmd::with_arg("query", ArgKind::DictKey),
+            SubCmd::with_arg("copy", ArgKind::DictKey),
+            SubCmd::with_arg("dictdelete", ArgKind::Dict),
+            SubCmd::with_arg("keydelete", ArgKind::DictKey),
+            SubCmd::new("churn"),
+            SubCmd::new("flush"),
+            SubCmd::new("sync"),
+        ]
+    }
+
     // inserts boilerplate for command API
 
     fn process(
@@ -1330,10 +1352,9 @@ impl<'a> ShellCmdApi<'a> for PddbCmd {
                 #[cfg(not(target_os = "xous"))]
                 "rkyvtest" => {
                     use rkyv::{
-                        archived_value,
+                        AlignedVec, Deserialize, archived_value,
                         de::deserializers::AllocDeserializer,
-                        ser::{serializers::WriteSerializer, Serializer},
-                        AlignedVec, Deserialize,
+                        ser::{Serializer, serializers::WriteSerializer},
                     };
                     let test = pddb::PddbKeyRecord {
                         name: "test".to_string(),
@@ -4,7 +4,7 @@ use chrono::offset::Utc;
 use chrono::{DateTime, NaiveDateTime};
 use xous_ipc::String;
 
-use crate::{CommonEnv, ShellCmdApi};
+use crate::{CommonEnv, ShellCmdApi, SubCmd};
 
 #[derive(Debug)]
 pub struct RtcCmd {}
@@ -14,6 +14,8 @@ impl RtcCmd {
 impl<'a> ShellCmdApi<'a> for RtcCmd {
     cmd_api!(rtc);
 
+    fn subcommands(&self) -> &'static [SubCmd] { &[SubCmd::new("utc"), SubCmd::new("local")] }
+
     fn process(
         &mut self,
         args: String<1024>,
@@ -0,0 +1,105 @@
+use core::fmt::Write as FmtWrite;
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use xous_ipc::String;
+
+use crate::script::SCRIPT_DICT;
+use crate::{ArgKind, CommonEnv, ShellCmdApi, SubCmd};
+
+pub struct ScriptCmd {
+    pddb: pddb::Pddb,
+}
+impl ScriptCmd {
+    pub fn new() -> ScriptCmd { ScriptCmd { pddb: pddb::Pddb::new() } }
+}
+
+impl<'a> ShellCmdApi<'a> for ScriptCmd {
+    cmd_api!(script);
+
+    fn subcommands(&self) -> &'static [SubCmd] {
+        &[
+            SubCmd::new("list"),
+            SubCmd::with_arg("show", ArgKind::Script),
+            SubCmd::with_arg("add", ArgKind::Script),
+            SubCmd::with_arg("delete", ArgKind::Script),
+        ]
+    }
+
+    fn process(
+        &mut self,
+        args: String<1024>,
+        _env: &mut CommonEnv,
+    ) -> Result<Option<String<1024>>, xous::Error> {
+        let mut ret = String::<1024>::new();
+        let helpstring = "script [list] [show <name>] [add <name> <command line>] [delete <name>]\nrun scripts with `run <name> [args]`";
+
+        let mut tokens = args.as_str().unwrap().split(' ');
+        match tokens.next() {
+            Some("list") => match self.pddb.list_keys(SCRIPT_DICT, None) {
+                Ok(names) => {
+                    for name in names {
+                        write!(ret, "{}\n", name).ok();
+                    }
+                }
+                Err(_) => write!(ret, "No scripts").unwrap(),
+            },
+            Some("show") => {
+                if let Some(name) = tokens.next() {
+                    match self.pddb.get(SCRIPT_DICT, name, None, false, false, None, None::<fn()>) {
+                        Ok(mut key) => {
+                            let mut text = std::string::String::new();
+                            match key.read_to_string(&mut text) {
+                                Ok(_) => write!(ret, "{}", text).ok(),
+                                Err(e) => write!(ret, "Error reading {}: {:?}", name, e).ok(),
+                            };
+                        }
+                        Err(_) => write!(ret, "No script {}", name).unwrap(),
+                    }
+                } else {
+                    write!(ret, "Missing script name").unwrap();
+                }
+            }
+            Some("add") => {
+                let name = tokens.next().unwrap_or("");
+                let line = tokens.collect::<Vec<&str>>().join(" ");
+                if name.is_empty() || line.trim().is_empty() {
+                    write!(ret, "Usage: script add <name> <command line>").unwrap();
+                } else {
+                    // each command line is added to the end of the script
+                    match self.pddb.get(SCRIPT_DICT, name, None, true, true, None, None::<fn()>) {
+                        Ok(mut key) => {
+                            match key
+                                .seek(SeekFrom::End(0))
+                                .and_then(|_| key.write_all(line.trim().as_bytes()))
+                            {
+                                Ok(_) => {
+                                    key.write_all(b"\n").ok();
+                                    self.pddb.sync().ok();
+                                    write!(ret, "Added to {}: {}", name, line.trim()).ok();
+                                }
+                                Err(e) => write!(ret, "Error writing {}: {:?}", name, e).unwrap(),
+                            }
+                        }
+                        Err(e) => write!(ret, "Error creating {}: {:?}", name, e).unwrap(),
+                    }
+                }
+            }
+            Some("delete") => {
+                if let Some(name) = tokens.next() {
+                    match self.pddb.delete_key(SCRIPT_DICT, name, None) {
+                        Ok(_) => {
+                            // you must call sync after all deletions are done
+                            self.pddb.sync().ok();
+                            write!(ret, "Deleted {}", name).unwrap();
+                        }
+                        Err(_) => write!(ret, "No script {}", name).unwrap(),
+                    }
+                } else {
+                    write!(ret, "Missing script name").unwrap();
+                }
+            }
+            _ => write!(ret, "{}", helpstring).unwrap(),
+        }
+        Ok(Some(ret))
+    }
+}
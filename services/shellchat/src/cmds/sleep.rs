@@ -2,7 +2,7 @@ use std::thread;
 
 use xous_ipc::String;
 
-use crate::{CommonEnv, ShellCmdApi};
+use crate::{CommonEnv, ShellCmdApi, SubCmd};
 
 #[derive(Debug)]
 pub struct Sleep {
@@ -43,6 +43,24 @@ fn kill_thread(bounce: usize) {
 impl<'a> ShellCmdApi<'a> for Sleep {
     cmd_api!(sleep);
 
+    fn subcommands(&self) -> &'static [SubCmd] {
+        &[
+            SubCmd::new("now"),
+            SubCmd::new("current"),
+            SubCmd::new("ship"),
+            SubCmd::new("kill"),
+            SubCmd::new("coldboot"),
+            SubCmd::new("killbounce"),
+            SubCmd::new("sus"),
+            SubCmd::new("stress"),
+            SubCmd::new("crypton"),
+            SubCmd::new("cryptoff"),
+            SubCmd::new("wfioff"),
+            SubCmd::new("wfion"),
+            SubCmd::new("debugwfi"),
+        ]
+    }
+
     // inserts boilerplate for command API
 
     fn process(
@@ -3,7 +3,7 @@ use core::fmt::Write;
 use xous::MessageEnvelope;
 use xous_ipc::String;
 
-use crate::{CommonEnv, ShellCmdApi};
+use crate::{CommonEnv, ShellCmdApi, SubCmd};
 
 #[derive(Debug)]
 pub struct Ssid {
@@ -15,6 +15,8 @@ impl Ssid {
 impl<'a> ShellCmdApi<'a> for Ssid {
     cmd_api!(ssid);
 
+    fn subcommands(&self) -> &'static [SubCmd] { &[SubCmd::new("scan")] }
+
     fn process(
         &mut self,
         args: String<1024>,
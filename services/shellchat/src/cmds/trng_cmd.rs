@@ -1,6 +1,6 @@
 use xous_ipc::String;
 
-use crate::{CommonEnv, ShellCmdApi};
+use crate::{CommonEnv, ShellCmdApi, SubCmd};
 
 #[derive(Debug)]
 pub struct TrngCmd {}
@@ -11,6 +11,17 @@ impl TrngCmd {
 impl<'a> ShellCmdApi<'a> for TrngCmd {
     cmd_api!(trng);
 
+    fn subcommands(&self) -> &'static [SubCmd] {
+        &[
+            SubCmd::new("avnist"),
+            SubCmd::new("ronist"),
+            SubCmd::new("runs"),
+            SubCmd::new("excur"),
+            SubCmd::new("errs"),
+            SubCmd::new("pump"),
+        ]
+    }
+
     fn process(
         &mut self,
         args: String<1024>,
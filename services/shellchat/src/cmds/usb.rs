@@ -2,7 +2,7 @@ use std::fmt::Write;
 
 use usb_device_xous::{UsbDeviceState, UsbDeviceType, UsbHid};
 
-use crate::{CommonEnv, ShellCmdApi};
+use crate::{CommonEnv, ShellCmdApi, SubCmd};
 
 #[derive(Debug)]
 pub struct Usb {
@@ -15,6 +15,40 @@ impl Usb {
 impl<'a> ShellCmdApi<'a> for Usb {
     cmd_api!(usb);
 
+    fn subcommands(&self) -> &'static [SubCmd] {
+        #[cfg(not(feature = "mass-storage"))]
+        {
+            &[
+                SubCmd::new("hid"),
+                SubCmd::new("fido"),
+                SubCmd::new("debug"),
+                SubCmd::new("send"),
+                SubCmd::new("status"),
+                SubCmd::new("leds"),
+                SubCmd::new("lock"),
+                SubCmd::new("unlock"),
+                SubCmd::new("kbdtest"),
+            ]
+        }
+        #[cfg(feature = "mass-storage")]
+        {
+            &[
+                SubCmd::new("hid"),
+                SubCmd::new("fido"),
+                SubCmd::new("ms"),
+                SubCmd::new("debug"),
+                SubCmd::new("send"),
+                SubCmd::new("status"),
+                SubCmd::new("leds"),
+                SubCmd::new("lock"),
+                SubCmd::new("unlock"),
+                SubCmd::new("kbdtest"),
+                SubCmd::new("console"),
+                SubCmd::new("noconsole"),
+            ]
+        }
+    }
+
     // inserts boilerplate for command API
 
     fn process(
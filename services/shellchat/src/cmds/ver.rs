@@ -1,6 +1,6 @@
 use xous_ipc::String;
 
-use crate::{CommonEnv, ShellCmdApi};
+use crate::{CommonEnv, ShellCmdApi, SubCmd};
 
 #[derive(Debug)]
 pub struct Ver {}
@@ -8,6 +8,17 @@ pub struct Ver {}
 impl<'a> ShellCmdApi<'a> for Ver {
     cmd_api!(ver);
 
+    fn subcommands(&self) -> &'static [SubCmd] {
+        &[
+            SubCmd::new("ec"),
+            SubCmd::new("wf200"),
+            SubCmd::new("soc"),
+            SubCmd::new("dna"),
+            SubCmd::new("xous"),
+            SubCmd::new("ecreset"),
+        ]
+    }
+
     // inserts boilerplate for command API
 
     fn process(
@@ -2,7 +2,7 @@ use core::fmt::Write;
 
 use xous_ipc::String;
 
-use crate::{CommonEnv, ShellCmdApi};
+use crate::{CommonEnv, ShellCmdApi, SubCmd};
 
 #[derive(Debug)]
 pub struct Vibe {}
@@ -13,6 +13,10 @@ impl Vibe {
 impl<'a> ShellCmdApi<'a> for Vibe {
     cmd_api!(vibe);
 
+    fn subcommands(&self) -> &'static [SubCmd] {
+        &[SubCmd::new("on"), SubCmd::new("off"), SubCmd::new("long"), SubCmd::new("double")]
+    }
+
     // inserts boilerplate for command API
 
     fn process(
@@ -3,7 +3,7 @@ use std::io::Write as PddbWrite;
 
 use xous_ipc::String;
 
-use crate::{CommonEnv, ShellCmdApi};
+use crate::{ArgKind, CommonEnv, ShellCmdApi, SubCmd};
 
 #[derive(Debug)]
 pub struct Wlan {
@@ -28,6 +28,20 @@ wlan shell command:
 impl<'a> ShellCmdApi<'a> for Wlan {
     cmd_api!(wlan);
 
+    fn subcommands(&self) -> &'static [SubCmd] {
+        &[
+            SubCmd::new("on"),
+            SubCmd::new("off"),
+            SubCmd::with_arg("setssid", ArgKind::Ssid),
+            SubCmd::new("setpass"),
+            SubCmd::new("join"),
+            SubCmd::new("leave"),
+            SubCmd::new("status"),
+            SubCmd::new("save"),
+            SubCmd::new("known"),
+        ]
+    }
+
     // inserts boilerplate for command API
 
     fn process(
@@ -0,0 +1,179 @@
+//! Completes shell command lines for the IME's prediction bar.
+//!
+//! The verbs and subcommands come from `ShellCmdApi::subcommands()`; arguments are looked up as they're
+//! needed. The completer runs in its own thread, because the IME shell plugin waits on it while the
+//! shellchat main loop may itself be waiting on the IME.
+
+use std::thread;
+
+use ime_plugin_shell::{CompleterOpcode, Completions, MAX_COMPLETIONS};
+use num_traits::FromPrimitive;
+use xous_ipc::Buffer;
+
+use crate::script::SCRIPT_DICT;
+use crate::{ArgKind, SubCmd};
+
+/// Starts the completer and registers it with the IME shell plugin, along with a saved history
+pub(crate) fn start(table: Vec<(&'static str, &'static [SubCmd])>) {
+    thread::spawn(move || {
+        let xns = xous_names::XousNames::new().unwrap();
+        let sid = xous::create_server().unwrap();
+        if let Err(e) = ime_plugin_shell::register_app(&xns, gam::APP_NAME_SHELLCHAT, Some(sid), true) {
+            log::warn!("couldn't register the command completer: {:?}", e);
+            xous::destroy_server(sid).ok();
+            return;
+        }
+        let pddb = pddb::Pddb::new();
+        let netmgr = net::NetManager::new();
+        let args = |kind: ArgKind, prefix: &str| -> Vec<String> {
+            let mounted = pddb.is_mounted_nonblocking();
+            match kind {
+                ArgKind::None => Vec::new(),
+                ArgKind::Basis if mounted => pddb.list_basis(),
+                ArgKind::Dict if mounted => pddb.list_dict(None).unwrap_or_default(),
+                // the dictionary is completed first, then the keys in it
+                ArgKind::DictKey if mounted => match prefix.split_once(':') {
+                    Some((dict, _)) => pddb
+                        .list_keys(dict, None)
+                        .map(|keys| keys.iter().map(|key| format!("{}:{}", dict, key)).collect())
+                        .unwrap_or_default(),
+                    None => pddb
+                        .list_dict(None)
+                        .map(|dicts| dicts.iter().map(|dict| format!("{}:", dict)).collect())
+                        .unwrap_or_default(),
+                },
+                ArgKind::Script if mounted => pddb.list_keys(SCRIPT_DICT, None).unwrap_or_default(),
+                ArgKind::Ssid => match netmgr.wifi_get_ssid_list() {
+                    Ok((ssids, _state)) => ssids
+                        .iter()
+                        .filter_map(|ssid| ssid.name.as_str().ok())
+                        .filter(|name| !name.is_empty())
+                        .map(String::from)
+                        .collect(),
+                    Err(_) => Vec::new(),
+                },
+                // the PDDB isn't mounted yet
+                _ => Vec::new(),
+            }
+        };
+        loop {
+            let mut msg = xous::receive_message(sid).unwrap();
+            match FromPrimitive::from_usize(msg.body.id()) {
+                Some(CompleterOpcode::Complete) => {
+                    let mut buffer =
+                        unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                    let mut request = buffer.to_original::<Completions, _>().unwrap();
+                    let line = request.line.as_str().unwrap_or("").to_string();
+                    let completions = complete_line(&line, &table, &args, MAX_COMPLETIONS);
+                    for (slot, completion) in request.completions.iter_mut().zip(completions.iter()) {
+                        *slot = Some(xous_ipc::String::from_str(completion));
+                    }
+                    buffer.replace(request).unwrap();
+                }
+                None => log::error!("unknown opcode"),
+            }
+        }
+    });
+}
+
+/// Returns up to `max` completions of `line`, as whole lines. A completion that can be followed by
+/// more ends in a space, so the next part can be completed right away.
+pub(crate) fn complete_line(
+    line: &str,
+    table: &[(&'static str, &'static [SubCmd])],
+    args: &dyn Fn(ArgKind, &str) -> Vec<String>,
+    max: usize,
+) -> Vec<String> {
+    let words: Vec<&str> = line.split(' ').collect();
+    let mut completions: Vec<String> = match words.as_slice() {
+        [verb] => table
+            .iter()
+            .filter(|(v, _)| v.starts_with(verb))
+            .map(|(v, subs)| if subs.is_empty() { v.to_string() } else { format!("{} ", v) })
+            .collect(),
+        [verb, sub] => match table.iter().find(|(v, _)| v == verb) {
+            Some((_, subs)) => subs
+                .iter()
+                .filter(|s| s.name.starts_with(sub))
+                .map(|s| {
+                    if s.arg == ArgKind::None {
+                        format!("{} {}", verb, s.name)
+                    } else {
+                        format!("{} {} ", verb, s.name)
+                    }
+                })
+                .collect(),
+            None => Vec::new(),
+        },
+        [verb, sub, arg] => {
+            let kind = table
+                .iter()
+                .find(|(v, _)| v == verb)
+                .and_then(|(_, subs)| subs.iter().find(|s| s.name == *sub))
+                .map_or(ArgKind::None, |s| s.arg);
+            if kind == ArgKind::None {
+                Vec::new()
+            } else {
+                let mut candidates = args(kind, arg);
+                candidates.sort();
+                candidates
+                    .iter()
+                    .filter(|c| c.starts_with(arg))
+                    .map(|c| format!("{} {} {}", verb, sub, c))
+                    .collect()
+            }
+        }
+        _ => Vec::new(),
+    };
+    completions.retain(|c| c != line);
+    completions.dedup();
+    completions.truncate(max);
+    completions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PDDB: &[SubCmd] = &[
+        SubCmd::new("basislist"),
+        SubCmd::with_arg("basisunlock", ArgKind::Basis),
+        SubCmd::with_arg("keylist", ArgKind::Dict),
+        SubCmd::with_arg("query", ArgKind::DictKey),
+    ];
+    const TABLE: &[(&str, &[SubCmd])] = &[("echo", &[]), ("pddb", PDDB), ("ping", &[])];
+
+    fn args(kind: ArgKind, prefix: &str) -> Vec<String> {
+        match kind {
+            ArgKind::Basis => vec!["work".to_string(), "home".to_string()],
+            ArgKind::DictKey => match prefix.split_once(':') {
+                Some(_) => vec!["wlan.networks:home".to_string(), "wlan.networks:cafe".to_string()],
+                None => vec!["wlan.networks:".to_string(), "sys.rtc:".to_string()],
+            },
+            _ => Vec::new(),
+        }
+    }
+
+    #[test]
+    fn verbs_and_subcommands() {
+        assert_eq!(complete_line("p", TABLE, &args, 4), vec!["pddb ", "ping"]);
+        assert_eq!(complete_line("ping", TABLE, &args, 4), Vec::<String>::new());
+        assert_eq!(complete_line("pddb b", TABLE, &args, 4), vec!["pddb basislist", "pddb basisunlock "]);
+        assert_eq!(complete_line("pddb ", TABLE, &args, 2), vec!["pddb basislist", "pddb basisunlock "]);
+        assert!(complete_line("nope x", TABLE, &args, 4).is_empty());
+    }
+
+    #[test]
+    fn arguments() {
+        assert_eq!(
+            complete_line("pddb basisunlock ", TABLE, &args, 4),
+            vec!["pddb basisunlock home", "pddb basisunlock work"]
+        );
+        assert_eq!(complete_line("pddb query w", TABLE, &args, 4), vec!["pddb query wlan.networks:"]);
+        assert_eq!(
+            complete_line("pddb query wlan.networks:c", TABLE, &args, 4),
+            vec!["pddb query wlan.networks:cafe"]
+        );
+        assert!(complete_line("pddb basislist x", TABLE, &args, 4).is_empty());
+    }
+}
@@ -25,7 +25,7 @@ impl<'a> ShellCmdApi<'a> for Echo {
 ```
 
 Once you've added your command to the directory, go to the `cmds.rs` file, and follow
-the four-step instructions embedded within the file, starting around line 40.
+the five-step instructions embedded within the file, starting around line 40.
 
 Check for more detailed docs under Modules/cmds "Shell Chat" below
 */
@@ -43,6 +43,9 @@ use xous_ipc::Buffer;
 #[doc = include_str!("../README.md")]
 mod cmds;
 use cmds::*;
+mod script;
+#[cfg(not(feature = "tts"))]
+mod completion;
 
 #[cfg(not(feature = "no-codec"))]
 mod oqc_test;
@@ -415,6 +418,9 @@ fn wrapped_main() -> ! {
     let tts = TtsFrontend::new(&xns).unwrap();
 
     let mut repl = Repl::new(&xns, shch_sid);
+    // the shell plugin is the predictor unless text to speech is on
+    #[cfg(not(feature = "tts"))]
+    completion::start(repl.env.completion_table());
     let mut update_repl = false;
     let mut was_callback = false;
 
@@ -0,0 +1,332 @@
+//! Scripting on top of the shell's one-command-per-line model.
+//!
+//! Lines typed into the shell are run as they are, so nothing in an argument, like a password, is taken
+//! for an operator. A typed line only goes through the scripting below if it starts with `sh`; the lines
+//! of a script always do.
+//!
+//! A line can hold several commands: `;` runs the next one regardless, `&&` only if the previous one
+//! succeeded. `set NAME value` defines a variable, which later commands use as `$NAME` or `${NAME}`; `$?`
+//! is 0 if the previous command succeeded. `> dict:key` at the end of a command writes its output to a
+//! PDDB key instead of the screen, and `>> dict:key` adds to the end of the key. Text between single
+//! quotes is taken as it is, and `\` takes the character after it as it is; the quotes and backslashes
+//! are removed before the command runs.
+//!
+//! Scripts are kept in the PDDB, one line per command, and managed with the `script` command.
+//! `run <script> [args]` runs one; within it, `$1`..`$9` are the args. A command fails if it isn't
+//! known or it returns an error; the output of a command is not looked at. Results that come back
+//! later through callbacks, like `net ping` replies, are shown as they arrive, so the script doesn't
+//! wait for them.
+
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom, Write};
+
+/// PDDB dictionary that holds the scripts, one key per script
+pub(crate) const SCRIPT_DICT: &str = "shellchat.scripts";
+/// How deep scripts can run other scripts
+pub(crate) const MAX_SCRIPT_DEPTH: usize = 4;
+/// Typed lines that start with this are run as a line of script
+pub(crate) const SCRIPT_PREFIX: &str = "sh";
+
+/// How a command is joined to the one before it
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Chain {
+    /// `;`, or the first command of a line
+    Always,
+    /// `&&`
+    OnSuccess,
+}
+
+/// The state that outlives a line: the variables, and a connection to the PDDB for scripts and
+/// redirection
+pub(crate) struct ScriptEnv {
+    pddb: pddb::Pddb,
+    pub vars: HashMap<String, String>,
+}
+
+impl ScriptEnv {
+    pub fn new() -> Self { ScriptEnv { pddb: pddb::Pddb::new(), vars: HashMap::new() } }
+
+    /// The commands of a script, without blank lines and `#` comments
+    pub fn load(&self, name: &str) -> Result<Vec<String>, std::io::Error> {
+        let mut key = self.pddb.get(SCRIPT_DICT, name, None, false, false, None, None::<fn()>)?;
+        let mut text = String::new();
+        key.read_to_string(&mut text)?;
+        Ok(text
+            .lines()
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(String::from)
+            .collect())
+    }
+
+    /// Writes `text` to the key at `target` (`dict:key`), after what is already there if `append` is set
+    pub fn redirect(&self, target: &str, text: &str, append: bool) -> Result<(), std::io::Error> {
+        let (dict, key_name) = target.split_once(':').ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "target must be of the form dict:key")
+        })?;
+        if !append {
+            // delete the key first, so no part of a longer value is left behind
+            self.pddb.delete_key(dict, key_name, None).ok();
+        }
+        let mut key = self.pddb.get(dict, key_name, None, true, true, Some(text.len()), None::<fn()>)?;
+        key.seek(SeekFrom::End(0))?;
+        key.write_all(text.as_bytes())?;
+        if append {
+            key.write_all(b"\n")?;
+        }
+        self.pddb.sync().ok();
+        Ok(())
+    }
+}
+
+/// Splits a line into its commands, each with how it is joined to the one before
+pub(crate) fn split(line: &str) -> Vec<(Chain, &str)> {
+    let mut steps = Vec::new();
+    let mut chain = Chain::Always;
+    let mut rest = line;
+    loop {
+        let mut active = unquoted(rest).peekable();
+        let mut next = None;
+        while let Some((i, c)) = active.next() {
+            if c == ';' {
+                next = Some((i, 1, Chain::Always));
+            } else if c == '&' && active.peek() == Some(&(i + 1, '&')) {
+                next = Some((i, 2, Chain::OnSuccess));
+            } else {
+                continue;
+            }
+            break;
+        }
+        let (step, next_chain) = match next {
+            Some((i, len, c)) => {
+                let step = &rest[..i];
+                rest = &rest[i + len..];
+                (step, Some(c))
+            }
+            None => (rest, None),
+        };
+        if !step.trim().is_empty() {
+            steps.push((chain, step.trim()));
+        }
+        match next_chain {
+            Some(c) => chain = c,
+            None => break,
+        }
+    }
+    steps
+}
+
+/// Separates a trailing `> dict:key` or `>> dict:key` from a command. Returns the command, and the
+/// target and whether to append, if there is one.
+pub(crate) fn redirection(cmd: &str) -> (&str, Option<(&str, bool)>) {
+    match unquoted(cmd).find(|&(_, c)| c == '>').map(|(i, _)| i) {
+        Some(i) => {
+            let append = cmd[i + 1..].starts_with('>');
+            let target = cmd[i + if append { 2 } else { 1 }..].trim();
+            (cmd[..i].trim_end(), Some((target, append)))
+        }
+        None => (cmd, None),
+    }
+}
+
+/// Substitutes the variables in a command. `$1`..`$9` are taken from `args`; unknown variables are
+/// empty, and `$$` is a `$`. Values are escaped, so what they hold is never taken for an operator.
+pub(crate) fn expand(cmd: &str, vars: &HashMap<String, String>, args: &[String]) -> String {
+    let mut out = String::new();
+    let mut chars = cmd.chars().peekable();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        if quoted || c == '\\' || c == '\'' {
+            out.push(c);
+            match c {
+                '\'' => quoted = !quoted,
+                '\\' if !quoted => out.extend(chars.next()),
+                _ => {}
+            }
+            continue;
+        }
+        if c != '$' {
+            out.push(c);
+            continue;
+        }
+        let mut name = String::new();
+        match chars.peek() {
+            Some('$') => {
+                chars.next();
+                out.push_str("\\$");
+                continue;
+            }
+            Some('?') => {
+                chars.next();
+                name.push('?');
+            }
+            Some('{') => {
+                chars.next();
+                for n in chars.by_ref() {
+                    if n == '}' {
+                        break;
+                    }
+                    name.push(n);
+                }
+            }
+            _ => {
+                while let Some(n) = chars.peek() {
+                    if !is_name_char(*n) {
+                        break;
+                    }
+                    name.push(*n);
+                    chars.next();
+                }
+            }
+        }
+        if name.is_empty() {
+            out.push('$');
+        } else if let Ok(index) = name.parse::<usize>() {
+            if let Some(arg) = index.checked_sub(1).and_then(|i| args.get(i)) {
+                out.push_str(&escape(arg));
+            }
+        } else if let Some(value) = vars.get(&name) {
+            out.push_str(&escape(value));
+        }
+    }
+    out
+}
+
+/// The line of script in a typed line, if it starts with `sh`
+pub(crate) fn interactive(line: &str) -> Option<&str> {
+    let line = line.trim_start();
+    match line.split_once(' ') {
+        Some((SCRIPT_PREFIX, rest)) => Some(rest),
+        None if line.trim_end() == SCRIPT_PREFIX => Some(""),
+        _ => None,
+    }
+}
+
+/// The characters of `text` that are outside of quotes and not escaped, with their byte offsets
+fn unquoted(text: &str) -> impl Iterator<Item = (usize, char)> + '_ {
+    let mut quoted = false;
+    let mut escaped = false;
+    text.char_indices().filter(move |&(_, c)| {
+        if escaped {
+            escaped = false;
+        } else if c == '\'' {
+            quoted = !quoted;
+        } else if c == '\\' && !quoted {
+            escaped = true;
+        } else {
+            return !quoted;
+        }
+        false
+    })
+}
+
+/// Removes the quotes and the escaping backslashes from a command
+pub(crate) fn unquote(text: &str) -> String {
+    let mut out = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => quoted = !quoted,
+            '\\' if !quoted => out.extend(chars.next()),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Escapes the characters that would otherwise be taken for quotes or operators
+fn escape(text: &str) -> String {
+    let mut out = String::new();
+    for c in text.chars() {
+        if "\\';&>$".contains(c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn is_name_char(c: char) -> bool { c.is_ascii_alphanumeric() || c == '_' }
+
+/// Whether `name` can be set as a variable
+pub(crate) fn is_var_name(name: &str) -> bool {
+    name.chars().next().map_or(false, |c| !c.is_ascii_digit()) && name.chars().all(is_name_char)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chains_and_redirection() {
+        assert_eq!(
+            split("wlan join; net ping 1.1.1.1 && net tcpget bunniefoo.com/bunnie/test.txt"),
+            vec![
+                (Chain::Always, "wlan join"),
+                (Chain::Always, "net ping 1.1.1.1"),
+                (Chain::OnSuccess, "net tcpget bunniefoo.com/bunnie/test.txt"),
+            ]
+        );
+        assert_eq!(split(" ; ver xous;"), vec![(Chain::Always, "ver xous")]);
+        assert_eq!(redirection("ver xous > qa:version"), ("ver xous", Some(("qa:version", false))));
+        assert_eq!(redirection("ver ec>>qa:log"), ("ver ec", Some(("qa:log", true))));
+        assert_eq!(redirection("ver xous"), ("ver xous", None));
+    }
+
+    #[test]
+    fn typed_lines() {
+        // only typed lines that start with `sh` are scripted; anything else runs as it was typed
+        let password = "wlan setpass pa$$;w0rd&&x>y $HOME";
+        assert_eq!(interactive(password), None);
+        assert_eq!(interactive("shutdown; ver xous"), None);
+        assert_eq!(interactive("  sh ver xous > qa:version"), Some("ver xous > qa:version"));
+        assert_eq!(interactive("sh"), Some(""));
+    }
+
+    #[test]
+    fn quoting() {
+        let vars = HashMap::new();
+        let line = "wlan setpass 'pa$$;w0rd&&x>y' && ver\\; xous \\> \\$1 \\\\";
+        let steps = split(line);
+        assert_eq!(
+            steps,
+            vec![
+                (Chain::Always, "wlan setpass 'pa$$;w0rd&&x>y'"),
+                (Chain::OnSuccess, "ver\\; xous \\> \\$1 \\\\")
+            ]
+        );
+        let first = expand(steps[0].1, &vars, &[]);
+        assert_eq!(redirection(&first), (first.as_str(), None));
+        assert_eq!(unquote(&first), "wlan setpass pa$$;w0rd&&x>y");
+        let second = expand(steps[1].1, &vars, &["arg".to_string()]);
+        assert_eq!(redirection(&second), (second.as_str(), None));
+        assert_eq!(unquote(&second), "ver; xous > $1 \\");
+        // an unterminated quote runs to the end of the line
+        assert_eq!(split("echo 'a; b"), vec![(Chain::Always, "echo 'a; b")]);
+        assert_eq!(redirection("echo 'a > b' > qa:x"), ("echo 'a > b'", Some(("qa:x", false))));
+    }
+
+    #[test]
+    fn values_stay_literal() {
+        let mut vars = HashMap::new();
+        vars.insert("PASS".to_string(), "a;b&&c>d'e$f\\g".to_string());
+        let args = vec!["1>2".to_string()];
+        let cmd = expand("wlan setpass $PASS $1 $$", &vars, &args);
+        assert_eq!(redirection(&cmd), (cmd.as_str(), None));
+        assert_eq!(unquote(&cmd), "wlan setpass a;b&&c>d'e$f\\g 1>2 $");
+    }
+
+    #[test]
+    fn variables() {
+        let mut vars = HashMap::new();
+        vars.insert("HOST".to_string(), "1.1.1.1".to_string());
+        vars.insert("?".to_string(), "0".to_string());
+        let args = vec!["ssid".to_string()];
+        assert_eq!(expand("net ping $HOST", &vars, &args), "net ping 1.1.1.1");
+        assert_eq!(expand("echo ${HOST}x $? $1 $2 $NOPE $$ $", &vars, &args), "echo 1.1.1.1x 0 ssid   \\$ $");
+        assert!(is_var_name("HOST_2"));
+        assert!(!is_var_name("2HOST"));
+        assert!(!is_var_name("?"));
+    }
+}
@@ -40,6 +40,13 @@ const SERVER_NAME_STATUS: &str = "_Status_";
 /// but not so long that we're likely to have expired compatibility revision data
 /// in the header metadata. Initially, it's set at one day until it is automatically deleted.
 const BACKUP_EXPIRATION_HOURS: i64 = 24;
+/// State of charge, in percent, at or below which an urgent notification is posted while on battery.
+const BATTERY_CRITICAL_SOC: u8 = 5;
+/// Incorrect unlock PINs allowed before the PIN prompt is held back. Past that, the wait doubles with
+/// every incorrect PIN, from `UNLOCK_BACKOFF_MS` up to `UNLOCK_BACKOFF_MAX_MS`.
+const UNLOCK_FREE_ATTEMPTS: u32 = 3;
+const UNLOCK_BACKOFF_MS: u64 = 5_000;
+const UNLOCK_BACKOFF_MAX_MS: u64 = 300_000;
 
 #[derive(Debug, num_derive::FromPrimitive, num_derive::ToPrimitive)]
 pub(crate) enum StatusOpcode {
@@ -96,6 +103,14 @@ pub(crate) enum StatusOpcode {
 
     /// Raise the preferences menu
     Preferences,
+    /// Show the notification history
+    NotificationHistory,
+    /// Pick an app to tile above the shell, or go back to a single app
+    SplitView,
+    /// Lock the screen: from the main menu, the idle timer, or the GAM when the lock key is held
+    LockScreen,
+    /// Result of a PIN check from the unlock thread
+    UnlockResult,
     Quit,
 }
 
@@ -123,6 +138,7 @@ pub fn pump_thread(
     last_key_hit_secs: Arc<AtomicU32>,
     autosleep_duration_mins: Arc<AtomicU32>,
     reboot_on_autosleep: Arc<AtomicBool>,
+    screen_locked: Arc<AtomicBool>,
 ) {
     let ticktimer = ticktimer_server::Ticktimer::new().unwrap();
     let xns = xous_names::XousNames::new().unwrap();
@@ -155,6 +171,15 @@ pub fn pump_thread(
             let last_key_hit_duration_mins =
                 ((ticktimer.elapsed_ms() / 1000) as u32 - last_key_hit_secs.load(Ordering::SeqCst)) / 60;
             if last_key_hit_duration_mins >= asdm {
+                if !screen_locked.load(Ordering::SeqCst) {
+                    // lock before anything else, so the device is locked even if it can't sleep
+                    log::info!("Idle, locking screen...");
+                    send_message(
+                        conn as u32,
+                        Message::new_scalar(StatusOpcode::LockScreen.to_usize().unwrap(), 0, 0, 0, 0),
+                    )
+                    .ok();
+                }
                 log::debug!("autosleep duration hit, trying to sleep");
                 if cur_power_state == false {
                     // is_plugged_in() is false
@@ -235,6 +260,7 @@ fn wrapped_main() -> ! {
     let last_key_hit_secs = Arc::new(AtomicU32::new(0)); // rolls over in 126 years. Can't AtomicU64 on a 32-bit platform.
     let autosleep_duration_mins = Arc::new(AtomicU32::new(0));
     let reboot_on_autosleep = Arc::new(AtomicBool::new(false));
+    let screen_locked = Arc::new(AtomicBool::new(false));
     let autobacklight_duration_secs = Arc::new(AtomicU32::new(0));
     let pump_conn = xous::connect(status_sid).unwrap();
     let _ = thread::spawn({
@@ -242,6 +268,7 @@ fn wrapped_main() -> ! {
         let last_key_hit_secs = last_key_hit_secs.clone();
         let autosleep_duration_mins = autosleep_duration_mins.clone();
         let reboot_on_autosleep = reboot_on_autosleep.clone();
+        let screen_locked = screen_locked.clone();
         move || {
             pump_thread(
                 pump_conn as _,
@@ -249,6 +276,7 @@ fn wrapped_main() -> ! {
                 last_key_hit_secs,
                 autosleep_duration_mins,
                 reboot_on_autosleep,
+                screen_locked,
             );
         }
     });
@@ -323,6 +351,13 @@ fn wrapped_main() -> ! {
     // initialize to some "sane" mid-point defaults, so we don't trigger errors later on before the first real
     // battstat reading comes
     let mut stats = BattStats { voltage: 3700, soc: 50, current: 0, remaining_capacity: 650 };
+    // latches so the battery critical notification is posted once per discharge cycle
+    let mut battery_critical_notified = false;
+    // set while the unlock thread has a PIN prompt up
+    let unlock_pending = Arc::new(AtomicBool::new(false));
+    // incorrect PINs entered since the screen was last unlocked, and when the next prompt is allowed
+    let mut unlock_failures: u32 = 0;
+    let mut unlock_after_ms: u64 = 0;
 
     let llio = llio::Llio::new(&xns);
     let usb_hid = usb_device_xous::UsbHid::new();
@@ -342,6 +377,18 @@ fn wrapped_main() -> ! {
     security_tv.draw_border = false;
     security_tv.margin = Point::new(0, 0);
     security_tv.token = gam.claim_token(gam::STATUS_BAR_NAME).expect("couldn't request token"); // this is a shared magic word to identify this process
+    // we own the screen lock: the GAM relays lock requests from the keyboard back to us
+    gam.register_screen_lock(
+        security_tv.token.unwrap(),
+        status_sid,
+        StatusOpcode::LockScreen.to_u32().unwrap(),
+    )
+    .expect("couldn't register as the screen lock owner");
+    // only we can lock and unlock autotyping, for as long as the screen is locked
+    let autotype_lock_token = xous::create_server_id().unwrap().to_array();
+    usb_hid
+        .register_autotype_lock(autotype_lock_token)
+        .expect("couldn't register as the autotype lock owner");
     security_tv.clear_area = true;
     security_tv.invert = true;
     write!(&mut security_tv, "{}", t!("secnote.startup", locales::LANG)).unwrap();
@@ -790,6 +837,7 @@ fn wrapped_main() -> ! {
         let autosleep_duration_mins = autosleep_duration_mins.clone();
         let reboot_on_autosleep = reboot_on_autosleep.clone();
         let autobacklight_duration_secs = autobacklight_duration_secs.clone();
+        let kbd = kbd.clone();
         move || {
             let pddb = pddb::Pddb::new();
             let prefs = prefs_thread_clone.lock().unwrap();
@@ -845,7 +893,25 @@ fn wrapped_main() -> ! {
                 xous::Result::Ok
             });
 
-            // keyboard mapping is restored directly by the keyboard hardware
+            // keyboard mapping is restored directly by the keyboard hardware, except for custom maps, which
+            // only exist in the PDDB
+            if !all_prefs.custom_keymap.is_empty() {
+                match preferences::load_custom_keymap(&all_prefs.custom_keymap) {
+                    Ok(map) => kbd.lock().unwrap().set_custom_keymap(&map).unwrap_or_else(|error| {
+                        log::error!("cannot install custom keymap: {:?}", error);
+                    }),
+                    Err(error) => {
+                        log::error!("cannot load custom keymap {}: {}", all_prefs.custom_keymap, error)
+                    }
+                }
+            }
+            for map in [KeyMap::Qwerty, KeyMap::Azerty, KeyMap::Qwertz, KeyMap::Dvorak, KeyMap::Custom] {
+                if all_prefs.dead_keys & keyboard::dead_key_bit(map) != 0 {
+                    kbd.lock().unwrap().set_dead_keys(map, true).unwrap_or_else(|error| {
+                        log::error!("cannot turn on dead keys: {:?}", error);
+                    });
+                }
+            }
             #[cfg(not(feature = "no-codec"))]
             {
                 log::info!("audio enable state: {}", all_prefs.audio_enabled);
@@ -1038,6 +1104,21 @@ fn wrapped_main() -> ! {
             }
             Some(StatusOpcode::BattStats) => msg_scalar_unpack!(msg, lo, hi, _, _, {
                 stats = [lo, hi].into();
+                if stats.soc <= BATTERY_CRITICAL_SOC && stats.soc != 0xdd && !llio.is_plugged_in() {
+                    if !battery_critical_notified {
+                        gam.post_notification(
+                            security_tv.token.unwrap(),
+                            t!("notifications.battery_critical", locales::LANG),
+                            &t!("notifications.battery_critical_body", locales::LANG)
+                                .replace("{soc}", &stats.soc.to_string()),
+                            gam::NotificationPriority::Urgent,
+                        )
+                        .ok();
+                        battery_critical_notified = true;
+                    }
+                } else if stats.soc > BATTERY_CRITICAL_SOC {
+                    battery_critical_notified = false;
+                }
                 // have to clear the entire rectangle area, because the SSID has a variable width and can be
                 // much wider or shorter than battstats
                 gam.draw_rectangle(status_gid, stats_rect).ok();
@@ -1118,12 +1199,118 @@ fn wrapped_main() -> ! {
             Some(StatusOpcode::WifiStats) => {
                 let buffer =
                     unsafe { xous_ipc::Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
-                wifi_status = WlanStatus::from_ipc(buffer.to_original::<com::WlanStatusIpc, _>().unwrap());
+                let new_status = WlanStatus::from_ipc(buffer.to_original::<com::WlanStatusIpc, _>().unwrap());
+                if let (Some(old_ssid), None) = (wifi_status.ssid.as_ref(), new_status.ssid.as_ref()) {
+                    gam.post_notification(
+                        security_tv.token.unwrap(),
+                        t!("notifications.wifi_lost", locales::LANG),
+                        old_ssid.name.as_str().unwrap_or("UTF-8 Error"),
+                        gam::NotificationPriority::Normal,
+                    )
+                    .ok();
+                }
+                wifi_status = new_status;
             }
             Some(StatusOpcode::Preferences) => {
                 ticktimer.sleep_ms(100).ok(); // yield for a moment to allow the previous menu to close
                 gam.raise_menu(gam::PREFERENCES_MENU_NAME).unwrap();
             }
+            Some(StatusOpcode::NotificationHistory) => {
+                ticktimer.sleep_ms(100).ok(); // yield for a moment to allow the previous menu to close
+                let history = gam.notification_history().unwrap_or(Vec::new());
+                if history.len() == 0 {
+                    modals.show_notification(t!("notifications.none", locales::LANG), None).ok();
+                    continue;
+                }
+                let now = ticktimer.elapsed_ms();
+                let mut listing = std::string::String::new();
+                for n in history.iter() {
+                    let mins_ago = now.saturating_sub(n.timestamp_ms) / 60_000;
+                    write!(
+                        listing,
+                        "[{}] {} ({}{})\n{}\n\n",
+                        n.source.as_str().unwrap_or(""),
+                        n.title.as_str().unwrap_or(""),
+                        mins_ago,
+                        t!("notifications.mins_ago", locales::LANG),
+                        n.body.as_str().unwrap_or("")
+                    )
+                    .ok();
+                }
+                modals.show_notification(listing.trim_end(), None).ok();
+                modals.add_list_item(t!("rootkeys.gwup.yes", locales::LANG)).ok();
+                modals.add_list_item(t!("rootkeys.gwup.no", locales::LANG)).ok();
+                if let Ok(response) = modals.get_radiobutton(t!("notifications.clear_confirm", locales::LANG))
+                {
+                    if response.as_str() == t!("rootkeys.gwup.yes", locales::LANG) {
+                        gam.clear_notifications(security_tv.token.unwrap()).ok();
+                    }
+                }
+            }
+            Some(StatusOpcode::LockScreen) => {
+                if screen_locked.load(Ordering::SeqCst) {
+                    continue;
+                }
+                if !pddb_poller.is_mounted_nonblocking() {
+                    // nothing to protect yet, and no PIN to unlock with
+                    log::info!("PDDB not mounted, screen lock ignored");
+                    continue;
+                }
+                ticktimer.sleep_ms(100).ok(); // yield for a moment to allow the previous menu to close
+                screen_locked.store(true, Ordering::SeqCst);
+                usb_hid.set_autotype_lock(autotype_lock_token, true).ok();
+                gam.set_screen_lock(security_tv.token.unwrap(), true).expect("couldn't lock screen");
+                gam.redraw().ok();
+            }
+            Some(StatusOpcode::UnlockResult) => msg_scalar_unpack!(msg, result, _, _, _, {
+                unlock_pending.store(false, Ordering::SeqCst);
+                match result {
+                    1 => {
+                        log::info!("screen unlocked");
+                        unlock_failures = 0;
+                        screen_locked.store(false, Ordering::SeqCst);
+                        usb_hid.set_autotype_lock(autotype_lock_token, false).ok();
+                        gam.set_screen_lock(security_tv.token.unwrap(), false)
+                            .expect("couldn't unlock screen");
+                        // the unlock counts as activity
+                        last_key_hit_secs.store((ticktimer.elapsed_ms() / 1000) as u32, Ordering::SeqCst);
+                    }
+                    2 => {
+                        // no PIN to check against, the only way out is through the boot PIN prompt
+                        log::warn!("can't verify unlock PIN, rebooting to lock the device");
+                        send_message(
+                            cb_cid,
+                            Message::new_scalar(StatusOpcode::Reboot.to_usize().unwrap(), 0, 0, 0, 0),
+                        )
+                        .ok();
+                    }
+                    _ => {
+                        unlock_failures += 1;
+                        // modals can't show through the curtain, so use a toast
+                        if unlock_failures >= UNLOCK_FREE_ATTEMPTS {
+                            let doublings = (unlock_failures - UNLOCK_FREE_ATTEMPTS).min(16);
+                            let backoff = (UNLOCK_BACKOFF_MS << doublings).min(UNLOCK_BACKOFF_MAX_MS);
+                            unlock_after_ms = ticktimer.elapsed_ms() + backoff;
+                            gam.post_notification(
+                                security_tv.token.unwrap(),
+                                &t!("screenlock.backoff", locales::LANG)
+                                    .replace("{secs}", &(backoff / 1000).to_string()),
+                                "",
+                                gam::NotificationPriority::Normal,
+                            )
+                            .ok();
+                        } else {
+                            gam.post_notification(
+                                security_tv.token.unwrap(),
+                                t!("screenlock.bad_pin", locales::LANG),
+                                "",
+                                gam::NotificationPriority::Normal,
+                            )
+                            .ok();
+                        }
+                    }
+                }
+            }),
             Some(StatusOpcode::Pump) => {
                 let elapsed_time = ticktimer.elapsed_ms();
                 {
@@ -1320,6 +1507,42 @@ fn wrapped_main() -> ! {
                 send_message(cb_cid, Message::new_scalar(StatusOpcode::Pump.to_usize().unwrap(), 0, 0, 0, 0))
                     .expect("couldn't trigger status update");
             }
+            Some(StatusOpcode::SplitView) => {
+                ticktimer.sleep_ms(100).ok(); // yield for a moment to allow the previous menu to close
+                let mut apps = Vec::<(&str, &str)>::new();
+                let mut index = 0;
+                while let (Ok(name), Ok(context)) =
+                    (app_autogen::app_index_to_name(index), app_autogen::app_index_to_context(index))
+                {
+                    // only apps with a chat layout can be tiled, and the shell is always the other pane
+                    if context != gam::APP_NAME_SHELLCHAT && gam.can_split(context) {
+                        apps.push((name, context));
+                    }
+                    index += 1;
+                }
+                if apps.is_empty() {
+                    modals.show_notification(t!("splitview.no_apps", locales::LANG), None).ok();
+                } else {
+                    modals.add_list_item(t!("splitview.full_screen", locales::LANG)).ok();
+                    for (name, _) in apps.iter() {
+                        modals.add_list_item(name).ok();
+                    }
+                    if let Ok(choice) = modals.get_radiobutton(t!("splitview.top_pane", locales::LANG)) {
+                        // the shell always takes the bottom pane, so commands can be typed while the other
+                        // app is watched
+                        let result = match apps.iter().find(|(name, _)| *name == choice.as_str()) {
+                            Some((_, context)) => {
+                                gam.split_view(context, gam::APP_NAME_SHELLCHAT, security_tv.token.unwrap())
+                            }
+                            None => gam.unsplit_view(security_tv.token.unwrap()),
+                        };
+                        if let Err(e) = result {
+                            log::warn!("couldn't change split view: {:?}", e);
+                            modals.show_notification(t!("splitview.failed", locales::LANG), None).ok();
+                        }
+                    }
+                }
+            }
             Some(StatusOpcode::SwitchToApp) => msg_scalar_unpack!(msg, index, _, _, _, {
                 ticktimer.sleep_ms(100).ok();
                 let app_name = app_autogen::app_index_to_name(index).expect("app index not found");
@@ -1406,6 +1629,44 @@ fn wrapped_main() -> ! {
                 // this will roll over in 126 years of uptime. meh?
                 last_key_hit_secs.store((ticktimer.elapsed_ms() / 1000) as u32, Ordering::SeqCst);
 
+                // any key hit on a locked screen raises the PIN prompt, unless it is held back after
+                // incorrect PINs
+                if screen_locked.load(Ordering::SeqCst)
+                    && ticktimer.elapsed_ms() >= unlock_after_ms
+                    && !unlock_pending.swap(true, Ordering::SeqCst)
+                {
+                    // The check runs in its own thread, because the prompt blocks until the user is done with
+                    // it. The thread talks to the keys server directly, so the `keys` lock isn't held while
+                    // it waits; the keys server answers with the same codes as `UnlockResult`.
+                    let (key_conn, verify_op) = {
+                        let keys = keys.lock().unwrap();
+                        (keys.conn(), keys.get_verify_boot_password_op())
+                    };
+                    thread::spawn({
+                        let conn = cb_cid;
+                        move || {
+                            let result = match send_message(
+                                key_conn,
+                                Message::new_blocking_scalar(verify_op as usize, 0, 0, 0, 0),
+                            ) {
+                                Ok(xous::Result::Scalar1(result)) => result,
+                                _ => 0,
+                            };
+                            send_message(
+                                conn,
+                                Message::new_scalar(
+                                    StatusOpcode::UnlockResult.to_usize().unwrap(),
+                                    result,
+                                    0,
+                                    0,
+                                    0,
+                                ),
+                            )
+                            .ok();
+                        }
+                    });
+                }
+
                 if !*autobacklight_enabled.lock().unwrap() {
                     log::trace!("ignoring keypress, automatic backlight is disabled");
                     continue;
@@ -1600,7 +1861,18 @@ fn wrapped_main() -> ! {
                 metadata.ec_ver = com.get_ec_sw_tag().unwrap().into();
                 metadata.op = BackupOp::Backup;
                 metadata.dna = llio.soc_dna().unwrap().to_le_bytes();
-                let map = kbd.lock().unwrap().get_keymap().expect("couldn't get key mapping");
+                let map = match kbd.lock().unwrap().get_keymap().expect("couldn't get key mapping") {
+                    // a custom map comes back with the PDDB; until then, type on the map it is based on
+                    KeyMap::Custom => prefs
+                        .lock()
+                        .unwrap()
+                        .custom_keymap_or_default()
+                        .ok()
+                        .and_then(|name| preferences::load_custom_keymap(&name).ok())
+                        .map(|custom| custom.base)
+                        .unwrap_or(KeyMap::Qwerty),
+                    map => map,
+                };
                 let map_serialize: BackupKeyboardLayout = map.into();
                 metadata.kbd_layout = map_serialize.into();
                 // the backup process is coded to accept the option of no checksums, but the UX currently
@@ -55,6 +55,22 @@ pub fn create_main_menu(
         close_on_select: true,
     });
 
+    menuitems.push(MenuItem {
+        name: String::from_str(t!("mainmenu.splitview", locales::LANG)),
+        action_conn: Some(status_conn),
+        action_opcode: StatusOpcode::SplitView.to_u32().unwrap(),
+        action_payload: MenuPayload::Scalar([0, 0, 0, 0]),
+        close_on_select: true,
+    });
+
+    menuitems.push(MenuItem {
+        name: String::from_str(t!("mainmenu.notifications", locales::LANG)),
+        action_conn: Some(status_conn),
+        action_opcode: StatusOpcode::NotificationHistory.to_u32().unwrap(),
+        action_payload: MenuPayload::Scalar([0, 0, 0, 0]),
+        close_on_select: true,
+    });
+
     menuitems.push(MenuItem {
         name: String::from_str(t!("mainmenu.preferences", locales::LANG)),
         action_conn: Some(status_conn),
@@ -144,6 +160,14 @@ pub fn create_main_menu(
         });
     }
 
+    menuitems.push(MenuItem {
+        name: String::from_str(t!("mainmenu.lockscreen", locales::LANG)),
+        action_conn: Some(status_conn),
+        action_opcode: StatusOpcode::LockScreen.to_u32().unwrap(),
+        action_payload: MenuPayload::Scalar([0, 0, 0, 0]),
+        close_on_select: true,
+    });
+
     menuitems.push(MenuItem {
         name: String::from_str(t!("mainmenu.lockdevice", locales::LANG)),
         action_conn: Some(status_conn),
@@ -6,6 +6,11 @@ use userprefs::Manager;
 
 use crate::wifi;
 
+/// Upper bounds of the timeout spinners
+const BACKLIGHT_TIMEOUT_MAX_SECS: i32 = 3600;
+const AUTOSLEEP_TIMEOUT_MAX_MINS: i32 = 24 * 60;
+const SHELL_HISTORY_MAX_LEN: i32 = 200;
+
 pub trait PrefHandler {
     // If handle() returns true, it has handled the operation.
     fn handle(&mut self, op: usize) -> bool;
@@ -23,6 +28,8 @@ enum DevicePrefsOp {
     AutoUnmountTimeout,
     RebootOnAutoSleep,
     KeyboardLayout,
+    DeadKeys,
+    ShellHistoryLength,
     WLANMenu,
     SetTime,
     SetTimezone,
@@ -53,6 +60,8 @@ impl Display for DevicePrefsOp {
             Self::ConnectKnownNetworksOnBoot => write!(f, "{}", t!("prefs.wifi_connect_auto", locales::LANG)),
             Self::WifiKill => write!(f, "{}", t!("prefs.wifi_kill", locales::LANG)),
             Self::KeyboardLayout => write!(f, "{}", t!("prefs.keyboard_layout", locales::LANG)),
+            Self::DeadKeys => write!(f, "{}", t!("prefs.dead_keys", locales::LANG)),
+            Self::ShellHistoryLength => write!(f, "{}", t!("prefs.shell_history_len", locales::LANG)),
             Self::WLANMenu => write!(f, "{}", t!("prefs.wifi_setting", locales::LANG)),
             Self::SetTime => write!(f, "{}", t!("mainmenu.set_rtc", locales::LANG)),
             Self::SetTimezone => write!(f, "{}", t!("mainmenu.set_tz", locales::LANG)),
@@ -67,9 +76,11 @@ impl Display for DevicePrefsOp {
 }
 
 #[derive(Debug)]
-enum DevicePrefsError {
+pub(crate) enum DevicePrefsError {
     PrefsError(userprefs::Error),
     XousError(xous::Error),
+    IoError(std::io::Error),
+    KeyMapError(keyboard::KeyMapError),
 }
 
 impl From<userprefs::Error> for DevicePrefsError {
@@ -80,6 +91,14 @@ impl From<xous::Error> for DevicePrefsError {
     fn from(e: xous::Error) -> Self { Self::XousError(e) }
 }
 
+impl From<std::io::Error> for DevicePrefsError {
+    fn from(e: std::io::Error) -> Self { Self::IoError(e) }
+}
+
+impl From<keyboard::KeyMapError> for DevicePrefsError {
+    fn from(e: keyboard::KeyMapError) -> Self { Self::KeyMapError(e) }
+}
+
 impl Display for DevicePrefsError {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         use DevicePrefsError::*;
@@ -87,6 +106,8 @@ impl Display for DevicePrefsError {
         match self {
             PrefsError(e) => write!(f, "Preferences engine error: {:?}", e),
             XousError(e) => write!(f, "Kernel error: {:#?}", e),
+            IoError(e) => write!(f, "Storage error: {}", e),
+            KeyMapError(e) => write!(f, "Keymap error: {}", e),
         }
     }
 }
@@ -211,6 +232,8 @@ impl DevicePrefs {
             AutoSleepTimeout,
             RebootOnAutoSleep,
             KeyboardLayout,
+            DeadKeys,
+            ShellHistoryLength,
             // Note: this vec sets the order of items in the preferences menu
             // The CI system assumes that the time setting items are always at
             // the bottom of the preferences menu, in this particular order.
@@ -249,6 +272,8 @@ impl DevicePrefs {
             AutoSleepTimeout => self.autosleep_timeout(),
             RebootOnAutoSleep => self.reboot_on_autosleep(),
             KeyboardLayout => self.keyboard_layout(),
+            DeadKeys => self.dead_keys(),
+            ShellHistoryLength => self.shell_history_len(),
             WLANMenu => self.wlan_menu(),
             SetTime => self.set_time_menu(),
             SetTimezone => self.set_timezone_menu(),
@@ -339,20 +364,14 @@ impl DevicePrefs {
 
         log::debug!("backlight timeout in store after closure: {}", cv);
 
-        let raw_timeout = self
-            .modals
-            .alert_builder(t!("prefs.autobacklight_duration_in_secs", locales::LANG))
-            .field(
-                Some(cv.to_string()),
-                Some(|tf| match tf.as_str().parse::<u64>() {
-                    Ok(_) => None,
-                    Err(_) => Some(xous_ipc::String::from_str(t!("prefs.autobacklight_err", locales::LANG))),
-                }),
-            )
-            .build()
-            .unwrap();
-
-        let new_timeout = raw_timeout.first().as_str().parse::<u64>().unwrap(); // we know this is a number, we checked with validator;
+        let new_timeout = self.modals.get_number(
+            t!("prefs.autobacklight_duration_in_secs", locales::LANG),
+            1,
+            BACKLIGHT_TIMEOUT_MAX_SECS,
+            5,
+            cv.min(BACKLIGHT_TIMEOUT_MAX_SECS as u64) as i32,
+            Some("s"),
+        )? as u64;
 
         Ok(self.up.set_autobacklight_timeout(new_timeout)?)
     }
@@ -360,20 +379,14 @@ impl DevicePrefs {
     fn autosleep_timeout(&self) -> Result<(), DevicePrefsError> {
         let cv = self.up.autosleep_timeout_or_default()?;
 
-        let raw_timeout = self
-            .modals
-            .alert_builder(t!("prefs.autosleep_duration_in_mins", locales::LANG))
-            .field(
-                Some(cv.to_string()),
-                Some(|tf| match tf.as_str().parse::<u64>() {
-                    Ok(_) => None,
-                    Err(_) => Some(xous_ipc::String::from_str(t!("prefs.autobacklight_err", locales::LANG))),
-                }),
-            )
-            .build()
-            .unwrap();
-
-        let new_timeout = raw_timeout.first().as_str().parse::<u64>().unwrap(); // we know this is a number, we checked with validator;
+        let new_timeout = self.modals.get_number(
+            t!("prefs.autosleep_duration_in_mins", locales::LANG),
+            0,
+            AUTOSLEEP_TIMEOUT_MAX_MINS,
+            5,
+            cv.min(AUTOSLEEP_TIMEOUT_MAX_MINS as u64) as i32,
+            Some("min"),
+        )? as u64;
 
         Ok(self.up.set_autosleep_timeout(new_timeout)?)
     }
@@ -480,31 +493,78 @@ impl DevicePrefs {
     }
 
     fn keyboard_layout(&mut self) -> Result<(), DevicePrefsError> {
-        let kl: usize = self.kbd.get_keymap().unwrap().into();
+        use keyboard::KeyMap;
 
-        let mappings = vec!["QWERTY", "AZERTY", "QWERTZ", "Dvorak"];
+        let current = match self.kbd.get_keymap()? {
+            KeyMap::Custom => self.up.custom_keymap_or_default()?,
+            map => map.to_string(),
+        };
 
-        self.modals.add_list(mappings.clone()).unwrap();
+        // the built-in maps, followed by any custom maps installed in the PDDB
+        let builtin = [KeyMap::Qwerty, KeyMap::Azerty, KeyMap::Qwertz, KeyMap::Dvorak];
+        let mut mappings: Vec<String> = builtin.iter().map(|map| map.to_string()).collect();
+        mappings.extend(pddb::Pddb::new().list_keys(keyboard::CUSTOM_KEYMAP_DICT, None).unwrap_or_default());
+
+        self.modals.add_list(mappings.iter().map(|name| name.as_str()).collect()).unwrap();
 
         let new_result = self
             .modals
-            .get_radiobutton(&format!(
-                "{} {}",
-                t!("prefs.current_setting", locales::LANG),
-                keyboard::KeyMap::from(kl)
-            ))
+            .get_radiobutton(&format!("{} {}", t!("prefs.current_setting", locales::LANG), current))
             .unwrap();
 
-        let new_result = match mappings.iter().position(|&elem| elem == new_result.as_str()) {
-            Some(val) => val,
-            None => 0,
-        };
-
-        self.kbd.set_keymap(keyboard::KeyMap::from(new_result)).unwrap();
+        match builtin.iter().find(|map| map.to_string() == new_result) {
+            Some(&map) => {
+                self.up.set_custom_keymap(String::new())?;
+                self.kbd.set_keymap(map)?;
+            }
+            None => {
+                let custom = load_custom_keymap(&new_result)?;
+                self.kbd.set_custom_keymap(&custom)?;
+                self.up.set_custom_keymap(new_result)?;
+            }
+        }
 
         Ok(())
     }
 
+    fn dead_keys(&mut self) -> Result<(), DevicePrefsError> {
+        // dead keys are set per keymap, so this applies to the current one
+        let map = self.kbd.get_keymap()?;
+        let bit = keyboard::dead_key_bit(map);
+        let mask = self.up.dead_keys_or_default()?;
+
+        self.modals.add_list(vec![t!("prefs.yes", locales::LANG), t!("prefs.no", locales::LANG)]).unwrap();
+        let new_result = yes_no_to_bool(
+            self.modals
+                .get_radiobutton(&format!(
+                    "{} {}",
+                    t!("prefs.current_setting", locales::LANG),
+                    bool_to_yes_no(mask & bit != 0)
+                ))
+                .unwrap()
+                .as_str(),
+        );
+
+        self.kbd.set_dead_keys(map, new_result)?;
+        Ok(self.up.set_dead_keys(if new_result { mask | bit } else { mask & !bit })?)
+    }
+
+    fn shell_history_len(&self) -> Result<(), DevicePrefsError> {
+        let cv = self.up.shell_history_len_or_value(userprefs::DEFAULT_SHELL_HISTORY_LEN)?;
+
+        // the shell plugin picks up the new length the next time it stores a command
+        let new_len = self.modals.get_number(
+            t!("prefs.shell_history_len_prompt", locales::LANG),
+            1,
+            SHELL_HISTORY_MAX_LEN,
+            1,
+            cv.min(SHELL_HISTORY_MAX_LEN as u32) as i32,
+            None,
+        )? as u32;
+
+        Ok(self.up.set_shell_history_len(new_len)?)
+    }
+
     #[cfg(not(feature = "no-codec"))]
     fn audio_on(&mut self) -> Result<(), DevicePrefsError> {
         self.codec.setup_8k_stream()?;
@@ -638,6 +698,17 @@ pub fn percentage_to_db(value: u32) -> i32 {
     (negated_val as i32 * -80) / 100
 }
 
+/// Reads and parses a user-installed keymap from the PDDB
+pub(crate) fn load_custom_keymap(name: &str) -> Result<keyboard::CustomKeyMap, DevicePrefsError> {
+    use std::io::Read;
+
+    let mut key =
+        pddb::Pddb::new().get(keyboard::CUSTOM_KEYMAP_DICT, name, None, false, false, None, None::<fn()>)?;
+    let mut text = String::new();
+    key.read_to_string(&mut text)?;
+    Ok(keyboard::CustomKeyMap::parse(name, &text)?)
+}
+
 fn yes_no_to_bool(val: &str) -> bool {
     if val == t!("prefs.yes", locales::LANG) {
         true
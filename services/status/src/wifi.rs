@@ -141,24 +141,26 @@ impl WLANMan {
     fn add_new_ssid(&mut self) -> Result<(), WLANError> {
         let connection_data = self
             .modals
-            .alert_builder(t!("wlan.ssid_entry", locales::LANG))
-            .field(
-                Some("SSID".to_string()),
-                Some(|text| {
-                    if text.as_str().is_empty() {
+            .form_builder(t!("wlan.ssid_entry", locales::LANG))
+            .text(
+                "SSID",
+                None,
+                Some(|value| {
+                    if value.as_str().unwrap_or("").is_empty() {
                         return Some(xous_ipc::String::from_str("SSID cannot be empty"));
                     }
 
                     None
                 }),
             )
-            .field(Some(t!("wlan.password", locales::LANG).to_string()), None)
+            .password(t!("wlan.password", locales::LANG), None)
             .build()
             .unwrap();
 
-        let content = connection_data.content();
-
-        self.store_connection_info(content[0].as_str(), content[1].as_str())
+        self.store_connection_info(
+            connection_data.text(0).unwrap_or(""),
+            connection_data.text(1).unwrap_or(""),
+        )
     }
 
     fn store_connection_info(&mut self, ssid: &str, pass: &str) -> Result<(), WLANError> {
@@ -29,6 +29,14 @@ pub(crate) enum Opcode {
     SetAutotypeRate = 10,
     /// Register a USB event observer
     RegisterUsbObserver = 11,
+    /// Suppress (or re-allow) keyboard autotyping, e.g. while the screen is locked (`AutotypeLock`)
+    SetAutotypeLock = 12,
+    /// Set the keyboard layout of the host and how characters outside of it are typed
+    SetHostLayout = 13,
+    /// "Type" a string to the keyboard, overriding the host layout for this string only
+    SendStringLayout = 14,
+    /// Claim the autotype lock with a token that `SetAutotypeLock` then has to present
+    RegisterAutotypeLock = 15,
 
     /// Send a U2F message
     U2fTx = 128,
@@ -54,6 +62,8 @@ pub(crate) enum Opcode {
     SerialClearHooks = 517,
     /// TRNG send poll
     SerialTrngPoll = 518,
+    /// Blocking write of binary data to the serial port
+    SerialSendBinary = 519,
 
     #[cfg(feature = "mass-storage")]
     SetBlockDevice = 1024,
@@ -75,6 +85,16 @@ pub(crate) enum Opcode {
     /// Unset HID descriptor and reset HIDv2 state
     HIDUnsetDescriptor = 1030,
 
+    // CCID
+    /// Wait for a command APDU from the smart card reader
+    CcidRxDeferred = 1040,
+
+    /// Send the response APDU to the last command
+    CcidTx = 1041,
+
+    /// A bump from the time extension process, while a command is being worked on
+    CcidTimeExtension = 1042,
+
     /// Handle the USB interrupt
     UsbIrqHandler = 2048,
     /// Suspend/resume callback
@@ -94,6 +114,96 @@ pub struct UsbString {
     pub sent: Option<u32>,
 }
 
+/// The keyboard layout the host computer expects. Autotype picks the keys that produce each character
+/// on this layout, including AltGr combinations and dead-key sequences. The tables follow the Windows
+/// flavor of each layout.
+#[derive(
+    num_derive::FromPrimitive,
+    num_derive::ToPrimitive,
+    Debug,
+    rkyv::Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+)]
+pub enum HostLayout {
+    /// Assume the host matches the keymap selected on this device
+    Native = 0,
+    Us101 = 1,
+    Dvorak = 2,
+    /// French AZERTY
+    Azerty = 3,
+    /// German QWERTZ
+    Qwertz = 4,
+    /// UK QWERTY
+    Uk = 5,
+    /// Swedish and Finnish
+    Nordic = 6,
+}
+impl Default for HostLayout {
+    fn default() -> Self { HostLayout::Native }
+}
+impl std::fmt::Display for HostLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HostLayout::Native => write!(f, "Same as device"),
+            HostLayout::Us101 => write!(f, "US"),
+            HostLayout::Dvorak => write!(f, "Dvorak"),
+            HostLayout::Azerty => write!(f, "AZERTY (French)"),
+            HostLayout::Qwertz => write!(f, "QWERTZ (German)"),
+            HostLayout::Uk => write!(f, "UK"),
+            HostLayout::Nordic => write!(f, "Nordic (Swedish/Finnish)"),
+        }
+    }
+}
+
+/// How characters that can't be reached on the host layout are typed. Each method relies on a feature
+/// of the host operating system, so it has to match the host.
+#[derive(num_derive::FromPrimitive, num_derive::ToPrimitive, Debug, Copy, Clone, Eq, PartialEq)]
+pub enum UnicodeEntry {
+    /// Skip the character
+    Off = 0,
+    /// Ctrl+Shift+U, the hex code point, then space. Supported by GTK and IBus.
+    Linux = 1,
+    /// Option held down while typing the UTF-16 code units in hex. Requires the "Unicode Hex Input"
+    /// input source to be selected on the host, which is based on the US layout.
+    MacOs = 2,
+    /// Alt held down while typing keypad-plus and the hex code point. Requires the `EnableHexNumpad`
+    /// registry setting on the host, and only reaches the Basic Multilingual Plane.
+    Windows = 3,
+}
+impl Default for UnicodeEntry {
+    fn default() -> Self { UnicodeEntry::Off }
+}
+impl std::fmt::Display for UnicodeEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UnicodeEntry::Off => write!(f, "Off"),
+            UnicodeEntry::Linux => write!(f, "Linux"),
+            UnicodeEntry::MacOs => write!(f, "macOS"),
+            UnicodeEntry::Windows => write!(f, "Windows"),
+        }
+    }
+}
+
+/// A string to autotype, with an optional host layout that applies to it instead of the one set with
+/// `SetHostLayout`.
+#[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Copy, Clone)]
+pub struct UsbAutotype {
+    pub s: xous_ipc::String<4000>,
+    pub layout: Option<HostLayout>,
+    pub sent: Option<u32>,
+}
+
+#[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Copy, Clone)]
+pub(crate) struct AutotypeLock {
+    pub token: [u32; 4],
+    pub locked: bool,
+}
+
 #[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Copy, Clone)]
 pub struct U2fMsgIpc {
     /// All U2F protocol messages are 64 bytes
@@ -125,6 +235,8 @@ pub enum UsbDeviceType {
     MassStorage = 3,
     Serial = 4,
     HIDv2 = 5,
+    /// A smart card reader with a card that is implemented by an application
+    Ccid = 6,
 }
 use std::convert::TryFrom;
 
@@ -140,6 +252,7 @@ impl TryFrom<usize> for UsbDeviceType {
             3 => Ok(UsbDeviceType::MassStorage),
             4 => Ok(UsbDeviceType::Serial),
             5 => Ok(UsbDeviceType::HIDv2),
+            6 => Ok(UsbDeviceType::Ccid),
             _ => Err("Invalid UsbDeviceType specifier"),
         }
     }
@@ -180,6 +293,17 @@ pub struct HIDReportMessage {
     pub data: Option<HIDReport>,
 }
 
+/// Only short APDUs are exchanged: a command is at most a 4-byte header, Lc, 255 bytes of data and Le,
+/// and a response at most 256 bytes of data and the status word.
+pub const CCID_APDU_BUFLEN: usize = 261;
+#[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Copy, Clone)]
+pub struct CcidApdu {
+    pub data: [u8; CCID_APDU_BUFLEN],
+    pub len: usize,
+    /// Encodes the state of the message, with the same handshake as U2F messages
+    pub code: U2fCode,
+}
+
 /// this structure is used to register a USB listener.
 #[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Copy, Clone)]
 pub(crate) struct UsbListenerRegistration {
@@ -0,0 +1,533 @@
+use std::collections::VecDeque;
+
+use usb_device::class_prelude::*;
+use usb_device::device::{UsbDevice, UsbDeviceBuilder, UsbVidPid};
+use usb_device::UsbError;
+use usb_device_xous::UsbDeviceState;
+use xous_ipc::Buffer;
+
+use crate::api::{CcidApdu, U2fCode, CCID_APDU_BUFLEN};
+
+/// Section 4.1 of the USB CCID specification, rev 1.1
+const USB_CLASS_CCID: u8 = 0x0b;
+const CCID_DESCRIPTOR_TYPE: u8 = 0x21;
+const PACKET_LEN: u16 = 64;
+const HEADER_LEN: usize = 10;
+/// A header and a short command APDU
+const MAX_MESSAGE_LEN: usize = HEADER_LEN + CCID_APDU_BUFLEN;
+
+const PC_TO_RDR_SET_PARAMETERS: u8 = 0x61;
+const PC_TO_RDR_ICC_POWER_ON: u8 = 0x62;
+const PC_TO_RDR_ICC_POWER_OFF: u8 = 0x63;
+const PC_TO_RDR_GET_SLOT_STATUS: u8 = 0x65;
+const PC_TO_RDR_ESCAPE: u8 = 0x6b;
+const PC_TO_RDR_GET_PARAMETERS: u8 = 0x6c;
+const PC_TO_RDR_RESET_PARAMETERS: u8 = 0x6d;
+const PC_TO_RDR_XFR_BLOCK: u8 = 0x6f;
+const RDR_TO_PC_DATA_BLOCK: u8 = 0x80;
+const RDR_TO_PC_SLOT_STATUS: u8 = 0x81;
+const RDR_TO_PC_PARAMETERS: u8 = 0x82;
+const RDR_TO_PC_ESCAPE: u8 = 0x83;
+
+// bmICCStatus and bmCommandStatus, which share the bStatus byte
+const ICC_ACTIVE: u8 = 0x00;
+const ICC_INACTIVE: u8 = 0x01;
+const COMMAND_FAILED: u8 = 0x40;
+const TIME_EXTENSION: u8 = 0x80;
+
+// bError values for failed commands
+const ERROR_CMD_NOT_SUPPORTED: u8 = 0x00;
+/// The offset of the faulty field in the command
+const ERROR_BAD_LENGTH: u8 = 1;
+const ERROR_BAD_SLOT: u8 = 5;
+const ERROR_ICC_MUTE: u8 = 0xfe;
+const ERROR_CMD_SLOT_BUSY: u8 = 0xe0;
+
+/// T=1 with the default rates, an IFSC of 254 and a card that announces command chaining in its
+/// historical bytes. Applications can present their own historical bytes, e.g. in the OpenPGP
+/// `5F52` data object.
+const ATR: [u8; 19] = [
+    0x3b, 0xd8, 0x11, 0xff, 0x81, 0xb1, 0xfe, 0x55, 0x1f, 0x03, // interface bytes
+    0x00, 0x73, 0x00, 0x00, 0x80, 0x05, 0x90, 0x00, // historical bytes
+    0xd7, // TCK
+];
+/// The T=1 protocol data structure that matches the ATR, see section 6.2.3 of the CCID specification
+const T1_PARAMETERS: [u8; 7] = [0x11, 0x10, 0xff, 0x55, 0x03, 0xfe, 0x00];
+
+/// Something the card application has to know about.
+#[derive(Debug, Eq, PartialEq)]
+pub enum CcidEvent {
+    /// A command APDU, which has to be answered with `Ccid::respond`
+    Apdu(Vec<u8>),
+    /// The host powered the card on or off, or the bus was reset
+    Reset,
+}
+
+/// The reader's side of its one slot, which always holds a card that is off until the host powers it on.
+/// Host messages are answered here, and command APDUs are queued as `CcidEvent`s for the application
+/// that is the card; what goes over USB is left to `CcidClass`.
+#[derive(Default)]
+struct Slot {
+    powered: bool,
+    /// The sequence number of the command APDU the application is working on
+    pending: Option<u8>,
+    events: VecDeque<CcidEvent>,
+}
+
+impl Slot {
+    fn icc_status(&self) -> u8 { if self.powered { ICC_ACTIVE } else { ICC_INACTIVE } }
+
+    /// Answers a message from the host. `None` means the answer is up to the application.
+    fn handle(&mut self, message: &[u8]) -> Option<Vec<u8>> {
+        let (kind, slot, seq) = (message[0], message[5], message[6]);
+        let failed = COMMAND_FAILED | self.icc_status();
+        if slot != 0 {
+            return Some(encode(RDR_TO_PC_SLOT_STATUS, seq, failed, ERROR_BAD_SLOT, 0, &[]));
+        }
+        if self.pending.is_some() {
+            return Some(encode(RDR_TO_PC_SLOT_STATUS, seq, failed, ERROR_CMD_SLOT_BUSY, 0, &[]));
+        }
+        let response = match kind {
+            PC_TO_RDR_ICC_POWER_ON => {
+                self.powered = true;
+                self.events.push_back(CcidEvent::Reset);
+                encode(RDR_TO_PC_DATA_BLOCK, seq, ICC_ACTIVE, 0, 0, &ATR)
+            }
+            PC_TO_RDR_ICC_POWER_OFF => {
+                if self.powered {
+                    self.powered = false;
+                    self.events.push_back(CcidEvent::Reset);
+                }
+                // bClockStatus: clock running
+                encode(RDR_TO_PC_SLOT_STATUS, seq, ICC_INACTIVE, 0, 0, &[])
+            }
+            PC_TO_RDR_GET_SLOT_STATUS => encode(RDR_TO_PC_SLOT_STATUS, seq, self.icc_status(), 0, 0, &[]),
+            PC_TO_RDR_XFR_BLOCK => {
+                if !self.powered {
+                    encode(RDR_TO_PC_DATA_BLOCK, seq, failed, ERROR_ICC_MUTE, 0, &[])
+                } else {
+                    self.pending = Some(seq);
+                    self.events.push_back(CcidEvent::Apdu(message[HEADER_LEN..].to_vec()));
+                    return None;
+                }
+            }
+            // the parameters are fixed, so setting them is the same as reading them back
+            PC_TO_RDR_GET_PARAMETERS | PC_TO_RDR_RESET_PARAMETERS | PC_TO_RDR_SET_PARAMETERS => {
+                // bProtocolNum: T=1
+                encode(RDR_TO_PC_PARAMETERS, seq, self.icc_status(), 0, 1, &T1_PARAMETERS)
+            }
+            PC_TO_RDR_ESCAPE => encode(RDR_TO_PC_ESCAPE, seq, failed, ERROR_CMD_NOT_SUPPORTED, 0, &[]),
+            _ => {
+                log::debug!("unsupported CCID message {:#x}", kind);
+                encode(RDR_TO_PC_SLOT_STATUS, seq, failed, ERROR_CMD_NOT_SUPPORTED, 0, &[])
+            }
+        };
+        Some(response)
+    }
+
+    /// Turns down a message that `decode` dropped for being too long
+    fn reject(&self, seq: u8) -> Vec<u8> {
+        encode(RDR_TO_PC_SLOT_STATUS, seq, COMMAND_FAILED | self.icc_status(), ERROR_BAD_LENGTH, 0, &[])
+    }
+
+    fn respond(&mut self, response: &[u8]) -> Option<Vec<u8>> {
+        let seq = self.pending.take()?;
+        Some(encode(RDR_TO_PC_DATA_BLOCK, seq, ICC_ACTIVE, 0, 0, response))
+    }
+
+    fn time_extension(&self) -> Option<Vec<u8>> {
+        // the error field carries the multiplier of the block waiting time
+        self.pending.map(|seq| encode(RDR_TO_PC_DATA_BLOCK, seq, TIME_EXTENSION | ICC_ACTIVE, 1, 0, &[]))
+    }
+
+    fn reset(&mut self) {
+        self.pending = None;
+        self.powered = false;
+        self.events.clear();
+        self.events.push_back(CcidEvent::Reset);
+    }
+}
+
+/// A reader to host message, see section 6.2 of the CCID specification
+fn encode(kind: u8, seq: u8, status: u8, error: u8, specific: u8, data: &[u8]) -> Vec<u8> {
+    let mut message = Vec::with_capacity(HEADER_LEN + data.len());
+    message.push(kind);
+    message.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    // slot 0 is the only one
+    message.extend_from_slice(&[0, seq, status, error, specific]);
+    message.extend_from_slice(data);
+    message
+}
+
+/// Splits a message into the packets that carry it to the host
+fn packets(message: &[u8]) -> Vec<Vec<u8>> {
+    let mut packets: Vec<Vec<u8>> = message.chunks(PACKET_LEN as usize).map(|p| p.to_vec()).collect();
+    // the host only sees the end of a message with a short packet
+    if message.len() % PACKET_LEN as usize == 0 {
+        packets.push(Vec::new());
+    }
+    packets
+}
+
+/// Takes the first complete host to reader message off `rx`. A message too long for the reader is
+/// dropped along with everything else received, and comes back as `Err` with its sequence number.
+fn decode(rx: &mut Vec<u8>) -> Option<Result<Vec<u8>, u8>> {
+    if rx.len() < HEADER_LEN {
+        return None;
+    }
+    let len = u32::from_le_bytes([rx[1], rx[2], rx[3], rx[4]]) as usize;
+    if len > MAX_MESSAGE_LEN - HEADER_LEN {
+        let seq = rx[6];
+        log::warn!("dropping CCID message of {} bytes", len);
+        rx.clear();
+        return Some(Err(seq));
+    }
+    if rx.len() < HEADER_LEN + len {
+        return None;
+    }
+    Some(Ok(rx.drain(..HEADER_LEN + len).collect()))
+}
+
+/// A CCID class with a single slot. The card itself lives in an application: command APDUs are queued
+/// as `CcidEvent`s for it, and the reader asks the host for more time until the application responds.
+/// Only short APDU level exchanges are supported.
+pub struct CcidClass<'a, B: UsbBus> {
+    interface: InterfaceNumber,
+    read_ep: EndpointOut<'a, B>,
+    write_ep: EndpointIn<'a, B>,
+    // announces card insertion and removal; the card is never removed, so this stays quiet
+    interrupt_ep: EndpointIn<'a, B>,
+    rx: Vec<u8>,
+    /// Packets waiting for the IN endpoint, including the zero-length packets that end messages
+    tx: VecDeque<Vec<u8>>,
+    slot: Slot,
+}
+
+impl<'a, B: UsbBus> CcidClass<'a, B> {
+    pub fn new(alloc: &'a UsbBusAllocator<B>) -> Self {
+        CcidClass {
+            interface: alloc.interface(),
+            read_ep: alloc.bulk(PACKET_LEN),
+            write_ep: alloc.bulk(PACKET_LEN),
+            interrupt_ep: alloc.interrupt(8, 255),
+            rx: Vec::new(),
+            tx: VecDeque::new(),
+            slot: Slot::default(),
+        }
+    }
+
+    /// Answers the command APDU handed out last. Returns `false` if there is no such command, e.g.
+    /// because the card was reset in the meantime.
+    pub fn respond(&mut self, response: &[u8]) -> bool {
+        match self.slot.respond(response) {
+            Some(message) => {
+                self.send(&message);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Asks the host to keep waiting for the pending command APDU, if any.
+    pub fn time_extension(&mut self) {
+        if let Some(message) = self.slot.time_extension() {
+            self.send(&message);
+        }
+    }
+
+    pub fn next_event(&mut self) -> Option<CcidEvent> { self.slot.events.pop_front() }
+
+    fn send(&mut self, message: &[u8]) {
+        self.tx.extend(packets(message));
+        self.flush();
+    }
+
+    fn flush(&mut self) {
+        if let Some(packet) = self.tx.front() {
+            match self.write_ep.write(packet) {
+                Ok(_) => {
+                    self.tx.pop_front();
+                }
+                Err(UsbError::WouldBlock) => (),
+                Err(e) => log::warn!("CCID write error: {:?}", e),
+            }
+        }
+    }
+
+    fn receive(&mut self) {
+        let mut packet = [0u8; PACKET_LEN as usize];
+        loop {
+            match self.read_ep.read(&mut packet) {
+                Ok(len) => self.rx.extend_from_slice(&packet[..len]),
+                Err(UsbError::WouldBlock) => break,
+                Err(e) => {
+                    log::warn!("CCID read error: {:?}", e);
+                    break;
+                }
+            }
+        }
+        let response = match decode(&mut self.rx) {
+            Some(Ok(message)) => self.slot.handle(&message),
+            Some(Err(seq)) => Some(self.slot.reject(seq)),
+            None => None,
+        };
+        if let Some(response) = response {
+            self.send(&response);
+        }
+    }
+}
+
+impl<B: UsbBus> UsbClass<B> for CcidClass<'_, B> {
+    fn get_configuration_descriptors(&self, writer: &mut DescriptorWriter) -> usb_device::Result<()> {
+        writer.interface(self.interface, USB_CLASS_CCID, 0, 0)?;
+        writer.write(
+            CCID_DESCRIPTOR_TYPE,
+            &[
+                0x10,
+                0x01, // bcdCCID: 1.10
+                0x00, // bMaxSlotIndex
+                0x07, // bVoltageSupport: 5.0V, 3.0V and 1.8V
+                0x02,
+                0x00,
+                0x00,
+                0x00, // dwProtocols: T=1
+                0xfc,
+                0x0d,
+                0x00,
+                0x00, // dwDefaultClock: 3.58 MHz
+                0xfc,
+                0x0d,
+                0x00,
+                0x00, // dwMaximumClock
+                0x00, // bNumClockSupported
+                0x80,
+                0x25,
+                0x00,
+                0x00, // dwDataRate: 9600 bps
+                0x80,
+                0x25,
+                0x00,
+                0x00, // dwMaxDataRate
+                0x00, // bNumDataRatesSupported
+                0xfe,
+                0x00,
+                0x00,
+                0x00, // dwMaxIFSD
+                0x00,
+                0x00,
+                0x00,
+                0x00, // dwSynchProtocols
+                0x00,
+                0x00,
+                0x00,
+                0x00, // dwMechanical
+                // dwFeatures: automatic parameters, voltage, clock, baud rate and PPS; short APDU exchange
+                0xfa,
+                0x00,
+                0x02,
+                0x00,
+                (MAX_MESSAGE_LEN & 0xff) as u8, // dwMaxCCIDMessageLength
+                (MAX_MESSAGE_LEN >> 8) as u8,
+                0x00,
+                0x00,
+                0xff, // bClassGetResponse: echo the APDU class
+                0xff, // bClassEnvelope
+                0x00,
+                0x00, // wLcdLayout: none
+                0x00, // bPINSupport: none
+                0x01, // bMaxCCIDBusySlots
+            ],
+        )?;
+        writer.endpoint(&self.read_ep)?;
+        writer.endpoint(&self.write_ep)?;
+        writer.endpoint(&self.interrupt_ep)
+    }
+
+    fn reset(&mut self) {
+        self.rx.clear();
+        self.tx.clear();
+        self.slot.reset();
+    }
+
+    fn poll(&mut self) {
+        self.receive();
+        self.flush();
+    }
+}
+
+/// Ccid wraps a USB device with a single CCID class, like `AppHID` does for HID.
+pub struct Ccid<'a, B: UsbBus> {
+    class: CcidClass<'a, B>,
+    device: UsbDevice<'a, B>,
+}
+
+impl<'a, B: UsbBus> Ccid<'a, B> {
+    pub fn new(vid_pid: UsbVidPid, serial_number: &'a str, alloc: &'a UsbBusAllocator<B>) -> Self {
+        let class = CcidClass::new(alloc);
+
+        let device = UsbDeviceBuilder::new(alloc, vid_pid)
+            .manufacturer("Kosagi")
+            .product("Precursor")
+            .serial_number(&serial_number)
+            .self_powered(false)
+            .max_power(500)
+            .build();
+
+        Ccid { class, device }
+    }
+
+    /// Forces the reset of the underlying USB device.
+    /// Causes host to re-enumerate.
+    pub fn force_reset(&mut self) -> usb_device::Result<()> { self.device.force_reset() }
+
+    /// Returns the current state of the underlying USB device.
+    pub fn state(&self) -> UsbDeviceState { self.device.state() }
+
+    /// Moves messages between the bus and the class.
+    pub fn poll(&mut self) { self.device.poll(&mut [&mut self.class]); }
+
+    pub fn next_event(&mut self) -> Option<CcidEvent> { self.class.next_event() }
+
+    pub fn respond(&mut self, response: &[u8]) -> bool { self.class.respond(response) }
+
+    pub fn time_extension(&mut self) { self.class.time_extension() }
+
+    /// Whether a command APDU is waiting for a response
+    pub fn busy(&self) -> bool { self.class.slot.pending.is_some() }
+}
+
+/// Answers a deferred `CcidRxDeferred` request with `event`.
+pub(crate) fn answer_listener(mut listener: xous::MessageEnvelope, event: CcidEvent) {
+    let mut response =
+        unsafe { Buffer::from_memory_message_mut(listener.body.memory_message_mut().unwrap()) };
+    let mut buf = response.to_original::<CcidApdu, _>().unwrap();
+    assert_eq!(buf.code, U2fCode::RxWait, "Expected U2fcode::RxWait in wrapper");
+    match event {
+        CcidEvent::Apdu(apdu) => {
+            buf.data[..apdu.len()].copy_from_slice(&apdu);
+            buf.len = apdu.len();
+            buf.code = U2fCode::RxAck;
+        }
+        CcidEvent::Reset => buf.code = U2fCode::Hangup,
+    }
+    response.replace(buf).unwrap();
+}
+
+/// Turns away a `CcidRxDeferred` request, or a deferred one once the card is gone.
+pub(crate) fn deny_listener(mut listener: xous::MessageEnvelope) {
+    let mut response =
+        unsafe { Buffer::from_memory_message_mut(listener.body.memory_message_mut().unwrap()) };
+    let mut buf = response.to_original::<CcidApdu, _>().unwrap();
+    buf.code = U2fCode::Denied;
+    response.replace(buf).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A host to reader message in slot 0. Its header is laid out like the reader's, with three message
+    /// specific bytes in place of the status and error.
+    fn command(kind: u8, seq: u8, data: &[u8]) -> Vec<u8> { encode(kind, seq, 0, 0, 0, data) }
+
+    #[test]
+    fn test_packets() {
+        let lengths = |data_len: usize| -> Vec<usize> {
+            packets(&encode(RDR_TO_PC_DATA_BLOCK, 0, 0, 0, 0, &vec![0x55; data_len]))
+                .iter()
+                .map(|p| p.len())
+                .collect()
+        };
+        assert_eq!(lengths(0), vec![HEADER_LEN]);
+        assert_eq!(lengths(60), vec![64, 6]);
+        // a message that fills its last packet is ended by a zero-length one
+        assert_eq!(lengths(54), vec![64, 0]);
+        assert_eq!(lengths(118), vec![64, 64, 0]);
+
+        let message = encode(RDR_TO_PC_DATA_BLOCK, 7, ICC_ACTIVE, 0, 0, &[1, 2, 3]);
+        assert_eq!(message, vec![RDR_TO_PC_DATA_BLOCK, 3, 0, 0, 0, 0, 7, ICC_ACTIVE, 0, 0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_decode() {
+        let first = command(PC_TO_RDR_XFR_BLOCK, 1, &[0x00, 0xa4, 0x04, 0x00]);
+        let second = command(PC_TO_RDR_GET_SLOT_STATUS, 2, &[]);
+        let mut rx = first[..5].to_vec();
+        assert_eq!(decode(&mut rx), None);
+        rx.extend_from_slice(&first[5..12]);
+        assert_eq!(decode(&mut rx), None);
+        rx.extend_from_slice(&first[12..]);
+        rx.extend_from_slice(&second);
+        assert_eq!(decode(&mut rx), Some(Ok(first)));
+        assert_eq!(decode(&mut rx), Some(Ok(second)));
+        assert_eq!(decode(&mut rx), None);
+
+        // too long messages are dropped as soon as their header is in
+        let mut rx = command(PC_TO_RDR_XFR_BLOCK, 9, &[])[..HEADER_LEN].to_vec();
+        rx[1..5].copy_from_slice(&(MAX_MESSAGE_LEN as u32).to_le_bytes());
+        assert_eq!(decode(&mut rx), Some(Err(9)));
+        assert!(rx.is_empty());
+        let rejection = Slot::default().reject(9);
+        assert_eq!(rejection[6..9], [9, COMMAND_FAILED | ICC_INACTIVE, ERROR_BAD_LENGTH]);
+    }
+
+    #[test]
+    fn test_power() {
+        let mut slot = Slot::default();
+        let apdu = [0x00, 0xca, 0x00, 0x6e, 0x00];
+        // the card doesn't answer until it's powered on
+        let response = slot.handle(&command(PC_TO_RDR_XFR_BLOCK, 1, &apdu)).unwrap();
+        assert_eq!(response[6..9], [1, COMMAND_FAILED | ICC_INACTIVE, ERROR_ICC_MUTE]);
+        assert_eq!(slot.events.pop_front(), None);
+
+        let response = slot.handle(&command(PC_TO_RDR_ICC_POWER_ON, 2, &[])).unwrap();
+        assert_eq!(response, encode(RDR_TO_PC_DATA_BLOCK, 2, ICC_ACTIVE, 0, 0, &ATR));
+        assert_eq!(slot.events.pop_front(), Some(CcidEvent::Reset));
+
+        let response = slot.handle(&command(PC_TO_RDR_ICC_POWER_OFF, 3, &[])).unwrap();
+        assert_eq!(response, encode(RDR_TO_PC_SLOT_STATUS, 3, ICC_INACTIVE, 0, 0, &[]));
+        assert_eq!(slot.events.pop_front(), Some(CcidEvent::Reset));
+        // powering off a card that is off doesn't reset it again
+        slot.handle(&command(PC_TO_RDR_ICC_POWER_OFF, 4, &[])).unwrap();
+        assert_eq!(slot.events.pop_front(), None);
+
+        slot.handle(&command(PC_TO_RDR_ICC_POWER_ON, 5, &[])).unwrap();
+        slot.handle(&command(PC_TO_RDR_XFR_BLOCK, 6, &apdu));
+        slot.reset();
+        assert_eq!(slot.pending, None);
+        assert_eq!(slot.events.drain(..).collect::<Vec<_>>(), vec![CcidEvent::Reset]);
+        assert_eq!(slot.icc_status(), ICC_INACTIVE);
+    }
+
+    #[test]
+    fn test_apdu_exchange() {
+        let mut slot = Slot::default();
+        assert_eq!(slot.time_extension(), None);
+        slot.handle(&command(PC_TO_RDR_ICC_POWER_ON, 0, &[])).unwrap();
+        slot.events.clear();
+
+        let apdu = [0x00, 0xca, 0x00, 0x6e, 0x00];
+        assert_eq!(slot.handle(&command(PC_TO_RDR_XFR_BLOCK, 0x42, &apdu)), None);
+        assert_eq!(slot.events.pop_front(), Some(CcidEvent::Apdu(apdu.to_vec())));
+        // the host is asked to wait, and anything else it sends meanwhile is turned away
+        assert_eq!(
+            slot.time_extension(),
+            Some(encode(RDR_TO_PC_DATA_BLOCK, 0x42, TIME_EXTENSION | ICC_ACTIVE, 1, 0, &[]))
+        );
+        let busy = slot.handle(&command(PC_TO_RDR_GET_SLOT_STATUS, 0x43, &[])).unwrap();
+        assert_eq!(busy[6..9], [0x43, COMMAND_FAILED | ICC_ACTIVE, ERROR_CMD_SLOT_BUSY]);
+
+        // the response goes out with the sequence number of its command, once
+        assert_eq!(
+            slot.respond(&[0x90, 0x00]),
+            Some(encode(RDR_TO_PC_DATA_BLOCK, 0x42, ICC_ACTIVE, 0, 0, &[0x90, 0x00]))
+        );
+        assert_eq!(slot.respond(&[0x90, 0x00]), None);
+        assert_eq!(slot.time_extension(), None);
+
+        let mut other_slot = command(PC_TO_RDR_GET_SLOT_STATUS, 0x44, &[]);
+        other_slot[5] = 1;
+        let response = slot.handle(&other_slot).unwrap();
+        assert_eq!(response[6..9], [0x44, COMMAND_FAILED | ICC_ACTIVE, ERROR_BAD_SLOT]);
+    }
+}
@@ -123,6 +123,8 @@ impl UsbHid {
                 #[cfg(feature = "mass-storage")]
                 3 => Ok(UsbDeviceType::MassStorage),
                 4 => Ok(UsbDeviceType::Serial),
+                5 => Ok(UsbDeviceType::HIDv2),
+                6 => Ok(UsbDeviceType::Ccid),
                 _ => Err(xous::Error::InternalError),
             },
             _ => panic!("Internal error: illegal return type"),
@@ -237,6 +239,38 @@ impl UsbHid {
         }
     }
 
+    /// Like `send_str`, but types for a host set to `layout` instead of the layout chosen with
+    /// `set_host_layout`.
+    pub fn send_str_layout(&self, s: &str, layout: HostLayout) -> Result<usize, xous::Error> {
+        let serializer =
+            UsbAutotype { s: xous_ipc::String::<4000>::from_str(s), layout: Some(layout), sent: None };
+        let mut buf = Buffer::into_buf(serializer).or(Err(xous::Error::InternalError))?;
+        buf.lend_mut(self.conn, Opcode::SendStringLayout.to_u32().unwrap())
+            .or(Err(xous::Error::InternalError))?;
+        let returned = buf.to_original::<UsbAutotype, _>().or(Err(xous::Error::InternalError))?;
+        match returned.sent {
+            Some(sent) => Ok(sent as usize),
+            None => Err(xous::Error::UseBeforeInit),
+        }
+    }
+
+    /// Sets the keyboard layout of the host that `send_str` types for, and how characters that the layout
+    /// can't produce are typed. Defaults to `HostLayout::Native` with no Unicode entry on boot, and must be
+    /// set again every time on reboot.
+    pub fn set_host_layout(&self, layout: HostLayout, unicode: UnicodeEntry) {
+        send_message(
+            self.conn,
+            Message::new_scalar(
+                Opcode::SetHostLayout.to_usize().unwrap(),
+                layout.to_usize().unwrap(),
+                unicode.to_usize().unwrap(),
+                0,
+                0,
+            ),
+        )
+        .unwrap();
+    }
+
     /// Sets the autotype delay. Defaults to 30ms on boot, must be reset every time on reboot.
     pub fn set_autotype_delay_ms(&self, rate: usize) {
         send_message(
@@ -246,6 +280,35 @@ impl UsbHid {
         .unwrap(); // just unwrap it. If the send fails, we want to see the panic at this spot!
     }
 
+    /// Claims the autotype lock, so that only the holder of `token` can set it. Only the first claim is
+    /// accepted; the status bar makes it at boot, as the owner of the screen lock.
+    pub fn register_autotype_lock(&self, token: [u32; 4]) -> Result<(), xous::Error> {
+        match send_message(
+            self.conn,
+            Message::new_blocking_scalar(
+                Opcode::RegisterAutotypeLock.to_usize().unwrap(),
+                token[0] as usize,
+                token[1] as usize,
+                token[2] as usize,
+                token[3] as usize,
+            ),
+        ) {
+            Ok(xous::Result::Scalar1(1)) => Ok(()),
+            Ok(_) => Err(xous::Error::AccessDenied),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// While set, `send_str` and `send_keycode` refuse to type into the host. Used by the screen lock;
+    /// `token` is the one the lock was claimed with.
+    pub fn set_autotype_lock(&self, token: [u32; 4], locked: bool) -> Result<(), xous::Error> {
+        let lock = AutotypeLock { token, locked };
+        let buf = Buffer::into_buf(lock).or(Err(xous::Error::InternalError))?;
+        buf.send(self.conn, Opcode::SetAutotypeLock.to_u32().unwrap())
+            .or(Err(xous::Error::InternalError))
+            .map(|_| ())
+    }
+
     pub fn get_led_state(&self) -> Result<KeyboardLedsReport, xous::Error> {
         match send_message(
             self.conn,
@@ -335,6 +398,23 @@ impl UsbHid {
         resp.d[..resp.len].to_vec()
     }
 
+    /// Blocks until all of `data` has been written to the serial port. Fails with `UseBeforeInit` if
+    /// the serial core isn't the active one.
+    pub fn serial_write_binary(&self, data: &[u8]) -> Result<(), xous::Error> {
+        for chunk in data.chunks(SERIAL_BINARY_BUFLEN) {
+            let mut req = UsbSerialBinary { d: [0u8; SERIAL_BINARY_BUFLEN], len: chunk.len() };
+            req.d[..chunk.len()].copy_from_slice(chunk);
+            let mut buf = Buffer::into_buf(req).or(Err(xous::Error::InternalError))?;
+            buf.lend_mut(self.conn, Opcode::SerialSendBinary.to_u32().unwrap())
+                .or(Err(xous::Error::InternalError))?;
+            let resp = buf.to_original::<UsbSerialBinary, _>().or(Err(xous::Error::InternalError))?;
+            if resp.len != chunk.len() {
+                return Err(xous::Error::UseBeforeInit);
+            }
+        }
+        Ok(())
+    }
+
     /// Non-blocking call that issues a serial flush command to the USB stack
     pub fn serial_flush(&self) -> Result<(), xous::Error> {
         send_message(self.conn, Message::new_scalar(Opcode::SerialFlush.to_usize().unwrap(), 0, 0, 0, 0))
@@ -448,6 +528,42 @@ impl UsbHid {
 
         Ok(())
     }
+
+    /// Blocks until the host sends a command APDU to the smart card reader core.
+    /// `Err(ProcessTerminated)` means the host powered the card off or reset it, and session state such as
+    /// verified PINs should be dropped. `Err(AccessDenied)` means the reader went away because another core
+    /// was selected, or that another process is already serving the card.
+    pub fn ccid_wait_apdu(&self) -> Result<Vec<u8>, xous::Error> {
+        let req = CcidApdu { data: [0; CCID_APDU_BUFLEN], len: 0, code: U2fCode::RxWait };
+        let mut buf = Buffer::into_buf(req).or(Err(xous::Error::InternalError))?;
+        buf.lend_mut(self.conn, Opcode::CcidRxDeferred.to_u32().unwrap())
+            .or(Err(xous::Error::InternalError))?;
+        let ack = buf.to_original::<CcidApdu, _>().unwrap();
+        match ack.code {
+            U2fCode::RxAck => Ok(ack.data[..ack.len].to_vec()),
+            U2fCode::Hangup => Err(xous::Error::ProcessTerminated),
+            U2fCode::Denied => Err(xous::Error::AccessDenied),
+            _ => Err(xous::Error::InternalError),
+        }
+    }
+
+    /// Answers the command APDU last returned by `ccid_wait_apdu`. Until then, the reader keeps asking the
+    /// host for more time.
+    pub fn ccid_send_response(&self, response: &[u8]) -> Result<(), xous::Error> {
+        if response.len() > CCID_APDU_BUFLEN {
+            return Err(xous::Error::OutOfMemory);
+        }
+        let mut req = CcidApdu { data: [0; CCID_APDU_BUFLEN], len: response.len(), code: U2fCode::Tx };
+        req.data[..response.len()].copy_from_slice(response);
+        let mut buf = Buffer::into_buf(req).or(Err(xous::Error::InternalError))?;
+        buf.lend_mut(self.conn, Opcode::CcidTx.to_u32().unwrap()).or(Err(xous::Error::InternalError))?;
+        let ack = buf.to_original::<CcidApdu, _>().unwrap();
+        match ack.code {
+            U2fCode::TxAck => Ok(()),
+            U2fCode::Denied => Err(xous::Error::AccessDenied),
+            _ => Err(xous::Error::InternalError),
+        }
+    }
 }
 
 use core::sync::atomic::{AtomicU32, Ordering};
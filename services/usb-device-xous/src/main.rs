@@ -29,6 +29,8 @@ mod apps_block_device;
 
 #[cfg(any(feature = "precursor", feature = "renode", feature = "cramium-soc"))]
 mod hid;
+#[cfg(any(feature = "precursor", feature = "renode", feature = "cramium-soc"))]
+mod ccid;
 #[cfg(not(target_os = "xous"))]
 mod hosted;
 use std::collections::BTreeMap;
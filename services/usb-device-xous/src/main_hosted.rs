@@ -279,9 +279,18 @@ pub(crate) fn main_hosted() -> ! {
                 let usb_send = buffer.to_original::<api::UsbString, _>().unwrap(); // suppress mut warning on hosted mode
                 buffer.replace(usb_send).unwrap();
             }
+            Some(Opcode::SendStringLayout) => {
+                let mut buffer =
+                    unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                let usb_send = buffer.to_original::<api::UsbAutotype, _>().unwrap();
+                buffer.replace(usb_send).unwrap();
+            }
             Some(Opcode::GetLedState) => {
                 xous::return_scalar(msg.sender, 0).unwrap();
             }
+            Some(Opcode::RegisterAutotypeLock) => {
+                xous::return_scalar(msg.sender, 1).unwrap();
+            }
             Some(Opcode::Quit) => {
                 log::warn!("Quit received, goodbye world!");
                 break;
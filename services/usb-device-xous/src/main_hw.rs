@@ -51,6 +51,7 @@ enum Views {
     MassStorage = 2,
     Serial = 3,
     HIDv2 = 4,
+    Ccid = 5,
 }
 
 #[derive(num_derive::FromPrimitive, num_derive::ToPrimitive, Debug)]
@@ -425,6 +426,20 @@ pub(crate) fn main_hw() -> ! {
         AppHIDConfig::default(),
         100, // 100 * 64 bytes = 6.4kb, quite the backlog
     );
+
+    #[cfg(any(feature = "renode", feature = "precursor"))]
+    let usb_ccid_dev = SpinalUsbDevice::new(usbdev_sid, usb.clone(), csr.clone());
+    #[cfg(any(feature = "renode", feature = "precursor"))]
+    usb_ccid_dev.init();
+    #[cfg(feature = "cramium-soc")]
+    // safety: this is safe because we allocated ifram_range to have the same physical and virtual addresses
+    let usb_ccid_dev = usbwrapper.clone();
+
+    let ccid_alloc = UsbBusAllocator::new(usb_ccid_dev);
+    let mut ccid = ccid::Ccid::new(UsbVidPid(0x1209, 0x3613), &serial_number, &ccid_alloc);
+    // the card application, locked on first use just like the U2F listener
+    let mut ccid_listener: Option<xous::MessageEnvelope> = None;
+    let mut ccid_listener_pid: Option<NonZeroU8> = None;
     // track which view is visible on the device core
     #[cfg(all(not(feature = "minimal"), not(feature = "cramium-soc")))]
     let mut view = Views::FidoWithKbd;
@@ -486,6 +501,13 @@ pub(crate) fn main_hw() -> ! {
     let mut lockstatus_force_update = true; // some state to track if we've been through a suspend/resume, to help out the status thread with its UX update after a restart-from-cold
     let mut was_suspend = true;
     let mut autotype_delay_ms = 30;
+    // set by the status bar while the screen is locked; no keystrokes may be typed into the host
+    let mut autotype_locked = false;
+    // the token the status bar claimed the autotype lock with
+    let mut autotype_lock_token: Option<[u32; 4]> = None;
+    // the layout the host expects, and how to type characters that aren't on it
+    let mut host_layout = HostLayout::Native;
+    let mut unicode_entry = UnicodeEntry::Off;
 
     // event observer connection
     let mut observer_conn: Option<xous::CID> = None;
@@ -511,6 +533,35 @@ pub(crate) fn main_hw() -> ! {
         tt.sleep_ms(EXTENDED_CORE_RESET_MS).ok();
         usbmgmt.ll_reset(false);
     }
+    // ask the host for more time while the card application works on a command. The application may be
+    // waiting for the user to approve it on the device, which takes much longer than the reader's timeout.
+    const CCID_TIME_EXTENSION_MS: usize = 1000;
+    let ccid_te_server = xous::create_server().unwrap();
+    let ccid_te_conn = xous::connect(ccid_te_server).unwrap();
+    let ccid_busy = Arc::new(AtomicBool::new(false));
+    std::thread::spawn({
+        let cid = cid;
+        let ccid_busy = ccid_busy.clone();
+        move || {
+            let tt = ticktimer_server::Ticktimer::new().unwrap();
+            loop {
+                // woken up once for every command that is handed to the card application
+                xous::receive_message(ccid_te_server).unwrap();
+                loop {
+                    tt.sleep_ms(CCID_TIME_EXTENSION_MS).ok();
+                    if !ccid_busy.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    xous::try_send_message(
+                        cid,
+                        xous::Message::new_scalar(Opcode::CcidTimeExtension.to_usize().unwrap(), 0, 0, 0, 0),
+                    )
+                    .ok();
+                }
+            }
+        }
+    });
+
     // manage FIDO Rx timeouts -- not tested yet
     let to_server = xous::create_server().unwrap();
     let to_conn = xous::connect(to_server).unwrap();
@@ -688,6 +739,10 @@ pub(crate) fn main_hw() -> ! {
                         Err(e) => log::warn!("USB reset on resume failed: {:?}", e),
                         _ => (),
                     },
+                    Views::Ccid => match ccid.force_reset() {
+                        Err(e) => log::warn!("USB reset on resume failed: {:?}", e),
+                        _ => (),
+                    },
                 }
                 // resume2 brings us to our last application state
                 usbmgmt.xous_resume2();
@@ -806,6 +861,7 @@ pub(crate) fn main_hw() -> ! {
                         Views::MassStorage => panic!("did not expect u2f tx when in mass storage mode!"),
                         Views::Serial => panic!("did not expect u2f tx while in serial mode!"),
                         Views::HIDv2 => panic!("did not expect u2f tx while in hidv2 mode!"),
+                        Views::Ccid => panic!("did not expect u2f tx while in ccid mode!"),
                     };
                     u2f.write_report(&u2f_msg).ok();
                     log::debug!("sent U2F packet {:x?}", u2f_ipc.data);
@@ -967,6 +1023,22 @@ pub(crate) fn main_hw() -> ! {
 
                         None
                     }
+                    Views::Ccid => {
+                        let was_busy = ccid.busy();
+                        ccid.poll();
+                        let busy = ccid.busy();
+                        ccid_busy.store(busy, Ordering::SeqCst);
+                        if busy && !was_busy {
+                            xous::try_send_message(ccid_te_conn, xous::Message::new_scalar(0, 0, 0, 0, 0))
+                                .ok();
+                        }
+                        if ccid_listener.is_some() {
+                            if let Some(event) = ccid.next_event() {
+                                ccid::answer_listener(ccid_listener.take().unwrap(), event);
+                            }
+                        }
+                        None
+                    }
                 };
                 if let Some(u2f) = maybe_u2f {
                     match u2f.read_report() {
@@ -1000,6 +1072,7 @@ pub(crate) fn main_hw() -> ! {
                     Views::MassStorage => ums_device.state() == UsbDeviceState::Suspend,
                     Views::Serial => serial_device.state() == UsbDeviceState::Suspend,
                     Views::HIDv2 => hidv2.state() == UsbDeviceState::Suspend,
+                    Views::Ccid => ccid.state() == UsbDeviceState::Suspend,
                 };
                 if is_suspend {
                     log::info!("suspend detected");
@@ -1053,6 +1126,11 @@ pub(crate) fn main_hw() -> ! {
                 }
 
                 let devtype: UsbDeviceType = core.try_into().unwrap();
+                // the reset takes the card away from the card application
+                if let Some(listener) = ccid_listener.take() {
+                    ccid::deny_listener(listener);
+                }
+                ccid_busy.store(false, Ordering::SeqCst);
                 #[cfg(feature = "cramium-soc")]
                 {
                     match devtype {
@@ -1072,6 +1150,9 @@ pub(crate) fn main_hw() -> ! {
                         UsbDeviceType::Serial => {
                             serial_device.force_reset().ok();
                         }
+                        UsbDeviceType::Ccid => {
+                            ccid.force_reset().ok();
+                        }
                         UsbDeviceType::Debug => {
                             log::warn!("No debug core in this target");
                         }
@@ -1158,6 +1239,20 @@ pub(crate) fn main_hw() -> ! {
                             }
                         }
                     }
+                    UsbDeviceType::Ccid => {
+                        log::info!("Connecting CCID device");
+                        match view {
+                            Views::Ccid => usbmgmt.connect_device_core(true),
+                            _ => {
+                                view = Views::Ccid;
+                                usbmgmt.ll_reset(true);
+                                tt.sleep_ms(1000).ok();
+                                usbmgmt.ll_connect_device_core(true);
+                                tt.sleep_ms(EXTENDED_CORE_RESET_MS).ok();
+                                usbmgmt.ll_reset(false);
+                            }
+                        }
+                    }
                 }
                 xous::return_scalar(msg.sender, 0).unwrap();
             }),
@@ -1194,6 +1289,13 @@ pub(crate) fn main_hw() -> ! {
                         trng.set_test_mode(trng::api::TrngTestMode::None);
                     }
                 }
+                // likewise, the card application loses its card
+                if view == Views::Ccid && devtype != UsbDeviceType::Ccid {
+                    if let Some(listener) = ccid_listener.take() {
+                        ccid::deny_listener(listener);
+                    }
+                    ccid_busy.store(false, Ordering::SeqCst);
+                }
 
                 #[cfg(feature = "cramium-soc")]
                 let mut usbmgmt = usbwrapper.core();
@@ -1294,6 +1396,22 @@ pub(crate) fn main_hw() -> ! {
                             }
                         }
                     }
+                    UsbDeviceType::Ccid => {
+                        log::info!("Ensuring CCID device");
+                        if !usbmgmt.is_device_connected() {
+                            view = Views::Ccid;
+                            usbmgmt.connect_device_core(true);
+                        } else {
+                            if view != Views::Ccid {
+                                view = Views::Ccid;
+                                usbmgmt.ll_reset(true);
+                                tt.sleep_ms(1000).ok();
+                                usbmgmt.ll_connect_device_core(true);
+                                tt.sleep_ms(EXTENDED_CORE_RESET_MS).ok();
+                                usbmgmt.ll_reset(false);
+                            }
+                        }
+                    }
                 }
                 xous::return_scalar(msg.sender, 0).unwrap();
             }),
@@ -1318,6 +1436,7 @@ pub(crate) fn main_hw() -> ! {
                         Views::HIDv2 => {
                             xous::return_scalar(msg.sender, UsbDeviceType::HIDv2 as usize).unwrap()
                         }
+                        Views::Ccid => xous::return_scalar(msg.sender, UsbDeviceType::Ccid as usize).unwrap(),
                     }
                 } else {
                     xous::return_scalar(msg.sender, UsbDeviceType::Debug as usize).unwrap();
@@ -1377,9 +1496,15 @@ pub(crate) fn main_hw() -> ! {
                     }
                     Views::Serial => xous::return_scalar(msg.sender, serial_device.state() as usize).unwrap(),
                     Views::HIDv2 => xous::return_scalar(msg.sender, hidv2.state() as usize).unwrap(),
+                    Views::Ccid => xous::return_scalar(msg.sender, ccid.state() as usize).unwrap(),
                 }
             }),
             Some(Opcode::SendKeyCode) => msg_blocking_scalar_unpack!(msg, code0, code1, code2, autoup, {
+                if autotype_locked {
+                    log::warn!("screen is locked, keycode not sent");
+                    xous::return_scalar(msg.sender, 1).unwrap();
+                    continue;
+                }
                 match view {
                     Views::FidoWithKbd => {
                         if usb_dev.state() == UsbDeviceState::Configured {
@@ -1470,10 +1595,44 @@ pub(crate) fn main_hw() -> ! {
                 // there is no limit on the minimum rate. good luck if you set it to 0!
                 autotype_delay_ms = checked_rate;
             }),
-            Some(Opcode::SendString) => {
+            Some(Opcode::RegisterAutotypeLock) => msg_blocking_scalar_unpack!(msg, t0, t1, t2, t3, {
+                if autotype_lock_token.is_none() {
+                    autotype_lock_token = Some([t0 as u32, t1 as u32, t2 as u32, t3 as u32]);
+                    xous::return_scalar(msg.sender, 1).unwrap();
+                } else {
+                    log::warn!("attempt to re-register the autotype lock, ignored");
+                    xous::return_scalar(msg.sender, 0).unwrap();
+                }
+            }),
+            Some(Opcode::SetAutotypeLock) => {
+                let buffer = unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
+                let lock = buffer.to_original::<api::AutotypeLock, _>().unwrap();
+                if autotype_lock_token.is_some() && autotype_lock_token == Some(lock.token) {
+                    autotype_locked = lock.locked;
+                    log::info!("autotype lock: {:?}", autotype_locked);
+                } else {
+                    log::warn!("attempt to set the autotype lock with an invalid token, ignored");
+                }
+            }
+            Some(Opcode::SetHostLayout) => msg_scalar_unpack!(msg, layout, unicode, _, _, {
+                host_layout = FromPrimitive::from_usize(layout).unwrap_or(HostLayout::Native);
+                unicode_entry = FromPrimitive::from_usize(unicode).unwrap_or(UnicodeEntry::Off);
+                log::info!("host layout: {:?}, unicode entry: {:?}", host_layout, unicode_entry);
+            }),
+            Some(Opcode::SendString) | Some(Opcode::SendStringLayout) => {
                 let mut buffer =
                     unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
-                let mut usb_send = buffer.to_original::<api::UsbString, _>().unwrap();
+                // the log crate relies on `UsbString` not changing, so a per-string layout comes in its own
+                // structure
+                let layout_request = if matches!(opcode, Some(Opcode::SendStringLayout)) {
+                    Some(buffer.to_original::<api::UsbAutotype, _>().unwrap())
+                } else {
+                    None
+                };
+                let mut usb_send = match layout_request {
+                    Some(request) => api::UsbString { s: request.s, sent: None },
+                    None => buffer.to_original::<api::UsbString, _>().unwrap(),
+                };
                 #[cfg(not(feature = "minimal"))]
                 let mut sent = 0;
                 #[cfg(feature = "minimal")]
@@ -1481,23 +1640,48 @@ pub(crate) fn main_hw() -> ! {
                 match view {
                     #[cfg(not(feature = "minimal"))]
                     Views::FidoWithKbd => {
-                        // check keymap on every call because we may need to toggle this for e.g. plugging
-                        // into a new host with a different map
-                        let native_map = native_kbd.get_keymap().unwrap();
-                        for ch in usb_send.s.as_str().unwrap().chars() {
-                            // ASSUME: user's keyboard type matches the preference on their Precursor device.
-                            let codes = match native_map {
-                                KeyMap::Dvorak => mappings::char_to_hid_code_dvorak(ch),
-                                _ => mappings::char_to_hid_code_us101(ch),
+                        if autotype_locked {
+                            log::warn!("screen is locked, refusing to autotype");
+                        } else {
+                            let layout = match layout_request.and_then(|r| r.layout).unwrap_or(host_layout) {
+                                HostLayout::Native => {
+                                    // check keymap on every call because we may need to toggle this for e.g.
+                                    // plugging into a new host with a different map
+                                    // ASSUME: user's keyboard type matches the preference on their Precursor
+                                    // device.
+                                    match native_kbd.get_keymap().unwrap() {
+                                        KeyMap::Dvorak => HostLayout::Dvorak,
+                                        KeyMap::Azerty => HostLayout::Azerty,
+                                        KeyMap::Qwertz => HostLayout::Qwertz,
+                                        _ => HostLayout::Us101,
+                                    }
+                                }
+                                layout => layout,
                             };
-                            let keyboard = composite.device::<NKROBootKeyboard<'_, _>, _>();
-                            keyboard.write_report(codes).ok();
-                            keyboard.tick().ok();
-                            tt.sleep_ms(autotype_delay_ms).ok();
-                            keyboard.write_report([Keyboard::NoEventIndicated]).ok(); // this is the key-up
-                            keyboard.tick().ok();
-                            tt.sleep_ms(autotype_delay_ms).ok();
-                            sent += 1;
+                            for ch in usb_send.s.as_str().unwrap().chars() {
+                                let sequence = mappings::char_to_key_sequence(ch, layout, unicode_entry);
+                                let keyboard = composite.device::<NKROBootKeyboard<'_, _>, _>();
+                                for stroke in sequence.strokes.iter() {
+                                    let mut codes = stroke.clone();
+                                    codes.extend(sequence.held);
+                                    keyboard.write_report(codes).ok();
+                                    keyboard.tick().ok();
+                                    tt.sleep_ms(autotype_delay_ms).ok();
+                                    // this is the key-up, leaving any held modifier down
+                                    match sequence.held {
+                                        Some(held) => keyboard.write_report([held]).ok(),
+                                        None => keyboard.write_report([Keyboard::NoEventIndicated]).ok(),
+                                    };
+                                    keyboard.tick().ok();
+                                    tt.sleep_ms(autotype_delay_ms).ok();
+                                }
+                                if sequence.held.is_some() {
+                                    keyboard.write_report([Keyboard::NoEventIndicated]).ok();
+                                    keyboard.tick().ok();
+                                    tt.sleep_ms(autotype_delay_ms).ok();
+                                }
+                                sent += 1;
+                            }
                         }
                     }
                     Views::Serial => {
@@ -1529,7 +1713,13 @@ pub(crate) fn main_hw() -> ! {
                     _ => {} // do nothing; will report that 0 characters were sent
                 }
                 usb_send.sent = Some(sent as _);
-                buffer.replace(usb_send).unwrap();
+                match layout_request {
+                    Some(mut request) => {
+                        request.sent = usb_send.sent;
+                        buffer.replace(request).unwrap();
+                    }
+                    None => buffer.replace(usb_send).unwrap(),
+                }
             }
             Some(Opcode::GetLedState) => msg_blocking_scalar_unpack!(msg, _, _, _, _, {
                 let mut code = [0u8; 1];
@@ -1549,6 +1739,36 @@ pub(crate) fn main_hw() -> ! {
                 serial_listen_mode = SerialListenMode::BinaryListener;
                 serial_listener = Some(msg);
             }
+            Some(Opcode::SerialSendBinary) => {
+                let mut buffer =
+                    unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                let mut usb_send = buffer.to_original::<UsbSerialBinary, _>().unwrap();
+                let to_send = usb_send.len.min(SERIAL_BINARY_BUFLEN);
+                let mut sent = 0;
+                if let Views::Serial = view {
+                    // same blocking write as `SendString`, but without the UTF-8 round trip
+                    while sent < to_send {
+                        match serial_port.write(&usb_send.d[sent..to_send]) {
+                            Ok(written) => {
+                                sent += written;
+                            }
+                            Err(_) => {
+                                log::warn!("Serial send is blocking. Delaying and trying again.");
+                                tt.sleep_ms(100).ok();
+                            }
+                        }
+                        match serial_port.flush() {
+                            Ok(_) => {}
+                            Err(_) => {
+                                log::warn!("Serial port reported WouldBlock on flush");
+                                tt.sleep_ms(100).ok();
+                            }
+                        }
+                    }
+                }
+                usb_send.len = sent;
+                buffer.replace(usb_send).unwrap();
+            }
             Some(Opcode::SerialHookConsole) => msg_scalar_unpack!(msg, _, _, _, _, {
                 let log_conn = xous::connect(xous::SID::from_bytes(b"xous-log-server ").unwrap()).unwrap();
                 match xous::send_message(
@@ -1617,7 +1837,8 @@ pub(crate) fn main_hw() -> ! {
                                 let mut buf = response.to_original::<UsbSerialBinary, _>().unwrap();
                                 let chars_avail = serial_buf.len().min(SERIAL_BINARY_BUFLEN);
                                 buf.len = chars_avail;
-                                buf.d.copy_from_slice(serial_buf.drain(..chars_avail).as_slice());
+                                buf.d[..chars_avail]
+                                    .copy_from_slice(serial_buf.drain(..chars_avail).as_slice());
                                 response.replace(buf).unwrap();
                                 // the rx_msg will drop and respond to the listener
                             }
@@ -1882,6 +2103,48 @@ pub(crate) fn main_hw() -> ! {
 
                 hidv2.write_report(data);
             }
+            Some(Opcode::CcidRxDeferred) => {
+                if ccid_listener_pid.is_none() {
+                    ccid_listener_pid = msg.sender.pid();
+                }
+                if ccid_listener_pid != msg.sender.pid() {
+                    log::warn!(
+                        "CCID interface capability is locked on first use; additional servers are ignored: {:?}",
+                        msg.sender
+                    );
+                    ccid::deny_listener(msg);
+                } else if view != Views::Ccid {
+                    log::warn!("CCID listener registered while the CCID core is not selected");
+                    ccid::deny_listener(msg);
+                } else if let Some(event) = ccid.next_event() {
+                    ccid::answer_listener(msg, event);
+                } else {
+                    if ccid_listener.is_some() {
+                        log::error!("Double CCID listener request detected; the first one gets dropped.");
+                    }
+                    ccid_listener = Some(msg);
+                }
+            }
+            Some(Opcode::CcidTx) => {
+                let mut buffer =
+                    unsafe { Buffer::from_memory_message_mut(msg.body.memory_message_mut().unwrap()) };
+                let mut apdu = buffer.to_original::<CcidApdu, _>().unwrap();
+                if ccid_listener_pid == msg.sender.pid()
+                    && view == Views::Ccid
+                    && ccid.respond(&apdu.data[..apdu.len.min(CCID_APDU_BUFLEN)])
+                {
+                    ccid_busy.store(false, Ordering::SeqCst);
+                    apdu.code = U2fCode::TxAck;
+                } else {
+                    apdu.code = U2fCode::Denied;
+                }
+                buffer.replace(apdu).unwrap();
+            }
+            Some(Opcode::CcidTimeExtension) => {
+                if view == Views::Ccid && ccid.busy() {
+                    ccid.time_extension();
+                }
+            }
             Some(Opcode::RegisterUsbObserver) => {
                 let buffer = unsafe { Buffer::from_memory_message(msg.body.memory_message().unwrap()) };
                 let ur = buffer.as_flat::<UsbListenerRegistration, _>().unwrap();
@@ -1,7 +1,9 @@
 #![allow(unused_imports)]
 pub use xous_usb_hid::page::Keyboard as UsbKeyCode;
 
-#[cfg(any(feature = "precursor", feature = "renode", feature = "cramium-soc"))]
+use crate::api::{HostLayout, UnicodeEntry};
+
+#[cfg(any(feature = "precursor", feature = "renode", feature = "cramium-soc", test))]
 #[rustfmt::skip]
 pub fn char_to_hid_code_us101(key: char) -> Vec<UsbKeyCode> {
     let mut code = vec![];
@@ -114,13 +116,13 @@ pub fn char_to_hid_code_us101(key: char) -> Vec<UsbKeyCode> {
         '\u{000a}' => code.push(UsbKeyCode::ReturnEnter), // turn LF ('\n') into enter
         ' ' => {code.push(UsbKeyCode::Space); },
         '\u{0008}' => code.push(UsbKeyCode::DeleteBackspace),
-        _ => log::warn!("Ignoring unhandled character: {}", key),
+        _ => {} // not on this layout; the caller decides how to fall back
     };
     code
 }
 
 /// auto-generated using tools/kbd_layout.py + `usb kbdtest` on device for dvorak layout on US101
-#[cfg(any(feature = "precursor", feature = "renode", feature = "cramium-soc"))]
+#[cfg(any(feature = "precursor", feature = "renode", feature = "cramium-soc", test))]
 #[rustfmt::skip]
 pub fn char_to_hid_code_dvorak(key: char) -> Vec<UsbKeyCode> {
     let mut code = vec![];
@@ -223,7 +225,382 @@ pub fn char_to_hid_code_dvorak(key: char) -> Vec<UsbKeyCode> {
         '\u{000d}' => {}, // ignore CR
         '\u{000a}' => code.push(UsbKeyCode::ReturnEnter), // turn LF ('\n') into enter
         '\u{0008}' => code.push(UsbKeyCode::DeleteBackspace),
-        _ => log::warn!("Ignoring unhandled character: {}", key),
+        _ => {} // not on this layout; the caller decides how to fall back
     };
     code
 }
+
+/// The chords that type one character on the host. Each stroke is pressed and released in turn, and
+/// `held` stays down from the first stroke until after the last one.
+#[cfg(any(feature = "precursor", feature = "renode", feature = "cramium-soc", test))]
+#[derive(Debug, Default)]
+pub struct KeySequence {
+    pub strokes: Vec<Vec<UsbKeyCode>>,
+    pub held: Option<UsbKeyCode>,
+}
+
+/// Finds the keys that type `key` on a host set to `layout`. Characters that aren't on the layout are
+/// built from a dead key and a base letter where the layout has the dead key, and are otherwise typed
+/// with `unicode`, if the host supports it.
+#[cfg(any(feature = "precursor", feature = "renode", feature = "cramium-soc", test))]
+pub fn char_to_key_sequence(key: char, layout: HostLayout, unicode: UnicodeEntry) -> KeySequence {
+    if key == '\u{000d}' {
+        return KeySequence::default(); // ignore CR
+    }
+    if let Some(code) = direct(key, layout) {
+        return KeySequence { strokes: vec![code], held: None };
+    }
+    if let Some(dead) = dead_key(key, layout) {
+        // a dead key followed by space types the accent itself
+        return KeySequence { strokes: vec![dead, vec![UsbKeyCode::Space]], held: None };
+    }
+    if let Some(&(_, accent, base)) = DECOMPOSITIONS.iter().find(|&&(composed, _, _)| composed == key) {
+        if let (Some(dead), Some(code)) = (dead_key(accent, layout), direct(base, layout)) {
+            return KeySequence { strokes: vec![dead, code], held: None };
+        }
+    }
+    if let Some(sequence) = unicode_sequence(key, layout, unicode) {
+        return sequence;
+    }
+    log::warn!("Ignoring unhandled character: {}", key);
+    KeySequence::default()
+}
+
+#[cfg(any(feature = "precursor", feature = "renode", feature = "cramium-soc", test))]
+fn direct(key: char, layout: HostLayout) -> Option<Vec<UsbKeyCode>> {
+    let code = match layout {
+        HostLayout::Native | HostLayout::Us101 => char_to_hid_code_us101(key),
+        HostLayout::Dvorak => char_to_hid_code_dvorak(key),
+        HostLayout::Azerty => char_to_hid_code_azerty(key),
+        HostLayout::Qwertz => char_to_hid_code_qwertz(key),
+        HostLayout::Uk => char_to_hid_code_uk(key),
+        HostLayout::Nordic => char_to_hid_code_nordic(key),
+    };
+    if code.is_empty() { None } else { Some(code) }
+}
+
+#[cfg(any(feature = "precursor", feature = "renode", feature = "cramium-soc", test))]
+fn shifted(code: UsbKeyCode) -> Vec<UsbKeyCode> { vec![code, UsbKeyCode::LeftShift] }
+
+#[cfg(any(feature = "precursor", feature = "renode", feature = "cramium-soc", test))]
+/// AltGr is the right Alt key on the layouts that have one
+fn altgr(code: UsbKeyCode) -> Vec<UsbKeyCode> { vec![code, UsbKeyCode::RightAlt] }
+
+/// Letters and digits, given the keys that differ from US101, and whether the digits need shift
+#[cfg(any(feature = "precursor", feature = "renode", feature = "cramium-soc", test))]
+fn alphanumeric(key: char, swaps: &[(char, UsbKeyCode)], shifted_digits: bool) -> Vec<UsbKeyCode> {
+    let lower = key.to_ascii_lowercase();
+    if lower.is_ascii_lowercase() {
+        let code = match swaps.iter().find(|&&(c, _)| c == lower) {
+            Some(&(_, code)) => code,
+            None => char_to_hid_code_us101(lower)[0],
+        };
+        if key.is_ascii_uppercase() { shifted(code) } else { vec![code] }
+    } else if key.is_ascii_digit() {
+        let code = char_to_hid_code_us101(key)[0];
+        if shifted_digits { shifted(code) } else { vec![code] }
+    } else {
+        match key {
+            '\u{000a}' | '\u{0008}' | ' ' | '←' | '→' | '↑' | '↓' => char_to_hid_code_us101(key),
+            _ => vec![],
+        }
+    }
+}
+
+#[cfg(any(feature = "precursor", feature = "renode", feature = "cramium-soc", test))]
+pub fn char_to_hid_code_azerty(key: char) -> Vec<UsbKeyCode> {
+    use UsbKeyCode::*;
+    match key {
+        '²' => vec![Grave],
+        '&' => vec![Keyboard1],
+        'é' => vec![Keyboard2],
+        '"' => vec![Keyboard3],
+        '\'' => vec![Keyboard4],
+        '(' => vec![Keyboard5],
+        '-' => vec![Keyboard6],
+        'è' => vec![Keyboard7],
+        '_' => vec![Keyboard8],
+        'ç' => vec![Keyboard9],
+        'à' => vec![Keyboard0],
+        ')' => vec![Minus],          '°' => shifted(Minus),          ']' => altgr(Minus),
+        '=' => vec![Equal],          '+' => shifted(Equal),          '}' => altgr(Equal),
+        '#' => altgr(Keyboard3),
+        '{' => altgr(Keyboard4),
+        '[' => altgr(Keyboard5),
+        '|' => altgr(Keyboard6),
+        '\\' => altgr(Keyboard8),
+        '^' => altgr(Keyboard9),
+        '@' => altgr(Keyboard0),
+        '€' => altgr(E),
+        '$' => vec![RightBrace],     '£' => shifted(RightBrace),     '¤' => altgr(RightBrace),
+        'ù' => vec![Apostrophe],     '%' => shifted(Apostrophe),
+        '*' => vec![NonUSHash],      'µ' => shifted(NonUSHash),
+        '<' => vec![NonUSBackslash], '>' => shifted(NonUSBackslash),
+        ',' => vec![M],              '?' => shifted(M),
+        ';' => vec![Comma],          '.' => shifted(Comma),
+        ':' => vec![Dot],            '/' => shifted(Dot),
+        '!' => vec![ForwardSlash],   '§' => shifted(ForwardSlash),
+        _ => alphanumeric(key, &[('a', Q), ('q', A), ('z', W), ('w', Z), ('m', Semicolon)], true),
+    }
+}
+
+#[cfg(any(feature = "precursor", feature = "renode", feature = "cramium-soc", test))]
+pub fn char_to_hid_code_qwertz(key: char) -> Vec<UsbKeyCode> {
+    use UsbKeyCode::*;
+    match key {
+        '°' => shifted(Grave),
+        '!' => shifted(Keyboard1),
+        '"' => shifted(Keyboard2),   '²' => altgr(Keyboard2),
+        '§' => shifted(Keyboard3),   '³' => altgr(Keyboard3),
+        '$' => shifted(Keyboard4),
+        '%' => shifted(Keyboard5),
+        '&' => shifted(Keyboard6),
+        '/' => shifted(Keyboard7),   '{' => altgr(Keyboard7),
+        '(' => shifted(Keyboard8),   '[' => altgr(Keyboard8),
+        ')' => shifted(Keyboard9),   ']' => altgr(Keyboard9),
+        '=' => shifted(Keyboard0),   '}' => altgr(Keyboard0),
+        'ß' => vec![Minus],          '?' => shifted(Minus),          '\\' => altgr(Minus),
+        'ü' => vec![LeftBrace],      'Ü' => shifted(LeftBrace),
+        '+' => vec![RightBrace],     '*' => shifted(RightBrace),     '~' => altgr(RightBrace),
+        'ö' => vec![Semicolon],      'Ö' => shifted(Semicolon),
+        'ä' => vec![Apostrophe],     'Ä' => shifted(Apostrophe),
+        '#' => vec![NonUSHash],      '\'' => shifted(NonUSHash),
+        '<' => vec![NonUSBackslash], '>' => shifted(NonUSBackslash), '|' => altgr(NonUSBackslash),
+        ',' => vec![Comma],          ';' => shifted(Comma),
+        '.' => vec![Dot],            ':' => shifted(Dot),
+        '-' => vec![ForwardSlash],   '_' => shifted(ForwardSlash),
+        '@' => altgr(Q),
+        '€' => altgr(E),
+        'µ' => altgr(M),
+        _ => alphanumeric(key, &[('y', Z), ('z', Y)], false),
+    }
+}
+
+#[cfg(any(feature = "precursor", feature = "renode", feature = "cramium-soc", test))]
+pub fn char_to_hid_code_uk(key: char) -> Vec<UsbKeyCode> {
+    use UsbKeyCode::*;
+    match key {
+        '`' => vec![Grave],          '¬' => shifted(Grave),          '¦' => altgr(Grave),
+        '"' => shifted(Keyboard2),
+        '£' => shifted(Keyboard3),
+        '€' => altgr(Keyboard4),
+        '\'' => vec![Apostrophe],    '@' => shifted(Apostrophe),
+        '#' => vec![NonUSHash],      '~' => shifted(NonUSHash),
+        '\\' => vec![NonUSBackslash], '|' => shifted(NonUSBackslash),
+        // everything else is where it is on US101
+        _ => char_to_hid_code_us101(key),
+    }
+}
+
+#[cfg(any(feature = "precursor", feature = "renode", feature = "cramium-soc", test))]
+pub fn char_to_hid_code_nordic(key: char) -> Vec<UsbKeyCode> {
+    use UsbKeyCode::*;
+    match key {
+        '§' => vec![Grave],          '½' => shifted(Grave),
+        '!' => shifted(Keyboard1),
+        '"' => shifted(Keyboard2),   '@' => altgr(Keyboard2),
+        '#' => shifted(Keyboard3),   '£' => altgr(Keyboard3),
+        '¤' => shifted(Keyboard4),   '$' => altgr(Keyboard4),
+        '%' => shifted(Keyboard5),   '€' => altgr(Keyboard5),
+        '&' => shifted(Keyboard6),
+        '/' => shifted(Keyboard7),   '{' => altgr(Keyboard7),
+        '(' => shifted(Keyboard8),   '[' => altgr(Keyboard8),
+        ')' => shifted(Keyboard9),   ']' => altgr(Keyboard9),
+        '=' => shifted(Keyboard0),   '}' => altgr(Keyboard0),
+        '+' => vec![Minus],          '?' => shifted(Minus),          '\\' => altgr(Minus),
+        'å' => vec![LeftBrace],      'Å' => shifted(LeftBrace),
+        'ö' => vec![Semicolon],      'Ö' => shifted(Semicolon),
+        'ä' => vec![Apostrophe],     'Ä' => shifted(Apostrophe),
+        '\'' => vec![NonUSHash],     '*' => shifted(NonUSHash),
+        '<' => vec![NonUSBackslash], '>' => shifted(NonUSBackslash), '|' => altgr(NonUSBackslash),
+        ',' => vec![Comma],          ';' => shifted(Comma),
+        '.' => vec![Dot],            ':' => shifted(Dot),
+        '-' => vec![ForwardSlash],   '_' => shifted(ForwardSlash),
+        'µ' => altgr(M),
+        _ => alphanumeric(key, &[], false),
+    }
+}
+
+#[cfg(any(feature = "precursor", feature = "renode", feature = "cramium-soc", test))]
+/// The dead keys of each layout, by the accent they put on the next letter
+fn dead_key(accent: char, layout: HostLayout) -> Option<Vec<UsbKeyCode>> {
+    use UsbKeyCode::*;
+    match (layout, accent) {
+        (HostLayout::Azerty, '^') => Some(vec![LeftBrace]),
+        (HostLayout::Azerty, '¨') => Some(shifted(LeftBrace)),
+        (HostLayout::Azerty, '~') => Some(altgr(Keyboard2)),
+        (HostLayout::Azerty, '`') => Some(altgr(Keyboard7)),
+        (HostLayout::Qwertz, '^') => Some(vec![Grave]),
+        (HostLayout::Qwertz, '´') => Some(vec![Equal]),
+        (HostLayout::Qwertz, '`') => Some(shifted(Equal)),
+        (HostLayout::Nordic, '´') => Some(vec![Equal]),
+        (HostLayout::Nordic, '`') => Some(shifted(Equal)),
+        (HostLayout::Nordic, '¨') => Some(vec![RightBrace]),
+        (HostLayout::Nordic, '^') => Some(shifted(RightBrace)),
+        (HostLayout::Nordic, '~') => Some(altgr(RightBrace)),
+        _ => None,
+    }
+}
+
+#[cfg(any(feature = "precursor", feature = "renode", feature = "cramium-soc", test))]
+/// `(composed, accent, base)` for the letters that dead keys can produce
+const DECOMPOSITIONS: &[(char, char, char)] = &[
+    ('á', '´', 'a'), ('é', '´', 'e'), ('í', '´', 'i'), ('ó', '´', 'o'), ('ú', '´', 'u'), ('ý', '´', 'y'),
+    ('Á', '´', 'A'), ('É', '´', 'E'), ('Í', '´', 'I'), ('Ó', '´', 'O'), ('Ú', '´', 'U'), ('Ý', '´', 'Y'),
+    ('à', '`', 'a'), ('è', '`', 'e'), ('ì', '`', 'i'), ('ò', '`', 'o'), ('ù', '`', 'u'),
+    ('À', '`', 'A'), ('È', '`', 'E'), ('Ì', '`', 'I'), ('Ò', '`', 'O'), ('Ù', '`', 'U'),
+    ('â', '^', 'a'), ('ê', '^', 'e'), ('î', '^', 'i'), ('ô', '^', 'o'), ('û', '^', 'u'),
+    ('Â', '^', 'A'), ('Ê', '^', 'E'), ('Î', '^', 'I'), ('Ô', '^', 'O'), ('Û', '^', 'U'),
+    ('ä', '¨', 'a'), ('ë', '¨', 'e'), ('ï', '¨', 'i'), ('ö', '¨', 'o'), ('ü', '¨', 'u'), ('ÿ', '¨', 'y'),
+    ('Ä', '¨', 'A'), ('Ë', '¨', 'E'), ('Ï', '¨', 'I'), ('Ö', '¨', 'O'), ('Ü', '¨', 'U'),
+    ('ã', '~', 'a'), ('ñ', '~', 'n'), ('õ', '~', 'o'),
+    ('Ã', '~', 'A'), ('Ñ', '~', 'N'), ('Õ', '~', 'O'),
+];
+
+#[cfg(any(feature = "precursor", feature = "renode", feature = "cramium-soc", test))]
+const KEYPAD_DIGITS: [UsbKeyCode; 10] = [
+    UsbKeyCode::Keypad0, UsbKeyCode::Keypad1, UsbKeyCode::Keypad2, UsbKeyCode::Keypad3, UsbKeyCode::Keypad4,
+    UsbKeyCode::Keypad5, UsbKeyCode::Keypad6, UsbKeyCode::Keypad7, UsbKeyCode::Keypad8, UsbKeyCode::Keypad9,
+];
+
+#[cfg(any(feature = "precursor", feature = "renode", feature = "cramium-soc", test))]
+fn unicode_sequence(key: char, layout: HostLayout, unicode: UnicodeEntry) -> Option<KeySequence> {
+    use UsbKeyCode::*;
+    match unicode {
+        UnicodeEntry::Off => None,
+        UnicodeEntry::Linux => {
+            let mut strokes = vec![vec![U, LeftControl, LeftShift]];
+            for digit in format!("{:x}", key as u32).chars() {
+                strokes.push(direct(digit, layout)?);
+            }
+            strokes.push(vec![Space]);
+            Some(KeySequence { strokes, held: None })
+        }
+        UnicodeEntry::MacOs => {
+            // the Unicode Hex Input source is a US layout, so the digits are where they are on US101
+            let mut units = [0u16; 2];
+            let mut strokes = vec![];
+            for unit in key.encode_utf16(&mut units).iter() {
+                for digit in format!("{:04x}", unit).chars() {
+                    strokes.push(char_to_hid_code_us101(digit));
+                }
+            }
+            Some(KeySequence { strokes, held: Some(LeftAlt) })
+        }
+        UnicodeEntry::Windows => {
+            if key as u32 > 0xffff {
+                return None;
+            }
+            let mut strokes = vec![vec![KeypadAdd]];
+            for digit in format!("{:x}", key as u32).chars() {
+                strokes.push(match digit.to_digit(10) {
+                    Some(d) => vec![KEYPAD_DIGITS[d as usize]],
+                    // the hex letters come from the letter keys of the host layout
+                    None => direct(digit, layout)?,
+                });
+            }
+            Some(KeySequence { strokes, held: Some(LeftAlt) })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use UsbKeyCode::*;
+
+    fn strokes(key: char, layout: HostLayout) -> Vec<Vec<UsbKeyCode>> {
+        let sequence = char_to_key_sequence(key, layout, UnicodeEntry::Off);
+        assert_eq!(sequence.held, None);
+        sequence.strokes
+    }
+
+    #[test]
+    fn test_direct() {
+        assert_eq!(strokes('a', HostLayout::Us101), vec![vec![A]]);
+        assert_eq!(strokes('A', HostLayout::Native), vec![vec![A, LeftShift]]);
+        assert_eq!(strokes('\u{000d}', HostLayout::Us101), Vec::<Vec<UsbKeyCode>>::new());
+        assert_eq!(strokes('\u{000a}', HostLayout::Azerty), vec![vec![ReturnEnter]]);
+    }
+
+    #[test]
+    fn test_azerty() {
+        assert_eq!(char_to_hid_code_azerty('a'), vec![Q]);
+        assert_eq!(char_to_hid_code_azerty('Q'), vec![A, LeftShift]);
+        assert_eq!(char_to_hid_code_azerty('m'), vec![Semicolon]);
+        // the digits are on the shifted level
+        assert_eq!(char_to_hid_code_azerty('1'), vec![Keyboard1, LeftShift]);
+        assert_eq!(char_to_hid_code_azerty('é'), vec![Keyboard2]);
+        assert_eq!(char_to_hid_code_azerty('@'), vec![Keyboard0, RightAlt]);
+        assert_eq!(char_to_hid_code_azerty('!'), vec![ForwardSlash]);
+    }
+
+    #[test]
+    fn test_qwertz() {
+        assert_eq!(char_to_hid_code_qwertz('z'), vec![Y]);
+        assert_eq!(char_to_hid_code_qwertz('Y'), vec![Z, LeftShift]);
+        assert_eq!(char_to_hid_code_qwertz('1'), vec![Keyboard1]);
+        assert_eq!(char_to_hid_code_qwertz('ß'), vec![Minus]);
+        assert_eq!(char_to_hid_code_qwertz('@'), vec![Q, RightAlt]);
+        assert_eq!(char_to_hid_code_qwertz('-'), vec![ForwardSlash]);
+    }
+
+    #[test]
+    fn test_uk_and_nordic() {
+        assert_eq!(char_to_hid_code_uk('@'), vec![Apostrophe, LeftShift]);
+        assert_eq!(char_to_hid_code_uk('"'), vec![Keyboard2, LeftShift]);
+        assert_eq!(char_to_hid_code_uk('#'), vec![NonUSHash]);
+        assert_eq!(char_to_hid_code_uk('q'), vec![Q]);
+        assert_eq!(char_to_hid_code_nordic('å'), vec![LeftBrace]);
+        assert_eq!(char_to_hid_code_nordic('$'), vec![Keyboard4, RightAlt]);
+        assert_eq!(char_to_hid_code_nordic('2'), vec![Keyboard2]);
+        // not on the layout at all
+        assert!(char_to_hid_code_nordic('ß').is_empty());
+    }
+
+    #[test]
+    fn test_dead_keys() {
+        assert_eq!(strokes('ê', HostLayout::Azerty), vec![vec![LeftBrace], vec![E]]);
+        assert_eq!(strokes('é', HostLayout::Qwertz), vec![vec![Equal], vec![E]]);
+        assert_eq!(strokes('Ê', HostLayout::Nordic), vec![vec![RightBrace, LeftShift], vec![E, LeftShift]]);
+        // the accent on its own is the dead key followed by space
+        assert_eq!(strokes('^', HostLayout::Qwertz), vec![vec![Grave], vec![Space]]);
+        // no dead key for it on US101, and no Unicode entry to fall back on
+        assert!(strokes('é', HostLayout::Us101).is_empty());
+    }
+
+    #[test]
+    fn test_unicode_sequence() {
+        let linux = char_to_key_sequence('é', HostLayout::Us101, UnicodeEntry::Linux);
+        assert_eq!(linux.strokes, vec![vec![U, LeftControl, LeftShift], vec![E], vec![Keyboard9], vec![Space]]);
+        assert_eq!(linux.held, None);
+        // the hex digits come from the host layout
+        let linux = char_to_key_sequence('ł', HostLayout::Azerty, UnicodeEntry::Linux);
+        assert_eq!(
+            linux.strokes,
+            vec![
+                vec![U, LeftControl, LeftShift],
+                vec![Keyboard1, LeftShift],
+                vec![Keyboard4, LeftShift],
+                vec![Keyboard2, LeftShift],
+                vec![Space],
+            ]
+        );
+
+        let windows = char_to_key_sequence('é', HostLayout::Us101, UnicodeEntry::Windows);
+        assert_eq!(windows.strokes, vec![vec![KeypadAdd], vec![E], vec![Keypad9]]);
+        assert_eq!(windows.held, Some(LeftAlt));
+        // Alt codes stop at the BMP
+        assert!(char_to_key_sequence('😊', HostLayout::Us101, UnicodeEntry::Windows).strokes.is_empty());
+
+        // macOS takes UTF-16, so this is a surrogate pair, and always in US101 positions
+        let mac = char_to_key_sequence('😊', HostLayout::Azerty, UnicodeEntry::MacOs);
+        assert_eq!(
+            mac.strokes,
+            vec![vec![D], vec![Keyboard8], vec![Keyboard3], vec![D], vec![D], vec![E], vec![Keyboard0], vec![A]]
+        );
+        assert_eq!(mac.held, Some(LeftAlt));
+
+        assert!(unicode_sequence('é', HostLayout::Us101, UnicodeEntry::Off).is_none());
+    }
+}
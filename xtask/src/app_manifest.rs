@@ -164,6 +164,26 @@ pub(crate) fn app_index_to_name(index: usize) -> Result<&'static str, AppDispatc
     }}
 }}
 
+#[allow(dead_code)]
+pub(crate) fn app_index_to_context(index: usize) -> Result<&'static str, AppDispatchError> {{
+    match index {{"####
+    )
+    .unwrap();
+    for (index, (app_name, _manifest)) in working_set.iter().enumerate() {
+        writeln!(
+            menu,
+            "        {} => Ok(gam::APP_NAME_{}),",
+            index,
+            app_name.to_uppercase().replace('-', "_")
+        )
+        .unwrap();
+    }
+    writeln!(
+        menu,
+        r####"        _ => Err(AppDispatchError::IndexNotFound(index)),
+    }}
+}}
+
 pub(crate) fn app_menu_items(menu_items: &mut Vec::<MenuItem>, status_conn: u32) {{
 "####
     )
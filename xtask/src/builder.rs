@@ -140,8 +140,8 @@ pub(crate) struct Builder {
     run_svd2repl: bool,
     locale_override: Option<String>,
     locale_stash: String,
-    /// when set to true, hosted mode builds but does not run
-    dry_run: bool,
+    /// UI scripts that hosted mode is run with, once each, instead of interactively
+    ui_scripts: Vec<String>,
     /// when set to true, user selected packages are compiled but no image is created
     no_image: bool,
     /// when Some, specifies a swap region as offset, size
@@ -172,7 +172,7 @@ impl Builder {
             run_svd2repl: false,
             locale_override: None,
             locale_stash: String::new(),
-            dry_run: false,
+            ui_scripts: Vec::new(),
             no_image: false,
             swap: None,
             change_target: false,
@@ -430,9 +430,10 @@ impl Builder {
         self
     }
 
-    /// only build a hosted target. don't run it. Used exclusively to confirm that hosted mode builds in CI.
-    pub fn hosted_build_only(&mut self) -> &mut Builder {
-        self.dry_run = true;
+    /// run hosted mode with the UI script at `path` (relative to the project root), and fail the build
+    /// unless every check in it passes. Can be given several times; hosted mode is started once per script.
+    pub fn add_ui_script(&mut self, path: &str) -> &mut Builder {
+        self.ui_scripts.push(path.into());
         self
     }
 
@@ -745,7 +746,13 @@ impl Builder {
             let mut binary_files: Vec<&str> = binary_files_storage.iter().map(|s| s.as_ref()).collect();
             hosted_args.append(&mut binary_files);
 
-            if !self.dry_run {
+            if !self.ui_scripts.is_empty() {
+                let mut dir = project_root();
+                dir.push("kernel");
+                for script in self.ui_scripts.iter() {
+                    self.run_ui_script(&dir, &hosted_args, script)?;
+                }
+            } else {
                 let mut dir = project_root();
                 dir.push("kernel");
                 println!("Starting hosted mode...");
@@ -758,17 +765,6 @@ impl Builder {
                 if !status.success() {
                     return Err("cargo run failed to launch hosted mode".into());
                 }
-            } else {
-                // confirm the kernel can build before quitting
-                let _ = self.builder(
-                    &[CrateSpec::Local("xous-kernel".into(), LoaderRegion::Ram)],
-                    &self.kernel_features,
-                    &self.target_kernel.as_deref(),
-                    self.stream,
-                    &[],
-                    false,
-                )?;
-                println!("Dry run requested: only building and not running");
             }
         } else {
             let svd_spec_path = format!(
@@ -1057,6 +1053,31 @@ impl Builder {
         Ok(project_root().join(output_file))
     }
 
+    /// Runs hosted mode with a UI script (see `services/graphics-server/src/uiscript.rs`). The script shuts
+    /// hosted mode down when it quits, and leaves the number of failed checks behind in a file.
+    fn run_ui_script(&self, dir: &Path, hosted_args: &[&str], script: &str) -> Result<(), DynError> {
+        let script = project_root().join(script);
+        let result = project_root().join("target").join("ui-script-result");
+        std::fs::remove_file(&result).ok(); // don't fail if the file does not exist
+        println!("Running UI script {}...", script.display());
+        let status = Command::new(cargo())
+            .current_dir(dir)
+            .args(hosted_args)
+            .env("XOUS_UI_SCRIPT", &script)
+            .env("XOUS_UI_SCRIPT_RESULT", &result)
+            .status()?;
+        if !status.success() {
+            return Err("cargo run failed to launch hosted mode".into());
+        }
+        match std::fs::read_to_string(&result).ok().and_then(|r| r.trim().parse::<u32>().ok()) {
+            Some(0) => Ok(()),
+            Some(failures) => {
+                Err(format!("UI script {}: {} check(s) failed", script.display(), failures).into())
+            }
+            None => Err(format!("UI script {} didn't run to the end", script.display()).into()),
+        }
+    }
+
     fn fetch_prebuilds(&self) -> Result<Vec<String>, DynError> {
         let mut paths = Vec::<String>::new();
         for item in [&self.services[..], &self.apps[..]].concat() {
@@ -111,6 +111,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         ],
     ]
     .concat();
+    // input methods that aren't in the hardware images by default, to save space; see the help text
+    let ime_pkgs = ["ime-plugin-pinyin", "ime-plugin-kana"];
     // for fast testing of compilation targets of the PDDB to real hardware
     let pddb_dev_pkgs = [&base_pkgs[..], &["pddb", "sha2"]].concat();
     // for fast checking of AES hardware accelerator
@@ -322,6 +324,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             builder
                 .target_hosted()
                 .add_services(&user_pkgs)
+                .add_services(&ime_pkgs)
                 .add_feature("pddbtest")
                 .add_feature("ditherpunk")
                 .add_feature("tls")
@@ -348,6 +351,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             builder
                 .target_hosted()
                 .add_services(&user_pkgs)
+                .add_services(&ime_pkgs)
                 .add_feature("pddbtest")
                 .add_feature("ditherpunk")
                 .add_feature("tracking-alloc")
@@ -363,7 +367,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .add_feature("graphics-server/gfx-testing");
         }
         Some("hosted-ci") => {
-            builder.target_hosted().add_services(&user_pkgs).hosted_build_only().add_apps(&get_cratespecs());
+            builder
+                .target_hosted()
+                .add_services(&user_pkgs)
+                .add_feature("graphics-server/headless")
+                .add_ui_script("services/graphics-server/ui-tests/boot.uiscript")
+                .add_ui_script("services/graphics-server/ui-tests/menu.uiscript")
+                .add_apps(&get_cratespecs());
         }
 
         // ------ Precursor hardware image configs ------
@@ -680,6 +690,7 @@ Hosted emulation:
  pddb-btest              PDDB stress tester for secret basis creation/deletion [cratespecs] ignored.
  hosted-debug            Run user image in hosted mode with debug flags. [cratespecs] are apps
  gfx-dev                 Testing mode for graphics primitives. [cratespecs] are services
+ hosted-ci               Runs the hosted image headless with the UI test scripts, checking the screen against golden images. [cratespecs] are apps
  pddb-dev                Testing for compilation errors on hardware targets on the PDDB.
 
 Renode emulation:
@@ -699,6 +710,10 @@ Other commands:
  compile-apps            Just compiles the apps specified in [cratespecs], for example in order to use app server
  dummy-template          Generate dummy templates for formatting and checking purposes
 
+Input methods: `ime-plugin-pinyin` (Chinese) and `ime-plugin-kana` (Japanese) are opt-in on hardware, to save
+space. Add them as services, e.g. `cargo xtask app-image --service ime-plugin-pinyin --service ime-plugin-kana`.
+`run` and `hosted-debug` include both. An app uses one by naming it in `UxRegistration::predictor`.
+
 Note: By default, the `ticktimer` will get rebuilt every time. You can skip this by appending `--no-timestamp` to the command.
 "
     )